@@ -0,0 +1,346 @@
+//! The `#[node]` and `#[tool]` attribute macros of
+//! [rustyflow](https://docs.rs/rustyflow).
+//!
+//! Both are re-exported by rustyflow, as `rustyflow::node` and
+//! `rustyflow::tool`, and documented with examples in its `macros` module.
+//! The generated code refers to `::rustyflow`, so the crate has to be a
+//! dependency under that name.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Expr, ExprLit, FnArg, GenericArgument, Ident, ItemFn, Lit, LitStr,
+    MetaNameValue, Pat, PathArguments, ReturnType, Token, Type,
+};
+
+/// Turn an async fn into a [`Node`](https://docs.rs/rustyflow/latest/rustyflow/node/trait.Node.html).
+///
+/// The function is kept as written. Next to it, a unit struct named after
+/// it in `CamelCase` implements `Node`: a call deserializes the input
+/// object into the function's arguments, one field per argument, and
+/// serializes what the function returns. The node's input schema is
+/// derived from the argument types, and the struct gets a `TYPE_NAME`
+/// constant and a `register` function for a `NodeRegistry`.
+///
+/// The registered type name is the function's name, or the one given as
+/// `#[node(name = "...")]`.
+#[proc_macro_attribute]
+pub fn node(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand(Kind::Node, attr, item)
+}
+
+/// Turn an async fn into a [`Tool`](https://docs.rs/rustyflow/latest/rustyflow/tool/trait.Tool.html).
+///
+/// The function is kept as written. Next to it, a unit struct named after
+/// it in `CamelCase` implements `Tool`, with an `...Input` struct of the
+/// function's arguments as its input and a JSON Schema derived from it. The
+/// struct gets `NAME` and `DESCRIPTION` constants, the latter from the
+/// function's doc comment, and a `register` function for a `ToolRegistry`.
+///
+/// The tool's name is the function's name, or the one given as
+/// `#[tool(name = "...")]`.
+#[proc_macro_attribute]
+pub fn tool(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand(Kind::Tool, attr, item)
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Node,
+    Tool,
+}
+
+impl Kind {
+    fn attribute(self) -> &'static str {
+        match self {
+            Kind::Node => "#[node]",
+            Kind::Tool => "#[tool]",
+        }
+    }
+}
+
+/// The options of an attribute, such as `name = "weather"`.
+#[derive(Default)]
+struct Options {
+    name: Option<LitStr>,
+}
+
+impl Parse for Options {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Options::default();
+        for option in Punctuated::<MetaNameValue, Token![,]>::parse_terminated(input)? {
+            match &option.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(name),
+                    ..
+                }) if option.path.is_ident("name") => options.name = Some(name.clone()),
+                _ if option.path.is_ident("name") => {
+                    return Err(syn::Error::new_spanned(
+                        &option.value,
+                        "`name` must be a string literal",
+                    ))
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &option.path,
+                        "unknown option, expected `name`",
+                    ))
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// A function argument, which becomes a field of the input.
+struct Argument {
+    name: Ident,
+    ty: Type,
+}
+
+fn expand(kind: Kind, attr: TokenStream, item: TokenStream) -> TokenStream {
+    let options = parse_macro_input!(attr as Options);
+    let function = parse_macro_input!(item as ItemFn);
+    // The function is emitted even on errors, so that its own uses don't
+    // add errors of their own.
+    let generated = generate(kind, options, &function).unwrap_or_else(|e| e.to_compile_error());
+    quote!(#function #generated).into()
+}
+
+fn generate(kind: Kind, options: Options, function: &ItemFn) -> syn::Result<TokenStream2> {
+    let signature = &function.sig;
+    if signature.asyncness.is_none() {
+        return Err(syn::Error::new_spanned(
+            signature.fn_token,
+            format!("{} needs an async fn", kind.attribute()),
+        ));
+    }
+    if !signature.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &signature.generics,
+            format!("{} does not support generic functions", kind.attribute()),
+        ));
+    }
+    let arguments = signature
+        .inputs
+        .iter()
+        .map(argument)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let function_name = signature.ident.unraw().to_string();
+    let name = options
+        .name
+        .map(|name| name.value())
+        .unwrap_or_else(|| function_name.clone());
+    let target = format_ident!("{}", camel_case(&function_name));
+    let input = format_ident!("{target}Input");
+    let docs: Vec<_> = function
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .collect();
+    let (output, fallible) = output_type(&signature.output);
+
+    let function_ident = &signature.ident;
+    let names: Vec<_> = arguments.iter().map(|argument| &argument.name).collect();
+    let types: Vec<_> = arguments.iter().map(|argument| &argument.ty).collect();
+    let question = fallible.then(|| quote!(?));
+    let call = quote!(#function_ident(#(#names),*).await #question);
+    let vis = &function.vis;
+
+    let target_struct = quote! {
+        #(#docs)*
+        #[derive(Debug, Clone, Copy, Default)]
+        #vis struct #target;
+    };
+    let input_derives = quote! {
+        #[derive(
+            ::rustyflow::__private::serde::Deserialize,
+            ::rustyflow::__private::schemars::JsonSchema
+        )]
+        #[serde(crate = "::rustyflow::__private::serde")]
+        #[schemars(crate = "::rustyflow::__private::schemars")]
+    };
+    let input_schema = quote! {
+        fn input_schema(&self) -> ::std::option::Option<::rustyflow::__private::serde_json::Value> {
+            ::std::option::Option::Some(
+                ::rustyflow::__private::schemars::schema_for!(#input).to_value(),
+            )
+        }
+    };
+
+    Ok(match kind {
+        Kind::Node => quote! {
+            #target_struct
+
+            impl #target {
+                /// The type name the node is registered under.
+                pub const TYPE_NAME: &'static str = #name;
+
+                /// Register the node with `registry` under
+                /// [`TYPE_NAME`](Self::TYPE_NAME). It takes no params.
+                pub fn register(registry: &mut ::rustyflow::registry::NodeRegistry) {
+                    registry.register(Self::TYPE_NAME, |_| {
+                        ::std::result::Result::Ok(
+                            ::std::boxed::Box::new(#target) as ::std::boxed::Box<dyn ::rustyflow::Node>
+                        )
+                    });
+                }
+            }
+
+            const _: () = {
+                #input_derives
+                struct #input {
+                    #(#names: #types),*
+                }
+
+                #[::rustyflow::__private::async_trait]
+                impl ::rustyflow::Node for #target {
+                    async fn call(
+                        &self,
+                        input: ::rustyflow::__private::serde_json::Value,
+                    ) -> ::std::result::Result<
+                        ::rustyflow::__private::serde_json::Value,
+                        ::rustyflow::FlowError,
+                    > {
+                        let #input { #(#names),* } =
+                            ::rustyflow::__private::serde_json::from_value(input).map_err(|e| {
+                                ::rustyflow::FlowError::NodeFailed(::std::format!(
+                                    "Invalid input for node '{}': {e}",
+                                    #name
+                                ))
+                            })?;
+                        let output = #call;
+                        ::std::result::Result::Ok(
+                            ::rustyflow::__private::serde_json::to_value(output)?,
+                        )
+                    }
+
+                    #input_schema
+                }
+            };
+        },
+        Kind::Tool => {
+            let description = docs_text(&docs);
+            let input_doc = format!("The arguments of [`{target}`].");
+            quote! {
+                #target_struct
+
+                #[doc = #input_doc]
+                #[derive(Debug)]
+                #input_derives
+                #vis struct #input {
+                    #(pub #names: #types),*
+                }
+
+                impl #target {
+                    /// The name the tool is registered under.
+                    pub const NAME: &'static str = #name;
+
+                    /// What the tool does, from its doc comment.
+                    pub const DESCRIPTION: &'static str = #description;
+
+                    /// Register the tool with `registry` under [`NAME`](Self::NAME).
+                    pub fn register(
+                        registry: &mut ::rustyflow::ToolRegistry,
+                    ) -> &mut ::rustyflow::ToolRegistry {
+                        registry.register(Self::NAME, Self::DESCRIPTION, #target)
+                    }
+                }
+
+                #[::rustyflow::__private::async_trait]
+                impl ::rustyflow::Tool for #target {
+                    type Input = #input;
+                    type Output = #output;
+
+                    async fn run(
+                        &self,
+                        input: Self::Input,
+                    ) -> ::std::result::Result<Self::Output, ::rustyflow::FlowError> {
+                        let #input { #(#names),* } = input;
+                        ::std::result::Result::Ok(#call)
+                    }
+
+                    #input_schema
+                }
+            }
+        }
+    })
+}
+
+fn argument(argument: &FnArg) -> syn::Result<Argument> {
+    let FnArg::Typed(typed) = argument else {
+        return Err(syn::Error::new_spanned(
+            argument,
+            "methods are not supported, use a free function",
+        ));
+    };
+    match &*typed.pat {
+        Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => Ok(Argument {
+            name: pat.ident.clone(),
+            ty: (*typed.ty).clone(),
+        }),
+        _ => Err(syn::Error::new_spanned(
+            &typed.pat,
+            "arguments must be plain names, such as `city: String`",
+        )),
+    }
+}
+
+/// The type a function returns on success, and whether it returns a
+/// `Result`.
+fn output_type(output: &ReturnType) -> (Type, bool) {
+    let ReturnType::Type(_, ty) = output else {
+        return (syn::parse_quote!(()), false);
+    };
+    if let Type::Path(path) = &**ty {
+        if let Some(last) = path.path.segments.last() {
+            if last.ident == "Result" {
+                if let PathArguments::AngleBracketed(arguments) = &last.arguments {
+                    if let Some(GenericArgument::Type(ok)) = arguments.args.first() {
+                        return (ok.clone(), true);
+                    }
+                }
+            }
+        }
+    }
+    ((**ty).clone(), false)
+}
+
+/// The text of doc comments, without the space after each `///`.
+fn docs_text(docs: &[&syn::Attribute]) -> String {
+    let lines: Vec<String> = docs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(MetaNameValue {
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(line),
+                        ..
+                    }),
+                ..
+            }) => Some(line.value()),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').unwrap_or(&line).to_string())
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// `get_weather` as `GetWeather`.
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
@@ -0,0 +1,246 @@
+//! Running aggregates over a sequence of inputs.
+//!
+//! [`Accumulator`] folds each input it receives into a running value, such
+//! as a count, a rolling average or a concatenated transcript, and reports
+//! it with every output. A [`Flush`] policy marks outputs at which the value
+//! is complete, optionally starting a fresh aggregate afterwards.
+//!
+//! The accumulator is a [`StatefulNode`], so it is used wrapped in
+//! [`Stateful`](crate::state::Stateful), and its progress is saved with the
+//! checkpoints of a resumable run. It is typically placed in a
+//! [`StreamFlow`](crate::stream::StreamFlow), which feeds it the items of a
+//! source one at a time.
+
+use crate::error::FlowError;
+use crate::state::StatefulNode;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A custom fold from the current value and an input to the next value.
+pub type ReduceFn = Arc<dyn Fn(Value, &Value) -> Result<Value, FlowError> + Send + Sync>;
+
+/// How inputs are combined.
+#[derive(Clone)]
+pub enum Reducer {
+    /// Number of inputs.
+    Count,
+    /// Sum of numeric inputs.
+    Sum,
+    /// Mean of all numeric inputs.
+    Mean,
+    /// Mean of the last `n` numeric inputs.
+    RollingMean(usize),
+    /// String inputs joined with a separator.
+    Concat(String),
+    /// A custom fold, starting from `null`.
+    Custom(ReduceFn),
+}
+
+impl Reducer {
+    /// A custom fold from the current value and an input to the next value.
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(Value, &Value) -> Result<Value, FlowError> + Send + Sync + 'static,
+    {
+        Reducer::Custom(Arc::new(f))
+    }
+}
+
+/// When an [`Accumulator`] reports its value as complete.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Flush {
+    /// After every input.
+    EveryItem,
+    /// After every `n` inputs.
+    Every(usize),
+    /// On the first input at least this long after the previous flush.
+    Interval(Duration),
+    /// On inputs where the value at this JSON pointer is `true`.
+    When(String),
+}
+
+/// The saved progress of an [`Accumulator`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccumulatorState {
+    /// The current aggregate.
+    pub value: Value,
+    /// Inputs folded in since the last reset.
+    pub count: u64,
+    sum: f64,
+    #[serde(default, skip_serializing_if = "VecDeque::is_empty")]
+    window: VecDeque<f64>,
+    last_flush_ms: u64,
+}
+
+/// A node that folds inputs into a running aggregate.
+///
+/// Each call folds the input, or the value at
+/// [`Accumulator::with_pointer`], into the aggregate and returns
+/// `{"value": ..., "count": ..., "flush": ...}`, where `flush` tells whether
+/// the [`Flush`] policy fired. With [`Accumulator::with_reset_on_flush`],
+/// the aggregate starts over after each flush.
+///
+/// # Example
+///
+/// ```rust
+/// use futures::stream::{self, StreamExt};
+/// use rustyflow::accumulate::{Accumulator, Flush, Reducer};
+/// use rustyflow::state::Stateful;
+/// use rustyflow::stream::{Source, StreamFlow, ValueStream};
+/// use rustyflow::FlowError;
+/// use serde_json::{json, Value};
+///
+/// struct Transcript;
+///
+/// impl Source for Transcript {
+///     fn stream(&self, _input: Value) -> ValueStream<'_> {
+///         stream::iter(["Hi.", "How are you?", "Fine.", "Bye."])
+///             .map(|text| Ok(json!({"text": text})))
+///             .boxed()
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let chunker = Accumulator::new(Reducer::Concat(" ".to_string()))
+///     .with_pointer("/text")
+///     .with_flush(Flush::Every(2))
+///     .with_reset_on_flush();
+/// let flow = StreamFlow::new(Box::new(Transcript), vec![Box::new(Stateful::new(chunker))]);
+///
+/// let outputs = flow.execute(json!(null)).await?;
+/// let chunks: Vec<&Value> = outputs
+///     .as_array()
+///     .unwrap()
+///     .iter()
+///     .filter(|output| output["flush"] == true)
+///     .map(|output| &output["value"])
+///     .collect();
+/// assert_eq!(chunks, [&json!("Hi. How are you?"), &json!("Fine. Bye.")]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Accumulator {
+    reducer: Reducer,
+    pointer: Option<String>,
+    flush: Flush,
+    reset_on_flush: bool,
+}
+
+impl Accumulator {
+    /// Create an accumulator that flushes after every input.
+    pub fn new(reducer: Reducer) -> Self {
+        Self {
+            reducer,
+            pointer: None,
+            flush: Flush::EveryItem,
+            reset_on_flush: false,
+        }
+    }
+
+    /// Fold the value at a JSON pointer (e.g. `/text`) instead of the whole
+    /// input.
+    pub fn with_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.pointer = Some(pointer.into());
+        self
+    }
+
+    /// Set when the aggregate is reported as complete.
+    pub fn with_flush(mut self, flush: Flush) -> Self {
+        self.flush = flush;
+        self
+    }
+
+    /// Start a fresh aggregate after each flush.
+    pub fn with_reset_on_flush(mut self) -> Self {
+        self.reset_on_flush = true;
+        self
+    }
+
+    fn fold(&self, state: &mut AccumulatorState, item: &Value) -> Result<(), FlowError> {
+        let number = || {
+            item.as_f64().ok_or_else(|| {
+                FlowError::NodeFailed(format!("Accumulator expected a number, got {item}"))
+            })
+        };
+        state.value = match &self.reducer {
+            Reducer::Count => json!(state.count + 1),
+            Reducer::Sum => {
+                state.sum += number()?;
+                json!(state.sum)
+            }
+            Reducer::Mean => {
+                state.sum += number()?;
+                json!(state.sum / (state.count + 1) as f64)
+            }
+            Reducer::RollingMean(n) => {
+                state.window.push_back(number()?);
+                while state.window.len() > (*n).max(1) {
+                    state.window.pop_front();
+                }
+                json!(state.window.iter().sum::<f64>() / state.window.len() as f64)
+            }
+            Reducer::Concat(separator) => {
+                let text = match item {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                match state.value.as_str() {
+                    Some(previous) if state.count > 0 => {
+                        json!(format!("{previous}{separator}{text}"))
+                    }
+                    _ => json!(text),
+                }
+            }
+            Reducer::Custom(reduce) => reduce(std::mem::take(&mut state.value), item)?,
+        };
+        state.count += 1;
+        Ok(())
+    }
+
+    fn should_flush(&self, state: &AccumulatorState, input: &Value, now_ms: u64) -> bool {
+        match &self.flush {
+            Flush::EveryItem => true,
+            Flush::Every(n) => state.count % (*n).max(1) as u64 == 0,
+            Flush::Interval(interval) => {
+                now_ms.saturating_sub(state.last_flush_ms) >= interval.as_millis() as u64
+            }
+            Flush::When(pointer) => input.pointer(pointer) == Some(&Value::Bool(true)),
+        }
+    }
+}
+
+#[async_trait]
+impl StatefulNode for Accumulator {
+    type State = AccumulatorState;
+
+    async fn call(&self, state: &mut AccumulatorState, input: Value) -> Result<Value, FlowError> {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        if state.count == 0 && state.last_flush_ms == 0 {
+            state.last_flush_ms = now_ms;
+        }
+
+        let item = match &self.pointer {
+            Some(pointer) => input.pointer(pointer).unwrap_or(&Value::Null),
+            None => &input,
+        };
+        self.fold(state, item)?;
+
+        let flush = self.should_flush(state, &input, now_ms);
+        let output = json!({ "value": state.value, "count": state.count, "flush": flush });
+        if flush {
+            if self.reset_on_flush {
+                *state = AccumulatorState::default();
+            }
+            state.last_flush_ms = now_ms;
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,302 @@
+//! Agent nodes that combine a language model with tools.
+//!
+//! This module provides [`ReActAgent`], a node implementing the ReAct
+//! (reason + act) loop on top of a [`ChatModel`] and a [`ToolRegistry`].
+
+use crate::error::FlowError;
+use crate::llm::{record_usage, ChatModel, ChatRequest, Message};
+use crate::node::Node;
+use crate::tool::ToolRegistry;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a helpful assistant that solves tasks step by step. \
+You can use the tools listed below.";
+
+const DEFAULT_MAX_ITERATIONS: usize = 8;
+
+/// A single tool invocation made by the agent.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentStep {
+    /// The reasoning the model gave before acting, if any.
+    pub thought: Option<String>,
+    /// The tool the model called.
+    pub action: String,
+    /// The arguments passed to the tool.
+    pub action_input: Value,
+    /// The tool output (or error) fed back to the model.
+    pub observation: String,
+}
+
+/// What the model asked for in a text (non tool-calling) reply.
+#[derive(Debug, PartialEq)]
+enum ReActReply {
+    Final(String),
+    Action {
+        thought: Option<String>,
+        name: String,
+        input: Value,
+    },
+}
+
+/// A node that runs the ReAct loop: call the model, execute the requested
+/// tool, feed the observation back, and repeat until a final answer.
+///
+/// Tools are offered to the model both natively (via [`ChatRequest::tools`])
+/// and through a text protocol in the system prompt, so models without native
+/// tool calling can participate:
+///
+/// ```text
+/// Thought: <reasoning>
+/// Action: <tool name>
+/// Action Input: <JSON arguments>
+/// ```
+///
+/// or `Final Answer: <answer>` once the task is solved.
+///
+/// The input is either a string or an object with a `question` field. The
+/// output is `{"answer": ..., "iterations": n, "steps": [...]}`, plus the
+/// full conversation as `"transcript"` if
+/// [`ReActAgent::with_transcript`] is set.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::agent::ReActAgent;
+/// use rustyflow::llm::{ChatModel, ChatRequest, ChatResponse, Message};
+/// use rustyflow::tool::ToolRegistry;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// struct ScriptedModel;
+///
+/// #[async_trait]
+/// impl ChatModel for ScriptedModel {
+///     async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, FlowError> {
+///         let reply = if request.messages.len() <= 2 {
+///             "Thought: I should look it up\nAction: lookup\nAction Input: {\"key\": \"rust\"}"
+///         } else {
+///             "Final Answer: a systems language"
+///         };
+///         Ok(ChatResponse { message: Message::assistant(reply), usage: None })
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let agent = ReActAgent::new(ScriptedModel, ToolRegistry::new()).with_max_iterations(4);
+/// let result = agent.call(json!({"question": "What is Rust?"})).await?;
+/// assert_eq!(result["answer"], "a systems language");
+/// assert_eq!(result["steps"][0]["action"], "lookup");
+/// # Ok(())
+/// # }
+/// ```
+pub struct ReActAgent<M: ChatModel> {
+    model: M,
+    tools: ToolRegistry,
+    system_prompt: String,
+    max_iterations: usize,
+    transcript: bool,
+}
+
+impl<M: ChatModel> ReActAgent<M> {
+    /// Create a new agent with the given model and tools.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The chat model that drives the agent
+    /// * `tools` - The tools the agent may call
+    pub fn new(model: M, tools: ToolRegistry) -> Self {
+        Self {
+            model,
+            tools,
+            system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            transcript: false,
+        }
+    }
+
+    /// Replace the instructions placed before the tool list in the system prompt.
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = prompt.into();
+        self
+    }
+
+    /// Set the maximum number of model calls before giving up.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Include every message exchanged with the model in the output, as a
+    /// `"transcript"` array of [`Message`]s, for example to keep it in a
+    /// [`RunRecord`](crate::history::RunRecord).
+    pub fn with_transcript(mut self) -> Self {
+        self.transcript = true;
+        self
+    }
+
+    fn build_system_prompt(&self) -> String {
+        let mut prompt = self.system_prompt.clone();
+        prompt.push_str("\n\nAvailable tools:\n");
+        for spec in self.tools.specs() {
+            prompt.push_str(&format!(
+                "- {}: {} (arguments schema: {})\n",
+                spec.name, spec.description, spec.parameters
+            ));
+        }
+        prompt.push_str(
+            "\nTo use a tool, reply exactly in this format:\n\
+             Thought: <your reasoning>\n\
+             Action: <tool name>\n\
+             Action Input: <JSON arguments>\n\n\
+             When you know the answer, reply with:\n\
+             Final Answer: <the answer>",
+        );
+        prompt
+    }
+
+    async fn observe(&self, name: &str, arguments: Value) -> String {
+        match self.tools.invoke(name, arguments).await {
+            Ok(Value::String(text)) => text,
+            Ok(value) => value.to_string(),
+            Err(e) => format!("Error: {e}"),
+        }
+    }
+}
+
+/// Extract the question from the agent input.
+fn question_from(input: &Value) -> Result<String, FlowError> {
+    match input {
+        Value::String(question) => Ok(question.clone()),
+        Value::Object(map) => map
+            .get("question")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| FlowError::NodeFailed("Expected 'question' field".to_string())),
+        _ => Err(FlowError::NodeFailed(
+            "Input must be a string or an object with a 'question' field".to_string(),
+        )),
+    }
+}
+
+/// Parse a ReAct-formatted model reply.
+fn parse_reply(text: &str) -> Option<ReActReply> {
+    if let Some(idx) = text.find("Final Answer:") {
+        let answer = text[idx + "Final Answer:".len()..].trim();
+        return Some(ReActReply::Final(answer.to_string()));
+    }
+
+    let thought = text
+        .find("Thought:")
+        .map(|idx| &text[idx + "Thought:".len()..])
+        .map(|rest| rest.lines().next().unwrap_or("").trim().to_string());
+
+    let action_idx = text.find("Action:")?;
+    let after_action = &text[action_idx + "Action:".len()..];
+    let name = after_action.lines().next()?.trim().to_string();
+    if name.is_empty() {
+        return None;
+    }
+
+    let input = match text.find("Action Input:") {
+        Some(idx) => {
+            let raw = text[idx + "Action Input:".len()..].trim();
+            serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+        }
+        None => json!({}),
+    };
+
+    Some(ReActReply::Action {
+        thought,
+        name,
+        input,
+    })
+}
+
+#[async_trait]
+impl<M: ChatModel> Node for ReActAgent<M> {
+    /// Run the agent loop until the model produces a final answer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the input has no question or the
+    /// model does not produce a final answer within the iteration limit.
+    /// Tool failures are reported back to the model as observations rather
+    /// than aborting the loop.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let question = question_from(&input)?;
+        let mut messages = vec![
+            Message::system(self.build_system_prompt()),
+            Message::user(question),
+        ];
+        let mut steps = Vec::new();
+
+        for iteration in 1..=self.max_iterations {
+            let request = ChatRequest::new(messages.clone()).with_tools(self.tools.specs());
+            let reply = record_usage(self.model.chat(request).await?).message;
+
+            // Native tool calls take precedence over the text protocol
+            if !reply.tool_calls.is_empty() {
+                let calls = reply.tool_calls.clone();
+                let thought = (!reply.content.is_empty()).then(|| reply.content.clone());
+                messages.push(reply);
+                for call in calls {
+                    let observation = self.observe(&call.name, call.arguments.clone()).await;
+                    messages.push(Message::tool(call.id, observation.clone()));
+                    steps.push(AgentStep {
+                        thought: thought.clone(),
+                        action: call.name,
+                        action_input: call.arguments,
+                        observation,
+                    });
+                }
+                continue;
+            }
+
+            match parse_reply(&reply.content) {
+                Some(ReActReply::Final(answer)) => {
+                    let mut output = json!({
+                        "answer": answer,
+                        "iterations": iteration,
+                        "steps": steps,
+                    });
+                    if self.transcript {
+                        messages.push(reply);
+                        output["transcript"] = serde_json::to_value(&messages)?;
+                    }
+                    return Ok(output);
+                }
+                Some(ReActReply::Action {
+                    thought,
+                    name,
+                    input,
+                }) => {
+                    let observation = self.observe(&name, input.clone()).await;
+                    messages.push(reply);
+                    messages.push(Message::user(format!("Observation: {observation}")));
+                    steps.push(AgentStep {
+                        thought,
+                        action: name,
+                        action_input: input,
+                        observation,
+                    });
+                }
+                None => {
+                    messages.push(reply);
+                    messages.push(Message::user(
+                        "Observation: your reply did not follow the required format. \
+                         Reply with an Action or a Final Answer.",
+                    ));
+                }
+            }
+        }
+
+        Err(FlowError::NodeFailed(format!(
+            "ReAct agent did not reach a final answer within {} iterations",
+            self.max_iterations
+        )))
+    }
+}
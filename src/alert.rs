@@ -0,0 +1,491 @@
+//! Threshold alerts on flow failure rate, latency and cost.
+//!
+//! An [`AlertMonitor`] is fed each finished run as a
+//! [`RunRecord`](crate::history::RunRecord), keeps a sliding window of recent
+//! runs per flow, and evaluates its [`AlertRule`]s against that window. When
+//! a rule is crossed it sends an [`Alert`] to every configured [`Notifier`],
+//! then stays quiet for that rule and flow until the cooldown has passed.
+//!
+//! [`EmailNotifier`] hands alerts to the local `sendmail`. With the `reqwest`
+//! feature, `WebhookNotifier` posts them as JSON and `SlackNotifier` posts
+//! them to a Slack incoming webhook.
+
+use crate::error::FlowError;
+use crate::history::RunRecord;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The quantity an [`AlertRule`] watches.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "threshold")]
+pub enum AlertKind {
+    /// Fraction of failed runs, from `0.0` to `1.0`.
+    ErrorRate(f64),
+    /// 95th percentile run duration.
+    P95Latency(Duration),
+    /// Spend per hour, extrapolated from the cost of the window's runs.
+    BurnRate(f64),
+}
+
+impl fmt::Display for AlertKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertKind::ErrorRate(_) => write!(f, "error rate"),
+            AlertKind::P95Latency(_) => write!(f, "p95 latency"),
+            AlertKind::BurnRate(_) => write!(f, "burn rate"),
+        }
+    }
+}
+
+/// A threshold evaluated over a sliding window of runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRule {
+    kind: AlertKind,
+    window: Duration,
+    min_runs: usize,
+}
+
+impl AlertRule {
+    fn new(kind: AlertKind) -> Self {
+        Self {
+            kind,
+            window: Duration::from_secs(300),
+            min_runs: 1,
+        }
+    }
+
+    /// Alert when more than `threshold` of the runs fail.
+    pub fn error_rate(threshold: f64) -> Self {
+        Self::new(AlertKind::ErrorRate(threshold)).with_min_runs(10)
+    }
+
+    /// Alert when the 95th percentile run duration exceeds `threshold`.
+    pub fn p95_latency(threshold: Duration) -> Self {
+        Self::new(AlertKind::P95Latency(threshold)).with_min_runs(10)
+    }
+
+    /// Alert when spend extrapolated to an hour exceeds `per_hour`.
+    pub fn burn_rate(per_hour: f64) -> Self {
+        Self::new(AlertKind::BurnRate(per_hour))
+    }
+
+    /// Set the sliding window the rule looks at. Defaults to five minutes.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set how many runs the window needs before the rule is evaluated.
+    ///
+    /// Defaults to 10 for error rate and latency rules, so a single failure
+    /// after a quiet period does not page anyone, and 1 for burn rate rules.
+    pub fn with_min_runs(mut self, min_runs: usize) -> Self {
+        self.min_runs = min_runs.max(1);
+        self
+    }
+
+    /// The quantity and threshold the rule watches.
+    pub fn kind(&self) -> AlertKind {
+        self.kind
+    }
+
+    /// The observed value, if it crosses the threshold.
+    fn evaluate(&self, runs: &[Sample]) -> Option<f64> {
+        if runs.len() < self.min_runs {
+            return None;
+        }
+        match self.kind {
+            AlertKind::ErrorRate(threshold) => {
+                let failed = runs.iter().filter(|run| run.failed).count();
+                let rate = failed as f64 / runs.len() as f64;
+                (rate > threshold).then_some(rate)
+            }
+            AlertKind::P95Latency(threshold) => {
+                let mut durations: Vec<u64> = runs.iter().map(|run| run.duration_ms).collect();
+                durations.sort_unstable();
+                let rank = (durations.len() * 95).div_ceil(100).max(1) - 1;
+                let p95 = Duration::from_millis(durations[rank]);
+                (p95 > threshold).then_some(p95.as_secs_f64())
+            }
+            AlertKind::BurnRate(per_hour) => {
+                let cost: f64 = runs.iter().map(|run| run.cost).sum();
+                let rate = cost * 3600.0 / self.window.as_secs_f64().max(1.0);
+                (rate > per_hour).then_some(rate)
+            }
+        }
+    }
+}
+
+/// A notification that a rule was crossed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    /// The flow the rule was evaluated for.
+    pub flow: String,
+    /// The rule's quantity and threshold.
+    pub kind: AlertKind,
+    /// The observed value: a fraction, seconds, or cost per hour.
+    pub value: f64,
+    /// Number of runs in the window.
+    pub runs: usize,
+    /// A human-readable summary.
+    pub message: String,
+}
+
+/// A destination for alerts.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Deliver an alert.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the alert cannot be delivered.
+    async fn notify(&self, alert: &Alert) -> Result<(), FlowError>;
+}
+
+#[async_trait]
+impl<F> Notifier for F
+where
+    F: Fn(&Alert) + Send + Sync,
+{
+    async fn notify(&self, alert: &Alert) -> Result<(), FlowError> {
+        self(alert);
+        Ok(())
+    }
+}
+
+/// What the monitor remembers of a run.
+#[derive(Clone, Copy)]
+struct Sample {
+    at: Instant,
+    failed: bool,
+    duration_ms: u64,
+    cost: f64,
+}
+
+/// Watches run records and fires alerts when rules are crossed.
+///
+/// Rules added with [`AlertMonitor::with_rule`] apply to every flow; rules
+/// added with [`AlertMonitor::for_flow`] only to the named flow. Each flow is
+/// evaluated over its own window of runs.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::alert::{Alert, AlertMonitor, AlertRule};
+/// use rustyflow::history::RunRecord;
+/// use rustyflow::FlowError;
+/// use std::sync::{Arc, Mutex};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let fired = Arc::new(Mutex::new(Vec::new()));
+/// let sink = Arc::clone(&fired);
+///
+/// let monitor = AlertMonitor::new()
+///     .for_flow("checkout", AlertRule::error_rate(0.2).with_min_runs(5))
+///     .with_notifier(move |alert: &Alert| sink.lock().unwrap().push(alert.message.clone()));
+///
+/// for i in 0..5 {
+///     let run = RunRecord::new(format!("run-{i}"), "checkout");
+///     let run = if i < 2 { run.with_error("upstream 502") } else { run };
+///     monitor.observe(&run).await;
+/// }
+///
+/// assert_eq!(*fired.lock().unwrap(), ["checkout: error rate is 0.40 over 5 runs (threshold 0.20)"]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AlertMonitor {
+    rules: Vec<(Option<String>, AlertRule)>,
+    notifiers: Vec<Box<dyn Notifier>>,
+    cooldown: Option<Duration>,
+    samples: Mutex<HashMap<String, VecDeque<Sample>>>,
+    last_fired: Mutex<HashMap<(String, usize), Instant>>,
+}
+
+impl Default for AlertMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlertMonitor {
+    /// Create a monitor without rules or notifiers.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            notifiers: Vec::new(),
+            cooldown: None,
+            samples: Mutex::new(HashMap::new()),
+            last_fired: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add a rule evaluated for every flow.
+    pub fn with_rule(mut self, rule: AlertRule) -> Self {
+        self.rules.push((None, rule));
+        self
+    }
+
+    /// Add a rule evaluated only for the flow named `flow`.
+    pub fn for_flow(mut self, flow: impl Into<String>, rule: AlertRule) -> Self {
+        self.rules.push((Some(flow.into()), rule));
+        self
+    }
+
+    /// Add a destination for alerts.
+    pub fn with_notifier(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.notifiers.push(Box::new(notifier));
+        self
+    }
+
+    /// Set how long a rule stays quiet for a flow after firing.
+    ///
+    /// Defaults to the rule's window.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Record a finished run and fire any rules it crosses.
+    ///
+    /// Notifier failures are logged rather than returned, so a broken
+    /// destination cannot fail the caller.
+    ///
+    /// # Returns
+    ///
+    /// The alerts that fired.
+    pub async fn observe(&self, run: &RunRecord) -> Vec<Alert> {
+        let alerts = self.evaluate(run);
+        for alert in &alerts {
+            tracing::warn!(flow = %alert.flow, "{}", alert.message);
+            for notifier in &self.notifiers {
+                if let Err(e) = notifier.notify(alert).await {
+                    tracing::error!("Failed to deliver alert: {e}");
+                }
+            }
+        }
+        alerts
+    }
+
+    fn evaluate(&self, run: &RunRecord) -> Vec<Alert> {
+        let rules: Vec<(usize, &AlertRule)> = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, (flow, _))| match flow {
+                Some(flow) => *flow == run.flow,
+                None => true,
+            })
+            .map(|(index, (_, rule))| (index, rule))
+            .collect();
+        if rules.is_empty() {
+            return Vec::new();
+        }
+
+        let now = Instant::now();
+        let longest = rules.iter().map(|(_, rule)| rule.window).max();
+        let mut samples = self.samples.lock().unwrap();
+        let window = samples.entry(run.flow.clone()).or_default();
+        window.push_back(Sample {
+            at: now,
+            failed: run.error.is_some(),
+            duration_ms: run.duration_ms,
+            cost: run.usage.iter().map(|usage| usage.cost).sum(),
+        });
+        if let Some(longest) = longest {
+            while window
+                .front()
+                .is_some_and(|sample| now.duration_since(sample.at) > longest)
+            {
+                window.pop_front();
+            }
+        }
+
+        let mut last_fired = self.last_fired.lock().unwrap();
+        let mut alerts = Vec::new();
+        for (index, rule) in rules {
+            let recent: Vec<Sample> = window
+                .iter()
+                .filter(|sample| now.duration_since(sample.at) <= rule.window)
+                .copied()
+                .collect();
+            let Some(value) = rule.evaluate(&recent) else {
+                continue;
+            };
+
+            let key = (run.flow.clone(), index);
+            let cooldown = self.cooldown.unwrap_or(rule.window);
+            if last_fired
+                .get(&key)
+                .is_some_and(|fired| now.duration_since(*fired) < cooldown)
+            {
+                continue;
+            }
+            last_fired.insert(key, now);
+            alerts.push(Alert {
+                flow: run.flow.clone(),
+                kind: rule.kind,
+                value,
+                runs: recent.len(),
+                message: message(&run.flow, rule.kind, value, recent.len()),
+            });
+        }
+        alerts
+    }
+}
+
+fn message(flow: &str, kind: AlertKind, value: f64, runs: usize) -> String {
+    match kind {
+        AlertKind::ErrorRate(threshold) => {
+            format!("{flow}: {kind} is {value:.2} over {runs} runs (threshold {threshold:.2})")
+        }
+        AlertKind::P95Latency(threshold) => format!(
+            "{flow}: {kind} is {value:.3}s over {runs} runs (threshold {:.3}s)",
+            threshold.as_secs_f64()
+        ),
+        AlertKind::BurnRate(threshold) => {
+            format!("{flow}: {kind} is {value:.2}/h over {runs} runs (threshold {threshold:.2}/h)")
+        }
+    }
+}
+
+/// A [`Notifier`] that emails alerts through the local `sendmail` command.
+pub struct EmailNotifier {
+    from: String,
+    to: Vec<String>,
+    command: String,
+}
+
+impl EmailNotifier {
+    /// Create a notifier that sends from `from` to each of `to`.
+    pub fn new(from: impl Into<String>, to: Vec<String>) -> Self {
+        Self {
+            from: from.into(),
+            to,
+            command: "sendmail".to_string(),
+        }
+    }
+
+    /// Set the sendmail-compatible command. Defaults to `sendmail`.
+    pub fn with_command(mut self, command: impl Into<String>) -> Self {
+        self.command = command.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, alert: &Alert) -> Result<(), FlowError> {
+        use tokio::io::AsyncWriteExt;
+
+        let mail = format!(
+            "From: {}\r\nTo: {}\r\nSubject: [rustyflow] {} alert for {}\r\n\r\n{}\r\n",
+            self.from,
+            self.to.join(", "),
+            alert.kind,
+            alert.flow,
+            alert.message
+        );
+        let mut child = tokio::process::Command::new(&self.command)
+            .arg("-t")
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| FlowError::NodeFailed(format!("Failed to run {}: {e}", self.command)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(mail.as_bytes())
+                .await
+                .map_err(|e| FlowError::NodeFailed(format!("Failed to write email: {e}")))?;
+        }
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Failed to run {}: {e}", self.command)))?;
+        if !status.success() {
+            return Err(FlowError::NodeFailed(format!(
+                "{} exited with {status}",
+                self.command
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "reqwest")]
+pub use webhook::{SlackNotifier, WebhookNotifier};
+
+#[cfg(feature = "reqwest")]
+mod webhook {
+    use super::{Alert, Notifier};
+    use crate::error::FlowError;
+    use async_trait::async_trait;
+    use reqwest::Client;
+    use serde_json::{json, Value};
+
+    async fn post(client: &Client, url: &str, body: &Value) -> Result<(), FlowError> {
+        client
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| FlowError::NodeFailed(format!("Alert delivery to {url} failed: {e}")))?;
+        Ok(())
+    }
+
+    /// A [`Notifier`] that posts each [`Alert`] as JSON to a URL.
+    ///
+    /// This type is available with the `reqwest` feature.
+    pub struct WebhookNotifier {
+        client: Client,
+        url: String,
+    }
+
+    impl WebhookNotifier {
+        /// Create a notifier posting to `url`.
+        pub fn new(url: impl Into<String>) -> Self {
+            Self {
+                client: crate::http::shared_client(),
+                url: url.into(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Notifier for WebhookNotifier {
+        async fn notify(&self, alert: &Alert) -> Result<(), FlowError> {
+            post(&self.client, &self.url, &serde_json::to_value(alert)?).await
+        }
+    }
+
+    /// A [`Notifier`] that posts alert messages to a Slack incoming webhook.
+    ///
+    /// This type is available with the `reqwest` feature.
+    pub struct SlackNotifier {
+        client: Client,
+        url: String,
+    }
+
+    impl SlackNotifier {
+        /// Create a notifier posting to the incoming webhook `url`.
+        pub fn new(url: impl Into<String>) -> Self {
+            Self {
+                client: crate::http::shared_client(),
+                url: url.into(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Notifier for SlackNotifier {
+        async fn notify(&self, alert: &Alert) -> Result<(), FlowError> {
+            let text = format!(":rotating_light: {}", alert.message);
+            post(&self.client, &self.url, &json!({ "text": text })).await
+        }
+    }
+}
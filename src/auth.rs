@@ -0,0 +1,304 @@
+//! Authentication and per-caller rate limits for the HTTP server.
+//!
+//! [`RequireAuth`] wraps a [`Router`] in a middleware layer that asks an
+//! [`Authenticator`] who is calling, rejects unknown callers with
+//! `401 Unauthorized`, and throttles each caller to its [`RateLimit`] with
+//! `429 Too Many Requests`. Authenticated requests carry the caller's
+//! [`Principal`] as a request extension, so handlers can read it with
+//! `Extension<Principal>`.
+//!
+//! [`ApiKeys`] is the built-in authenticator for static keys, sent as
+//! `Authorization: Bearer <key>` or `X-API-Key: <key>`. Implement
+//! [`Authenticator`] to check callers against anything else.
+
+use crate::error::FlowError;
+use async_trait::async_trait;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Json, Response};
+use axum::Router;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How many requests a caller may make in a period.
+///
+/// Limits are enforced as a token bucket: a caller can burst up to
+/// `requests` at once, and regains capacity evenly over `per`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Requests allowed per period.
+    pub requests: u32,
+    /// The period.
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Allow `requests` per second.
+    pub fn per_second(requests: u32) -> Self {
+        Self {
+            requests,
+            per: Duration::from_secs(1),
+        }
+    }
+
+    /// Allow `requests` per minute.
+    pub fn per_minute(requests: u32) -> Self {
+        Self {
+            requests,
+            per: Duration::from_secs(60),
+        }
+    }
+}
+
+/// An authenticated caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    /// A stable identifier of the caller, such as a key name or user id.
+    /// Rate limits are tracked per id.
+    pub id: String,
+    /// The caller's rate limit, overriding the default of [`RequireAuth`].
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl Principal {
+    /// A caller with the default rate limit.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            rate_limit: None,
+        }
+    }
+
+    /// Give the caller its own rate limit.
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+}
+
+/// Decides who is making a request.
+#[async_trait]
+pub trait Authenticator: Send + Sync {
+    /// Identify the caller from the request headers.
+    ///
+    /// # Returns
+    ///
+    /// The caller, or `None` to reject the request as unauthenticated.
+    ///
+    /// # Errors
+    ///
+    /// An error is answered with `500 Internal Server Error`, for example
+    /// when an identity provider cannot be reached.
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<Option<Principal>, FlowError>;
+}
+
+/// An [`Authenticator`] for a fixed set of API keys.
+///
+/// Only SHA-256 digests of the keys are kept in memory.
+#[derive(Debug, Default, Clone)]
+pub struct ApiKeys {
+    keys: HashMap<[u8; 32], Principal>,
+}
+
+impl ApiKeys {
+    /// Create an authenticator that accepts no keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept `key`, identifying its holder as `id`.
+    pub fn with_key(self, id: impl Into<String>, key: &str) -> Self {
+        self.with_principal(key, Principal::new(id))
+    }
+
+    /// Accept `key`, identifying its holder as `principal`, which may carry
+    /// its own rate limit.
+    pub fn with_principal(mut self, key: &str, principal: Principal) -> Self {
+        self.keys.insert(digest(key), principal);
+        self
+    }
+
+    /// Parse keys from `id:key` entries separated by commas, as used by the
+    /// `RUSTYFLOW_API_KEYS` variable of the server binary.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if an entry has no `:`.
+    pub fn parse(entries: &str) -> Result<Self, FlowError> {
+        entries
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .try_fold(Self::new(), |keys, entry| match entry.split_once(':') {
+                Some((id, key)) if !key.is_empty() => Ok(keys.with_key(id, key)),
+                _ => Err(FlowError::NodeFailed(format!(
+                    "API key entry '{}' is not of the form id:key",
+                    entry.split(':').next().unwrap_or_default()
+                ))),
+            })
+    }
+
+    /// Whether no keys are configured.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[async_trait]
+impl Authenticator for ApiKeys {
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<Option<Principal>, FlowError> {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let key = bearer.or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()));
+        Ok(key.and_then(|key| self.keys.get(&digest(key.trim())).cloned()))
+    }
+}
+
+fn digest(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Middleware that authenticates and rate-limits every request of a router.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::body::Body;
+/// use axum::http::{Request, StatusCode};
+/// use axum::routing::get;
+/// use axum::{Extension, Router};
+/// use rustyflow::auth::{ApiKeys, Principal, RateLimit, RequireAuth};
+/// use tower::ServiceExt;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let app = Router::new().route(
+///     "/whoami",
+///     get(|Extension(caller): Extension<Principal>| async move { caller.id }),
+/// );
+/// let keys = ApiKeys::new().with_key("ci", "secret-1");
+/// let app = RequireAuth::new(keys)
+///     .with_rate_limit(RateLimit::per_minute(1))
+///     .apply(app);
+///
+/// let request = |key: &str| {
+///     Request::get("/whoami")
+///         .header("authorization", format!("Bearer {key}"))
+///         .body(Body::empty())
+///         .unwrap()
+/// };
+/// let response = app.clone().oneshot(request("wrong")).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+///
+/// let response = app.clone().oneshot(request("secret-1")).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::OK);
+///
+/// let response = app.oneshot(request("secret-1")).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+/// assert!(response.headers().contains_key("retry-after"));
+/// # }
+/// ```
+pub struct RequireAuth {
+    authenticator: Arc<dyn Authenticator>,
+    rate_limit: Option<RateLimit>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RequireAuth {
+    /// Require callers to be accepted by `authenticator`, without a default
+    /// rate limit.
+    pub fn new(authenticator: impl Authenticator + 'static) -> Self {
+        Self {
+            authenticator: Arc::new(authenticator),
+            rate_limit: None,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limit callers whose [`Principal`] has no rate limit of its own.
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// Wrap every route of `router` in this middleware.
+    pub fn apply(self, router: Router) -> Router {
+        router.layer(middleware::from_fn_with_state(Arc::new(self), authenticate))
+    }
+
+    /// Take one request from the caller's bucket, or return how long until
+    /// one is available.
+    fn acquire(&self, principal: &Principal) -> Result<(), Duration> {
+        let Some(limit) = principal.rate_limit.or(self.rate_limit) else {
+            return Ok(());
+        };
+        let capacity = f64::from(limit.requests.max(1));
+        let refill_per_sec = capacity / limit.per.as_secs_f64().max(f64::EPSILON);
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(principal.id.clone()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / refill_per_sec,
+            ))
+        }
+    }
+}
+
+async fn authenticate(
+    State(auth): State<Arc<RequireAuth>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let principal = match auth.authenticator.authenticate(request.headers()).await {
+        Ok(Some(principal)) => principal,
+        Ok(None) => {
+            let body = json!({ "error": "Missing or invalid API key" });
+            let mut response = (StatusCode::UNAUTHORIZED, Json(body)).into_response();
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            return response;
+        }
+        Err(e) => {
+            tracing::error!("Authentication failed: {e}");
+            let body = json!({ "error": e.to_string() });
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response();
+        }
+    };
+
+    if let Err(wait) = auth.acquire(&principal) {
+        tracing::warn!("Rate limit exceeded for '{}'", principal.id);
+        let body = json!({ "error": format!("Rate limit exceeded for '{}'", principal.id) });
+        let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+        let seconds = wait.as_secs_f64().ceil().max(1.0) as u64;
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        return response;
+    }
+
+    request.extensions_mut().insert(principal);
+    next.run(request).await
+}
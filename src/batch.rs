@@ -1,19 +1,208 @@
 //! Batch processing for concurrent array operations.
 //!
 //! This module provides the [`Batch`] wrapper that applies a node to each
-//! element of a JSON array concurrently.
+//! element of a JSON array concurrently, optionally under a fixed or
+//! [`AdaptiveConcurrency`] limit or in chunks spaced out over time, or to
+//! each element of an async stream as it arrives with [`Batch::call_stream`]
+//! and [`Batch::call_stream_unordered`].
+//! [`BatchFlow`] does the same with a whole multi-node [`Flow`] per element.
+//!
+//! Both report their progress as elements finish: to a callback set with
+//! `with_on_progress`, and, in an execution with
+//! [events](crate::events), as
+//! [`ExecutionEvent::Progress`](crate::events::ExecutionEvent::Progress)
+//! events that the server streams to clients.
 
 use crate::error::FlowError;
+use crate::events::{self, ExecutionEvent};
+use crate::executor::NodeInput;
+use crate::flow::Flow;
 use crate::node::Node;
+use crate::telemetry;
 use async_trait::async_trait;
 use futures::future::join_all;
+use futures::stream::{self, BoxStream, FuturesUnordered, Stream, StreamExt};
+use serde::Serialize;
 use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Elements in flight at once in a stream, unless a limit is set.
+const DEFAULT_STREAM_CONCURRENCY: usize = 16;
+
+/// Settings for AIMD (additive increase, multiplicative decrease) concurrency.
+///
+/// The batch starts with `initial` elements in flight. Each success within
+/// the latency target raises the limit by about one per round of requests;
+/// a `FlowError::RateLimited` or `FlowError::Timeout`, or a success slower
+/// than the target, multiplies it by `decrease_factor`. Rate-limited and
+/// timed-out elements are retried with exponential backoff. The learned limit
+/// carries over to the next call of the same [`Batch`].
+#[derive(Debug, Clone)]
+pub struct AdaptiveConcurrency {
+    initial: usize,
+    min: usize,
+    max: usize,
+    decrease_factor: f64,
+    latency_target: Option<Duration>,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl Default for AdaptiveConcurrency {
+    fn default() -> Self {
+        Self {
+            initial: 4,
+            min: 1,
+            max: 64,
+            decrease_factor: 0.5,
+            latency_target: None,
+            max_retries: 5,
+            backoff: Duration::from_millis(250),
+        }
+    }
+}
+
+impl AdaptiveConcurrency {
+    /// Create settings that start at 4 concurrent elements, range between 1
+    /// and 64, halve on overload, and retry overloaded elements 5 times.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of elements in flight at the start.
+    pub fn with_initial(mut self, initial: usize) -> Self {
+        self.initial = initial.max(1);
+        self
+    }
+
+    /// Bound the concurrency limit.
+    pub fn with_limits(mut self, min: usize, max: usize) -> Self {
+        self.min = min.max(1);
+        self.max = max.max(self.min);
+        self
+    }
+
+    /// Set the factor applied to the limit on overload, between 0 and 1.
+    pub fn with_decrease_factor(mut self, factor: f64) -> Self {
+        self.decrease_factor = factor.clamp(0.05, 0.95);
+        self
+    }
+
+    /// Treat successful calls slower than `target` as a sign of overload.
+    pub fn with_latency_target(mut self, target: Duration) -> Self {
+        self.latency_target = Some(target);
+        self
+    }
+
+    /// Set how often a rate-limited or timed-out element is retried.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry; it doubles on each attempt.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    fn clamp(&self, limit: f64) -> f64 {
+        limit.clamp(self.min as f64, self.max as f64)
+    }
+}
+
+/// How far a batch has got, passed to its progress callback each time an
+/// element finishes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchProgress {
+    /// Elements finished so far, including failed ones.
+    pub completed: usize,
+    /// Elements that failed so far.
+    pub failed: usize,
+    /// The number of elements, unknown for streams.
+    pub total: Option<usize>,
+    /// Time since the batch started.
+    pub elapsed: Duration,
+    /// The estimated time until every element has finished, at the average
+    /// rate so far.
+    pub eta: Option<Duration>,
+}
+
+type ProgressCallback = Arc<dyn Fn(&BatchProgress) + Send + Sync>;
+
+/// Counts finished elements and reports progress.
+struct Progress {
+    total: Option<usize>,
+    completed: AtomicUsize,
+    failed: AtomicUsize,
+    started: Instant,
+    callback: Option<ProgressCallback>,
+}
+
+impl Progress {
+    fn new(total: Option<usize>, callback: Option<ProgressCallback>) -> Self {
+        Self {
+            total,
+            completed: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            started: Instant::now(),
+            callback,
+        }
+    }
+
+    /// Count one finished element and report the progress.
+    fn record(&self, succeeded: bool) {
+        let completed = self.completed.fetch_add(1, Ordering::SeqCst) + 1;
+        let failed = match succeeded {
+            true => self.failed.load(Ordering::SeqCst),
+            false => self.failed.fetch_add(1, Ordering::SeqCst) + 1,
+        };
+        let elapsed = self.started.elapsed();
+        let eta = self.total.map(|total| {
+            elapsed.mul_f64(total.saturating_sub(completed) as f64 / completed as f64)
+        });
+        let progress = BatchProgress {
+            completed,
+            failed,
+            total: self.total,
+            elapsed,
+            eta,
+        };
+        events::emit(|index| ExecutionEvent::Progress {
+            index,
+            completed,
+            failed,
+            total: self.total,
+            eta_ms: eta.map(|eta| eta.as_millis() as u64),
+        });
+        if let Some(callback) = &self.callback {
+            callback(&progress);
+        }
+    }
+
+    /// Count the element whose call returned `result`.
+    fn track<T>(&self, result: Result<T, FlowError>) -> Result<T, FlowError> {
+        self.record(result.is_ok());
+        result
+    }
+}
+
+/// Returns `true` for errors that signal an overloaded upstream.
+fn is_overload(error: &FlowError) -> bool {
+    matches!(
+        error.root_cause(),
+        FlowError::RateLimited(_) | FlowError::Timeout(_)
+    )
+}
 
 /// A wrapper node that applies another node to each element of a JSON array concurrently.
 ///
 /// `Batch` takes any node and applies it to each element of a JSON array in parallel,
 /// collecting the results back into an array. This is useful for processing large
-/// datasets efficiently.
+/// datasets efficiently: each element is moved into its call rather than cloned.
 ///
 /// # Example
 ///
@@ -47,7 +236,13 @@ pub struct Batch<T>
 where
     T: Node,
 {
-    wrapped_node: T,
+    wrapped_node: Arc<T>,
+    adaptive: Option<(AdaptiveConcurrency, Mutex<f64>)>,
+    max_concurrency: Option<usize>,
+    chunk_size: Option<usize>,
+    chunk_delay: Duration,
+    spawned: Option<Arc<dyn Node>>,
+    on_progress: Option<ProgressCallback>,
 }
 
 impl<T> Batch<T>
@@ -64,7 +259,620 @@ where
     ///
     /// A new `Batch` instance that will process arrays concurrently
     pub fn new(wrapped_node: T) -> Self {
-        Self { wrapped_node }
+        Self {
+            wrapped_node: Arc::new(wrapped_node),
+            adaptive: None,
+            max_concurrency: None,
+            chunk_size: None,
+            chunk_delay: Duration::ZERO,
+            spawned: None,
+            on_progress: None,
+        }
+    }
+
+    /// Limit concurrency adaptively instead of running every element at once.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::batch::AdaptiveConcurrency;
+    /// use rustyflow::{Batch, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use async_trait::async_trait;
+    ///
+    /// /// Rejects every third request, like a rate-limited API.
+    /// struct Quota(AtomicUsize);
+    ///
+    /// #[async_trait]
+    /// impl Node for Quota {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         if self.0.fetch_add(1, Ordering::SeqCst) % 3 == 2 {
+    ///             return Err(FlowError::RateLimited("429".to_string()));
+    ///         }
+    ///         Ok(input)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let batch = Batch::new(Quota(AtomicUsize::new(0))).with_adaptive_concurrency(
+    ///     AdaptiveConcurrency::new().with_backoff(std::time::Duration::from_millis(1)),
+    /// );
+    /// let result = batch.call(json!([1, 2, 3, 4, 5, 6])).await?;
+    /// assert_eq!(result, json!([1, 2, 3, 4, 5, 6]));
+    ///
+    /// // Elements in flight finish, and the first failed element's error is
+    /// // returned even if a later one failed sooner
+    /// struct Slow;
+    ///
+    /// #[async_trait]
+    /// impl Node for Slow {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         let millis = input.as_u64().unwrap();
+    ///         tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
+    ///         Err(FlowError::NodeFailed(format!("after {millis}ms")))
+    ///     }
+    /// }
+    ///
+    /// let batch = Batch::new(Slow).with_adaptive_concurrency(AdaptiveConcurrency::new());
+    /// let error = batch.call(json!([50, 1])).await.unwrap_err();
+    /// assert!(matches!(error, FlowError::NodeError { node_index: 0, .. }));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_adaptive_concurrency(mut self, settings: AdaptiveConcurrency) -> Self {
+        let initial = settings.clamp(settings.initial as f64);
+        self.adaptive = Some((settings, Mutex::new(initial)));
+        self
+    }
+
+    /// Process at most `max` elements at a time, at least 1.
+    ///
+    /// Adaptive concurrency, if enabled, takes precedence.
+    pub fn with_max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = Some(max.max(1));
+        self
+    }
+
+    /// Split the input array into chunks of `size` elements, at least 1, and
+    /// process one chunk at a time, the elements of each concurrently.
+    ///
+    /// Each chunk finishes before the next starts, so together with
+    /// [`Batch::with_chunk_delay`] this spreads large jobs out to stay under
+    /// provider rate limits. A fixed or adaptive concurrency limit applies
+    /// within each chunk, and an adaptive one carries over between chunks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::batch::AdaptiveConcurrency;
+    /// use rustyflow::{Batch, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// static RUNNING: AtomicUsize = AtomicUsize::new(0);
+    /// static PEAK: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// /// Stands in for an embedding API call.
+    /// struct Ingest;
+    ///
+    /// #[async_trait]
+    /// impl Node for Ingest {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+    ///         PEAK.fetch_max(running, Ordering::SeqCst);
+    ///         tokio::time::sleep(Duration::from_millis(5)).await;
+    ///         RUNNING.fetch_sub(1, Ordering::SeqCst);
+    ///         Ok(input)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let batch = Batch::new(Ingest)
+    ///     .with_chunk_size(2)
+    ///     .with_chunk_delay(Duration::from_millis(20));
+    ///
+    /// let started = Instant::now();
+    /// let result = batch.call(json!([1, 2, 3, 4, 5])).await?;
+    /// assert_eq!(result, json!([1, 2, 3, 4, 5]));
+    /// assert_eq!(PEAK.load(Ordering::SeqCst), 2);
+    /// // Three chunks, with a pause between each
+    /// assert!(started.elapsed() >= Duration::from_millis(40));
+    ///
+    /// // Chunks of four, at most two elements of each at a time
+    /// PEAK.store(0, Ordering::SeqCst);
+    /// let adaptive = Batch::new(Ingest)
+    ///     .with_chunk_size(4)
+    ///     .with_chunk_delay(Duration::from_millis(20))
+    ///     .with_adaptive_concurrency(AdaptiveConcurrency::new().with_limits(1, 2));
+    /// let started = Instant::now();
+    /// let result = adaptive.call(json!([1, 2, 3, 4, 5])).await?;
+    /// assert_eq!(result, json!([1, 2, 3, 4, 5]));
+    /// assert_eq!(PEAK.load(Ordering::SeqCst), 2);
+    /// assert!(started.elapsed() >= Duration::from_millis(20));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = Some(size.max(1));
+        self
+    }
+
+    /// Wait `delay` between chunks set with [`Batch::with_chunk_size`].
+    pub fn with_chunk_delay(mut self, delay: Duration) -> Self {
+        self.chunk_delay = delay;
+        self
+    }
+
+    /// Call `callback` with the batch's [`BatchProgress`] each time an
+    /// element finishes, successfully or not.
+    ///
+    /// The callback runs on the task executing the batch, so it should
+    /// return quickly, for example by sending the progress to a channel.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::events::ExecutionEvent;
+    /// use rustyflow::{Batch, Flow, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// struct Ingest;
+    ///
+    /// #[async_trait]
+    /// impl Node for Ingest {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(input)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let seen = Arc::new(Mutex::new(Vec::new()));
+    /// let log = Arc::clone(&seen);
+    /// let batch = Batch::new(Ingest).with_on_progress(move |progress| {
+    ///     log.lock().unwrap().push((progress.completed, progress.total));
+    /// });
+    ///
+    /// // Progress is also streamed to event listeners of the enclosing flow
+    /// let flow = Flow::new(vec![Box::new(batch)]);
+    /// let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    /// flow.execute_with_events(json!([1, 2, 3]), &sender).await?;
+    ///
+    /// assert_eq!(seen.lock().unwrap().last(), Some(&(3, Some(3))));
+    /// let mut progress_events = 0;
+    /// while let Ok(event) = receiver.try_recv() {
+    ///     if let ExecutionEvent::Progress { completed, failed, .. } = event {
+    ///         progress_events += 1;
+    ///         assert_eq!((completed, failed), (progress_events, 0));
+    ///     }
+    /// }
+    /// assert_eq!(progress_events, 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&BatchProgress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Process the elements of a stream as they arrive, yielding the results
+    /// in input order.
+    ///
+    /// At most the [maximum concurrency](Batch::with_max_concurrency), 16 by
+    /// default, of elements are in flight at once, and the stream is only
+    /// read as they finish, so memory stays bounded however long the stream
+    /// is.
+    ///
+    /// With a [chunk size](Batch::with_chunk_size), the elements that have
+    /// arrived, up to a chunk, are processed together, each chunk finishing
+    /// before the [delay](Batch::with_chunk_delay) and the next one; a
+    /// source that pauses does not hold back the elements it already sent.
+    /// Under [adaptive concurrency](Batch::with_adaptive_concurrency), chunks
+    /// of up to the chunk size, or the adaptive maximum without one, are
+    /// processed as by [`call`](Node::call): the limit adapts and overloaded
+    /// elements are retried.
+    ///
+    /// Results are kept in order, so an element that is slow to finish holds
+    /// back the results of the elements behind it, and while it runs at most
+    /// the limit of later elements start. Use
+    /// [`Batch::call_stream_unordered`] when order does not matter.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The elements, such as parsed NDJSON lines or queue messages
+    ///
+    /// # Returns
+    ///
+    /// A stream with the result for each element. Errors are wrapped in a
+    /// `FlowError::NodeError` carrying the element's index, and do not end
+    /// the stream.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use futures::{stream, StreamExt, TryStreamExt};
+    /// use rustyflow::batch::AdaptiveConcurrency;
+    /// use rustyflow::{Batch, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// struct Total;
+    ///
+    /// #[async_trait]
+    /// impl Node for Total {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         let qty = input["qty"].as_i64().unwrap_or(0);
+    ///         let price = input["price"].as_i64().unwrap_or(0);
+    ///         Ok(json!({"order": input["order"], "total": qty * price}))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let ndjson = "{\"order\": 1, \"qty\": 2, \"price\": 5}\n\
+    ///               {\"order\": 2, \"qty\": 1, \"price\": 7}\n";
+    /// let orders = stream::iter(ndjson.lines().map(|line| serde_json::from_str(line).unwrap()));
+    ///
+    /// let batch = Batch::new(Total).with_max_concurrency(4);
+    /// let totals: Vec<Value> = batch
+    ///     .call_stream(orders)
+    ///     .map_ok(|total| total["total"].clone())
+    ///     .try_collect()
+    ///     .await?;
+    /// assert_eq!(totals, [json!(10), json!(7)]);
+    ///
+    /// // Chunks of one with a pause between each, as under a rate limit
+    /// let throttled = Batch::new(Total)
+    ///     .with_chunk_size(1)
+    ///     .with_chunk_delay(Duration::from_millis(20));
+    /// let started = Instant::now();
+    /// let orders = stream::iter(vec![json!({"qty": 1, "price": 1}); 3]);
+    /// let totals: Vec<Value> = throttled.call_stream(orders).try_collect().await?;
+    /// assert_eq!(totals.len(), 3);
+    /// assert!(started.elapsed() >= Duration::from_millis(40));
+    ///
+    /// // Elements already sent are processed while the source pauses
+    /// let queue = stream::iter(vec![json!({"qty": 1, "price": 2}); 2]).chain(stream::pending());
+    /// let chunked = Batch::new(Total).with_chunk_size(10);
+    /// let first: Vec<Value> = chunked.call_stream(queue).take(2).try_collect().await?;
+    /// assert_eq!(first.len(), 2);
+    ///
+    /// // Under adaptive concurrency, rate-limited elements are retried
+    /// struct Quota(AtomicUsize);
+    ///
+    /// #[async_trait]
+    /// impl Node for Quota {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         if self.0.fetch_add(1, Ordering::SeqCst) % 3 == 2 {
+    ///             return Err(FlowError::RateLimited("429".to_string()));
+    ///         }
+    ///         Ok(input)
+    ///     }
+    /// }
+    ///
+    /// let adaptive = Batch::new(Quota(AtomicUsize::new(0))).with_adaptive_concurrency(
+    ///     AdaptiveConcurrency::new().with_backoff(Duration::from_millis(1)),
+    /// );
+    /// let results: Vec<Value> = adaptive
+    ///     .call_stream(stream::iter((1..=6).map(|n| json!(n))))
+    ///     .try_collect()
+    ///     .await?;
+    /// assert_eq!(results, [1, 2, 3, 4, 5, 6].map(|n| json!(n)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_stream<'a, S>(
+        &'a self,
+        input: S,
+    ) -> impl Stream<Item = Result<Value, FlowError>> + Send + 'a
+    where
+        S: Stream<Item = Value> + Send + 'a,
+    {
+        self.stream_calls(input, true)
+    }
+
+    /// Process the elements of a stream as they arrive, yielding the results
+    /// as they finish.
+    ///
+    /// This is [`Batch::call_stream`] without the ordering: a slow element
+    /// does not hold back the others, so the limit stays busy and memory
+    /// stays constant, as for NDJSON files or queue messages whose results
+    /// carry their own ids. Errors still carry the element's index. Under
+    /// adaptive concurrency, each chunk's results are yielded in order once
+    /// the chunk finishes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use futures::{stream, TryStreamExt};
+    /// use rustyflow::{Batch, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use std::time::Duration;
+    ///
+    /// /// Takes as many milliseconds as the message says.
+    /// struct Handle;
+    ///
+    /// #[async_trait]
+    /// impl Node for Handle {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         let millis = input["millis"].as_u64().unwrap_or(0);
+    ///         tokio::time::sleep(Duration::from_millis(millis)).await;
+    ///         Ok(input["id"].clone())
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let messages = stream::iter([
+    ///     json!({"id": "slow", "millis": 200}),
+    ///     json!({"id": "fast", "millis": 0}),
+    /// ]);
+    /// let batch = Batch::new(Handle).with_max_concurrency(2);
+    /// let ids: Vec<Value> = batch.call_stream_unordered(messages).try_collect().await?;
+    /// assert_eq!(ids, [json!("fast"), json!("slow")]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_stream_unordered<'a, S>(
+        &'a self,
+        input: S,
+    ) -> impl Stream<Item = Result<Value, FlowError>> + Send + 'a
+    where
+        S: Stream<Item = Value> + Send + 'a,
+    {
+        self.stream_calls(input, false)
+    }
+
+    /// Call the wrapped node on each element of `input`, chunk by chunk if a
+    /// chunk size is set or under adaptive concurrency, yielding the results
+    /// in order if `ordered`.
+    fn stream_calls<'a, S>(
+        &'a self,
+        input: S,
+        ordered: bool,
+    ) -> BoxStream<'a, Result<Value, FlowError>>
+    where
+        S: Stream<Item = Value> + Send + 'a,
+    {
+        let progress = Arc::new(Progress::new(None, self.on_progress.clone()));
+        let elements = input.enumerate();
+
+        // Chunks take whatever has arrived, up to their size, rather than
+        // waiting for a full one
+        if let Some((settings, shared_limit)) = &self.adaptive {
+            let size = self.chunk_size.unwrap_or(settings.max);
+            return self
+                .spaced(elements.ready_chunks(size))
+                .then(move |chunk| {
+                    let progress = Arc::clone(&progress);
+                    async move {
+                        let results = self
+                            .adaptive_results(settings, shared_limit, chunk, &progress, false)
+                            .await;
+                        stream::iter(results.into_iter().flatten())
+                    }
+                })
+                .flatten()
+                .boxed();
+        }
+
+        let limit = self.max_concurrency.unwrap_or(DEFAULT_STREAM_CONCURRENCY);
+        let calls = move |elements: BoxStream<'a, (usize, Value)>| {
+            let progress = Arc::clone(&progress);
+            let calls = elements.map(move |(index, element)| {
+                let progress = Arc::clone(&progress);
+                async move { progress.track(self.call_element(index, element).await) }
+            });
+            if ordered {
+                calls.buffered(limit).boxed()
+            } else {
+                calls.buffer_unordered(limit).boxed()
+            }
+        };
+        match self.chunk_size {
+            None => calls(elements.boxed()),
+            Some(size) => self
+                .spaced(elements.ready_chunks(size))
+                .flat_map(move |chunk| calls(stream::iter(chunk).boxed()))
+                .boxed(),
+        }
+    }
+
+    /// Wait the chunk delay before each chunk after the first, if a chunk
+    /// size is set.
+    fn spaced<'a, C: Send + 'a>(
+        &self,
+        chunks: impl Stream<Item = C> + Send + 'a,
+    ) -> impl Stream<Item = C> + Send + 'a {
+        let delay = match self.chunk_size {
+            Some(_) => self.chunk_delay,
+            None => Duration::ZERO,
+        };
+        chunks.enumerate().then(move |(n, chunk)| async move {
+            if n > 0 && !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            chunk
+        })
+    }
+
+    /// The current adaptive concurrency limit, if adaptive mode is enabled.
+    pub fn concurrency_limit(&self) -> Option<usize> {
+        self.adaptive
+            .as_ref()
+            .map(|(_, limit)| *limit.lock().unwrap() as usize)
+    }
+
+    /// Call the wrapped node on one element, on a task of its own if the
+    /// batch is [spawned](Batch::spawned).
+    async fn call_element(
+        &self,
+        index: usize,
+        element: impl Into<NodeInput>,
+    ) -> Result<Value, FlowError> {
+        match &self.spawned {
+            Some(node) => telemetry::spawn_node(Arc::clone(node), index, element).await,
+            None => telemetry::call_node(&*self.wrapped_node, index, element).await,
+        }
+    }
+
+    /// Process one chunk of elements concurrently, under the fixed limit if
+    /// one is set.
+    async fn call_chunk(
+        &self,
+        chunk: Vec<(usize, Value)>,
+        progress: &Progress,
+    ) -> Result<Vec<Value>, FlowError> {
+        let calls = chunk.into_iter().map(|(index, element)| async move {
+            progress.track(self.call_element(index, element).await)
+        });
+        // Run every element to completion, then return the first error
+        let results: Vec<_> = match self.max_concurrency {
+            Some(limit) => stream::iter(calls).buffered(limit).collect().await,
+            None => join_all(calls).await,
+        };
+        results.into_iter().collect()
+    }
+
+    /// Process one chunk of elements under an AIMD concurrency limit.
+    async fn call_adaptive(
+        &self,
+        settings: &AdaptiveConcurrency,
+        shared_limit: &Mutex<f64>,
+        chunk: Vec<(usize, Value)>,
+        progress: &Progress,
+    ) -> Result<Vec<Value>, FlowError> {
+        let results = self
+            .adaptive_results(settings, shared_limit, chunk, progress, true)
+            .await;
+        // Elements skipped after a failure have no result; the first error
+        // by position is returned
+        let mut values = Vec::with_capacity(results.len());
+        for result in results.into_iter().flatten() {
+            values.push(result?);
+        }
+        Ok(values)
+    }
+
+    /// Call the wrapped node on each element of a chunk under an AIMD
+    /// concurrency limit, retrying overloaded calls, and return the result
+    /// of each element in order. With `stop_on_error`, elements not yet
+    /// started when one fails are skipped and have no result.
+    async fn adaptive_results(
+        &self,
+        settings: &AdaptiveConcurrency,
+        shared_limit: &Mutex<f64>,
+        chunk: Vec<(usize, Value)>,
+        progress: &Progress,
+        stop_on_error: bool,
+    ) -> Vec<Option<Result<Value, FlowError>>> {
+        // Elements are kept for retries and shared with the calls that
+        // process them
+        let chunk: Vec<(usize, Arc<Value>)> = chunk
+            .into_iter()
+            .map(|(index, element)| (index, Arc::new(element)))
+            .collect();
+        let mut limit = *shared_limit.lock().unwrap();
+        let mut last_decrease = Instant::now();
+        let mut pending: VecDeque<(usize, u32)> = (0..chunk.len()).map(|i| (i, 0)).collect();
+        let mut results: Vec<Option<Result<Value, FlowError>>> =
+            (0..chunk.len()).map(|_| None).collect();
+        let mut in_flight = FuturesUnordered::new();
+        let mut failed = false;
+
+        loop {
+            // After a failure, only the calls already in flight finish
+            while !(stop_on_error && failed) && in_flight.len() < limit as usize {
+                let Some((position, attempt)) = pending.pop_front() else {
+                    break;
+                };
+                let delay = match attempt {
+                    0 => Duration::ZERO,
+                    n => settings.backoff * 2u32.saturating_pow(n - 1),
+                };
+                let (index, element) = &chunk[position];
+                let (index, element) = (*index, Arc::clone(element));
+                in_flight.push(async move {
+                    tokio::time::sleep(delay).await;
+                    let started = Instant::now();
+                    let result = self.call_element(index, element).await;
+                    (position, attempt, started, result)
+                });
+            }
+
+            let Some((position, attempt, started, result)) = in_flight.next().await else {
+                break;
+            };
+            let slow = settings
+                .latency_target
+                .is_some_and(|target| started.elapsed() > target);
+            let overloaded = match &result {
+                Ok(_) => slow,
+                Err(e) => is_overload(e),
+            };
+
+            if overloaded {
+                // Back off once per round: ignore requests that were already
+                // in flight when the limit last decreased
+                if started > last_decrease {
+                    limit = settings.clamp(limit * settings.decrease_factor);
+                    last_decrease = Instant::now();
+                    tracing::debug!("Batch concurrency decreased to {}", limit as usize);
+                }
+            } else if result.is_ok() {
+                limit = settings.clamp(limit + 1.0 / limit);
+            }
+
+            match result {
+                Ok(value) => {
+                    progress.record(true);
+                    results[position] = Some(Ok(value));
+                }
+                Err(e) if is_overload(&e) && attempt < settings.max_retries => {
+                    tracing::warn!(
+                        "Retrying batch element {} (attempt {}): {e}",
+                        chunk[position].0,
+                        attempt + 1
+                    );
+                    crate::report::record_retry();
+                    pending.push_back((position, attempt + 1));
+                }
+                Err(e) => {
+                    progress.record(false);
+                    failed = true;
+                    results[position] = Some(Err(e));
+                }
+            }
+        }
+
+        *shared_limit.lock().unwrap() = limit;
+        results
+    }
+}
+
+impl<T> Batch<T>
+where
+    T: Node + 'static,
+{
+    /// Process each element on a task of its own with `tokio::spawn`, so
+    /// CPU-heavy nodes run in parallel across the worker threads of a
+    /// multi-threaded runtime, as with [`ParallelFlow::spawned`](crate::ParallelFlow::spawned).
+    pub fn spawned(mut self) -> Self {
+        self.spawned = Some(Arc::clone(&self.wrapped_node) as Arc<dyn Node>);
+        self
     }
 }
 
@@ -87,34 +895,269 @@ where
     /// # Errors
     ///
     /// Returns `FlowError::NodeFailed` if the input is not a JSON array,
-    /// or propagates any error from the wrapped node.
+    /// or the first error from the wrapped node, wrapped in a
+    /// `FlowError::NodeError` carrying the element's index. The elements of
+    /// a chunk run to completion before the error of the first failed
+    /// element is returned, and no later chunks start. Under adaptive
+    /// concurrency, elements not yet started when one fails are skipped, but
+    /// those in flight still finish.
     async fn call(&self, input: Value) -> Result<Value, FlowError> {
-        // Ensure input is an array
-        let array = match input.as_array() {
-            Some(arr) => arr,
-            None => {
+        // Ensure input is an array, taking ownership of its elements
+        let array = match input {
+            Value::Array(arr) => arr,
+            _ => {
                 return Err(FlowError::NodeFailed(
                     "Input must be a JSON array".to_string(),
                 ))
             }
         };
 
-        // Create futures for processing each element
-        let futures: Vec<_> = array
-            .iter()
-            .map(|element| self.wrapped_node.call(element.clone()))
-            .collect();
+        crate::metrics::batch_size(array.len());
+        let span = telemetry::flow_span("Batch", None, array.len(), None);
+        let progress = Progress::new(Some(array.len()), self.on_progress.clone());
 
-        // Execute all operations concurrently
-        let results = join_all(futures).await;
+        // Move each element into its call rather than cloning it, one chunk
+        // at a time; without a chunk size the whole array is one chunk
+        let chunk_size = self.chunk_size.unwrap_or(array.len()).max(1);
+        let mut elements = array.into_iter().enumerate().peekable();
+        telemetry::in_flow_span(span, async {
+            let mut values = Vec::new();
+            while elements.peek().is_some() {
+                if !values.is_empty() && !self.chunk_delay.is_zero() {
+                    tokio::time::sleep(self.chunk_delay).await;
+                }
+                let chunk = elements.by_ref().take(chunk_size).collect();
+                let chunk_values = match &self.adaptive {
+                    Some((settings, limit)) => {
+                        self.call_adaptive(settings, limit, chunk, &progress)
+                            .await?
+                    }
+                    None => self.call_chunk(chunk, &progress).await?,
+                };
+                values.extend(chunk_values);
+            }
 
-        // Collect successful results or return first error
-        let mut values = Vec::new();
-        for result in results {
-            values.push(result?);
+            // Return as JSON array
+            Ok(Value::Array(values))
+        })
+        .await
+    }
+
+    async fn snapshot(&self) -> Result<Option<Value>, FlowError> {
+        self.wrapped_node.snapshot().await
+    }
+
+    async fn restore(&self, state: Value) -> Result<(), FlowError> {
+        self.wrapped_node.restore(state).await
+    }
+}
+
+/// Applies a whole [`Flow`] to each element of a JSON array concurrently.
+///
+/// Where [`Batch`] runs a single node per element, `BatchFlow` runs every
+/// node of a flow in order for each element, such as fetch, clean and
+/// embed for each document of an ingest job. The flow's executor and
+/// scheduler apply to all of its runs, so a scheduler shared with other
+/// flows also bounds them; [`BatchFlow::with_max_concurrency`] bounds the
+/// elements in flight.
+///
+/// Errors are reported per element: each is wrapped in a
+/// `FlowError::NodeError` carrying the element's index, and with
+/// [`BatchFlow::collect_errors`] every failed element is reported, not only
+/// the first.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::{BatchFlow, Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// struct Clean;
+///
+/// #[async_trait]
+/// impl Node for Clean {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let text = input.as_str().ok_or_else(|| FlowError::NodeFailed("not text".into()))?;
+///         Ok(json!(text.trim()))
+///     }
+/// }
+///
+/// struct Count;
+///
+/// #[async_trait]
+/// impl Node for Count {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(input.as_str().unwrap_or("").split_whitespace().count()))
+///     }
+/// }
+///
+/// /// Stores its input after a short write, like a database insert.
+/// struct Save(Arc<AtomicUsize>);
+///
+/// #[async_trait]
+/// impl Node for Save {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+///         self.0.fetch_add(1, Ordering::SeqCst);
+///         Ok(input)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let flow = Flow::new(vec![Box::new(Clean), Box::new(Count)]);
+/// let batch = BatchFlow::new(flow).with_max_concurrency(8).collect_errors();
+///
+/// assert_eq!(batch.execute(json!([" a b ", "c"])).await?, json!([2, 1]));
+///
+/// let Err(FlowError::Multiple(errors)) = batch.execute(json!(["a", 1, "b", 2])).await else {
+///     panic!("expected every failure");
+/// };
+/// let failed: Vec<&str> = errors.iter().map(|(element, _)| element.as_str()).collect();
+/// assert_eq!(failed, ["element 1", "element 3"]);
+///
+/// // Without collect_errors, the other runs still finish and the first
+/// // failed element's error is returned
+/// let saved = Arc::new(AtomicUsize::new(0));
+/// let save = Flow::new(vec![Box::new(Clean), Box::new(Save(Arc::clone(&saved)))]);
+/// let error = BatchFlow::new(save).execute(json!([1, "a b", "c"])).await.unwrap_err();
+/// assert!(matches!(error, FlowError::NodeError { node_index: 0, .. }));
+/// assert_eq!(saved.load(Ordering::SeqCst), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct BatchFlow {
+    flow: Flow,
+    max_concurrency: Option<usize>,
+    collect_errors: bool,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl BatchFlow {
+    /// Create a batch that runs `flow` on every element at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `flow` - The flow to run for each array element
+    pub fn new(flow: Flow) -> Self {
+        Self {
+            flow,
+            max_concurrency: None,
+            collect_errors: false,
+            on_progress: None,
         }
+    }
+
+    /// Run the flow on at most `max` elements at a time, at least 1.
+    pub fn with_max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = Some(max.max(1));
+        self
+    }
+
+    /// Report every failed element instead of only the first.
+    ///
+    /// Every element then runs to completion, and the batch returns a
+    /// [`FlowError::Multiple`] naming each failed element as `element <index>`.
+    pub fn collect_errors(mut self) -> Self {
+        self.collect_errors = true;
+        self
+    }
+
+    /// Call `callback` with the batch's [`BatchProgress`] each time a run
+    /// finishes, as with [`Batch::with_on_progress`].
+    pub fn with_on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&BatchProgress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Run the flow on each element of the input array.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A JSON array; each element is the input of one flow run
+    ///
+    /// # Returns
+    ///
+    /// The outputs of the runs, in element order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the input is not a JSON array, the
+    /// first failed run's error wrapped in a `FlowError::NodeError` carrying
+    /// its element index, or a `FlowError::Multiple` with every failed run if
+    /// [`BatchFlow::collect_errors`] is set. Every run finishes before the
+    /// error is returned. The wrapping error's input snippet is that of the
+    /// node that failed within the run, which is the element itself when
+    /// the flow's first node failed.
+    pub async fn execute(&self, input: Value) -> Result<Value, FlowError> {
+        let Value::Array(elements) = input else {
+            return Err(FlowError::NodeFailed(
+                "BatchFlow input must be a JSON array".to_string(),
+            ));
+        };
+
+        crate::metrics::batch_size(elements.len());
+        let name = self.flow.name().unwrap_or("Flow").to_string();
+        let span = telemetry::flow_span("BatchFlow", self.flow.name(), elements.len(), None);
+        let limit = self.max_concurrency.unwrap_or(elements.len()).max(1);
+        let progress = Progress::new(Some(elements.len()), self.on_progress.clone());
+        let runs = futures::stream::iter(elements.into_iter().enumerate())
+            .map(|(index, element)| {
+                let name = &name;
+                let progress = &progress;
+                async move {
+                    let result = self.flow.execute(element).await;
+                    progress.track(result).map_err(|e| {
+                        // The run's error already holds the input of the node
+                        // that failed, so the element is not serialized up
+                        // front
+                        let snippet = match &e {
+                            FlowError::NodeError { input_snippet, .. } => input_snippet.clone(),
+                            _ => String::new(),
+                        };
+                        e.in_node(name, index, snippet)
+                    })
+                }
+            })
+            .buffered(limit);
+
+        telemetry::in_flow_span(span, async {
+            // Run every element to completion, as Batch does
+            let results: Vec<_> = runs.collect().await;
+            if !self.collect_errors {
+                return results
+                    .into_iter()
+                    .collect::<Result<_, _>>()
+                    .map(Value::Array);
+            }
+            let mut values = Vec::new();
+            let mut errors = Vec::new();
+            for (index, result) in results.into_iter().enumerate() {
+                match result {
+                    Ok(value) => values.push(value),
+                    Err(e) => errors.push((format!("element {index}"), e)),
+                }
+            }
+            match errors.is_empty() {
+                true => Ok(Value::Array(values)),
+                false => Err(FlowError::Multiple(errors)),
+            }
+        })
+        .await
+    }
+}
 
-        // Return as JSON array
-        Ok(Value::Array(values))
+#[async_trait]
+impl Node for BatchFlow {
+    /// Run the flow on each element of the input array, as
+    /// [`BatchFlow::execute`].
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        self.execute(input).await
     }
 }
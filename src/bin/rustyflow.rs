@@ -0,0 +1,310 @@
+use rustyflow::{
+    config::FlowConfig,
+    error::FlowError,
+    flow::Flow,
+    pack::FlowPack,
+    pack_store::{self, DirPackStore, PackRef, PackStore},
+    registry::NodeRegistry,
+    scaffold::{Pattern, Scaffold},
+};
+use serde_json::{json, Value};
+use std::io::Read;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage:
+    rustyflow new <name> [--pattern rag|react|batch-etl] [--path <rustyflow checkout>]
+    rustyflow run <flow.json> [--input <json> | --input-file <path>] [--trace] [--compact]
+                  [--from <node>] [--to <node>]
+    rustyflow graph <flow.json> [--dot | --mermaid]
+    rustyflow check <flow.json>
+    rustyflow pack <dir> <out.flowpack>
+    rustyflow push <pack> <store>
+    rustyflow pull <name[:version][@sha256:digest]> <store> <out.flowpack>
+    rustyflow types
+
+Commands:
+    new      Create a project for a flow pattern, with typed tools and tests
+    run      Execute a flow definition and print its result as JSON
+    graph    Print the flow's topology as a Mermaid (default) or DOT diagram
+    check    Run the health checks of the flow's nodes, such as reaching their APIs
+    pack     Bundle a directory with flowpack.json and flow.json into a .flowpack archive
+    push     Upload a pack to a package store under its manifest name and version
+    pull     Download a pack from a package store, verifying its digest
+    types    List the node types available to flow definitions
+
+`run`, `graph` and `check` also accept a flow.yaml (with the `yaml` feature), a .flowpack
+archive or an unpacked pack directory in place of <flow.json>. Every problem in a
+flow definition is reported with its line and column.
+A <store> is a directory, or an http(s) URL when built with the `reqwest` feature;
+RUSTYFLOW_STORE_TOKEN is sent to HTTP stores as a bearer token.
+Input for `run` is read from --input, --input-file, or stdin, in that order.
+With --trace, the result is printed together with the per-node execution report.
+With --from and --to, only the nodes between them run, and the input is given to
+the --from node. Nodes are given by type name or by index.";
+
+/// Parsed command-line options.
+#[derive(Default)]
+struct Options {
+    input: Option<String>,
+    input_file: Option<String>,
+    trace: bool,
+    compact: bool,
+    dot: bool,
+    from: Option<String>,
+    to: Option<String>,
+    pattern: Option<String>,
+    path: Option<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => options.input = Some(args.next().ok_or("--input needs a value")?.clone()),
+            "--input-file" => {
+                options.input_file = Some(args.next().ok_or("--input-file needs a path")?.clone())
+            }
+            "--from" => options.from = Some(args.next().ok_or("--from needs a node")?.clone()),
+            "--to" => options.to = Some(args.next().ok_or("--to needs a node")?.clone()),
+            "--pattern" => {
+                options.pattern = Some(args.next().ok_or("--pattern needs a name")?.clone())
+            }
+            "--path" => options.path = Some(args.next().ok_or("--path needs a directory")?.clone()),
+            "--trace" => options.trace = true,
+            "--compact" => options.compact = true,
+            "--dot" => options.dot = true,
+            "--mermaid" => options.dot = false,
+            other => return Err(format!("Unknown option '{other}'")),
+        }
+    }
+    Ok(options)
+}
+
+fn read_input(options: &Options) -> Result<Value, String> {
+    let text = match (&options.input, &options.input_file) {
+        (Some(input), _) => input.clone(),
+        (None, Some(path)) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read input file {path}: {e}"))?,
+        (None, None) => {
+            let mut buffer = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buffer)
+                .map_err(|e| format!("Cannot read stdin: {e}"))?;
+            buffer
+        }
+    };
+    if text.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&text).map_err(|e| format!("Input is not valid JSON: {e}"))
+}
+
+fn print_json(value: &Value, compact: bool) {
+    let text = if compact {
+        value.to_string()
+    } else {
+        serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+    };
+    println!("{text}");
+}
+
+/// Build the flow defined by a JSON or YAML file, a `.flowpack` archive or
+/// a pack directory.
+fn load_flow(path: &str) -> Result<Flow, String> {
+    let registry = NodeRegistry::with_builtins();
+    let is_pack = Path::new(path).is_dir() || path.ends_with(".flowpack");
+    let flow = if is_pack {
+        FlowPack::open(path).and_then(|pack| pack.build(&registry))
+    } else {
+        FlowConfig::load_file(path, &registry)
+    };
+    flow.map_err(|e| match e {
+        FlowError::InvalidConfig(problems) => {
+            let problems: Vec<String> = problems
+                .iter()
+                .map(|problem| format!("  {problem}"))
+                .collect();
+            format!("Invalid flow definition:\n{}", problems.join("\n"))
+        }
+        e => e.to_string(),
+    })
+}
+
+async fn run(path: &str, options: &Options) -> Result<(), String> {
+    let flow = load_flow(path)?;
+    let input = read_input(options)?;
+
+    if options.from.is_some() || options.to.is_some() {
+        if options.trace {
+            return Err("--trace cannot be combined with --from or --to".to_string());
+        }
+        let entry = options.from.as_deref().unwrap_or("0");
+        let result = flow
+            .execute_from(entry, options.to.as_deref(), input)
+            .await
+            .map_err(|e| e.to_string())?;
+        print_json(&result, options.compact);
+        Ok(())
+    } else if options.trace {
+        let (result, report) = flow.execute_traced(input).await;
+        let report = serde_json::to_value(&report).map_err(|e| e.to_string())?;
+        let output = match &result {
+            Ok(value) => json!({ "result": value, "report": report }),
+            Err(e) => json!({ "error": e.to_string(), "report": report }),
+        };
+        print_json(&output, options.compact);
+        result.map(|_| ()).map_err(|e| e.to_string())
+    } else {
+        let result = flow
+            .execute(input)
+            .await
+            .map_err(|e: FlowError| e.to_string())?;
+        print_json(&result, options.compact);
+        Ok(())
+    }
+}
+
+fn graph(path: &str, options: &Options) -> Result<(), String> {
+    let flow = load_flow(path)?;
+    if options.dot {
+        print!("{}", flow.to_dot());
+    } else {
+        print!("{}", flow.to_mermaid());
+    }
+    Ok(())
+}
+
+async fn check(path: &str) -> Result<(), String> {
+    let flow = load_flow(path)?;
+    let health = flow.check_health().await;
+    let mut failed = 0;
+    for node in &health {
+        match &node.error {
+            Some(error) => {
+                failed += 1;
+                println!("{} {}: {error}", node.index, node.name);
+            }
+            None => println!("{} {}: ok", node.index, node.name),
+        }
+    }
+    if health.is_empty() {
+        println!("No node has a health check");
+    }
+    match failed {
+        0 => Ok(()),
+        failed => Err(format!("{failed} of {} checks failed", health.len())),
+    }
+}
+
+fn new_project(name: &str, options: &Options) -> Result<(), String> {
+    let pattern: Pattern = options
+        .pattern
+        .as_deref()
+        .unwrap_or("rag")
+        .parse()
+        .map_err(|e: FlowError| e.to_string())?;
+    let mut scaffold = Scaffold::new(name, pattern).map_err(|e| e.to_string())?;
+    if let Some(path) = &options.path {
+        let path = std::fs::canonicalize(path).map_err(|e| format!("Cannot find {path}: {e}"))?;
+        scaffold = scaffold.with_rustyflow_path(path);
+    }
+    scaffold.write(name).map_err(|e| e.to_string())?;
+    println!("Created {pattern} project in {name}/ (cd {name} && cargo test)");
+    Ok(())
+}
+
+fn pack(dir: &str, out: &str) -> Result<(), String> {
+    let pack = FlowPack::from_dir(dir).map_err(|e| e.to_string())?;
+    pack.build(&NodeRegistry::with_builtins())
+        .map_err(|e| e.to_string())?;
+    pack.save(out).map_err(|e| e.to_string())?;
+    println!(
+        "Packed {} {} into {out}",
+        pack.manifest.name, pack.manifest.version
+    );
+    Ok(())
+}
+
+fn open_store(location: &str) -> Result<Box<dyn PackStore>, String> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        #[cfg(feature = "reqwest")]
+        {
+            let store = pack_store::HttpPackStore::new(location);
+            return Ok(match std::env::var("RUSTYFLOW_STORE_TOKEN") {
+                Ok(token) => Box::new(store.with_token(token)),
+                Err(_) => Box::new(store),
+            });
+        }
+        #[cfg(not(feature = "reqwest"))]
+        return Err("HTTP package stores need the `reqwest` feature".to_string());
+    }
+    Ok(Box::new(DirPackStore::new(location)))
+}
+
+async fn push(path: &str, location: &str) -> Result<(), String> {
+    let pack = FlowPack::open(path).map_err(|e| e.to_string())?;
+    let store = open_store(location)?;
+    let reference = pack_store::push(store.as_ref(), &pack)
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("Pushed {reference}");
+    Ok(())
+}
+
+async fn pull(reference: &str, location: &str, out: &str) -> Result<(), String> {
+    let reference: PackRef = reference.parse().map_err(|e: FlowError| e.to_string())?;
+    let store = open_store(location)?;
+    let pack = pack_store::pull(store.as_ref(), &reference)
+        .await
+        .map_err(|e| e.to_string())?;
+    pack.save(out).map_err(|e| e.to_string())?;
+    println!(
+        "Pulled {}:{} into {out}",
+        pack.manifest.name, pack.manifest.version
+    );
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("run") | Some("graph") | Some("check") | Some("new") if args.len() < 2 => {
+            Err(USAGE.to_string())
+        }
+        Some("run") => match parse_options(&args[2..]) {
+            Ok(options) => run(&args[1], &options).await,
+            Err(e) => Err(e),
+        },
+        Some("graph") => parse_options(&args[2..]).and_then(|options| graph(&args[1], &options)),
+        Some("check") if args.len() == 2 => check(&args[1]).await,
+        Some("new") => {
+            parse_options(&args[2..]).and_then(|options| new_project(&args[1], &options))
+        }
+        Some("pack") if args.len() == 3 => pack(&args[1], &args[2]),
+        Some("push") if args.len() == 3 => push(&args[1], &args[2]).await,
+        Some("pull") if args.len() == 4 => pull(&args[1], &args[2], &args[3]).await,
+        Some("types") => {
+            for name in NodeRegistry::with_builtins().type_names() {
+                println!("{name}");
+            }
+            Ok(())
+        }
+        Some("-h") | Some("--help") | Some("help") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -3,23 +3,33 @@ use axum::{
     extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
-    routing::post,
-    Router,
+    routing::{get, post},
 };
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
 use rustyflow::{
+    auth::{ApiKeys, RateLimit, RequireAuth},
     error::FlowError,
     flow::Flow,
+    jobs::JobQueue,
+    metrics,
     node::Node,
+    pack::FlowPack,
+    registry::NodeRegistry,
+    security::{ContentTypes, Cors, SecurityHeaders},
+    server::{self, FlowRegistry, ServerOptions},
+    session::{InMemorySessionStore, SessionStore, Sessions},
     tool::{Tool, ToolNode},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::Value;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 // --- Tool Definition (could be in its own module) ---
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 struct AddRequest {
     a: i32,
     b: i32,
@@ -41,10 +51,15 @@ impl Tool for AddTool {
         let result = input.a + input.b;
         Ok(AddResponse { result })
     }
+
+    fn input_schema(&self) -> Option<Value> {
+        Some(schemars::schema_for!(AddRequest).to_value())
+    }
 }
 
 // --- Axum Handler ---
 
+/// Handles the original single-flow `/execute` route.
 async fn execute_flow(
     State(flow): State<Arc<Flow>>,
     Json(payload): Json<Value>,
@@ -53,12 +68,11 @@ async fn execute_flow(
     match flow.execute(payload).await {
         Ok(result) => {
             tracing::info!("Flow executed successfully with result: {:?}", result);
-            (StatusCode::OK, Json(result))
+            (StatusCode::OK, Json(result)).into_response()
         }
         Err(e) => {
             tracing::error!("Flow execution failed: {}", e);
-            let error_response = json!({ "error": e.to_string() });
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+            e.into_response()
         }
     }
 }
@@ -67,24 +81,156 @@ async fn execute_flow(
 
 #[tokio::main]
 async fn main() {
-    // Initialize logging
-    tracing_subscriber::registry()
+    // Initialize logging, exporting spans to OpenTelemetry when a collector
+    // endpoint is configured
+    #[cfg(feature = "otel")]
+    let (otel, _otel_guard) = match std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(_) => {
+            let (layer, guard) = rustyflow::otel::layer("rustyflow-server", None).unwrap();
+            (Some(layer), Some(guard))
+        }
+        Err(_) => (None, None),
+    };
+
+    let registry = tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "rustyflow=debug,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+        .with(tracing_subscriber::fmt::layer());
+    #[cfg(feature = "otel")]
+    let registry = registry.with(otel);
+    registry.init();
+
+    // Record execution metrics for the /metrics endpoint
+    let prometheus = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(metrics::NODE_DURATION.to_string()),
+            &[
+                0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+            ],
+        )
+        .and_then(|builder| {
+            builder.set_buckets_for_metric(
+                Matcher::Full(metrics::BATCH_SIZE.to_string()),
+                &[1.0, 10.0, 100.0, 1000.0, 10000.0],
+            )
+        })
+        .unwrap()
+        .install_recorder()
+        .unwrap();
+    metrics::describe();
 
     // Create a reusable flow instance
     let add_tool = AddTool;
     let tool_node: Box<dyn Node> = Box::new(ToolNode::new(add_tool));
-    let flow = Arc::new(Flow::new(vec![tool_node]));
+    let flow = Arc::new(Flow::new(vec![tool_node]).with_name("add"));
+
+    // Host every registered flow under /flows/{name}
+    let registry = FlowRegistry::new()
+        .with_shared_flow("add", Arc::clone(&flow))
+        .with_description("add", "Adds the integers `a` and `b`");
+
+    // Host flowpacks listed in RUSTYFLOW_FLOWPACKS, separated like PATH
+    let nodes = NodeRegistry::with_builtins();
+    let packs = std::env::var_os("RUSTYFLOW_FLOWPACKS").unwrap_or_default();
+    let registry = std::env::split_paths(&packs)
+        .filter(|path| !path.as_os_str().is_empty())
+        .fold(registry, |registry, path| {
+            let pack = FlowPack::open(&path).unwrap_or_else(|e| panic!("{e}"));
+            tracing::info!(
+                "Loaded flowpack {} {} from {}",
+                pack.manifest.name,
+                pack.manifest.version,
+                path.display()
+            );
+            registry
+                .with_pack(&pack, &nodes)
+                .unwrap_or_else(|e| panic!("{e}"))
+        });
+
+    // Send event stream keep-alives every RUSTYFLOW_KEEP_ALIVE_SECS, and
+    // turn execute requests running past RUSTYFLOW_ASYNC_AFTER_SECS into jobs
+    let seconds = |name: &str| {
+        std::env::var(name).ok().map(|secs| {
+            let secs: f64 = secs
+                .parse()
+                .unwrap_or_else(|_| panic!("{name} must be a number"));
+            Duration::from_secs_f64(secs)
+        })
+    };
+    let mut options = ServerOptions::new();
+    if let Some(interval) = seconds("RUSTYFLOW_KEEP_ALIVE_SECS") {
+        options = options.with_keep_alive(interval);
+    }
+    if let Some(limit) = seconds("RUSTYFLOW_ASYNC_AFTER_SECS") {
+        options = options.with_async_after(limit);
+    }
+    // Keep finished jobs for RUSTYFLOW_JOB_RETENTION_SECS, an hour by default
+    if let Some(retention) = seconds("RUSTYFLOW_JOB_RETENTION_SECS") {
+        options = options.with_jobs(JobQueue::new().with_retention(retention));
+    }
 
     // Build our application with a route
-    let app = Router::new()
-        .route("/execute", post(execute_flow))
-        .with_state(flow);
+    let app = server::router_with_options(Arc::new(registry), options)
+        .route("/execute", post(execute_flow).with_state(flow));
+
+    // Carry chat sessions across requests, in Redis when
+    // RUSTYFLOW_SESSION_REDIS is set so replicas share them, expiring them
+    // after RUSTYFLOW_SESSION_TTL_SECS, a day by default. Applied before
+    // authentication, so that sessions are kept per API key
+    let session_ttl =
+        seconds("RUSTYFLOW_SESSION_TTL_SECS").unwrap_or(Duration::from_secs(24 * 60 * 60));
+    let sessions: Arc<dyn SessionStore> = match std::env::var("RUSTYFLOW_SESSION_REDIS") {
+        #[cfg(feature = "redis")]
+        Ok(url) => Arc::new(
+            rustyflow::checkpoint::RedisStore::connect(&url)
+                .await
+                .unwrap_or_else(|e| panic!("{e}"))
+                .with_session_ttl(session_ttl),
+        ),
+        #[cfg(not(feature = "redis"))]
+        Ok(_) => panic!("RUSTYFLOW_SESSION_REDIS needs the 'redis' feature"),
+        Err(_) => Arc::new(InMemorySessionStore::new().with_idle_ttl(session_ttl)),
+    };
+    let app = Sessions::new(sessions).apply(app);
+
+    // Require an API key from RUSTYFLOW_API_KEYS (comma-separated id:key
+    // entries), rate-limited to RUSTYFLOW_RATE_LIMIT requests per minute
+    let keys = ApiKeys::parse(&std::env::var("RUSTYFLOW_API_KEYS").unwrap_or_default())
+        .unwrap_or_else(|e| panic!("{e}"));
+    let app = if keys.is_empty() {
+        tracing::warn!("RUSTYFLOW_API_KEYS is not set; flows are open to anyone who can connect");
+        app
+    } else {
+        let mut auth = RequireAuth::new(keys);
+        if let Ok(limit) = std::env::var("RUSTYFLOW_RATE_LIMIT") {
+            let limit = limit
+                .parse()
+                .expect("RUSTYFLOW_RATE_LIMIT must be a number");
+            auth = auth.with_rate_limit(RateLimit::per_minute(limit));
+        }
+        auth.apply(app)
+    };
+
+    // Metrics stay reachable by scrapers without a key
+    let app = app.route("/metrics", get(move || async move { prometheus.render() }));
+
+    // Accept JSON bodies only, harden responses, and let the origins in
+    // RUSTYFLOW_CORS_ORIGINS (comma-separated, or "*") call flows from a
+    // browser
+    let app = SecurityHeaders::new().apply(ContentTypes::json().apply(app));
+    let app = match std::env::var("RUSTYFLOW_CORS_ORIGINS") {
+        Ok(origins) if origins.trim() == "*" => Cors::new().with_any_origin().apply(app),
+        Ok(origins) => origins
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .try_fold(Cors::new(), Cors::with_origin)
+            .and_then(|cors| cors.apply(app)),
+        Err(_) => Ok(app),
+    }
+    .unwrap_or_else(|e| panic!("{e}"));
 
     // Run it
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
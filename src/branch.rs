@@ -0,0 +1,108 @@
+//! If/else dispatch inside a flow.
+//!
+//! This module provides [`Branch`], which checks its input against a list of
+//! [`Condition`]s and passes it to the node of the first arm that matches.
+//! Conditions are the ones [`GraphFlow`](crate::graph::GraphFlow) edges use:
+//! a JSON pointer equality or existence check, an `action` field, or a Rust
+//! closure.
+
+use crate::error::FlowError;
+use crate::graph::Condition;
+use crate::node::Node;
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// A node that calls the node of the first arm whose condition matches its
+/// input.
+///
+/// If no arm matches, the default node is called, and without a default the
+/// input is returned unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::branch::Branch;
+/// use rustyflow::graph::Condition;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// struct Reply(&'static str);
+///
+/// #[async_trait]
+/// impl Node for Reply {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(self.0))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let branch = Branch::new()
+///     .with_arm(
+///         Condition::Equals("/ticket/priority".into(), json!("urgent")),
+///         Box::new(Reply("page on-call")),
+///     )
+///     .with_arm(Condition::Exists("/ticket/refund".into()), Box::new(Reply("billing")))
+///     .with_arm(
+///         Condition::when("long", |input| input["text"].as_str().unwrap_or("").len() > 500),
+///         Box::new(Reply("summarize first")),
+///     )
+///     .with_default(Box::new(Reply("triage queue")));
+///
+/// let urgent = json!({"ticket": {"priority": "urgent", "refund": 20}});
+/// assert_eq!(branch.call(urgent).await?, json!("page on-call"));
+/// let refund = json!({"ticket": {"priority": "low", "refund": 20}});
+/// assert_eq!(branch.call(refund).await?, json!("billing"));
+/// assert_eq!(branch.call(json!({"ticket": {}})).await?, json!("triage queue"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Branch {
+    arms: Vec<(Condition, Box<dyn Node>)>,
+    default: Option<Box<dyn Node>>,
+}
+
+impl Branch {
+    /// Create a branch without arms, which returns its input unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `node` when `condition` matches, unless an earlier arm matched.
+    pub fn with_arm(mut self, condition: Condition, node: Box<dyn Node>) -> Self {
+        self.arms.push((condition, node));
+        self
+    }
+
+    /// Call `node` when no arm matches.
+    pub fn with_default(mut self, node: Box<dyn Node>) -> Self {
+        self.default = Some(node);
+        self
+    }
+}
+
+#[async_trait]
+impl Node for Branch {
+    /// Call the node of the first matching arm, or the default.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error of the chosen node.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let chosen = self
+            .arms
+            .iter()
+            .find(|(condition, _)| condition.matches(&input))
+            .map(|(condition, node)| {
+                tracing::debug!("Branch took arm '{condition}'");
+                node
+            })
+            .or(self.default.as_ref());
+        match chosen {
+            Some(node) => node.call(input).await,
+            None => Ok(input),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Shared cost budgets for bounding spend across nodes.
+//!
+//! This module provides [`Budget`], a cheaply cloneable spending limit that
+//! nodes reserve cost from before doing expensive work such as LLM calls.
+
+use crate::error::FlowError;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Budgets are tracked in millionths of a cost unit so they can be updated
+/// atomically without locks.
+const SCALE: f64 = 1_000_000.0;
+
+/// A shared, thread-safe spending limit.
+///
+/// Cost units are up to the caller (dollars, tokens, API calls). Clones share
+/// the same underlying allowance, so one budget can bound several nodes or
+/// several concurrent executions.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::budget::Budget;
+///
+/// let budget = Budget::new(1.0);
+/// assert!(budget.try_spend(0.75).is_ok());
+/// assert!(budget.try_spend(0.5).is_err());
+/// assert_eq!(budget.remaining(), 0.25);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Budget {
+    limit: u64,
+    spent: Arc<AtomicU64>,
+}
+
+impl Budget {
+    /// Create a budget with the given limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The total cost that may be spent
+    pub fn new(limit: f64) -> Self {
+        Self {
+            limit: to_units(limit),
+            spent: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Create a budget that never runs out.
+    pub fn unlimited() -> Self {
+        Self {
+            limit: u64::MAX,
+            spent: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Atomically reserve `cost` from the budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::BudgetExceeded` if the remaining allowance is
+    /// smaller than `cost`; nothing is spent in that case.
+    pub fn try_spend(&self, cost: f64) -> Result<(), FlowError> {
+        let units = to_units(cost);
+        self.spent
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |spent| {
+                spent
+                    .checked_add(units)
+                    .filter(|total| *total <= self.limit)
+            })
+            .map(|_| ())
+            .map_err(|spent| {
+                FlowError::BudgetExceeded(format!(
+                    "cannot spend {cost}, {} of {} remaining",
+                    from_units(self.limit.saturating_sub(spent)),
+                    from_units(self.limit)
+                ))
+            })
+    }
+
+    /// Return previously reserved cost to the budget.
+    pub fn refund(&self, cost: f64) {
+        let units = to_units(cost);
+        let _ = self
+            .spent
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |spent| {
+                Some(spent.saturating_sub(units))
+            });
+    }
+
+    /// The total cost spent so far.
+    pub fn spent(&self) -> f64 {
+        from_units(self.spent.load(Ordering::SeqCst))
+    }
+
+    /// The cost that can still be spent.
+    pub fn remaining(&self) -> f64 {
+        from_units(self.limit.saturating_sub(self.spent.load(Ordering::SeqCst)))
+    }
+
+    /// The configured limit.
+    pub fn limit(&self) -> f64 {
+        from_units(self.limit)
+    }
+}
+
+fn to_units(cost: f64) -> u64 {
+    (cost.max(0.0) * SCALE).round() as u64
+}
+
+fn from_units(units: u64) -> f64 {
+    if units == u64::MAX {
+        f64::INFINITY
+    } else {
+        units as f64 / SCALE
+    }
+}
@@ -0,0 +1,368 @@
+//! Caching node results by input.
+//!
+//! [`Cached`] wraps a node and remembers its output for each distinct input,
+//! so repeated identical LLM or embedding calls are paid for once. Inputs are
+//! canonicalized (object keys sorted) and hashed with SHA-256 to form the
+//! cache key, so `{"a": 1, "b": 2}` and `{"b": 2, "a": 1}` share an entry.
+//! Only successful results are cached.
+//!
+//! Entries live in a [`CacheBackend`]. [`InMemoryCache`] keeps the most
+//! recently used entries in process memory. With the `redis` feature,
+//! `RedisStore` keeps them in Redis, shared by every server replica;
+//! implement [`CacheBackend`] for other stores. One backend can serve many
+//! nodes, since keys are prefixed with a per-node namespace. Unless one is
+//! set with [`Cached::with_namespace`], the namespace is unique to the node
+//! instance, so entries are only shared, across nodes or server replicas,
+//! where a namespace says so.
+//!
+//! Idempotent flows that are often re-run with the same input, such as
+//! document classification webhooks, can cache their final output as a
+//! whole with [`Flow::with_cache`](crate::Flow::with_cache), skipping every
+//! node on a hit, or per step with
+//! [`Flow::execute_incremental`](crate::Flow::execute_incremental),
+//! re-running only the steps whose input changed.
+
+use crate::error::FlowError;
+use crate::metrics;
+use crate::node::{short_type_name, HealthCheck, Node, SchemaProvider};
+use crate::resources::Resource;
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "redis")]
+pub use crate::checkpoint::RedisStore;
+
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Storage for cached node results.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Look up the value stored under `key`, if it has not expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend cannot be read.
+    async fn get(&self, key: &str) -> Result<Option<Value>, FlowError>;
+
+    /// Store `value` under `key`, expiring after `ttl` if one is given.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend cannot be written.
+    async fn set(&self, key: &str, value: Value, ttl: Option<Duration>) -> Result<(), FlowError>;
+}
+
+struct Entry {
+    value: Value,
+    expires_at: Option<Instant>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, Entry>,
+    /// Keys by the tick they were last used at, oldest first.
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &str) -> u64 {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.order.insert(self.tick, key.to_string());
+        }
+        self.tick
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.last_used);
+        }
+    }
+}
+
+/// A [`CacheBackend`] held in process memory that evicts the least
+/// recently used entry once it is full.
+pub struct InMemoryCache {
+    capacity: usize,
+    lru: Mutex<Lru>,
+}
+
+impl Default for InMemoryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl InMemoryCache {
+    /// Create a cache that holds up to `capacity` entries, at least 1.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            lru: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// The number of entries held, including expired ones not yet evicted.
+    pub fn len(&self) -> usize {
+        self.lru.lock().unwrap().entries.len()
+    }
+
+    /// Whether the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl CacheBackend for InMemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<Value>, FlowError> {
+        let mut lru = self.lru.lock().unwrap();
+        let expired = match lru.entries.get(key) {
+            None => return Ok(None),
+            Some(entry) => matches!(entry.expires_at, Some(at) if at <= Instant::now()),
+        };
+        if expired {
+            lru.remove(key);
+            return Ok(None);
+        }
+        lru.touch(key);
+        Ok(lru.entries.get(key).map(|entry| entry.value.clone()))
+    }
+
+    async fn set(&self, key: &str, value: Value, ttl: Option<Duration>) -> Result<(), FlowError> {
+        let mut lru = self.lru.lock().unwrap();
+        lru.remove(key);
+        while lru.entries.len() >= self.capacity {
+            let Some((_, oldest)) = lru.order.pop_first() else {
+                break;
+            };
+            lru.entries.remove(&oldest);
+        }
+        let tick = lru.touch(key);
+        lru.entries.insert(
+            key.to_string(),
+            Entry {
+                value,
+                expires_at: ttl.map(|ttl| Instant::now() + ttl),
+                last_used: tick,
+            },
+        );
+        lru.order.insert(tick, key.to_string());
+        Ok(())
+    }
+}
+
+/// A node whose results are cached by input.
+///
+/// A failing backend never fails the call: the error is logged and the node
+/// is called as if the entry were missing.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::cache::{Cached, InMemoryCache};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// static CALLS: AtomicUsize = AtomicUsize::new(0);
+///
+/// struct Embed;
+///
+/// #[async_trait]
+/// impl Node for Embed {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         CALLS.fetch_add(1, Ordering::SeqCst);
+///         Ok(json!({"text": input["text"], "vector": [0.1, 0.2]}))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let node = Cached::new(Embed)
+///     .with_backend(Arc::new(InMemoryCache::new(10_000)))
+///     .with_ttl(Duration::from_secs(3600));
+///
+/// node.call(json!({"text": "hello", "model": "small"})).await?;
+/// // Same input with keys in another order: served from the cache
+/// node.call(json!({"model": "small", "text": "hello"})).await?;
+/// assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Cached<N: Node> {
+    node: N,
+    backend: Arc<dyn CacheBackend>,
+    ttl: Option<Duration>,
+    namespace: String,
+}
+
+impl<N: Node> Cached<N> {
+    /// Cache the results of `node` in a private [`InMemoryCache`] of 1024
+    /// entries that never expire.
+    pub fn new(node: N) -> Self {
+        Self {
+            node,
+            backend: Arc::new(InMemoryCache::default()),
+            ttl: None,
+            namespace: format!("{}#{}", short_type_name::<N>(), uuid::Uuid::new_v4()),
+        }
+    }
+
+    /// Keep entries in `backend`, which may be shared with other nodes.
+    ///
+    /// Nodes only see each other's entries if they are given the same
+    /// [namespace](Cached::with_namespace); without one, two nodes of the
+    /// same type over one backend, such as two differently prompted chat
+    /// nodes, keep apart.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::cache::{Cached, InMemoryCache};
+    /// use rustyflow::node::FnNode;
+    /// use rustyflow::{FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use std::sync::Arc;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let backend = Arc::new(InMemoryCache::new(100));
+    /// let greeting = |word: &'static str| {
+    ///     FnNode::new(move |input: Value| async move { Ok(json!(format!("{word}, {input}"))) })
+    ///         .with_name("Greet")
+    /// };
+    ///
+    /// let hello = Cached::new(greeting("Hello")).with_backend(backend.clone());
+    /// let bye = Cached::new(greeting("Bye")).with_backend(backend.clone());
+    /// assert_eq!(hello.call(json!("Ada")).await?, json!("Hello, \"Ada\""));
+    /// assert_eq!(bye.call(json!("Ada")).await?, json!("Bye, \"Ada\""));
+    ///
+    /// // A shared namespace shares entries
+    /// let a = Cached::new(greeting("Hi")).with_backend(backend.clone()).with_namespace("greet-v1");
+    /// let b = Cached::new(greeting("Yo")).with_backend(backend).with_namespace("greet-v1");
+    /// a.call(json!("Bob")).await?;
+    /// assert_eq!(b.call(json!("Bob")).await?, json!("Hi, \"Bob\""));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_backend(mut self, backend: Arc<dyn CacheBackend>) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Expire entries `ttl` after they are stored.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Prefix keys with `namespace` instead of one unique to this node, to
+    /// share entries between nodes that compute the same thing, across
+    /// restarts and server replicas, or to drop every entry of a node by
+    /// changing its namespace after a prompt change.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    fn key(&self, input: &Value) -> String {
+        key(&self.namespace, input)
+    }
+}
+
+/// The cache key of `input` under `namespace`.
+pub(crate) fn key(namespace: &str, input: &Value) -> String {
+    format!("{namespace}:{}", digest(input))
+}
+
+/// The hex SHA-256 digest of `value`, the same for equal values whatever
+/// the order of their object keys.
+pub(crate) fn digest(value: &Value) -> String {
+    let digest = Sha256::digest(canonical(value).to_string().as_bytes());
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// `value` with the keys of every object in sorted order.
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let sorted: Map<String, Value> = keys
+                .into_iter()
+                .map(|key| (key.clone(), canonical(&map[key])))
+                .collect();
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonical).collect()),
+        other => other.clone(),
+    }
+}
+
+#[async_trait]
+impl<N: Node> Node for Cached<N> {
+    /// Return the cached result for `input`, or call the node and cache its
+    /// result.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error of the node.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let key = self.key(&input);
+        match self.backend.get(&key).await {
+            Ok(Some(value)) => {
+                metrics::cache_lookup(self.node.name(), true);
+                return Ok(value);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Cache lookup for '{}' failed: {e}", self.node.name()),
+        }
+        metrics::cache_lookup(self.node.name(), false);
+
+        let value = self.node.call(input).await?;
+        if let Err(e) = self.backend.set(&key, value.clone(), self.ttl).await {
+            tracing::warn!("Caching the result of '{}' failed: {e}", self.node.name());
+        }
+        Ok(value)
+    }
+
+    fn name(&self) -> &str {
+        self.node.name()
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.node.input_schema()
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        self.node.schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        self.node.health_checkable()
+    }
+
+    fn resources(&self) -> Vec<Resource> {
+        self.node.resources()
+    }
+
+    async fn snapshot(&self) -> Result<Option<Value>, FlowError> {
+        self.node.snapshot().await
+    }
+
+    async fn restore(&self, state: Value) -> Result<(), FlowError> {
+        self.node.restore(state).await
+    }
+}
@@ -0,0 +1,269 @@
+//! Checkpoint storage for resumable flow execution.
+//!
+//! [`Flow::execute_resumable`](crate::flow::Flow::execute_resumable) saves the
+//! intermediate value after every node to a [`CheckpointStore`], so a run
+//! that crashes part way through can pick up from the last completed step
+//! instead of repeating expensive work such as LLM calls.
+//!
+//! With the `sqlite` feature, `SqliteCheckpointStore` provides a durable
+//! backend that also records run status, per-step payloads, and timestamps.
+//! With the `redis` feature, `RedisStore` keeps checkpoints in Redis, so
+//! several server replicas can share them.
+
+use crate::error::FlowError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{RunSummary, SqliteCheckpointStore, StepRecord};
+
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "redis")]
+pub use self::redis::RedisStore;
+
+/// The saved progress of a flow run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Number of nodes that have completed.
+    pub step: usize,
+    /// The output of the last completed node.
+    pub value: Value,
+    /// Snapshots of the flow's stateful nodes, if any, as returned by
+    /// [`Flow::snapshot`](crate::flow::Flow::snapshot).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<Value>,
+}
+
+/// The lifecycle state of a checkpointed run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    /// The run has started and not yet finished.
+    Running,
+    /// Every node completed.
+    Completed,
+    /// A node or the store returned an error.
+    Failed,
+}
+
+impl RunStatus {
+    /// The lowercase name of the status, as stored by persistent backends.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Running => "running",
+            RunStatus::Completed => "completed",
+            RunStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Persistent storage for flow checkpoints, keyed by run id.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Record that `step` nodes of the run have completed with `value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - Identifies the flow run
+    /// * `step` - Number of nodes completed so far
+    /// * `value` - The output of the last completed node
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the checkpoint cannot be stored.
+    async fn save(&self, run_id: &str, step: usize, value: &Value) -> Result<(), FlowError>;
+
+    /// Record a checkpoint together with the node state it carries.
+    ///
+    /// Flows call this rather than [`CheckpointStore::save`]. The default
+    /// saves only the step and value, dropping `checkpoint.state`, so stores
+    /// should override it to support [stateful nodes](crate::state).
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the checkpoint cannot be stored.
+    async fn save_checkpoint(
+        &self,
+        run_id: &str,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), FlowError> {
+        self.save(run_id, checkpoint.step, &checkpoint.value).await
+    }
+
+    /// Load the latest checkpoint of a run.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Checkpoint))` - The latest saved progress
+    /// * `Ok(None)` - If the run has no checkpoint
+    /// * `Err(FlowError)` - An error if the store cannot be read
+    async fn load(&self, run_id: &str) -> Result<Option<Checkpoint>, FlowError>;
+
+    /// Remove all checkpoints of a run.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the checkpoint cannot be removed.
+    async fn clear(&self, run_id: &str) -> Result<(), FlowError>;
+
+    /// Record a change in the run's status.
+    ///
+    /// Stores that do not track run metadata can rely on the default, which
+    /// does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the status cannot be stored.
+    async fn set_status(&self, run_id: &str, status: RunStatus) -> Result<(), FlowError> {
+        let _ = (run_id, status);
+        Ok(())
+    }
+}
+
+/// A [`CheckpointStore`] held in process memory.
+///
+/// Checkpoints do not survive a restart, which makes this store suited to
+/// tests and to retrying failed runs within a single process.
+#[derive(Debug, Default)]
+pub struct InMemoryCheckpointStore {
+    checkpoints: Mutex<HashMap<String, Checkpoint>>,
+}
+
+impl InMemoryCheckpointStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for InMemoryCheckpointStore {
+    async fn save(&self, run_id: &str, step: usize, value: &Value) -> Result<(), FlowError> {
+        let checkpoint = Checkpoint {
+            step,
+            value: value.clone(),
+            state: None,
+        };
+        self.save_checkpoint(run_id, &checkpoint).await
+    }
+
+    async fn save_checkpoint(
+        &self,
+        run_id: &str,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), FlowError> {
+        self.checkpoints
+            .lock()
+            .unwrap()
+            .insert(run_id.to_string(), checkpoint.clone());
+        Ok(())
+    }
+
+    async fn load(&self, run_id: &str) -> Result<Option<Checkpoint>, FlowError> {
+        Ok(self.checkpoints.lock().unwrap().get(run_id).cloned())
+    }
+
+    async fn clear(&self, run_id: &str) -> Result<(), FlowError> {
+        self.checkpoints.lock().unwrap().remove(run_id);
+        Ok(())
+    }
+}
+
+/// A [`CheckpointStore`] that keeps one JSON file per run in a directory.
+///
+/// Each save writes to a temporary file and renames it into place, so a crash
+/// during a save leaves the previous checkpoint intact.
+#[derive(Debug, Clone)]
+pub struct FileCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileCheckpointStore {
+    /// Create a store in the given directory.
+    ///
+    /// The directory is created on the first save if it does not exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The directory holding the checkpoint files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, run_id: &str) -> Result<PathBuf, FlowError> {
+        let valid = !run_id.is_empty()
+            && run_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && !run_id.starts_with('.');
+        if !valid {
+            return Err(FlowError::Checkpoint(format!(
+                "Invalid run id '{run_id}': use letters, digits, '-', '_' or '.'"
+            )));
+        }
+        Ok(self.dir.join(format!("{run_id}.json")))
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn save(&self, run_id: &str, step: usize, value: &Value) -> Result<(), FlowError> {
+        let checkpoint = Checkpoint {
+            step,
+            value: value.clone(),
+            state: None,
+        };
+        self.save_checkpoint(run_id, &checkpoint).await
+    }
+
+    async fn save_checkpoint(
+        &self,
+        run_id: &str,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), FlowError> {
+        let path = self.path(run_id)?;
+        let bytes = serde_json::to_vec(checkpoint)?;
+        let io_error = |e: std::io::Error| {
+            FlowError::Checkpoint(format!("Cannot write {}: {e}", path.display()))
+        };
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(io_error)?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, bytes).await.map_err(io_error)?;
+        tokio::fs::rename(&tmp, &path).await.map_err(io_error)
+    }
+
+    async fn load(&self, run_id: &str) -> Result<Option<Checkpoint>, FlowError> {
+        let path = self.path(run_id)?;
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(FlowError::Checkpoint(format!(
+                "Cannot read {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+
+    async fn clear(&self, run_id: &str) -> Result<(), FlowError> {
+        let path = self.path(run_id)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(FlowError::Checkpoint(format!(
+                "Cannot remove {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+}
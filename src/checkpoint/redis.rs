@@ -0,0 +1,285 @@
+//! [Redis](https://redis.io) backend for [`CheckpointStore`], [`CacheBackend`]
+//! and [`SessionStore`].
+
+use crate::cache::CacheBackend;
+use crate::checkpoint::{Checkpoint, CheckpointStore, RunStatus};
+use crate::error::FlowError;
+use crate::session::{Session, SessionStore};
+use ::redis::aio::ConnectionManager;
+use ::redis::{AsyncCommands, Client};
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+const DEFAULT_PREFIX: &str = "rustyflow";
+
+/// The first bytes of a gzip stream, which JSON text never starts with.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// A [`CheckpointStore`], [`CacheBackend`] and [`SessionStore`] backed by
+/// Redis.
+///
+/// Server replicas pointed at the same Redis share checkpoints, cached
+/// results and sessions instead of each keeping its own in memory, so a run
+/// started on one replica can resume on another, and a conversation can
+/// move between them. Keys are namespaced by a prefix,
+/// `rustyflow` by default:
+///
+/// | Key | Holds |
+/// |-----|-------|
+/// | `{prefix}:checkpoint:{run_id}` | The latest [`Checkpoint`] of a run |
+/// | `{prefix}:status:{run_id}` | The [`RunStatus`] of a run |
+/// | `{prefix}:cache:{key}` | A [`Cached`](crate::cache::Cached) result |
+/// | `{prefix}:session:{id}` | A [`Session`] |
+///
+/// Values are stored as JSON, or gzip-compressed JSON with
+/// [`with_compression`](RedisStore::with_compression). Compressed and plain
+/// values can be read either way, so compression can be switched on for a
+/// live deployment.
+///
+/// The connection reconnects on its own after Redis restarts. This type is
+/// available with the `redis` feature.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use rustyflow::cache::Cached;
+/// use rustyflow::checkpoint::RedisStore;
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use async_trait::async_trait;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// struct Summarize;
+///
+/// #[async_trait]
+/// impl Node for Summarize {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!({"summary": input["text"]}))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let store = Arc::new(
+///     RedisStore::connect("redis://127.0.0.1/")
+///         .await?
+///         .with_prefix("summarizer")
+///         .with_checkpoint_ttl(Duration::from_secs(24 * 3600))
+///         .with_compression(),
+/// );
+///
+/// // Named, so every replica shares the entries
+/// let node = Cached::new(Summarize)
+///     .with_backend(store.clone())
+///     .with_namespace("summarize-v1");
+/// let flow = Flow::new(vec![Box::new(node)]);
+/// flow.execute_resumable("run-1", json!({"text": "..."}), store.as_ref())
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RedisStore {
+    connection: ConnectionManager,
+    prefix: String,
+    checkpoint_ttl: Option<Duration>,
+    session_ttl: Option<Duration>,
+    compression: bool,
+}
+
+impl RedisStore {
+    /// Connect to Redis.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - A Redis URL such as `redis://:password@host:6379/0`, or
+    ///   `rediss://` for TLS
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the URL is invalid or Redis cannot
+    /// be reached.
+    pub async fn connect(url: &str) -> Result<Self, FlowError> {
+        let client = Client::open(url).map_err(redis_error)?;
+        let connection = ConnectionManager::new(client).await.map_err(redis_error)?;
+        Ok(Self {
+            connection,
+            prefix: DEFAULT_PREFIX.to_string(),
+            checkpoint_ttl: None,
+            session_ttl: None,
+            compression: false,
+        })
+    }
+
+    /// Namespace every key with `prefix`, for example to share one Redis
+    /// between deployments.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Expire checkpoints and run statuses `ttl` after they were last saved.
+    /// Without a TTL they are kept until cleared.
+    pub fn with_checkpoint_ttl(mut self, ttl: Duration) -> Self {
+        self.checkpoint_ttl = Some(ttl);
+        self
+    }
+
+    /// Expire sessions `ttl` after they were last saved. Without a TTL they
+    /// are kept until deleted.
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.session_ttl = Some(ttl);
+        self
+    }
+
+    /// Gzip-compress values before storing them.
+    pub fn with_compression(mut self) -> Self {
+        self.compression = true;
+        self
+    }
+
+    /// The latest status reported for a run, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if Redis cannot be read.
+    pub async fn status(&self, run_id: &str) -> Result<Option<RunStatus>, FlowError> {
+        self.fetch(self.key("status", run_id)).await
+    }
+
+    fn key(&self, kind: &str, id: &str) -> String {
+        format!("{}:{kind}:{id}", self.prefix)
+    }
+
+    fn encode(&self, value: &impl Serialize) -> Result<Vec<u8>, FlowError> {
+        let json = serde_json::to_vec(value)?;
+        if !self.compression {
+            return Ok(json);
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&json)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| FlowError::Checkpoint(format!("Cannot compress value: {e}")))
+    }
+
+    async fn put(
+        &self,
+        key: String,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> Result<(), FlowError> {
+        let mut connection = self.connection.clone();
+        match ttl {
+            Some(ttl) => {
+                let millis = (ttl.as_millis() as u64).max(1);
+                connection.pset_ex::<_, _, ()>(key, value, millis).await
+            }
+            None => connection.set::<_, _, ()>(key, value).await,
+        }
+        .map_err(redis_error)
+    }
+
+    async fn fetch<T: DeserializeOwned>(&self, key: String) -> Result<Option<T>, FlowError> {
+        let mut connection = self.connection.clone();
+        let bytes: Option<Vec<u8>> = connection.get(key).await.map_err(redis_error)?;
+        bytes.map(|bytes| decode(&bytes)).transpose()
+    }
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, FlowError> {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return Ok(serde_json::from_slice(bytes)?);
+    }
+    let mut json = Vec::new();
+    GzDecoder::new(bytes)
+        .read_to_end(&mut json)
+        .map_err(|e| FlowError::Checkpoint(format!("Cannot decompress value: {e}")))?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+#[async_trait]
+impl CheckpointStore for RedisStore {
+    async fn save(&self, run_id: &str, step: usize, value: &Value) -> Result<(), FlowError> {
+        let checkpoint = Checkpoint {
+            step,
+            value: value.clone(),
+            state: None,
+        };
+        self.save_checkpoint(run_id, &checkpoint).await
+    }
+
+    async fn save_checkpoint(
+        &self,
+        run_id: &str,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), FlowError> {
+        let value = self.encode(checkpoint)?;
+        self.put(self.key("checkpoint", run_id), value, self.checkpoint_ttl)
+            .await
+    }
+
+    async fn load(&self, run_id: &str) -> Result<Option<Checkpoint>, FlowError> {
+        self.fetch(self.key("checkpoint", run_id)).await
+    }
+
+    async fn clear(&self, run_id: &str) -> Result<(), FlowError> {
+        let keys = [self.key("checkpoint", run_id), self.key("status", run_id)];
+        let mut connection = self.connection.clone();
+        connection
+            .del::<_, ()>(&keys[..])
+            .await
+            .map_err(redis_error)
+    }
+
+    async fn set_status(&self, run_id: &str, status: RunStatus) -> Result<(), FlowError> {
+        let value = self.encode(&status)?;
+        self.put(self.key("status", run_id), value, self.checkpoint_ttl)
+            .await
+    }
+}
+
+#[async_trait]
+impl CacheBackend for RedisStore {
+    async fn get(&self, key: &str) -> Result<Option<Value>, FlowError> {
+        self.fetch(self.key("cache", key)).await
+    }
+
+    async fn set(&self, key: &str, value: Value, ttl: Option<Duration>) -> Result<(), FlowError> {
+        let value = self.encode(&value)?;
+        self.put(self.key("cache", key), value, ttl).await
+    }
+}
+
+#[async_trait]
+impl SessionStore for RedisStore {
+    async fn load(&self, id: &str) -> Result<Option<Session>, FlowError> {
+        self.fetch(self.key("session", id)).await
+    }
+
+    async fn save(&self, id: &str, session: &Session) -> Result<(), FlowError> {
+        let value = self.encode(session)?;
+        self.put(self.key("session", id), value, self.session_ttl)
+            .await
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), FlowError> {
+        let mut connection = self.connection.clone();
+        connection
+            .del::<_, ()>(self.key("session", id))
+            .await
+            .map_err(redis_error)
+    }
+}
+
+fn redis_error(e: ::redis::RedisError) -> FlowError {
+    FlowError::Checkpoint(format!("Redis error: {e}"))
+}
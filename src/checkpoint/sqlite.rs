@@ -0,0 +1,584 @@
+use super::{Checkpoint, CheckpointStore, RunStatus};
+use crate::error::FlowError;
+use crate::history::{HistoryStore, ModelUsage, RunRecord};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    run_id     TEXT PRIMARY KEY,
+    status     TEXT NOT NULL,
+    step       INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS steps (
+    run_id     TEXT NOT NULL,
+    step       INTEGER NOT NULL,
+    payload    TEXT NOT NULL,
+    state      TEXT,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (run_id, step)
+);
+CREATE TABLE IF NOT EXISTS history (
+    run_id      TEXT PRIMARY KEY,
+    flow        TEXT NOT NULL,
+    tenant      TEXT,
+    started_at  INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    error       TEXT
+);
+CREATE INDEX IF NOT EXISTS history_started_at ON history (started_at);
+CREATE TABLE IF NOT EXISTS history_usage (
+    run_id            TEXT NOT NULL,
+    model             TEXT NOT NULL,
+    prompt_tokens     INTEGER NOT NULL,
+    completion_tokens INTEGER NOT NULL,
+    cost              REAL NOT NULL,
+    PRIMARY KEY (run_id, model)
+);
+CREATE TABLE IF NOT EXISTS history_transcripts (
+    run_id   TEXT PRIMARY KEY,
+    messages TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS history_feedback (
+    run_id   TEXT PRIMARY KEY,
+    score    REAL,
+    feedback TEXT
+);
+CREATE TABLE IF NOT EXISTS history_logs (
+    run_id TEXT PRIMARY KEY,
+    lines  TEXT NOT NULL
+);
+";
+
+/// Metadata about a run stored by [`SqliteCheckpointStore`].
+///
+/// Timestamps are milliseconds since the UNIX epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunSummary {
+    /// The run id.
+    pub run_id: String,
+    /// The latest reported status.
+    pub status: RunStatus,
+    /// Number of nodes completed.
+    pub step: usize,
+    /// When the run was first recorded.
+    pub created_at: u64,
+    /// When the run was last updated.
+    pub updated_at: u64,
+}
+
+/// The output of one completed step, as stored by [`SqliteCheckpointStore`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepRecord {
+    /// Number of nodes completed after this step.
+    pub step: usize,
+    /// The output of the step's node.
+    pub value: Value,
+    /// When the step completed, in milliseconds since the UNIX epoch.
+    pub created_at: u64,
+}
+
+/// A durable [`CheckpointStore`] backed by SQLite.
+///
+/// Unlike the in-memory and file stores, every step's output is kept rather
+/// than only the latest, together with the run's status and timestamps, so
+/// runs can be inspected after they finish with [`SqliteCheckpointStore::runs`]
+/// and [`SqliteCheckpointStore::steps`]. It is also a [`HistoryStore`], so
+/// usage reports can be built from the same database.
+///
+/// This type is available with the `sqlite` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::checkpoint::{RunStatus, SqliteCheckpointStore};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use async_trait::async_trait;
+///
+/// struct Double;
+///
+/// #[async_trait]
+/// impl Node for Double {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(input.as_i64().unwrap_or(0) * 2))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let store = SqliteCheckpointStore::in_memory()?;
+/// let flow = Flow::new(vec![Box::new(Double), Box::new(Double)]);
+/// flow.execute_resumable("run-1", json!(3), &store).await?;
+///
+/// let run = store.run("run-1").await?.unwrap();
+/// assert_eq!(run.status, RunStatus::Completed);
+/// let steps = store.steps("run-1").await?;
+/// assert_eq!(steps.iter().map(|s| s.value.clone()).collect::<Vec<_>>(), [json!(6), json!(12)]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SqliteCheckpointStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteCheckpointStore {
+    /// Open (or create) a database file and its tables.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the database cannot be opened or
+    /// initialized.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FlowError> {
+        Self::init(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    /// Create a store in a private in-memory database.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the database cannot be initialized.
+    pub fn in_memory() -> Result<Self, FlowError> {
+        Self::init(Connection::open_in_memory().map_err(sqlite_error)?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, FlowError> {
+        conn.execute_batch(SCHEMA).map_err(sqlite_error)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Run a database operation on the blocking thread pool.
+    async fn with_conn<T, F>(&self, f: F) -> Result<T, FlowError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, FlowError> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| FlowError::Checkpoint("SQLite connection poisoned".to_string()))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| FlowError::Checkpoint(format!("SQLite task failed: {e}")))?
+    }
+
+    /// List all recorded runs, most recently updated first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the query fails.
+    pub async fn runs(&self) -> Result<Vec<RunSummary>, FlowError> {
+        self.with_conn(|conn| {
+            let mut statement = conn
+                .prepare(
+                    "SELECT run_id, status, step, created_at, updated_at
+                     FROM runs ORDER BY updated_at DESC, run_id",
+                )
+                .map_err(sqlite_error)?;
+            let rows = statement
+                .query_map([], read_run)
+                .map_err(sqlite_error)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(sqlite_error)?;
+            rows.into_iter().map(into_summary).collect()
+        })
+        .await
+    }
+
+    /// Look up a single run.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the query fails.
+    pub async fn run(&self, run_id: &str) -> Result<Option<RunSummary>, FlowError> {
+        let run_id = run_id.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                "SELECT run_id, status, step, created_at, updated_at
+                 FROM runs WHERE run_id = ?1",
+                params![run_id],
+                read_run,
+            )
+            .optional()
+            .map_err(sqlite_error)?
+            .map(into_summary)
+            .transpose()
+        })
+        .await
+    }
+
+    /// List the recorded steps of a run in execution order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the query fails, or
+    /// `FlowError::SerdeError` if a stored payload is not valid JSON.
+    pub async fn steps(&self, run_id: &str) -> Result<Vec<StepRecord>, FlowError> {
+        let run_id = run_id.to_string();
+        self.with_conn(move |conn| {
+            let mut statement = conn
+                .prepare(
+                    "SELECT step, payload, created_at FROM steps
+                     WHERE run_id = ?1 ORDER BY step",
+                )
+                .map_err(sqlite_error)?;
+            let rows = statement
+                .query_map(params![run_id], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                })
+                .map_err(sqlite_error)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(sqlite_error)?;
+            rows.into_iter()
+                .map(|(step, payload, created_at)| {
+                    Ok(StepRecord {
+                        step: step as usize,
+                        value: serde_json::from_str(&payload)?,
+                        created_at: created_at as u64,
+                    })
+                })
+                .collect()
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for SqliteCheckpointStore {
+    async fn save(&self, run_id: &str, step: usize, value: &Value) -> Result<(), FlowError> {
+        let checkpoint = Checkpoint {
+            step,
+            value: value.clone(),
+            state: None,
+        };
+        self.save_checkpoint(run_id, &checkpoint).await
+    }
+
+    async fn save_checkpoint(
+        &self,
+        run_id: &str,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), FlowError> {
+        let run_id = run_id.to_string();
+        let step = checkpoint.step;
+        let payload = serde_json::to_string(&checkpoint.value)?;
+        let state = checkpoint
+            .state
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        self.with_conn(move |conn| {
+            let now = now_millis();
+            let tx = conn.transaction().map_err(sqlite_error)?;
+            tx.execute(
+                "INSERT OR REPLACE INTO steps (run_id, step, payload, state, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![run_id, step as i64, payload, state, now],
+            )
+            .map_err(sqlite_error)?;
+            tx.execute(
+                "INSERT INTO runs (run_id, status, step, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?4)
+                 ON CONFLICT(run_id) DO UPDATE SET step = ?3, updated_at = ?4",
+                params![run_id, RunStatus::Running.as_str(), step as i64, now],
+            )
+            .map_err(sqlite_error)?;
+            tx.commit().map_err(sqlite_error)
+        })
+        .await
+    }
+
+    async fn load(&self, run_id: &str) -> Result<Option<Checkpoint>, FlowError> {
+        let run_id = run_id.to_string();
+        self.with_conn(move |conn| {
+            let row = conn
+                .query_row(
+                    "SELECT step, payload, state FROM steps
+                     WHERE run_id = ?1 ORDER BY step DESC LIMIT 1",
+                    params![run_id],
+                    |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, Option<String>>(2)?,
+                        ))
+                    },
+                )
+                .optional()
+                .map_err(sqlite_error)?;
+            row.map(|(step, payload, state)| {
+                Ok(Checkpoint {
+                    step: step as usize,
+                    value: serde_json::from_str(&payload)?,
+                    state: state.as_deref().map(serde_json::from_str).transpose()?,
+                })
+            })
+            .transpose()
+        })
+        .await
+    }
+
+    async fn clear(&self, run_id: &str) -> Result<(), FlowError> {
+        let run_id = run_id.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction().map_err(sqlite_error)?;
+            tx.execute("DELETE FROM steps WHERE run_id = ?1", params![run_id])
+                .map_err(sqlite_error)?;
+            tx.execute("DELETE FROM runs WHERE run_id = ?1", params![run_id])
+                .map_err(sqlite_error)?;
+            tx.commit().map_err(sqlite_error)
+        })
+        .await
+    }
+
+    async fn set_status(&self, run_id: &str, status: RunStatus) -> Result<(), FlowError> {
+        let run_id = run_id.to_string();
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO runs (run_id, status, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?3)
+                 ON CONFLICT(run_id) DO UPDATE SET status = ?2, updated_at = ?3",
+                params![run_id, status.as_str(), now_millis()],
+            )
+            .map(|_| ())
+            .map_err(sqlite_error)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl HistoryStore for SqliteCheckpointStore {
+    async fn record(&self, run: &RunRecord) -> Result<(), FlowError> {
+        let run = run.clone();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction().map_err(sqlite_error)?;
+            tx.execute(
+                "INSERT OR REPLACE INTO history (run_id, flow, tenant, started_at, duration_ms, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    run.run_id,
+                    run.flow,
+                    run.tenant,
+                    run.started_at as i64,
+                    run.duration_ms as i64,
+                    run.error
+                ],
+            )
+            .map_err(sqlite_error)?;
+            tx.execute(
+                "DELETE FROM history_usage WHERE run_id = ?1",
+                params![run.run_id],
+            )
+            .map_err(sqlite_error)?;
+            tx.execute(
+                "DELETE FROM history_transcripts WHERE run_id = ?1",
+                params![run.run_id],
+            )
+            .map_err(sqlite_error)?;
+            if !run.transcript.is_empty() {
+                tx.execute(
+                    "INSERT INTO history_transcripts (run_id, messages) VALUES (?1, ?2)",
+                    params![run.run_id, serde_json::to_string(&run.transcript)?],
+                )
+                .map_err(sqlite_error)?;
+            }
+            tx.execute(
+                "DELETE FROM history_logs WHERE run_id = ?1",
+                params![run.run_id],
+            )
+            .map_err(sqlite_error)?;
+            if !run.logs.is_empty() {
+                tx.execute(
+                    "INSERT INTO history_logs (run_id, lines) VALUES (?1, ?2)",
+                    params![run.run_id, serde_json::to_string(&run.logs)?],
+                )
+                .map_err(sqlite_error)?;
+            }
+            tx.execute(
+                "DELETE FROM history_feedback WHERE run_id = ?1",
+                params![run.run_id],
+            )
+            .map_err(sqlite_error)?;
+            if run.score.is_some() || run.feedback.is_some() {
+                tx.execute(
+                    "INSERT INTO history_feedback (run_id, score, feedback) VALUES (?1, ?2, ?3)",
+                    params![run.run_id, run.score, run.feedback],
+                )
+                .map_err(sqlite_error)?;
+            }
+            for usage in &run.usage {
+                tx.execute(
+                    "INSERT INTO history_usage
+                     (run_id, model, prompt_tokens, completion_tokens, cost)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        run.run_id,
+                        usage.model,
+                        usage.prompt_tokens as i64,
+                        usage.completion_tokens as i64,
+                        usage.cost
+                    ],
+                )
+                .map_err(sqlite_error)?;
+            }
+            tx.commit().map_err(sqlite_error)
+        })
+        .await
+    }
+
+    async fn runs_between(&self, from: u64, to: u64) -> Result<Vec<RunRecord>, FlowError> {
+        self.with_conn(move |conn| {
+            let mut statement = conn
+                .prepare(
+                    "SELECT run_id, flow, tenant, started_at, duration_ms, error FROM history
+                     WHERE started_at >= ?1 AND started_at < ?2
+                     ORDER BY started_at, run_id",
+                )
+                .map_err(sqlite_error)?;
+            let mut runs = statement
+                .query_map(params![from as i64, to as i64], |row| {
+                    Ok(RunRecord {
+                        run_id: row.get(0)?,
+                        flow: row.get(1)?,
+                        tenant: row.get(2)?,
+                        started_at: row.get::<_, i64>(3)? as u64,
+                        duration_ms: row.get::<_, i64>(4)? as u64,
+                        error: row.get(5)?,
+                        usage: Vec::new(),
+                        transcript: Vec::new(),
+                        score: None,
+                        feedback: None,
+                        logs: Vec::new(),
+                    })
+                })
+                .map_err(sqlite_error)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(sqlite_error)?;
+
+            let mut statement = conn
+                .prepare(
+                    "SELECT model, prompt_tokens, completion_tokens, cost FROM history_usage
+                     WHERE run_id = ?1 ORDER BY model",
+                )
+                .map_err(sqlite_error)?;
+            for run in &mut runs {
+                run.usage = statement
+                    .query_map(params![run.run_id], |row| {
+                        Ok(ModelUsage {
+                            model: row.get(0)?,
+                            prompt_tokens: row.get::<_, i64>(1)? as u64,
+                            completion_tokens: row.get::<_, i64>(2)? as u64,
+                            cost: row.get(3)?,
+                        })
+                    })
+                    .map_err(sqlite_error)?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(sqlite_error)?;
+            }
+
+            let mut statement = conn
+                .prepare("SELECT messages FROM history_transcripts WHERE run_id = ?1")
+                .map_err(sqlite_error)?;
+            for run in &mut runs {
+                let messages: Option<String> = statement
+                    .query_row(params![run.run_id], |row| row.get(0))
+                    .optional()
+                    .map_err(sqlite_error)?;
+                if let Some(messages) = messages {
+                    run.transcript = serde_json::from_str(&messages)?;
+                }
+            }
+
+            let mut statement = conn
+                .prepare("SELECT score, feedback FROM history_feedback WHERE run_id = ?1")
+                .map_err(sqlite_error)?;
+            for run in &mut runs {
+                let row: Option<(Option<f64>, Option<String>)> = statement
+                    .query_row(params![run.run_id], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .optional()
+                    .map_err(sqlite_error)?;
+                if let Some((score, feedback)) = row {
+                    run.score = score;
+                    run.feedback = feedback;
+                }
+            }
+
+            let mut statement = conn
+                .prepare("SELECT lines FROM history_logs WHERE run_id = ?1")
+                .map_err(sqlite_error)?;
+            for run in &mut runs {
+                let lines: Option<String> = statement
+                    .query_row(params![run.run_id], |row| row.get(0))
+                    .optional()
+                    .map_err(sqlite_error)?;
+                if let Some(lines) = lines {
+                    run.logs = serde_json::from_str(&lines)?;
+                }
+            }
+            Ok(runs)
+        })
+        .await
+    }
+}
+
+type RunRow = (String, String, i64, i64, i64);
+
+fn read_run(row: &rusqlite::Row<'_>) -> rusqlite::Result<RunRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+    ))
+}
+
+fn into_summary(
+    (run_id, status, step, created_at, updated_at): RunRow,
+) -> Result<RunSummary, FlowError> {
+    let status = match status.as_str() {
+        "running" => RunStatus::Running,
+        "completed" => RunStatus::Completed,
+        "failed" => RunStatus::Failed,
+        other => {
+            return Err(FlowError::Checkpoint(format!(
+                "Unknown status '{other}' for run '{run_id}'"
+            )))
+        }
+    };
+    Ok(RunSummary {
+        run_id,
+        status,
+        step: step as usize,
+        created_at: created_at as u64,
+        updated_at: updated_at as u64,
+    })
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn sqlite_error(e: rusqlite::Error) -> FlowError {
+    FlowError::Checkpoint(format!("SQLite error: {e}"))
+}
@@ -0,0 +1,297 @@
+//! Host commands run as isolated flow steps.
+//!
+//! This module provides [`CommandNode`], which runs a program with the
+//! node's input as JSON on stdin and returns what it prints. Each run starts
+//! from an empty environment, so API keys and other host variables do not
+//! leak into tools: the child sees only the variables set with
+//! [`CommandNode::with_env`] and the secrets named with
+//! [`CommandNode::with_secret`], looked up in a [`SecretProvider`]. It runs
+//! in a fresh temporary directory, also set as `HOME` and `TMPDIR`, which is
+//! removed once the run finishes, fails or is cancelled. What it writes to
+//! stderr is recorded line by line with [`record_log`], so it reaches the
+//! node's span, its execution report and run history.
+//!
+//! Python and other interpreted tools need no node of their own: run the
+//! interpreter, as in `CommandNode::new("python3").with_args(["-c", script])`,
+//! and they get the same isolation.
+
+use crate::error::FlowError;
+use crate::node::Node;
+use crate::report::{record_log, LogStream};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{ChildStderr, Command};
+
+/// A source of secrets injected into isolated commands.
+pub trait SecretProvider: Send + Sync {
+    /// Look up the secret called `name`, or `None` if there is none.
+    fn secret(&self, name: &str) -> Option<String>;
+}
+
+impl SecretProvider for HashMap<String, String> {
+    fn secret(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+/// Secrets read from the host's own environment variables.
+///
+/// Only the variables a [`CommandNode`] names are passed on, so the host
+/// environment can hold the secrets without every tool seeing all of them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecrets;
+
+impl SecretProvider for EnvSecrets {
+    fn secret(&self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+/// A node that runs a host program with an isolated environment and
+/// working directory.
+///
+/// The input is written to the program's stdin as JSON. Its stdout is
+/// parsed as JSON, or returned as a string if it is not JSON.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::command::CommandNode;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let secrets = HashMap::from([("API_TOKEN".to_string(), "s3cret".to_string())]);
+/// let script = r#"touch scratch.txt
+/// printf '{"token":"%s","user":"%s","dir":"%s","files":"%s"}' \
+///     "$API_TOKEN" "$USER" "$PWD" "$(ls)""#;
+/// let node = CommandNode::new("sh")
+///     .with_args(["-c", script])
+///     .with_secrets(Arc::new(secrets))
+///     .with_secret("API_TOKEN");
+///
+/// std::env::set_var("USER", "host-user");
+/// let output = node.call(json!({})).await?;
+/// assert_eq!(output["token"], "s3cret");
+/// // Host variables are not inherited
+/// assert_eq!(output["user"], "");
+/// // The scratch directory is gone once the run is over
+/// assert_eq!(output["files"], "scratch.txt");
+/// assert!(!std::path::Path::new(output["dir"].as_str().unwrap()).exists());
+///
+/// // A secret the provider does not have fails the call before it starts
+/// let missing = CommandNode::new("true").with_secret("DB_PASSWORD");
+/// let error = missing.call(json!({})).await.unwrap_err();
+/// assert_eq!(error.kind(), "node_failed");
+///
+/// // Input and output larger than a pipe buffer pass through whole
+/// let text = "x".repeat(1 << 20);
+/// assert_eq!(CommandNode::new("cat").call(json!(text)).await?, json!(text));
+/// # Ok(())
+/// # }
+/// ```
+pub struct CommandNode {
+    program: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    secrets: Vec<String>,
+    provider: Arc<dyn SecretProvider>,
+}
+
+impl CommandNode {
+    /// Create a node that runs `program`, found on the host's `PATH`.
+    ///
+    /// Secrets are read from the host environment with [`EnvSecrets`] until
+    /// [`with_secrets`](CommandNode::with_secrets) sets another provider.
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            env: Vec::new(),
+            secrets: Vec::new(),
+            provider: Arc::new(EnvSecrets),
+        }
+    }
+
+    /// Pass `args` to the program.
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the environment variable `key` to `value` for the program.
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Look up the secrets named with
+    /// [`with_secret`](CommandNode::with_secret) in `provider`.
+    pub fn with_secrets(mut self, provider: Arc<dyn SecretProvider>) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    /// Set the environment variable `name` to the secret of the same name.
+    pub fn with_secret(mut self, name: impl Into<String>) -> Self {
+        self.secrets.push(name.into());
+        self
+    }
+
+    fn environment(&self, dir: &Path) -> Result<Vec<(String, String)>, FlowError> {
+        let dir = dir.to_string_lossy().into_owned();
+        let mut env = vec![
+            ("HOME".to_string(), dir.clone()),
+            ("TMPDIR".to_string(), dir),
+        ];
+        env.extend(self.env.iter().cloned());
+        for name in &self.secrets {
+            let value = self.provider.secret(name).ok_or_else(|| {
+                FlowError::NodeFailed(format!("Missing secret '{name}' for '{}'", self.program))
+            })?;
+            env.push((name.clone(), value));
+        }
+        Ok(env)
+    }
+}
+
+/// A temporary directory removed when dropped.
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn create() -> Result<Self, FlowError> {
+        let path = std::env::temp_dir().join(format!("rustyflow-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&path).map_err(|e| {
+            FlowError::NodeFailed(format!("Cannot create '{}': {e}", path.display()))
+        })?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            tracing::warn!("Cannot remove '{}': {e}", self.0.display());
+        }
+    }
+}
+
+/// Record every line the program writes to stderr with [`record_log`], and
+/// return them for the error message of a failed run.
+async fn record_stderr(stderr: Option<ChildStderr>) -> std::io::Result<String> {
+    let mut text = String::new();
+    let Some(stderr) = stderr else {
+        return Ok(text);
+    };
+    let mut lines = BufReader::new(stderr).lines();
+    while let Some(line) = lines.next_line().await? {
+        text.push_str(&line);
+        text.push('\n');
+        record_log(LogStream::Stderr, line);
+    }
+    Ok(text)
+}
+
+#[async_trait]
+impl Node for CommandNode {
+    /// Run the program in a new scratch directory and return its output.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if a secret is missing, which the
+    /// server reports as a `500` since it is a misconfiguration, if the
+    /// program cannot start, or if it exits with a failure status, with what
+    /// it wrote to stderr.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::command::CommandNode;
+    /// use rustyflow::history::RunRecord;
+    /// use rustyflow::report::LogStream;
+    /// use rustyflow::{Flow, FlowError};
+    /// use serde_json::json;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let script = "echo 'loading model' >&2; echo 'slow disk' >&2; echo 42";
+    /// let flow = Flow::new(vec![Box::new(CommandNode::new("sh").with_args(["-c", script]))]);
+    /// let (result, report) = flow.execute_traced(json!({})).await;
+    /// assert_eq!(result?, json!(42));
+    ///
+    /// let logs = &report.nodes[0].logs;
+    /// assert_eq!(logs.len(), 2);
+    /// assert_eq!(logs[0].stream, LogStream::Stderr);
+    /// assert_eq!(logs[0].line, "loading model");
+    ///
+    /// let run = RunRecord::new("run-1", "scoring").with_logs(&report);
+    /// assert_eq!(run.logs[1].line, "slow disk");
+    /// assert_eq!(run.logs[1].node, "CommandNode");
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let scratch = ScratchDir::create()?;
+        let env = self.environment(&scratch.0)?;
+        let failed = |e: std::io::Error| FlowError::NodeFailed(format!("'{}': {e}", self.program));
+
+        // Declared after `scratch`, so a cancelled run kills the program
+        // before its directory is removed
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .env_clear()
+            .envs(env)
+            .current_dir(&scratch.0)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(failed)?;
+
+        // Write the input while draining the output, so neither side blocks
+        // on a full pipe when both are larger than its buffer
+        let input = serde_json::to_vec(&input)?;
+        let stdin = child.stdin.take();
+        let write = async move {
+            if let Some(mut stdin) = stdin {
+                // A program that exits without reading its input is not an error
+                let _ = stdin.write_all(&input).await;
+            }
+        };
+        let mut stdout = Vec::new();
+        let read = async {
+            match child.stdout.take() {
+                Some(mut out) => out.read_to_end(&mut stdout).await.map(drop),
+                None => Ok(()),
+            }
+        };
+        let ((), read, stderr) = tokio::join!(write, read, record_stderr(child.stderr.take()));
+        read.map_err(failed)?;
+        let stderr = stderr.map_err(failed)?;
+        let status = child.wait().await.map_err(failed)?;
+        if !status.success() {
+            return Err(FlowError::NodeFailed(format!(
+                "'{}' exited with {status}: {}",
+                self.program,
+                stderr.trim()
+            )));
+        }
+
+        let stdout = String::from_utf8_lossy(&stdout);
+        Ok(serde_json::from_str(&stdout)
+            .unwrap_or_else(|_| Value::String(stdout.trim().to_string())))
+    }
+}
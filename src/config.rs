@@ -0,0 +1,1015 @@
+//! Declarative flow definitions.
+//!
+//! A [`FlowConfig`] describes a sequential [`Flow`] as JSON, with each step
+//! given as a [`NodeSpec`] that a [`NodeRegistry`] turns into a node:
+//!
+//! ```json
+//! {
+//!   "name": "summarize",
+//!   "nodes": [
+//!     {"type": "prompt_template", "params": {"template": "Summarize: {{ text }}"}}
+//!   ]
+//! }
+//! ```
+//!
+//! A node spec may also name the shared resources the node holds while it
+//! runs, as `"resources": {"gpu": 1}`; they are enforced when the flow is
+//! given a [`ResourcePools`](crate::resources::ResourcePools) scheduler.
+//!
+//! [`FlowConfig::load`] checks a definition, written as JSON or, with the
+//! `yaml` feature, YAML, against the [schema](FlowConfig::schema) generated
+//! from these types and against the registry, and reports every problem it
+//! finds at once as a [`ConfigProblem`] with its line and column. Unknown
+//! node types come with the closest registered name as a suggestion.
+
+use crate::error::FlowError;
+use crate::flow::Flow;
+use crate::node::Node;
+use crate::registry::{NodeRegistry, NodeSpec};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The syntax a flow definition is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// JSON.
+    Json,
+    /// YAML, read with the `yaml` feature.
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// The format of a file, by its extension: `.yaml` or `.yml` is YAML,
+    /// anything else JSON.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
+
+/// One problem found in a flow definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigProblem {
+    /// The file the problem is in, if the definition was read from one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// The JSON pointer of the offending value, `""` for the whole document.
+    pub path: String,
+    /// The line the problem is on, from 1.
+    pub line: usize,
+    /// The column the problem starts at, from 1.
+    pub column: usize,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        write!(f, "line {}, column {}", self.line, self.column)?;
+        if !self.path.is_empty() {
+            write!(f, " ({})", self.path)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// A sequential flow described as data.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::config::FlowConfig;
+/// use rustyflow::registry::NodeRegistry;
+/// use rustyflow::FlowError;
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let config = FlowConfig::from_json(r#"{
+///     "name": "greeting",
+///     "nodes": [{"type": "prompt_template", "params": {"template": "Hello, {{ name }}!"}}]
+/// }"#)?;
+/// let flow = config.build(&NodeRegistry::with_builtins())?;
+///
+/// let result = flow.execute(json!({"name": "Ada"})).await?;
+/// assert_eq!(result[0]["content"], "Hello, Ada!");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FlowConfig {
+    /// The flow name used in telemetry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The nodes to run in order.
+    pub nodes: Vec<NodeSpec>,
+    /// Check that each node's output fits the next node's input when the
+    /// flow is built, with [`Flow::with_contracts`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub contracts: bool,
+    /// Reusable node lists with parameters, by name.
+    ///
+    /// Only [`FlowConfig::load`] instantiates them; [`FlowConfig::build`]
+    /// ignores them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub subflows: BTreeMap<String, Subflow>,
+}
+
+/// A reusable list of nodes with parameters, like a function of a flow
+/// definition.
+///
+/// Each parameter is declared as a JSON Schema, such as
+/// `{"type": "integer", "default": 5}`; parameters without a `default` must
+/// be given. In the node entries, `{"$param": "<name>"}` is replaced by the
+/// argument as is, and `${<name>}` inside a string by the argument as text.
+/// Entries may use other subflows and includes.
+///
+/// A flow instantiates a subflow in its `nodes` with
+/// `{"use": "<name>", "with": {<arguments>}}`, as often as it likes.
+/// Subflows are local to the file that defines them.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::config::{ConfigFormat, FlowConfig};
+/// use rustyflow::registry::NodeRegistry;
+/// use rustyflow::FlowError;
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let source = json!({
+///     "subflows": {
+///         "ask": {
+///             "params": {
+///                 "topic": {"type": "string"},
+///                 "tone": {"type": "string", "default": "plain"}
+///             },
+///             "nodes": [{
+///                 "type": "prompt_template",
+///                 "params": {"template": "In a ${tone} tone, explain ${topic}."}
+///             }]
+///         }
+///     },
+///     "nodes": [
+///         {"use": "ask", "with": {"topic": "ownership", "tone": "friendly"}},
+///         {"use": "ask", "with": {"topic": "lifetimes"}}
+///     ]
+/// })
+/// .to_string();
+///
+/// let registry = NodeRegistry::with_builtins();
+/// let flow = FlowConfig::load(&source, ConfigFormat::Json, &registry)?;
+/// assert_eq!(flow.len(), 2);
+/// let result = flow.execute(json!({})).await?;
+/// assert_eq!(result[0]["content"], "In a plain tone, explain lifetimes.");
+///
+/// // Arguments are checked against the declared parameters
+/// let source = source.replace(r#"{"topic":"lifetimes"}"#, r#"{"topic":7}"#);
+/// let Err(FlowError::InvalidConfig(problems)) =
+///     FlowConfig::load(&source, ConfigFormat::Json, &registry)
+/// else {
+///     panic!("expected problems");
+/// };
+/// assert_eq!(problems[0].path, "/nodes/1/with/topic");
+/// assert_eq!(problems[0].message, "expected string, got number");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Subflow {
+    /// The parameters, each declared as a JSON Schema.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, Value>,
+    /// The node entries, with placeholders for the parameters.
+    pub nodes: Vec<Value>,
+}
+
+impl FlowConfig {
+    /// Parse a definition from a JSON string.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if the JSON is not a valid definition.
+    pub fn from_json(json: &str) -> Result<Self, FlowError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Read a definition from a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the file cannot be read, or
+    /// `FlowError::SerdeError` if it is not a valid definition.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FlowError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            FlowError::NodeFailed(format!("Cannot read flow file {}: {e}", path.display()))
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// The JSON Schema of a definition, for editors and for
+    /// [`FlowConfig::load`].
+    ///
+    /// Node params are left open, since their shape depends on the type.
+    pub fn schema() -> Value {
+        schemars::schema_for!(FlowConfig).to_value()
+    }
+
+    /// Check a definition and build its flow, creating each node with
+    /// `registry`.
+    ///
+    /// Unlike [`FlowConfig::from_json`] followed by [`FlowConfig::build`],
+    /// which stop at the first error, this reports every problem: syntax
+    /// errors, fields the [schema](FlowConfig::schema) does not allow,
+    /// unknown node types with a did-you-mean suggestion, params each
+    /// type's factory rejects, includes that cannot be read, arguments that
+    /// do not match a subflow's parameters, and include or subflow cycles.
+    ///
+    /// An entry of `nodes` may be `{"include": "<path>"}` in place of a
+    /// node spec, to splice in the nodes of another definition file, so a
+    /// shared sub-pipeline is written once. Paths are relative to the
+    /// current directory here, and to the including file with
+    /// [`FlowConfig::load_file`]. Included files may include others. An
+    /// entry may also instantiate one of the definition's
+    /// [`subflows`](Subflow) with arguments. In
+    /// YAML, anchors, aliases and `<<` merge keys can also repeat parts of
+    /// one file; anchors may be defined under top-level fields starting
+    /// with `x-`, which are otherwise ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The definition's text
+    /// * `format` - Whether `source` is JSON or YAML
+    /// * `registry` - The node types the definition may use
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::InvalidConfig` with every problem found,
+    /// including a YAML definition read without the `yaml` feature, or
+    /// `FlowError::Validation` if `contracts` is set and adjacent nodes do
+    /// not fit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::config::{ConfigFormat, FlowConfig};
+    /// use rustyflow::registry::NodeRegistry;
+    /// use rustyflow::FlowError;
+    ///
+    /// let source = r#"{
+    ///   "name": "greeting",
+    ///   "nodes": [
+    ///     {"type": "promt_template", "params": {"template": "Hi"}},
+    ///     {"type": "prompt_template", "parms": {}}
+    ///   ]
+    /// }"#;
+    /// let registry = NodeRegistry::with_builtins();
+    /// let Err(FlowError::InvalidConfig(problems)) =
+    ///     FlowConfig::load(source, ConfigFormat::Json, &registry)
+    /// else {
+    ///     panic!("expected problems");
+    /// };
+    /// assert_eq!(problems.len(), 2);
+    /// assert_eq!(problems[0].path, "/nodes/0/type");
+    /// assert_eq!((problems[0].line, problems[0].column), (4, 6));
+    /// assert!(problems[0].message.contains("did you mean 'prompt_template'?"));
+    /// assert_eq!((problems[1].line, problems[1].column), (5, 33));
+    /// assert_eq!(problems[1].message, "unknown field 'parms'");
+    ///
+    /// // YAML definitions are located the same way
+    /// # #[cfg(feature = "yaml")]
+    /// # {
+    /// let source = "nodes:\n  - type: prompt_template\n    params: {template: Hi}\n  - type: batc\n";
+    /// let Err(FlowError::InvalidConfig(problems)) =
+    ///     FlowConfig::load(source, ConfigFormat::Yaml, &registry)
+    /// else {
+    ///     panic!("expected problems");
+    /// };
+    /// assert_eq!((problems[0].line, problems[0].column), (4, 5));
+    /// assert!(problems[0].message.contains("did you mean 'batch'?"));
+    /// # }
+    /// ```
+    pub fn load(
+        source: &str,
+        format: ConfigFormat,
+        registry: &NodeRegistry,
+    ) -> Result<Flow, FlowError> {
+        let mut loader = Loader {
+            registry,
+            stack: Vec::new(),
+            calls: Vec::new(),
+        };
+        let loaded = loader
+            .load(source, format, None)
+            .map_err(FlowError::InvalidConfig)?;
+        assemble(loaded)
+    }
+
+    /// Check the definition in a file and build its flow, reading `.yaml`
+    /// and `.yml` files as YAML and anything else as JSON.
+    ///
+    /// Includes are resolved relative to the including file, and problems
+    /// carry the file they were found in.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the file cannot be read, or any
+    /// error from [`FlowConfig::load`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::config::FlowConfig;
+    /// use rustyflow::registry::NodeRegistry;
+    /// use rustyflow::FlowError;
+    /// use serde_json::json;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let dir = std::env::temp_dir().join(format!("rustyflow-include-{}", std::process::id()));
+    /// std::fs::create_dir_all(dir.join("blocks")).unwrap();
+    /// let write = |name: &str, json: serde_json::Value| {
+    ///     std::fs::write(dir.join(name), json.to_string()).unwrap();
+    /// };
+    ///
+    /// // A shared block, defined once
+    /// write("blocks/greet.json", json!({"nodes": [
+    ///     {"type": "prompt_template", "params": {"template": "Hello, {{ name }}!"}}
+    /// ]}));
+    /// write("flow.json", json!({"name": "greeting", "nodes": [{"include": "blocks/greet.json"}]}));
+    ///
+    /// let registry = NodeRegistry::with_builtins();
+    /// let flow = FlowConfig::load_file(dir.join("flow.json"), &registry)?;
+    /// let result = flow.execute(json!({"name": "Ada"})).await?;
+    /// assert_eq!(result[0]["content"], "Hello, Ada!");
+    ///
+    /// // Including a file from itself, directly or not, is reported
+    /// write("loop.json", json!({"nodes": [{"include": "loop.json"}]}));
+    /// let Err(FlowError::InvalidConfig(problems)) =
+    ///     FlowConfig::load_file(dir.join("loop.json"), &registry)
+    /// else {
+    ///     panic!("expected problems");
+    /// };
+    /// assert!(problems[0].message.starts_with("include cycle"));
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_file(path: impl AsRef<Path>, registry: &NodeRegistry) -> Result<Flow, FlowError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            FlowError::NodeFailed(format!("Cannot read flow file {}: {e}", path.display()))
+        })?;
+        let mut loader = Loader {
+            registry,
+            stack: vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())],
+            calls: Vec::new(),
+        };
+        let loaded = loader
+            .load(&source, ConfigFormat::from_path(path), Some(path))
+            .map_err(FlowError::InvalidConfig)?;
+        assemble(loaded)
+    }
+
+    /// Build the flow, creating each node with `registry`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from [`NodeRegistry::create_spec`], prefixed
+    /// with the position of the failing node, or `FlowError::Validation` if
+    /// `contracts` is set and adjacent nodes do not fit.
+    pub fn build(&self, registry: &NodeRegistry) -> Result<Flow, FlowError> {
+        let nodes = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, spec)| {
+                registry.create_spec(spec).map_err(|e| {
+                    FlowError::NodeFailed(format!("Node {index} ({}): {e}", spec.type_name))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        assemble(Loaded {
+            name: self.name.clone(),
+            contracts: self.contracts,
+            nodes,
+        })
+    }
+}
+
+/// Build the flow of a checked definition.
+fn assemble(loaded: Loaded) -> Result<Flow, FlowError> {
+    let mut flow = Flow::new(loaded.nodes);
+    if let Some(name) = loaded.name {
+        flow = flow.with_name(name);
+    }
+    if loaded.contracts {
+        flow = flow.with_contracts()?;
+    }
+    Ok(flow)
+}
+
+/// Reads a definition and the files it includes, keeping the chains of
+/// files being read and subflows being instantiated to detect cycles.
+struct Loader<'a> {
+    registry: &'a NodeRegistry,
+    stack: Vec<PathBuf>,
+    calls: Vec<String>,
+}
+
+/// The subflows node entries may use, and the file they are in.
+struct Scope<'v> {
+    subflows: Option<&'v Map<String, Value>>,
+    file: Option<&'v Path>,
+}
+
+/// What reading a list of node entries produced.
+#[derive(Default)]
+struct Output {
+    nodes: Vec<Box<dyn Node>>,
+    /// Problems in the file being read, by JSON pointer.
+    problems: Vec<(String, String)>,
+    /// Problems in included files.
+    included: Vec<ConfigProblem>,
+}
+
+/// Why an include could not be resolved: a problem with the include entry
+/// itself, or problems inside the included file.
+enum IncludeError {
+    Entry(String),
+    File(Vec<ConfigProblem>),
+}
+
+/// The parts of a checked definition a flow is built from.
+struct Loaded {
+    name: Option<String>,
+    contracts: bool,
+    nodes: Vec<Box<dyn Node>>,
+}
+
+impl Loader<'_> {
+    fn load(
+        &mut self,
+        source: &str,
+        format: ConfigFormat,
+        file: Option<&Path>,
+    ) -> Result<Loaded, Vec<ConfigProblem>> {
+        let problem = |path: String, message: String| {
+            let (line, column) = locate(source, format, &path);
+            ConfigProblem {
+                file: file.map(Path::to_path_buf),
+                path,
+                line,
+                column,
+                message,
+            }
+        };
+        let value = parse(source, format).map_err(|(line, column, message)| {
+            vec![ConfigProblem {
+                line,
+                column,
+                ..problem(String::new(), message)
+            }]
+        })?;
+
+        // Node entries are checked one by one, since they may be includes
+        // or subflow uses, and top-level `x-` fields only hold YAML anchors
+        let mut output = Output {
+            problems: crate::schema::validate(&FlowConfig::schema(), &value)
+                .into_iter()
+                .filter(|error| !error.path.starts_with("/nodes/"))
+                .filter(|error| !is_anchor_field(&error.path))
+                .map(|error| (error.path, error.message))
+                .collect(),
+            ..Output::default()
+        };
+        if output.problems.is_empty() {
+            let scope = Scope {
+                subflows: value.get("subflows").and_then(Value::as_object),
+                file,
+            };
+            let entries = value["nodes"].as_array().map(Vec::as_slice);
+            self.entries(entries.unwrap_or_default(), "/nodes", &scope, &mut output);
+        }
+
+        if output.problems.is_empty() && output.included.is_empty() {
+            return Ok(Loaded {
+                name: value
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                contracts: value["contracts"] == true,
+                nodes: output.nodes,
+            });
+        }
+        Err(output
+            .problems
+            .into_iter()
+            .map(|(path, message)| problem(path, message))
+            .chain(output.included)
+            .collect())
+    }
+
+    /// Build the nodes of a list of entries found at `base`.
+    fn entries(&mut self, entries: &[Value], base: &str, scope: &Scope, output: &mut Output) {
+        for (index, entry) in entries.iter().enumerate() {
+            let path = format!("{base}/{index}");
+            if entry.get("include").is_some() {
+                match self.include(entry, scope.file) {
+                    Ok(nodes) => output.nodes.extend(nodes),
+                    Err(IncludeError::Entry(message)) => {
+                        output.problems.push((format!("{path}/include"), message))
+                    }
+                    Err(IncludeError::File(problems)) => output.included.extend(problems),
+                }
+                continue;
+            }
+            if entry.get("use").is_some() {
+                self.instantiate(entry, &path, scope, output);
+                continue;
+            }
+
+            let errors =
+                crate::schema::validate(&schemars::schema_for!(NodeSpec).to_value(), entry);
+            if !errors.is_empty() {
+                let errors = errors
+                    .into_iter()
+                    .map(|error| (format!("{path}{}", error.path), error.message));
+                output.problems.extend(errors);
+                continue;
+            }
+            let Ok(spec) = serde_json::from_value::<NodeSpec>(entry.clone()) else {
+                continue;
+            };
+            if !self.registry.contains(&spec.type_name) {
+                output.problems.push((
+                    format!("{path}/type"),
+                    self.registry.unknown_type(&spec.type_name),
+                ));
+                continue;
+            }
+            let message = match self.registry.create_spec(&spec) {
+                Ok(node) => {
+                    output.nodes.push(node);
+                    continue;
+                }
+                Err(FlowError::NodeFailed(message)) => message,
+                Err(FlowError::SerdeError(e)) => e.to_string(),
+                Err(e) => e.to_string(),
+            };
+            output.problems.push((format!("{path}/params"), message));
+        }
+    }
+
+    /// Build the nodes of a `{"use": "<name>", "with": {...}}` entry found
+    /// at `path`.
+    fn instantiate(&mut self, entry: &Value, path: &str, scope: &Scope, output: &mut Output) {
+        let mut fail = |at: &str, message: String| {
+            output.problems.push((format!("{path}{at}"), message));
+        };
+        let Some(name) = entry["use"].as_str() else {
+            return fail("/use", "expected a subflow name".to_string());
+        };
+        if let Some(field) = entry
+            .as_object()
+            .and_then(|fields| fields.keys().find(|key| *key != "use" && *key != "with"))
+        {
+            return fail(&format!("/{field}"), format!("unknown field '{field}'"));
+        }
+        let subflows = scope.subflows;
+        let Some(subflow) = subflows.and_then(|subflows| subflows.get(name)) else {
+            let names = subflows.into_iter().flat_map(|subflows| subflows.keys());
+            let message = match crate::registry::closest(name, names.map(String::as_str)) {
+                Some(closest) => format!("Unknown subflow '{name}' (did you mean '{closest}'?)"),
+                None => format!("Unknown subflow '{name}'"),
+            };
+            return fail("/use", message);
+        };
+        if let Some(start) = self.calls.iter().position(|call| call == name) {
+            let chain = [&self.calls[start..], &[name.to_string()]].concat();
+            return fail("/use", format!("subflow cycle: {}", chain.join(" -> ")));
+        }
+
+        let params = subflow["params"].as_object().cloned().unwrap_or_default();
+        let required: Vec<&String> = params
+            .iter()
+            .filter(|(_, param)| param.get("default").is_none())
+            .map(|(name, _)| name)
+            .collect();
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": params,
+            "required": required,
+            "additionalProperties": false,
+        });
+        let given = entry
+            .get("with")
+            .cloned()
+            .unwrap_or(Value::Object(Map::new()));
+        let errors = crate::schema::validate(&schema, &given);
+        if !errors.is_empty() {
+            for error in errors {
+                fail(&format!("/with{}", error.path), error.message);
+            }
+            return;
+        }
+        let mut args = given.as_object().cloned().unwrap_or_default();
+        for (param, declared) in &params {
+            if let (false, Some(default)) = (args.contains_key(param), declared.get("default")) {
+                args.insert(param.clone(), default.clone());
+            }
+        }
+
+        let base = format!(
+            "/subflows/{}/nodes",
+            name.replace('~', "~0").replace('/', "~1")
+        );
+        let entries = subflow["nodes"].as_array().cloned().unwrap_or_default();
+        let mut undeclared = Vec::new();
+        let entries = substitute(&Value::Array(entries), &args, &base, &mut undeclared);
+        if !undeclared.is_empty() {
+            output.problems.extend(undeclared);
+            return;
+        }
+
+        let mut inner = Output::default();
+        self.calls.push(name.to_string());
+        let entries = entries.as_array().map(Vec::as_slice).unwrap_or_default();
+        self.entries(entries, &base, scope, &mut inner);
+        self.calls.pop();
+        output.nodes.extend(inner.nodes);
+        output.included.extend(inner.included);
+        output.problems.extend(
+            inner
+                .problems
+                .into_iter()
+                .map(|(at, message)| (at, format!("{message} (in the use of '{name}' at {path})"))),
+        );
+    }
+
+    /// The nodes of the file an `{"include": "<path>"}` entry names.
+    fn include(
+        &mut self,
+        entry: &Value,
+        file: Option<&Path>,
+    ) -> Result<Vec<Box<dyn Node>>, IncludeError> {
+        let Some(relative) = entry["include"].as_str() else {
+            return Err(IncludeError::Entry("expected a file path".to_string()));
+        };
+        if entry.as_object().map_or(0, |fields| fields.len()) > 1 {
+            return Err(IncludeError::Entry(
+                "an include entry takes no other fields".to_string(),
+            ));
+        }
+        let base = file.and_then(Path::parent).unwrap_or(Path::new(""));
+        let path = base.join(relative);
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| IncludeError::Entry(format!("Cannot read {}: {e}", path.display())))?;
+        if let Some(start) = self.stack.iter().position(|seen| *seen == canonical) {
+            let chain: Vec<String> = self.stack[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|seen| seen.display().to_string())
+                .collect();
+            return Err(IncludeError::Entry(format!(
+                "include cycle: {}",
+                chain.join(" -> ")
+            )));
+        }
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| IncludeError::Entry(format!("Cannot read {}: {e}", path.display())))?;
+
+        // Subflow names are local to each file
+        let calls = std::mem::take(&mut self.calls);
+        self.stack.push(canonical);
+        let loaded = self.load(&source, ConfigFormat::from_path(&path), Some(&path));
+        self.stack.pop();
+        self.calls = calls;
+        loaded
+            .map(|loaded| loaded.nodes)
+            .map_err(IncludeError::File)
+    }
+}
+
+/// Replace parameter placeholders in `value`, found at `path`, with their
+/// arguments, noting placeholders for undeclared parameters.
+fn substitute(
+    value: &Value,
+    args: &Map<String, Value>,
+    path: &str,
+    undeclared: &mut Vec<(String, String)>,
+) -> Value {
+    let mut argument = |name: &str| {
+        let found = args.get(name).cloned();
+        if found.is_none() {
+            undeclared.push((path.to_string(), format!("undeclared parameter '{name}'")));
+        }
+        found.unwrap_or(Value::Null)
+    };
+    match value {
+        Value::Object(fields) if fields.len() == 1 && fields.contains_key("$param") => {
+            match fields["$param"].as_str() {
+                Some(name) => argument(name),
+                None => value.clone(),
+            }
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, field)| {
+                    let at = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                    (key.clone(), substitute(field, args, &at, undeclared))
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| substitute(item, args, &format!("{path}/{index}"), undeclared))
+                .collect(),
+        ),
+        Value::String(text) if text.contains("${") => {
+            let mut result = String::new();
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("${") {
+                let Some(end) = rest[start..].find('}') else {
+                    break;
+                };
+                result.push_str(&rest[..start]);
+                match argument(&rest[start + 2..start + end]) {
+                    Value::String(text) => result.push_str(&text),
+                    other => result.push_str(&other.to_string()),
+                }
+                rest = &rest[start + end + 1..];
+            }
+            result.push_str(rest);
+            Value::String(result)
+        }
+        other => other.clone(),
+    }
+}
+
+/// Whether `path` is a top-level `x-` field, which holds YAML anchors.
+fn is_anchor_field(path: &str) -> bool {
+    path.strip_prefix("/x-")
+        .is_some_and(|rest| !rest.contains('/'))
+}
+
+/// Parse a definition, or give the line, column and description of why it
+/// cannot be.
+fn parse(source: &str, format: ConfigFormat) -> Result<Value, (usize, usize, String)> {
+    // Drop the position serde appends, which the problem carries already
+    let message = |message: String| match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message,
+    };
+    match format {
+        ConfigFormat::Json => {
+            serde_json::from_str(source).map_err(|e| (e.line(), e.column(), message(e.to_string())))
+        }
+        #[cfg(feature = "yaml")]
+        ConfigFormat::Yaml => {
+            let mut value: serde_yaml::Value = serde_yaml::from_str(source).map_err(|e| {
+                let (line, column) = e
+                    .location()
+                    .map_or((1, 1), |location| (location.line(), location.column()));
+                (line, column, message(e.to_string()))
+            })?;
+            value
+                .apply_merge()
+                .map_err(|e| (1, 1, message(e.to_string())))?;
+            serde_json::to_value(value).map_err(|e| (1, 1, e.to_string()))
+        }
+        #[cfg(not(feature = "yaml"))]
+        ConfigFormat::Yaml => Err((
+            1,
+            1,
+            "Reading a YAML flow definition needs the 'yaml' feature".to_string(),
+        )),
+    }
+}
+
+/// The line and column of the value a JSON pointer names, or of the
+/// deepest part of it that can be found in `source`.
+fn locate(source: &str, format: ConfigFormat, pointer: &str) -> (usize, usize) {
+    let segments: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    match format {
+        ConfigFormat::Json => {
+            let offset = locate_json(source, &segments);
+            let before = &source[..offset];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+            (line, column)
+        }
+        ConfigFormat::Yaml => locate_yaml(source, &segments),
+    }
+}
+
+/// The byte offset of the key or array element a pointer names.
+fn locate_json(source: &str, segments: &[String]) -> usize {
+    let bytes = source.as_bytes();
+    let mut position = skip_whitespace(bytes, 0);
+    let mut found = position;
+    for segment in segments {
+        let next = match bytes.get(position) {
+            Some(b'{') => find_key(source, position, segment),
+            Some(b'[') => segment
+                .parse()
+                .ok()
+                .and_then(|index| find_element(bytes, position, index))
+                .map(|at| (at, at)),
+            _ => None,
+        };
+        let Some((at, value)) = next else { break };
+        found = at;
+        position = value;
+    }
+    found
+}
+
+/// The offsets of `key` and of its value in the object starting at `open`.
+fn find_key(source: &str, open: usize, key: &str) -> Option<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let mut position = skip_whitespace(bytes, open + 1);
+    while bytes.get(position) == Some(&b'"') {
+        let key_end = end_of_value(bytes, position);
+        let name: Option<String> = serde_json::from_str(&source[position..key_end]).ok();
+        let colon = skip_whitespace(bytes, key_end);
+        if bytes.get(colon) != Some(&b':') {
+            return None;
+        }
+        let value = skip_whitespace(bytes, colon + 1);
+        if name.as_deref() == Some(key) {
+            return Some((position, value));
+        }
+        position = skip_whitespace(bytes, end_of_value(bytes, value));
+        if bytes.get(position) != Some(&b',') {
+            return None;
+        }
+        position = skip_whitespace(bytes, position + 1);
+    }
+    None
+}
+
+/// The offset of element `index` of the array starting at `open`.
+fn find_element(bytes: &[u8], open: usize, index: usize) -> Option<usize> {
+    let mut position = skip_whitespace(bytes, open + 1);
+    if bytes.get(position) == Some(&b']') {
+        return None;
+    }
+    for _ in 0..index {
+        position = skip_whitespace(bytes, end_of_value(bytes, position));
+        if bytes.get(position) != Some(&b',') {
+            return None;
+        }
+        position = skip_whitespace(bytes, position + 1);
+    }
+    Some(position)
+}
+
+/// The offset just past the JSON value starting at `start`.
+fn end_of_value(bytes: &[u8], start: usize) -> usize {
+    let mut position = start;
+    let mut depth = 0usize;
+    while let Some(&byte) = bytes.get(position) {
+        match byte {
+            b'"' => {
+                position += 1;
+                while let Some(&byte) = bytes.get(position) {
+                    position += if byte == b'\\' { 2 } else { 1 };
+                    if byte == b'"' {
+                        break;
+                    }
+                }
+                if depth == 0 {
+                    return position;
+                }
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return position + 1;
+                }
+            }
+            b',' | b'}' | b']' if depth == 0 => return position,
+            byte if depth == 0 && byte.is_ascii_whitespace() => return position,
+            _ => {}
+        }
+        position += 1;
+    }
+    position
+}
+
+fn skip_whitespace(bytes: &[u8], mut position: usize) -> usize {
+    while bytes.get(position).is_some_and(u8::is_ascii_whitespace) {
+        position += 1;
+    }
+    position
+}
+
+/// A block-style YAML line, split at each `- ` that opens a list item.
+struct YamlToken<'a> {
+    line: usize,
+    column: usize,
+    dash: bool,
+    text: &'a str,
+}
+
+/// The line and column of the key or list item a pointer names in
+/// block-style YAML. Flow-style collections (`{...}`, `[...]`) are not
+/// looked into; the key holding them is reported instead.
+fn locate_yaml(source: &str, segments: &[String]) -> (usize, usize) {
+    let mut tokens = Vec::new();
+    for (line, text) in source.lines().enumerate() {
+        let mut rest = text.trim_start();
+        if rest.is_empty() || rest.starts_with('#') || rest == "---" {
+            continue;
+        }
+        let mut column = text.len() - rest.len();
+        while rest == "-" || rest.starts_with("- ") {
+            tokens.push(YamlToken {
+                line,
+                column,
+                dash: true,
+                text: "-",
+            });
+            let after = rest[1..].trim_start();
+            column += rest.len() - after.len();
+            rest = after;
+        }
+        if !rest.is_empty() {
+            tokens.push(YamlToken {
+                line,
+                column,
+                dash: false,
+                text: rest,
+            });
+        }
+    }
+
+    let mut start = 0;
+    let mut parent: Option<usize> = None;
+    let mut found = tokens
+        .first()
+        .map_or((0, 0), |token| (token.line, token.column));
+    for segment in segments {
+        let block: Vec<(usize, &YamlToken)> = tokens
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take_while(|(_, token)| parent.is_none() || Some(token.column) > parent)
+            .collect();
+        let Some(column) = block.first().map(|(_, token)| token.column) else {
+            break;
+        };
+        let mut siblings = block.iter().filter(|(_, token)| token.column == column);
+        let next = match segment.parse::<usize>() {
+            Ok(index) if block[0].1.dash => siblings.filter(|(_, token)| token.dash).nth(index),
+            _ => siblings.find(|(_, token)| {
+                !token.dash
+                    && [
+                        segment.clone(),
+                        format!("\"{segment}\""),
+                        format!("'{segment}'"),
+                    ]
+                    .iter()
+                    .any(|key| {
+                        token
+                            .text
+                            .strip_prefix(key.as_str())
+                            .is_some_and(|rest| rest.starts_with(':'))
+                    })
+            }),
+        };
+        let Some(&(index, token)) = next else { break };
+        found = (token.line, token.column);
+        start = index + 1;
+        parent = Some(token.column);
+    }
+    (found.0 + 1, found.1 + 1)
+}
@@ -0,0 +1,196 @@
+//! Constraints on the text a language model may generate.
+//!
+//! A [`Constraint`] set on a [`ChatRequest`](crate::llm::ChatRequest) asks
+//! the model to produce only text of a given shape: JSON matching a schema,
+//! text matching a regular expression, or a sentence of a grammar. Models
+//! that can enforce a constraint while decoding say so through
+//! [`ChatModel::supports_constraint`](crate::llm::ChatModel::supports_constraint),
+//! and their output is then guaranteed to satisfy it instead of being
+//! validated and retried after the fact.
+//!
+//! [`Constraint::to_regex`] translates common JSON Schemas into an
+//! equivalent regular expression, for backends that can only enforce
+//! regular expressions.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// How deeply `$ref`s are followed before a schema counts as recursive.
+const MAX_DEPTH: usize = 16;
+
+/// A restriction on the text a model may generate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Constraint {
+    /// JSON that validates against this JSON Schema.
+    JsonSchema(Value),
+    /// Text that matches this regular expression in full, in Rust `regex`
+    /// syntax.
+    Regex(String),
+    /// Text derived from this EBNF grammar, in the dialect the provider
+    /// accepts (such as llama.cpp's GBNF).
+    Grammar(String),
+}
+
+impl Constraint {
+    /// A regular expression equivalent to the constraint, if there is one.
+    ///
+    /// JSON Schemas are translated for the subset that describes a regular
+    /// language: `const`, `enum`, `anyOf`, `oneOf`, local `$ref`s and the
+    /// types `string`, `integer`, `number`, `boolean`, `null`, `array` and
+    /// `object` with `properties`. The generated JSON has no whitespace, has
+    /// only the listed properties, and lists required ones first. Free-form
+    /// objects, recursive schemas and grammars have no equivalent and return
+    /// `None`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::constraint::Constraint;
+    /// use serde_json::json;
+    ///
+    /// let constraint = Constraint::JsonSchema(json!({
+    ///     "type": "object",
+    ///     "properties": {"label": {"enum": ["positive", "negative"]}},
+    ///     "required": ["label"],
+    /// }));
+    /// assert_eq!(
+    ///     constraint.to_regex().as_deref(),
+    ///     Some(r#"\{"label":(?:"positive"|"negative")\}"#)
+    /// );
+    /// ```
+    pub fn to_regex(&self) -> Option<String> {
+        match self {
+            Constraint::JsonSchema(schema) => schema_regex(schema, schema, 0),
+            Constraint::Regex(pattern) => Some(pattern.clone()),
+            Constraint::Grammar(_) => None,
+        }
+    }
+}
+
+const STRING: &str = r#""(?:[^"\\\x00-\x1F]|\\["\\/bfnrt]|\\u[0-9a-fA-F]{4})*""#;
+const INTEGER: &str = r"-?(?:0|[1-9][0-9]*)";
+const NUMBER: &str = r"-?(?:0|[1-9][0-9]*)(?:\.[0-9]+)?(?:[eE][+-]?[0-9]+)?";
+
+/// Escape the regex metacharacters in `text`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if r"\.+*?()|[]{}^$#&-~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn alternation(options: Vec<String>) -> String {
+    match options.len() {
+        1 => options.into_iter().next().unwrap_or_default(),
+        _ => format!("(?:{})", options.join("|")),
+    }
+}
+
+/// The schema a local `$ref` such as `#/$defs/Item` points to.
+fn resolve<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    root.pointer(reference.strip_prefix('#')?)
+}
+
+fn schema_regex(schema: &Value, root: &Value, depth: usize) -> Option<String> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    let schema = schema.as_object()?;
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        return schema_regex(resolve(root, reference)?, root, depth + 1);
+    }
+    if let Some(value) = schema.get("const") {
+        return Some(escape(&value.to_string()));
+    }
+    if let Some(Value::Array(values)) = schema.get("enum") {
+        let options = values.iter().map(|value| escape(&value.to_string()));
+        return Some(alternation(options.collect()));
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(Value::Array(schemas)) = schema.get(key) {
+            let options = schemas
+                .iter()
+                .map(|schema| schema_regex(schema, root, depth + 1))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(alternation(options));
+        }
+    }
+
+    match schema.get("type")? {
+        Value::String(kind) => type_regex(kind, schema, root, depth),
+        Value::Array(kinds) => {
+            let options = kinds
+                .iter()
+                .map(|kind| type_regex(kind.as_str()?, schema, root, depth))
+                .collect::<Option<Vec<_>>>()?;
+            Some(alternation(options))
+        }
+        _ => None,
+    }
+}
+
+fn type_regex(
+    kind: &str,
+    schema: &Map<String, Value>,
+    root: &Value,
+    depth: usize,
+) -> Option<String> {
+    match kind {
+        "string" => Some(STRING.to_string()),
+        "integer" => Some(INTEGER.to_string()),
+        "number" => Some(NUMBER.to_string()),
+        "boolean" => Some("(?:true|false)".to_string()),
+        "null" => Some("null".to_string()),
+        "array" => {
+            let item = schema_regex(schema.get("items")?, root, depth + 1)?;
+            Some(format!(r"\[(?:{item}(?:,{item})*)?\]"))
+        }
+        "object" => object_regex(schema, root, depth),
+        _ => None,
+    }
+}
+
+fn object_regex(schema: &Map<String, Value>, root: &Value, depth: usize) -> Option<String> {
+    let properties = schema.get("properties")?.as_object()?;
+    let required: Vec<&str> = match schema.get("required") {
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let mut fields = Vec::with_capacity(properties.len());
+    for (name, property) in properties {
+        let key = escape(&Value::String(name.clone()).to_string());
+        let value = schema_regex(property, root, depth + 1)?;
+        fields.push((format!("{key}:{value}"), required.contains(&name.as_str())));
+    }
+
+    // Required fields come first, so each optional one can bring its comma
+    let (required, optional): (Vec<_>, Vec<_>) = fields.into_iter().partition(|(_, r)| *r);
+    let required: Vec<String> = required.into_iter().map(|(field, _)| field).collect();
+    let optional: Vec<String> = optional.into_iter().map(|(field, _)| field).collect();
+    let body = if !required.is_empty() {
+        let mut body = required.join(",");
+        for field in &optional {
+            body.push_str(&format!("(?:,{field})?"));
+        }
+        body
+    } else if optional.is_empty() {
+        String::new()
+    } else {
+        // Any subset of optional fields: pick the first one present, then
+        // each later one may follow
+        let firsts = (0..optional.len()).map(|first| {
+            let mut body = optional[first].clone();
+            for field in &optional[first + 1..] {
+                body.push_str(&format!("(?:,{field})?"));
+            }
+            body
+        });
+        format!("(?:{})?", firsts.collect::<Vec<_>>().join("|"))
+    };
+    Some(format!(r"\{{{body}\}}"))
+}
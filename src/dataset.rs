@@ -0,0 +1,367 @@
+//! Fine-tuning datasets built from recorded runs.
+//!
+//! [`DatasetBuilder`] turns production traffic kept in a [`HistoryStore`]
+//! into training data. It keeps the successful runs with a transcript that
+//! pass its score and custom filters, redacts their text, drops duplicate
+//! conversations, and splits the rest into training and validation sets,
+//! which [`Dataset::write`] saves as JSON Lines in a [`TranscriptFormat`].
+//!
+//! Runs are assigned to a split by a hash of their run id, so a run stays in
+//! the same split when the dataset is rebuilt later with more traffic.
+
+use crate::error::FlowError;
+use crate::history::{HistoryStore, RunRecord};
+use crate::llm::Message;
+use crate::transcript::{render, TranscriptFormat};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::Path;
+
+type RunFilter = dyn Fn(&RunRecord) -> bool + Send + Sync;
+type Redactor = dyn Fn(&str) -> String + Send + Sync;
+
+const DEFAULT_VALIDATION_FRACTION: f64 = 0.1;
+
+/// How many runs a [`DatasetBuilder`] looked at and why it dropped some.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DatasetStats {
+    /// Runs given to the builder.
+    pub runs: usize,
+    /// Runs dropped because they failed, had no transcript, or did not pass
+    /// the score or custom filter.
+    pub filtered: usize,
+    /// Runs dropped because an earlier run had the same conversation.
+    pub duplicates: usize,
+}
+
+/// Training and validation runs selected by a [`DatasetBuilder`].
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    /// The runs to train on, with redacted transcripts.
+    pub train: Vec<RunRecord>,
+    /// The runs held out for validation, with redacted transcripts.
+    pub validation: Vec<RunRecord>,
+    /// What the builder kept and dropped.
+    pub stats: DatasetStats,
+}
+
+impl Dataset {
+    /// The training set as JSON Lines in `format`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if a message cannot be serialized.
+    pub fn train_jsonl(&self, format: TranscriptFormat) -> Result<String, FlowError> {
+        render(&self.train, format)
+    }
+
+    /// The validation set as JSON Lines in `format`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if a message cannot be serialized.
+    pub fn validation_jsonl(&self, format: TranscriptFormat) -> Result<String, FlowError> {
+        render(&self.validation, format)
+    }
+
+    /// Write `train.jsonl` and `validation.jsonl` to `dir`, creating it if
+    /// needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to write to
+    /// * `format` - [`TranscriptFormat::OpenAi`] or
+    ///   [`TranscriptFormat::ShareGpt`]
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if a file cannot be written, or
+    /// `FlowError::SerdeError` if a message cannot be serialized.
+    pub async fn write(
+        &self,
+        dir: impl AsRef<Path>,
+        format: TranscriptFormat,
+    ) -> Result<(), FlowError> {
+        let dir = dir.as_ref();
+        let io_error = |e: std::io::Error| {
+            FlowError::NodeFailed(format!("Cannot write dataset to {}: {e}", dir.display()))
+        };
+        tokio::fs::create_dir_all(dir).await.map_err(io_error)?;
+        tokio::fs::write(dir.join("train.jsonl"), self.train_jsonl(format)?)
+            .await
+            .map_err(io_error)?;
+        tokio::fs::write(dir.join("validation.jsonl"), self.validation_jsonl(format)?)
+            .await
+            .map_err(io_error)
+    }
+}
+
+/// Builds a fine-tuning [`Dataset`] from recorded runs.
+///
+/// Failed runs and runs without a transcript are always left out. Of runs
+/// with the same conversation, after redaction and ignoring case and
+/// whitespace, only the first is kept.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::dataset::{redact_emails, DatasetBuilder};
+/// use rustyflow::history::RunRecord;
+/// use rustyflow::llm::Message;
+/// use rustyflow::transcript::TranscriptFormat;
+///
+/// # fn main() -> Result<(), rustyflow::FlowError> {
+/// let run = |id: &str, score: f64, question: &str| {
+///     RunRecord::new(id, "support")
+///         .with_score(score)
+///         .with_transcript(vec![
+///             Message::user(question),
+///             Message::assistant("We have sent you a reset link."),
+///         ])
+/// };
+/// let runs = vec![
+///     run("a", 0.9, "Reset the password for ann@example.com"),
+///     run("b", 0.9, "Reset the password for  bob@example.com"),
+///     run("c", 0.2, "Reset my password"),
+/// ];
+///
+/// let dataset = DatasetBuilder::new()
+///     .with_min_score(0.5)
+///     .with_redactor(redact_emails)
+///     .with_validation_fraction(0.0)
+///     .build(&runs);
+///
+/// // "c" scored too low, and "b" is "a" once the email is redacted
+/// assert_eq!(dataset.train.len(), 1);
+/// assert_eq!(dataset.stats.filtered, 1);
+/// assert_eq!(dataset.stats.duplicates, 1);
+///
+/// let jsonl = dataset.train_jsonl(TranscriptFormat::OpenAi)?;
+/// assert!(jsonl.contains("Reset the password for [EMAIL]"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct DatasetBuilder {
+    min_score: Option<f64>,
+    filter: Option<Box<RunFilter>>,
+    redactors: Vec<Box<Redactor>>,
+    validation_fraction: f64,
+    seed: u64,
+}
+
+impl Default for DatasetBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DatasetBuilder {
+    /// Create a builder that keeps every successful run with a transcript
+    /// and holds out 10% of them for validation.
+    pub fn new() -> Self {
+        Self {
+            min_score: None,
+            filter: None,
+            redactors: Vec::new(),
+            validation_fraction: DEFAULT_VALIDATION_FRACTION,
+            seed: 0,
+        }
+    }
+
+    /// Keep only runs scored at least `min_score`. Unscored runs are left
+    /// out.
+    pub fn with_min_score(mut self, min_score: f64) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
+    /// Keep only runs for which `filter` returns `true`, for example those
+    /// with positive [`feedback`](RunRecord::feedback).
+    pub fn with_filter(
+        mut self,
+        filter: impl Fn(&RunRecord) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Pass the text of every message and every string in tool call
+    /// arguments through `redactor`. Redactors run in the order they were
+    /// added.
+    pub fn with_redactor(
+        mut self,
+        redactor: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.redactors.push(Box::new(redactor));
+        self
+    }
+
+    /// Set the share of runs held out for validation, between 0 and 1.
+    pub fn with_validation_fraction(mut self, fraction: f64) -> Self {
+        self.validation_fraction = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Change which runs land in the validation set.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Select, redact, deduplicate and split `runs`.
+    pub fn build(&self, runs: &[RunRecord]) -> Dataset {
+        let mut dataset = Dataset::default();
+        dataset.stats.runs = runs.len();
+        let mut seen = HashSet::new();
+        for run in runs {
+            if !self.keeps(run) {
+                dataset.stats.filtered += 1;
+                continue;
+            }
+            let mut run = run.clone();
+            for message in &mut run.transcript {
+                self.redact_message(message);
+            }
+            if !seen.insert(conversation_key(&run.transcript)) {
+                dataset.stats.duplicates += 1;
+                continue;
+            }
+            if self.in_validation(&run.run_id) {
+                dataset.validation.push(run);
+            } else {
+                dataset.train.push(run);
+            }
+        }
+        dataset
+    }
+
+    /// Build a dataset from the runs that started in `[from, to)`, in
+    /// milliseconds since the UNIX epoch.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the store cannot be read.
+    pub async fn build_from(
+        &self,
+        store: &dyn HistoryStore,
+        from: u64,
+        to: u64,
+    ) -> Result<Dataset, FlowError> {
+        Ok(self.build(&store.runs_between(from, to).await?))
+    }
+
+    fn keeps(&self, run: &RunRecord) -> bool {
+        if run.error.is_some() || run.transcript.is_empty() {
+            return false;
+        }
+        if let Some(min_score) = self.min_score {
+            match run.score {
+                Some(score) if score >= min_score => {}
+                _ => return false,
+            }
+        }
+        match &self.filter {
+            Some(filter) => filter(run),
+            None => true,
+        }
+    }
+
+    fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for redactor in &self.redactors {
+            text = redactor(&text);
+        }
+        text
+    }
+
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.redact(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_value(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.redact_value(item)),
+            _ => {}
+        }
+    }
+
+    fn redact_message(&self, message: &mut Message) {
+        if self.redactors.is_empty() {
+            return;
+        }
+        message.content = self.redact(&message.content);
+        for call in &mut message.tool_calls {
+            self.redact_value(&mut call.arguments);
+        }
+    }
+
+    fn in_validation(&self, run_id: &str) -> bool {
+        let digest = Sha256::new()
+            .chain_update(self.seed.to_le_bytes())
+            .chain_update(run_id.as_bytes())
+            .finalize();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&digest[..8]);
+        (u64::from_le_bytes(bytes) as f64 / u64::MAX as f64) < self.validation_fraction
+    }
+}
+
+/// A digest of a conversation that ignores case and whitespace.
+fn conversation_key(messages: &[Message]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for message in messages {
+        let content = message.content.to_lowercase();
+        let words: Vec<&str> = content.split_whitespace().collect();
+        hasher.update(format!("{:?}\n{}\n", message.role, words.join(" ")));
+        for call in &message.tool_calls {
+            hasher.update(format!("{}({})\n", call.name, call.arguments));
+        }
+    }
+    hasher.finalize().into()
+}
+
+/// Replace email addresses in `text` with `[EMAIL]`.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::dataset::redact_emails;
+///
+/// assert_eq!(
+///     redact_emails("Write to jane.doe@mail.example.org today."),
+///     "Write to [EMAIL] today."
+/// );
+/// ```
+pub fn redact_emails(text: &str) -> String {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || c == '.' || c == '-';
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('@') {
+        let start = rest[..at]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_local(*c))
+            .last()
+            .map_or(at, |(i, _)| i);
+        let domain = &rest[at + 1..];
+        let domain_len = domain.find(|c: char| !is_domain(c)).unwrap_or(domain.len());
+        // A trailing dot ends the sentence rather than the domain
+        let domain = domain[..domain_len].trim_end_matches('.');
+        let is_email = start < at
+            && domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.starts_with('-');
+        if is_email {
+            out.push_str(&rest[..start]);
+            out.push_str("[EMAIL]");
+            rest = &rest[at + 1 + domain.len()..];
+        } else {
+            out.push_str(&rest[..=at]);
+            rest = &rest[at + 1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,164 @@
+//! Tiered timeouts for slow nodes.
+//!
+//! This module provides [`Deadline`], which bounds a node call with a hard
+//! timeout and, optionally, an earlier soft one. Passing the soft timeout
+//! does not fail the call: it logs a warning, sends an
+//! [`ExecutionEvent::Warning`](crate::events::ExecutionEvent::Warning) to
+//! live event listeners, and can start a fallback node that races the slow
+//! call. Only the hard timeout cancels what is still running, so
+//! borderline-slow calls degrade to a cheaper answer instead of failing
+//! outright.
+
+use crate::error::FlowError;
+use crate::events;
+use crate::node::{HealthCheck, Node, SchemaProvider};
+use async_trait::async_trait;
+use futures::future::select_ok;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A node that escalates from a warning to a fallback to cancellation as
+/// its inner node runs long.
+///
+/// Until the soft timeout, the inner node runs alone and its result or
+/// error is returned as is. From the soft timeout on, the fallback, if any,
+/// runs alongside it and the first success of the two is returned. At the
+/// hard timeout every call still running is cancelled. A soft timeout that
+/// is not shorter than the hard one is ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::deadline::Deadline;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::time::Duration;
+///
+/// struct Model(&'static str, u64);
+///
+/// #[async_trait]
+/// impl Node for Model {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         tokio::time::sleep(Duration::from_millis(self.1)).await;
+///         Ok(json!(self.0))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// // The large model is slow today: after 50ms the small one is asked too
+/// let answer = Deadline::new(Box::new(Model("large", 5_000)), Duration::from_secs(1))
+///     .with_soft_timeout(Duration::from_millis(50))
+///     .with_fallback(Box::new(Model("small", 10)));
+/// assert_eq!(answer.call(json!({})).await?, json!("small"));
+///
+/// // Without a fallback, the hard timeout fails the call
+/// let bounded = Deadline::new(Box::new(Model("large", 5_000)), Duration::from_millis(50));
+/// let error = bounded.call(json!({})).await.unwrap_err();
+/// assert!(matches!(error, FlowError::Timeout(_)));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Deadline {
+    node: Box<dyn Node>,
+    hard: Duration,
+    soft: Option<Duration>,
+    fallback: Option<Box<dyn Node>>,
+}
+
+impl Deadline {
+    /// Create a deadline that cancels `node` after `hard`.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node or flow to bound
+    /// * `hard` - How long it may run before it is cancelled
+    pub fn new(node: Box<dyn Node>, hard: Duration) -> Self {
+        Self {
+            node,
+            hard,
+            soft: None,
+            fallback: None,
+        }
+    }
+
+    /// Warn, and start the fallback if one is set, once the node has run
+    /// for `soft`.
+    pub fn with_soft_timeout(mut self, soft: Duration) -> Self {
+        self.soft = Some(soft);
+        self
+    }
+
+    /// Race `fallback` against the node once the soft timeout has passed.
+    pub fn with_fallback(mut self, fallback: Box<dyn Node>) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    fn timed_out(&self) -> FlowError {
+        FlowError::Timeout(format!(
+            "'{}' did not finish within {:?}",
+            self.node.name(),
+            self.hard
+        ))
+    }
+}
+
+#[async_trait]
+impl Node for Deadline {
+    /// Call the node, escalating as it passes each timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Timeout` if no result arrives before the hard
+    /// timeout, the node's error if it fails before the soft timeout, and
+    /// otherwise the error of the call that failed last.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let input = Arc::new(input);
+        let mut primary = self.node.call_shared(Arc::clone(&input));
+        let Some(soft) = self.soft.filter(|soft| *soft < self.hard) else {
+            return tokio::time::timeout(self.hard, primary)
+                .await
+                .unwrap_or_else(|_| Err(self.timed_out()));
+        };
+
+        if let Ok(result) = tokio::time::timeout(soft, &mut primary).await {
+            return result;
+        }
+
+        let message = format!("'{}' is still running after {soft:?}", self.node.name());
+        tracing::warn!("{message}");
+        events::emit_warning(message);
+
+        let remaining = self.hard - soft;
+        let result = match &self.fallback {
+            Some(fallback) => {
+                tracing::debug!("Starting fallback '{}'", fallback.name());
+                let calls = vec![primary, fallback.call_shared(input)];
+                tokio::time::timeout(remaining, select_ok(calls))
+                    .await
+                    .map(|result| result.map(|(value, _)| value))
+            }
+            None => tokio::time::timeout(remaining, primary).await,
+        };
+        result.unwrap_or_else(|_| Err(self.timed_out()))
+    }
+
+    fn name(&self) -> &str {
+        self.node.name()
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.node.input_schema()
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        self.node.schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        self.node.health_checkable()
+    }
+}
@@ -0,0 +1,205 @@
+//! Diagram rendering for flow topologies.
+//!
+//! Flow types describe their structure as a [`Diagram`] of named steps and
+//! edges, which renders to Graphviz DOT or Mermaid. Every diagram starts at
+//! an `input` terminal and ends at an `output` terminal.
+//!
+//! Flows whose diagram steps are numbered like their nodes can also render
+//! an [`ExecutionOverlay`] of a run on top of the topology.
+
+use crate::overlay::{ExecutionOverlay, StepStatus};
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+/// A directed graph of named steps.
+pub(crate) struct Diagram {
+    title: Option<String>,
+    nodes: Vec<String>,
+    edges: Vec<(Endpoint, Endpoint, Option<String>)>,
+}
+
+/// One end of an edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Endpoint {
+    Input,
+    Node(usize),
+    Output,
+}
+
+impl Endpoint {
+    fn id(self) -> String {
+        match self {
+            Endpoint::Input => "input".to_string(),
+            Endpoint::Node(index) => format!("n{index}"),
+            Endpoint::Output => "output".to_string(),
+        }
+    }
+}
+
+impl Diagram {
+    pub(crate) fn new(title: Option<&str>) -> Self {
+        Self {
+            title: title.map(str::to_string),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Add a step labelled `name`, returning its endpoint.
+    pub(crate) fn node(&mut self, name: &str) -> Endpoint {
+        self.nodes.push(name.to_string());
+        Endpoint::Node(self.nodes.len() - 1)
+    }
+
+    /// Add an edge, optionally labelled.
+    pub(crate) fn edge(&mut self, from: Endpoint, to: Endpoint, label: Option<&str>) {
+        self.edges.push((from, to, label.map(str::to_string)));
+    }
+
+    /// Render as a Graphviz DOT digraph.
+    pub(crate) fn to_dot(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::from("digraph flow {\n    rankdir=LR;\n");
+        if let Some(title) = &self.title {
+            let _ = writeln!(out, "    label={};", quote(title));
+            out.push_str("    labelloc=t;\n");
+        }
+        out.push_str("    input [shape=circle];\n    output [shape=doublecircle];\n");
+        for (index, name) in self.nodes.iter().enumerate() {
+            let _ = writeln!(out, "    n{index} [shape=box, label={}];", quote(name));
+        }
+        for (from, to, label) in &self.edges {
+            let _ = write!(out, "    {} -> {}", from.id(), to.id());
+            if let Some(label) = label {
+                let _ = write!(out, " [label={}]", quote(label));
+            }
+            out.push_str(";\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render as a Mermaid flowchart.
+    pub(crate) fn to_mermaid(&self) -> String {
+        self.mermaid(None)
+    }
+
+    /// Render as a Mermaid flowchart with each step styled by its status in
+    /// `overlay`, its time in its label, and the edges the run took
+    /// highlighted.
+    pub(crate) fn to_live_mermaid(&self, overlay: &ExecutionOverlay) -> String {
+        self.mermaid(Some(overlay))
+    }
+
+    fn mermaid(&self, overlay: Option<&ExecutionOverlay>) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('"', "#quot;"));
+        let mut out = String::new();
+        if let Some(title) = &self.title {
+            let _ = writeln!(out, "---\ntitle: {}\n---", quote(title));
+        }
+        out.push_str("flowchart LR\n    input((input))\n    output(((output)))\n");
+        for (index, name) in self.nodes.iter().enumerate() {
+            let label = match overlay.and_then(|overlay| overlay.step(index)) {
+                Some(step) if step.calls > 1 => {
+                    format!("{name} ({} ms, {} calls)", step.duration_ms, step.calls)
+                }
+                Some(step) if step.status != StepStatus::Running => {
+                    format!("{name} ({} ms)", step.duration_ms)
+                }
+                _ => name.clone(),
+            };
+            let _ = writeln!(out, "    n{index}[{}]", quote(&label));
+        }
+        for (from, to, label) in &self.edges {
+            match label {
+                Some(label) => {
+                    let _ = writeln!(out, "    {} -->|{}| {}", from.id(), quote(label), to.id());
+                }
+                None => {
+                    let _ = writeln!(out, "    {} --> {}", from.id(), to.id());
+                }
+            }
+        }
+        if let Some(overlay) = overlay {
+            out.push_str("    classDef running fill:#fff3cd,stroke:#d39e00\n");
+            out.push_str("    classDef succeeded fill:#d4edda,stroke:#28a745\n");
+            out.push_str("    classDef failed fill:#f8d7da,stroke:#dc3545\n");
+            for status in [
+                StepStatus::Running,
+                StepStatus::Succeeded,
+                StepStatus::Failed,
+            ] {
+                let ids: Vec<String> = (0..self.nodes.len())
+                    .filter(|&index| overlay.step_status(index) == status)
+                    .map(|index| format!("n{index}"))
+                    .collect();
+                if !ids.is_empty() {
+                    let _ = writeln!(out, "    class {} {}", ids.join(","), status.as_str());
+                }
+            }
+            // Mermaid numbers links in the order they are declared
+            let taken: Vec<String> = self
+                .edges
+                .iter()
+                .enumerate()
+                .filter(|(_, (from, to, _))| overlay.took(*from, *to))
+                .map(|(index, _)| index.to_string())
+                .collect();
+            if !taken.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "    linkStyle {} stroke:#28a745,stroke-width:3px",
+                    taken.join(",")
+                );
+            }
+        }
+        out
+    }
+
+    /// Describe the diagram and `overlay` as JSON for a UI to draw.
+    pub(crate) fn live_view(&self, overlay: &ExecutionOverlay) -> Value {
+        let nodes: Vec<Value> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let mut node = json!({
+                    "id": Endpoint::Node(index).id(),
+                    "index": index,
+                    "name": name,
+                    "status": overlay.step_status(index).as_str(),
+                });
+                if let Some(step) = overlay.step(index) {
+                    node["duration_ms"] = json!(step.duration_ms);
+                    node["calls"] = json!(step.calls);
+                    if let Some(error) = &step.error {
+                        node["error"] = json!(error);
+                    }
+                }
+                node
+            })
+            .collect();
+        let edges: Vec<Value> = self
+            .edges
+            .iter()
+            .map(|(from, to, label)| {
+                let mut edge = json!({
+                    "from": from.id(),
+                    "to": to.id(),
+                    "taken": overlay.took(*from, *to),
+                });
+                if let Some(label) = label {
+                    edge["label"] = json!(label);
+                }
+                edge
+            })
+            .collect();
+        json!({
+            "title": self.title,
+            "status": overlay.status().as_str(),
+            "current": overlay.current(),
+            "nodes": nodes,
+            "edges": edges,
+        })
+    }
+}
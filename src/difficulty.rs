@@ -0,0 +1,201 @@
+//! Task difficulty estimation for cascade routing.
+//!
+//! This module provides [`DifficultyEstimator`], a node placed in front of a
+//! [`ModelSelector`](crate::selector::ModelSelector) or a branching step. It
+//! scores how hard a request looks, with cheap text heuristics or a small
+//! model, and attaches the result as a [`RoutingHint`].
+
+use crate::error::FlowError;
+use crate::llm::{record_usage, ChatModel, ChatRequest, Message, Role};
+use crate::node::Node;
+use crate::selector::{RoutingHint, Tier};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+/// Words that suggest multi-step reasoning.
+const REASONING_TERMS: &[&str] = &[
+    "prove",
+    "derive",
+    "analyze",
+    "analyse",
+    "compare",
+    "optimize",
+    "design",
+    "debug",
+    "explain why",
+    "step by step",
+    "trade-off",
+    "tradeoff",
+    "algorithm",
+    "complexity",
+    "architecture",
+    "evaluate",
+];
+
+/// A node that estimates request difficulty and emits a routing hint.
+///
+/// The input is a messages array, a chat request object, or a plain string
+/// (treated as one user message). The output is the chat request with a
+/// `hint` field holding the `difficulty` (0 to 1) and its `tier` (`easy`,
+/// `medium` or `hard`), ready for a
+/// [`ModelSelector`](crate::selector::ModelSelector). Any existing hint
+/// fields, such as required capabilities, are kept.
+///
+/// By default, difficulty is scored from the user messages: their length,
+/// reasoning terms such as "prove" or "step by step", code, math, and the
+/// number of questions asked. With [`DifficultyEstimator::with_model`], a
+/// model rates the request instead, falling back to the heuristics if its
+/// reply has no usable score.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::difficulty::DifficultyEstimator;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let estimator = DifficultyEstimator::new();
+///
+/// let easy = estimator.call(json!("What is the capital of France?")).await?;
+/// assert_eq!(easy["hint"]["tier"], "easy");
+///
+/// let hard = estimator
+///     .call(json!(
+///         "Design a lock-free queue, prove it is linearizable, and analyze the \
+///          complexity of each operation step by step. Compare it to a mutex-based \
+///          design: when is each faster? What are the trade-offs under contention?"
+///     ))
+///     .await?;
+/// assert_eq!(hard["hint"]["tier"], "hard");
+/// # Ok(())
+/// # }
+/// ```
+pub struct DifficultyEstimator {
+    model: Option<Box<dyn ChatModel>>,
+    long_prompt_tokens: usize,
+}
+
+impl Default for DifficultyEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DifficultyEstimator {
+    /// Create a heuristic estimator.
+    pub fn new() -> Self {
+        Self {
+            model: None,
+            long_prompt_tokens: 2000,
+        }
+    }
+
+    /// Ask `model` to rate difficulty instead of using heuristics.
+    pub fn with_model(mut self, model: impl ChatModel + 'static) -> Self {
+        self.model = Some(Box::new(model));
+        self
+    }
+
+    /// Set the prompt length, in tokens, at which length alone counts as
+    /// hard. Defaults to 2000; tokens are estimated at four characters each.
+    pub fn with_long_prompt_tokens(mut self, tokens: usize) -> Self {
+        self.long_prompt_tokens = tokens.max(1);
+        self
+    }
+
+    /// Score the text of a request with heuristics.
+    ///
+    /// # Returns
+    ///
+    /// A difficulty between `0.0` and `1.0`.
+    pub fn heuristic_score(&self, text: &str) -> f64 {
+        let lower = text.to_lowercase();
+        let tokens = text.chars().count() / 4;
+        let length = (tokens as f64 / self.long_prompt_tokens as f64).min(1.0) * 0.35;
+
+        let reasoning = REASONING_TERMS
+            .iter()
+            .filter(|term| lower.contains(*term))
+            .count() as f64
+            * 0.15;
+        let code = if text.contains("```") || lower.contains("fn ") || lower.contains("def ") {
+            0.15
+        } else {
+            0.0
+        };
+        let math = if text.contains('\\')
+            || text.contains('∑')
+            || text.contains('∫')
+            || lower.contains("equation")
+            || lower.contains("integral")
+        {
+            0.1
+        } else {
+            0.0
+        };
+        let questions = text.matches('?').count().saturating_sub(1) as f64 * 0.1;
+
+        (length + reasoning.min(0.6) + code + math + questions.min(0.2)).clamp(0.0, 1.0)
+    }
+
+    async fn model_score(
+        &self,
+        model: &dyn ChatModel,
+        text: &str,
+    ) -> Result<Option<f64>, FlowError> {
+        let request = ChatRequest::new(vec![
+            Message::system(
+                "Rate how difficult the following task is for a language model, from 0 \
+                 (trivial lookup or small talk) to 10 (expert multi-step reasoning). \
+                 Respond with the number only.",
+            ),
+            Message::user(text),
+        ]);
+        let reply = record_usage(model.chat(request).await?).message.content;
+        let score = reply
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .find_map(|part| part.parse::<f64>().ok());
+        Ok(score.map(|score| (score / 10.0).clamp(0.0, 1.0)))
+    }
+}
+
+#[async_trait]
+impl Node for DifficultyEstimator {
+    /// Estimate the difficulty of the request and attach a routing hint.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if the input is not a request, or
+    /// propagates the model's error.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let mut request = match input {
+            Value::String(text) => json!({ "messages": [Message::user(text)] }),
+            Value::Array(messages) => json!({ "messages": messages }),
+            other => other,
+        };
+        let mut hint: RoutingHint = match request.get("hint") {
+            Some(hint) => serde_json::from_value(hint.clone())?,
+            None => RoutingHint::default(),
+        };
+        let messages: Vec<Message> = serde_json::from_value(request["messages"].clone())?;
+        let text = messages
+            .iter()
+            .filter(|message| message.role == Role::User)
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let model_score = match &self.model {
+            Some(model) => self.model_score(model.as_ref(), &text).await?,
+            None => None,
+        };
+        let difficulty = model_score.unwrap_or_else(|| self.heuristic_score(&text));
+
+        hint.difficulty = Some(difficulty);
+        hint.tier = Some(Tier::from_difficulty(difficulty));
+        request["hint"] = serde_json::to_value(hint)?;
+        Ok(request)
+    }
+}
@@ -0,0 +1,213 @@
+//! Text embeddings for retrieval workflows.
+//!
+//! This module defines the [`Embedder`] trait implemented by embedding
+//! providers, [`EmbedNode`] for embedding text inside a flow, and (with the
+//! `reqwest` feature) [`OpenAiEmbedder`] for OpenAI-compatible APIs.
+
+use crate::error::FlowError;
+use crate::node::Node;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// A provider that maps texts to dense vectors.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::embeddings::Embedder;
+/// use rustyflow::FlowError;
+///
+/// /// Embeds text by its length and vowel count; handy in tests.
+/// struct ToyEmbedder;
+///
+/// #[async_trait]
+/// impl Embedder for ToyEmbedder {
+///     async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, FlowError> {
+///         Ok(texts
+///             .iter()
+///             .map(|t| vec![t.len() as f32, t.matches(['a', 'e', 'i', 'o', 'u']).count() as f32])
+///             .collect())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Embed a batch of texts.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` - The texts to embed
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<Vec<f32>>)` - One vector per text, in input order
+    /// * `Err(FlowError)` - An error if the provider call fails
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, FlowError>;
+}
+
+#[async_trait]
+impl<E: Embedder + ?Sized> Embedder for Arc<E> {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, FlowError> {
+        (**self).embed(texts).await
+    }
+}
+
+/// Compute the cosine similarity of two vectors.
+///
+/// Returns `0.0` if the vectors differ in length or either has zero norm.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// A node that embeds text using an [`Embedder`].
+///
+/// The input is a JSON array of strings and the output is a JSON array of
+/// vectors in the same order. A single string input produces a single vector.
+pub struct EmbedNode<E: Embedder> {
+    embedder: E,
+}
+
+impl<E: Embedder> EmbedNode<E> {
+    /// Create a new embedding node.
+    ///
+    /// # Arguments
+    ///
+    /// * `embedder` - The embedding provider to use
+    pub fn new(embedder: E) -> Self {
+        Self { embedder }
+    }
+}
+
+#[async_trait]
+impl<E: Embedder> Node for EmbedNode<E> {
+    /// Embed the input text(s).
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if the input is not a string or an
+    /// array of strings, or propagates any error from the embedder.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        if let Value::String(text) = input {
+            let mut vectors = self.embedder.embed(&[text]).await?;
+            return Ok(serde_json::to_value(vectors.pop().unwrap_or_default())?);
+        }
+        let texts: Vec<String> = serde_json::from_value(input)?;
+        let vectors = self.embedder.embed(&texts).await?;
+        Ok(serde_json::to_value(vectors)?)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+pub use openai::OpenAiEmbedder;
+
+#[cfg(feature = "reqwest")]
+mod openai {
+    use super::Embedder;
+    use crate::error::FlowError;
+    use async_trait::async_trait;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize)]
+    struct EmbeddingResponse {
+        data: Vec<EmbeddingData>,
+    }
+
+    #[derive(Deserialize)]
+    struct EmbeddingData {
+        index: usize,
+        embedding: Vec<f32>,
+    }
+
+    /// An [`Embedder`] for the OpenAI `/embeddings` API and compatible
+    /// servers (Azure OpenAI, vLLM, Ollama, LM Studio, ...).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use rustyflow::embeddings::{EmbedNode, OpenAiEmbedder};
+    ///
+    /// let embedder = OpenAiEmbedder::new(std::env::var("OPENAI_API_KEY").unwrap(), "text-embedding-3-small")
+    ///     .with_base_url("http://localhost:11434/v1");
+    /// let node = EmbedNode::new(embedder);
+    /// ```
+    pub struct OpenAiEmbedder {
+        client: reqwest::Client,
+        base_url: String,
+        api_key: String,
+        model: String,
+    }
+
+    impl OpenAiEmbedder {
+        /// Create an embedder for the given API key and model.
+        ///
+        /// # Arguments
+        ///
+        /// * `api_key` - Bearer token sent with every request
+        /// * `model` - The embedding model name
+        pub fn new(api_key: impl Into<String>, model: impl Into<String>) -> Self {
+            Self {
+                client: crate::http::shared_client(),
+                base_url: "https://api.openai.com/v1".to_string(),
+                api_key: api_key.into(),
+                model: model.into(),
+            }
+        }
+
+        /// Point the embedder at a different OpenAI-compatible server.
+        pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+            self.base_url = base_url.into().trim_end_matches('/').to_string();
+            self
+        }
+
+        /// Use a preconfigured HTTP client.
+        pub fn with_client(mut self, client: reqwest::Client) -> Self {
+            self.client = client;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl Embedder for OpenAiEmbedder {
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, FlowError> {
+            if texts.is_empty() {
+                return Ok(Vec::new());
+            }
+            let response = self
+                .client
+                .post(format!("{}/embeddings", self.base_url))
+                .bearer_auth(&self.api_key)
+                .json(&json!({ "model": self.model, "input": texts }))
+                .send()
+                .await
+                .map_err(|e| FlowError::NodeFailed(format!("Embedding request failed: {e}")))?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(FlowError::NodeFailed(format!(
+                    "Embedding request failed with status {status}: {body}"
+                )));
+            }
+
+            let mut parsed: EmbeddingResponse = response
+                .json()
+                .await
+                .map_err(|e| FlowError::NodeFailed(format!("Invalid embedding response: {e}")))?;
+            parsed.data.sort_by_key(|d| d.index);
+            Ok(parsed.data.into_iter().map(|d| d.embedding).collect())
+        }
+    }
+}
@@ -1,7 +1,16 @@
 //! Error types for RustyFlow operations.
 
+use crate::config::ConfigProblem;
+use crate::schema::FieldError;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Json, Response};
+use serde_json::{json, Value};
+use std::io::Write;
 use thiserror::Error;
 
+/// How much of a failed node's input [`FlowError::NodeError`] keeps.
+const SNIPPET_LEN: usize = 200;
+
 /// Error types that can occur during flow execution.
 ///
 /// This enum represents all possible errors that can happen when executing
@@ -22,9 +31,386 @@ pub enum FlowError {
     #[error("Data serialization/deserialization error: {0}")]
     SerdeError(#[from] serde_json::Error),
 
+    /// A prompt template could not be compiled or rendered.
+    ///
+    /// Syntax errors are reported when the template is constructed, so a
+    /// malformed template fails fast instead of in the middle of a flow.
+    #[error("Template error: {0}")]
+    Template(String),
+
+    /// A cost budget did not have enough remaining allowance.
+    ///
+    /// This error occurs when a node tries to spend from a
+    /// [`Budget`](crate::budget::Budget) that is exhausted.
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    /// A checkpoint could not be saved, loaded, or removed.
+    ///
+    /// This error occurs when a
+    /// [`CheckpointStore`](crate::checkpoint::CheckpointStore) fails or
+    /// holds a checkpoint that does not fit the flow being resumed.
+    #[error("Checkpoint error: {0}")]
+    Checkpoint(String),
+
+    /// An operation did not finish within its time limit.
+    ///
+    /// This error occurs when a node or branch runs past a configured
+    /// timeout.
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// An operation was stopped before it finished.
+    ///
+    /// This error occurs when the cancellation token of a
+    /// [`NodeContext`](crate::node::NodeContext) is cancelled while a node
+    /// runs, for example because the server is shutting down.
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
+    /// An upstream service rejected a request because of rate limiting.
+    ///
+    /// Nodes return this for responses such as HTTP `429 Too Many
+    /// Requests`, so callers like an adaptive [`Batch`](crate::Batch) can
+    /// back off and retry.
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+
+    /// A flow exceeded a loop or step limit.
+    ///
+    /// This error occurs when a [`GraphFlow`](crate::graph::GraphFlow)
+    /// would follow a bounded edge more often than allowed, or runs more
+    /// steps than its limit.
+    #[error("Loop limit exceeded: {0}")]
+    LoopLimit(String),
+
+    /// A requested flow, job, package or other resource does not exist.
+    ///
+    /// This error occurs when something is looked up by name or id and
+    /// nothing is registered or stored under it.
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// A node inside a flow failed.
+    ///
+    /// [`Flow`](crate::Flow), [`ParallelFlow`](crate::ParallelFlow),
+    /// [`GraphFlow`](crate::graph::GraphFlow) and [`Batch`](crate::Batch)
+    /// wrap the errors of the nodes they call in this variant, so the error
+    /// says which node failed and what it was given. Nested flows add one
+    /// layer each; [`FlowError::root_cause`] finds the original error.
+    ///
+    /// The input is only kept in the `input_snippet` field, not in the
+    /// error's message, which is logged and sent in HTTP responses: inputs
+    /// may carry credentials or personal data.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::{json, Value};
+    ///
+    /// struct Parse;
+    ///
+    /// #[async_trait]
+    /// impl Node for Parse {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         input["count"]
+    ///             .as_u64()
+    ///             .map(|count| json!(count))
+    ///             .ok_or_else(|| FlowError::NodeFailed("count is not a number".to_string()))
+    ///     }
+    ///
+    ///     fn name(&self) -> &str {
+    ///         "Parse"
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let flow = Flow::new(vec![Box::new(Parse)]);
+    /// let error = flow.execute(json!({"count": "three"})).await.unwrap_err();
+    ///
+    /// let FlowError::NodeError { node_name, node_index, input_snippet, .. } = &error else {
+    ///     panic!("expected a node error");
+    /// };
+    /// assert_eq!((node_name.as_str(), *node_index), ("Parse", 0));
+    /// assert_eq!(input_snippet, r#"{"count":"three"}"#);
+    /// assert!(matches!(error.root_cause(), FlowError::NodeFailed(_)));
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "Node 0 ('Parse') failed: Node execution failed: count is not a number"
+    /// );
+    /// # }
+    /// ```
+    #[error("Node {node_index} ('{node_name}') failed: {source}")]
+    NodeError {
+        /// The failed node's [`name`](crate::Node::name).
+        node_name: String,
+        /// The node's position in its flow, or the element's position in a
+        /// batch.
+        node_index: usize,
+        /// The error the node returned.
+        #[source]
+        source: Box<FlowError>,
+        /// The start of the node's input as JSON, cut after 200 bytes.
+        input_snippet: String,
+    },
+
+    /// A flow definition has problems.
+    ///
+    /// [`FlowConfig::load`](crate::config::FlowConfig::load) returns this
+    /// with every problem it finds, each with its line and column, rather
+    /// than stopping at the first.
+    #[error("Invalid flow definition: {}", list_problems(.0))]
+    InvalidConfig(Vec<ConfigProblem>),
+
+    /// A value does not match its JSON Schema.
+    ///
+    /// A [`ValidateNode`](crate::schema::ValidateNode) returns this with
+    /// every violation in the value it checked, and
+    /// [`Flow::with_contracts`](crate::flow::Flow::with_contracts) with
+    /// every mismatch between the schemas of adjacent nodes.
+    #[error("Validation failed: {}", list_fields(.0))]
+    Validation(Vec<FieldError>),
+
+    /// Several branches of a flow failed.
+    ///
+    /// A [`ParallelFlow`](crate::ParallelFlow) set to
+    /// [`collect_errors`](crate::ParallelFlow::collect_errors) returns this
+    /// with the name and error of every failed branch, in branch order. A
+    /// [`Fallback`](crate::fallback::Fallback) or [`Race`](crate::race::Race)
+    /// returns it when every node it tried failed.
+    #[error("{} branch(es) failed: {}", .0.len(), list_errors(.0))]
+    Multiple(Vec<(String, FlowError)>),
+
     /// An unknown error occurred.
     ///
     /// This is a catch-all for unexpected errors.
     #[error("An unknown error occurred")]
     Unknown,
 }
+
+impl FlowError {
+    /// Wrap `self`, returned by `node_name` at `node_index`, in a
+    /// [`FlowError::NodeError`].
+    pub(crate) fn in_node(self, node_name: &str, node_index: usize, input_snippet: String) -> Self {
+        FlowError::NodeError {
+            node_name: node_name.to_string(),
+            node_index,
+            source: Box::new(self),
+            input_snippet,
+        }
+    }
+
+    /// The error that started a failure, looking through the
+    /// [`FlowError::NodeError`] layers added by flows.
+    pub fn root_cause(&self) -> &FlowError {
+        let mut error = self;
+        while let FlowError::NodeError { source, .. } = error {
+            error = source;
+        }
+        error
+    }
+
+    /// A short, stable name for the kind of the
+    /// [root cause](FlowError::root_cause), such as `"timeout"` or
+    /// `"rate_limited"`, for matching errors in configuration and logs.
+    ///
+    /// A [`FlowError::NodeError`] reports the kind of the error it wraps,
+    /// however deeply nested, so a node's error matches the same way inside
+    /// and outside a flow.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::FlowError;
+    ///
+    /// let error = FlowError::RateLimited("429 from provider".to_string());
+    /// assert_eq!(error.kind(), "rate_limited");
+    ///
+    /// let error = FlowError::NodeError {
+    ///     node_name: "Search".to_string(),
+    ///     node_index: 1,
+    ///     source: Box::new(error),
+    ///     input_snippet: String::new(),
+    /// };
+    /// assert_eq!(error.kind(), "rate_limited");
+    /// ```
+    pub fn kind(&self) -> &'static str {
+        match self.root_cause() {
+            FlowError::NodeFailed(_) => "node_failed",
+            FlowError::SerdeError(_) => "serde",
+            FlowError::Template(_) => "template",
+            FlowError::BudgetExceeded(_) => "budget_exceeded",
+            FlowError::Checkpoint(_) => "checkpoint",
+            FlowError::Timeout(_) => "timeout",
+            FlowError::Cancelled(_) => "cancelled",
+            FlowError::RateLimited(_) => "rate_limited",
+            FlowError::LoopLimit(_) => "loop_limit",
+            FlowError::NotFound(_) => "not_found",
+            FlowError::NodeError { .. } => unreachable!("root_cause looks through NodeError"),
+            FlowError::InvalidConfig(_) => "invalid_config",
+            FlowError::Validation(_) => "validation",
+            FlowError::Multiple(_) => "multiple",
+            FlowError::Unknown => "unknown",
+        }
+    }
+
+    /// The HTTP status that reports this error to a client.
+    ///
+    /// Errors caused by the request map to `4xx` codes, which clients should
+    /// not retry unchanged; timeouts and rate limits map to codes that tell
+    /// clients a retry may succeed; everything else is a `500`. A
+    /// `NodeError` maps like its [root cause](FlowError::root_cause), and
+    /// `Multiple` like its errors if they all map alike.
+    ///
+    /// Decoding and schema errors only blame the request when they are about
+    /// it: a `SerdeError` raised outside any node, or a `Validation` raised
+    /// outside any node or by the first node of a flow, which checks the
+    /// request body itself. Raised deeper in a flow, such as for a model
+    /// reply that is not valid JSON, they are server failures.
+    ///
+    /// | Error | Status |
+    /// |-------|--------|
+    /// | `SerdeError`, `Validation` about the request | `400 Bad Request` |
+    /// | `NotFound` | `404 Not Found` |
+    /// | `RateLimited` | `429 Too Many Requests` |
+    /// | `Timeout` | `504 Gateway Timeout` |
+    /// | `Cancelled` | `503 Service Unavailable` |
+    /// | any other | `500 Internal Server Error` |
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::http::StatusCode;
+    /// use axum::response::IntoResponse;
+    /// use rustyflow::FlowError;
+    ///
+    /// let error = FlowError::Timeout("node 'search' took longer than 5s".to_string());
+    /// assert_eq!(error.status_code(), StatusCode::GATEWAY_TIMEOUT);
+    ///
+    /// let response = FlowError::NotFound("Flow 'echo'".to_string()).into_response();
+    /// assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    ///
+    /// let error = FlowError::from(serde_json::from_str::<u32>("\"one\"").unwrap_err());
+    /// assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+    ///
+    /// // The same error from the third node of a flow is the server's fault
+    /// let error = FlowError::NodeError {
+    ///     node_name: "ParseReply".to_string(),
+    ///     node_index: 2,
+    ///     source: Box::new(error),
+    ///     input_snippet: String::new(),
+    /// };
+    /// assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    /// ```
+    pub fn status_code(&self) -> StatusCode {
+        match self.root_cause() {
+            FlowError::Multiple(errors) => {
+                let mut statuses = errors.iter().map(|(_, error)| error.status_code());
+                match statuses.next() {
+                    Some(first) if statuses.all(|status| status == first) => first,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                }
+            }
+            FlowError::SerdeError(_) if !matches!(self, FlowError::NodeError { .. }) => {
+                StatusCode::BAD_REQUEST
+            }
+            FlowError::Validation(_) if self.raised_by_first_node() => StatusCode::BAD_REQUEST,
+            FlowError::NotFound(_) => StatusCode::NOT_FOUND,
+            FlowError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            FlowError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            FlowError::Cancelled(_) => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Whether every [`FlowError::NodeError`] layer is at index 0, so the
+    /// error was raised outside any node or by a node given the flow's own
+    /// input.
+    fn raised_by_first_node(&self) -> bool {
+        let mut error = self;
+        while let FlowError::NodeError {
+            node_index, source, ..
+        } = error
+        {
+            if *node_index != 0 {
+                return false;
+            }
+            error = source;
+        }
+        true
+    }
+}
+
+/// Answers with [`FlowError::status_code`] and a `{"error": message}` body.
+impl IntoResponse for FlowError {
+    fn into_response(self) -> Response {
+        let body = json!({ "error": self.to_string() });
+        (self.status_code(), Json(body)).into_response()
+    }
+}
+
+/// Format the problems of a [`FlowError::InvalidConfig`] one after another.
+fn list_problems(problems: &[ConfigProblem]) -> String {
+    let problems: Vec<String> = problems.iter().map(ConfigProblem::to_string).collect();
+    problems.join("; ")
+}
+
+/// Format the violations of a [`FlowError::Validation`] one after another.
+fn list_fields(errors: &[FieldError]) -> String {
+    let errors: Vec<String> = errors.iter().map(FieldError::to_string).collect();
+    errors.join("; ")
+}
+
+/// Format the branches of a [`FlowError::Multiple`] as `name: error` pairs.
+fn list_errors(errors: &[(String, FlowError)]) -> String {
+    let errors: Vec<String> = errors
+        .iter()
+        .map(|(name, error)| format!("{name}: {error}"))
+        .collect();
+    errors.join("; ")
+}
+
+/// The start of `input` as JSON, for [`FlowError::NodeError`].
+///
+/// Serialization stops once the snippet is full, so large inputs cost no
+/// more than small ones.
+pub(crate) fn input_snippet(input: &Value) -> String {
+    struct Limited(Vec<u8>);
+
+    impl Write for Limited {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let room = SNIPPET_LEN + 1 - self.0.len();
+            if room == 0 {
+                return Err(std::io::ErrorKind::WriteZero.into());
+            }
+            let len = buf.len().min(room);
+            self.0.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut writer = Limited(Vec::with_capacity(SNIPPET_LEN + 1));
+    // A full writer stops serialization with an error; the bytes so far are
+    // the snippet
+    let _ = serde_json::to_writer(&mut writer, input);
+    let mut bytes = writer.0;
+    let truncated = bytes.len() > SNIPPET_LEN;
+    bytes.truncate(SNIPPET_LEN);
+    let valid = match std::str::from_utf8(&bytes) {
+        Ok(text) => text.len(),
+        Err(e) => e.valid_up_to(),
+    };
+    bytes.truncate(valid);
+    let mut snippet = String::from_utf8(bytes).unwrap_or_default();
+    if truncated {
+        snippet.push_str("...");
+    }
+    snippet
+}
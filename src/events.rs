@@ -0,0 +1,123 @@
+//! Live execution events for incremental feedback.
+//!
+//! [`Flow::execute_with_events`](crate::flow::Flow::execute_with_events)
+//! sends an [`ExecutionEvent`] when each node starts and ends, and one with
+//! the final result or error. Nodes that produce text incrementally, such as
+//! streaming model calls, can add [`ExecutionEvent::TokenDelta`] events for
+//! their step with [`emit_token`], and nodes that notice trouble short of
+//! failing, such as a [`Deadline`](crate::deadline::Deadline) past its soft
+//! timeout, add [`ExecutionEvent::Warning`] events with [`emit_warning`].
+//! Batches add an [`ExecutionEvent::Progress`] event as each element
+//! finishes.
+//! Outside an execution with events these do nothing, and what is emitted
+//! from work spawned onto other tasks is not attributed to the step.
+//!
+//! Events serialize with a `type` tag in snake case, which is what the
+//! server's `POST /flows/:name/stream` endpoint sends as Server-Sent Events.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Where the events of one execution are sent.
+pub type EventSender = UnboundedSender<ExecutionEvent>;
+
+/// Something that happened during a flow execution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExecutionEvent {
+    /// A node was called.
+    NodeStart {
+        /// Position of the node in the flow.
+        index: usize,
+        /// The node's [`Node::name`](crate::node::Node::name).
+        name: String,
+    },
+    /// A node emitted a piece of its output with [`emit_token`].
+    TokenDelta {
+        /// Position of the node in the flow.
+        index: usize,
+        /// The emitted text.
+        text: String,
+    },
+    /// A node reported a problem that did not fail it, with
+    /// [`emit_warning`].
+    Warning {
+        /// Position of the node in the flow.
+        index: usize,
+        /// What went wrong.
+        message: String,
+    },
+    /// A [`Batch`](crate::Batch) or [`BatchFlow`](crate::BatchFlow) finished
+    /// an element.
+    Progress {
+        /// Position of the node in the flow.
+        index: usize,
+        /// Elements finished so far, including failed ones.
+        completed: usize,
+        /// Elements that failed so far.
+        failed: usize,
+        /// The number of elements, if known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        total: Option<usize>,
+        /// Estimated milliseconds until every element has finished.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        eta_ms: Option<u64>,
+    },
+    /// A node returned.
+    NodeEnd {
+        /// Position of the node in the flow.
+        index: usize,
+        /// The node's [`Node::name`](crate::node::Node::name).
+        name: String,
+        /// Wall-clock time spent in the node in milliseconds.
+        duration_ms: u64,
+        /// The error message, if the node failed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// The flow finished successfully. Always the last event of a run.
+    FinalResult {
+        /// The flow's output.
+        output: Value,
+    },
+    /// The flow failed. Always the last event of a run.
+    Error {
+        /// The error message.
+        error: String,
+    },
+}
+
+tokio::task_local! {
+    static CURRENT: (EventSender, usize);
+}
+
+/// Emit a piece of the current node's output as a
+/// [`ExecutionEvent::TokenDelta`].
+pub fn emit_token(text: impl Into<String>) {
+    emit(|index| ExecutionEvent::TokenDelta {
+        index,
+        text: text.into(),
+    });
+}
+
+/// Emit a warning about the current node as an [`ExecutionEvent::Warning`].
+pub fn emit_warning(message: impl Into<String>) {
+    emit(|index| ExecutionEvent::Warning {
+        index,
+        message: message.into(),
+    });
+}
+
+/// Send the event `make` builds from the current step's index.
+pub(crate) fn emit(make: impl FnOnce(usize) -> ExecutionEvent) {
+    let _ = CURRENT.try_with(|(sender, index)| {
+        let _ = sender.send(make(*index));
+    });
+}
+
+/// Run `future` as the step at `index`, sending what it emits to `sender`.
+pub(crate) async fn scope<F: Future>(sender: &EventSender, index: usize, future: F) -> F::Output {
+    CURRENT.scope((sender.clone(), index), future).await
+}
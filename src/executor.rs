@@ -0,0 +1,500 @@
+//! Extension points for how and when node calls run.
+//!
+//! Every node call made by a [`Flow`](crate::Flow),
+//! [`ParallelFlow`](crate::ParallelFlow),
+//! [`GraphFlow`](crate::graph::GraphFlow) or [`Batch`](crate::Batch) goes
+//! through two hooks:
+//!
+//! 1. A [`FlowScheduler`] admits the call, and may hold it back until
+//!    capacity is free, for example to cap concurrent calls to a local model
+//!    server or to let long-waiting work of low priority go first.
+//! 2. A [`NodeExecutor`] performs the admitted call, for example to pin it
+//!    to a GPU, run it on a dedicated thread pool, or forward it to a remote
+//!    worker.
+//!
+//! Both default to calling the node directly. Hooks set on a flow with
+//! `with_executor` or `with_scheduler` also apply to the nodes of flows and
+//! batches nested inside it, unless those set their own. Tracing spans and
+//! metrics are recorded around the hooks, so custom executors keep the
+//! built-in instrumentation.
+//!
+//! Around the executor, a chain of [`Middleware`] added with
+//! `with_middleware` sees every call's input, output and error, for
+//! logging, authorization, input scrubbing or metrics across all nodes.
+//! Nested flows run their own middleware inside that of the flows
+//! enclosing them.
+
+use crate::error::FlowError;
+use crate::node::Node;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::any::Any;
+use std::future::Future;
+use std::sync::Arc;
+
+/// One node call, as seen by the hooks.
+#[derive(Clone, Copy)]
+pub struct NodeCall<'a> {
+    /// The node to call.
+    pub node: &'a dyn Node,
+    /// The node's position in its flow, or the element's position in a
+    /// batch.
+    pub index: usize,
+}
+
+/// Decides when node calls may start.
+///
+/// # Example
+///
+/// A scheduler that lets one call run at a time:
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::executor::{Admission, FlowScheduler, NodeCall};
+/// use rustyflow::{FlowError, Node, ParallelFlow};
+/// use serde_json::{json, Value};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use tokio::sync::Semaphore;
+///
+/// struct OneAtATime(Arc<Semaphore>);
+///
+/// #[async_trait]
+/// impl FlowScheduler for OneAtATime {
+///     async fn admit(&self, _call: &NodeCall<'_>) -> Result<Admission, FlowError> {
+///         let permit = Arc::clone(&self.0).acquire_owned().await.unwrap();
+///         Ok(Admission::holding(permit))
+///     }
+/// }
+///
+/// static RUNNING: AtomicUsize = AtomicUsize::new(0);
+/// static PEAK: AtomicUsize = AtomicUsize::new(0);
+///
+/// struct Inference;
+///
+/// #[async_trait]
+/// impl Node for Inference {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+///         PEAK.fetch_max(running, Ordering::SeqCst);
+///         tokio::time::sleep(Duration::from_millis(10)).await;
+///         RUNNING.fetch_sub(1, Ordering::SeqCst);
+///         Ok(input)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let flow = ParallelFlow::new(vec![Box::new(Inference), Box::new(Inference), Box::new(Inference)])
+///     .with_scheduler(OneAtATime(Arc::new(Semaphore::new(1))));
+///
+/// flow.execute(json!(1)).await?;
+/// assert_eq!(PEAK.load(Ordering::SeqCst), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait FlowScheduler: Send + Sync {
+    /// Wait until `call` may run.
+    ///
+    /// # Returns
+    ///
+    /// An [`Admission`] that is held while the node runs. Dropping it, once
+    /// the call has finished, releases whatever capacity it holds.
+    ///
+    /// # Errors
+    ///
+    /// An error fails the node call without running it.
+    async fn admit(&self, call: &NodeCall<'_>) -> Result<Admission, FlowError>;
+}
+
+#[async_trait]
+impl<S: FlowScheduler + ?Sized> FlowScheduler for Arc<S> {
+    async fn admit(&self, call: &NodeCall<'_>) -> Result<Admission, FlowError> {
+        (**self).admit(call).await
+    }
+}
+
+/// Performs node calls.
+///
+/// # Example
+///
+/// An executor that records every call, including those of a nested batch:
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::executor::{NodeCall, NodeExecutor};
+/// use rustyflow::{Batch, Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::{Arc, Mutex};
+///
+/// struct Recording(Arc<Mutex<Vec<String>>>);
+///
+/// #[async_trait]
+/// impl NodeExecutor for Recording {
+///     async fn execute(&self, call: &NodeCall<'_>, input: Value) -> Result<Value, FlowError> {
+///         let name = format!("{}#{}", call.node.name(), call.index);
+///         self.0.lock().unwrap().push(name);
+///         call.node.call(input).await
+///     }
+/// }
+///
+/// struct Double;
+///
+/// #[async_trait]
+/// impl Node for Double {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(input.as_i64().unwrap_or(0) * 2))
+///     }
+///
+///     fn name(&self) -> &str {
+///         "Double"
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let calls = Arc::new(Mutex::new(Vec::new()));
+/// let flow = Flow::new(vec![Box::new(Batch::new(Double))])
+///     .with_executor(Recording(Arc::clone(&calls)));
+///
+/// assert_eq!(flow.execute(json!([1, 2])).await?, json!([2, 4]));
+///
+/// let calls = calls.lock().unwrap();
+/// assert_eq!(calls.len(), 3);
+/// assert!(calls.contains(&"Double#0".to_string()));
+/// assert!(calls.contains(&"Double#1".to_string()));
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait NodeExecutor: Send + Sync {
+    /// Run `call.node` with `input`.
+    ///
+    /// Implementations decide where and how the node runs, and must
+    /// eventually call [`Node::call`] or return an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns the node's error, or any error of the executor itself.
+    async fn execute(&self, call: &NodeCall<'_>, input: Value) -> Result<Value, FlowError>;
+}
+
+#[async_trait]
+impl<E: NodeExecutor + ?Sized> NodeExecutor for Arc<E> {
+    async fn execute(&self, call: &NodeCall<'_>, input: Value) -> Result<Value, FlowError> {
+        (**self).execute(call, input).await
+    }
+}
+
+/// Cross-cutting logic run around every node call of a flow.
+///
+/// Middleware added to a flow form a chain, the first added outermost:
+/// [`before`](Middleware::before) runs in the order they were added, and
+/// [`after`](Middleware::after) or [`on_error`](Middleware::on_error) in
+/// the reverse order, each seeing what the inner ones returned. A
+/// middleware whose `before` fails, or any before it, is not asked about
+/// the error; the ones outside it are.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::executor::{Middleware, NodeCall};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::{Arc, Mutex};
+///
+/// /// Keeps passwords away from every node.
+/// struct Scrub;
+///
+/// #[async_trait]
+/// impl Middleware for Scrub {
+///     async fn before(&self, _call: &NodeCall<'_>, mut input: Value) -> Result<Value, FlowError> {
+///         if let Some(fields) = input.as_object_mut() {
+///             fields.remove("password");
+///         }
+///         Ok(input)
+///     }
+/// }
+///
+/// /// Logs each call, and stands in a default for a failing lookup.
+/// struct Audit(Arc<Mutex<Vec<String>>>);
+///
+/// #[async_trait]
+/// impl Middleware for Audit {
+///     async fn after(&self, call: &NodeCall<'_>, output: Value) -> Result<Value, FlowError> {
+///         self.0.lock().unwrap().push(format!("{} ok", call.node.name()));
+///         Ok(output)
+///     }
+///
+///     async fn on_error(&self, call: &NodeCall<'_>, error: FlowError) -> Result<Value, FlowError> {
+///         self.0.lock().unwrap().push(format!("{} failed", call.node.name()));
+///         match call.node.name() {
+///             "Lookup" => Ok(json!({"plan": "free"})),
+///             _ => Err(error),
+///         }
+///     }
+/// }
+///
+/// struct Echo;
+///
+/// #[async_trait]
+/// impl Node for Echo {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(input)
+///     }
+/// }
+///
+/// struct Lookup;
+///
+/// #[async_trait]
+/// impl Node for Lookup {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         Err(FlowError::NodeFailed("billing service unavailable".to_string()))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let log = Arc::new(Mutex::new(Vec::new()));
+/// let flow = Flow::new(vec![Box::new(Echo)])
+///     .with_middleware(Audit(Arc::clone(&log)))
+///     .with_middleware(Scrub);
+/// let output = flow.execute(json!({"user": "ada", "password": "hunter2"})).await?;
+/// assert_eq!(output, json!({"user": "ada"}));
+///
+/// let flow = Flow::new(vec![Box::new(Lookup)]).with_middleware(Audit(Arc::clone(&log)));
+/// assert_eq!(flow.execute(json!({})).await?, json!({"plan": "free"}));
+/// assert_eq!(*log.lock().unwrap(), ["Echo ok", "Lookup failed"]);
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Inspect or rewrite a node's input before the call. The default
+    /// passes it on unchanged.
+    ///
+    /// # Errors
+    ///
+    /// An error, such as a failed authorization, fails the call without
+    /// running the node.
+    async fn before(&self, call: &NodeCall<'_>, input: Value) -> Result<Value, FlowError> {
+        let _ = call;
+        Ok(input)
+    }
+
+    /// Inspect or rewrite a node's output after a successful call. The
+    /// default passes it on unchanged.
+    ///
+    /// # Errors
+    ///
+    /// An error fails the call.
+    async fn after(&self, call: &NodeCall<'_>, output: Value) -> Result<Value, FlowError> {
+        let _ = call;
+        Ok(output)
+    }
+
+    /// Observe a failed call, and optionally recover from it. The default
+    /// returns the error unchanged.
+    ///
+    /// # Returns
+    ///
+    /// An output that replaces the failure, or the error to pass on.
+    async fn on_error(&self, call: &NodeCall<'_>, error: FlowError) -> Result<Value, FlowError> {
+        let _ = call;
+        Err(error)
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + ?Sized> Middleware for Arc<M> {
+    async fn before(&self, call: &NodeCall<'_>, input: Value) -> Result<Value, FlowError> {
+        (**self).before(call, input).await
+    }
+
+    async fn after(&self, call: &NodeCall<'_>, output: Value) -> Result<Value, FlowError> {
+        (**self).after(call, output).await
+    }
+
+    async fn on_error(&self, call: &NodeCall<'_>, error: FlowError) -> Result<Value, FlowError> {
+        (**self).on_error(call, error).await
+    }
+}
+
+/// Permission for one node call to run, returned by
+/// [`FlowScheduler::admit`].
+pub struct Admission {
+    _guard: Option<Box<dyn Any + Send>>,
+}
+
+impl Admission {
+    /// Admit a call without holding anything.
+    pub fn granted() -> Self {
+        Self { _guard: None }
+    }
+
+    /// Admit a call, keeping `guard` alive until it finishes, such as a
+    /// semaphore permit.
+    pub fn holding(guard: impl Send + 'static) -> Self {
+        Self {
+            _guard: Some(Box::new(guard)),
+        }
+    }
+}
+
+/// The hooks configured on a flow.
+#[derive(Clone, Default)]
+pub(crate) struct Hooks {
+    executor: Option<Arc<dyn NodeExecutor>>,
+    scheduler: Option<Arc<dyn FlowScheduler>>,
+    middleware: Arc<Vec<Arc<dyn Middleware>>>,
+}
+
+tokio::task_local! {
+    static CURRENT: Hooks;
+}
+
+impl Hooks {
+    pub(crate) fn set_executor(&mut self, executor: impl NodeExecutor + 'static) {
+        self.executor = Some(Arc::new(executor));
+    }
+
+    pub(crate) fn set_scheduler(&mut self, scheduler: impl FlowScheduler + 'static) {
+        self.scheduler = Some(Arc::new(scheduler));
+    }
+
+    pub(crate) fn add_middleware(&mut self, middleware: impl Middleware + 'static) {
+        Arc::make_mut(&mut self.middleware).push(Arc::new(middleware));
+    }
+
+    /// The hooks in effect for the current task, to carry into tasks it
+    /// spawns.
+    pub(crate) fn current() -> Hooks {
+        CURRENT.try_with(Hooks::clone).unwrap_or_default()
+    }
+
+    /// Run `future` with these hooks, inheriting any the enclosing flow set
+    /// and these do not, and inside the enclosing flow's middleware.
+    pub(crate) async fn scope<F: Future>(&self, future: F) -> F::Output {
+        let outer = Hooks::current();
+        let middleware = if outer.middleware.is_empty() {
+            Arc::clone(&self.middleware)
+        } else if self.middleware.is_empty() {
+            outer.middleware
+        } else {
+            Arc::new(
+                outer
+                    .middleware
+                    .iter()
+                    .chain(self.middleware.iter())
+                    .cloned()
+                    .collect(),
+            )
+        };
+        let hooks = Hooks {
+            executor: self.executor.clone().or(outer.executor),
+            scheduler: self.scheduler.clone().or(outer.scheduler),
+            middleware,
+        };
+        CURRENT.scope(hooks, future).await
+    }
+}
+
+/// Admit `call` with the current scheduler.
+pub(crate) async fn admit(call: &NodeCall<'_>) -> Result<Admission, FlowError> {
+    match CURRENT
+        .try_with(|hooks| hooks.scheduler.clone())
+        .ok()
+        .flatten()
+    {
+        Some(scheduler) => scheduler.admit(call).await,
+        None => Ok(Admission::granted()),
+    }
+}
+
+/// The input of a node call, owned or shared with other calls.
+pub(crate) enum NodeInput {
+    Owned(Value),
+    Shared(Arc<Value>),
+}
+
+impl NodeInput {
+    pub(crate) fn value(&self) -> &Value {
+        match self {
+            NodeInput::Owned(value) => value,
+            NodeInput::Shared(value) => value,
+        }
+    }
+}
+
+impl From<Value> for NodeInput {
+    fn from(value: Value) -> Self {
+        NodeInput::Owned(value)
+    }
+}
+
+impl From<Arc<Value>> for NodeInput {
+    fn from(value: Arc<Value>) -> Self {
+        NodeInput::Shared(value)
+    }
+}
+
+/// Run `call` with the current executor, inside the current middleware.
+///
+/// Custom executors and middleware take an owned input, so a shared input
+/// is only passed on without cloning when the node is called directly.
+pub(crate) async fn execute(call: &NodeCall<'_>, input: NodeInput) -> Result<Value, FlowError> {
+    let (executor, chain) = CURRENT
+        .try_with(|hooks| (hooks.executor.clone(), Arc::clone(&hooks.middleware)))
+        .unwrap_or_default();
+    if chain.is_empty() {
+        return perform(executor, call, input).await;
+    }
+
+    let mut input = Ok(match input {
+        NodeInput::Owned(value) => value,
+        NodeInput::Shared(value) => Arc::unwrap_or_clone(value),
+    });
+    let mut entered = 0;
+    for middleware in chain.iter() {
+        input = middleware.before(call, input?).await;
+        if input.is_err() {
+            break;
+        }
+        entered += 1;
+    }
+    let mut result = match input {
+        Ok(input) => perform(executor, call, NodeInput::Owned(input)).await,
+        Err(e) => Err(e),
+    };
+    for middleware in chain[..entered].iter().rev() {
+        result = match result {
+            Ok(output) => middleware.after(call, output).await,
+            Err(e) => middleware.on_error(call, e).await,
+        };
+    }
+    result
+}
+
+/// Run `call` with `executor`, or call the node directly.
+async fn perform(
+    executor: Option<Arc<dyn NodeExecutor>>,
+    call: &NodeCall<'_>,
+    input: NodeInput,
+) -> Result<Value, FlowError> {
+    match executor {
+        Some(executor) => {
+            let input = match input {
+                NodeInput::Owned(value) => value,
+                NodeInput::Shared(value) => Arc::unwrap_or_clone(value),
+            };
+            executor.execute(call, input).await
+        }
+        None => match input {
+            NodeInput::Owned(value) => call.node.call(value).await,
+            NodeInput::Shared(value) => call.node.call_shared(value).await,
+        },
+    }
+}
@@ -0,0 +1,397 @@
+//! Side-by-side comparison of flow variants over a corpus.
+//!
+//! An [`Experiment`] runs several variants of a flow, such as the same
+//! pipeline with different prompts or models, over one set of
+//! [`Example`]s, concurrently. Every output is scored against the example's
+//! expected value with the experiment's [`Scorer`]s, and each run is traced
+//! for its latency and token usage, which a variant's prices turn into
+//! cost. The resulting [`ExperimentReport`] summarizes each variant, keeps
+//! every individual result for inspection, and renders as a Markdown table:
+//!
+//! ```text
+//! | Variant | exact_match | Errors | Mean latency (ms) | p95 latency (ms) | Tokens | Cost |
+//! |---------|-------------|--------|-------------------|------------------|--------|------|
+//! | terse   | 1.000       | 0      | 812               | 1040             | 5120   | 0.0031 |
+//! | verbose | 0.500       | 0      | 1930              | 2410             | 14200  | 0.0088 |
+//! ```
+
+use crate::error::FlowError;
+use crate::flow::Flow;
+use crate::llm::Usage;
+use crate::scorer::Scorer;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Runs in flight at once, unless a limit is set.
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// One input of the corpus, with the output it should produce.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Example {
+    /// The flow input.
+    pub input: Value,
+    /// The expected output, compared with each variant's output by the
+    /// scorers.
+    #[serde(default)]
+    pub expected: Value,
+}
+
+impl Example {
+    /// Create an example.
+    pub fn new(input: Value, expected: Value) -> Self {
+        Self { input, expected }
+    }
+}
+
+/// A named flow to compare, with the price of its token usage.
+pub struct Variant {
+    name: String,
+    flow: Flow,
+    input_cost_per_1k: f64,
+    output_cost_per_1k: f64,
+}
+
+impl Variant {
+    /// Create a variant whose usage costs nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the variant is reported under
+    /// * `flow` - The flow to run on each example
+    pub fn new(name: impl Into<String>, flow: Flow) -> Self {
+        Self {
+            name: name.into(),
+            flow,
+            input_cost_per_1k: 0.0,
+            output_cost_per_1k: 0.0,
+        }
+    }
+
+    /// Price the variant's token usage, per 1000 prompt and completion
+    /// tokens, as in [`ModelProfile::with_cost`](crate::selector::ModelProfile::with_cost).
+    pub fn with_cost(mut self, input_per_1k: f64, output_per_1k: f64) -> Self {
+        self.input_cost_per_1k = input_per_1k;
+        self.output_cost_per_1k = output_per_1k;
+        self
+    }
+
+    fn cost(&self, usage: Usage) -> f64 {
+        (usage.prompt_tokens as f64 * self.input_cost_per_1k
+            + usage.completion_tokens as f64 * self.output_cost_per_1k)
+            / 1000.0
+    }
+}
+
+/// The outcome of one variant on one example.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExampleResult {
+    /// The variant's name.
+    pub variant: String,
+    /// The example's position in the corpus.
+    pub example: usize,
+    /// The flow's output, if it succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+    /// The flow's error, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The score of each scorer, by name; failed runs score `0.0`.
+    pub scores: BTreeMap<String, f64>,
+    /// Wall-clock time of the run, in milliseconds.
+    pub latency_ms: u64,
+    /// Token usage recorded by the flow's nodes.
+    pub usage: Usage,
+    /// The cost of that usage at the variant's prices.
+    pub cost: f64,
+}
+
+/// The aggregate results of one variant.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariantSummary {
+    /// The variant's name.
+    pub name: String,
+    /// The number of examples run.
+    pub examples: usize,
+    /// The number of runs that failed.
+    pub errors: usize,
+    /// The mean score of each scorer over all examples, by name.
+    pub scores: BTreeMap<String, f64>,
+    /// The mean latency, in milliseconds.
+    pub mean_latency_ms: u64,
+    /// The 95th percentile latency, in milliseconds.
+    pub p95_latency_ms: u64,
+    /// Token usage summed over all examples.
+    pub usage: Usage,
+    /// The cost summed over all examples.
+    pub cost: f64,
+}
+
+/// The results of an [`Experiment`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExperimentReport {
+    /// One summary per variant, in the order they were added.
+    pub variants: Vec<VariantSummary>,
+    /// Every run, by variant and then example.
+    pub results: Vec<ExampleResult>,
+}
+
+impl ExperimentReport {
+    /// The summary of the variant with the given name.
+    pub fn variant(&self, name: &str) -> Option<&VariantSummary> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    /// The variant with the highest mean score from the named scorer.
+    pub fn best(&self, scorer: &str) -> Option<&VariantSummary> {
+        self.variants
+            .iter()
+            .filter(|variant| variant.scores.contains_key(scorer))
+            .max_by(|a, b| a.scores[scorer].total_cmp(&b.scores[scorer]))
+    }
+
+    /// Render the summaries as a Markdown table, one row per variant.
+    pub fn to_markdown(&self) -> String {
+        let scorers: Vec<&String> = self
+            .variants
+            .first()
+            .map(|variant| variant.scores.keys().collect())
+            .unwrap_or_default();
+        let mut header = String::from("| Variant |");
+        let mut rule = String::from("|---------|");
+        for scorer in &scorers {
+            header.push_str(&format!(" {scorer} |"));
+            rule.push_str(&format!("{}|", "-".repeat(scorer.len() + 2)));
+        }
+        header.push_str(" Errors | Mean latency (ms) | p95 latency (ms) | Tokens | Cost |\n");
+        rule.push_str("--------|-------------------|------------------|--------|------|\n");
+
+        let mut table = header + &rule;
+        for variant in &self.variants {
+            table.push_str(&format!("| {} |", variant.name));
+            for scorer in &scorers {
+                let score = variant.scores.get(*scorer).copied().unwrap_or_default();
+                table.push_str(&format!(" {score:.3} |"));
+            }
+            table.push_str(&format!(
+                " {} | {} | {} | {} | {:.4} |\n",
+                variant.errors,
+                variant.mean_latency_ms,
+                variant.p95_latency_ms,
+                variant.usage.prompt_tokens + variant.usage.completion_tokens,
+                variant.cost
+            ));
+        }
+        table
+    }
+}
+
+/// Runs flow variants over a corpus and compares them.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::experiment::{Example, Experiment, Variant};
+/// use rustyflow::llm::Usage;
+/// use rustyflow::report::record_usage;
+/// use rustyflow::scorer::ExactMatch;
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// /// Stands in for a sentiment prompt; the verbose one hedges.
+/// struct Classify {
+///     verbose: bool,
+/// }
+///
+/// #[async_trait]
+/// impl Node for Classify {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let text = input.as_str().unwrap_or("");
+///         let tokens = if self.verbose { 400 } else { 100 };
+///         record_usage(Usage { prompt_tokens: tokens, completion_tokens: 5 });
+///         let label = match (text.contains("love"), self.verbose) {
+///             (true, _) => "positive",
+///             (false, true) => "neutral",
+///             (false, false) => "negative",
+///         };
+///         Ok(json!(label))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let corpus = vec![
+///     Example::new(json!("I love it"), json!("positive")),
+///     Example::new(json!("Broken on arrival"), json!("negative")),
+/// ];
+/// let report = Experiment::new(corpus)
+///     .with_variant(
+///         Variant::new("terse", Flow::new(vec![Box::new(Classify { verbose: false })]))
+///             .with_cost(0.5, 1.5),
+///     )
+///     .with_variant(
+///         Variant::new("verbose", Flow::new(vec![Box::new(Classify { verbose: true })]))
+///             .with_cost(0.5, 1.5),
+///     )
+///     .with_scorer(Box::new(ExactMatch::new()))
+///     .run()
+///     .await?;
+///
+/// assert_eq!(report.variant("terse").unwrap().scores["exact_match"], 1.0);
+/// assert_eq!(report.variant("verbose").unwrap().scores["exact_match"], 0.5);
+/// assert_eq!(report.best("exact_match").unwrap().name, "terse");
+/// assert!(report.variant("terse").unwrap().cost < report.variant("verbose").unwrap().cost);
+/// assert!(report.to_markdown().contains("| terse | 1.000 |"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Experiment {
+    examples: Vec<Example>,
+    variants: Vec<Variant>,
+    scorers: Vec<Box<dyn Scorer>>,
+    max_concurrency: usize,
+}
+
+impl Experiment {
+    /// Create an experiment over `examples`, with no variants or scorers.
+    pub fn new(examples: Vec<Example>) -> Self {
+        Self {
+            examples,
+            variants: Vec::new(),
+            scorers: Vec::new(),
+            max_concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Add a variant to compare.
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    /// Score every output with `scorer`.
+    pub fn with_scorer(mut self, scorer: Box<dyn Scorer>) -> Self {
+        self.scorers.push(scorer);
+        self
+    }
+
+    /// Run at most `max` flows at a time, 8 by default, at least 1.
+    pub fn with_max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = max.max(1);
+        self
+    }
+
+    /// Run every variant on every example and summarize the results.
+    ///
+    /// Failed runs are reported, not returned as errors: they count towards
+    /// the variant's errors and score `0.0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if two variants share a name, or the
+    /// first error of a scorer.
+    pub async fn run(&self) -> Result<ExperimentReport, FlowError> {
+        for (index, variant) in self.variants.iter().enumerate() {
+            if self.variants[..index]
+                .iter()
+                .any(|other| other.name == variant.name)
+            {
+                return Err(FlowError::NodeFailed(format!(
+                    "Duplicate experiment variant '{}'",
+                    variant.name
+                )));
+            }
+        }
+
+        let runs = self.variants.iter().flat_map(|variant| {
+            self.examples
+                .iter()
+                .enumerate()
+                .map(move |(index, example)| self.run_one(variant, index, example))
+        });
+        let results: Vec<ExampleResult> = stream::iter(runs)
+            .buffered(self.max_concurrency)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let variants = self
+            .variants
+            .iter()
+            .map(|variant| {
+                let runs: Vec<&ExampleResult> = results
+                    .iter()
+                    .filter(|result| result.variant == variant.name)
+                    .collect();
+                self.summarize(&variant.name, &runs)
+            })
+            .collect();
+        Ok(ExperimentReport { variants, results })
+    }
+
+    async fn run_one(
+        &self,
+        variant: &Variant,
+        index: usize,
+        example: &Example,
+    ) -> Result<ExampleResult, FlowError> {
+        let (result, report) = variant.flow.execute_traced(example.input.clone()).await;
+        let usage = report.total_usage();
+        let mut scores = BTreeMap::new();
+        for scorer in &self.scorers {
+            let score = match &result {
+                Ok(output) => scorer.score(output, &example.expected).await?,
+                Err(_) => 0.0,
+            };
+            scores.insert(scorer.name().to_string(), score);
+        }
+        let (output, error) = match result {
+            Ok(output) => (Some(output), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Ok(ExampleResult {
+            variant: variant.name.clone(),
+            example: index,
+            output,
+            error,
+            scores,
+            latency_ms: report.duration.as_millis() as u64,
+            usage,
+            cost: variant.cost(usage),
+        })
+    }
+
+    fn summarize(&self, name: &str, runs: &[&ExampleResult]) -> VariantSummary {
+        let count = runs.len().max(1);
+        let scores = self
+            .scorers
+            .iter()
+            .map(|scorer| {
+                let total: f64 = runs.iter().map(|run| run.scores[scorer.name()]).sum();
+                (scorer.name().to_string(), total / count as f64)
+            })
+            .collect();
+        let mut latencies: Vec<u64> = runs.iter().map(|run| run.latency_ms).collect();
+        latencies.sort_unstable();
+        let p95 = latencies
+            .get((latencies.len() * 95).div_ceil(100).saturating_sub(1))
+            .copied()
+            .unwrap_or_default();
+        VariantSummary {
+            name: name.to_string(),
+            examples: runs.len(),
+            errors: runs.iter().filter(|run| run.error.is_some()).count(),
+            scores,
+            mean_latency_ms: latencies.iter().sum::<u64>() / count as u64,
+            p95_latency_ms: p95,
+            usage: runs.iter().fold(Usage::default(), |total, run| Usage {
+                prompt_tokens: total.prompt_tokens + run.usage.prompt_tokens,
+                completion_tokens: total.completion_tokens + run.usage.completion_tokens,
+            }),
+            cost: runs.iter().map(|run| run.cost).sum(),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Search-based exploration over candidate continuations.
+//!
+//! This module provides [`BeamSearch`], a tree-of-thoughts style construct
+//! that expands several candidate next steps at each depth, scores them with
+//! an evaluator node, and keeps only the most promising trajectories.
+
+use crate::error::FlowError;
+use crate::node::Node;
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde_json::{json, Value};
+
+#[derive(Clone)]
+struct Beam {
+    trajectory: Vec<Value>,
+    scores: Vec<f64>,
+    finished: bool,
+}
+
+impl Beam {
+    fn score(&self) -> f64 {
+        self.scores.last().copied().unwrap_or(f64::NEG_INFINITY)
+    }
+}
+
+/// Read a score from an evaluator output (a number or `{"score": n}`).
+fn score_from(value: &Value) -> Result<f64, FlowError> {
+    value
+        .as_f64()
+        .or_else(|| value["score"].as_f64())
+        .ok_or_else(|| {
+            FlowError::NodeFailed(format!(
+                "Evaluator must return a number or an object with a 'score' field, got {value}"
+            ))
+        })
+}
+
+/// A node that explores candidate reasoning paths with beam search.
+///
+/// At each step the `expander` node receives `{"input": ..., "trajectory": [...]}`
+/// for every live beam and returns a JSON array of candidate next steps. The
+/// `evaluator` node receives the same shape with the candidate appended to the
+/// trajectory and returns a score (a number or `{"score": n}`, higher is
+/// better). The top `beam_width` trajectories survive to the next step.
+///
+/// A beam whose expansion is empty is considered finished and keeps competing
+/// on its last score. The search stops after `max_depth` steps or once every
+/// beam has finished.
+///
+/// The output is `{"trajectory": [...], "score": n, "scores": [...]}` for the
+/// best trajectory, where `scores` is the evaluator score after each step.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::explore::BeamSearch;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// // Proposes adding 1, 2 or 3 to the running total
+/// struct Propose;
+///
+/// #[async_trait]
+/// impl Node for Propose {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         Ok(json!([1, 2, 3]))
+///     }
+/// }
+///
+/// // Prefers totals close to 5
+/// struct CloseToFive;
+///
+/// #[async_trait]
+/// impl Node for CloseToFive {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let total: i64 = input["trajectory"].as_array().unwrap().iter().filter_map(Value::as_i64).sum();
+///         Ok(json!(-(5 - total).abs()))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let search = BeamSearch::new(Box::new(Propose), Box::new(CloseToFive))
+///     .with_beam_width(2)
+///     .with_max_depth(2);
+/// let result = search.call(json!({})).await?;
+/// assert_eq!(result["score"], 0.0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct BeamSearch {
+    expander: Box<dyn Node>,
+    evaluator: Box<dyn Node>,
+    beam_width: usize,
+    max_depth: usize,
+}
+
+impl BeamSearch {
+    /// Create a new beam search with a beam width of 3 and a depth of 3.
+    ///
+    /// # Arguments
+    ///
+    /// * `expander` - Node that proposes candidate next steps
+    /// * `evaluator` - Node that scores a partial trajectory
+    pub fn new(expander: Box<dyn Node>, evaluator: Box<dyn Node>) -> Self {
+        Self {
+            expander,
+            evaluator,
+            beam_width: 3,
+            max_depth: 3,
+        }
+    }
+
+    /// Set how many trajectories are kept after each step.
+    pub fn with_beam_width(mut self, beam_width: usize) -> Self {
+        self.beam_width = beam_width.max(1);
+        self
+    }
+
+    /// Set the maximum number of expansion steps.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    async fn expand(&self, input: &Value, beam: &Beam) -> Result<Vec<Value>, FlowError> {
+        let output = self
+            .expander
+            .call(json!({ "input": input, "trajectory": beam.trajectory }))
+            .await?;
+        match output {
+            Value::Array(candidates) => Ok(candidates),
+            other => Err(FlowError::NodeFailed(format!(
+                "Expander must return a JSON array of candidates, got {other}"
+            ))),
+        }
+    }
+
+    async fn evaluate(&self, input: &Value, trajectory: &[Value]) -> Result<f64, FlowError> {
+        let output = self
+            .evaluator
+            .call(json!({ "input": input, "trajectory": trajectory }))
+            .await?;
+        score_from(&output)
+    }
+}
+
+#[async_trait]
+impl Node for BeamSearch {
+    /// Run the beam search from the given input.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the expander does not return an
+    /// array or the evaluator does not return a score, or propagates any
+    /// error from either node.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let mut beams = vec![Beam {
+            trajectory: Vec::new(),
+            scores: Vec::new(),
+            finished: false,
+        }];
+
+        for _ in 0..self.max_depth {
+            // Expand every live beam concurrently
+            let live: Vec<&Beam> = beams.iter().filter(|b| !b.finished).collect();
+            if live.is_empty() {
+                break;
+            }
+            let expansions = join_all(live.iter().map(|beam| self.expand(&input, beam))).await;
+
+            let mut candidates: Vec<Beam> = beams.iter().filter(|b| b.finished).cloned().collect();
+            let mut pending = Vec::new();
+            for (beam, expansion) in live.into_iter().zip(expansions) {
+                let expansion = expansion?;
+                if expansion.is_empty() {
+                    candidates.push(Beam {
+                        finished: true,
+                        ..beam.clone()
+                    });
+                    continue;
+                }
+                for step in expansion {
+                    let mut trajectory = beam.trajectory.clone();
+                    trajectory.push(step);
+                    pending.push((trajectory, beam.scores.clone()));
+                }
+            }
+
+            // Score all new candidates concurrently
+            let scores = join_all(
+                pending
+                    .iter()
+                    .map(|(trajectory, _)| self.evaluate(&input, trajectory)),
+            )
+            .await;
+            for ((trajectory, mut trace), score) in pending.into_iter().zip(scores) {
+                trace.push(score?);
+                candidates.push(Beam {
+                    trajectory,
+                    scores: trace,
+                    finished: false,
+                });
+            }
+
+            candidates.sort_by(|a, b| b.score().total_cmp(&a.score()));
+            candidates.truncate(self.beam_width);
+            beams = candidates;
+        }
+
+        // Beams are kept sorted best-first after every step
+        let best = beams
+            .into_iter()
+            .next()
+            .expect("beam search always keeps at least one beam");
+        let score = best.scores.last().copied();
+
+        Ok(json!({
+            "trajectory": best.trajectory,
+            "score": score,
+            "scores": best.scores,
+        }))
+    }
+}
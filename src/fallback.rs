@@ -0,0 +1,152 @@
+//! Falling back to alternative nodes when one fails.
+//!
+//! This module provides [`Fallback`], which calls a primary node and, if it
+//! fails, tries alternates in order: a large model, then a smaller one, then
+//! a cached answer. An error filter limits fallbacks to failures worth
+//! working around, such as timeouts and rate limits, so bugs like invalid
+//! input still surface at once.
+
+use crate::error::FlowError;
+use crate::node::{HealthCheck, Node, SchemaProvider};
+use async_trait::async_trait;
+use serde_json::Value;
+
+type ErrorFilter = dyn Fn(&FlowError) -> bool + Send + Sync;
+
+/// A node that tries a primary node, then each alternate in turn, until one
+/// succeeds.
+///
+/// Every attempt receives the same input. If the last attempt fails, or a
+/// failure does not pass the [error filter](Fallback::with_error_filter),
+/// that error is returned as is when it was the only attempt, and otherwise
+/// a [`FlowError::Multiple`] holds the name and error of every attempt.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::fallback::Fallback;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// enum Model {
+///     Limited,
+///     Broken,
+///     Cached,
+/// }
+///
+/// #[async_trait]
+/// impl Node for Model {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         match self {
+///             Model::Limited => Err(FlowError::RateLimited("429".to_string())),
+///             Model::Broken => Err(FlowError::NodeFailed("invalid prompt".to_string())),
+///             Model::Cached => Ok(json!("cached answer")),
+///         }
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let rate_limited = |error: &FlowError| error.kind() == "rate_limited";
+///
+/// // GPT-4, then GPT-3.5, then a cached answer
+/// let fallback = Fallback::new(Box::new(Model::Limited))
+///     .with_fallback(Box::new(Model::Limited))
+///     .with_fallback(Box::new(Model::Cached))
+///     .with_error_filter(rate_limited);
+/// assert_eq!(fallback.call(json!({})).await?, json!("cached answer"));
+///
+/// // Other failures are not worked around
+/// let broken = Fallback::new(Box::new(Model::Broken))
+///     .with_fallback(Box::new(Model::Cached))
+///     .with_error_filter(rate_limited);
+/// assert_eq!(broken.call(json!({})).await.unwrap_err().kind(), "node_failed");
+/// # Ok(())
+/// # }
+/// ```
+pub struct Fallback {
+    nodes: Vec<Box<dyn Node>>,
+    filter: Option<Box<ErrorFilter>>,
+}
+
+impl Fallback {
+    /// Create a fallback chain that so far only calls `primary`.
+    pub fn new(primary: Box<dyn Node>) -> Self {
+        Self {
+            nodes: vec![primary],
+            filter: None,
+        }
+    }
+
+    /// Try `node` if every node before it failed.
+    pub fn with_fallback(mut self, node: Box<dyn Node>) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Fall back only on errors for which `filter` returns `true`.
+    ///
+    /// Without a filter every error falls back. Errors of nested flows
+    /// arrive wrapped in [`FlowError::NodeError`]; match on
+    /// [`FlowError::kind`] or [`FlowError::root_cause`] to see through them.
+    pub fn with_error_filter(
+        mut self,
+        filter: impl Fn(&FlowError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    fn falls_back(&self, error: &FlowError) -> bool {
+        match &self.filter {
+            Some(filter) => filter(error),
+            None => true,
+        }
+    }
+}
+
+#[async_trait]
+impl Node for Fallback {
+    /// Call the nodes in order until one succeeds.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the only failed attempt, or
+    /// `FlowError::Multiple` with the errors of all attempts.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let mut errors = Vec::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let error = match node.call(input.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+            let last = index + 1 == self.nodes.len() || !self.falls_back(&error);
+            if !last {
+                tracing::warn!("Node '{}' failed, falling back: {error}", node.name());
+            }
+            errors.push((node.name().to_string(), error));
+            if last {
+                break;
+            }
+        }
+
+        if errors.len() == 1 {
+            let (_, error) = errors.remove(0);
+            return Err(error);
+        }
+        Err(FlowError::Multiple(errors))
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.nodes[0].input_schema()
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        self.nodes[0].schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        self.nodes[0].health_checkable()
+    }
+}
@@ -0,0 +1,293 @@
+//! Seed data for tests and demos.
+//!
+//! [`Fixtures`] hold known conversations and memories, loaded from JSON,
+//! JSON Lines or, with the `yaml` feature, YAML, and seed them into stores
+//! before a run: conversations become transcripts in a [`HistoryStore`],
+//! memories become embedded documents in a [`VectorStore`]. Multi-turn
+//! behavior can then be exercised from the same state every time.
+//!
+//! A fixture document lists both kinds:
+//!
+//! ```yaml
+//! conversations:
+//!   - id: refund-followup
+//!     flow: support
+//!     messages:
+//!       - {role: user, content: "My order 1042 arrived broken"}
+//!       - {role: assistant, content: "Sorry to hear that! I have opened a refund."}
+//! memories:
+//!   - id: pref-contact
+//!     text: "The customer prefers to be contacted by email"
+//!     metadata: {customer: 42}
+//! ```
+//!
+//! In JSON Lines, each line is one conversation (with `messages`) or one
+//! memory (with `text`).
+
+use crate::embeddings::Embedder;
+use crate::error::FlowError;
+use crate::history::{HistoryStore, RunRecord};
+use crate::llm::Message;
+use crate::vector_store::{Document, VectorStore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// The flow name recorded for conversations that do not name one.
+const DEFAULT_FLOW: &str = "fixture";
+
+/// A conversation to seed as a run transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConversationFixture {
+    /// The run id the conversation is recorded under.
+    pub id: String,
+    /// The flow the run is attributed to, `fixture` if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow: Option<String>,
+    /// The tenant the run is attributed to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// The messages of the conversation, in order.
+    pub messages: Vec<Message>,
+}
+
+/// A memory to seed as a vector store document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryFixture {
+    /// The document id.
+    pub id: String,
+    /// The text to embed and store.
+    pub text: String,
+    /// Metadata stored with the document.
+    #[serde(default)]
+    pub metadata: Value,
+}
+
+/// One line of a JSON Lines fixture file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Conversation(ConversationFixture),
+    Memory(MemoryFixture),
+}
+
+/// Conversations and memories to seed stores with.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::fixtures::Fixtures;
+/// use rustyflow::history::{HistoryStore, InMemoryHistoryStore};
+/// use rustyflow::FlowError;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let fixtures = Fixtures::from_jsonl(concat!(
+///     r#"{"id": "greeting", "flow": "support", "messages": ["#,
+///     r#"{"role": "user", "content": "Hi"}, {"role": "assistant", "content": "Hello!"}]}"#,
+///     "\n",
+///     r#"{"id": "pref", "text": "Prefers email", "metadata": {"customer": 42}}"#,
+/// ))?;
+/// assert_eq!(fixtures.conversations.len(), 1);
+/// assert_eq!(fixtures.memories.len(), 1);
+///
+/// let history = InMemoryHistoryStore::new();
+/// fixtures.seed_history(&history).await?;
+/// let runs = history.runs_between(0, u64::MAX).await?;
+/// assert_eq!(runs[0].transcript[1].content, "Hello!");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fixtures {
+    /// Conversations to record as run transcripts.
+    #[serde(default)]
+    pub conversations: Vec<ConversationFixture>,
+    /// Memories to store as embedded documents.
+    #[serde(default)]
+    pub memories: Vec<MemoryFixture>,
+}
+
+impl Fixtures {
+    /// Parse fixtures from a JSON document with `conversations` and
+    /// `memories` arrays.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if the JSON is not a valid fixture
+    /// document.
+    pub fn from_json(json: &str) -> Result<Self, FlowError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Parse fixtures from JSON Lines, one conversation or memory per line.
+    /// Blank lines are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` naming the first line that is neither
+    /// a conversation nor a memory.
+    pub fn from_jsonl(jsonl: &str) -> Result<Self, FlowError> {
+        let mut fixtures = Self::default();
+        for (number, line) in jsonl.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(Entry::Conversation(conversation)) => fixtures.conversations.push(conversation),
+                Ok(Entry::Memory(memory)) => fixtures.memories.push(memory),
+                Err(_) => {
+                    return Err(FlowError::NodeFailed(format!(
+                        "Fixture line {} is neither a conversation nor a memory",
+                        number + 1
+                    )))
+                }
+            }
+        }
+        Ok(fixtures)
+    }
+
+    /// Parse fixtures from a YAML document with `conversations` and
+    /// `memories` lists.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the YAML is not a valid fixture
+    /// document.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::fixtures::Fixtures;
+    /// use rustyflow::llm::Role;
+    ///
+    /// # fn main() -> Result<(), rustyflow::FlowError> {
+    /// let fixtures = Fixtures::from_yaml(
+    ///     "conversations:\n\
+    ///      \x20 - id: greeting\n\
+    ///      \x20   messages:\n\
+    ///      \x20     - {role: user, content: Hi}\n",
+    /// )?;
+    /// let greeting = fixtures.conversation("greeting").unwrap();
+    /// assert_eq!(greeting.messages[0].role, Role::User);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, FlowError> {
+        serde_yaml::from_str(yaml)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid YAML fixtures: {e}")))
+    }
+
+    /// Read fixtures from a file, parsed by its extension: `.jsonl` as JSON
+    /// Lines, `.yaml` or `.yml` as YAML, anything else as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the file cannot be read, is YAML
+    /// without the `yaml` feature, or is not valid, or `FlowError::SerdeError`
+    /// if a JSON file is not a valid fixture document.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FlowError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            FlowError::NodeFailed(format!("Cannot read fixture file {}: {e}", path.display()))
+        })?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl") => Self::from_jsonl(&text),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => Self::from_yaml(&text),
+            #[cfg(not(feature = "yaml"))]
+            Some("yaml" | "yml") => Err(FlowError::NodeFailed(format!(
+                "Reading {} needs the 'yaml' feature",
+                path.display()
+            ))),
+            _ => Self::from_json(&text),
+        }
+    }
+
+    /// Add the conversations and memories of `other`.
+    pub fn merge(mut self, other: Fixtures) -> Self {
+        self.conversations.extend(other.conversations);
+        self.memories.extend(other.memories);
+        self
+    }
+
+    /// The conversation with the given id.
+    pub fn conversation(&self, id: &str) -> Option<&ConversationFixture> {
+        self.conversations
+            .iter()
+            .find(|conversation| conversation.id == id)
+    }
+
+    /// Record each conversation in `store` as a run with its transcript.
+    ///
+    /// # Returns
+    ///
+    /// The number of conversations recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from [`HistoryStore::record`].
+    pub async fn seed_history(&self, store: &dyn HistoryStore) -> Result<usize, FlowError> {
+        for conversation in &self.conversations {
+            let flow = conversation.flow.as_deref().unwrap_or(DEFAULT_FLOW);
+            let mut run = RunRecord::new(conversation.id.clone(), flow)
+                .with_transcript(conversation.messages.clone());
+            if let Some(tenant) = &conversation.tenant {
+                run = run.with_tenant(tenant.clone());
+            }
+            store.record(&run).await?;
+        }
+        Ok(self.conversations.len())
+    }
+
+    /// Embed each memory with `embedder` and upsert it into `store`.
+    ///
+    /// # Returns
+    ///
+    /// The number of memories stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error of the embedder or store, or
+    /// `FlowError::NodeFailed` if the embedder returns the wrong number of
+    /// vectors.
+    pub async fn seed_vector_store(
+        &self,
+        store: &dyn VectorStore,
+        embedder: &dyn Embedder,
+    ) -> Result<usize, FlowError> {
+        if self.memories.is_empty() {
+            return Ok(0);
+        }
+        let texts: Vec<String> = self
+            .memories
+            .iter()
+            .map(|memory| memory.text.clone())
+            .collect();
+        let vectors = embedder.embed(&texts).await?;
+        if vectors.len() != self.memories.len() {
+            return Err(FlowError::NodeFailed(format!(
+                "Embedder returned {} vectors for {} memories",
+                vectors.len(),
+                self.memories.len()
+            )));
+        }
+        let documents = self
+            .memories
+            .iter()
+            .zip(vectors)
+            .map(|(memory, vector)| Document {
+                id: memory.id.clone(),
+                text: memory.text.clone(),
+                vector,
+                metadata: memory.metadata.clone(),
+            })
+            .collect();
+        store.upsert(documents).await?;
+        Ok(self.memories.len())
+    }
+}
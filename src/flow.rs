@@ -3,10 +3,23 @@
 //! This module provides the core flow types for organizing nodes into
 //! execution pipelines.
 
+use crate::cache::{self, CacheBackend};
+use crate::checkpoint::{Checkpoint, CheckpointStore, RunStatus};
+use crate::diagram::{Diagram, Endpoint};
 use crate::error::FlowError;
-use crate::node::Node;
+use crate::events::{self, EventSender, ExecutionEvent};
+use crate::executor::{FlowScheduler, Hooks, Middleware, NodeExecutor, NodeInput};
+use crate::node::{Named, Node, NodeHealth, NodeInfo};
+use crate::overlay::ExecutionOverlay;
+use crate::report::{self, ExecutionReport, NodeReport};
+use crate::schema;
+use crate::telemetry;
 use futures::future::join_all;
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
 
 /// A sequential execution pipeline for nodes.
 ///
@@ -43,6 +56,10 @@ use serde_json::Value;
 /// ```
 pub struct Flow {
     nodes: Vec<Box<dyn Node>>,
+    name: Option<String>,
+    hooks: Hooks,
+    cache: Option<(Arc<dyn CacheBackend>, Option<Duration>)>,
+    cache_namespace: String,
 }
 
 impl Flow {
@@ -52,7 +69,395 @@ impl Flow {
     ///
     /// * `nodes` - Vector of boxed nodes to execute in sequence
     pub fn new(nodes: Vec<Box<dyn Node>>) -> Self {
-        Self { nodes }
+        Self {
+            nodes,
+            name: None,
+            hooks: Hooks::default(),
+            cache: None,
+            cache_namespace: format!("Flow#{}", uuid::Uuid::new_v4()),
+        }
+    }
+
+    /// Name the flow in tracing spans and exported telemetry.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Prefix the keys of [`Flow::with_cache`] and
+    /// [`Flow::execute_incremental`] with `namespace` instead of one unique
+    /// to this flow, to share cached outputs between flows that compute the
+    /// same thing, across restarts and server replicas.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::cache::InMemoryCache;
+    /// use rustyflow::node::FnNode;
+    /// use rustyflow::{Flow, FlowError};
+    /// use serde_json::{json, Value};
+    /// use std::sync::Arc;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let backend = Arc::new(InMemoryCache::new(100));
+    /// let tag = |label: &'static str| {
+    ///     Flow::new(vec![Box::new(FnNode::new(move |input: Value| async move {
+    ///         Ok(json!({"label": label, "input": input}))
+    ///     }))])
+    ///     .with_cache(backend.clone(), None)
+    /// };
+    ///
+    /// // Unnamed flows over one backend keep apart
+    /// let (spam, ham) = (tag("spam"), tag("ham"));
+    /// assert_eq!(spam.execute(json!(1)).await?["label"], "spam");
+    /// assert_eq!(ham.execute(json!(1)).await?["label"], "ham");
+    ///
+    /// // Flows with one namespace share outputs
+    /// let first = tag("v1").with_cache_namespace("tagger");
+    /// let second = tag("v2").with_cache_namespace("tagger");
+    /// first.execute(json!(2)).await?;
+    /// assert_eq!(second.execute(json!(2)).await?["label"], "v1");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_cache_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.cache_namespace = namespace.into();
+        self
+    }
+
+    /// Append `node` to the end of the flow.
+    pub fn with_node(mut self, node: impl Node + 'static) -> Self {
+        self.nodes.push(Box::new(node));
+        self
+    }
+
+    /// Append `node` to the end of the flow under `name`, which replaces its
+    /// [`Node::name`] in traces, errors, diagrams and [`Flow::nodes`]. Add
+    /// a [`Named`] with [`Flow::with_node`] to describe the node as well.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::node::{Named, NodeInfo};
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::{json, Value};
+    ///
+    /// struct Search;
+    ///
+    /// #[async_trait]
+    /// impl Node for Search {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!({"question": input["question"], "documents": ["..."]}))
+    ///     }
+    /// }
+    ///
+    /// struct Answer;
+    ///
+    /// #[async_trait]
+    /// impl Node for Answer {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!({"answer": input["documents"][0]}))
+    ///     }
+    /// }
+    ///
+    /// let flow = Flow::new(Vec::new())
+    ///     .add_named("retrieve", Search)
+    ///     .with_node(Named::new("generate", Answer).with_description("Answer from the documents"));
+    ///
+    /// assert_eq!(flow.node_index("generate"), Some(1));
+    /// assert!(flow.to_mermaid().contains("n0[\"retrieve\"]"));
+    /// assert_eq!(
+    ///     flow.nodes()[1],
+    ///     NodeInfo {
+    ///         index: 1,
+    ///         name: "generate".to_string(),
+    ///         type_name: "Answer".to_string(),
+    ///         description: Some("Answer from the documents".to_string()),
+    ///     }
+    /// );
+    /// assert_eq!(
+    ///     serde_json::to_value(&flow.nodes()[0]).unwrap(),
+    ///     json!({"index": 0, "name": "retrieve", "type": "Search"})
+    /// );
+    /// ```
+    pub fn add_named(self, name: impl Into<String>, node: impl Node + 'static) -> Self {
+        self.with_node(Named::new(name, node))
+    }
+
+    /// Check that each node's output fits the next node's input, as declared
+    /// by their schemas, before the flow ever runs.
+    ///
+    /// A node's output schema comes from its
+    /// [`SchemaProvider`](crate::node::SchemaProvider) and the next node's
+    /// input schema from [`Node::input_schema`]; they are compared with
+    /// [`schema::compatible`]. Pairs where either node declares nothing are
+    /// not checked, so a [`ValidateNode`](crate::schema::ValidateNode) can
+    /// state a contract for a node that cannot.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Validation` with every mismatch, each message
+    /// naming the two nodes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::schema::ValidateNode;
+    /// use rustyflow::{Flow, FlowError};
+    /// use serde_json::json;
+    ///
+    /// let user = json!({
+    ///     "type": "object",
+    ///     "properties": {"id": {"type": "integer"}, "email": {"type": "string"}},
+    ///     "required": ["id"]
+    /// });
+    /// let mailing = json!({
+    ///     "type": "object",
+    ///     "properties": {"email": {"type": "string"}},
+    ///     "required": ["email"]
+    /// });
+    ///
+    /// let flow = Flow::new(vec![
+    ///     Box::new(ValidateNode::new(user)),
+    ///     Box::new(ValidateNode::new(mailing)),
+    /// ]);
+    /// let Err(FlowError::Validation(errors)) = flow.with_contracts() else {
+    ///     panic!("expected a contract violation");
+    /// };
+    /// assert_eq!(
+    ///     errors[0].message,
+    ///     "node 0 (ValidateNode) feeds node 1 (ValidateNode): required field 'email' may be missing"
+    /// );
+    /// ```
+    pub fn with_contracts(self) -> Result<Self, FlowError> {
+        let mut errors = Vec::new();
+        for (index, pair) in self.nodes.windows(2).enumerate() {
+            let output = pair[0]
+                .schema_provider()
+                .and_then(|schemas| schemas.output_schema());
+            let (Some(output), Some(input)) = (output, pair[1].input_schema()) else {
+                continue;
+            };
+            for mut error in schema::compatible(&output, &input) {
+                error.message = format!(
+                    "node {index} ({}) feeds node {} ({}): {}",
+                    pair[0].name(),
+                    index + 1,
+                    pair[1].name(),
+                    error.message
+                );
+                errors.push(error);
+            }
+        }
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(FlowError::Validation(errors))
+        }
+    }
+
+    /// Perform node calls, including those of nested flows and batches, with
+    /// a custom [`NodeExecutor`].
+    pub fn with_executor(mut self, executor: impl NodeExecutor + 'static) -> Self {
+        self.hooks.set_executor(executor);
+        self
+    }
+
+    /// Admit node calls, including those of nested flows and batches, with a
+    /// custom [`FlowScheduler`]. Pass an `Arc` to share one scheduler across
+    /// flows.
+    pub fn with_scheduler(mut self, scheduler: impl FlowScheduler + 'static) -> Self {
+        self.hooks.set_scheduler(scheduler);
+        self
+    }
+
+    /// Run node calls, including those of nested flows and batches, inside
+    /// a [`Middleware`]. Middleware added first runs outermost.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.hooks.add_middleware(middleware);
+        self
+    }
+
+    /// Cache the final output of [`Flow::execute`] by input, across runs.
+    ///
+    /// Before running any node, the flow looks its input up in `backend`
+    /// and returns the stored output on a hit. Only successful runs are
+    /// stored. Inputs are canonicalized and hashed as for
+    /// [`Cached`](crate::cache::Cached), under a namespace unique to the
+    /// flow, or the one set with [`Flow::with_cache_namespace`] to share
+    /// outputs between flows. Use this only for flows whose output
+    /// depends on nothing but their input. Traced, resumable and event
+    /// streaming executions always run the nodes.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - Where outputs are kept, possibly shared with other flows
+    /// * `ttl` - How long outputs are kept, or `None` for as long as the
+    ///   backend keeps them
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::cache::InMemoryCache;
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// static CALLS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// /// Stands in for an LLM classifier.
+    /// struct Classify;
+    ///
+    /// #[async_trait]
+    /// impl Node for Classify {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         CALLS.fetch_add(1, Ordering::SeqCst);
+    ///         let invoice = input["text"].as_str().unwrap_or("").contains("invoice");
+    ///         Ok(json!({"label": if invoice { "invoice" } else { "other" }}))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = Flow::new(vec![Box::new(Classify)])
+    ///     .with_name("classify")
+    ///     .with_cache(Arc::new(InMemoryCache::new(10_000)), Some(Duration::from_secs(3600)));
+    ///
+    /// let document = json!({"id": 7, "text": "invoice #1042"});
+    /// assert_eq!(flow.execute(document.clone()).await?, json!({"label": "invoice"}));
+    /// // The webhook fires again for the same document
+    /// assert_eq!(flow.execute(document).await?, json!({"label": "invoice"}));
+    /// assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_cache(mut self, backend: Arc<dyn CacheBackend>, ttl: Option<Duration>) -> Self {
+        self.cache = Some((backend, ttl));
+        self
+    }
+
+    /// The output [`Flow::with_cache`] holds for `input`, without running
+    /// any node.
+    ///
+    /// # Returns
+    ///
+    /// The stored output, or `None` if the flow has no cache, holds no
+    /// fresh output for `input`, or cannot read its backend.
+    pub async fn cached_output(&self, input: &Value) -> Option<Value> {
+        let (backend, _) = self.cache.as_ref()?;
+        let name = self.name.as_deref().unwrap_or("Flow");
+        match backend.get(&cache::key(&self.cache_namespace, input)).await {
+            Ok(output) => output,
+            Err(e) => {
+                tracing::warn!("Cache lookup for flow '{name}' failed: {e}");
+                None
+            }
+        }
+    }
+
+    /// The flow's name, if one was set with [`Flow::with_name`].
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Describe the flow's nodes, in execution order: their names, types,
+    /// positions and descriptions.
+    pub fn nodes(&self) -> Vec<NodeInfo> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| NodeInfo::new(index, node.as_ref()))
+            .collect()
+    }
+
+    /// The node at `index`, if the flow has that many.
+    pub fn node(&self, index: usize) -> Option<&dyn Node> {
+        self.nodes.get(index).map(AsRef::as_ref)
+    }
+
+    /// The number of nodes in the flow.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the flow has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Render the flow as a Graphviz DOT digraph.
+    ///
+    /// Nodes are labelled with [`Node::name`] and the flow name, if set, is
+    /// used as the graph label.
+    pub fn to_dot(&self) -> String {
+        self.diagram().to_dot()
+    }
+
+    /// Render the flow as a Mermaid flowchart.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::Value;
+    /// use async_trait::async_trait;
+    ///
+    /// struct Fetch;
+    /// struct Summarize;
+    ///
+    /// #[async_trait]
+    /// impl Node for Fetch {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(input)
+    ///     }
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl Node for Summarize {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(input)
+    ///     }
+    /// }
+    ///
+    /// let flow = Flow::new(vec![Box::new(Fetch), Box::new(Summarize)]);
+    /// let mermaid = flow.to_mermaid();
+    ///
+    /// assert!(mermaid.starts_with("flowchart LR"));
+    /// assert!(mermaid.contains("n0[\"Fetch\"]"));
+    /// assert!(mermaid.contains("n0 --> n1"));
+    /// ```
+    pub fn to_mermaid(&self) -> String {
+        self.diagram().to_mermaid()
+    }
+
+    /// Render the flow as a Mermaid flowchart overlaid with the state of a
+    /// run: each step gets a `running`, `succeeded` or `failed` class and its
+    /// time, and the edges the run took are highlighted.
+    pub fn to_live_mermaid(&self, overlay: &ExecutionOverlay) -> String {
+        self.diagram().to_live_mermaid(overlay)
+    }
+
+    /// Describe the flow and the state of a run as JSON for a UI to draw,
+    /// in the format shown in [`overlay`](crate::overlay).
+    pub fn live_view(&self, overlay: &ExecutionOverlay) -> Value {
+        self.diagram().live_view(overlay)
+    }
+
+    fn diagram(&self) -> Diagram {
+        let mut diagram = Diagram::new(self.name.as_deref());
+        let mut previous = Endpoint::Input;
+        for node in &self.nodes {
+            let current = diagram.node(node.name());
+            diagram.edge(previous, current, None);
+            previous = current;
+        }
+        diagram.edge(previous, Endpoint::Output, None);
+        diagram
     }
 
     /// Execute the flow with the given input.
@@ -67,13 +472,751 @@ impl Flow {
     /// # Returns
     ///
     /// The final output after all nodes have been executed, or the first
-    /// error encountered.
+    /// error encountered, wrapped in a [`FlowError::NodeError`] that names
+    /// the failed node.
     pub async fn execute(&self, mut input: Value) -> Result<Value, FlowError> {
-        for node in &self.nodes {
-            input = node.call(input).await?;
+        let body = async move {
+            let name = self.name.as_deref().unwrap_or("Flow");
+            let cached = match &self.cache {
+                Some((backend, ttl)) => {
+                    let key = cache::key(&self.cache_namespace, &input);
+                    match backend.get(&key).await {
+                        Ok(Some(output)) => {
+                            crate::metrics::cache_lookup(name, true);
+                            return Ok(output);
+                        }
+                        Ok(None) => {}
+                        Err(e) => tracing::warn!("Cache lookup for flow '{name}' failed: {e}"),
+                    }
+                    crate::metrics::cache_lookup(name, false);
+                    Some((backend, *ttl, key))
+                }
+                None => None,
+            };
+
+            for (index, node) in self.nodes.iter().enumerate() {
+                input = telemetry::call_node(node.as_ref(), index, input).await?;
+            }
+            if let Some((backend, ttl, key)) = cached {
+                if let Err(e) = backend.set(&key, input.clone(), ttl).await {
+                    tracing::warn!("Caching the output of flow '{name}' failed: {e}");
+                }
+            }
+            Ok(input)
+        };
+        telemetry::in_flow_span(self.span(None), self.hooks.scope(body)).await
+    }
+
+    /// The position of a node, given its [`Node::name`] or its index.
+    ///
+    /// When several nodes share a name, the first one is returned.
+    pub fn node_index(&self, node: &str) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|candidate| candidate.name() == node)
+            .or_else(|| node.parse().ok().filter(|&index| index < self.nodes.len()))
+    }
+
+    /// Execute only part of the flow, starting at `entry` with a synthetic
+    /// input.
+    ///
+    /// This lets one stage of a long pipeline be iterated on or tested
+    /// without running the stages before it. Nodes are given by name or
+    /// index, as in [`Flow::node_index`].
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The first node to run
+    /// * `until` - The last node to run, or `None` to run to the end
+    /// * `input` - The input of the entry node
+    ///
+    /// # Returns
+    ///
+    /// The output of the last node run, or the first error encountered.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if a node is not found or `until`
+    /// comes before `entry`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use async_trait::async_trait;
+    ///
+    /// struct Fetch;
+    /// struct Clean;
+    /// struct Count;
+    ///
+    /// #[async_trait]
+    /// impl Node for Fetch {
+    ///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+    ///         Err(FlowError::NodeFailed("network unavailable".to_string()))
+    ///     }
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl Node for Clean {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!(input.as_str().unwrap_or_default().trim().to_lowercase()))
+    ///     }
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl Node for Count {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!(input.as_str().unwrap_or_default().split_whitespace().count()))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = Flow::new(vec![Box::new(Fetch), Box::new(Clean), Box::new(Count)]);
+    ///
+    /// // Skip the fetch and feed the cleaning stage a sample page
+    /// let cleaned = flow.execute_from("Clean", Some("Clean"), json!("  Hello World ")).await?;
+    /// assert_eq!(cleaned, json!("hello world"));
+    /// assert_eq!(flow.execute_from("1", None, json!("a b c")).await?, json!(3));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_from(
+        &self,
+        entry: &str,
+        until: Option<&str>,
+        mut input: Value,
+    ) -> Result<Value, FlowError> {
+        let find = |node: &str| {
+            self.node_index(node)
+                .ok_or_else(|| FlowError::NodeFailed(format!("Unknown node '{node}'")))
+        };
+        let first = find(entry)?;
+        let last = match until {
+            Some(until) => find(until)?,
+            None => self.nodes.len() - 1,
+        };
+        if last < first {
+            return Err(FlowError::NodeFailed(format!(
+                "Node '{}' comes before entry node '{entry}'",
+                until.unwrap_or_default()
+            )));
+        }
+
+        let body = async move {
+            for (index, node) in self.nodes.iter().enumerate().take(last + 1).skip(first) {
+                input = telemetry::call_node(node.as_ref(), index, input).await?;
+            }
+            Ok(input)
+        };
+        telemetry::in_flow_span(self.span(None), self.hooks.scope(body)).await
+    }
+
+    /// Execute the flow incrementally, reusing the outputs of steps whose
+    /// input has not changed since an earlier run.
+    ///
+    /// Each step's input is hashed, canonicalized as for
+    /// [`Cached`](crate::cache::Cached), and its output is stored in `cache`
+    /// under the flow's [cache namespace](Flow::with_cache_namespace), the
+    /// step's index and name, and that hash. Set a namespace to reuse step
+    /// outputs across processes. A
+    /// step whose input is found is not run and passes the stored output on;
+    /// any other step runs, so a changed input re-executes its node and
+    /// every later node whose input changes as a result. When a re-executed
+    /// node returns the same output as before, the steps after it are reused
+    /// again.
+    ///
+    /// Input hashes cannot tell that a node itself changed, as when
+    /// iterating on a late stage of a pipeline; name it as `rerun_from` to
+    /// re-execute it and every step after it regardless.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The initial input value for the flow
+    /// * `cache` - Where step outputs of earlier runs are kept
+    /// * `rerun_from` - A node, by name or index as in [`Flow::node_index`],
+    ///   from which every step runs
+    ///
+    /// # Returns
+    ///
+    /// The final output, and the indices of the steps that ran rather than
+    /// being reused.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if `rerun_from` is not a node of the
+    /// flow, or the first node error, wrapped in a [`FlowError::NodeError`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::cache::InMemoryCache;
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::{json, Value};
+    ///
+    /// struct Extract;
+    /// struct Summarize;
+    /// struct Format;
+    ///
+    /// #[async_trait]
+    /// impl Node for Extract {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!(input["text"].as_str().unwrap_or("").to_lowercase()))
+    ///     }
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl Node for Summarize {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!(input.as_str().unwrap_or("").split('.').next()))
+    ///     }
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl Node for Format {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!({"summary": input}))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = Flow::new(vec![Box::new(Extract), Box::new(Summarize), Box::new(Format)])
+    ///     .with_name("digest");
+    /// let cache = InMemoryCache::new(1024);
+    /// let input = json!({"text": "Rust is fast. It has no GC."});
+    ///
+    /// let (_, ran) = flow.execute_incremental(input.clone(), &cache, None).await?;
+    /// assert_eq!(ran, [0, 1, 2]);
+    ///
+    /// // Nothing changed: every step is reused
+    /// let (output, ran) = flow.execute_incremental(input.clone(), &cache, None).await?;
+    /// assert_eq!(output, json!({"summary": "rust is fast"}));
+    /// assert!(ran.is_empty());
+    ///
+    /// // After editing the Format node, only it runs again
+    /// let (_, ran) = flow.execute_incremental(input.clone(), &cache, Some("Format")).await?;
+    /// assert_eq!(ran, [2]);
+    ///
+    /// // Another flow over the same cache reuses none of its steps
+    /// let other = Flow::new(vec![Box::new(Extract), Box::new(Summarize), Box::new(Format)]);
+    /// let (_, ran) = other.execute_incremental(input, &cache, None).await?;
+    /// assert_eq!(ran, [0, 1, 2]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_incremental(
+        &self,
+        mut input: Value,
+        cache: &dyn CacheBackend,
+        rerun_from: Option<&str>,
+    ) -> Result<(Value, Vec<usize>), FlowError> {
+        let rerun_from = match rerun_from {
+            Some(node) => self
+                .node_index(node)
+                .ok_or_else(|| FlowError::NodeFailed(format!("Unknown node '{node}'")))?,
+            None => self.nodes.len(),
+        };
+        let namespace = &self.cache_namespace;
+
+        let body = async move {
+            let mut ran = Vec::new();
+            for (index, node) in self.nodes.iter().enumerate() {
+                let key = cache::key(&format!("{namespace}:{index}:{}", node.name()), &input);
+                if index < rerun_from {
+                    match cache.get(&key).await {
+                        Ok(Some(output)) => {
+                            tracing::debug!("Reusing the output of step {index}");
+                            input = output;
+                            continue;
+                        }
+                        Ok(None) => {}
+                        Err(e) => tracing::warn!("Step output lookup failed: {e}"),
+                    }
+                }
+                input = telemetry::call_node(node.as_ref(), index, input).await?;
+                ran.push(index);
+                if let Err(e) = cache.set(&key, input.clone(), None).await {
+                    tracing::warn!("Storing the output of step {index} failed: {e}");
+                }
+            }
+            Ok((input, ran))
+        };
+        telemetry::in_flow_span(self.span(None), self.hooks.scope(body)).await
+    }
+
+    fn span(&self, run_id: Option<&str>) -> telemetry::FlowSpan {
+        telemetry::flow_span("Flow", self.name.as_deref(), self.nodes.len(), run_id)
+    }
+
+    /// Execute the flow and report per-node measurements.
+    ///
+    /// Behaves like [`Flow::execute`], but also returns an
+    /// [`ExecutionReport`] with each node's name, duration, input and output
+    /// sizes, reported retries and token usage, and error. The report is
+    /// returned even when a node fails, and then ends with the failed node.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The initial input value for the flow
+    ///
+    /// # Returns
+    ///
+    /// The flow's result together with the execution report.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use async_trait::async_trait;
+    ///
+    /// struct Upper;
+    ///
+    /// #[async_trait]
+    /// impl Node for Upper {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!(input.as_str().unwrap_or_default().to_uppercase()))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = Flow::new(vec![Box::new(Upper)]);
+    /// let (result, report) = flow.execute_traced(json!("hi")).await;
+    ///
+    /// assert_eq!(result?, json!("HI"));
+    /// assert_eq!(report.nodes[0].name, "Upper");
+    /// assert_eq!(report.nodes[0].input_bytes, 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_traced(
+        &self,
+        input: Value,
+    ) -> (Result<Value, FlowError>, ExecutionReport) {
+        let flow = self.span(None);
+        flow.start();
+        let (result, report) = self
+            .hooks
+            .scope(self.run_traced(input))
+            .instrument(flow.span().clone())
+            .await;
+        flow.finish(&result);
+        (result, report)
+    }
+
+    async fn run_traced(&self, mut input: Value) -> (Result<Value, FlowError>, ExecutionReport) {
+        let started = Instant::now();
+        let mut report = ExecutionReport::default();
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let input_bytes = report::json_size(&input);
+            let node_started = Instant::now();
+            let (result, metrics) =
+                report::measure(telemetry::call_node(node.as_ref(), index, input)).await;
+
+            let mut entry = NodeReport {
+                index,
+                name: node.name().to_string(),
+                duration: node_started.elapsed(),
+                input_bytes,
+                output_bytes: None,
+                retries: metrics.retries,
+                usage: metrics.usage,
+                scores: metrics.scores,
+                logs: metrics.logs,
+                metadata: metrics.metadata,
+                error: None,
+            };
+            match result {
+                Ok(output) => {
+                    entry.output_bytes = Some(report::json_size(&output));
+                    report.nodes.push(entry);
+                    input = output;
+                }
+                Err(e) => {
+                    entry.error = Some(e.to_string());
+                    report.nodes.push(entry);
+                    report.duration = started.elapsed();
+                    return (Err(e), report);
+                }
+            }
+        }
+
+        report.duration = started.elapsed();
+        (Ok(input), report)
+    }
+
+    /// Execute the flow, sending an [`ExecutionEvent`] as each node starts
+    /// and ends.
+    ///
+    /// Behaves like [`Flow::execute`]. The last event is always
+    /// [`ExecutionEvent::FinalResult`] or [`ExecutionEvent::Error`], and
+    /// nodes can add [`ExecutionEvent::TokenDelta`] events with
+    /// [`events::emit_token`]. Execution continues if the receiver is
+    /// dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The initial input value for the flow
+    /// * `events` - Where the events are sent
+    ///
+    /// # Returns
+    ///
+    /// The final output after all nodes have been executed, or the first
+    /// error encountered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::events::{self, ExecutionEvent};
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use async_trait::async_trait;
+    ///
+    /// /// Streams its answer word by word.
+    /// struct Speaker;
+    ///
+    /// #[async_trait]
+    /// impl Node for Speaker {
+    ///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+    ///         for word in ["Hello", " world"] {
+    ///             events::emit_token(word);
+    ///         }
+    ///         Ok(json!("Hello world"))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = Flow::new(vec![Box::new(Speaker)]);
+    /// let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    /// flow.execute_with_events(json!(null), &sender).await?;
+    /// drop(sender);
+    ///
+    /// let mut kinds = Vec::new();
+    /// while let Some(event) = receiver.recv().await {
+    ///     kinds.push(serde_json::to_value(&event)?["type"].clone());
+    /// }
+    /// assert_eq!(
+    ///     kinds,
+    ///     ["node_start", "token_delta", "token_delta", "node_end", "final_result"]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_with_events(
+        &self,
+        input: Value,
+        events: &EventSender,
+    ) -> Result<Value, FlowError> {
+        let result = telemetry::in_flow_span(
+            self.span(None),
+            self.hooks.scope(self.run_with_events(input, events)),
+        )
+        .await;
+        let _ = events.send(match &result {
+            Ok(output) => ExecutionEvent::FinalResult {
+                output: output.clone(),
+            },
+            Err(e) => ExecutionEvent::Error {
+                error: e.to_string(),
+            },
+        });
+        result
+    }
+
+    async fn run_with_events(
+        &self,
+        mut input: Value,
+        events: &EventSender,
+    ) -> Result<Value, FlowError> {
+        for (index, node) in self.nodes.iter().enumerate() {
+            let name = node.name().to_string();
+            let _ = events.send(ExecutionEvent::NodeStart {
+                index,
+                name: name.clone(),
+            });
+            let started = Instant::now();
+            let result = events::scope(
+                events,
+                index,
+                telemetry::call_node(node.as_ref(), index, input),
+            )
+            .await;
+            let _ = events.send(ExecutionEvent::NodeEnd {
+                index,
+                name,
+                duration_ms: started.elapsed().as_millis() as u64,
+                error: result.as_ref().err().map(ToString::to_string),
+            });
+            input = result?;
         }
         Ok(input)
     }
+
+    /// Execute the flow, checkpointing after every node.
+    ///
+    /// If `store` already holds a checkpoint for `run_id`, execution resumes
+    /// after the last completed node with its saved output and `input` is
+    /// ignored. The final checkpoint is kept once the run completes, so
+    /// executing a finished run again returns its output without calling
+    /// any node; use [`CheckpointStore::clear`] to start over.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - Identifies the run in the checkpoint store
+    /// * `input` - The initial input value for a new run
+    /// * `store` - Where progress is saved
+    ///
+    /// # Returns
+    ///
+    /// The final output after all nodes have been executed, or the first
+    /// error encountered. Progress made before an error stays checkpointed,
+    /// and the run's [`RunStatus`] is reported to the store as it changes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::checkpoint::{CheckpointStore, InMemoryCheckpointStore};
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use async_trait::async_trait;
+    ///
+    /// struct AddNode(i64);
+    ///
+    /// #[async_trait]
+    /// impl Node for AddNode {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!({"value": input["value"].as_i64().unwrap_or(0) + self.0}))
+    ///     }
+    /// }
+    ///
+    /// /// Fails on its first call, like a process crashing mid-run.
+    /// struct FlakyNode(AtomicBool);
+    ///
+    /// #[async_trait]
+    /// impl Node for FlakyNode {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         if !self.0.swap(true, Ordering::SeqCst) {
+    ///             return Err(FlowError::NodeFailed("crashed".to_string()));
+    ///         }
+    ///         Ok(input)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let store = InMemoryCheckpointStore::new();
+    /// let flow = Flow::new(vec![
+    ///     Box::new(AddNode(5)),
+    ///     Box::new(FlakyNode(AtomicBool::new(false))),
+    ///     Box::new(AddNode(10)),
+    /// ]);
+    ///
+    /// assert!(flow.execute_resumable("run-1", json!({"value": 0}), &store).await.is_err());
+    /// assert_eq!(store.load("run-1").await?.unwrap().step, 1);
+    ///
+    /// // The retry resumes after the first node instead of starting over
+    /// let result = flow.execute_resumable("run-1", json!({"value": 0}), &store).await?;
+    /// assert_eq!(result["value"], 15);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_resumable(
+        &self,
+        run_id: &str,
+        input: Value,
+        store: &dyn CheckpointStore,
+    ) -> Result<Value, FlowError> {
+        telemetry::in_flow_span(
+            self.span(Some(run_id)),
+            self.hooks.scope(self.resume(run_id, input, store)),
+        )
+        .await
+    }
+
+    async fn resume(
+        &self,
+        run_id: &str,
+        input: Value,
+        store: &dyn CheckpointStore,
+    ) -> Result<Value, FlowError> {
+        let (start, value) = match store.load(run_id).await? {
+            Some(checkpoint) if checkpoint.step > self.nodes.len() => {
+                return Err(FlowError::Checkpoint(format!(
+                    "Run '{run_id}' completed {} steps but the flow has only {} nodes",
+                    checkpoint.step,
+                    self.nodes.len()
+                )));
+            }
+            Some(checkpoint) => {
+                tracing::info!("Resuming run '{run_id}' after step {}", checkpoint.step);
+                if let Some(state) = checkpoint.state {
+                    self.restore(state).await?;
+                }
+                (checkpoint.step, checkpoint.value)
+            }
+            None => (0, input),
+        };
+
+        if start == self.nodes.len() {
+            return Ok(value);
+        }
+
+        store.set_status(run_id, RunStatus::Running).await?;
+        match self.run_from(start, value, run_id, store).await {
+            Ok(output) => {
+                store.set_status(run_id, RunStatus::Completed).await?;
+                Ok(output)
+            }
+            Err(e) => {
+                if let Err(status_error) = store.set_status(run_id, RunStatus::Failed).await {
+                    tracing::warn!("Cannot mark run '{run_id}' as failed: {status_error}");
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Run the nodes from `start` onwards, saving a checkpoint after each.
+    async fn run_from(
+        &self,
+        start: usize,
+        mut value: Value,
+        run_id: &str,
+        store: &dyn CheckpointStore,
+    ) -> Result<Value, FlowError> {
+        for (step, node) in self.nodes.iter().enumerate().skip(start) {
+            value = telemetry::call_node(node.as_ref(), step, value).await?;
+            let checkpoint = Checkpoint {
+                step: step + 1,
+                value,
+                state: self.snapshot().await?,
+            };
+            store.save_checkpoint(run_id, &checkpoint).await?;
+            value = checkpoint.value;
+        }
+        Ok(value)
+    }
+
+    /// Run the [`HealthCheck`](crate::node::HealthCheck) of every node that
+    /// has one, concurrently.
+    ///
+    /// # Returns
+    ///
+    /// One entry per checked node, in flow order; nodes without a check are
+    /// left out.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::node::HealthCheck;
+    /// use rustyflow::{Flow, FlowError, Node};
+    /// use serde_json::Value;
+    ///
+    /// /// Stands in for a client of an API that is down.
+    /// struct Api;
+    ///
+    /// #[async_trait]
+    /// impl HealthCheck for Api {
+    ///     async fn check(&self) -> Result<(), FlowError> {
+    ///         Err(FlowError::NodeFailed("connection refused".to_string()))
+    ///     }
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl Node for Api {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(input)
+    ///     }
+    ///
+    ///     fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+    ///         Some(self)
+    ///     }
+    /// }
+    ///
+    /// struct Local;
+    ///
+    /// #[async_trait]
+    /// impl Node for Local {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(input)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let flow = Flow::new(vec![Box::new(Local), Box::new(Api)]);
+    /// let health = flow.check_health().await;
+    /// assert_eq!(health.len(), 1);
+    /// assert_eq!(health[0].index, 1);
+    /// assert_eq!(health[0].error.as_deref(), Some("Node execution failed: connection refused"));
+    /// # }
+    /// ```
+    pub async fn check_health(&self) -> Vec<NodeHealth> {
+        let checks = self.nodes.iter().enumerate().filter_map(|(index, node)| {
+            let check = node.health_checkable()?;
+            Some(async move {
+                NodeHealth {
+                    index,
+                    name: node.name().to_string(),
+                    error: check.check().await.err().map(|e| e.to_string()),
+                }
+            })
+        });
+        join_all(checks).await
+    }
+
+    /// Capture the state of the flow's stateful nodes.
+    ///
+    /// # Returns
+    ///
+    /// An object mapping node positions to their [`Node::snapshot`], or
+    /// `None` if no node has state.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from [`Node::snapshot`].
+    pub async fn snapshot(&self) -> Result<Option<Value>, FlowError> {
+        let mut states = Map::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(state) = node.snapshot().await? {
+                states.insert(index.to_string(), state);
+            }
+        }
+        Ok((!states.is_empty()).then_some(Value::Object(states)))
+    }
+
+    /// Restore node state captured by [`Flow::snapshot`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if `state` refers to a node the flow
+    /// does not have, or the first error from [`Node::restore`].
+    pub async fn restore(&self, state: Value) -> Result<(), FlowError> {
+        let Value::Object(states) = state else {
+            return Err(FlowError::Checkpoint(
+                "Flow state must be an object keyed by node position".to_string(),
+            ));
+        };
+        for (key, state) in states {
+            let node = key
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| self.nodes.get(index))
+                .ok_or_else(|| {
+                    FlowError::Checkpoint(format!("Saved state for unknown node '{key}'"))
+                })?;
+            node.restore(state).await?;
+        }
+        Ok(())
+    }
 }
 
 /// A parallel execution pipeline for nodes.
@@ -111,7 +1254,28 @@ impl Flow {
 /// # }
 /// ```
 pub struct ParallelFlow {
-    nodes: Vec<Box<dyn Node>>,
+    nodes: Vec<Arc<dyn Node>>,
+    timeout: Option<Duration>,
+    branch_timeouts: HashMap<usize, Duration>,
+    branch_inputs: HashMap<usize, String>,
+    output_keys: HashMap<usize, String>,
+    late_policy: LatePolicy,
+    labeled: bool,
+    collect_errors: bool,
+    spawned: bool,
+    name: Option<String>,
+    hooks: Hooks,
+}
+
+/// What a [`ParallelFlow`] does with a branch that exceeds its timeout.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LatePolicy {
+    /// Abandon the branch and put this value in its slot of the output.
+    Placeholder(Value),
+    /// Abandon the branch and fail the flow with `FlowError::Timeout`.
+    Fail,
+    /// Log a warning and keep waiting for the branch to finish.
+    Wait,
 }
 
 impl ParallelFlow {
@@ -121,13 +1285,391 @@ impl ParallelFlow {
     ///
     /// * `nodes` - Vector of boxed nodes to execute in parallel
     pub fn new(nodes: Vec<Box<dyn Node>>) -> Self {
-        Self { nodes }
+        Self {
+            nodes: nodes.into_iter().map(Arc::from).collect(),
+            timeout: None,
+            branch_timeouts: HashMap::new(),
+            branch_inputs: HashMap::new(),
+            output_keys: HashMap::new(),
+            late_policy: LatePolicy::Fail,
+            labeled: false,
+            collect_errors: false,
+            spawned: false,
+            name: None,
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Name the flow in tracing spans and exported telemetry.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Perform node calls, including those of nested flows and batches, with
+    /// a custom [`NodeExecutor`].
+    pub fn with_executor(mut self, executor: impl NodeExecutor + 'static) -> Self {
+        self.hooks.set_executor(executor);
+        self
+    }
+
+    /// Admit node calls, including those of nested flows and batches, with a
+    /// custom [`FlowScheduler`]. Pass an `Arc` to share one scheduler across
+    /// flows.
+    pub fn with_scheduler(mut self, scheduler: impl FlowScheduler + 'static) -> Self {
+        self.hooks.set_scheduler(scheduler);
+        self
+    }
+
+    /// Run node calls, including those of nested flows and batches, inside
+    /// a [`Middleware`]. Middleware added first runs outermost.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.hooks.add_middleware(middleware);
+        self
+    }
+
+    /// Report every failed branch instead of only the first.
+    ///
+    /// All branches always run to completion; by default the flow then
+    /// returns the error of the first failed branch in node order. With this
+    /// set, it returns a [`FlowError::Multiple`] holding the
+    /// [`Node::name`] and error of each failed branch.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::{FlowError, Node, ParallelFlow};
+    /// use serde_json::{json, Value};
+    ///
+    /// struct Provider(&'static str, bool);
+    ///
+    /// #[async_trait]
+    /// impl Node for Provider {
+    ///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+    ///         match self.1 {
+    ///             true => Ok(json!(self.0)),
+    ///             false => Err(FlowError::NodeFailed(format!("{} is down", self.0))),
+    ///         }
+    ///     }
+    ///
+    ///     fn name(&self) -> &str {
+    ///         self.0
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let flow = ParallelFlow::new(vec![
+    ///     Box::new(Provider("search", false)),
+    ///     Box::new(Provider("wiki", true)),
+    ///     Box::new(Provider("news", false)),
+    /// ])
+    /// .collect_errors();
+    ///
+    /// let Err(FlowError::Multiple(errors)) = flow.execute(json!({})).await else {
+    ///     panic!("expected every failure");
+    /// };
+    /// let failed: Vec<&str> = errors.iter().map(|(name, _)| name.as_str()).collect();
+    /// assert_eq!(failed, ["search", "news"]);
+    /// # }
+    /// ```
+    pub fn collect_errors(mut self) -> Self {
+        self.collect_errors = true;
+        self
+    }
+
+    /// Run each branch on a task of its own with `tokio::spawn`.
+    ///
+    /// By default all branches are polled by the task executing the flow,
+    /// so they overlap while waiting on I/O but share one thread for
+    /// computation. Spawned branches are scheduled across the worker threads
+    /// of a multi-threaded runtime, so CPU-heavy nodes run in parallel.
+    /// Branches keep the flow's executor and scheduler, and are aborted if
+    /// the flow is dropped or abandons them on timeout. Usage and logs they
+    /// record are not attributed to an enclosing traced step.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::{FlowError, Node, ParallelFlow};
+    /// use serde_json::{json, Value};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// /// Blocks its thread, like a CPU-bound scoring model.
+    /// struct Score;
+    ///
+    /// #[async_trait]
+    /// impl Node for Score {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         std::thread::sleep(Duration::from_millis(100));
+    ///         Ok(input)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main(flavor = "multi_thread", worker_threads = 2)]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = ParallelFlow::new(vec![Box::new(Score), Box::new(Score)]).spawned();
+    ///
+    /// let started = Instant::now();
+    /// assert_eq!(flow.execute(json!(1)).await?, json!([1, 1]));
+    /// assert!(started.elapsed() < Duration::from_millis(190));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawned(mut self) -> Self {
+        self.spawned = true;
+        self
+    }
+
+    /// Render the flow as a Graphviz DOT digraph, with one branch per node.
+    ///
+    /// Each branch's edge into the output is labelled with the slot it
+    /// fills: its array index, or its node name if the flow is labeled.
+    pub fn to_dot(&self) -> String {
+        self.diagram().to_dot()
+    }
+
+    /// Render the flow as a Mermaid flowchart, with one branch per node.
+    pub fn to_mermaid(&self) -> String {
+        self.diagram().to_mermaid()
+    }
+
+    fn diagram(&self) -> Diagram {
+        let mut diagram = Diagram::new(self.name.as_deref());
+        for (index, node) in self.nodes.iter().enumerate() {
+            let branch = diagram.node(node.name());
+            let pointer = self.branch_inputs.get(&index).map(String::as_str);
+            diagram.edge(Endpoint::Input, branch, pointer);
+            // Label the edge with the output slot the branch fills
+            let slot = match self.key(index) {
+                Some(key) => key.to_string(),
+                None => format!("[{index}]"),
+            };
+            diagram.edge(branch, Endpoint::Output, Some(&slot));
+        }
+        diagram
+    }
+
+    /// Return an object keyed by node name instead of a positional array.
+    ///
+    /// Each output is stored under its node's [`Node::name`], so downstream
+    /// nodes keep working when the node list is reordered. Names must be
+    /// unique; override [`Node::name`] to distinguish nodes of the same type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::{FlowError, Node, ParallelFlow};
+    /// use serde_json::{json, Value};
+    /// use async_trait::async_trait;
+    ///
+    /// struct Sentiment;
+    /// struct Summary;
+    ///
+    /// #[async_trait]
+    /// impl Node for Sentiment {
+    ///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!("positive"))
+    ///     }
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl Node for Summary {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!(format!("{} words", input.as_str().unwrap_or("").split_whitespace().count())))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = ParallelFlow::new(vec![Box::new(Summary), Box::new(Sentiment)]).labeled();
+    ///
+    /// let result = flow.execute(json!("a great day")).await?;
+    /// assert_eq!(result, json!({"Sentiment": "positive", "Summary": "3 words"}));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn labeled(mut self) -> Self {
+        self.labeled = true;
+        self
+    }
+
+    /// Pass the branch at `index` only the part of the input at a JSON
+    /// pointer, instead of a clone of the whole input.
+    ///
+    /// The branch fails if the input has nothing at `pointer`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::{FlowError, Node, ParallelFlow};
+    /// use serde_json::{json, Value};
+    ///
+    /// struct Count;
+    ///
+    /// #[async_trait]
+    /// impl Node for Count {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!(input.as_array().map_or(0, Vec::len)))
+    ///     }
+    /// }
+    ///
+    /// struct Title;
+    ///
+    /// #[async_trait]
+    /// impl Node for Title {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!(input.as_str().unwrap_or("").to_uppercase()))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = ParallelFlow::new(vec![Box::new(Title), Box::new(Count), Box::new(Count)])
+    ///     .with_branch_input(0, "/doc/title")
+    ///     .with_branch_input(1, "/doc/pages")
+    ///     .with_branch_input(2, "/doc/images")
+    ///     .with_output_key(0, "title")
+    ///     .with_output_key(1, "pages")
+    ///     .with_output_key(2, "images");
+    ///
+    /// let doc = json!({"doc": {"title": "report", "pages": [1, 2, 3], "images": []}});
+    /// let result = flow.execute(doc).await?;
+    /// assert_eq!(result, json!({"title": "REPORT", "pages": 3, "images": 0}));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_branch_input(mut self, index: usize, pointer: impl Into<String>) -> Self {
+        self.branch_inputs.insert(index, pointer.into());
+        self
+    }
+
+    /// Store the output of the branch at `index` under `key`.
+    ///
+    /// Setting a key makes the flow return an object, as if it were
+    /// [`labeled`](ParallelFlow::labeled): branches without a key are stored
+    /// under their node name.
+    pub fn with_output_key(mut self, index: usize, key: impl Into<String>) -> Self {
+        self.output_keys.insert(index, key.into());
+        self
+    }
+
+    /// The key the output of the branch at `index` is stored under, if the
+    /// flow returns an object.
+    fn key(&self, index: usize) -> Option<&str> {
+        if let Some(key) = self.output_keys.get(&index) {
+            return Some(key);
+        }
+        (self.labeled || !self.output_keys.is_empty()).then(|| self.nodes[index].name())
+    }
+
+    /// Set the timeout applied to every branch without its own timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the timeout for the branch at `index` in the node list.
+    pub fn with_branch_timeout(mut self, index: usize, timeout: Duration) -> Self {
+        self.branch_timeouts.insert(index, timeout);
+        self
+    }
+
+    /// Choose how branches that exceed their timeout are handled.
+    ///
+    /// Defaults to [`LatePolicy::Fail`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::flow::{LatePolicy, ParallelFlow};
+    /// use rustyflow::{FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use std::time::Duration;
+    /// use async_trait::async_trait;
+    ///
+    /// struct Provider(u64);
+    ///
+    /// #[async_trait]
+    /// impl Node for Provider {
+    ///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+    ///         tokio::time::sleep(Duration::from_millis(self.0)).await;
+    ///         Ok(json!(self.0))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = ParallelFlow::new(vec![Box::new(Provider(10)), Box::new(Provider(5_000))])
+    ///     .with_timeout(Duration::from_millis(200))
+    ///     .with_late_policy(LatePolicy::Placeholder(Value::Null));
+    ///
+    /// let result = flow.execute(json!({})).await?;
+    /// assert_eq!(result, json!([10, null]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_late_policy(mut self, policy: LatePolicy) -> Self {
+        self.late_policy = policy;
+        self
+    }
+
+    /// Run the branch at `index` on its part of the input, applying its
+    /// timeout and the late policy.
+    async fn run_branch(&self, index: usize, input: Arc<Value>) -> Result<Value, FlowError> {
+        let input = match self.branch_inputs.get(&index) {
+            Some(pointer) => {
+                NodeInput::Owned(input.pointer(pointer).cloned().ok_or_else(|| {
+                    FlowError::NodeFailed(format!(
+                        "Branch {index} input has nothing at '{pointer}'"
+                    ))
+                })?)
+            }
+            None => NodeInput::Shared(input),
+        };
+        let node = Arc::clone(&self.nodes[index]);
+        let timeout = self.branch_timeouts.get(&index).copied().or(self.timeout);
+        let spawned = self.spawned;
+        let call = async move {
+            match spawned {
+                true => telemetry::spawn_node(node, index, input).await,
+                false => telemetry::call_node(&*node, index, input).await,
+            }
+        };
+        let Some(timeout) = timeout else {
+            return call.await;
+        };
+
+        tokio::pin!(call);
+        match tokio::time::timeout(timeout, &mut call).await {
+            Ok(result) => result,
+            Err(_) => match &self.late_policy {
+                LatePolicy::Placeholder(value) => {
+                    tracing::warn!("Branch {index} timed out after {timeout:?}; using placeholder");
+                    Ok(value.clone())
+                }
+                LatePolicy::Fail => Err(FlowError::Timeout(format!(
+                    "Branch {index} did not finish within {timeout:?}"
+                ))),
+                LatePolicy::Wait => {
+                    tracing::warn!("Branch {index} exceeded {timeout:?}; still waiting");
+                    call.await
+                }
+            },
+        }
     }
 
     /// Execute all nodes in parallel with the same input.
     ///
-    /// Each node receives a clone of the input and executes concurrently.
-    /// Results are collected into a JSON array in the same order as the nodes.
+    /// Each node receives the input through [`Node::call_shared`], or a clone
+    /// of the part selected with [`ParallelFlow::with_branch_input`], and
+    /// executes concurrently.
+    /// Results are collected into a JSON array in the same order as the nodes,
+    /// regardless of the order in which they finish. Branches that exceed
+    /// their timeout are handled according to the [`LatePolicy`].
     ///
     /// # Arguments
     ///
@@ -135,23 +1677,53 @@ impl ParallelFlow {
     ///
     /// # Returns
     ///
-    /// A JSON array containing the outputs from all nodes, or the first
-    /// error encountered.
+    /// A JSON array containing the outputs from all nodes (or an object keyed
+    /// by node name or [output key](ParallelFlow::with_output_key) if
+    /// [`ParallelFlow::labeled`] or an output key is set), or the first error
+    /// encountered ([every error](ParallelFlow::collect_errors) if set).
     pub async fn execute(&self, input: Value) -> Result<Value, FlowError> {
-        // Create futures for all nodes, each receiving a clone of the input
-        let futures: Vec<_> = self
-            .nodes
-            .iter()
-            .map(|node| node.call(input.clone()))
+        let span =
+            telemetry::flow_span("ParallelFlow", self.name.as_deref(), self.nodes.len(), None);
+        telemetry::in_flow_span(span, self.hooks.scope(self.run(input))).await
+    }
+
+    async fn run(&self, input: Value) -> Result<Value, FlowError> {
+        // Create futures for all nodes, sharing the input rather than
+        // cloning it for each
+        let input = Arc::new(input);
+        let futures: Vec<_> = (0..self.nodes.len())
+            .map(|index| self.run_branch(index, Arc::clone(&input)))
             .collect();
+        drop(input);
 
         // Execute all nodes concurrently
         let results = join_all(futures).await;
 
-        // Collect successful results or return first error
+        // Collect successful results, or the first error or all of them
         let mut values = Vec::new();
-        for result in results {
-            values.push(result?);
+        let mut errors = Vec::new();
+        for (node, result) in self.nodes.iter().zip(results) {
+            match result {
+                Ok(value) => values.push(value),
+                Err(e) if self.collect_errors => errors.push((node.name().to_string(), e)),
+                Err(e) => return Err(e),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(FlowError::Multiple(errors));
+        }
+
+        if self.labeled || !self.output_keys.is_empty() {
+            let mut labeled = Map::new();
+            for (index, value) in values.into_iter().enumerate() {
+                let key = self.key(index).unwrap_or_default();
+                if labeled.insert(key.to_string(), value).is_some() {
+                    return Err(FlowError::NodeFailed(format!(
+                        "Duplicate branch key '{key}'; override Node::name or set an output key to label it uniquely"
+                    )));
+                }
+            }
+            return Ok(Value::Object(labeled));
         }
 
         // Return as JSON array
@@ -0,0 +1,603 @@
+//! Graph-shaped flows with conditional edges and bounded loops.
+//!
+//! A [`GraphFlow`] runs named nodes and follows edges between them: after a
+//! node runs, the first of its outgoing edges whose [`Condition`] matches
+//! the node's output leads to the next node, and the run ends at a node with
+//! no matching edge. Edges may form cycles, such as a draft/review loop, as
+//! long as every cycle contains an edge with a maximum number of
+//! traversals. A run that would cross an edge more often fails with
+//! `FlowError::LoopLimit` instead of looping forever.
+
+use crate::diagram::{Diagram, Endpoint};
+use crate::error::FlowError;
+use crate::events::{self, EventSender, ExecutionEvent};
+use crate::executor::{FlowScheduler, Hooks, Middleware, NodeExecutor};
+use crate::node::Node;
+use crate::overlay::ExecutionOverlay;
+use crate::telemetry;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// When an [`Edge`] is followed.
+#[derive(Clone)]
+pub enum Condition {
+    /// Always.
+    Always,
+    /// When the output's `action` field equals this string.
+    Action(String),
+    /// When the value at a JSON pointer in the output equals a value.
+    Equals(String, Value),
+    /// When the output has a value at a JSON pointer.
+    Exists(String),
+    /// When a predicate on the output returns `true`, labelled for diagrams
+    /// and errors.
+    Predicate(String, Arc<dyn Fn(&Value) -> bool + Send + Sync>),
+}
+
+impl Condition {
+    /// A predicate on the node's output, with a label for diagrams.
+    pub fn when<F>(label: impl Into<String>, predicate: F) -> Self
+    where
+        F: Fn(&Value) -> bool + Send + Sync + 'static,
+    {
+        Condition::Predicate(label.into(), Arc::new(predicate))
+    }
+
+    /// Whether the condition holds for `output`.
+    pub fn matches(&self, output: &Value) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Action(action) => output["action"].as_str() == Some(action.as_str()),
+            Condition::Equals(pointer, value) => output.pointer(pointer) == Some(value),
+            Condition::Exists(pointer) => output.pointer(pointer).is_some(),
+            Condition::Predicate(_, predicate) => predicate(output),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Always => write!(f, "always"),
+            Condition::Action(action) => write!(f, "{action}"),
+            Condition::Equals(pointer, value) => write!(f, "{pointer} == {value}"),
+            Condition::Exists(pointer) => write!(f, "{pointer} exists"),
+            Condition::Predicate(label, _) => write!(f, "{label}"),
+        }
+    }
+}
+
+/// A transition between two named nodes of a [`GraphFlow`].
+#[derive(Clone)]
+pub struct Edge {
+    from: String,
+    to: String,
+    condition: Condition,
+    max_traversals: Option<usize>,
+}
+
+impl Edge {
+    /// An unconditional, unbounded edge from `from` to `to`.
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+            condition: Condition::Always,
+            max_traversals: None,
+        }
+    }
+
+    /// Only follow the edge when `condition` matches the output of `from`.
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = condition;
+        self
+    }
+
+    /// Fail the run if the edge would be followed more than `max` times.
+    ///
+    /// Every cycle in a graph needs at least one bounded edge.
+    pub fn with_max_traversals(mut self, max: usize) -> Self {
+        self.max_traversals = Some(max);
+        self
+    }
+
+    fn label(&self) -> Option<String> {
+        let condition = match self.condition {
+            Condition::Always => None,
+            _ => Some(self.condition.to_string()),
+        };
+        match (condition, self.max_traversals) {
+            (None, None) => None,
+            (Some(condition), None) => Some(condition),
+            (None, Some(max)) => Some(format!("max {max}")),
+            (Some(condition), Some(max)) => Some(format!("{condition}, max {max}")),
+        }
+    }
+}
+
+/// A flow whose nodes are connected by conditional edges.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::graph::{Condition, Edge, GraphFlow};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+/// use async_trait::async_trait;
+///
+/// /// Appends a paragraph to the draft.
+/// struct Write;
+///
+/// #[async_trait]
+/// impl Node for Write {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let paragraphs = input["paragraphs"].as_u64().unwrap_or(0) + 1;
+///         Ok(json!({"paragraphs": paragraphs}))
+///     }
+/// }
+///
+/// /// Asks for revisions until the draft has three paragraphs.
+/// struct Review;
+///
+/// #[async_trait]
+/// impl Node for Review {
+///     async fn call(&self, mut input: Value) -> Result<Value, FlowError> {
+///         let done = input["paragraphs"].as_u64() >= Some(3);
+///         input["action"] = json!(if done { "approve" } else { "revise" });
+///         Ok(input)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let graph = GraphFlow::new()
+///     .with_node("write", Box::new(Write))
+///     .with_node("review", Box::new(Review))
+///     .with_edge(Edge::new("write", "review"))?
+///     .with_edge(
+///         Edge::new("review", "write")
+///             .with_condition(Condition::Action("revise".into()))
+///             .with_max_traversals(5),
+///     )?;
+///
+/// let result = graph.execute(json!({})).await?;
+/// assert_eq!(result, json!({"paragraphs": 3, "action": "approve"}));
+///
+/// // A tighter bound stops the loop with a clear error
+/// let strict = GraphFlow::new()
+///     .with_node("write", Box::new(Write))
+///     .with_node("review", Box::new(Review))
+///     .with_edge(Edge::new("write", "review"))?
+///     .with_edge(
+///         Edge::new("review", "write")
+///             .with_condition(Condition::Action("revise".into()))
+///             .with_max_traversals(1),
+///     )?;
+/// let error = strict.execute(json!({})).await.unwrap_err();
+/// assert!(matches!(error, FlowError::LoopLimit(_)));
+/// # Ok(())
+/// # }
+/// ```
+pub struct GraphFlow {
+    nodes: Vec<(String, Box<dyn Node>)>,
+    edges: Vec<Edge>,
+    start: Option<String>,
+    max_steps: usize,
+    name: Option<String>,
+    hooks: Hooks,
+}
+
+impl Default for GraphFlow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphFlow {
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            start: None,
+            max_steps: 1000,
+            name: None,
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Add a node under a unique name. The first node added is the entry
+    /// point unless [`GraphFlow::with_start`] says otherwise.
+    pub fn with_node(mut self, name: impl Into<String>, node: Box<dyn Node>) -> Self {
+        let name = name.into();
+        self.nodes.retain(|(existing, _)| *existing != name);
+        self.nodes.push((name, node));
+        self
+    }
+
+    /// Add an edge between two nodes that have already been added.
+    ///
+    /// Edges are tried in the order they are added.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if either end is not a node of the
+    /// graph.
+    pub fn with_edge(mut self, edge: Edge) -> Result<Self, FlowError> {
+        for end in [&edge.from, &edge.to] {
+            if self.index_of(end).is_none() {
+                return Err(FlowError::NodeFailed(format!(
+                    "Edge {} -> {} refers to unknown node '{end}'",
+                    edge.from, edge.to
+                )));
+            }
+        }
+        self.edges.push(edge);
+        Ok(self)
+    }
+
+    /// Start runs at the named node instead of the first one added.
+    pub fn with_start(mut self, name: impl Into<String>) -> Self {
+        self.start = Some(name.into());
+        self
+    }
+
+    /// Fail runs that execute more than `max_steps` nodes. Defaults to 1000.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Name the flow in tracing spans and exported telemetry.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Perform node calls, including those of nested flows and batches, with
+    /// a custom [`NodeExecutor`].
+    pub fn with_executor(mut self, executor: impl NodeExecutor + 'static) -> Self {
+        self.hooks.set_executor(executor);
+        self
+    }
+
+    /// Admit node calls, including those of nested flows and batches, with a
+    /// custom [`FlowScheduler`]. Pass an `Arc` to share one scheduler across
+    /// flows.
+    pub fn with_scheduler(mut self, scheduler: impl FlowScheduler + 'static) -> Self {
+        self.hooks.set_scheduler(scheduler);
+        self
+    }
+
+    /// Run node calls, including those of nested flows and batches, inside
+    /// a [`Middleware`]. Middleware added first runs outermost.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.hooks.add_middleware(middleware);
+        self
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|(existing, _)| existing == name)
+    }
+
+    /// Check that the entry point exists and every cycle has a bounded edge.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` naming the first problem found.
+    pub fn validate(&self) -> Result<(), FlowError> {
+        self.entry()?;
+
+        // Depth-first search over unbounded edges only: any cycle found
+        // there has no bound that could stop it
+        let unbounded: Vec<(usize, usize)> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.max_traversals.is_none())
+            .filter_map(|edge| Some((self.index_of(&edge.from)?, self.index_of(&edge.to)?)))
+            .collect();
+        let mut state = vec![0u8; self.nodes.len()];
+        let mut path = Vec::new();
+        for root in 0..self.nodes.len() {
+            if let Some(cycle) = find_cycle(root, &unbounded, &mut state, &mut path) {
+                let names: Vec<&str> = cycle.iter().map(|&i| self.nodes[i].0.as_str()).collect();
+                return Err(FlowError::NodeFailed(format!(
+                    "Cycle {} has no bounded edge; set max traversals on one of its edges",
+                    names.join(" -> ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn entry(&self) -> Result<usize, FlowError> {
+        match &self.start {
+            Some(start) => self
+                .index_of(start)
+                .ok_or_else(|| FlowError::NodeFailed(format!("Unknown start node '{start}'"))),
+            None if self.nodes.is_empty() => {
+                Err(FlowError::NodeFailed("Graph has no nodes".to_string()))
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Execute the graph from its entry point.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input of the entry node
+    ///
+    /// # Returns
+    ///
+    /// The output of the node at which the run ended, or the first error.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the graph is invalid (see
+    /// [`GraphFlow::validate`]), `FlowError::LoopLimit` if an edge bound or
+    /// the step limit is exceeded, or the first node error.
+    pub async fn execute(&self, input: Value) -> Result<Value, FlowError> {
+        let span = telemetry::flow_span("GraphFlow", self.name.as_deref(), self.nodes.len(), None);
+        let body = async move {
+            self.validate()?;
+            self.run(self.entry()?, None, input, None).await
+        };
+        telemetry::in_flow_span(span, self.hooks.scope(body)).await
+    }
+
+    /// Execute the graph, sending an [`ExecutionEvent`] when each node
+    /// starts and ends and one with the result, as
+    /// [`Flow::execute_with_events`](crate::Flow::execute_with_events) does.
+    ///
+    /// Event indices are node positions in the order nodes were added, so
+    /// the events show which branch each run took.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as [`GraphFlow::execute`].
+    pub async fn execute_with_events(
+        &self,
+        input: Value,
+        events: &EventSender,
+    ) -> Result<Value, FlowError> {
+        let span = telemetry::flow_span("GraphFlow", self.name.as_deref(), self.nodes.len(), None);
+        let body = async move {
+            self.validate()?;
+            self.run(self.entry()?, None, input, Some(events)).await
+        };
+        let result = telemetry::in_flow_span(span, self.hooks.scope(body)).await;
+        let _ = events.send(match &result {
+            Ok(output) => ExecutionEvent::FinalResult {
+                output: output.clone(),
+            },
+            Err(e) => ExecutionEvent::Error {
+                error: e.to_string(),
+            },
+        });
+        result
+    }
+
+    /// Execute the subgraph reachable from a named node, with a synthetic
+    /// input.
+    ///
+    /// This lets one stage of a large graph be iterated on or tested
+    /// without running the nodes before it. Edges are followed as in
+    /// [`GraphFlow::execute`], with fresh traversal counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The node to start at
+    /// * `until` - A node after which the run stops, or `None` to run until
+    ///   no edge matches
+    /// * `input` - The input of the entry node
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if a node is unknown or the graph is
+    /// invalid, and otherwise the same errors as [`GraphFlow::execute`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::graph::{Edge, GraphFlow};
+    /// use rustyflow::{FlowError, Node};
+    /// use serde_json::{json, Value};
+    /// use async_trait::async_trait;
+    ///
+    /// struct Add(i64);
+    ///
+    /// #[async_trait]
+    /// impl Node for Add {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         Ok(json!(input.as_i64().unwrap_or(0) + self.0))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let graph = GraphFlow::new()
+    ///     .with_node("a", Box::new(Add(1)))
+    ///     .with_node("b", Box::new(Add(10)))
+    ///     .with_node("c", Box::new(Add(100)))
+    ///     .with_edge(Edge::new("a", "b"))?
+    ///     .with_edge(Edge::new("b", "c"))?;
+    ///
+    /// assert_eq!(graph.execute_from("b", None, json!(0)).await?, json!(110));
+    /// assert_eq!(graph.execute_from("b", Some("b"), json!(0)).await?, json!(10));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_from(
+        &self,
+        entry: &str,
+        until: Option<&str>,
+        input: Value,
+    ) -> Result<Value, FlowError> {
+        let find = |node: &str| {
+            self.index_of(node)
+                .ok_or_else(|| FlowError::NodeFailed(format!("Unknown node '{node}'")))
+        };
+        let start = find(entry)?;
+        let stop = until.map(find).transpose()?;
+
+        let span = telemetry::flow_span("GraphFlow", self.name.as_deref(), self.nodes.len(), None);
+        let body = async move {
+            self.validate()?;
+            self.run(start, stop, input, None).await
+        };
+        telemetry::in_flow_span(span, self.hooks.scope(body)).await
+    }
+
+    async fn run(
+        &self,
+        mut current: usize,
+        stop: Option<usize>,
+        mut value: Value,
+        events: Option<&EventSender>,
+    ) -> Result<Value, FlowError> {
+        let mut traversals: HashMap<usize, usize> = HashMap::new();
+
+        for _ in 0..self.max_steps {
+            let (name, node) = &self.nodes[current];
+            let call = telemetry::call_node(node.as_ref(), current, value);
+            value = match events {
+                Some(sender) => {
+                    let _ = sender.send(ExecutionEvent::NodeStart {
+                        index: current,
+                        name: name.clone(),
+                    });
+                    let started = Instant::now();
+                    let result = events::scope(sender, current, call).await;
+                    let _ = sender.send(ExecutionEvent::NodeEnd {
+                        index: current,
+                        name: name.clone(),
+                        duration_ms: started.elapsed().as_millis() as u64,
+                        error: result.as_ref().err().map(ToString::to_string),
+                    });
+                    result?
+                }
+                None => call.await?,
+            };
+            if stop == Some(current) {
+                return Ok(value);
+            }
+
+            let next = self
+                .edges
+                .iter()
+                .enumerate()
+                .find(|(_, edge)| edge.from == *name && edge.condition.matches(&value));
+            let Some((edge_index, edge)) = next else {
+                return Ok(value);
+            };
+
+            let count = traversals.entry(edge_index).or_insert(0);
+            *count += 1;
+            if let Some(max) = edge.max_traversals {
+                if *count > max {
+                    return Err(FlowError::LoopLimit(format!(
+                        "Edge {} -> {} would be traversed more than {max} times",
+                        edge.from, edge.to
+                    )));
+                }
+            }
+            tracing::debug!(from = %edge.from, to = %edge.to, traversal = *count, "Following edge");
+            current = self.index_of(&edge.to).expect("edge ends are validated");
+        }
+
+        Err(FlowError::LoopLimit(format!(
+            "Graph executed {} steps without finishing",
+            self.max_steps
+        )))
+    }
+
+    /// Render the graph as a Graphviz DOT digraph.
+    ///
+    /// Edges are labelled with their condition and bound; nodes without
+    /// outgoing edges lead to the output.
+    pub fn to_dot(&self) -> String {
+        self.diagram().to_dot()
+    }
+
+    /// Render the graph as a Mermaid flowchart.
+    pub fn to_mermaid(&self) -> String {
+        self.diagram().to_mermaid()
+    }
+
+    /// Render the graph as a Mermaid flowchart overlaid with the state of a
+    /// run: each step gets a `running`, `succeeded` or `failed` class and its
+    /// time, and the edges the run took are highlighted.
+    pub fn to_live_mermaid(&self, overlay: &ExecutionOverlay) -> String {
+        self.diagram().to_live_mermaid(overlay)
+    }
+
+    /// Describe the graph and the state of a run as JSON for a UI to draw,
+    /// in the format shown in [`overlay`](crate::overlay).
+    pub fn live_view(&self, overlay: &ExecutionOverlay) -> Value {
+        self.diagram().live_view(overlay)
+    }
+
+    fn diagram(&self) -> Diagram {
+        let mut diagram = Diagram::new(self.name.as_deref());
+        let endpoints: Vec<Endpoint> = self
+            .nodes
+            .iter()
+            .map(|(name, _)| diagram.node(name))
+            .collect();
+        if let Ok(entry) = self.entry() {
+            diagram.edge(Endpoint::Input, endpoints[entry], None);
+        }
+        for edge in &self.edges {
+            if let (Some(from), Some(to)) = (self.index_of(&edge.from), self.index_of(&edge.to)) {
+                diagram.edge(endpoints[from], endpoints[to], edge.label().as_deref());
+            }
+        }
+        for (index, (name, _)) in self.nodes.iter().enumerate() {
+            let exits = self.edges.iter().filter(|edge| edge.from == *name);
+            let always_continues = exits
+                .clone()
+                .any(|edge| matches!(edge.condition, Condition::Always));
+            if !always_continues {
+                diagram.edge(endpoints[index], Endpoint::Output, None);
+            }
+        }
+        diagram
+    }
+}
+
+/// Find a cycle reachable from `node`, returning its nodes in order.
+///
+/// `state` marks nodes as unvisited (0), on the current path (1) or done (2).
+fn find_cycle(
+    node: usize,
+    edges: &[(usize, usize)],
+    state: &mut [u8],
+    path: &mut Vec<usize>,
+) -> Option<Vec<usize>> {
+    match state[node] {
+        1 => {
+            let start = path.iter().position(|&n| n == node).unwrap_or(0);
+            let mut cycle = path[start..].to_vec();
+            cycle.push(node);
+            return Some(cycle);
+        }
+        2 => return None,
+        _ => {}
+    }
+    state[node] = 1;
+    path.push(node);
+    for &(from, to) in edges {
+        if from == node {
+            if let Some(cycle) = find_cycle(to, edges, state, path) {
+                return Some(cycle);
+            }
+        }
+    }
+    path.pop();
+    state[node] = 2;
+    None
+}
@@ -0,0 +1,313 @@
+//! GraphQL client node.
+//!
+//! This module is available with the `reqwest` feature. It provides
+//! [`GraphQlNode`] for running queries and mutations against a GraphQL
+//! endpoint with variables bound from the flow payload.
+
+use crate::error::FlowError;
+use crate::node::{HealthCheck, Node};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// A node that executes a GraphQL operation.
+///
+/// By default the input object is sent as the operation's variables. Use
+/// [`GraphQlNode::with_variable`] to bind individual variables from JSON
+/// pointers into the input instead.
+///
+/// With [`GraphQlNode::with_persisted_query`], the node uses Automatic
+/// Persisted Queries: it first sends only the SHA-256 hash of the document and
+/// falls back to sending the full document if the server does not know it.
+///
+/// Transport failures, `429` and `5xx` responses are retried with exponential
+/// backoff. The output is the `data` field of the response; GraphQL errors
+/// without data fail the node.
+///
+/// The node's [`HealthCheck`] sends the endpoint a `{ __typename }` query.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use rustyflow::graphql::GraphQlNode;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// # async fn example() -> Result<(), FlowError> {
+/// let node = GraphQlNode::new(
+///     "https://api.example.com/graphql",
+///     "query Repo($owner: String!, $name: String!) { repository(owner: $owner, name: $name) { stars } }",
+/// )
+/// .with_variable("owner", "/repo/owner")
+/// .with_variable("name", "/repo/name")
+/// .with_persisted_query()
+/// .with_retries(3);
+///
+/// let data = node.call(json!({"repo": {"owner": "rust-lang", "name": "rust"}})).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct GraphQlNode {
+    client: Client,
+    endpoint: String,
+    query: String,
+    operation_name: Option<String>,
+    headers: HeaderMap,
+    bindings: Vec<(String, String)>,
+    persisted: bool,
+    max_retries: usize,
+    retry_delay: Duration,
+}
+
+impl GraphQlNode {
+    /// Create a node for the given endpoint and GraphQL document.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The GraphQL HTTP endpoint
+    /// * `query` - The query or mutation document
+    pub fn new(endpoint: impl Into<String>, query: impl Into<String>) -> Self {
+        Self {
+            client: crate::http::shared_client(),
+            endpoint: endpoint.into(),
+            query: query.into(),
+            operation_name: None,
+            headers: HeaderMap::new(),
+            bindings: Vec::new(),
+            persisted: false,
+            max_retries: 0,
+            retry_delay: Duration::from_millis(200),
+        }
+    }
+
+    /// Use a preconfigured HTTP client.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Select the operation to run when the document defines several.
+    pub fn with_operation_name(mut self, name: impl Into<String>) -> Self {
+        self.operation_name = Some(name.into());
+        self
+    }
+
+    /// Send a header (such as `Authorization`) with every request.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the name or value is not a valid
+    /// HTTP header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self, FlowError> {
+        let name = HeaderName::try_from(name)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid header name '{name}': {e}")))?;
+        let value = HeaderValue::try_from(value)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid header value: {e}")))?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Bind the variable `name` to the value at `pointer` in the input.
+    pub fn with_variable(mut self, name: impl Into<String>, pointer: impl Into<String>) -> Self {
+        self.bindings.push((name.into(), pointer.into()));
+        self
+    }
+
+    /// Use Automatic Persisted Queries.
+    pub fn with_persisted_query(mut self) -> Self {
+        self.persisted = true;
+        self
+    }
+
+    /// Retry transient failures up to `max_retries` times.
+    pub fn with_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry; it doubles on each attempt.
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Run the operation and deserialize its `data` into `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Node::call`], or `FlowError::SerdeError`
+    /// if the data does not match `T`.
+    pub async fn execute<T: DeserializeOwned>(&self, input: Value) -> Result<T, FlowError> {
+        Ok(serde_json::from_value(self.call(input).await?)?)
+    }
+
+    fn variables(&self, input: &Value) -> Result<Value, FlowError> {
+        if self.bindings.is_empty() {
+            return Ok(match input {
+                Value::Object(_) => input.clone(),
+                _ => Value::Null,
+            });
+        }
+        let mut variables = Map::new();
+        for (name, pointer) in &self.bindings {
+            let value = input.pointer(pointer).ok_or_else(|| {
+                FlowError::NodeFailed(format!(
+                    "Missing value at {pointer} for GraphQL variable '{name}'"
+                ))
+            })?;
+            variables.insert(name.clone(), value.clone());
+        }
+        Ok(Value::Object(variables))
+    }
+
+    fn body(&self, variables: &Value, include_query: bool) -> Value {
+        let mut body = Map::new();
+        if include_query {
+            body.insert("query".to_string(), json!(self.query));
+        }
+        if !variables.is_null() {
+            body.insert("variables".to_string(), variables.clone());
+        }
+        if let Some(name) = &self.operation_name {
+            body.insert("operationName".to_string(), json!(name));
+        }
+        if self.persisted {
+            let hash = Sha256::digest(self.query.as_bytes());
+            let hash: String = hash.iter().map(|b| format!("{b:02x}")).collect();
+            body.insert(
+                "extensions".to_string(),
+                json!({ "persistedQuery": { "version": 1, "sha256Hash": hash } }),
+            );
+        }
+        Value::Object(body)
+    }
+
+    /// POST a request body, retrying transient failures.
+    async fn post(&self, body: &Value) -> Result<Value, FlowError> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .client
+                .post(&self.endpoint)
+                .headers(self.headers.clone())
+                .json(body)
+                .send()
+                .await;
+
+            let retryable = match result {
+                Ok(response) => {
+                    let status = response.status();
+                    if status.is_success() || status == StatusCode::BAD_REQUEST {
+                        // GraphQL servers report query errors in the body
+                        return response.json().await.map_err(|e| {
+                            FlowError::NodeFailed(format!("Invalid GraphQL response: {e}"))
+                        });
+                    }
+                    let text = response.text().await.unwrap_or_default();
+                    let message = format!("GraphQL request failed with status {status}: {text}");
+                    let error = if status == StatusCode::TOO_MANY_REQUESTS {
+                        FlowError::RateLimited(message)
+                    } else {
+                        FlowError::NodeFailed(message)
+                    };
+                    if status != StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+                        return Err(error);
+                    }
+                    error
+                }
+                Err(e) => FlowError::NodeFailed(format!("GraphQL request failed: {e}")),
+            };
+
+            if attempt >= self.max_retries {
+                return Err(retryable);
+            }
+            attempt += 1;
+            tracing::warn!("Retrying GraphQL request (attempt {attempt}): {retryable}");
+            crate::report::record_retry();
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+/// Returns `true` if the server does not recognize a persisted query hash.
+fn persisted_query_not_found(response: &Value) -> bool {
+    response["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|error| {
+            error["message"] == "PersistedQueryNotFound"
+                || error["extensions"]["code"] == "PERSISTED_QUERY_NOT_FOUND"
+        })
+}
+
+#[async_trait]
+impl Node for GraphQlNode {
+    /// Execute the GraphQL operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if a bound variable is missing, the
+    /// request fails after all retries, or the response has errors and no data.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let variables = self.variables(&input)?;
+
+        let mut response = self.post(&self.body(&variables, !self.persisted)).await?;
+        if self.persisted && persisted_query_not_found(&response) {
+            response = self.post(&self.body(&variables, true)).await?;
+        }
+
+        let errors = response["errors"].as_array().filter(|e| !e.is_empty());
+        match (response.get("data"), errors) {
+            (Some(data), Some(errors)) if !data.is_null() => {
+                tracing::warn!("GraphQL returned partial data with errors: {errors:?}");
+                Ok(data.clone())
+            }
+            (_, Some(errors)) => {
+                let messages: Vec<&str> = errors
+                    .iter()
+                    .filter_map(|e| e["message"].as_str())
+                    .collect();
+                Err(FlowError::NodeFailed(format!(
+                    "GraphQL errors: {}",
+                    messages.join("; ")
+                )))
+            }
+            (Some(data), None) => Ok(data.clone()),
+            (None, None) => Err(FlowError::NodeFailed(
+                "GraphQL response has neither data nor errors".to_string(),
+            )),
+        }
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl HealthCheck for GraphQlNode {
+    /// Check that the endpoint answers the smallest possible query.
+    ///
+    /// # Errors
+    ///
+    /// Returns the request's error after all retries, or
+    /// `FlowError::NodeFailed` if the endpoint answers with errors.
+    async fn check(&self) -> Result<(), FlowError> {
+        let response = self.post(&json!({ "query": "{ __typename }" })).await?;
+        match response["errors"].as_array().filter(|e| !e.is_empty()) {
+            Some(errors) => Err(FlowError::NodeFailed(format!(
+                "GraphQL endpoint {} is unhealthy: {}",
+                self.endpoint,
+                Value::Array(errors.clone())
+            ))),
+            None => Ok(()),
+        }
+    }
+}
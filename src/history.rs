@@ -0,0 +1,506 @@
+//! Run history and aggregated usage reports.
+//!
+//! A [`HistoryStore`] keeps one [`RunRecord`] per finished flow run: which
+//! flow ran, for which tenant, how long it took, whether it failed, and the
+//! tokens and cost spent on each model. [`UsageReport`] aggregates the runs
+//! of a time range by flow, tenant and/or model and exports the result as CSV
+//! or JSON, for example for monthly billing. Runs can also keep the
+//! conversation an agent had, which [`transcript`](crate::transcript)
+//! exports for review, a score and feedback, which
+//! [`dataset`](crate::dataset) uses to pick runs worth training on, and the
+//! output lines its nodes recorded.
+//!
+//! With the `sqlite` feature, `SqliteCheckpointStore` also implements
+//! [`HistoryStore`], keeping history next to the checkpoints of each run.
+
+use crate::error::FlowError;
+use crate::llm::{Message, Usage};
+use crate::report::{ExecutionReport, LogStream};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Tokens and cost spent on one model during a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    /// The model name.
+    pub model: String,
+    /// Tokens consumed by prompts.
+    pub prompt_tokens: u64,
+    /// Tokens generated in completions.
+    pub completion_tokens: u64,
+    /// Cost in the caller's unit, such as dollars.
+    pub cost: f64,
+}
+
+/// An output line a node of a run recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeLog {
+    /// The position of the node in the flow.
+    pub index: usize,
+    /// The node's [`Node::name`](crate::node::Node::name).
+    pub node: String,
+    /// The stream the line was written to.
+    pub stream: LogStream,
+    /// The line.
+    pub line: String,
+}
+
+/// One finished flow run.
+///
+/// Timestamps are milliseconds since the UNIX epoch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// The run id.
+    pub run_id: String,
+    /// The name of the flow that ran.
+    pub flow: String,
+    /// The tenant the run was made for, if any.
+    pub tenant: Option<String>,
+    /// When the run started.
+    pub started_at: u64,
+    /// How long the run took, in milliseconds.
+    pub duration_ms: u64,
+    /// The error message, if the run failed.
+    pub error: Option<String>,
+    /// Usage per model.
+    pub usage: Vec<ModelUsage>,
+    /// The conversation of the run's agent, if one was kept.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transcript: Vec<Message>,
+    /// A quality score given to the run, such as an evaluator grade or a
+    /// user rating.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// Feedback left on the run, such as a reviewer comment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<String>,
+    /// Output lines the run's nodes recorded, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<NodeLog>,
+}
+
+impl RunRecord {
+    /// Create a successful record of a run that started now.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - Identifies the run
+    /// * `flow` - The name of the flow
+    pub fn new(run_id: impl Into<String>, flow: impl Into<String>) -> Self {
+        Self {
+            run_id: run_id.into(),
+            flow: flow.into(),
+            tenant: None,
+            started_at: now_millis(),
+            duration_ms: 0,
+            error: None,
+            usage: Vec::new(),
+            transcript: Vec::new(),
+            score: None,
+            feedback: None,
+            logs: Vec::new(),
+        }
+    }
+
+    /// Attribute the run to a tenant.
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// Set the start time, in milliseconds since the UNIX epoch.
+    pub fn with_started_at(mut self, started_at: u64) -> Self {
+        self.started_at = started_at;
+        self
+    }
+
+    /// Set how long the run took.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration_ms = duration.as_millis() as u64;
+        self
+    }
+
+    /// Mark the run as failed.
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// Keep the conversation of the run's agent, such as the `transcript`
+    /// of a [`ReActAgent`](crate::ReActAgent) built with
+    /// [`with_transcript`](crate::ReActAgent::with_transcript).
+    pub fn with_transcript(mut self, transcript: Vec<Message>) -> Self {
+        self.transcript = transcript;
+        self
+    }
+
+    /// Keep the output lines the nodes of a traced run recorded with
+    /// [`record_log`](crate::report::record_log).
+    pub fn with_logs(mut self, report: &ExecutionReport) -> Self {
+        self.logs = report
+            .nodes
+            .iter()
+            .flat_map(|node| {
+                node.logs.iter().map(|log| NodeLog {
+                    index: node.index,
+                    node: node.name.clone(),
+                    stream: log.stream,
+                    line: log.line.clone(),
+                })
+            })
+            .collect();
+        self
+    }
+
+    /// Score the run, for example to select it for a
+    /// [`DatasetBuilder`](crate::dataset::DatasetBuilder).
+    pub fn with_score(mut self, score: f64) -> Self {
+        self.score = Some(score);
+        self
+    }
+
+    /// Attach feedback to the run.
+    pub fn with_feedback(mut self, feedback: impl Into<String>) -> Self {
+        self.feedback = Some(feedback.into());
+        self
+    }
+
+    /// Add usage for a model, merging it with earlier usage of the same
+    /// model.
+    pub fn with_usage(mut self, model: impl Into<String>, usage: Usage, cost: f64) -> Self {
+        let model = model.into();
+        match self.usage.iter_mut().find(|entry| entry.model == model) {
+            Some(entry) => {
+                entry.prompt_tokens += u64::from(usage.prompt_tokens);
+                entry.completion_tokens += u64::from(usage.completion_tokens);
+                entry.cost += cost;
+            }
+            None => self.usage.push(ModelUsage {
+                model,
+                prompt_tokens: u64::from(usage.prompt_tokens),
+                completion_tokens: u64::from(usage.completion_tokens),
+                cost,
+            }),
+        }
+        self
+    }
+}
+
+/// Storage for the records of finished runs.
+#[async_trait]
+pub trait HistoryStore: Send + Sync {
+    /// Store a run, replacing any earlier record with the same run id.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the record cannot be stored.
+    async fn record(&self, run: &RunRecord) -> Result<(), FlowError>;
+
+    /// List the runs that started in `[from, to)`, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Checkpoint` if the store cannot be read.
+    async fn runs_between(&self, from: u64, to: u64) -> Result<Vec<RunRecord>, FlowError>;
+}
+
+/// A [`HistoryStore`] held in process memory.
+#[derive(Debug, Default)]
+pub struct InMemoryHistoryStore {
+    runs: Mutex<BTreeMap<String, RunRecord>>,
+}
+
+impl InMemoryHistoryStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl HistoryStore for InMemoryHistoryStore {
+    async fn record(&self, run: &RunRecord) -> Result<(), FlowError> {
+        self.runs
+            .lock()
+            .unwrap()
+            .insert(run.run_id.clone(), run.clone());
+        Ok(())
+    }
+
+    async fn runs_between(&self, from: u64, to: u64) -> Result<Vec<RunRecord>, FlowError> {
+        let mut runs: Vec<RunRecord> = self
+            .runs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|run| run.started_at >= from && run.started_at < to)
+            .cloned()
+            .collect();
+        runs.sort_by(|a, b| (a.started_at, &a.run_id).cmp(&(b.started_at, &b.run_id)));
+        Ok(runs)
+    }
+}
+
+/// A dimension to group usage by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// The flow name.
+    Flow,
+    /// The tenant; runs without one form their own group.
+    Tenant,
+    /// The model. A run that used several models counts towards each.
+    Model,
+}
+
+/// The flow, tenant and model of a group, where grouped by.
+type GroupKey = (Option<String>, Option<String>, Option<String>);
+
+/// Aggregated usage of one group.
+///
+/// Dimensions that the report is not grouped by are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRow {
+    /// The flow name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow: Option<String>,
+    /// The tenant, or an empty string for runs without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// The model name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Number of runs.
+    pub runs: u64,
+    /// Number of failed runs.
+    pub errors: u64,
+    /// Tokens consumed by prompts.
+    pub prompt_tokens: u64,
+    /// Tokens generated in completions.
+    pub completion_tokens: u64,
+    /// Total cost.
+    pub cost: f64,
+}
+
+/// Usage aggregated over a time range.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::history::{GroupBy, HistoryStore, InMemoryHistoryStore, RunRecord, UsageReport};
+/// use rustyflow::llm::Usage;
+/// use rustyflow::FlowError;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let store = InMemoryHistoryStore::new();
+/// let usage = Usage { prompt_tokens: 100, completion_tokens: 20 };
+/// store
+///     .record(&RunRecord::new("r1", "support").with_tenant("acme").with_started_at(1_000)
+///         .with_usage("gpt-4o-mini", usage, 0.01))
+///     .await?;
+/// store
+///     .record(&RunRecord::new("r2", "support").with_tenant("acme").with_started_at(2_000)
+///         .with_usage("gpt-4o-mini", usage, 0.01).with_error("timeout"))
+///     .await?;
+///
+/// let report = UsageReport::build(&store, 0, 10_000, &[GroupBy::Tenant, GroupBy::Model]).await?;
+/// assert_eq!(report.rows.len(), 1);
+/// assert_eq!(report.rows[0].runs, 2);
+/// assert_eq!(report.rows[0].errors, 1);
+/// assert_eq!(report.rows[0].prompt_tokens, 200);
+///
+/// let csv = report.to_csv();
+/// assert!(csv.starts_with("tenant,model,runs,errors,prompt_tokens,completion_tokens,cost\n"));
+/// assert!(csv.contains("acme,gpt-4o-mini,2,1,200,40,0.02"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Start of the range, inclusive, in milliseconds since the UNIX epoch.
+    pub from: u64,
+    /// End of the range, exclusive, in milliseconds since the UNIX epoch.
+    pub to: u64,
+    /// The dimensions the rows are grouped by.
+    pub group_by: Vec<GroupBy>,
+    /// One row per group, sorted by group.
+    pub rows: Vec<UsageRow>,
+}
+
+impl UsageReport {
+    /// Aggregate the runs of a store that started in `[from, to)`.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - The history to read
+    /// * `from` - Start of the range, in milliseconds since the UNIX epoch
+    /// * `to` - End of the range, exclusive
+    /// * `group_by` - The dimensions to group by; empty for a single total
+    ///
+    /// # Errors
+    ///
+    /// Returns the store's error if the runs cannot be read.
+    pub async fn build(
+        store: &dyn HistoryStore,
+        from: u64,
+        to: u64,
+        group_by: &[GroupBy],
+    ) -> Result<Self, FlowError> {
+        let runs = store.runs_between(from, to).await?;
+        Ok(Self::from_runs(&runs, from, to, group_by))
+    }
+
+    /// Aggregate runs that have already been loaded.
+    ///
+    /// Runs outside `[from, to)` are ignored.
+    pub fn from_runs(runs: &[RunRecord], from: u64, to: u64, group_by: &[GroupBy]) -> Self {
+        let by_model = group_by.contains(&GroupBy::Model);
+        let key_of = |run: &RunRecord, model: Option<&str>| {
+            let pick = |dimension: GroupBy, value: &str| {
+                group_by.contains(&dimension).then(|| value.to_string())
+            };
+            (
+                pick(GroupBy::Flow, &run.flow),
+                pick(GroupBy::Tenant, run.tenant.as_deref().unwrap_or("")),
+                model.map(str::to_string),
+            )
+        };
+
+        let mut groups: BTreeMap<GroupKey, UsageRow> = BTreeMap::new();
+        for run in runs
+            .iter()
+            .filter(|run| run.started_at >= from && run.started_at < to)
+        {
+            // Without model grouping, a run's models are folded into one entry
+            let entries: Vec<(Option<&str>, u64, u64, f64)> = if by_model {
+                run.usage
+                    .iter()
+                    .map(|u| {
+                        (
+                            Some(u.model.as_str()),
+                            u.prompt_tokens,
+                            u.completion_tokens,
+                            u.cost,
+                        )
+                    })
+                    .collect()
+            } else {
+                vec![run.usage.iter().fold((None, 0, 0, 0.0), |acc, u| {
+                    (
+                        None,
+                        acc.1 + u.prompt_tokens,
+                        acc.2 + u.completion_tokens,
+                        acc.3 + u.cost,
+                    )
+                })]
+            };
+            for (model, prompt_tokens, completion_tokens, cost) in entries {
+                let key = key_of(run, model);
+                let row = groups.entry(key.clone()).or_insert_with(|| UsageRow {
+                    flow: key.0,
+                    tenant: key.1,
+                    model: key.2,
+                    runs: 0,
+                    errors: 0,
+                    prompt_tokens: 0,
+                    completion_tokens: 0,
+                    cost: 0.0,
+                });
+                row.runs += 1;
+                row.errors += u64::from(run.error.is_some());
+                row.prompt_tokens += prompt_tokens;
+                row.completion_tokens += completion_tokens;
+                row.cost += cost;
+            }
+        }
+
+        Self {
+            from,
+            to,
+            group_by: group_by.to_vec(),
+            rows: groups.into_values().collect(),
+        }
+    }
+
+    /// Render the rows as CSV with a header line.
+    ///
+    /// Only the grouped dimensions get a column, in flow, tenant, model
+    /// order.
+    pub fn to_csv(&self) -> String {
+        let dimensions = [GroupBy::Flow, GroupBy::Tenant, GroupBy::Model]
+            .into_iter()
+            .filter(|dimension| self.group_by.contains(dimension))
+            .collect::<Vec<_>>();
+
+        let mut header: Vec<&str> = dimensions
+            .iter()
+            .map(|dimension| match dimension {
+                GroupBy::Flow => "flow",
+                GroupBy::Tenant => "tenant",
+                GroupBy::Model => "model",
+            })
+            .collect();
+        header.extend([
+            "runs",
+            "errors",
+            "prompt_tokens",
+            "completion_tokens",
+            "cost",
+        ]);
+
+        let mut out = header.join(",");
+        out.push('\n');
+        for row in &self.rows {
+            let mut fields: Vec<String> = dimensions
+                .iter()
+                .map(|dimension| {
+                    let value = match dimension {
+                        GroupBy::Flow => &row.flow,
+                        GroupBy::Tenant => &row.tenant,
+                        GroupBy::Model => &row.model,
+                    };
+                    csv_field(value.as_deref().unwrap_or(""))
+                })
+                .collect();
+            fields.extend([
+                row.runs.to_string(),
+                row.errors.to_string(),
+                row.prompt_tokens.to_string(),
+                row.completion_tokens.to_string(),
+                row.cost.to_string(),
+            ]);
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Render the report as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if serialization fails.
+    pub fn to_json(&self) -> Result<String, FlowError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub(crate) fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,888 @@
+//! HTTP nodes for calling REST APIs from flows.
+//!
+//! This module is available with the `reqwest` feature. It provides
+//! [`HttpRequestNode`], which makes one templated request and returns the
+//! response, [`PaginatedFetch`], which follows paginated APIs
+//! automatically, and the HTTP client the crate's network nodes share.
+//!
+//! Nodes that talk to HTTP APIs, such as [`HttpRequestNode`], [`PaginatedFetch`], the
+//! [`graphql`](crate::graphql) node, [`OpenAiEmbedder`](crate::embeddings::OpenAiEmbedder)
+//! and the Qdrant store, as well as [`HttpPackStore`](crate::pack_store::HttpPackStore)
+//! and the webhook notifiers of [`alert`](crate::alert), use [`shared_client`]
+//! unless given a client of their own, so they draw from one connection pool
+//! instead of each opening new connections. The pool keeps idle connections alive with TCP and HTTP/2
+//! keep-alives, negotiates HTTP/2 where the server supports it, and can be
+//! filled before the first node call with [`warm_up`] and kept warm with
+//! [`keep_warm`], so calls do not pay for DNS, TCP and TLS setup.
+
+use crate::error::FlowError;
+use crate::node::Node;
+use crate::stream::{Source, ValueStream};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use minijinja::{Environment, UndefinedBehavior};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, LINK};
+pub use reqwest::Method;
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// What an [`HttpRequestNode`] sends as the request body.
+enum RequestBody {
+    /// A template rendered with the input, sent as text.
+    Template,
+    /// The input value at a JSON pointer, sent as JSON.
+    Json(String),
+}
+
+/// A node that makes one HTTP request and returns the response.
+///
+/// The URL, header values and a text body are
+/// [minijinja](https://docs.rs/minijinja) templates rendered with the input,
+/// so `https://api.example.com/users/{{ id }}` reads the input's `id`
+/// field. Alternatively, part of the input can be sent as a JSON body with
+/// [`HttpRequestNode::with_json_body`].
+///
+/// The output is `{"status": 200, "headers": {...}, "body": ...}`, with
+/// header names in lowercase and the body parsed as JSON when the response
+/// says it is JSON, and as text otherwise.
+///
+/// Connection failures, timeouts, `429 Too Many Requests` and `5xx`
+/// responses are retried as configured with [`HttpRequestNode::with_retries`].
+/// Other non-success statuses fail at once, unless
+/// [`HttpRequestNode::with_error_status`] asks for them to be returned.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::routing::post;
+/// use axum::{Json, Router};
+/// use rustyflow::http::{HttpRequestNode, Method};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// // A stand-in for a REST API
+/// let app = Router::new().route(
+///     "/users/:id/notes",
+///     post(|Json(note): Json<Value>| async move { Json(json!({"saved": note})) }),
+/// );
+/// let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+/// let address = listener.local_addr().unwrap();
+/// tokio::spawn(async move { axum::serve(listener, app).await });
+///
+/// let node = HttpRequestNode::new(format!("http://{address}/users/{{{{ user.id }}}}/notes"))?
+///     .with_method(Method::POST)
+///     .with_header("x-request-source", "flow {{ user.id }}")?
+///     .with_json_body("/note")
+///     .with_timeout(Duration::from_secs(5))
+///     .with_retries(2);
+///
+/// let response = node
+///     .call(json!({"user": {"id": 7}, "note": {"text": "call back"}}))
+///     .await?;
+/// assert_eq!(response["status"], 200);
+/// assert_eq!(response["headers"]["content-type"], "application/json");
+/// assert_eq!(response["body"], json!({"saved": {"text": "call back"}}));
+/// # Ok(())
+/// # }
+/// ```
+pub struct HttpRequestNode {
+    client: Client,
+    method: Method,
+    templates: Environment<'static>,
+    headers: Vec<HeaderName>,
+    body: Option<RequestBody>,
+    timeout: Option<Duration>,
+    max_retries: usize,
+    retry_delay: Duration,
+    error_status: bool,
+}
+
+impl HttpRequestNode {
+    /// Create a node that sends `GET` requests to a templated URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL template, rendered with the input
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Template` if the template has a syntax error.
+    pub fn new(url: impl Into<String>) -> Result<Self, FlowError> {
+        let mut templates = Environment::new();
+        templates.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+        templates
+            .add_template_owned("url", url.into())
+            .map_err(|e| FlowError::Template(e.to_string()))?;
+        Ok(Self {
+            client: shared_client(),
+            method: Method::GET,
+            templates,
+            headers: Vec::new(),
+            body: None,
+            timeout: None,
+            max_retries: 0,
+            retry_delay: Duration::from_millis(200),
+            error_status: false,
+        })
+    }
+
+    /// Use a preconfigured HTTP client.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Send requests with `method` instead of `GET`.
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Send a header whose value is a template rendered with the input.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the name is not a valid HTTP
+    /// header name, or `FlowError::Template` if the value has a syntax
+    /// error.
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self, FlowError> {
+        let name = HeaderName::try_from(name)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid header name '{name}': {e}")))?;
+        self.templates
+            .add_template_owned(format!("header:{name}"), value.to_string())
+            .map_err(|e| FlowError::Template(e.to_string()))?;
+        self.headers.push(name);
+        Ok(self)
+    }
+
+    /// Send a text body rendered from a template with the input.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Template` if the template has a syntax error.
+    pub fn with_body_template(mut self, template: impl Into<String>) -> Result<Self, FlowError> {
+        self.templates
+            .add_template_owned("body", template.into())
+            .map_err(|e| FlowError::Template(e.to_string()))?;
+        self.body = Some(RequestBody::Template);
+        Ok(self)
+    }
+
+    /// Send the input value at `pointer` as a JSON body; `""` sends the
+    /// whole input.
+    pub fn with_json_body(mut self, pointer: impl Into<String>) -> Self {
+        self.body = Some(RequestBody::Json(pointer.into()));
+        self
+    }
+
+    /// Give up on an attempt that takes longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retry transient failures up to `max_retries` times.
+    pub fn with_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry; it doubles on each attempt.
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Return responses with any status instead of failing on `4xx` and
+    /// `5xx`, once retries are exhausted.
+    pub fn with_error_status(mut self) -> Self {
+        self.error_status = true;
+        self
+    }
+
+    fn render(&self, name: &str, input: &Value) -> Result<String, FlowError> {
+        self.templates
+            .get_template(name)
+            .and_then(|template| template.render(input))
+            .map_err(|e| FlowError::Template(e.to_string()))
+    }
+
+    /// Build the request for `input`.
+    fn request(&self, input: &Value) -> Result<reqwest::RequestBuilder, FlowError> {
+        let url = self.render("url", input)?;
+        let url = Url::parse(&url)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid URL '{url}': {e}")))?;
+        let mut request = self.client.request(self.method.clone(), url);
+        for name in &self.headers {
+            let value = self.render(&format!("header:{name}"), input)?;
+            let value = HeaderValue::try_from(value)
+                .map_err(|e| FlowError::NodeFailed(format!("Invalid value for '{name}': {e}")))?;
+            request = request.header(name, value);
+        }
+        request = match &self.body {
+            Some(RequestBody::Template) => request.body(self.render("body", input)?),
+            Some(RequestBody::Json(pointer)) => {
+                let body = input.pointer(pointer).ok_or_else(|| {
+                    FlowError::NodeFailed(format!("Missing request body at '{pointer}'"))
+                })?;
+                request.json(body)
+            }
+            None => request,
+        };
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        Ok(request)
+    }
+}
+
+/// The `{status, headers, body}` value of a response.
+async fn response_value(response: reqwest::Response) -> Result<Value, FlowError> {
+    let status = response.status();
+    let mut headers = Map::new();
+    for (name, value) in response.headers() {
+        if let Ok(value) = value.to_str() {
+            headers.insert(name.to_string(), json!(value));
+        }
+    }
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("json"));
+    let text = response
+        .text()
+        .await
+        .map_err(|e| FlowError::NodeFailed(format!("Cannot read response body: {e}")))?;
+    let body = if is_json {
+        serde_json::from_str(&text).unwrap_or(Value::String(text))
+    } else {
+        Value::String(text)
+    };
+    Ok(json!({ "status": status.as_u16(), "headers": headers, "body": body }))
+}
+
+#[async_trait]
+impl Node for HttpRequestNode {
+    /// Make the request and return the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Template` if a template cannot be rendered with
+    /// the input, `FlowError::Timeout` if the last attempt timed out,
+    /// `FlowError::RateLimited` if it was answered with `429`, and
+    /// `FlowError::NodeFailed` for other failures and error statuses.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            let result = self.request(&input)?.send().await;
+            let retryable = match result {
+                Ok(response) => {
+                    let status = response.status();
+                    let transient =
+                        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+                    let last = attempt >= self.max_retries;
+                    if status.is_success() || (self.error_status && (!transient || last)) {
+                        return response_value(response).await;
+                    }
+                    let url = response.url().clone();
+                    let text = response.text().await.unwrap_or_default();
+                    let message = format!("Request to {url} failed with status {status}: {text}");
+                    let error = if status == StatusCode::TOO_MANY_REQUESTS {
+                        FlowError::RateLimited(message)
+                    } else {
+                        FlowError::NodeFailed(message)
+                    };
+                    if !transient {
+                        return Err(error);
+                    }
+                    error
+                }
+                Err(e) if e.is_timeout() => FlowError::Timeout(format!("Request timed out: {e}")),
+                Err(e) => FlowError::NodeFailed(format!("Request failed: {e}")),
+            };
+
+            if attempt >= self.max_retries {
+                return Err(retryable);
+            }
+            attempt += 1;
+            tracing::warn!("Retrying HTTP request (attempt {attempt}): {retryable}");
+            crate::report::record_retry();
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+/// How a paginated API exposes the next page.
+///
+/// In JSON, the variant is given by a snake_case `type` field, as in
+/// `{"type": "cursor", "param": "cursor", "pointer": "/next"}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Pagination {
+    /// Follow the `rel="next"` URL from the `Link` response header.
+    LinkHeader,
+    /// Read a cursor from the response body and send it as a query parameter.
+    Cursor {
+        /// Query parameter carrying the cursor, e.g. `"cursor"`.
+        param: String,
+        /// JSON pointer to the next cursor in the body, e.g. `"/meta/next_cursor"`.
+        pointer: String,
+    },
+    /// Page with offset/limit query parameters until a short page is returned.
+    Offset {
+        /// Query parameter carrying the offset, e.g. `"offset"`.
+        offset_param: String,
+        /// Query parameter carrying the page size, e.g. `"limit"`.
+        limit_param: String,
+        /// Number of items requested per page.
+        limit: usize,
+    },
+}
+
+/// A node that fetches every page of a paginated JSON API.
+///
+/// As a [`Node`], it returns one concatenated JSON array of the items on each
+/// page: those found at [`PaginatedFetch::with_items_pointer`], the page
+/// itself if it is an array, or otherwise the page body as a single item. As
+/// a [`Source`], it emits each page (or each page's items array) as it
+/// arrives.
+///
+/// The input may be `null`, or an object with an optional `url` overriding
+/// the configured URL and an optional `query` object of extra query
+/// parameters.
+///
+/// Fetching stops when the API reports no further page, or when the
+/// configured page or item limits are reached.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use rustyflow::http::{PaginatedFetch, Pagination};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// # async fn example() -> Result<(), FlowError> {
+/// let fetch = PaginatedFetch::new(
+///     "https://api.example.com/issues",
+///     Pagination::Cursor { param: "cursor".into(), pointer: "/next_cursor".into() },
+/// )
+/// .with_items_pointer("/items")
+/// .with_max_pages(10);
+///
+/// let issues = fetch.call(json!({"query": {"state": "open"}})).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PaginatedFetch {
+    client: Client,
+    url: String,
+    pagination: Pagination,
+    headers: HeaderMap,
+    items_pointer: Option<String>,
+    max_pages: usize,
+    max_items: Option<usize>,
+}
+
+/// Where the next request goes, if anywhere.
+enum NextPage {
+    Url(Url),
+    Done,
+}
+
+impl PaginatedFetch {
+    /// Create a fetcher for the given URL and pagination scheme.
+    ///
+    /// Defaults to at most 100 pages and no item limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the first page
+    /// * `pagination` - How the API links to subsequent pages
+    pub fn new(url: impl Into<String>, pagination: Pagination) -> Self {
+        Self {
+            client: shared_client(),
+            url: url.into(),
+            pagination,
+            headers: HeaderMap::new(),
+            items_pointer: None,
+            max_pages: 100,
+            max_items: None,
+        }
+    }
+
+    /// Use a preconfigured HTTP client.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Send a header with every request.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the name or value is not a valid
+    /// HTTP header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self, FlowError> {
+        let name = HeaderName::try_from(name)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid header name '{name}': {e}")))?;
+        let value = HeaderValue::try_from(value)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid header value: {e}")))?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Extract the items array from each page at this JSON pointer.
+    pub fn with_items_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.items_pointer = Some(pointer.into());
+        self
+    }
+
+    /// Stop after fetching this many pages.
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Stop once this many items have been collected.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    fn first_url(&self, input: &Value) -> Result<Url, FlowError> {
+        let base = input
+            .get("url")
+            .and_then(Value::as_str)
+            .unwrap_or(&self.url);
+        let mut url = Url::parse(base)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid URL '{base}': {e}")))?;
+
+        if let Some(query) = input.get("query").and_then(Value::as_object) {
+            let mut pairs = url.query_pairs_mut();
+            for (key, value) in query {
+                match value {
+                    Value::String(s) => pairs.append_pair(key, s),
+                    other => pairs.append_pair(key, &other.to_string()),
+                };
+            }
+        }
+        if let Pagination::Offset {
+            offset_param,
+            limit_param,
+            limit,
+        } = &self.pagination
+        {
+            set_query_param(&mut url, offset_param, "0");
+            set_query_param(&mut url, limit_param, &limit.to_string());
+        }
+        Ok(url)
+    }
+
+    /// Fetch one page and work out where the next one is.
+    async fn fetch_page(&self, url: Url) -> Result<(Value, NextPage), FlowError> {
+        let response = self
+            .client
+            .get(url.clone())
+            .headers(self.headers.clone())
+            .send()
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Request to {url} failed: {e}")))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(FlowError::NodeFailed(format!(
+                "Request to {url} failed with status {status}: {body}"
+            )));
+        }
+
+        let link_next = response
+            .headers()
+            .get_all(LINK)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(next_link);
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid JSON from {url}: {e}")))?;
+
+        let next = match &self.pagination {
+            Pagination::LinkHeader => match link_next {
+                Some(next) => NextPage::Url(url.join(&next).map_err(|e| {
+                    FlowError::NodeFailed(format!("Invalid next link '{next}': {e}"))
+                })?),
+                None => NextPage::Done,
+            },
+            Pagination::Cursor { param, pointer } => match body.pointer(pointer) {
+                Some(Value::String(cursor)) if !cursor.is_empty() => {
+                    let mut next = url.clone();
+                    set_query_param(&mut next, param, cursor);
+                    NextPage::Url(next)
+                }
+                Some(Value::Number(cursor)) => {
+                    let mut next = url.clone();
+                    set_query_param(&mut next, param, &cursor.to_string());
+                    NextPage::Url(next)
+                }
+                _ => NextPage::Done,
+            },
+            Pagination::Offset {
+                offset_param,
+                limit,
+                ..
+            } => {
+                let returned = self.page_items(&body)?.len();
+                if returned < *limit || returned == 0 {
+                    NextPage::Done
+                } else {
+                    let offset = url
+                        .query_pairs()
+                        .find(|(key, _)| key == offset_param.as_str())
+                        .and_then(|(_, value)| value.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    let mut next = url.clone();
+                    set_query_param(&mut next, offset_param, &(offset + returned).to_string());
+                    NextPage::Url(next)
+                }
+            }
+        };
+
+        Ok((body, next))
+    }
+
+    /// The items contained in a page.
+    fn page_items(&self, page: &Value) -> Result<Vec<Value>, FlowError> {
+        let items = match &self.items_pointer {
+            Some(pointer) => page.pointer(pointer).ok_or_else(|| {
+                FlowError::NodeFailed(format!("Page is missing items at {pointer}"))
+            })?,
+            None => page,
+        };
+        Ok(match items {
+            Value::Array(items) => items.clone(),
+            other => vec![other.clone()],
+        })
+    }
+
+    /// Emit the value produced for each page: its items array if an items
+    /// pointer is configured, otherwise the page body.
+    fn pages(&self, input: Value) -> ValueStream<'_> {
+        let first = match self.first_url(&input) {
+            Ok(url) => url,
+            Err(e) => return stream::once(async move { Err(e) }).boxed(),
+        };
+
+        stream::try_unfold(
+            (NextPage::Url(first), 0usize, 0usize),
+            move |(next, pages, items)| async move {
+                let url = match next {
+                    NextPage::Url(url) if pages < self.max_pages => url,
+                    _ => return Ok(None),
+                };
+                if self.max_items.is_some_and(|max| items >= max) {
+                    return Ok(None);
+                }
+                let (body, next) = self.fetch_page(url).await?;
+                let value = match &self.items_pointer {
+                    Some(_) => Value::Array(self.page_items(&body)?),
+                    None => body,
+                };
+                let count = match &value {
+                    Value::Array(items) => items.len(),
+                    _ => 1,
+                };
+                Ok(Some((value, (next, pages + 1, items + count))))
+            },
+        )
+        .boxed()
+    }
+}
+
+/// Replace (or add) a query parameter on a URL.
+fn set_query_param(url: &mut Url, key: &str, value: &str) {
+    let retained: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| k != key)
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    let mut pairs = url.query_pairs_mut();
+    pairs.clear();
+    for (k, v) in &retained {
+        pairs.append_pair(k, v);
+    }
+    pairs.append_pair(key, value);
+}
+
+/// Extract the `rel="next"` target from a `Link` header value.
+fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|part| {
+        let mut segments = part.split(';');
+        let target = segments.next()?.trim();
+        let is_next = segments.any(|param| {
+            let param = param.trim().replace(' ', "");
+            param == "rel=\"next\"" || param == "rel=next"
+        });
+        (is_next && target.starts_with('<') && target.ends_with('>'))
+            .then(|| target[1..target.len() - 1].to_string())
+    })
+}
+
+impl Source for PaginatedFetch {
+    fn stream(&self, input: Value) -> ValueStream<'_> {
+        self.pages(input)
+    }
+}
+
+#[async_trait]
+impl Node for PaginatedFetch {
+    /// Fetch all pages and concatenate their items.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` on request failures, non-success
+    /// status codes, invalid JSON, or missing items.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let pages: Vec<Value> = self.pages(input).try_collect().await?;
+        let mut items: Vec<Value> = pages
+            .into_iter()
+            .flat_map(|page| match page {
+                Value::Array(items) => items,
+                other => vec![other],
+            })
+            .collect();
+        if let Some(max) = self.max_items {
+            items.truncate(max);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn as_streaming(&self) -> Option<&dyn Source> {
+        Some(self)
+    }
+}
+
+/// Connection pool settings for an HTTP client.
+///
+/// The defaults keep up to 32 idle connections per host for 90 seconds,
+/// send TCP keep-alives every 60 seconds and HTTP/2 pings every 30 seconds,
+/// also while idle, and give up connecting after 10 seconds.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::http::{ClientConfig, PaginatedFetch, Pagination};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), rustyflow::FlowError> {
+/// let client = ClientConfig::new()
+///     .with_max_idle_per_host(64)
+///     .with_idle_timeout(Duration::from_secs(300))
+///     .build()?;
+/// let fetch = PaginatedFetch::new("https://api.example.com/items", Pagination::LinkHeader)
+///     .with_client(client);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    max_idle_per_host: usize,
+    idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    http2_keepalive: Option<Duration>,
+    connect_timeout: Duration,
+    timeout: Option<Duration>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: 32,
+            idle_timeout: Some(Duration::from_secs(90)),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            http2_keepalive: Some(Duration::from_secs(30)),
+            connect_timeout: Duration::from_secs(10),
+            timeout: None,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Create the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `max` idle connections per host.
+    pub fn with_max_idle_per_host(mut self, max: usize) -> Self {
+        self.max_idle_per_host = max;
+        self
+    }
+
+    /// Close idle connections after `timeout`, or never with `None`.
+    pub fn with_idle_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.idle_timeout = timeout.into();
+        self
+    }
+
+    /// Send TCP keep-alives at this interval, or none with `None`.
+    pub fn with_tcp_keepalive(mut self, interval: impl Into<Option<Duration>>) -> Self {
+        self.tcp_keepalive = interval.into();
+        self
+    }
+
+    /// Ping HTTP/2 connections at this interval, also while idle, or never
+    /// with `None`.
+    pub fn with_http2_keepalive(mut self, interval: impl Into<Option<Duration>>) -> Self {
+        self.http2_keepalive = interval.into();
+        self
+    }
+
+    /// Give up connecting to a host after `timeout`.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Fail requests that take longer than `timeout` in total.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Build a client with these settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the client cannot be created, for
+    /// example because TLS cannot be initialized.
+    pub fn build(&self) -> Result<Client, FlowError> {
+        let mut builder = Client::builder()
+            .pool_max_idle_per_host(self.max_idle_per_host)
+            .pool_idle_timeout(self.idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive)
+            .connect_timeout(self.connect_timeout)
+            .http2_adaptive_window(true);
+        if let Some(interval) = self.http2_keepalive {
+            builder = builder
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(true);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder
+            .build()
+            .map_err(|e| FlowError::NodeFailed(format!("Cannot create HTTP client: {e}")))
+    }
+}
+
+/// The HTTP client shared by the crate's network nodes, built with the
+/// default [`ClientConfig`] on first use.
+///
+/// Clones share one connection pool, so cloning is cheap.
+pub fn shared_client() -> Client {
+    static SHARED: OnceLock<Client> = OnceLock::new();
+    SHARED
+        .get_or_init(|| ClientConfig::default().build().unwrap_or_default())
+        .clone()
+}
+
+/// Open pooled connections to `urls` before the first node call needs them.
+///
+/// A `HEAD` request is sent to each URL concurrently. Any response, even an
+/// error status, leaves a connection in the pool, so only failures to
+/// connect are errors.
+///
+/// # Errors
+///
+/// Returns `FlowError::NodeFailed` naming the first URL that could not be
+/// reached.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::http::{shared_client, warm_up};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), rustyflow::FlowError> {
+/// // A local server that counts the connections it accepts
+/// let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+/// let url = format!("http://{}/", listener.local_addr().unwrap());
+/// let connections = Arc::new(AtomicUsize::new(0));
+/// let accepted = Arc::clone(&connections);
+/// tokio::spawn(async move {
+///     while let Ok((mut socket, _)) = listener.accept().await {
+///         accepted.fetch_add(1, Ordering::SeqCst);
+///         tokio::spawn(async move {
+///             let mut buf = [0; 1024];
+///             while matches!(socket.read(&mut buf).await, Ok(n) if n > 0) {
+///                 let reply = b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n";
+///                 if socket.write_all(reply).await.is_err() {
+///                     break;
+///                 }
+///             }
+///         });
+///     }
+/// });
+///
+/// let client = shared_client();
+/// warm_up(&client, [url.as_str()]).await?;
+///
+/// // The node call reuses the warm connection
+/// client.get(&url).send().await.unwrap();
+/// assert_eq!(connections.load(Ordering::SeqCst), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn warm_up<I, S>(client: &Client, urls: I) -> Result<(), FlowError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let requests = urls.into_iter().map(|url| {
+        let url = url.as_ref().to_string();
+        async move {
+            client.head(&url).send().await.map_err(|e| {
+                FlowError::NodeFailed(format!("Cannot warm up connection to {url}: {e}"))
+            })?;
+            tracing::debug!("Warmed up connection to {url}");
+            Ok::<_, FlowError>(())
+        }
+    });
+    futures::future::try_join_all(requests).await?;
+    Ok(())
+}
+
+/// Keep pooled connections to `urls` warm by calling [`warm_up`] every
+/// `interval`, which should be shorter than the pool's idle timeout.
+///
+/// Failures are logged and retried at the next interval. Abort the returned
+/// task to stop.
+pub fn keep_warm(
+    client: Client,
+    urls: Vec<String>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = warm_up(&client, &urls).await {
+                tracing::warn!("{e}");
+            }
+        }
+    })
+}
@@ -0,0 +1,342 @@
+//! Background flow executions.
+//!
+//! Long-running flows, such as agent loops, outlive the timeouts of most
+//! HTTP clients. A [`JobQueue`] runs them on worker tasks instead: submitting
+//! returns a job id at once, and the [`Job`] record is polled for its
+//! status, progress and result. The server exposes this as `POST /jobs` and
+//! `GET /jobs/:id`.
+//!
+//! Jobs are held in process memory and are lost on restart. Finished jobs
+//! are kept for an hour, or as long as set with
+//! [`JobQueue::with_retention`], and forgotten as new jobs are submitted,
+//! so that a busy server's memory stays bounded.
+
+use crate::error::FlowError;
+use crate::events::ExecutionEvent;
+use crate::flow::Flow;
+use crate::history::now_millis;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, Semaphore};
+
+const DEFAULT_RETENTION: Duration = Duration::from_secs(3600);
+
+/// Where a job is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a free worker.
+    Queued,
+    /// Executing.
+    Running,
+    /// Finished with a result.
+    Succeeded,
+    /// Finished with an error.
+    Failed,
+}
+
+impl JobStatus {
+    /// Whether the job has finished, successfully or not.
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed)
+    }
+}
+
+/// How far a job has got.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobProgress {
+    /// Nodes that have returned.
+    pub completed_nodes: usize,
+    /// Nodes in the flow.
+    pub total_nodes: usize,
+}
+
+/// The record of one submitted flow execution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    /// The job's unique id.
+    pub id: String,
+    /// The name of the flow it runs.
+    pub flow: String,
+    /// Where the job is in its lifecycle.
+    pub status: JobStatus,
+    /// How far the job has got.
+    pub progress: JobProgress,
+    /// The flow's output, once it succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// The error message, if it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// When the job was submitted, in milliseconds since the Unix epoch.
+    pub submitted_at: u64,
+    /// When the job started running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    /// When the job finished.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+}
+
+/// Runs flows in the background and keeps track of their jobs.
+///
+/// Cloning a queue gives another handle to the same jobs.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::jobs::{JobQueue, JobStatus};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use async_trait::async_trait;
+///
+/// struct SlowDouble;
+///
+/// #[async_trait]
+/// impl Node for SlowDouble {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         tokio::time::sleep(Duration::from_millis(20)).await;
+///         Ok(json!(input.as_i64().unwrap_or(0) * 2))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let queue = JobQueue::new().with_max_concurrency(4);
+/// let flow = Arc::new(Flow::new(vec![Box::new(SlowDouble), Box::new(SlowDouble)]));
+///
+/// let id = queue.submit("double-twice", flow, json!(5));
+/// assert!(!queue.get(&id).unwrap().status.is_finished());
+///
+/// let job = queue.wait(&id).await.unwrap();
+/// assert_eq!(job.status, JobStatus::Succeeded);
+/// assert_eq!(job.result, Some(json!(20)));
+/// assert_eq!(job.progress.completed_nodes, 2);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct JobQueue {
+    jobs: Arc<Mutex<HashMap<String, Job>>>,
+    workers: Option<Arc<Semaphore>>,
+    retention: Duration,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobQueue {
+    /// Create a queue that starts every job immediately and keeps finished
+    /// jobs for an hour.
+    pub fn new() -> Self {
+        Self {
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            workers: None,
+            retention: DEFAULT_RETENTION,
+        }
+    }
+
+    /// Keep finished jobs, and their results, for `retention` after they
+    /// finish. Older ones are forgotten when the next job is submitted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::jobs::JobQueue;
+    /// use rustyflow::Flow;
+    /// use serde_json::json;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let queue = JobQueue::new().with_retention(Duration::ZERO);
+    /// let flow = Arc::new(Flow::new(Vec::new()));
+    ///
+    /// let first = queue.submit("noop", Arc::clone(&flow), json!(1));
+    /// queue.wait(&first).await.unwrap();
+    /// tokio::time::sleep(Duration::from_millis(5)).await;
+    ///
+    /// // Submitting forgets the job that finished before
+    /// let second = queue.submit("noop", flow, json!(2));
+    /// assert!(queue.get(&first).is_none());
+    /// assert!(queue.get(&second).is_some());
+    /// # }
+    /// ```
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Run at most `max` jobs at a time; the rest wait as
+    /// [`JobStatus::Queued`].
+    pub fn with_max_concurrency(mut self, max: usize) -> Self {
+        self.workers = Some(Arc::new(Semaphore::new(max.max(1))));
+        self
+    }
+
+    /// Start executing `flow` with `input` on a worker task.
+    ///
+    /// Must be called within a Tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The flow name recorded on the job
+    /// * `flow` - The flow to execute
+    /// * `input` - The flow's input
+    ///
+    /// # Returns
+    ///
+    /// The id of the new job.
+    pub fn submit(&self, name: impl Into<String>, flow: Arc<Flow>, input: Value) -> String {
+        self.start(name.into(), flow, input, None)
+    }
+
+    /// Start a job like [`submit`](JobQueue::submit), and also hand back
+    /// the flow's result, with its error intact, when the job finishes.
+    pub(crate) fn submit_watched(
+        &self,
+        name: impl Into<String>,
+        flow: Arc<Flow>,
+        input: Value,
+    ) -> (String, oneshot::Receiver<Result<Value, FlowError>>) {
+        let (sender, receiver) = oneshot::channel();
+        (self.start(name.into(), flow, input, Some(sender)), receiver)
+    }
+
+    fn start(
+        &self,
+        name: String,
+        flow: Arc<Flow>,
+        input: Value,
+        watcher: Option<oneshot::Sender<Result<Value, FlowError>>>,
+    ) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let job = Job {
+            id: id.clone(),
+            flow: name,
+            status: JobStatus::Queued,
+            progress: JobProgress {
+                completed_nodes: 0,
+                total_nodes: flow.len(),
+            },
+            result: None,
+            error: None,
+            submitted_at: now_millis(),
+            started_at: None,
+            finished_at: None,
+        };
+        let retention = u64::try_from(self.retention.as_millis()).unwrap_or(u64::MAX);
+        self.prune(now_millis().saturating_sub(retention));
+        self.jobs.lock().unwrap().insert(id.clone(), job);
+
+        let queue = self.clone();
+        let job_id = id.clone();
+        tokio::spawn(async move {
+            let result = queue.run(&job_id, &flow, input).await;
+            if let Some(watcher) = watcher {
+                // The watcher may have stopped waiting
+                let _ = watcher.send(result);
+            }
+        });
+        id
+    }
+
+    async fn run(&self, id: &str, flow: &Flow, input: Value) -> Result<Value, FlowError> {
+        let _permit = match &self.workers {
+            Some(workers) => Arc::clone(workers).acquire_owned().await.ok(),
+            None => None,
+        };
+        self.update(id, |job| {
+            job.status = JobStatus::Running;
+            job.started_at = Some(now_millis());
+        });
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let progress = async {
+            while let Some(event) = receiver.recv().await {
+                if let ExecutionEvent::NodeEnd { .. } = event {
+                    self.update(id, |job| job.progress.completed_nodes += 1);
+                }
+            }
+        };
+        let execution = async {
+            let result = flow.execute_with_events(input, &sender).await;
+            drop(sender);
+            result
+        };
+        let (result, ()) = tokio::join!(execution, progress);
+
+        self.update(id, |job| {
+            job.finished_at = Some(now_millis());
+            match &result {
+                Ok(output) => {
+                    job.status = JobStatus::Succeeded;
+                    job.result = Some(output.clone());
+                }
+                Err(e) => {
+                    tracing::error!("Job {} of flow '{}' failed: {e}", job.id, job.flow);
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e.to_string());
+                }
+            }
+        });
+        result
+    }
+
+    /// Drop the record of a job whose result was already handed out.
+    pub(crate) fn forget(&self, id: &str) {
+        self.jobs.lock().unwrap().remove(id);
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            f(job);
+        }
+    }
+
+    /// The current record of a job.
+    pub fn get(&self, id: &str) -> Option<Job> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    /// All jobs, most recently submitted first.
+    pub fn list(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.submitted_at));
+        jobs
+    }
+
+    /// Wait for a job to finish and return its record, or `None` if there is
+    /// no such job.
+    pub async fn wait(&self, id: &str) -> Option<Job> {
+        loop {
+            let job = self.get(id)?;
+            if job.status.is_finished() {
+                return Some(job);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Forget finished jobs that ended before `before`, in milliseconds since
+    /// the Unix epoch, and return how many were removed. Jobs past the
+    /// queue's [retention](JobQueue::with_retention) are pruned on every
+    /// submit; call this to drop them sooner.
+    pub fn prune(&self, before: u64) -> usize {
+        let mut jobs = self.jobs.lock().unwrap();
+        let count = jobs.len();
+        jobs.retain(|_, job| match job.finished_at {
+            Some(finished_at) => finished_at >= before,
+            None => true,
+        });
+        count - jobs.len()
+    }
+}
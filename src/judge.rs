@@ -0,0 +1,430 @@
+//! LLM-as-judge scoring.
+//!
+//! This module provides [`Judge`], a node that asks a [`ChatModel`] to score
+//! an answer against a rubric of [`Criterion`]s and returns a numeric
+//! [`Judgment`] that evals, [`BeamSearch`](crate::explore::BeamSearch) and
+//! evaluator-optimizer loops can consume directly.
+//!
+//! Judges drift: one model grades generously, another harshly, and both
+//! favour whichever answer they read first. [`CalibrationExample`]s show the
+//! judge answers with agreed scores before it grades, anchoring its scale.
+//! When an answer is compared with a baseline, the judge grades both orders
+//! and averages them, cancelling out position bias.
+
+use crate::error::FlowError;
+use crate::llm::{extract_json, record_usage, ChatModel, ChatRequest, Message};
+use crate::node::Node;
+use crate::reflection::{Criterion, CriterionScore};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Score differences below this, on the `0.0` to `1.0` scale, are ties.
+const TIE_MARGIN: f64 = 0.05;
+
+/// An answer with agreed scores, shown to the judge to calibrate its scale.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationExample {
+    /// The task the answer responds to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// The example answer.
+    pub answer: String,
+    /// Scores from 0 to 10 by criterion name.
+    pub scores: BTreeMap<String, f64>,
+    /// Why the answer earned these scores.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub explanation: String,
+}
+
+impl CalibrationExample {
+    /// Create an example without scores yet.
+    pub fn new(answer: impl Into<String>) -> Self {
+        Self {
+            task: None,
+            answer: answer.into(),
+            scores: BTreeMap::new(),
+            explanation: String::new(),
+        }
+    }
+
+    /// Set the task the answer responds to.
+    pub fn with_task(mut self, task: impl Into<String>) -> Self {
+        self.task = Some(task.into());
+        self
+    }
+
+    /// Give the answer `score`, from 0 to 10, on `criterion`.
+    pub fn with_score(mut self, criterion: impl Into<String>, score: f64) -> Self {
+        self.scores.insert(criterion.into(), score);
+        self
+    }
+
+    /// Explain the scores.
+    pub fn with_explanation(mut self, explanation: impl Into<String>) -> Self {
+        self.explanation = explanation.into();
+        self
+    }
+}
+
+/// Which of two compared answers the judge preferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preference {
+    /// The judged answer scored higher.
+    Answer,
+    /// The baseline scored higher.
+    Baseline,
+    /// Neither scored clearly higher.
+    Tie,
+}
+
+/// The output of a [`Judge`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Judgment {
+    /// Weighted score of the answer between `0.0` and `1.0`.
+    pub score: f64,
+    /// Per-criterion scores of the answer, in rubric order.
+    pub criteria: Vec<CriterionScore>,
+    /// Weighted score of the baseline, when one was given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline_score: Option<f64>,
+    /// Which answer won, when a baseline was given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred: Option<Preference>,
+}
+
+/// A node that scores an answer against a rubric using a [`ChatModel`].
+///
+/// The input is an object with an `answer` field, an optional `task` field,
+/// and an optional `baseline` answer to compare against. The output is a
+/// [`Judgment`], whose `score` field makes it usable wherever a
+/// `{"score": n}` evaluator is expected.
+///
+/// With a baseline, the model sees the two answers in both orders and the
+/// scores of each answer are averaged over the two calls, unless
+/// [`with_position_swap`](Judge::with_position_swap) turns this off.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::judge::{CalibrationExample, Judge};
+/// use rustyflow::llm::{ChatModel, ChatRequest, ChatResponse, Message};
+/// use rustyflow::reflection::Criterion;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// /// A judge that always prefers whichever response it reads first.
+/// struct BiasedJudge;
+///
+/// #[async_trait]
+/// impl ChatModel for BiasedJudge {
+///     async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, FlowError> {
+///         let prompt = &request.messages.last().unwrap().content;
+///         let reply = if prompt.contains("Response 2") {
+///             r#"{"criteria": [{"name": "accuracy", "scores": [8, 6]}]}"#
+///         } else {
+///             r#"{"criteria": [{"name": "accuracy", "score": 7, "feedback": "Mostly right."}]}"#
+///         };
+///         Ok(ChatResponse { message: Message::assistant(reply), usage: None })
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let judge = Judge::new(BiasedJudge, vec![Criterion::new("accuracy", "Facts are correct")])
+///     .with_example(
+///         CalibrationExample::new("Paris is the capital of Germany.")
+///             .with_task("Capital of France?")
+///             .with_score("accuracy", 0.0),
+///     );
+///
+/// let judgment = judge.call(json!({"task": "2 + 2?", "answer": "4"})).await?;
+/// assert_eq!(judgment["score"], 0.7);
+///
+/// // Swapping the order cancels the judge's bias towards the first answer
+/// let judgment = judge
+///     .call(json!({"task": "2 + 2?", "answer": "4", "baseline": "four"}))
+///     .await?;
+/// assert_eq!(judgment["score"], judgment["baseline_score"]);
+/// assert_eq!(judgment["preferred"], "tie");
+/// # Ok(())
+/// # }
+/// ```
+pub struct Judge<M: ChatModel> {
+    model: M,
+    rubric: Vec<Criterion>,
+    examples: Vec<CalibrationExample>,
+    position_swap: bool,
+}
+
+impl<M: ChatModel> Judge<M> {
+    /// Create a new judge.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The chat model used as the judge
+    /// * `rubric` - The criteria answers are scored against
+    pub fn new(model: M, rubric: Vec<Criterion>) -> Self {
+        Self {
+            model,
+            rubric,
+            examples: Vec::new(),
+            position_swap: true,
+        }
+    }
+
+    /// Show the judge a scored example before it grades.
+    pub fn with_example(mut self, example: CalibrationExample) -> Self {
+        self.examples.push(example);
+        self
+    }
+
+    /// Whether to grade comparisons in both orders and average them.
+    /// Defaults to `true`; turning it off halves the model calls.
+    pub fn with_position_swap(mut self, swap: bool) -> Self {
+        self.position_swap = swap;
+        self
+    }
+
+    fn system_prompt(&self) -> String {
+        let mut prompt = String::from(
+            "You are an impartial judge. Score answers on each criterion from 0 to 10, \
+             judging only their content, not their length or position.\n\nCriteria:\n",
+        );
+        for criterion in &self.rubric {
+            prompt.push_str(&format!(
+                "- {}: {}\n",
+                criterion.name, criterion.description
+            ));
+        }
+        if !self.examples.is_empty() {
+            prompt.push_str("\nCalibrate your scores against these graded examples:\n");
+            for example in &self.examples {
+                if let Some(task) = &example.task {
+                    prompt.push_str(&format!("\nTask:\n{task}\n"));
+                }
+                prompt.push_str(&format!("Answer:\n{}\nScores:", example.answer));
+                for (name, score) in &example.scores {
+                    prompt.push_str(&format!(" {name}={score}"));
+                }
+                prompt.push('\n');
+                if !example.explanation.is_empty() {
+                    prompt.push_str(&format!("Why: {}\n", example.explanation));
+                }
+            }
+        }
+        prompt
+    }
+
+    async fn ask(&self, prompt: String) -> Result<Vec<Value>, FlowError> {
+        let request = ChatRequest::new(vec![
+            Message::system(self.system_prompt()),
+            Message::user(prompt),
+        ])
+        .with_temperature(0.0);
+        let reply = record_usage(self.model.chat(request).await?)
+            .message
+            .content;
+        match extract_json(&reply) {
+            Some(Value::Array(entries)) => Ok(entries),
+            Some(Value::Object(mut map)) => match map.remove("criteria") {
+                Some(Value::Array(entries)) => Ok(entries),
+                _ => Err(FlowError::NodeFailed(
+                    "Judge response has no 'criteria' array".to_string(),
+                )),
+            },
+            _ => Err(FlowError::NodeFailed(
+                "Judge response did not contain JSON".to_string(),
+            )),
+        }
+    }
+
+    /// Find the entry for each rubric criterion in a reply.
+    fn entries<'a>(&self, reply: &'a [Value]) -> Result<Vec<&'a Value>, FlowError> {
+        self.rubric
+            .iter()
+            .map(|criterion| {
+                reply
+                    .iter()
+                    .find(|entry| entry["name"] == criterion.name.as_str())
+                    .ok_or_else(|| {
+                        FlowError::NodeFailed(format!(
+                            "Judge response is missing criterion '{}'",
+                            criterion.name
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    async fn score(&self, task: Option<&str>, answer: &str) -> Result<Judgment, FlowError> {
+        let mut prompt = task_section(task);
+        prompt.push_str(&format!("Answer:\n{answer}\n"));
+        prompt.push_str(
+            "\nRespond with JSON only, in the form \
+             {\"criteria\": [{\"name\": \"...\", \"score\": 0-10, \"feedback\": \"...\"}]}",
+        );
+        let reply = self.ask(prompt).await?;
+
+        let mut criteria = Vec::with_capacity(self.rubric.len());
+        for (criterion, entry) in self.rubric.iter().zip(self.entries(&reply)?) {
+            criteria.push(CriterionScore {
+                name: criterion.name.clone(),
+                score: normalize(&entry["score"], &criterion.name)?,
+                feedback: feedback(entry),
+            });
+        }
+        Ok(Judgment {
+            score: self.weighted(&criteria),
+            criteria,
+            baseline_score: None,
+            preferred: None,
+        })
+    }
+
+    /// Score two responses in one call, in the order given.
+    async fn compare_once(
+        &self,
+        task: Option<&str>,
+        first: &str,
+        second: &str,
+    ) -> Result<Vec<(CriterionScore, f64)>, FlowError> {
+        let mut prompt = task_section(task);
+        prompt.push_str(&format!(
+            "Response 1:\n{first}\n\nResponse 2:\n{second}\n\
+             \nScore both responses on every criterion. Respond with JSON only, in the form \
+             {{\"criteria\": [{{\"name\": \"...\", \"scores\": [response 1, response 2], \
+             \"feedback\": \"...\"}}]}}"
+        ));
+        let reply = self.ask(prompt).await?;
+
+        let mut scores = Vec::with_capacity(self.rubric.len());
+        for (criterion, entry) in self.rubric.iter().zip(self.entries(&reply)?) {
+            let pair = &entry["scores"];
+            scores.push((
+                CriterionScore {
+                    name: criterion.name.clone(),
+                    score: normalize(&pair[0], &criterion.name)?,
+                    feedback: feedback(entry),
+                },
+                normalize(&pair[1], &criterion.name)?,
+            ));
+        }
+        Ok(scores)
+    }
+
+    async fn compare(
+        &self,
+        task: Option<&str>,
+        answer: &str,
+        baseline: &str,
+    ) -> Result<Judgment, FlowError> {
+        let mut scores = if self.position_swap {
+            let (forward, swapped) = futures::join!(
+                self.compare_once(task, answer, baseline),
+                self.compare_once(task, baseline, answer)
+            );
+            let mut scores = forward?;
+            for ((answer, baseline), (baseline_swapped, answer_swapped)) in
+                scores.iter_mut().zip(swapped?)
+            {
+                answer.score = (answer.score + answer_swapped) / 2.0;
+                *baseline = (*baseline + baseline_swapped.score) / 2.0;
+            }
+            scores
+        } else {
+            self.compare_once(task, answer, baseline).await?
+        };
+
+        let baseline: Vec<CriterionScore> = scores
+            .iter()
+            .map(|(answer, baseline)| CriterionScore {
+                name: answer.name.clone(),
+                score: *baseline,
+                feedback: String::new(),
+            })
+            .collect();
+        let criteria: Vec<CriterionScore> = scores.drain(..).map(|(answer, _)| answer).collect();
+        let score = self.weighted(&criteria);
+        let baseline_score = self.weighted(&baseline);
+        let preferred = if (score - baseline_score).abs() < TIE_MARGIN {
+            Preference::Tie
+        } else if score > baseline_score {
+            Preference::Answer
+        } else {
+            Preference::Baseline
+        };
+        Ok(Judgment {
+            score,
+            criteria,
+            baseline_score: Some(baseline_score),
+            preferred: Some(preferred),
+        })
+    }
+
+    fn weighted(&self, scores: &[CriterionScore]) -> f64 {
+        let mut weighted = 0.0;
+        let mut total_weight = 0.0;
+        for (criterion, scored) in self.rubric.iter().zip(scores) {
+            weighted += scored.score * criterion.weight;
+            total_weight += criterion.weight;
+        }
+        if total_weight > 0.0 {
+            weighted / total_weight
+        } else {
+            0.0
+        }
+    }
+}
+
+fn task_section(task: Option<&str>) -> String {
+    match task {
+        Some(task) => format!("Task:\n{task}\n\n"),
+        None => String::new(),
+    }
+}
+
+/// Read a 0 to 10 score and scale it to `0.0` to `1.0`.
+fn normalize(score: &Value, criterion: &str) -> Result<f64, FlowError> {
+    let score = score.as_f64().ok_or_else(|| {
+        FlowError::NodeFailed(format!(
+            "Judge response has no numeric score for criterion '{criterion}'"
+        ))
+    })?;
+    Ok((score / 10.0).clamp(0.0, 1.0))
+}
+
+fn feedback(entry: &Value) -> String {
+    entry["feedback"].as_str().unwrap_or_default().to_string()
+}
+
+fn text_field(input: &Value, field: &str) -> Option<String> {
+    match &input[field] {
+        Value::Null => None,
+        Value::String(text) => Some(text.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[async_trait]
+impl<M: ChatModel> Node for Judge<M> {
+    /// Score the input answer, against the baseline if one is given.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the input has no `answer` field or
+    /// a reply lacks a numeric score for some criterion.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let answer = text_field(&input, "answer")
+            .ok_or_else(|| FlowError::NodeFailed("Expected 'answer' field".to_string()))?;
+        let task = input["task"].as_str();
+        let judgment = match text_field(&input, "baseline") {
+            Some(baseline) => self.compare(task, &answer, &baseline).await?,
+            None => self.score(task, &answer).await?,
+        };
+        Ok(serde_json::to_value(judgment)?)
+    }
+}
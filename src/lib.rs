@@ -37,8 +37,73 @@
 //! - [`Node`]: Basic computation unit with async execution
 //! - [`Flow`]: Sequential orchestration of nodes
 //! - [`ParallelFlow`]: Concurrent execution of multiple nodes
+//! - [`GraphFlow`](graph::GraphFlow): Conditional edges and loops with traversal bounds
+//! - [`ExecutionOverlay`](overlay::ExecutionOverlay): Live or recorded run state on Mermaid diagrams, or as JSON for a UI
+//! - [`Branch`](branch::Branch): If/else dispatch by JSON pointer checks or closures
+//! - [`Loop`](looping::Loop): Refine-until-valid loops with an iteration cap
+//! - [`NodeExecutor`](executor::NodeExecutor) and [`FlowScheduler`](executor::FlowScheduler): Pluggable node placement and admission
+//! - [`Middleware`](executor::Middleware): Logging, authorization and input scrubbing around every node call
+//! - [`ResourcePools`](resources::ResourcePools): GPU, CPU and rate-limit pools shared across runs
+//! - [`CheckpointStore`](checkpoint::CheckpointStore): Resumable flow runs
+//! - [`StatefulNode`](state::StatefulNode): Framework-managed, checkpointed node state
+//! - [`ExecutionReport`](report::ExecutionReport): Per-node timing and usage
+//! - [`ExecutionEvent`](events::ExecutionEvent): Live node and token events, streamed over SSE
+//! - [`UsageReport`](history::UsageReport): Usage by flow, tenant and model as CSV or JSON
+//! - [`transcript`]: Agent transcripts as Markdown, HTML, or ShareGPT and OpenAI JSONL
+//! - [`DatasetBuilder`](dataset::DatasetBuilder): Filtered, redacted fine-tuning splits from run history
+//! - [`metrics`]: Prometheus-ready execution counters and latency histograms
+//! - [`AlertMonitor`](alert::AlertMonitor): Error-rate, latency and burn-rate alerts
+//! - [`NodeRegistry`](registry::NodeRegistry): Nodes built from type names and JSON params
+//! - [`FlowRegistry`](server::FlowRegistry): Named flows served over HTTP
+//! - [`RequireAuth`](auth::RequireAuth): API-key authentication and per-key rate limits
+//! - [`Cors`](security::Cors): CORS policies, security headers and accepted content types for browser frontends
+//! - [`Sessions`](session::Sessions): Per-session chat memory and context across HTTP requests
+//! - [`JobQueue`](jobs::JobQueue): Background flow runs with pollable status
+//! - [`FlowConfig`](config::FlowConfig): Flows defined in JSON or YAML and checked with located errors, runnable with the `rustyflow` CLI
+//! - [`FlowPack`](pack::FlowPack): Versioned bundles of a flow with its prompts, schemas and assets
+//! - [`PackStore`](pack_store::PackStore): Push and pull flowpacks with version tags and digests
+//! - [`Scaffold`](scaffold::Scaffold): New RAG, ReAct and batch ETL projects, as `rustyflow new`
 //! - [`Tool`]: Type-safe, structured computation with validation
+//! - [`#[node]`](macro@node) and [`#[tool]`](macro@tool): Nodes and tools, with schemas and registration, from plain async functions
+//! - [`schema::validate`]: JSON Schema checks with field-level errors, as [`ValidateNode`](schema::ValidateNode) steps and checked contracts between nodes
 //! - [`Batch`]: Concurrent processing of arrays
+//! - [`BatchFlow`]: A whole flow run per array element, with per-element errors
+//! - [`TransformNode`](transform::TransformNode): jq and JSONPath glue steps that pick, rename and flatten fields (`transform` feature)
+//! - [`MapReduce`](map_reduce::MapReduce): Map array elements concurrently, then fold the results with a reducer node
+//! - [`Source`](stream::Source): Generator nodes consumed as streams
+//! - [`HealthCheck`](node::HealthCheck): Node capabilities such as streaming, schemas and health checks, discovered through [`Node`]
+//! - [`NodeV2`](node::NodeV2): Nodes run with a context and cancellation, returning metadata, mixed freely with [`Node`]s
+//! - [`FnNode`](node::FnNode): Async closures and functions used as nodes, boxed with [`IntoNode`](node::IntoNode)
+//! - [`LifecycleNode`](lifecycle::LifecycleNode): PocketFlow-style prep, exec and post phases, with retried exec
+//! - [`Accumulator`](accumulate::Accumulator): Running counts, averages and transcripts
+//! - [`MemoryNode`](memory::MemoryNode): Per-session chat memory, with rolling summaries past a token budget
+//! - [`ChatModel`](llm::ChatModel): Provider-agnostic language model interface
+//! - [`PromptTemplate`](prompt::PromptTemplate): Jinja-style prompt rendering
+//! - [`StructuredOutput`](structured::StructuredOutput): Schema-checked model output
+//! - [`Constraint`](constraint::Constraint): JSON Schema, regex and grammar constrained generation
+//! - [`Embedder`](embeddings::Embedder): Text embedding providers for retrieval
+//! - [`VectorStore`](vector_store::VectorStore): Similarity search for RAG flows
+//! - [`ReActAgent`]: Tool-using agent loop built on the tool registry
+//! - [`EmulatedTools`](tool_emulation::EmulatedTools): Tool calling for models without native support
+//! - [`CritiqueNode`](reflection::CritiqueNode): Rubric-based answer critique
+//! - [`Judge`](judge::Judge): Calibrated LLM-as-judge scores with position-bias mitigation
+//! - [`Simulation`](simulation::Simulation): Scripted or LLM-played users driving a chat flow for end-to-end tests
+//! - [`Fixtures`](fixtures::Fixtures): Seed conversations and memories from JSON, JSONL or YAML before a run
+//! - [`Scorer`](scorer::Scorer): ROUGE, BLEU, embedding, regex and JSON field scorers, and quality gates
+//! - [`Experiment`](experiment::Experiment): Flow or prompt variants compared over a corpus by score, latency and cost
+//! - [`BeamSearch`](explore::BeamSearch): Tree-of-thoughts style exploration
+//! - [`MonteCarlo`](sampling::MonteCarlo): Repeated sampling with aggregation
+//! - [`WeightedRouter`](router::WeightedRouter): Weighted traffic splitting
+//! - [`Fallback`](fallback::Fallback): Alternate nodes tried in order when one fails
+//! - [`Race`](race::Race): Hedged calls that keep the first success
+//! - [`Hedged`](race::Hedged): Duplicate requests to a slow node, keeping whichever finishes first
+//! - [`CommandNode`](command::CommandNode): Host programs run with a cleared environment, injected secrets and a scratch directory
+//! - [`Deadline`](deadline::Deadline): Soft timeouts that warn and start a fallback, hard timeouts that cancel
+//! - [`RateLimit`](rate_limit::RateLimit): Token-bucket provider quotas shared across flows
+//! - [`Cached`](cache::Cached): Node results cached by input hash, with TTLs and pluggable backends
+//! - [`ModelSelector`](selector::ModelSelector): Cost- and difficulty-aware model choice
+//! - [`DifficultyEstimator`](difficulty::DifficultyEstimator): Routing hints for cascades
+//! - [`Budget`](budget::Budget): Shared cost limits across nodes
 //!
 //! ## Features
 //!
@@ -47,16 +112,129 @@
 //! - **Zero-Cost Abstractions**: High-level APIs with low-level performance
 //! - **Flexible Execution**: Sequential, parallel, and batch patterns
 //! - **Memory Safe**: Leverages Rust's ownership system
+//! - **Observable**: `tracing` spans for every flow run (`flow.execute`, with a
+//!   `run_id`) and node call (`node.call`, with index, name, and duration)
+//!
+//! ## Optional Features
+//!
+//! - `reqwest`: HTTP-backed integrations such as
+//!   [`OpenAiEmbedder`](embeddings::OpenAiEmbedder), the
+//!   [`HttpRequestNode`](http::HttpRequestNode) and other [`http`] nodes and
+//!   their shared, pre-warmable connection pool, the [`graphql`] client
+//!   node, webhook and Slack alert notifiers, and the HTTP flowpack store
+//! - `qdrant`: A Qdrant backend for [`VectorStore`](vector_store::VectorStore)
+//! - `grpc`: The [`GrpcNode`](grpc::GrpcNode) dynamic gRPC client
+//! - `sqlite`: A SQLite backend for
+//!   [`CheckpointStore`](checkpoint::CheckpointStore) and
+//!   [`HistoryStore`](history::HistoryStore) that keeps run history
+//! - `redis`: A Redis backend for
+//!   [`CheckpointStore`](checkpoint::CheckpointStore) and
+//!   [`CacheBackend`](cache::CacheBackend) and
+//!   [`SessionStore`](session::SessionStore) shared by server replicas
+//! - `transform`: The [`TransformNode`](transform::TransformNode) that
+//!   reshapes data between nodes with jq filters or JSONPath queries
+//! - `yaml`: YAML [fixture](fixtures::Fixtures::from_yaml) files and flow
+//!   [definitions](config::FlowConfig::load)
+//! - `otel`: [`otel::layer`] exports flow and node spans to OpenTelemetry
+//! - `candle`: [`LocalModel`](local_llm::LocalModel) runs GGUF models
+//!   in-process for offline agents, and
+//!   [`LocalEmbedder`](local_embeddings::LocalEmbedder) embeds text for
+//!   retrieval without an external API
 
+pub mod accumulate;
+pub mod agent;
+pub mod alert;
+pub mod auth;
 pub mod batch;
+pub mod branch;
+pub mod budget;
+pub mod cache;
+pub mod checkpoint;
+pub mod command;
+pub mod config;
+pub mod constraint;
+pub mod dataset;
+pub mod deadline;
+mod diagram;
+pub mod difficulty;
+pub mod embeddings;
 pub mod error;
+pub mod events;
+pub mod executor;
+pub mod experiment;
+pub mod explore;
+pub mod fallback;
+pub mod fixtures;
 pub mod flow;
+pub mod graph;
+#[cfg(feature = "reqwest")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod history;
+#[cfg(feature = "reqwest")]
+pub mod http;
+pub mod jobs;
+pub mod judge;
+pub mod lifecycle;
+pub mod llm;
+#[cfg(feature = "candle")]
+pub mod local_embeddings;
+#[cfg(feature = "candle")]
+pub mod local_llm;
+pub mod looping;
+pub mod macros;
+pub mod map_reduce;
+pub mod memory;
+pub mod metrics;
 pub mod node;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod overlay;
+pub mod pack;
+pub mod pack_store;
+pub mod prompt;
+pub mod race;
+pub mod rate_limit;
+pub mod reflection;
+pub mod registry;
+pub mod report;
+pub mod resources;
+pub mod router;
+pub mod sampling;
+pub mod scaffold;
+pub mod schema;
+pub mod scorer;
+pub mod security;
+pub mod selector;
+pub mod server;
+pub mod session;
+pub mod simulation;
+pub mod state;
+pub mod stream;
+pub mod structured;
+mod telemetry;
 pub mod tool;
+pub mod tool_emulation;
+pub mod transcript;
+#[cfg(feature = "transform")]
+pub mod transform;
+pub mod vector_store;
 
 // Re-export commonly used types for convenience
-pub use batch::Batch;
+pub use agent::ReActAgent;
+pub use batch::{Batch, BatchFlow};
 pub use error::FlowError;
 pub use flow::{Flow, ParallelFlow};
+pub use macros::{node, tool};
 pub use node::Node;
-pub use tool::{Tool, ToolNode};
+pub use tool::{Tool, ToolNode, ToolRegistry};
+
+// Paths used by the code the `node` and `tool` macros generate
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait::async_trait;
+    pub use schemars;
+    pub use serde;
+    pub use serde_json;
+}
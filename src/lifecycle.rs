@@ -0,0 +1,204 @@
+//! Nodes written in three phases: prep, exec and post.
+//!
+//! A [`LifecycleNode`] splits its work the way PocketFlow nodes do:
+//! [`prep`](LifecycleNode::prep) reads what it needs from the shared value
+//! passed along the flow, [`exec`](LifecycleNode::exec) does the actual
+//! computation on that alone, and [`post`](LifecycleNode::post) writes the
+//! result back into the shared value and picks the next action. Keeping
+//! `exec` free of the shared value makes it safe to retry, so
+//! [`Lifecycle`], the adapter that runs a `LifecycleNode` as a [`Node`],
+//! retries only that phase.
+//!
+//! The next action is chosen by setting the `action` field of the value
+//! `post` returns, which a [`GraphFlow`](crate::graph::GraphFlow) follows
+//! with [`Condition::Action`](crate::graph::Condition::Action) edges.
+//! Existing [`Node`]s are unaffected and mix freely with lifecycle nodes.
+
+use crate::error::FlowError;
+use crate::node::{short_type_name, Node};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::time::Duration;
+
+/// A node that runs in three phases, wrapped in [`Lifecycle`] to be used as
+/// a [`Node`].
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::lifecycle::{Lifecycle, LifecycleNode};
+/// use rustyflow::{Flow, FlowError};
+/// use serde_json::{json, Value};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// /// Summarizes the shared `text`, through a model that fails at first.
+/// struct Summarize {
+///     attempts: AtomicUsize,
+/// }
+///
+/// #[async_trait]
+/// impl LifecycleNode for Summarize {
+///     async fn prep(&self, shared: &Value) -> Result<Value, FlowError> {
+///         Ok(shared["text"].clone())
+///     }
+///
+///     async fn exec(&self, text: Value) -> Result<Value, FlowError> {
+///         if self.attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+///             return Err(FlowError::NodeFailed("model overloaded".to_string()));
+///         }
+///         let text = text.as_str().unwrap_or_default();
+///         Ok(json!(text.split('.').next().unwrap_or_default()))
+///     }
+///
+///     async fn post(&self, mut shared: Value, _text: Value, summary: Value) -> Result<Value, FlowError> {
+///         let done = summary.as_str().is_some_and(|summary| !summary.is_empty());
+///         shared["summary"] = summary;
+///         shared["action"] = json!(if done { "publish" } else { "retry" });
+///         Ok(shared)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let node = Lifecycle::new(Summarize { attempts: AtomicUsize::new(0) }).with_max_retries(2);
+/// let flow = Flow::new(vec![Box::new(node)]);
+///
+/// let (shared, report) = flow
+///     .execute_traced(json!({"id": 1, "text": "First point. Second point."}))
+///     .await;
+/// assert_eq!(
+///     shared?,
+///     json!({"id": 1, "text": "First point. Second point.", "summary": "First point", "action": "publish"})
+/// );
+/// assert_eq!(report.nodes[0].retries, 2);
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait LifecycleNode: Send + Sync {
+    /// Read what [`exec`](LifecycleNode::exec) needs from the shared value.
+    /// The default passes the whole shared value on.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shared value lacks what the node needs.
+    async fn prep(&self, shared: &Value) -> Result<Value, FlowError> {
+        Ok(shared.clone())
+    }
+
+    /// Compute a result from what [`prep`](LifecycleNode::prep) read.
+    ///
+    /// May be called several times for one run when it fails, so it should
+    /// not touch anything but its argument.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the computation fails; the adapter retries it
+    /// and then hands it to [`exec_fallback`](LifecycleNode::exec_fallback).
+    async fn exec(&self, prep: Value) -> Result<Value, FlowError>;
+
+    /// Produce a result once every attempt of
+    /// [`exec`](LifecycleNode::exec) failed. The default returns the last
+    /// error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no result can stand in for the failed ones.
+    async fn exec_fallback(&self, prep: Value, error: FlowError) -> Result<Value, FlowError> {
+        let _ = prep;
+        Err(error)
+    }
+
+    /// Write the result into the shared value and pick the next action by
+    /// setting its `action` field. The returned value is the node's output.
+    /// The default outputs the result of [`exec`](LifecycleNode::exec).
+    ///
+    /// # Arguments
+    ///
+    /// * `shared` - The node's input
+    /// * `prep` - What [`prep`](LifecycleNode::prep) read
+    /// * `exec` - What [`exec`](LifecycleNode::exec) computed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the result cannot be recorded.
+    async fn post(&self, shared: Value, prep: Value, exec: Value) -> Result<Value, FlowError> {
+        let _ = (shared, prep);
+        Ok(exec)
+    }
+}
+
+/// A [`Node`] that runs a [`LifecycleNode`] through its three phases.
+///
+/// A failed [`exec`](LifecycleNode::exec) is retried up to
+/// [`with_max_retries`](Lifecycle::with_max_retries) times, waiting
+/// [`with_retry_wait`](Lifecycle::with_retry_wait) between attempts, before
+/// [`exec_fallback`](LifecycleNode::exec_fallback) gets the last error.
+/// Errors of `prep` and `post` are not retried. Retries are counted in the
+/// node's [`NodeReport`](crate::report::NodeReport).
+pub struct Lifecycle<N> {
+    node: N,
+    max_retries: usize,
+    retry_wait: Duration,
+}
+
+impl<N: LifecycleNode> Lifecycle<N> {
+    /// Wrap `node` so it can be used as a [`Node`], with no retries.
+    pub fn new(node: N) -> Self {
+        Self {
+            node,
+            max_retries: 0,
+            retry_wait: Duration::ZERO,
+        }
+    }
+
+    /// Retry a failed `exec` up to `max_retries` times.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Wait `wait` before each retry. Defaults to no wait.
+    pub fn with_retry_wait(mut self, wait: Duration) -> Self {
+        self.retry_wait = wait;
+        self
+    }
+}
+
+#[async_trait]
+impl<N: LifecycleNode> Node for Lifecycle<N> {
+    /// Run `prep`, `exec` with its retries and fallback, and `post`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `prep` or `post`, or of `exec_fallback` once
+    /// `exec` has failed every attempt.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let prep = self.node.prep(&input).await?;
+        let mut attempt = 0;
+        let exec = loop {
+            match self.node.exec(prep.clone()).await {
+                Ok(exec) => break exec,
+                Err(e) if attempt < self.max_retries => {
+                    attempt += 1;
+                    crate::report::record_retry();
+                    tracing::debug!(
+                        "Retrying '{}' after a failed attempt ({attempt}/{}): {e}",
+                        self.name(),
+                        self.max_retries
+                    );
+                    if !self.retry_wait.is_zero() {
+                        tokio::time::sleep(self.retry_wait).await;
+                    }
+                }
+                Err(e) => break self.node.exec_fallback(prep.clone(), e).await?,
+            }
+        };
+        self.node.post(input, prep, exec).await
+    }
+
+    fn name(&self) -> &str {
+        short_type_name::<N>()
+    }
+}
@@ -0,0 +1,318 @@
+//! Chat model abstraction for LLM-backed nodes.
+//!
+//! This module defines the [`ChatModel`] trait that LLM providers implement,
+//! the message types exchanged with them, and [`ChatNode`] for using a chat
+//! model directly inside a flow.
+
+use crate::constraint::Constraint;
+use crate::error::FlowError;
+use crate::node::Node;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// The author of a chat message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Instructions that steer the model's behavior.
+    System,
+    /// Input from the end user.
+    User,
+    /// Output produced by the model.
+    Assistant,
+    /// The result of a tool invocation requested by the model.
+    Tool,
+}
+
+/// A tool invocation requested by the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Provider-assigned identifier used to correlate the tool result.
+    pub id: String,
+    /// Name of the tool to invoke.
+    pub name: String,
+    /// JSON arguments for the tool.
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// A single message in a chat conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    /// The author of the message.
+    pub role: Role,
+    /// The text content of the message.
+    #[serde(default)]
+    pub content: String,
+    /// Tool calls requested by the model (assistant messages only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// The tool call this message answers (tool messages only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl Message {
+    fn with_role(role: Role, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    /// Create a system message.
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::with_role(Role::System, content)
+    }
+
+    /// Create a user message.
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::with_role(Role::User, content)
+    }
+
+    /// Create an assistant message.
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::with_role(Role::Assistant, content)
+    }
+
+    /// Create a tool result message answering the given tool call.
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::with_role(Role::Tool, content)
+        }
+    }
+}
+
+/// Description of a tool the model may call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolSpec {
+    /// Name the model uses to refer to the tool.
+    pub name: String,
+    /// Human-readable description of what the tool does.
+    pub description: String,
+    /// JSON Schema describing the tool's arguments.
+    pub parameters: Value,
+}
+
+/// A request sent to a [`ChatModel`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatRequest {
+    /// The conversation so far.
+    pub messages: Vec<Message>,
+    /// Tools the model is allowed to call.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolSpec>,
+    /// Sampling temperature, if the provider supports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Sampling seed, for providers that support reproducible sampling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Maximum number of tokens to generate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// The shape the reply must have, for models that
+    /// [support](ChatModel::supports_constraint) it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<Constraint>,
+}
+
+impl ChatRequest {
+    /// Create a request for the given conversation.
+    pub fn new(messages: Vec<Message>) -> Self {
+        Self {
+            messages,
+            ..Self::default()
+        }
+    }
+
+    /// Offer the given tools to the model.
+    pub fn with_tools(mut self, tools: Vec<ToolSpec>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Set the sampling temperature.
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the sampling seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Constrain the reply to the given shape.
+    pub fn with_constraint(mut self, constraint: Constraint) -> Self {
+        self.constraint = Some(constraint);
+        self
+    }
+}
+
+/// Token accounting reported by a provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Tokens consumed by the prompt.
+    pub prompt_tokens: u32,
+    /// Tokens generated in the completion.
+    pub completion_tokens: u32,
+}
+
+/// A response produced by a [`ChatModel`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatResponse {
+    /// The assistant message generated by the model.
+    pub message: Message,
+    /// Token usage, if reported by the provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// A chat-completion capable language model.
+///
+/// Implement this trait to plug an LLM provider into RustyFlow. Agent and
+/// prompt-oriented nodes are generic over `ChatModel` so they work with any
+/// provider.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::llm::{ChatModel, ChatRequest, ChatResponse, Message};
+/// use rustyflow::FlowError;
+///
+/// struct EchoModel;
+///
+/// #[async_trait]
+/// impl ChatModel for EchoModel {
+///     async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, FlowError> {
+///         let last = request.messages.last().map(|m| m.content.clone()).unwrap_or_default();
+///         Ok(ChatResponse { message: Message::assistant(last), usage: None })
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait ChatModel: Send + Sync {
+    /// Generate the next assistant message for the given request.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The conversation and generation options
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ChatResponse)` - The generated assistant message
+    /// * `Err(FlowError)` - An error if the provider call fails
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, FlowError>;
+
+    /// Whether the model enforces `constraint` while generating.
+    ///
+    /// A model that returns `true` must only produce replies that satisfy a
+    /// request's [`ChatRequest::constraint`]. Other models ignore the
+    /// constraint, so callers should validate their replies. The default is
+    /// `false`.
+    fn supports_constraint(&self, _constraint: &Constraint) -> bool {
+        false
+    }
+}
+
+#[async_trait]
+impl<M: ChatModel + ?Sized> ChatModel for Arc<M> {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, FlowError> {
+        (**self).chat(request).await
+    }
+
+    fn supports_constraint(&self, constraint: &Constraint) -> bool {
+        (**self).supports_constraint(constraint)
+    }
+}
+
+/// A node that sends its input to a [`ChatModel`].
+///
+/// The input is either a JSON array of messages or a full [`ChatRequest`]
+/// object (`{"messages": [...], "temperature": 0.2, "seed": 7}`). The output
+/// is the serialized [`ChatResponse`].
+pub struct ChatNode<M: ChatModel> {
+    model: M,
+}
+
+impl<M: ChatModel> ChatNode<M> {
+    /// Create a new chat node backed by the given model.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The chat model to call
+    pub fn new(model: M) -> Self {
+        Self { model }
+    }
+}
+
+#[async_trait]
+impl<M: ChatModel> Node for ChatNode<M> {
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let request = match input {
+            Value::Array(_) => ChatRequest::new(serde_json::from_value(input)?),
+            other => serde_json::from_value(other)?,
+        };
+        let response = record_usage(self.model.chat(request).await?);
+        Ok(serde_json::to_value(response)?)
+    }
+}
+
+/// Attribute a response's token usage to the current traced step.
+pub(crate) fn record_usage(response: ChatResponse) -> ChatResponse {
+    if let Some(usage) = response.usage {
+        crate::report::record_usage(usage);
+    }
+    response
+}
+
+/// Extract the first JSON object or array embedded in model output.
+///
+/// Models frequently wrap JSON in prose or Markdown code fences; this scans
+/// for the first balanced `{...}` or `[...]` block that parses as JSON.
+pub(crate) fn extract_json(text: &str) -> Option<Value> {
+    if let Ok(value) = serde_json::from_str(text.trim()) {
+        return Some(value);
+    }
+    for (start, open) in text.char_indices().filter(|(_, c)| *c == '{' || *c == '[') {
+        let close = if open == '{' { '}' } else { ']' };
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+        for (offset, c) in text[start..].char_indices() {
+            if in_string {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match c {
+                '"' => in_string = true,
+                c if c == open => depth += 1,
+                c if c == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let candidate = &text[start..start + offset + c.len_utf8()];
+                        if let Ok(value) = serde_json::from_str(candidate) {
+                            return Some(value);
+                        }
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
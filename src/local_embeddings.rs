@@ -0,0 +1,261 @@
+//! In-process text embeddings with BERT-family models.
+//!
+//! This module is available with the `candle` feature. It provides
+//! [`LocalEmbedder`], an [`Embedder`] that runs sentence-transformers models
+//! such as `all-MiniLM-L6-v2` or `bge-small-en-v1.5` with
+//! [candle](https://github.com/huggingface/candle), so retrieval flows can
+//! embed documents and queries without calling an external API.
+//!
+//! Models are read from a local directory or downloaded once from the
+//! Hugging Face Hub into its cache (`~/.cache/huggingface`, or `HF_HOME`),
+//! after which they load without network access.
+//!
+//! Inference runs on a CUDA or Metal GPU when candle is built with its
+//! `cuda` or `metal` feature and a device is present, and on the CPU
+//! otherwise. To build candle with GPU support, enable the feature on
+//! `candle-core` in your own manifest.
+
+use crate::embeddings::Embedder;
+use crate::error::FlowError;
+use async_trait::async_trait;
+use candle_core::{Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use std::path::Path;
+use std::sync::Arc;
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+/// The files a model directory must contain.
+const MODEL_FILES: [&str; 3] = ["config.json", "tokenizer.json", "model.safetensors"];
+
+/// A loaded model and the device it runs on.
+struct Loaded {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+fn inference_error(e: candle_core::Error) -> FlowError {
+    FlowError::NodeFailed(format!("Embedding inference failed: {e}"))
+}
+
+/// Pick the first available GPU, or the CPU.
+fn default_device() -> Device {
+    match Device::cuda_if_available(0) {
+        Ok(device) if device.is_cuda() => device,
+        _ => Device::metal_if_available(0).unwrap_or(Device::Cpu),
+    }
+}
+
+fn load(dir: &Path) -> Result<Loaded, FlowError> {
+    let read = |file: &str| {
+        let path = dir.join(file);
+        std::fs::read(&path)
+            .map_err(|e| FlowError::NodeFailed(format!("Cannot read {}: {e}", path.display())))
+    };
+    let config: Config = serde_json::from_slice(&read("config.json")?)?;
+
+    let mut tokenizer = Tokenizer::from_bytes(read("tokenizer.json")?)
+        .map_err(|e| FlowError::NodeFailed(format!("Cannot load tokenizer: {e}")))?;
+    tokenizer.with_padding(Some(PaddingParams {
+        strategy: PaddingStrategy::BatchLongest,
+        ..Default::default()
+    }));
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            max_length: config.max_position_embeddings,
+            ..Default::default()
+        }))
+        .map_err(|e| FlowError::NodeFailed(format!("Cannot configure tokenizer: {e}")))?;
+
+    let device = default_device();
+    let model_error = |e: candle_core::Error| {
+        FlowError::NodeFailed(format!("Cannot load model {}: {e}", dir.display()))
+    };
+    let weights = VarBuilder::from_buffered_safetensors(read("model.safetensors")?, DTYPE, &device)
+        .map_err(model_error)?;
+    let model = BertModel::load(weights, &config).map_err(model_error)?;
+    Ok(Loaded {
+        model,
+        tokenizer,
+        device,
+    })
+}
+
+/// A BERT-family sentence embedding model running in-process.
+///
+/// Texts are embedded in batches, each padded to its longest text and cut
+/// to the model's maximum length. Token vectors are mean-pooled into one
+/// vector per text, which is scaled to unit length unless
+/// [`LocalEmbedder::with_normalize`] turns that off.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use rustyflow::embeddings::{cosine_similarity, Embedder};
+/// use rustyflow::local_embeddings::LocalEmbedder;
+/// use rustyflow::FlowError;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// // Downloaded on first use, then loaded from the cache
+/// let embedder = LocalEmbedder::from_hub("sentence-transformers/all-MiniLM-L6-v2")
+///     .await?
+///     .with_batch_size(64);
+///
+/// let texts = ["A cat sat on the mat.", "A kitten rests on a rug.", "Stock prices fell."];
+/// let texts: Vec<String> = texts.iter().map(|text| text.to_string()).collect();
+/// let vectors = embedder.embed(&texts).await?;
+///
+/// assert_eq!(vectors[0].len(), 384);
+/// assert!(cosine_similarity(&vectors[0], &vectors[1]) > cosine_similarity(&vectors[0], &vectors[2]));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct LocalEmbedder {
+    loaded: Arc<Loaded>,
+    batch_size: usize,
+    normalize: bool,
+}
+
+impl LocalEmbedder {
+    /// Load a model from a directory holding its `config.json`,
+    /// `tokenizer.json` and `model.safetensors`.
+    ///
+    /// Loading reads the whole model into memory and blocks; call it at
+    /// startup or within `tokio::task::spawn_blocking`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if a file cannot be read or does not
+    /// hold a BERT-family model, or `FlowError::SerdeError` if `config.json`
+    /// is malformed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, FlowError> {
+        Ok(Self::from_loaded(load(dir.as_ref())?))
+    }
+
+    /// Load a model from the Hugging Face Hub, such as
+    /// `sentence-transformers/all-MiniLM-L6-v2`, downloading its files only
+    /// if they are not cached yet.
+    ///
+    /// The `HF_HOME` and `HF_TOKEN` environment variables select the cache
+    /// and authenticate access to gated models.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if a file cannot be downloaded, and
+    /// otherwise the same errors as [`LocalEmbedder::open`].
+    pub async fn from_hub(model_id: &str) -> Result<Self, FlowError> {
+        let download_error = |e: hf_hub::api::tokio::ApiError| {
+            FlowError::NodeFailed(format!("Cannot download model '{model_id}': {e}"))
+        };
+        let api = hf_hub::api::tokio::ApiBuilder::from_env()
+            .with_progress(false)
+            .build()
+            .map_err(download_error)?;
+        let repo = api.model(model_id.to_string());
+
+        let mut dir = None;
+        for file in MODEL_FILES {
+            let path = repo.get(file).await.map_err(download_error)?;
+            dir = path.parent().map(Path::to_path_buf);
+        }
+        // Every file of a revision is stored in the same snapshot directory
+        let dir = dir.ok_or_else(|| {
+            FlowError::NodeFailed(format!("Model '{model_id}' has no cache directory"))
+        })?;
+        let loaded = tokio::task::spawn_blocking(move || load(&dir))
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Model loading task failed: {e}")))??;
+        Ok(Self::from_loaded(loaded))
+    }
+
+    fn from_loaded(loaded: Loaded) -> Self {
+        Self {
+            loaded: Arc::new(loaded),
+            batch_size: 32,
+            normalize: true,
+        }
+    }
+
+    /// Run at most `batch_size` texts through the model at once. Larger
+    /// batches are faster on a GPU but need more memory. The default is 32.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Whether to scale vectors to unit length, so dot products equal
+    /// cosine similarities. The default is `true`.
+    pub fn with_normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Whether inference runs on a GPU.
+    pub fn uses_gpu(&self) -> bool {
+        !self.loaded.device.is_cpu()
+    }
+}
+
+#[async_trait]
+impl Embedder for LocalEmbedder {
+    /// Embed `texts` batch by batch on a blocking thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if tokenization or inference fails.
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, FlowError> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size) {
+            let loaded = Arc::clone(&self.loaded);
+            let batch = batch.to_vec();
+            let normalize = self.normalize;
+            let embedded =
+                tokio::task::spawn_blocking(move || embed_batch(&loaded, batch, normalize))
+                    .await
+                    .map_err(|e| FlowError::NodeFailed(format!("Embedding task failed: {e}")))??;
+            vectors.extend(embedded);
+        }
+        Ok(vectors)
+    }
+}
+
+fn embed_batch(
+    loaded: &Loaded,
+    texts: Vec<String>,
+    normalize: bool,
+) -> Result<Vec<Vec<f32>>, FlowError> {
+    let encodings = loaded
+        .tokenizer
+        .encode_batch(texts, true)
+        .map_err(|e| FlowError::NodeFailed(format!("Cannot tokenize texts: {e}")))?;
+
+    let stack = |rows: Vec<&[u32]>| -> Result<Tensor, candle_core::Error> {
+        let rows = rows
+            .into_iter()
+            .map(|row| Tensor::new(row, &loaded.device))
+            .collect::<Result<Vec<_>, _>>()?;
+        Tensor::stack(&rows, 0)
+    };
+    let ids = stack(encodings.iter().map(|e| e.get_ids()).collect()).map_err(inference_error)?;
+    let mask = stack(encodings.iter().map(|e| e.get_attention_mask()).collect())
+        .map_err(inference_error)?;
+
+    let pooled = (|| {
+        let token_types = ids.zeros_like()?;
+        let hidden = loaded.model.forward(&ids, &token_types, Some(&mask))?;
+
+        // Average the vectors of real tokens, ignoring padding
+        let mask = mask.to_dtype(DTYPE)?.unsqueeze(2)?;
+        let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+        let mut pooled = summed.broadcast_div(&mask.sum(1)?)?;
+        if normalize {
+            let norms = pooled.sqr()?.sum_keepdim(1)?.sqrt()?;
+            pooled = pooled.broadcast_div(&norms)?;
+        }
+        pooled.to_vec2::<f32>()
+    })();
+    pooled.map_err(inference_error)
+}
@@ -0,0 +1,488 @@
+//! In-process language models loaded from GGUF files.
+//!
+//! This module is available with the `candle` feature. It provides
+//! [`LocalModel`], a [`ChatModel`] that runs quantized Llama-family models
+//! (Llama, Mistral, Qwen and other GGUF files with the `llama`
+//! architecture) on the CPU with [candle](https://github.com/huggingface/candle),
+//! so agents can run without network access.
+//!
+//! Loading a model is slow and its weights are large, so loaded models are
+//! cached: every `LocalModel` opened on the same files shares one copy of
+//! the weights for as long as any of them is alive. Requests to a shared
+//! model wait in one first-come, first-served queue and are generated one
+//! at a time on a blocking thread, so the async runtime stays responsive.
+//! To bound how many requests wait, tag the [`ChatNode`](crate::llm::ChatNode)
+//! with a resource from [`resources`](crate::resources).
+//!
+//! Requests with a regex or JSON Schema [`Constraint`] are decoded under it:
+//! at every step, tokens that would take the reply off every path to a
+//! match are masked out before sampling, so the reply always matches.
+
+use crate::constraint::Constraint;
+use crate::error::FlowError;
+use crate::llm::{ChatModel, ChatRequest, ChatResponse, Message, Role, Usage};
+use async_trait::async_trait;
+use candle_core::quantized::gguf_file;
+use candle_core::{Device, Tensor};
+use candle_transformers::generation::LogitsProcessor;
+use candle_transformers::models::quantized_llama::ModelWeights;
+use regex_automata::dfa::{dense, Automaton};
+use regex_automata::util::primitives::StateID;
+use regex_automata::util::start;
+use regex_automata::Anchored;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use tokenizers::Tokenizer;
+
+/// How chat messages are laid out in the prompt the model was trained on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatFormat {
+    /// `<|im_start|>role ... <|im_end|>`, used by Qwen and many fine-tunes.
+    ChatMl,
+    /// `<|start_header_id|>role<|end_header_id|> ... <|eot_id|>`, used by
+    /// Llama 3.
+    Llama3,
+    /// `[INST] ... [/INST]`, used by Llama 2 and Mistral.
+    Mistral,
+}
+
+impl ChatFormat {
+    /// Render `messages` as a prompt that ends where the assistant's reply
+    /// begins.
+    fn render(self, messages: &[Message]) -> String {
+        let mut prompt = String::new();
+        match self {
+            ChatFormat::ChatMl => {
+                for message in messages {
+                    prompt.push_str(&format!(
+                        "<|im_start|>{}\n{}<|im_end|>\n",
+                        role_name(message.role),
+                        message.content
+                    ));
+                }
+                prompt.push_str("<|im_start|>assistant\n");
+            }
+            ChatFormat::Llama3 => {
+                for message in messages {
+                    prompt.push_str(&format!(
+                        "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                        role_name(message.role),
+                        message.content
+                    ));
+                }
+                prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+            }
+            ChatFormat::Mistral => {
+                // System prompts are folded into the first instruction
+                let mut pending = String::new();
+                for message in messages {
+                    match message.role {
+                        Role::Assistant => {
+                            prompt.push_str(&format!("{}</s>", message.content));
+                        }
+                        _ => {
+                            pending.push_str(&message.content);
+                            if message.role == Role::System {
+                                pending.push_str("\n\n");
+                                continue;
+                            }
+                            prompt.push_str(&format!("[INST] {} [/INST]", pending.trim_end()));
+                            pending.clear();
+                        }
+                    }
+                }
+            }
+        }
+        prompt
+    }
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::Tool => "tool",
+    }
+}
+
+/// Tokens that end a reply in any of the supported formats.
+const STOP_TOKENS: &[&str] = &["</s>", "<|im_end|>", "<|eot_id|>", "<|end_of_text|>"];
+
+/// Weights and tokenizer shared by every [`LocalModel`] opened on the same
+/// files.
+struct Loaded {
+    weights: Arc<tokio::sync::Mutex<ModelWeights>>,
+    tokenizer: Tokenizer,
+    stop_tokens: Vec<u32>,
+    token_bytes: OnceLock<Vec<Vec<u8>>>,
+}
+
+impl Loaded {
+    /// The bytes each token adds to a reply, computed on first use.
+    fn token_bytes(&self) -> &[Vec<u8>] {
+        self.token_bytes.get_or_init(|| {
+            // Decoding after an anchor token keeps the leading space that
+            // tokenizers drop at the start of a text
+            let tokenizer = &self.tokenizer;
+            let anchor = tokenizer
+                .encode("a", false)
+                .ok()
+                .and_then(|encoding| encoding.get_ids().first().copied());
+            let prefix = anchor
+                .and_then(|anchor| tokenizer.decode(&[anchor], true).ok())
+                .unwrap_or_default();
+            (0..tokenizer.get_vocab_size(true) as u32)
+                .map(|id| {
+                    if let Some(byte) = tokenizer.id_to_token(id).as_deref().and_then(byte_token) {
+                        return vec![byte];
+                    }
+                    let ids: Vec<u32> = anchor.into_iter().chain([id]).collect();
+                    match tokenizer.decode(&ids, true) {
+                        Ok(text) => text.strip_prefix(&prefix).unwrap_or("").as_bytes().to_vec(),
+                        Err(_) => Vec::new(),
+                    }
+                })
+                .collect()
+        })
+    }
+}
+
+/// The byte of a byte-fallback token such as `<0x0A>`.
+fn byte_token(token: &str) -> Option<u8> {
+    let hex = token.strip_prefix("<0x")?.strip_suffix('>')?;
+    u8::from_str_radix(hex, 16).ok()
+}
+
+/// Tracks a reply through the automaton of a regular expression.
+struct Guide {
+    dfa: dense::DFA<Vec<u32>>,
+    state: StateID,
+}
+
+impl Guide {
+    fn new(pattern: &str) -> Result<Self, FlowError> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            FlowError::NodeFailed(format!("Cannot compile constraint: {e}"))
+        };
+        let dfa = dense::DFA::new(&format!("(?:{pattern})$")).map_err(|e| invalid(&e))?;
+        let state = dfa
+            .start_state(&start::Config::new().anchored(Anchored::Yes))
+            .map_err(|e| invalid(&e))?;
+        Ok(Self { dfa, state })
+    }
+
+    /// The state after `bytes`, or `None` if no match can follow them.
+    fn advance(&self, bytes: &[u8]) -> Option<StateID> {
+        let mut state = self.state;
+        for &byte in bytes {
+            state = self.dfa.next_state(state, byte);
+            if self.dfa.is_dead_state(state) {
+                return None;
+            }
+        }
+        Some(state)
+    }
+
+    /// Whether the reply so far matches in full.
+    fn is_complete(&self) -> bool {
+        self.dfa.is_match_state(self.dfa.next_eoi_state(self.state))
+    }
+}
+
+type Cache = Mutex<HashMap<(PathBuf, PathBuf), Weak<Loaded>>>;
+
+fn cache() -> &'static Cache {
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+fn load(model_path: &Path, tokenizer_path: &Path) -> Result<Arc<Loaded>, FlowError> {
+    let key = (model_path.to_path_buf(), tokenizer_path.to_path_buf());
+    let mut cache = cache().lock().unwrap();
+    if let Some(loaded) = cache.get(&key).and_then(Weak::upgrade) {
+        return Ok(loaded);
+    }
+
+    let model_error = |e: candle_core::Error| {
+        FlowError::NodeFailed(format!("Cannot load model {}: {e}", model_path.display()))
+    };
+    let mut file = std::fs::File::open(model_path).map_err(|e| {
+        FlowError::NodeFailed(format!("Cannot open model {}: {e}", model_path.display()))
+    })?;
+    let content = gguf_file::Content::read(&mut file).map_err(model_error)?;
+    let weights = ModelWeights::from_gguf(content, &mut file, &Device::Cpu).map_err(model_error)?;
+    let tokenizer = Tokenizer::from_file(tokenizer_path).map_err(|e| {
+        FlowError::NodeFailed(format!(
+            "Cannot load tokenizer {}: {e}",
+            tokenizer_path.display()
+        ))
+    })?;
+    let stop_tokens = STOP_TOKENS
+        .iter()
+        .filter_map(|token| tokenizer.token_to_id(token))
+        .collect();
+
+    let loaded = Arc::new(Loaded {
+        weights: Arc::new(tokio::sync::Mutex::new(weights)),
+        tokenizer,
+        stop_tokens,
+        token_bytes: OnceLock::new(),
+    });
+    cache.insert(key, Arc::downgrade(&loaded));
+    Ok(loaded)
+}
+
+/// A quantized model running in-process.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use rustyflow::llm::{ChatModel, ChatRequest, Message};
+/// use rustyflow::local_llm::{ChatFormat, LocalModel};
+/// use rustyflow::FlowError;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let model = LocalModel::open("models/qwen2.5-0.5b-q4.gguf", "models/tokenizer.json")?
+///     .with_chat_format(ChatFormat::ChatMl)
+///     .with_max_tokens(256);
+///
+/// let request = ChatRequest::new(vec![Message::user("Name three primary colors.")]);
+/// let response = model.chat(request).await?;
+/// println!("{}", response.message.content);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct LocalModel {
+    loaded: Arc<Loaded>,
+    format: ChatFormat,
+    max_tokens: u32,
+    temperature: f64,
+    seed: Option<u64>,
+}
+
+impl LocalModel {
+    /// Open a GGUF model with the `tokenizer.json` published alongside it,
+    /// or share an already loaded copy.
+    ///
+    /// Loading reads the whole model into memory and blocks; call it at
+    /// startup or within `tokio::task::spawn_blocking`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if either file cannot be read or the
+    /// model's architecture is not supported.
+    pub fn open(
+        model_path: impl AsRef<Path>,
+        tokenizer_path: impl AsRef<Path>,
+    ) -> Result<Self, FlowError> {
+        Ok(Self {
+            loaded: load(model_path.as_ref(), tokenizer_path.as_ref())?,
+            format: ChatFormat::ChatMl,
+            max_tokens: 512,
+            temperature: 0.7,
+            seed: None,
+        })
+    }
+
+    /// Lay out prompts in `format`, [`ChatFormat::ChatMl`] by default.
+    pub fn with_chat_format(mut self, format: ChatFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Generate at most `max_tokens` per reply unless a request asks for
+    /// fewer. The default is 512.
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Sample with `temperature` unless a request sets one. `0.0` always
+    /// picks the most likely token. The default is 0.7.
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Seed the sampler of every reply with `seed` unless a request sets
+    /// one, for reproducible replies. By default each reply is sampled with
+    /// a fresh random seed, so that repeated prompts can get different
+    /// replies.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+#[async_trait]
+impl ChatModel for LocalModel {
+    /// Generate a reply to the request's messages.
+    ///
+    /// Tool specs are not passed to the model; wrap it in
+    /// [`EmulatedTools`](crate::tool_emulation::EmulatedTools) or use the
+    /// text protocol of [`ReActAgent`](crate::ReActAgent) for tool use.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if tokenization or inference fails,
+    /// the constraint's regex is invalid, or a constrained reply does not
+    /// complete within the token limit.
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, FlowError> {
+        let pattern = match &request.constraint {
+            Some(constraint) => {
+                let pattern = constraint.to_regex();
+                if pattern.is_none() {
+                    tracing::warn!("LocalModel cannot enforce {constraint:?}; ignoring it");
+                }
+                pattern
+            }
+            None => None,
+        };
+        let prompt = self.format.render(&request.messages);
+        let max_tokens = request.max_tokens.unwrap_or(self.max_tokens);
+        let temperature = request.temperature.map_or(self.temperature, f64::from);
+        let loaded = Arc::clone(&self.loaded);
+        let seed = request
+            .seed
+            .or(self.seed)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().as_u64_pair().0);
+
+        // Wait for our turn, then generate without blocking the runtime
+        let weights = Arc::clone(&loaded.weights).lock_owned().await;
+        tokio::task::spawn_blocking(move || {
+            let mut weights = weights;
+            let guide = pattern.as_deref().map(Guide::new).transpose()?;
+            generate(
+                &loaded,
+                &mut weights,
+                &prompt,
+                guide,
+                max_tokens,
+                temperature,
+                seed,
+            )
+        })
+        .await
+        .map_err(|e| FlowError::NodeFailed(format!("Inference task failed: {e}")))?
+    }
+
+    /// Regular expressions, and the JSON Schemas that
+    /// [`Constraint::to_regex`] can translate.
+    fn supports_constraint(&self, constraint: &Constraint) -> bool {
+        constraint.to_regex().is_some()
+    }
+}
+
+fn generate(
+    loaded: &Loaded,
+    weights: &mut ModelWeights,
+    prompt: &str,
+    mut guide: Option<Guide>,
+    max_tokens: u32,
+    temperature: f64,
+    seed: u64,
+) -> Result<ChatResponse, FlowError> {
+    let inference_error =
+        |e: candle_core::Error| FlowError::NodeFailed(format!("Inference failed: {e}"));
+    let prompt_tokens = loaded
+        .tokenizer
+        .encode(prompt, true)
+        .map_err(|e| FlowError::NodeFailed(format!("Cannot tokenize prompt: {e}")))?
+        .get_ids()
+        .to_vec();
+
+    let mut sampler = LogitsProcessor::new(seed, Some(temperature), None);
+    let mut generated: Vec<u32> = Vec::new();
+    let mut input = prompt_tokens.clone();
+    let mut position = 0;
+    while generated.len() < max_tokens as usize {
+        let tensor = Tensor::new(input.as_slice(), &Device::Cpu)
+            .and_then(|tensor| tensor.unsqueeze(0))
+            .map_err(inference_error)?;
+        let mut logits = weights
+            .forward(&tensor, position)
+            .and_then(|logits| logits.squeeze(0))
+            .map_err(inference_error)?;
+        position += input.len();
+
+        if let Some(guide) = &guide {
+            match constrain(loaded, guide, &logits).map_err(inference_error)? {
+                Some(masked) => logits = masked,
+                None => break,
+            }
+        }
+        let next = sampler.sample(&logits).map_err(inference_error)?;
+        if loaded.stop_tokens.contains(&next) {
+            break;
+        }
+        if let Some(guide) = &mut guide {
+            let bytes = &loaded.token_bytes()[next as usize];
+            guide.state = guide.advance(bytes).unwrap_or(guide.state);
+        }
+        generated.push(next);
+        input = vec![next];
+    }
+    if guide.as_ref().is_some_and(|guide| !guide.is_complete()) {
+        return Err(FlowError::NodeFailed(format!(
+            "Constrained reply did not complete within {max_tokens} tokens"
+        )));
+    }
+
+    let content = loaded
+        .tokenizer
+        .decode(&generated, true)
+        .map_err(|e| FlowError::NodeFailed(format!("Cannot decode reply: {e}")))?;
+    // Whitespace may be part of a constrained reply
+    let content = match guide {
+        Some(_) => content.as_str(),
+        None => content.trim(),
+    };
+    Ok(ChatResponse {
+        message: Message::assistant(content),
+        usage: Some(Usage {
+            prompt_tokens: prompt_tokens.len() as u32,
+            completion_tokens: generated.len() as u32,
+        }),
+    })
+}
+
+/// Mask the logits of tokens that cannot continue the reply toward a match.
+///
+/// Stop tokens are allowed once the reply matches in full. Returns `None`
+/// if the reply is complete and no token may follow it.
+fn constrain(
+    loaded: &Loaded,
+    guide: &Guide,
+    logits: &Tensor,
+) -> Result<Option<Tensor>, candle_core::Error> {
+    let token_bytes = loaded.token_bytes();
+    let complete = guide.is_complete();
+    let mut values = logits.to_vec1::<f32>()?;
+    let mut any_allowed = false;
+    for (id, value) in values.iter_mut().enumerate() {
+        let allowed = if loaded.stop_tokens.contains(&(id as u32)) {
+            complete
+        } else {
+            match token_bytes.get(id) {
+                Some(bytes) if !bytes.is_empty() => guide.advance(bytes).is_some(),
+                _ => false,
+            }
+        };
+        if allowed {
+            any_allowed = true;
+        } else {
+            *value = f32::NEG_INFINITY;
+        }
+    }
+    if !any_allowed {
+        if complete {
+            return Ok(None);
+        }
+        candle_core::bail!("no token can continue the constrained reply");
+    }
+    Tensor::new(values, logits.device()).map(Some)
+}
@@ -0,0 +1,119 @@
+//! Repeating a node while a condition holds.
+//!
+//! This module provides [`Loop`], which runs a node, or a whole sub-flow,
+//! again on its own output for as long as a [`Condition`] over that output
+//! holds: refine a draft until it validates, or let an agent correct itself
+//! until it stops asking for another pass. Every loop has an iteration cap,
+//! and running into it is an error rather than a silently unfinished result.
+
+use crate::error::FlowError;
+use crate::graph::Condition;
+use crate::node::{HealthCheck, Node, SchemaProvider};
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// A node that calls an inner node on its own output while a condition
+/// holds.
+///
+/// The inner node always runs at least once. After each run the condition
+/// is checked against the output: if it holds, the output is fed back in;
+/// otherwise it is returned.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::graph::Condition;
+/// use rustyflow::looping::Loop;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// /// Fixes one problem per pass.
+/// struct Refine;
+///
+/// #[async_trait]
+/// impl Node for Refine {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let problems = input["problems"].as_u64().unwrap_or(0).saturating_sub(1);
+///         Ok(json!({"problems": problems, "valid": problems == 0}))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let refine = Loop::new(
+///     Box::new(Refine),
+///     Condition::Equals("/valid".into(), json!(false)),
+///     5,
+/// );
+/// let result = refine.call(json!({"problems": 3})).await?;
+/// assert_eq!(result, json!({"problems": 0, "valid": true}));
+///
+/// // Too many problems for the cap
+/// let error = refine.call(json!({"problems": 9})).await.unwrap_err();
+/// assert!(matches!(error, FlowError::LoopLimit(_)));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Loop {
+    node: Box<dyn Node>,
+    condition: Condition,
+    max_iterations: usize,
+}
+
+impl Loop {
+    /// Create a loop.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node or flow to repeat
+    /// * `condition` - Run again while this holds for the latest output
+    /// * `max_iterations` - The most times `node` may run
+    pub fn new(node: Box<dyn Node>, condition: Condition, max_iterations: usize) -> Self {
+        Self {
+            node,
+            condition,
+            max_iterations,
+        }
+    }
+}
+
+#[async_trait]
+impl Node for Loop {
+    /// Run the inner node until the condition no longer holds.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::LoopLimit` if the condition still holds after
+    /// `max_iterations` runs, or any error of the inner node.
+    async fn call(&self, mut input: Value) -> Result<Value, FlowError> {
+        for iteration in 1..=self.max_iterations {
+            input = self.node.call(input).await?;
+            if !self.condition.matches(&input) {
+                tracing::debug!(
+                    "Loop over '{}' finished after {iteration} iterations",
+                    self.node.name()
+                );
+                return Ok(input);
+            }
+        }
+        Err(FlowError::LoopLimit(format!(
+            "Loop over '{}' still matched '{}' after {} iterations",
+            self.node.name(),
+            self.condition,
+            self.max_iterations
+        )))
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.node.input_schema()
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        self.node.schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        self.node.health_checkable()
+    }
+}
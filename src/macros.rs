@@ -0,0 +1,74 @@
+//! Nodes and tools defined by plain async functions.
+//!
+//! [`node`] and [`tool`] turn an async fn with typed arguments into a unit
+//! struct, named after the function in `CamelCase`, that implements
+//! [`Node`](crate::Node) or [`Tool`](crate::Tool). The input is an object
+//! with one field per argument, deserialized into the arguments before the
+//! function is called, and its JSON Schema is derived from the argument
+//! types, so arguments need `Deserialize` and `JsonSchema`, and `Option`
+//! arguments may be left out. The function returns a `Serialize` value, or
+//! a `Result` of one whose error converts into [`FlowError`](crate::FlowError).
+//!
+//! The function itself is kept as written and can still be called
+//! directly. The struct also carries what registration needs:
+//!
+//! | Macro | Constants | Registers with |
+//! |-------|-----------|----------------|
+//! | `#[node]` | `TYPE_NAME` | `register(&mut NodeRegistry)`, under `TYPE_NAME` |
+//! | `#[tool]` | `NAME`, `DESCRIPTION` | `register(&mut ToolRegistry)`, under `NAME` |
+//!
+//! Names default to the function's name and can be set as
+//! `#[node(name = "...")]`. A tool's description is its doc comment, and
+//! its arguments are also available as a public `...Input` struct.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rustyflow::registry::NodeRegistry;
+//! use rustyflow::{Flow, FlowError, Node, ToolRegistry};
+//! use serde_json::json;
+//!
+//! /// Add two numbers.
+//! #[rustyflow::node]
+//! async fn add(a: i64, b: i64) -> Result<i64, FlowError> {
+//!     Ok(a + b)
+//! }
+//!
+//! #[rustyflow::node(name = "text.shout")]
+//! async fn shout(text: String, times: Option<usize>) -> String {
+//!     text.to_uppercase() + &"!".repeat(times.unwrap_or(1))
+//! }
+//!
+//! /// Look up the current temperature of a city.
+//! #[rustyflow::tool]
+//! async fn temperature(city: String) -> Result<f64, FlowError> {
+//!     match city.as_str() {
+//!         "Oslo" => Ok(4.5),
+//!         _ => Err(FlowError::NotFound(format!("No weather for {city}"))),
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), FlowError> {
+//! // Nodes run in flows and build from definitions by type name
+//! assert_eq!(Flow::new(vec![Box::new(Add)]).execute(json!({"a": 2, "b": 3})).await?, json!(5));
+//!
+//! let mut registry = NodeRegistry::new();
+//! Shout::register(&mut registry);
+//! let node = registry.build(&json!({"type": "text.shout"}))?;
+//! assert_eq!(node.call(json!({"text": "hi", "times": 2})).await?, json!("HI!!"));
+//! assert_eq!(node.input_schema().unwrap()["required"], json!(["text"]));
+//!
+//! // Tools describe themselves to models from their doc comment and arguments
+//! let mut tools = ToolRegistry::new();
+//! Temperature::register(&mut tools);
+//! let spec = &tools.specs()[0];
+//! assert_eq!(spec.name, "temperature");
+//! assert_eq!(spec.description, "Look up the current temperature of a city.");
+//! assert_eq!(spec.parameters["properties"]["city"]["type"], "string");
+//! assert_eq!(tools.invoke("temperature", json!({"city": "Oslo"})).await?, json!(4.5));
+//! # Ok(())
+//! # }
+//! ```
+
+pub use rustyflow_macros::{node, tool};
@@ -0,0 +1,129 @@
+//! Map-reduce over JSON arrays.
+//!
+//! This module provides [`MapReduce`], which applies a mapper node to each
+//! element of an array concurrently and then hands the array of results to a
+//! reducer node: summarize each chunk of a document, then merge the
+//! summaries; extract facts from each page, then deduplicate them.
+
+use crate::error::FlowError;
+use crate::node::Node;
+use crate::telemetry;
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde_json::Value;
+
+/// A node that maps each element of its input array with one node and
+/// reduces the results with another.
+///
+/// The reducer receives the mapped results as an array, in input order.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::map_reduce::MapReduce;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// /// Stands in for an LLM summary of one chunk.
+/// struct Summarize;
+///
+/// #[async_trait]
+/// impl Node for Summarize {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let first = input.as_str().unwrap_or("").split('.').next().unwrap_or("");
+///         Ok(json!(first))
+///     }
+/// }
+///
+/// /// Merges the chunk summaries.
+/// struct Merge;
+///
+/// #[async_trait]
+/// impl Node for Merge {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let parts: Vec<&str> = input
+///             .as_array()
+///             .into_iter()
+///             .flatten()
+///             .filter_map(Value::as_str)
+///             .collect();
+///         Ok(json!({"summary": parts.join(". ")}))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let summarize = MapReduce::new(Box::new(Summarize), Box::new(Merge)).with_max_concurrency(2);
+/// let chunks = json!([
+///     "Rust is fast. It has no GC.",
+///     "Tokio runs async tasks. It is popular.",
+///     "Serde handles JSON. It derives code.",
+/// ]);
+/// let result = summarize.call(chunks).await?;
+/// assert_eq!(
+///     result,
+///     json!({"summary": "Rust is fast. Tokio runs async tasks. Serde handles JSON"})
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub struct MapReduce {
+    mapper: Box<dyn Node>,
+    reducer: Box<dyn Node>,
+    max_concurrency: Option<usize>,
+}
+
+impl MapReduce {
+    /// Create a map-reduce node that maps every element at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `mapper` - The node applied to each element of the input array
+    /// * `reducer` - The node that receives the array of mapped results
+    pub fn new(mapper: Box<dyn Node>, reducer: Box<dyn Node>) -> Self {
+        Self {
+            mapper,
+            reducer,
+            max_concurrency: None,
+        }
+    }
+
+    /// Map at most `max` elements at a time, at least 1.
+    pub fn with_max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = Some(max.max(1));
+        self
+    }
+}
+
+#[async_trait]
+impl Node for MapReduce {
+    /// Map each element of the input array, then reduce the results.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the input is not a JSON array, the
+    /// first mapper error wrapped in a `FlowError::NodeError` carrying the
+    /// element's index, or any error of the reducer.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let Value::Array(elements) = input else {
+            return Err(FlowError::NodeFailed(
+                "MapReduce input must be a JSON array".to_string(),
+            ));
+        };
+
+        crate::metrics::batch_size(elements.len());
+        let span = telemetry::flow_span("MapReduce", None, elements.len(), None);
+        let limit = self.max_concurrency.unwrap_or(elements.len()).max(1);
+        let mapped = telemetry::in_flow_span(
+            span,
+            stream::iter(elements.into_iter().enumerate())
+                .map(|(index, element)| telemetry::call_node(&*self.mapper, index, element))
+                .buffered(limit)
+                .try_collect::<Vec<Value>>(),
+        )
+        .await?;
+
+        self.reducer.call(Value::Array(mapped)).await
+    }
+}
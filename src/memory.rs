@@ -0,0 +1,383 @@
+//! Conversation memory for multi-turn chat flows.
+//!
+//! A [`Memory`] keeps the messages of each conversation, keyed by a session
+//! id, and [`MemoryNode`] wraps a chat node or flow so that every call sees
+//! the conversation so far and adds its own turn to it. The node is called
+//! with `{"messages": [...]}`, the input [`ChatNode`](crate::llm::ChatNode)
+//! accepts.
+//!
+//! [`BufferMemory`] keeps every message. [`SummarizingMemory`] keeps
+//! conversations within a token budget: once a conversation grows past it,
+//! the oldest turns are folded into a rolling summary by a summarizer node,
+//! and only the summary and the most recent turns are returned. Both
+//! implement [`Memory`], so either can back a [`MemoryNode`] unchanged.
+
+use crate::error::FlowError;
+use crate::llm::Message;
+use crate::node::{HealthCheck, Node, SchemaProvider};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_SESSION: &str = "default";
+const DEFAULT_KEEP_RECENT: usize = 4;
+
+/// A store of conversations, keyed by session id.
+#[async_trait]
+pub trait Memory: Send + Sync {
+    /// The messages to send a model for a session.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The conversation to load
+    ///
+    /// # Returns
+    ///
+    /// The remembered messages in order, empty for an unknown session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the conversation cannot be loaded.
+    async fn messages(&self, session: &str) -> Result<Vec<Message>, FlowError>;
+
+    /// Add messages to the end of a session's conversation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the messages cannot be stored.
+    async fn append(&self, session: &str, messages: Vec<Message>) -> Result<(), FlowError>;
+}
+
+/// An in-process memory that keeps every message.
+#[derive(Default)]
+pub struct BufferMemory {
+    sessions: Mutex<HashMap<String, Vec<Message>>>,
+}
+
+impl BufferMemory {
+    /// Create an empty memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Memory for BufferMemory {
+    async fn messages(&self, session: &str) -> Result<Vec<Message>, FlowError> {
+        let sessions = self.sessions.lock().unwrap();
+        Ok(sessions.get(session).cloned().unwrap_or_default())
+    }
+
+    async fn append(&self, session: &str, messages: Vec<Message>) -> Result<(), FlowError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions
+            .entry(session.to_string())
+            .or_default()
+            .extend(messages);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+struct Summarized {
+    summary: Option<String>,
+    recent: Vec<Message>,
+}
+
+/// A memory that compresses old turns into a rolling summary once a
+/// conversation exceeds a token budget.
+///
+/// Tokens are estimated at four characters each. When a conversation's
+/// summary and recent messages go over the budget, every message but the
+/// last few is sent to the summarizer as
+/// `{"summary": <previous summary or null>, "messages": [...]}`, and its
+/// output (a string, a `summary` field, or the `content` of a
+/// [`ChatResponse`](crate::llm::ChatResponse)) replaces the summary. The
+/// summary is returned ahead of the recent messages as a system message.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::memory::{Memory, SummarizingMemory};
+/// use rustyflow::llm::{Message, Role};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// /// Stands in for an LLM summarizer: counts the messages it has seen.
+/// struct Counter;
+///
+/// #[async_trait]
+/// impl Node for Counter {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let seen = input["messages"].as_array().map_or(0, Vec::len);
+///         Ok(json!(format!("{seen} earlier messages")))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let memory = SummarizingMemory::new(Box::new(Counter), 20).with_keep_recent(2);
+/// for turn in 0..3 {
+///     memory
+///         .append("alice", vec![
+///             Message::user(format!("question {turn} with some detail")),
+///             Message::assistant(format!("answer {turn} with some detail")),
+///         ])
+///         .await?;
+/// }
+///
+/// let messages = memory.messages("alice").await?;
+/// assert_eq!(messages.len(), 3);
+/// assert_eq!(messages[0].role, Role::System);
+/// assert!(messages[0].content.contains("earlier messages"));
+/// assert_eq!(messages[2].content, "answer 2 with some detail");
+/// # Ok(())
+/// # }
+/// ```
+pub struct SummarizingMemory {
+    summarizer: Box<dyn Node>,
+    max_tokens: usize,
+    keep_recent: usize,
+    sessions: Mutex<HashMap<String, Arc<tokio::sync::Mutex<Summarized>>>>,
+}
+
+impl SummarizingMemory {
+    /// Create a memory that summarizes conversations longer than
+    /// `max_tokens`.
+    ///
+    /// # Arguments
+    ///
+    /// * `summarizer` - The node, usually an LLM prompt, that writes summaries
+    /// * `max_tokens` - The estimated token budget of a conversation
+    pub fn new(summarizer: Box<dyn Node>, max_tokens: usize) -> Self {
+        Self {
+            summarizer,
+            max_tokens,
+            keep_recent: DEFAULT_KEEP_RECENT,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The state of `session`, locked on its own so that summarizing one
+    /// conversation holds up no other.
+    fn session(&self, session: &str) -> Arc<tokio::sync::Mutex<Summarized>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        Arc::clone(sessions.entry(session.to_string()).or_default())
+    }
+
+    /// Keep the last `keep_recent` messages verbatim when summarizing
+    /// (default 4).
+    pub fn with_keep_recent(mut self, keep_recent: usize) -> Self {
+        self.keep_recent = keep_recent;
+        self
+    }
+
+    async fn summarize(
+        &self,
+        summary: Option<&str>,
+        messages: &[Message],
+    ) -> Result<String, FlowError> {
+        let output = self
+            .summarizer
+            .call(json!({ "summary": summary, "messages": messages }))
+            .await?;
+        let text = match &output {
+            Value::String(text) => Some(text.as_str()),
+            _ => output
+                .get("summary")
+                .or_else(|| output.pointer("/message/content"))
+                .and_then(Value::as_str),
+        };
+        text.map(str::to_string).ok_or_else(|| {
+            FlowError::NodeFailed(format!(
+                "No summary found in the summarizer's output: {}",
+                crate::error::input_snippet(&output)
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl Memory for SummarizingMemory {
+    async fn messages(&self, session: &str) -> Result<Vec<Message>, FlowError> {
+        let state = self.sessions.lock().unwrap().get(session).cloned();
+        let Some(state) = state else {
+            return Ok(Vec::new());
+        };
+        let state = state.lock().await;
+        Ok(state
+            .summary
+            .iter()
+            .map(|summary| summary_message(summary))
+            .chain(state.recent.iter().cloned())
+            .collect())
+    }
+
+    /// Add messages, summarizing older ones if the budget is exceeded.
+    ///
+    /// # Errors
+    ///
+    /// Returns the summarizer's error, or `FlowError::NodeFailed` if no
+    /// summary can be read from its output. The conversation is left
+    /// unsummarized, with the new messages added, when summarizing fails.
+    async fn append(&self, session: &str, messages: Vec<Message>) -> Result<(), FlowError> {
+        let state = self.session(session);
+        let mut state = state.lock().await;
+        state.recent.extend(messages);
+
+        let summary_tokens = state
+            .summary
+            .as_deref()
+            .map_or(0, |summary| estimate_tokens(&[summary_message(summary)]));
+        if summary_tokens + estimate_tokens(&state.recent) <= self.max_tokens
+            || state.recent.len() <= self.keep_recent
+        {
+            return Ok(());
+        }
+
+        let split = state.recent.len() - self.keep_recent;
+        let summary = self
+            .summarize(state.summary.as_deref(), &state.recent[..split])
+            .await?;
+        tracing::debug!("Summarized {split} messages of session '{session}'");
+        state.summary = Some(summary);
+        state.recent.drain(..split);
+        Ok(())
+    }
+}
+
+fn summary_message(summary: &str) -> Message {
+    Message::system(format!("Summary of the earlier conversation: {summary}"))
+}
+
+/// A rough token count, at four characters per token.
+fn estimate_tokens(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .map(|message| message.content.chars().count().div_ceil(4))
+        .sum()
+}
+
+/// A node that gives a chat node or flow a memory of the conversation.
+///
+/// The input is `{"session": "...", "message": "..."}`; `session` defaults
+/// to `"default"`. The inner node is called with the remembered messages
+/// followed by the new user message, as `{"messages": [...]}`, and its
+/// output is returned unchanged. Its reply (a string, the `content` of a
+/// [`ChatResponse`](crate::llm::ChatResponse), or an `answer` field) is then
+/// remembered together with the user message.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::memory::{BufferMemory, Memory, MemoryNode};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::Arc;
+///
+/// /// Replies with how many messages it was sent.
+/// struct Echo;
+///
+/// #[async_trait]
+/// impl Node for Echo {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(format!("seen {}", input["messages"].as_array().unwrap().len())))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let memory = Arc::new(BufferMemory::new());
+/// let chat = MemoryNode::new(memory.clone(), Box::new(Echo));
+///
+/// chat.call(json!({"session": "alice", "message": "hi"})).await?;
+/// let reply = chat.call(json!({"session": "alice", "message": "again"})).await?;
+/// assert_eq!(reply, json!("seen 3"));
+/// assert_eq!(memory.messages("alice").await?.len(), 4);
+/// # Ok(())
+/// # }
+/// ```
+pub struct MemoryNode {
+    memory: Arc<dyn Memory>,
+    node: Box<dyn Node>,
+}
+
+impl MemoryNode {
+    /// Create a node that remembers the conversations `node` takes part in.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory` - Where conversations are kept, possibly shared with other nodes
+    /// * `node` - The chat node or flow to call
+    pub fn new(memory: Arc<dyn Memory>, node: Box<dyn Node>) -> Self {
+        Self { memory, node }
+    }
+}
+
+#[async_trait]
+impl Node for MemoryNode {
+    /// Answer the message with the conversation so far, and remember the
+    /// turn.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the input has no `message` string
+    /// or the output has no reply, and any error of the memory or the inner
+    /// node.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let session = input
+            .get("session")
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_SESSION);
+        let message = input
+            .get("message")
+            .and_then(Value::as_str)
+            .map(Message::user)
+            .ok_or_else(|| {
+                FlowError::NodeFailed("MemoryNode input needs a 'message' string".to_string())
+            })?;
+
+        let mut messages = self.memory.messages(session).await?;
+        messages.push(message.clone());
+        let output = self.node.call(json!({ "messages": messages })).await?;
+
+        let reply = match &output {
+            Value::String(reply) => Some(reply.as_str()),
+            _ => output
+                .pointer("/message/content")
+                .or_else(|| output.get("answer"))
+                .and_then(Value::as_str),
+        }
+        .ok_or_else(|| {
+            FlowError::NodeFailed(format!(
+                "No reply found in the node's output: {}",
+                crate::error::input_snippet(&output)
+            ))
+        })?;
+        let reply = Message::assistant(reply);
+        self.memory.append(session, vec![message, reply]).await?;
+        Ok(output)
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "session": {"type": "string"},
+                "message": {"type": "string"}
+            },
+            "required": ["message"]
+        }))
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        self.node.schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        self.node.health_checkable()
+    }
+}
@@ -0,0 +1,80 @@
+//! Execution metrics recorded through the `metrics` crate.
+//!
+//! Flows, parallel flows, batches and cached nodes record the metrics below
+//! automatically. They are no-ops until the application installs a
+//! recorder, such as the Prometheus exporter used by the bundled server.
+//!
+//! | Name | Type | Labels |
+//! |------|------|--------|
+//! | `rustyflow_executions_started_total` | counter | `flow` |
+//! | `rustyflow_executions_succeeded_total` | counter | `flow` |
+//! | `rustyflow_executions_failed_total` | counter | `flow` |
+//! | `rustyflow_node_duration_seconds` | histogram | `node`, `status` |
+//! | `rustyflow_batch_size` | histogram | |
+//! | `rustyflow_cache_hits_total` | counter | `node` |
+//! | `rustyflow_cache_misses_total` | counter | `node` |
+//!
+//! The `flow` label is the flow's configured name, or its type (`Flow`,
+//! `ParallelFlow`, `Batch`) if it has none; `node` is [`Node::name`] and
+//! `status` is `ok` or `error`.
+//!
+//! [`Node::name`]: crate::node::Node::name
+
+use ::metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+use std::time::Duration;
+
+/// Counter of flow executions started.
+pub const EXECUTIONS_STARTED: &str = "rustyflow_executions_started_total";
+/// Counter of flow executions that returned a value.
+pub const EXECUTIONS_SUCCEEDED: &str = "rustyflow_executions_succeeded_total";
+/// Counter of flow executions that returned an error.
+pub const EXECUTIONS_FAILED: &str = "rustyflow_executions_failed_total";
+/// Histogram of node call durations in seconds.
+pub const NODE_DURATION: &str = "rustyflow_node_duration_seconds";
+/// Histogram of the number of elements per batch.
+pub const BATCH_SIZE: &str = "rustyflow_batch_size";
+/// Counter of [`Cached`](crate::cache::Cached) calls answered from the cache.
+pub const CACHE_HITS: &str = "rustyflow_cache_hits_total";
+/// Counter of [`Cached`](crate::cache::Cached) calls passed to the node.
+pub const CACHE_MISSES: &str = "rustyflow_cache_misses_total";
+
+/// Register descriptions and units for the crate's metrics.
+///
+/// Call once after installing a recorder so exporters can emit help text.
+pub fn describe() {
+    describe_counter!(EXECUTIONS_STARTED, "Flow executions started");
+    describe_counter!(EXECUTIONS_SUCCEEDED, "Flow executions that succeeded");
+    describe_counter!(EXECUTIONS_FAILED, "Flow executions that failed");
+    describe_histogram!(NODE_DURATION, Unit::Seconds, "Time spent in each node call");
+    describe_histogram!(BATCH_SIZE, Unit::Count, "Number of elements per batch");
+    describe_counter!(CACHE_HITS, "Node calls answered from the cache");
+    describe_counter!(CACHE_MISSES, "Node calls not found in the cache");
+}
+
+pub(crate) fn execution_started(flow: &str) {
+    counter!(EXECUTIONS_STARTED, "flow" => flow.to_string()).increment(1);
+}
+
+pub(crate) fn execution_finished(flow: &str, succeeded: bool) {
+    let name = if succeeded {
+        EXECUTIONS_SUCCEEDED
+    } else {
+        EXECUTIONS_FAILED
+    };
+    counter!(name, "flow" => flow.to_string()).increment(1);
+}
+
+pub(crate) fn node_called(node: &str, succeeded: bool, duration: Duration) {
+    let status = if succeeded { "ok" } else { "error" };
+    histogram!(NODE_DURATION, "node" => node.to_string(), "status" => status)
+        .record(duration.as_secs_f64());
+}
+
+pub(crate) fn batch_size(size: usize) {
+    histogram!(BATCH_SIZE).record(size as f64);
+}
+
+pub(crate) fn cache_lookup(node: &str, hit: bool) {
+    let name = if hit { CACHE_HITS } else { CACHE_MISSES };
+    counter!(name, "node" => node.to_string()).increment(1);
+}
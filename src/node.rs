@@ -1,11 +1,34 @@
 //! Core node abstraction for RustyFlow.
 //!
 //! This module defines the fundamental [`Node`] trait that all computation
-//! units in RustyFlow must implement.
+//! units in RustyFlow must implement, and the optional capabilities a node
+//! can expose through it: streaming its output as a [`Source`], describing
+//! its input and output with a [`SchemaProvider`], and checking its
+//! dependencies with a [`HealthCheck`]. Flows, the server and tooling
+//! discover these through the node itself, with no registry to keep in sync.
+//!
+//! [`NodeV2`] is the richer execution model nodes are moving to: a node runs
+//! with a [`NodeContext`] that carries the run's id, request-scoped values
+//! and a cancellation token, and returns a [`NodeOutput`] with metadata next
+//! to its value. Every [`Node`] is a `NodeV2` through a blanket
+//! implementation, and [`V2Node`] runs a `NodeV2` wherever a `Node` is
+//! expected, so both kinds mix in one flow.
+//!
+//! [`FnNode`] makes a node of an async closure or function, and
+//! [`IntoNode`] boxes one for a flow directly. [`Named`] gives any node a
+//! name and description of its own, which flows report in a [`NodeInfo`].
 
 use crate::error::FlowError;
+use crate::resources::Resource;
+use crate::stream::Source;
 use async_trait::async_trait;
-use serde_json::Value;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::future::Future;
+use std::sync::Arc;
+
+pub use tokio_util::sync::CancellationToken;
 
 /// The fundamental building block for all computations in RustyFlow.
 ///
@@ -49,4 +72,705 @@ pub trait Node: Send + Sync {
     /// * `Ok(Value)` - The processed output as a JSON value
     /// * `Err(FlowError)` - An error if processing fails
     async fn call(&self, input: Value) -> Result<Value, FlowError>;
+
+    /// Execute the node with an input shared with other nodes.
+    ///
+    /// [`ParallelFlow`](crate::ParallelFlow) hands every branch the same
+    /// input this way instead of a clone of its own. The default passes the
+    /// input to [`Node::call`], cloning it unless no other branch still holds
+    /// it. Nodes that only read their input can override this to skip the
+    /// clone, which matters for multi-megabyte payloads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::{FlowError, Node, ParallelFlow};
+    /// use serde_json::{json, Value};
+    /// use std::sync::Arc;
+    ///
+    /// /// Counts the documents of a batch without copying them.
+    /// struct CountDocuments;
+    ///
+    /// #[async_trait]
+    /// impl Node for CountDocuments {
+    ///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+    ///         self.call_shared(Arc::new(input)).await
+    ///     }
+    ///
+    ///     async fn call_shared(&self, input: Arc<Value>) -> Result<Value, FlowError> {
+    ///         Ok(json!(input["documents"].as_array().map_or(0, Vec::len)))
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let flow = ParallelFlow::new(vec![Box::new(CountDocuments), Box::new(CountDocuments)]);
+    /// let result = flow.execute(json!({"documents": ["a", "b", "c"]})).await?;
+    /// assert_eq!(result, json!([3, 3]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn call_shared(&self, input: Arc<Value>) -> Result<Value, FlowError> {
+        self.call(Arc::unwrap_or_clone(input)).await
+    }
+
+    /// A human-readable name for the node.
+    ///
+    /// Used to label outputs, for example by
+    /// [`ParallelFlow::labeled`](crate::flow::ParallelFlow::labeled). The
+    /// default is the node's type name without its module path or generic
+    /// parameters, so `rustyflow::llm::ChatNode<M>` is named `ChatNode`.
+    /// Override it to tell apart several nodes of the same type.
+    fn name(&self) -> &str {
+        short_type_name::<Self>()
+    }
+
+    /// The node's type name, without its module path or generic parameters.
+    ///
+    /// Unlike [`name`](Node::name), this is not meant to be overridden:
+    /// wrappers that only rename a node, such as [`Named`], report the type
+    /// of the node they wrap.
+    fn type_name(&self) -> &str {
+        short_type_name::<Self>()
+    }
+
+    /// What the node does, for tooling and flow listings. The default is
+    /// none.
+    fn description(&self) -> Option<&str> {
+        None
+    }
+
+    /// A JSON Schema describing the input the node accepts, if it declares
+    /// one.
+    ///
+    /// The server validates request bodies against the schema of a flow's
+    /// first node, answering invalid ones with field-level errors. The
+    /// default is the input schema of the node's
+    /// [`schema_provider`](Node::schema_provider), if any.
+    fn input_schema(&self) -> Option<Value> {
+        self.schema_provider()
+            .and_then(|schemas| schemas.input_schema())
+    }
+
+    /// The shared resources a call of the node holds, such as a GPU slot.
+    ///
+    /// A [`ResourcePools`](crate::resources::ResourcePools) scheduler waits
+    /// until they are free before the node runs. The default is none.
+    fn resources(&self) -> Vec<Resource> {
+        Vec::new()
+    }
+
+    /// Capture the node's internal state for a checkpoint.
+    ///
+    /// Stateless nodes, the default, return `None`. Nodes built with
+    /// [`Stateful`](crate::state::Stateful) return their state, so that
+    /// [`Flow::execute_resumable`](crate::flow::Flow::execute_resumable)
+    /// can restore it when a run resumes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if the state cannot be serialized.
+    async fn snapshot(&self) -> Result<Option<Value>, FlowError> {
+        Ok(None)
+    }
+
+    /// Replace the node's internal state with one taken by
+    /// [`Node::snapshot`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if `state` does not match the node's
+    /// state type.
+    async fn restore(&self, state: Value) -> Result<(), FlowError> {
+        let _ = state;
+        Ok(())
+    }
+
+    /// The node as a [`Source`], if it can produce its output as a stream
+    /// of items.
+    ///
+    /// [`StreamFlow::from_node`](crate::stream::StreamFlow::from_node)
+    /// pipes such a node's items through downstream nodes one at a time
+    /// instead of waiting for its whole output. The default is `None`.
+    fn as_streaming(&self) -> Option<&dyn Source> {
+        None
+    }
+
+    /// The node's [`SchemaProvider`], if it describes its input or output.
+    ///
+    /// The server lists a flow's output schema from its last node's
+    /// provider. The default is `None`.
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        None
+    }
+
+    /// The node's [`HealthCheck`], if it depends on something that can be
+    /// checked before a run, such as a remote API.
+    ///
+    /// [`Flow::check_health`](crate::flow::Flow::check_health) runs the
+    /// checks of every node that has one. The default is `None`.
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        None
+    }
+}
+
+/// A description of the JSON a node accepts and produces.
+///
+/// Nodes expose it through [`Node::schema_provider`].
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::node::SchemaProvider;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// struct Double;
+///
+/// impl SchemaProvider for Double {
+///     fn input_schema(&self) -> Option<Value> {
+///         Some(json!({"type": "number"}))
+///     }
+///
+///     fn output_schema(&self) -> Option<Value> {
+///         Some(json!({"type": "number"}))
+///     }
+/// }
+///
+/// #[async_trait]
+/// impl Node for Double {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(input.as_f64().unwrap_or(0.0) * 2.0))
+///     }
+///
+///     fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+///         Some(self)
+///     }
+/// }
+///
+/// let node: Box<dyn Node> = Box::new(Double);
+/// assert_eq!(node.input_schema(), Some(json!({"type": "number"})));
+/// let schemas = node.schema_provider().unwrap();
+/// assert_eq!(schemas.output_schema(), Some(json!({"type": "number"})));
+/// ```
+pub trait SchemaProvider: Send + Sync {
+    /// A JSON Schema for the node's input. The default is `None`.
+    fn input_schema(&self) -> Option<Value> {
+        None
+    }
+
+    /// A JSON Schema for the node's output. The default is `None`.
+    fn output_schema(&self) -> Option<Value> {
+        None
+    }
+}
+
+/// A check that a node's dependencies are ready to serve it.
+///
+/// Nodes expose it through [`Node::health_checkable`].
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// Check the node's dependencies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing what is unavailable.
+    async fn check(&self) -> Result<(), FlowError>;
+}
+
+/// The outcome of one node's [`HealthCheck`], as reported by
+/// [`Flow::check_health`](crate::flow::Flow::check_health).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeHealth {
+    /// Position of the node in the flow.
+    pub index: usize,
+    /// The node's [`Node::name`].
+    pub name: String,
+    /// Why the check failed, or `None` if it passed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A description of one node of a flow, as returned by
+/// [`Flow::nodes`](crate::flow::Flow::nodes).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeInfo {
+    /// Position of the node in the flow.
+    pub index: usize,
+    /// The node's [`Node::name`].
+    pub name: String,
+    /// The node's [`Node::type_name`].
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// The node's [`Node::description`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl NodeInfo {
+    pub(crate) fn new(index: usize, node: &dyn Node) -> Self {
+        Self {
+            index,
+            name: node.name().to_string(),
+            type_name: node.type_name().to_string(),
+            description: node.description().map(str::to_string),
+        }
+    }
+}
+
+/// A node under a name, and optionally a description, of its own.
+///
+/// The name is used wherever the node's [`Node::name`] is: in tracing
+/// spans, errors, diagrams and [`Flow::node_index`](crate::flow::Flow::node_index).
+/// Everything else is the wrapped node's. [`Flow::add_named`](crate::flow::Flow::add_named)
+/// wraps nodes in this.
+pub struct Named<N> {
+    node: N,
+    name: String,
+    description: Option<String>,
+}
+
+impl<N: Node> Named<N> {
+    /// Call `node` by `name`.
+    pub fn new(name: impl Into<String>, node: N) -> Self {
+        Self {
+            node,
+            name: name.into(),
+            description: None,
+        }
+    }
+
+    /// Describe what the node does.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+#[async_trait]
+impl<N: Node> Node for Named<N> {
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        self.node.call(input).await
+    }
+
+    async fn call_shared(&self, input: Arc<Value>) -> Result<Value, FlowError> {
+        self.node.call_shared(input).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn type_name(&self) -> &str {
+        self.node.type_name()
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref().or(self.node.description())
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.node.input_schema()
+    }
+
+    fn resources(&self) -> Vec<Resource> {
+        self.node.resources()
+    }
+
+    async fn snapshot(&self) -> Result<Option<Value>, FlowError> {
+        self.node.snapshot().await
+    }
+
+    async fn restore(&self, state: Value) -> Result<(), FlowError> {
+        self.node.restore(state).await
+    }
+
+    fn as_streaming(&self) -> Option<&dyn Source> {
+        self.node.as_streaming()
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        self.node.schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        self.node.health_checkable()
+    }
+}
+
+/// A type's name without its module path or generic parameters.
+pub(crate) fn short_type_name<T: ?Sized>() -> &'static str {
+    let full = std::any::type_name::<T>();
+    let base = full.split('<').next().unwrap_or(full);
+    base.rsplit("::").next().unwrap_or(base)
+}
+
+#[async_trait]
+impl<N: Node + ?Sized> Node for Box<N> {
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        (**self).call(input).await
+    }
+
+    async fn call_shared(&self, input: Arc<Value>) -> Result<Value, FlowError> {
+        (**self).call_shared(input).await
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn type_name(&self) -> &str {
+        (**self).type_name()
+    }
+
+    fn description(&self) -> Option<&str> {
+        (**self).description()
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        (**self).input_schema()
+    }
+
+    fn resources(&self) -> Vec<Resource> {
+        (**self).resources()
+    }
+
+    async fn snapshot(&self) -> Result<Option<Value>, FlowError> {
+        (**self).snapshot().await
+    }
+
+    async fn restore(&self, state: Value) -> Result<(), FlowError> {
+        (**self).restore(state).await
+    }
+
+    fn as_streaming(&self) -> Option<&dyn Source> {
+        (**self).as_streaming()
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        (**self).schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        (**self).health_checkable()
+    }
+}
+
+/// What a [`NodeV2`] knows about the call it is running in.
+#[derive(Debug, Clone, Default)]
+pub struct NodeContext {
+    run_id: Option<String>,
+    values: Map<String, Value>,
+    cancellation: CancellationToken,
+}
+
+impl NodeContext {
+    /// Create a context with no run id, no values and a token that is
+    /// never cancelled unless a clone of it is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the id of the run the call belongs to.
+    pub fn with_run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
+    /// Add a request-scoped value, such as the tenant or session id.
+    pub fn with_value(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.values.insert(key.into(), value);
+        self
+    }
+
+    /// Stop the call when `token`, or the token it was derived from, is
+    /// cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// The id of the run the call belongs to, if known.
+    pub fn run_id(&self) -> Option<&str> {
+        self.run_id.as_deref()
+    }
+
+    /// The request-scoped value stored under `key`.
+    pub fn value(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    /// The token that is cancelled when the call should stop.
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Returns `true` once the call should stop.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+/// The result of a [`NodeV2`] call: the output value and metadata about
+/// how it was produced.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeOutput {
+    /// The value passed on to the next node.
+    pub value: Value,
+    /// Facts about the call, such as the model that answered, kept out of
+    /// the value so downstream nodes do not have to strip them.
+    pub metadata: Map<String, Value>,
+}
+
+impl NodeOutput {
+    /// Create an output with no metadata.
+    pub fn new(value: Value) -> Self {
+        Self {
+            value,
+            metadata: Map::new(),
+        }
+    }
+
+    /// Add a piece of metadata.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.metadata.insert(key.into(), value);
+        self
+    }
+}
+
+/// A node that runs with a [`NodeContext`] and reports metadata with its
+/// output.
+///
+/// Every [`Node`] implements `NodeV2`: its call races the context's
+/// cancellation token, and its output has no metadata. Wrap a `NodeV2` in a
+/// [`V2Node`] to use it in a [`Flow`](crate::flow::Flow) or any other place
+/// that takes a [`Node`].
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::node::{CancellationToken, NodeContext, NodeOutput, NodeV2, V2Node};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// /// Answers from a cache it reports hits of.
+/// struct Lookup;
+///
+/// #[async_trait]
+/// impl NodeV2 for Lookup {
+///     async fn run(&self, input: Value, context: &NodeContext) -> Result<NodeOutput, FlowError> {
+///         if context.is_cancelled() {
+///             return Err(FlowError::Cancelled("lookup".to_string()));
+///         }
+///         Ok(NodeOutput::new(json!({"answer": input["question"]}))
+///             .with_metadata("cache_hit", json!(true)))
+///     }
+/// }
+///
+/// struct Shout;
+///
+/// #[async_trait]
+/// impl Node for Shout {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(input["answer"].as_str().unwrap_or_default().to_uppercase()))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// // Both kinds of node mix in one flow, and metadata reaches the report
+/// let flow = Flow::new(vec![Box::new(V2Node::new(Lookup)), Box::new(Shout)]);
+/// let (result, report) = flow.execute_traced(json!({"question": "hi"})).await;
+/// assert_eq!(result?, json!("HI"));
+/// assert_eq!(report.nodes[0].metadata["cache_hit"], json!(true));
+///
+/// // Existing nodes run as NodeV2, and stop when cancelled
+/// let token = CancellationToken::new();
+/// let context = NodeContext::new().with_cancellation(token.clone());
+/// let output = Shout.run(json!({"answer": "ok"}), &context).await?;
+/// assert_eq!(output.value, json!("OK"));
+///
+/// token.cancel();
+/// let error = Shout.run(json!({"answer": "ok"}), &context).await.unwrap_err();
+/// assert!(matches!(error, FlowError::Cancelled(_)));
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait NodeV2: Send + Sync {
+    /// Execute the node with the given input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON input value to process
+    /// * `context` - The run's id, request-scoped values and cancellation
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(NodeOutput)` - The output value and metadata about the call
+    /// * `Err(FlowError)` - An error if processing fails, or
+    ///   `FlowError::Cancelled` if the call was stopped
+    async fn run(&self, input: Value, context: &NodeContext) -> Result<NodeOutput, FlowError>;
+}
+
+#[async_trait]
+impl<N: Node + ?Sized> NodeV2 for N {
+    /// Call the node, giving up once the context is cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns the node's error, or `FlowError::Cancelled` if the context
+    /// was cancelled first.
+    async fn run(&self, input: Value, context: &NodeContext) -> Result<NodeOutput, FlowError> {
+        tokio::select! {
+            biased;
+            _ = context.cancellation().cancelled() => Err(FlowError::Cancelled(format!(
+                "Node '{}' was cancelled",
+                self.name()
+            ))),
+            result = self.call(input) => result.map(NodeOutput::new),
+        }
+    }
+}
+
+/// A [`Node`] that runs a [`NodeV2`].
+///
+/// Each call gets a fresh [`NodeContext`] whose token is cancelled if the
+/// call is dropped, as when a [`Race`](crate::race::Race) or
+/// [`Deadline`](crate::deadline::Deadline) abandons it, so work the node
+/// spawned can stop too. The output's metadata is recorded with
+/// [`report::record_metadata`](crate::report::record_metadata), so it shows
+/// up in the [`NodeReport`](crate::report::NodeReport) of a traced run.
+pub struct V2Node<N> {
+    node: N,
+}
+
+impl<N: NodeV2> V2Node<N> {
+    /// Wrap `node` so it can be used as a [`Node`].
+    pub fn new(node: N) -> Self {
+        Self { node }
+    }
+}
+
+#[async_trait]
+impl<N: NodeV2> Node for V2Node<N> {
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let token = CancellationToken::new();
+        let _cancel_on_drop = token.clone().drop_guard();
+        let context = NodeContext::new().with_cancellation(token);
+        let output = self.node.run(input, &context).await?;
+        for (key, value) in output.metadata {
+            crate::report::record_metadata(&key, value);
+        }
+        Ok(output.value)
+    }
+
+    fn name(&self) -> &str {
+        short_type_name::<N>()
+    }
+}
+
+/// The boxed async function a [`FnNode`] calls.
+pub type NodeFn = Arc<dyn Fn(Value) -> BoxFuture<'static, Result<Value, FlowError>> + Send + Sync>;
+
+/// A [`Node`] that calls an async closure or function.
+///
+/// Small steps and adapters don't need a struct and an impl block: any
+/// `Fn(Value) -> impl Future<Output = Result<Value, FlowError>>` is a node
+/// through `FnNode::new`, or boxed for [`Flow::new`](crate::flow::Flow::new)
+/// with [`IntoNode::into_node`]. The node is named after the function, or
+/// `{{closure}}` for a closure, unless [`with_name`](FnNode::with_name)
+/// names it.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::node::{FnNode, IntoNode};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// async fn double(input: Value) -> Result<Value, FlowError> {
+///     Ok(json!(input.as_i64().unwrap_or(0) * 2))
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let offset = 1;
+/// let flow = Flow::new(vec![
+///     double.into_node(),
+///     (move |input: Value| async move { Ok(json!(input.as_i64().unwrap_or(0) + offset)) })
+///         .into_node(),
+///     Box::new(FnNode::new(|input: Value| async move { Ok(json!({"total": input})) })
+///         .with_name("wrap")),
+/// ]);
+/// assert_eq!(flow.execute(json!(20)).await?, json!({"total": 41}));
+/// assert_eq!(FnNode::new(double).name(), "double");
+/// # Ok(())
+/// # }
+/// ```
+pub struct FnNode {
+    function: NodeFn,
+    name: String,
+}
+
+impl FnNode {
+    /// Create a node that calls `function` with each input.
+    pub fn new<F, Fut>(function: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, FlowError>> + Send + 'static,
+    {
+        Self {
+            function: Arc::new(move |input| Box::pin(function(input))),
+            name: short_type_name::<F>().to_string(),
+        }
+    }
+
+    /// Create a node from an already boxed function, such as one shared
+    /// between several nodes.
+    pub fn from_fn(function: NodeFn) -> Self {
+        Self {
+            function,
+            name: "FnNode".to_string(),
+        }
+    }
+
+    /// Set the node's [`Node::name`].
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+#[async_trait]
+impl Node for FnNode {
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        (self.function)(input).await
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Conversion of async closures and functions into boxed nodes.
+///
+/// Implemented for everything [`FnNode::new`] accepts. Node types are
+/// boxed as they are, with `Box::new`.
+pub trait IntoNode {
+    /// Box `self` as a node, ready for [`Flow::new`](crate::flow::Flow::new).
+    fn into_node(self) -> Box<dyn Node>;
+}
+
+impl<F, Fut> IntoNode for F
+where
+    F: Fn(Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Value, FlowError>> + Send + 'static,
+{
+    fn into_node(self) -> Box<dyn Node> {
+        Box::new(FnNode::new(self))
+    }
 }
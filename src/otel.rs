@@ -0,0 +1,97 @@
+//! OpenTelemetry export of flow and node spans.
+//!
+//! This module is available with the `otel` feature. It builds a
+//! `tracing-opentelemetry` layer that sends the `flow.execute` and
+//! `node.call` spans to an OTLP collector such as Jaeger or Grafana Tempo.
+//! Span fields become OpenTelemetry attributes (`run_id`, `flow.name`,
+//! `flow.kind`, `node.name`, `node.index`, `duration_ms`), and failed flows
+//! and nodes get an `ERROR` span status with the error message.
+
+use crate::error::FlowError;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::{Tracer, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Flushes and shuts down the exporter when dropped.
+///
+/// Keep the guard alive for as long as spans should be exported, typically
+/// until the end of `main`.
+pub struct OtelGuard {
+    provider: TracerProvider,
+}
+
+impl Drop for OtelGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("Failed to shut down OpenTelemetry exporter: {e}");
+        }
+    }
+}
+
+/// Build a tracing layer that exports spans over OTLP/gRPC.
+///
+/// Must be called from within a Tokio runtime, which runs the batch
+/// exporter. The provider is also installed as the global OpenTelemetry
+/// tracer provider.
+///
+/// # Arguments
+///
+/// * `service_name` - Reported as the `service.name` resource attribute
+/// * `endpoint` - The collector's OTLP/gRPC endpoint; if `None`, the
+///   `OTEL_EXPORTER_OTLP_ENDPOINT` environment variable or
+///   `http://localhost:4317` is used
+///
+/// # Errors
+///
+/// Returns `FlowError::NodeFailed` if the exporter cannot be created.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), rustyflow::FlowError> {
+/// let (otel, _guard) = rustyflow::otel::layer("my-service", None)?;
+/// tracing_subscriber::registry()
+///     .with(tracing_subscriber::fmt::layer())
+///     .with(otel)
+///     .init();
+/// # Ok(())
+/// # }
+/// ```
+pub fn layer<S>(
+    service_name: impl Into<String>,
+    endpoint: Option<&str>,
+) -> Result<(OpenTelemetryLayer<S, Tracer>, OtelGuard), FlowError>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let mut exporter = SpanExporter::builder().with_tonic();
+    if let Some(endpoint) = endpoint {
+        exporter = exporter.with_endpoint(endpoint);
+    }
+    let exporter = exporter
+        .build()
+        .map_err(|e| FlowError::NodeFailed(format!("Cannot create OTLP exporter: {e}")))?;
+
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            service_name.into(),
+        )]))
+        .build();
+    let tracer = provider.tracer("rustyflow");
+    opentelemetry::global::set_tracer_provider(provider.clone());
+
+    Ok((
+        tracing_opentelemetry::layer().with_tracer(tracer),
+        OtelGuard { provider },
+    ))
+}
@@ -0,0 +1,229 @@
+//! Execution state overlaid on flow diagrams.
+//!
+//! An [`ExecutionOverlay`] follows a run of a [`Flow`](crate::Flow) or
+//! [`GraphFlow`](crate::graph::GraphFlow): which steps have run, which one
+//! is running, how long each took, which failed, and which edges the run
+//! took. Feed it the [`ExecutionEvent`]s of an in-flight run as they arrive,
+//! or build it from the [`ExecutionReport`] of a recorded one.
+//!
+//! The flow then renders itself with the overlay, either as a Mermaid
+//! flowchart with `running`, `succeeded` and `failed` classes and the taken
+//! edges highlighted (`to_live_mermaid`), or as JSON for a UI to draw
+//! (`live_view`):
+//!
+//! ```json
+//! {
+//!   "title": "support",
+//!   "status": "running",
+//!   "current": 1,
+//!   "nodes": [{"id": "n0", "index": 0, "name": "Classify", "status": "succeeded",
+//!              "duration_ms": 12, "calls": 1}],
+//!   "edges": [{"from": "input", "to": "n0", "taken": true}]
+//! }
+//! ```
+
+use crate::diagram::Endpoint;
+use crate::events::ExecutionEvent;
+use crate::report::ExecutionReport;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The state of one step of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    /// The step has not run.
+    Pending,
+    /// The step is running.
+    Running,
+    /// The step's last call succeeded.
+    Succeeded,
+    /// The step's last call failed.
+    Failed,
+}
+
+impl StepStatus {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::Running => "running",
+            StepStatus::Succeeded => "succeeded",
+            StepStatus::Failed => "failed",
+        }
+    }
+}
+
+/// What is known about one step of a run.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepState {
+    /// The step's status.
+    pub status: StepStatus,
+    /// Time spent in the step over all its calls, in milliseconds.
+    pub duration_ms: u64,
+    /// How often the step was called; steps in graph loops run repeatedly.
+    pub calls: u32,
+    /// The error of the step's last call, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The execution state of a run, to render on the flow's diagram.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::overlay::{ExecutionOverlay, StepStatus};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// struct Step;
+///
+/// #[async_trait]
+/// impl Node for Step {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(input)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let flow = Flow::new(vec![Box::new(Step), Box::new(Step)]);
+/// let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+/// flow.execute_with_events(json!(1), &sender).await?;
+/// drop(sender);
+///
+/// // A live view would apply each event as it arrives and render again
+/// let mut overlay = ExecutionOverlay::new();
+/// while let Some(event) = receiver.recv().await {
+///     overlay.apply(&event);
+/// }
+///
+/// assert_eq!(overlay.step(1).unwrap().status, StepStatus::Succeeded);
+/// let mermaid = flow.to_live_mermaid(&overlay);
+/// assert!(mermaid.contains("class n0,n1 succeeded"));
+/// assert_eq!(flow.live_view(&overlay)["status"], "succeeded");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionOverlay {
+    steps: BTreeMap<usize, StepState>,
+    transitions: BTreeSet<(Endpoint, Endpoint)>,
+    last: Option<usize>,
+    outcome: Option<bool>,
+}
+
+impl ExecutionOverlay {
+    /// Create an overlay for a run that has not started.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the overlay of a recorded run from its report.
+    ///
+    /// A run is taken to have succeeded if no step in the report failed.
+    pub fn from_report(report: &ExecutionReport) -> Self {
+        let mut overlay = Self::new();
+        for node in &report.nodes {
+            overlay.apply(&ExecutionEvent::NodeStart {
+                index: node.index,
+                name: node.name.clone(),
+            });
+            overlay.apply(&ExecutionEvent::NodeEnd {
+                index: node.index,
+                name: node.name.clone(),
+                duration_ms: node.duration.as_millis() as u64,
+                error: node.error.clone(),
+            });
+        }
+        match report.failed() {
+            Some(node) => overlay.apply(&ExecutionEvent::Error {
+                error: node.error.clone().unwrap_or_default(),
+            }),
+            None if !report.nodes.is_empty() => overlay.apply(&ExecutionEvent::FinalResult {
+                output: serde_json::Value::Null,
+            }),
+            None => {}
+        }
+        overlay
+    }
+
+    /// Update the overlay with an event of the run.
+    pub fn apply(&mut self, event: &ExecutionEvent) {
+        match event {
+            ExecutionEvent::NodeStart { index, .. } => {
+                let from = self.last.map_or(Endpoint::Input, Endpoint::Node);
+                self.transitions.insert((from, Endpoint::Node(*index)));
+                self.last = Some(*index);
+                let step = self.steps.entry(*index).or_insert(StepState {
+                    status: StepStatus::Pending,
+                    duration_ms: 0,
+                    calls: 0,
+                    error: None,
+                });
+                step.status = StepStatus::Running;
+                step.calls += 1;
+            }
+            ExecutionEvent::NodeEnd {
+                index,
+                duration_ms,
+                error,
+                ..
+            } => {
+                if let Some(step) = self.steps.get_mut(index) {
+                    step.duration_ms += duration_ms;
+                    step.status = match error {
+                        Some(_) => StepStatus::Failed,
+                        None => StepStatus::Succeeded,
+                    };
+                    step.error = error.clone();
+                }
+            }
+            ExecutionEvent::FinalResult { .. } => {
+                if let Some(last) = self.last {
+                    self.transitions
+                        .insert((Endpoint::Node(last), Endpoint::Output));
+                }
+                self.outcome = Some(true);
+            }
+            ExecutionEvent::Error { .. } => self.outcome = Some(false),
+            ExecutionEvent::TokenDelta { .. }
+            | ExecutionEvent::Warning { .. }
+            | ExecutionEvent::Progress { .. } => {}
+        }
+    }
+
+    /// The state of the step at `index`, if it has started.
+    pub fn step(&self, index: usize) -> Option<&StepState> {
+        self.steps.get(&index)
+    }
+
+    /// The step that is running, if any.
+    pub fn current(&self) -> Option<usize> {
+        self.last
+            .filter(|last| self.steps[last].status == StepStatus::Running)
+    }
+
+    /// The status of the whole run: pending before its first event,
+    /// running until it finishes.
+    pub fn status(&self) -> StepStatus {
+        match self.outcome {
+            Some(true) => StepStatus::Succeeded,
+            Some(false) => StepStatus::Failed,
+            None if self.steps.is_empty() => StepStatus::Pending,
+            None => StepStatus::Running,
+        }
+    }
+
+    pub(crate) fn step_status(&self, index: usize) -> StepStatus {
+        self.steps
+            .get(&index)
+            .map_or(StepStatus::Pending, |step| step.status)
+    }
+
+    /// Whether the run went from `from` to `to`.
+    pub(crate) fn took(&self, from: Endpoint, to: Endpoint) -> bool {
+        self.transitions.contains(&(from, to))
+    }
+}
@@ -0,0 +1,345 @@
+//! Versioned flow bundles.
+//!
+//! A flowpack ships a pipeline as one artifact: a gzip-compressed tar
+//! archive, conventionally named `*.flowpack`, laid out as
+//!
+//! ```text
+//! flowpack.json        manifest: name, version, description
+//! flow.json            the FlowConfig
+//! prompts/*            prompt templates
+//! schemas/*.json       JSON Schemas; schemas/input.json describes the input
+//! assets/*             any other files the nodes need
+//! ```
+//!
+//! Node params in `flow.json` can refer to files of the pack with
+//! `{"$file": "prompts/summarize.j2"}`. When the flow is built, the
+//! reference is replaced by the file's contents: parsed JSON for `.json`
+//! files and a string otherwise. The same layout is accepted unpacked, as a
+//! directory, which is convenient while developing a pack.
+
+use crate::config::FlowConfig;
+use crate::error::FlowError;
+use crate::flow::Flow;
+use crate::registry::NodeRegistry;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// The name of the manifest file in a pack.
+pub const MANIFEST: &str = "flowpack.json";
+
+/// The name of the flow definition file in a pack.
+pub const FLOW: &str = "flow.json";
+
+/// The most bytes the files of a pack archive may unpack to, 64 MiB.
+pub const MAX_UNPACKED_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The `flowpack.json` manifest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackManifest {
+    /// The name the flow is hosted under.
+    pub name: String,
+    /// The pack's version, such as `1.2.0`.
+    pub version: String,
+    /// What the flow does.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A flow definition bundled with the files it uses.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::pack::{FlowPack, PackManifest};
+/// use rustyflow::registry::NodeRegistry;
+/// use rustyflow::FlowError;
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let manifest = PackManifest {
+///     name: "greeting".to_string(),
+///     version: "1.0.0".to_string(),
+///     description: None,
+/// };
+/// let flow = r#"{"nodes": [
+///     {"type": "prompt_template", "params": {"template": {"$file": "prompts/greet.j2"}}}
+/// ]}"#;
+/// let pack = FlowPack::new(manifest, serde_json::from_str(flow)?)
+///     .with_file("prompts/greet.j2", "Hello, {{ name }}!")
+///     .with_file("schemas/input.json", r#"{"type": "object", "required": ["name"]}"#);
+///
+/// // Round-trip through the archive format
+/// let mut archive = Vec::new();
+/// pack.write_to(&mut archive)?;
+/// let pack = FlowPack::read_from(archive.as_slice())?;
+///
+/// assert_eq!(pack.input_schema()?.unwrap()["required"], json!(["name"]));
+/// let flow = pack.build(&NodeRegistry::with_builtins())?;
+/// let result = flow.execute(json!({"name": "Ada"})).await?;
+/// assert_eq!(result[0]["content"], "Hello, Ada!");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowPack {
+    /// The pack's manifest.
+    pub manifest: PackManifest,
+    /// The flow definition, with `$file` references unresolved.
+    pub config: FlowConfig,
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl FlowPack {
+    /// Create a pack holding only a manifest and a flow definition.
+    pub fn new(manifest: PackManifest, config: FlowConfig) -> Self {
+        Self {
+            manifest,
+            config,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Add a prompt, schema or asset under a relative path such as
+    /// `prompts/summarize.j2`.
+    pub fn with_file(mut self, path: impl Into<String>, contents: impl Into<Vec<u8>>) -> Self {
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+
+    /// The contents of a bundled file.
+    pub fn file(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+
+    /// The paths of the bundled files, sorted.
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+
+    /// Read a pack from a `.flowpack` archive or an unpacked directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the path cannot be read or the
+    /// pack lacks a manifest or flow, or `FlowError::SerdeError` if either
+    /// is invalid.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FlowError> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            return Self::from_dir(path);
+        }
+        let file = std::fs::File::open(path).map_err(|e| {
+            FlowError::NodeFailed(format!("Cannot open flowpack {}: {e}", path.display()))
+        })?;
+        Self::read_from(file)
+    }
+
+    /// Read a pack from an unpacked directory.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`FlowPack::open`].
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, FlowError> {
+        let dir = dir.as_ref();
+        let mut files = BTreeMap::new();
+        collect_dir(dir, dir, &mut files)?;
+        Self::from_files(files)
+    }
+
+    /// Read a pack from a gzip-compressed tar archive.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`FlowPack::open`], and `FlowError::NodeFailed` if the
+    /// files unpack to more than [`MAX_UNPACKED_SIZE`] bytes, so that a
+    /// small archive from an untrusted store cannot exhaust memory.
+    pub fn read_from(reader: impl Read) -> Result<Self, FlowError> {
+        let io_error = |e: std::io::Error| FlowError::NodeFailed(format!("Invalid flowpack: {e}"));
+        let mut archive = tar::Archive::new(GzDecoder::new(reader));
+        let mut files = BTreeMap::new();
+        let mut remaining = MAX_UNPACKED_SIZE;
+        for entry in archive.entries().map_err(io_error)? {
+            let mut entry = entry.map_err(io_error)?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path().map_err(io_error)?;
+            let path = normalize(&path.to_string_lossy());
+            let mut contents = Vec::new();
+            // One byte over the budget is enough to tell it is exceeded
+            let read = (&mut entry)
+                .take(remaining + 1)
+                .read_to_end(&mut contents)
+                .map_err(io_error)? as u64;
+            if read > remaining {
+                return Err(FlowError::NodeFailed(format!(
+                    "Invalid flowpack: files unpack to more than {MAX_UNPACKED_SIZE} bytes"
+                )));
+            }
+            remaining -= read;
+            files.insert(path, contents);
+        }
+        Self::from_files(files)
+    }
+
+    fn from_files(mut files: BTreeMap<String, Vec<u8>>) -> Result<Self, FlowError> {
+        let mut take = |name: &str| {
+            files
+                .remove(name)
+                .ok_or_else(|| FlowError::NodeFailed(format!("Flowpack has no {name}")))
+        };
+        let manifest = serde_json::from_slice(&take(MANIFEST)?)?;
+        let config = serde_json::from_slice(&take(FLOW)?)?;
+        Ok(Self {
+            manifest,
+            config,
+            files,
+        })
+    }
+
+    /// Write the pack as a gzip-compressed tar archive.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if writing fails.
+    pub fn write_to(&self, writer: impl Write) -> Result<(), FlowError> {
+        let io_error =
+            |e: std::io::Error| FlowError::NodeFailed(format!("Cannot write flowpack: {e}"));
+        let mut builder = tar::Builder::new(GzEncoder::new(writer, Compression::default()));
+        let manifest = serde_json::to_vec_pretty(&self.manifest)?;
+        let config = serde_json::to_vec_pretty(&self.config)?;
+        let entries = [(MANIFEST, &manifest), (FLOW, &config)].into_iter().chain(
+            self.files
+                .iter()
+                .map(|(path, contents)| (path.as_str(), contents)),
+        );
+        for (path, contents) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, path, contents.as_slice())
+                .map_err(io_error)?;
+        }
+        builder
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Write the pack to a `.flowpack` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), FlowError> {
+        let path = path.as_ref();
+        let file = std::fs::File::create(path).map_err(|e| {
+            FlowError::NodeFailed(format!("Cannot create flowpack {}: {e}", path.display()))
+        })?;
+        self.write_to(file)
+    }
+
+    /// The JSON Schema of the flow's input, from `schemas/input.json`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if the schema is not valid JSON.
+    pub fn input_schema(&self) -> Result<Option<Value>, FlowError> {
+        self.file("schemas/input.json")
+            .map(serde_json::from_slice)
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// The flow definition with every `$file` reference replaced by the
+    /// referenced file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if a referenced file is missing or
+    /// not UTF-8, or `FlowError::SerdeError` if a `.json` file is invalid.
+    pub fn resolved_config(&self) -> Result<FlowConfig, FlowError> {
+        let mut config = serde_json::to_value(&self.config)?;
+        self.resolve(&mut config)?;
+        Ok(serde_json::from_value(config)?)
+    }
+
+    fn resolve(&self, value: &mut Value) -> Result<(), FlowError> {
+        match value {
+            Value::Object(map) if map.len() == 1 && map.contains_key("$file") => {
+                let path = map["$file"].as_str().unwrap_or_default().to_string();
+                let contents = self.file(&normalize(&path)).ok_or_else(|| {
+                    FlowError::NodeFailed(format!("Flowpack has no file '{path}'"))
+                })?;
+                *value = if path.ends_with(".json") {
+                    serde_json::from_slice(contents)?
+                } else {
+                    Value::String(String::from_utf8(contents.to_vec()).map_err(|_| {
+                        FlowError::NodeFailed(format!("Flowpack file '{path}' is not UTF-8"))
+                    })?)
+                };
+            }
+            Value::Object(map) => {
+                for child in map.values_mut() {
+                    self.resolve(child)?;
+                }
+            }
+            Value::Array(items) => {
+                for child in items {
+                    self.resolve(child)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Build the flow, named after the manifest unless the definition names
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from [`FlowPack::resolved_config`] or
+    /// [`FlowConfig::build`].
+    pub fn build(&self, registry: &NodeRegistry) -> Result<Flow, FlowError> {
+        let mut config = self.resolved_config()?;
+        config
+            .name
+            .get_or_insert_with(|| self.manifest.name.clone());
+        config.build(registry)
+    }
+}
+
+/// Strip a leading `./` so archive and directory paths compare equal.
+fn normalize(path: &str) -> String {
+    path.trim_start_matches("./").replace('\\', "/")
+}
+
+fn collect_dir(
+    root: &Path,
+    dir: &Path,
+    files: &mut BTreeMap<String, Vec<u8>>,
+) -> Result<(), FlowError> {
+    let io_error =
+        |e: std::io::Error| FlowError::NodeFailed(format!("Cannot read {}: {e}", dir.display()));
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.is_dir() {
+            collect_dir(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let contents = std::fs::read(&path).map_err(io_error)?;
+            files.insert(normalize(&relative.to_string_lossy()), contents);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,450 @@
+//! Sharing flowpacks through a package store.
+//!
+//! A [`PackStore`] keeps [`FlowPack`] archives under a name and a version
+//! tag, together with the SHA-256 digest of each archive. [`push`] uploads a
+//! pack under its manifest name and version; [`pull`] downloads one,
+//! checks it against the stored digest and, if given, a digest pinned by
+//! the caller, so a tampered or truncated package is never loaded.
+//!
+//! Two stores are provided:
+//!
+//! - [`DirPackStore`] keeps packages in a directory, which may be a shared
+//!   network drive;
+//! - `HttpPackStore`, with the `reqwest` feature, talks to an HTTP package
+//!   server.
+//!
+//! Packages are addressed with a [`PackRef`] such as `summarize`,
+//! `summarize:1.2.0` or `summarize:1.2.0@sha256:<hex>`. Without a version,
+//! the highest version in the store is used.
+
+use crate::error::FlowError;
+use crate::pack::FlowPack;
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// A package name with an optional version and pinned digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackRef {
+    /// The package name.
+    pub name: String,
+    /// The version tag, or `None` for the latest.
+    pub version: Option<String>,
+    /// The expected digest, as `sha256:<hex>`.
+    pub digest: Option<String>,
+}
+
+impl FromStr for PackRef {
+    type Err = FlowError;
+
+    /// Parse `name[:version][@sha256:<hex>]`.
+    fn from_str(reference: &str) -> Result<Self, FlowError> {
+        let (rest, digest) = match reference.split_once('@') {
+            Some((rest, digest)) => (rest, Some(digest.to_string())),
+            None => (reference, None),
+        };
+        let (name, version) = match rest.split_once(':') {
+            Some((name, version)) => (name, Some(version.to_string())),
+            None => (rest, None),
+        };
+        check_segment("package name", name)?;
+        if let Some(version) = &version {
+            check_segment("version", version)?;
+        }
+        if let Some(digest) = &digest {
+            let valid = digest
+                .strip_prefix("sha256:")
+                .is_some_and(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()));
+            if !valid {
+                return Err(FlowError::NodeFailed(format!(
+                    "Invalid digest '{digest}', expected sha256:<64 hex digits>"
+                )));
+            }
+        }
+        Ok(Self {
+            name: name.to_string(),
+            version,
+            digest,
+        })
+    }
+}
+
+impl fmt::Display for PackRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, ":{version}")?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{digest}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Names and versions become paths and URL segments, so they are limited
+/// to ASCII letters, digits, `.`, `_` and `-`, and may not start with `.`.
+fn check_segment(kind: &str, segment: &str) -> Result<(), FlowError> {
+    let valid = !segment.is_empty()
+        && !segment.starts_with('.')
+        && segment
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(FlowError::NodeFailed(format!("Invalid {kind} '{segment}'")))
+    }
+}
+
+/// The digest of a package archive, as `sha256:<hex>`.
+pub fn digest(archive: &[u8]) -> String {
+    let hash = Sha256::digest(archive);
+    let hex: String = hash.iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256:{hex}")
+}
+
+/// Order versions by their dot-separated parts, numerically where both
+/// parts are numbers, so that `1.10.0` sorts after `1.9.0`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut left = a.split(['.', '-']);
+    let mut right = b.split(['.', '-']);
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) => {
+                let order = match (l.parse::<u64>(), r.parse::<u64>()) {
+                    (Ok(l), Ok(r)) => l.cmp(&r),
+                    _ => l.cmp(r),
+                };
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+        }
+    }
+}
+
+/// Storage for versioned package archives.
+#[async_trait]
+pub trait PackStore: Send + Sync {
+    /// Store an archive under `name` and `version`, with its digest.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the version already exists or the
+    /// store cannot be written.
+    async fn put(&self, name: &str, version: &str, archive: Vec<u8>) -> Result<(), FlowError>;
+
+    /// Fetch an archive and the digest recorded when it was stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NotFound` if the version does not exist, or
+    /// `FlowError::NodeFailed` if the store cannot be read.
+    async fn get(&self, name: &str, version: &str) -> Result<(Vec<u8>, String), FlowError>;
+
+    /// The stored versions of a package, in any order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the store cannot be read.
+    async fn versions(&self, name: &str) -> Result<Vec<String>, FlowError>;
+}
+
+/// Upload a pack under its manifest name and version.
+///
+/// # Returns
+///
+/// The reference of the stored package, including its digest.
+///
+/// # Errors
+///
+/// Returns `FlowError::NodeFailed` if the manifest name or version is not a
+/// valid reference, or any error of the store.
+pub async fn push(store: &dyn PackStore, pack: &FlowPack) -> Result<PackRef, FlowError> {
+    let name = &pack.manifest.name;
+    let version = &pack.manifest.version;
+    check_segment("package name", name)?;
+    check_segment("version", version)?;
+
+    let mut archive = Vec::new();
+    pack.write_to(&mut archive)?;
+    let digest = digest(&archive);
+    store.put(name, version, archive).await?;
+    Ok(PackRef {
+        name: name.clone(),
+        version: Some(version.clone()),
+        digest: Some(digest),
+    })
+}
+
+/// Download a pack and verify its integrity.
+///
+/// # Errors
+///
+/// Returns `FlowError::NotFound` if the package or version does not exist,
+/// `FlowError::NodeFailed` if the archive does not match the stored or
+/// pinned digest or the store fails, or any error of
+/// [`FlowPack::read_from`].
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::pack::{FlowPack, PackManifest};
+/// use rustyflow::pack_store::{self, DirPackStore};
+/// use rustyflow::FlowError;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// # let dir = std::env::temp_dir().join(format!("rustyflow-packs-{}", std::process::id()));
+/// let store = DirPackStore::new(&dir);
+/// for version in ["1.9.0", "1.10.0"] {
+///     let manifest = PackManifest {
+///         name: "greeting".to_string(),
+///         version: version.to_string(),
+///         description: None,
+///     };
+///     let config = serde_json::from_str(r#"{"nodes": []}"#)?;
+///     pack_store::push(&store, &FlowPack::new(manifest, config)).await?;
+/// }
+///
+/// let latest = pack_store::pull(&store, &"greeting".parse()?).await?;
+/// assert_eq!(latest.manifest.version, "1.10.0");
+///
+/// let pinned = "greeting:1.9.0@sha256:0000000000000000000000000000000000000000000000000000000000000000";
+/// assert!(pack_store::pull(&store, &pinned.parse()?).await.is_err());
+/// # std::fs::remove_dir_all(&dir).ok();
+/// # Ok(())
+/// # }
+/// ```
+pub async fn pull(store: &dyn PackStore, reference: &PackRef) -> Result<FlowPack, FlowError> {
+    let version = match &reference.version {
+        Some(version) => version.clone(),
+        None => store
+            .versions(&reference.name)
+            .await?
+            .into_iter()
+            .max_by(|a, b| compare_versions(a, b))
+            .ok_or_else(|| {
+                FlowError::NotFound(format!("Package '{}' has no versions", reference.name))
+            })?,
+    };
+
+    let (archive, stored) = store.get(&reference.name, &version).await?;
+    let actual = digest(&archive);
+    let expected = reference.digest.as_ref().unwrap_or(&stored);
+    if actual != stored || actual != *expected {
+        return Err(FlowError::NodeFailed(format!(
+            "Package {}:{version} failed its integrity check: expected {expected}, got {actual}",
+            reference.name
+        )));
+    }
+    FlowPack::read_from(archive.as_slice())
+}
+
+/// A [`PackStore`] in a directory, laid out as
+/// `<root>/<name>/<version>.flowpack` with a `<version>.sha256` file next to
+/// each archive.
+#[derive(Debug, Clone)]
+pub struct DirPackStore {
+    root: PathBuf,
+}
+
+impl DirPackStore {
+    /// Use `root` as the store, creating it on the first push.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, name: &str, version: &str, extension: &str) -> Result<PathBuf, FlowError> {
+        check_segment("package name", name)?;
+        check_segment("version", version)?;
+        Ok(self.root.join(name).join(format!("{version}.{extension}")))
+    }
+}
+
+#[async_trait]
+impl PackStore for DirPackStore {
+    async fn put(&self, name: &str, version: &str, archive: Vec<u8>) -> Result<(), FlowError> {
+        let path = self.path(name, version, "flowpack")?;
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            return Err(FlowError::NodeFailed(format!(
+                "Package {name}:{version} already exists"
+            )));
+        }
+        let io_error = |e: std::io::Error| {
+            FlowError::NodeFailed(format!("Cannot write {name}:{version}: {e}"))
+        };
+        tokio::fs::create_dir_all(self.root.join(name))
+            .await
+            .map_err(io_error)?;
+        tokio::fs::write(self.path(name, version, "sha256")?, digest(&archive))
+            .await
+            .map_err(io_error)?;
+        tokio::fs::write(path, archive).await.map_err(io_error)
+    }
+
+    async fn get(&self, name: &str, version: &str) -> Result<(Vec<u8>, String), FlowError> {
+        let io_error = |e: std::io::Error| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                FlowError::NotFound(format!("Package {name}:{version}"))
+            }
+            _ => FlowError::NodeFailed(format!("Cannot read package {name}:{version}: {e}")),
+        };
+        let archive = tokio::fs::read(self.path(name, version, "flowpack")?)
+            .await
+            .map_err(io_error)?;
+        let digest = tokio::fs::read_to_string(self.path(name, version, "sha256")?)
+            .await
+            .map_err(io_error)?;
+        Ok((archive, digest.trim().to_string()))
+    }
+
+    async fn versions(&self, name: &str) -> Result<Vec<String>, FlowError> {
+        check_segment("package name", name)?;
+        let mut entries = match tokio::fs::read_dir(self.root.join(name)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(FlowError::NodeFailed(format!("Cannot list {name}: {e}"))),
+        };
+        let mut versions = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Cannot list {name}: {e}")))?
+        {
+            let file_name = entry.file_name();
+            if let Some(version) = file_name.to_string_lossy().strip_suffix(".flowpack") {
+                versions.push(version.to_string());
+            }
+        }
+        Ok(versions)
+    }
+}
+
+/// A [`PackStore`] served over HTTP.
+///
+/// The server is expected to implement:
+///
+/// | Request | Meaning |
+/// |---------|---------|
+/// | `PUT {base}/{name}/{version}` | Store the archive in the body, with its digest in the `X-Flowpack-Digest` header |
+/// | `GET {base}/{name}/{version}` | Return the archive, with its stored digest in `X-Flowpack-Digest` |
+/// | `GET {base}/{name}` | Return the versions as a JSON array of strings |
+///
+/// This store is available with the `reqwest` feature.
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Clone)]
+pub struct HttpPackStore {
+    base: String,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+/// The header carrying an archive's digest.
+#[cfg(feature = "reqwest")]
+pub const DIGEST_HEADER: &str = "x-flowpack-digest";
+
+#[cfg(feature = "reqwest")]
+impl HttpPackStore {
+    /// Use the package server at `base`, such as
+    /// `https://packs.example.com/v1`.
+    pub fn new(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into().trim_end_matches('/').to_string(),
+            token: None,
+            client: crate::http::shared_client(),
+        }
+    }
+
+    /// Use a preconfigured HTTP client.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Send `token` as a bearer token with every request.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, FlowError> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Package store request failed: {e}")))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(FlowError::NotFound(response.url().to_string()));
+        }
+        if !status.is_success() {
+            let url = response.url().clone();
+            let body = response.text().await.unwrap_or_default();
+            return Err(FlowError::NodeFailed(format!(
+                "Request to {url} failed with status {status}: {body}"
+            )));
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl PackStore for HttpPackStore {
+    async fn put(&self, name: &str, version: &str, archive: Vec<u8>) -> Result<(), FlowError> {
+        check_segment("package name", name)?;
+        check_segment("version", version)?;
+        let url = format!("{}/{name}/{version}", self.base);
+        let request = self
+            .request(reqwest::Method::PUT, &url)
+            .header(DIGEST_HEADER, digest(&archive))
+            .header(reqwest::header::CONTENT_TYPE, "application/gzip")
+            .body(archive);
+        self.send(request).await.map(|_| ())
+    }
+
+    async fn get(&self, name: &str, version: &str) -> Result<(Vec<u8>, String), FlowError> {
+        check_segment("package name", name)?;
+        check_segment("version", version)?;
+        let url = format!("{}/{name}/{version}", self.base);
+        let response = self.send(self.request(reqwest::Method::GET, &url)).await?;
+        let stored = response
+            .headers()
+            .get(DIGEST_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                FlowError::NodeFailed(format!("{url} did not send an {DIGEST_HEADER} header"))
+            })?;
+        let archive = response
+            .bytes()
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Cannot download {url}: {e}")))?;
+        Ok((archive.to_vec(), stored))
+    }
+
+    async fn versions(&self, name: &str) -> Result<Vec<String>, FlowError> {
+        check_segment("package name", name)?;
+        let url = format!("{}/{name}", self.base);
+        let response = self.send(self.request(reqwest::Method::GET, &url)).await?;
+        response
+            .json()
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid JSON from {url}: {e}")))
+    }
+}
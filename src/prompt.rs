@@ -0,0 +1,136 @@
+//! Prompt templating for LLM-backed flows.
+//!
+//! This module provides [`PromptTemplate`], a node that renders Jinja-style
+//! templates against the incoming JSON and produces a messages array ready for
+//! a [`ChatNode`](crate::llm::ChatNode).
+
+use crate::error::FlowError;
+use crate::llm::{Message, Role};
+use crate::node::Node;
+use async_trait::async_trait;
+use minijinja::{Environment, UndefinedBehavior};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// A node that renders chat messages from templates.
+///
+/// Templates use [minijinja](https://docs.rs/minijinja) syntax and are
+/// rendered with the node input as context, so `{{ user_question }}` reads the
+/// `user_question` field and `{% for doc in documents %}` loops over an array.
+/// Referencing a missing variable in output position is an error; use
+/// `{% if name is defined %}` for optional fields.
+///
+/// Templates are compiled when the node is constructed, and syntax errors
+/// are returned as [`FlowError::Template`].
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::llm::Role;
+/// use rustyflow::prompt::PromptTemplate;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let template = PromptTemplate::from_messages(vec![
+///     (Role::System, "Answer using only these documents:{% for d in docs %}\n- {{ d }}{% endfor %}"),
+///     (Role::User, "{{ user_question }}"),
+/// ])?;
+///
+/// let messages = template
+///     .call(json!({"docs": ["Rust is fast"], "user_question": "Is Rust fast?"}))
+///     .await?;
+/// assert_eq!(messages[1]["content"], "Is Rust fast?");
+///
+/// assert!(PromptTemplate::new("{{ unclosed").is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub struct PromptTemplate {
+    env: Environment<'static>,
+    roles: Vec<Role>,
+}
+
+impl PromptTemplate {
+    /// Create a template that renders a single user message.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The template source for the user message
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Template` if the template has a syntax error.
+    pub fn new(template: impl Into<String>) -> Result<Self, FlowError> {
+        Self::from_messages(vec![(Role::User, template.into())])
+    }
+
+    /// Create a template that renders one message per `(role, template)` pair.
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - The role and template source for each message, in order
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Template` if any template has a syntax error.
+    pub fn from_messages<S: Into<String>>(messages: Vec<(Role, S)>) -> Result<Self, FlowError> {
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+
+        let mut roles = Vec::with_capacity(messages.len());
+        for (index, (role, source)) in messages.into_iter().enumerate() {
+            env.add_template_owned(index.to_string(), source.into())
+                .map_err(|e| FlowError::Template(e.to_string()))?;
+            roles.push(role);
+        }
+
+        Ok(Self { env, roles })
+    }
+
+    /// Render the messages for the given context.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Template` if rendering fails, for example when a
+    /// referenced variable is missing from the context.
+    pub fn render(&self, context: &Value) -> Result<Vec<Message>, FlowError> {
+        self.roles
+            .iter()
+            .enumerate()
+            .map(|(index, role)| {
+                let content = self
+                    .env
+                    .get_template(&index.to_string())
+                    .and_then(|template| template.render(context))
+                    .map_err(|e| FlowError::Template(e.to_string()))?;
+                Ok(Message {
+                    role: *role,
+                    content,
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Node for PromptTemplate {
+    /// Render the templates with the input as context.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Value::Array)` - The rendered messages
+    /// * `Err(FlowError)` - A rendering error
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let messages = self.render(&input)?;
+        Ok(serde_json::to_value(messages)?)
+    }
+
+    async fn call_shared(&self, input: Arc<Value>) -> Result<Value, FlowError> {
+        let messages = self.render(&input)?;
+        Ok(serde_json::to_value(messages)?)
+    }
+}
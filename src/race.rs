@@ -0,0 +1,265 @@
+//! Racing several nodes for the first successful result.
+//!
+//! This module provides [`Race`], which calls several nodes concurrently
+//! with the same input and returns the first success, cancelling the calls
+//! still running. Racing providers that serve the same model cuts tail
+//! latency: one slow or failing provider no longer holds up the flow. With
+//! [`Race::with_stagger`], backup calls start only if the earlier ones have
+//! not answered yet, which bounds the extra cost of hedged requests.
+//!
+//! [`Hedged`] does the same with duplicate requests to a single node, for
+//! providers whose latency varies from call to call.
+
+use crate::error::FlowError;
+use crate::node::{HealthCheck, Node, SchemaProvider};
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A node that returns the first successful result of several nodes.
+///
+/// Losing calls are cancelled by dropping them, so nodes should not rely on
+/// running to completion. If every node fails, the error of a single node
+/// is returned as is, and otherwise a [`FlowError::Multiple`] holds the name
+/// and error of each node in node order.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::race::Race;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::time::Duration;
+///
+/// struct Provider(&'static str, u64);
+///
+/// #[async_trait]
+/// impl Node for Provider {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         tokio::time::sleep(Duration::from_millis(self.1)).await;
+///         Ok(json!(self.0))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let race = Race::new(vec![
+///     Box::new(Provider("slow", 5_000)),
+///     Box::new(Provider("fast", 10)),
+/// ]);
+/// assert_eq!(race.call(json!({})).await?, json!("fast"));
+///
+/// // Hedge: call the backup only if the primary is slower than 50ms
+/// let hedged = Race::new(vec![
+///     Box::new(Provider("primary", 10)),
+///     Box::new(Provider("backup", 10)),
+/// ])
+/// .with_stagger(Duration::from_millis(50));
+/// assert_eq!(hedged.call(json!({})).await?, json!("primary"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Race {
+    nodes: Vec<Box<dyn Node>>,
+    stagger: Duration,
+}
+
+impl Race {
+    /// Create a race between `nodes`, all started at once.
+    pub fn new(nodes: Vec<Box<dyn Node>>) -> Self {
+        Self {
+            nodes,
+            stagger: Duration::ZERO,
+        }
+    }
+
+    /// Start each node `stagger` after the one before it, unless a result
+    /// has arrived by then.
+    pub fn with_stagger(mut self, stagger: Duration) -> Self {
+        self.stagger = stagger;
+        self
+    }
+}
+
+#[async_trait]
+impl Node for Race {
+    /// Call the nodes concurrently and return the first success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the race has no nodes, the error of
+    /// the only node, or `FlowError::Multiple` if every node failed.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        if self.nodes.is_empty() {
+            return Err(FlowError::NodeFailed("Race has no nodes".to_string()));
+        }
+
+        let mut calls: FuturesUnordered<_> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| {
+                let input = input.clone();
+                let delay = self.stagger * index as u32;
+                async move {
+                    tokio::time::sleep(delay).await;
+                    (index, node.call(input).await)
+                }
+            })
+            .collect();
+
+        let mut errors = Vec::new();
+        while let Some((index, result)) = calls.next().await {
+            match result {
+                Ok(value) => {
+                    tracing::debug!("Race won by '{}'", self.nodes[index].name());
+                    return Ok(value);
+                }
+                Err(e) => errors.push((index, e)),
+            }
+        }
+
+        if errors.len() == 1 {
+            let (_, error) = errors.remove(0);
+            return Err(error);
+        }
+        errors.sort_by_key(|(index, _)| *index);
+        let errors = errors
+            .into_iter()
+            .map(|(index, error)| (self.nodes[index].name().to_string(), error))
+            .collect();
+        Err(FlowError::Multiple(errors))
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.nodes.first().and_then(|node| node.input_schema())
+    }
+}
+
+/// A node that sends a duplicate request to its inner node when the first
+/// is slow, and keeps whichever finishes first.
+///
+/// The first request starts at once, and each further one `delay` after the
+/// one before it, until a result arrives or the maximum number of requests
+/// is in flight. The first success is returned and the requests still
+/// running are cancelled by dropping them. A failure is only returned once
+/// no request is left running; a request that fails before the next one is
+/// due does not start it, since hedging covers slowness, not errors.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::race::Hedged;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::time::{Duration, Instant};
+///
+/// /// The first call hits a slow replica; later calls are fast.
+/// struct Provider(AtomicU64);
+///
+/// #[async_trait]
+/// impl Node for Provider {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         let call = self.0.fetch_add(1, Ordering::SeqCst);
+///         let latency = if call == 0 { 5_000 } else { 10 };
+///         tokio::time::sleep(Duration::from_millis(latency)).await;
+///         Ok(json!({"call": call}))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let hedged = Hedged::new(Box::new(Provider(AtomicU64::new(0))), Duration::from_millis(50));
+///
+/// let started = Instant::now();
+/// assert_eq!(hedged.call(json!({})).await?, json!({"call": 1}));
+/// assert!(started.elapsed() < Duration::from_secs(1));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Hedged {
+    node: Box<dyn Node>,
+    delay: Duration,
+    max_requests: usize,
+}
+
+impl Hedged {
+    /// Create a hedged node that sends one duplicate request after `delay`.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to call, typically a model or network call
+    /// * `delay` - How long to wait for a request before sending another,
+    ///   such as the provider's p95 latency
+    pub fn new(node: Box<dyn Node>, delay: Duration) -> Self {
+        Self {
+            node,
+            delay,
+            max_requests: 2,
+        }
+    }
+
+    /// Send up to `max` requests in total, at least 1.
+    pub fn with_max_requests(mut self, max: usize) -> Self {
+        self.max_requests = max.max(1);
+        self
+    }
+}
+
+#[async_trait]
+impl Node for Hedged {
+    /// Call the node, hedging slow requests, and return the first success.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the request that failed last, once no request
+    /// is left running.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let input = Arc::new(input);
+        let mut calls = FuturesUnordered::new();
+        calls.push(self.node.call_shared(Arc::clone(&input)));
+        let mut sent = 1;
+        let mut next_at = tokio::time::Instant::now() + self.delay;
+
+        loop {
+            tokio::select! {
+                Some(result) = calls.next() => match result {
+                    Ok(value) => return Ok(value),
+                    Err(e) if calls.is_empty() => return Err(e),
+                    Err(e) => tracing::debug!("Hedged request to '{}' failed: {e}", self.node.name()),
+                },
+                _ = tokio::time::sleep_until(next_at), if sent < self.max_requests => {
+                    tracing::debug!(
+                        "Hedging request to '{}' after {:?}",
+                        self.node.name(),
+                        self.delay
+                    );
+                    calls.push(self.node.call_shared(Arc::clone(&input)));
+                    sent += 1;
+                    next_at += self.delay;
+                }
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.node.name()
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.node.input_schema()
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        self.node.schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        self.node.health_checkable()
+    }
+}
@@ -0,0 +1,213 @@
+//! Token-bucket rate limits on node calls.
+//!
+//! A [`TokenBucket`] holds a provider quota: a sustained number of requests
+//! per second plus a burst. [`RateLimit`] makes a node take one token from a
+//! bucket before each call, waiting for a token if none is left. Share one
+//! `Arc<TokenBucket>` between every node that talks to the same provider,
+//! across flows and concurrent runs, and together they stay within its
+//! quota. Wrapping the node of a [`Batch`](crate::Batch) limits each element
+//! call on its own.
+
+use crate::error::FlowError;
+use crate::node::{HealthCheck, Node, SchemaProvider};
+use crate::resources::Resource;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+struct State {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A quota of requests per second with a burst allowance.
+///
+/// The bucket starts full with `burst` tokens and regains
+/// `requests_per_second` tokens each second. Callers that find it empty
+/// reserve the next token and wait for it, so they are served in the order
+/// they arrived.
+pub struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<State>,
+}
+
+impl TokenBucket {
+    /// Create a full bucket.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests_per_second` - The sustained rate
+    /// * `burst` - How many requests may be made at once, at least 1
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let capacity = f64::from(burst.max(1));
+        Self {
+            rate: requests_per_second.max(f64::EPSILON),
+            capacity,
+            state: Mutex::new(State {
+                tokens: capacity,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take a token, waiting until one is available.
+    pub async fn acquire(&self) {
+        let wait = self.reserve(None).unwrap_or_default();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take a token if one is available now.
+    ///
+    /// # Errors
+    ///
+    /// Returns how long until a token is available if the bucket is empty.
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        self.reserve(Some(Duration::ZERO)).map(|_| ())
+    }
+
+    /// Reserve a token and return how long until it may be used, unless
+    /// that is longer than `max_wait`.
+    fn reserve(&self, max_wait: Option<Duration>) -> Result<Duration, Duration> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let elapsed = now.duration_since(state.updated).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.capacity);
+        state.updated = now;
+
+        let wait = if state.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - state.tokens) / self.rate)
+        };
+        if let Some(max_wait) = max_wait {
+            if wait > max_wait {
+                return Err(wait);
+            }
+        }
+        // Tokens go negative while calls wait, which queues later callers
+        // behind them
+        state.tokens -= 1.0;
+        Ok(wait)
+    }
+}
+
+/// A node that takes a token from a [`TokenBucket`] before each call.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::rate_limit::{RateLimit, TokenBucket};
+/// use rustyflow::{Batch, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::Arc;
+/// use std::time::{Duration, Instant};
+///
+/// struct Provider;
+///
+/// #[async_trait]
+/// impl Node for Provider {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(input)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// // 20 requests per second, 2 at once, shared by every caller
+/// let quota = Arc::new(TokenBucket::new(20.0, 2));
+/// let batch = Batch::new(RateLimit::new(Provider, Arc::clone(&quota)));
+///
+/// let start = Instant::now();
+/// batch.call(json!([1, 2, 3, 4])).await?;
+/// // Two calls use the burst, the other two wait 50ms each
+/// assert!(start.elapsed() >= Duration::from_millis(90));
+///
+/// // Callers that would rather fail than wait
+/// let impatient = RateLimit::new(Provider, quota).with_max_wait(Duration::ZERO);
+/// let error = impatient.call(json!(5)).await.unwrap_err();
+/// assert_eq!(error.kind(), "rate_limited");
+/// # Ok(())
+/// # }
+/// ```
+pub struct RateLimit<N: Node> {
+    node: N,
+    bucket: Arc<TokenBucket>,
+    max_wait: Option<Duration>,
+}
+
+impl<N: Node> RateLimit<N> {
+    /// Limit calls of `node` to the quota of `bucket`.
+    pub fn new(node: N, bucket: Arc<TokenBucket>) -> Self {
+        Self {
+            node,
+            bucket,
+            max_wait: None,
+        }
+    }
+
+    /// Fail with `FlowError::RateLimited` instead of waiting longer than
+    /// `max_wait` for a token. [`Batch`](crate::Batch) with
+    /// [`AdaptiveConcurrency`](crate::batch::AdaptiveConcurrency) backs off
+    /// on this error.
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+}
+
+#[async_trait]
+impl<N: Node> Node for RateLimit<N> {
+    /// Wait for a token, then call the node.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::RateLimited` if a token is further away than the
+    /// maximum wait, or any error of the node.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let wait = self.bucket.reserve(self.max_wait).map_err(|wait| {
+            FlowError::RateLimited(format!(
+                "Node '{}' would wait {}ms for its rate limit",
+                self.node.name(),
+                wait.as_millis()
+            ))
+        })?;
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        self.node.call(input).await
+    }
+
+    fn name(&self) -> &str {
+        self.node.name()
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.node.input_schema()
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        self.node.schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        self.node.health_checkable()
+    }
+
+    fn resources(&self) -> Vec<Resource> {
+        self.node.resources()
+    }
+
+    async fn snapshot(&self) -> Result<Option<Value>, FlowError> {
+        self.node.snapshot().await
+    }
+
+    async fn restore(&self, state: Value) -> Result<(), FlowError> {
+        self.node.restore(state).await
+    }
+}
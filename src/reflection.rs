@@ -0,0 +1,245 @@
+//! Reflection and critique presets.
+//!
+//! This module provides [`CritiqueNode`], which asks a [`ChatModel`] to score
+//! an answer against a typed rubric and emits structured [`Critique`]
+//! feedback that revision loops can act on without bespoke prompts.
+
+use crate::error::FlowError;
+use crate::llm::{extract_json, record_usage, ChatModel, ChatRequest, Message};
+use crate::node::Node;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// A single rubric criterion an answer is scored against.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Criterion {
+    /// Short identifier for the criterion, e.g. `"accuracy"`.
+    pub name: String,
+    /// What a good answer looks like for this criterion.
+    pub description: String,
+    /// Relative weight of the criterion in the overall score.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl Criterion {
+    /// Create a criterion with a weight of `1.0`.
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            weight: default_weight(),
+        }
+    }
+
+    /// Set the relative weight of this criterion.
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+}
+
+/// The score and feedback for a single criterion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriterionScore {
+    /// The criterion name.
+    pub name: String,
+    /// Score between `0.0` and `1.0`.
+    pub score: f64,
+    /// Actionable feedback explaining the score.
+    #[serde(default)]
+    pub feedback: String,
+}
+
+/// Structured critique of an answer.
+///
+/// This is the `critique` field of [`CritiqueNode`]'s output and can be
+/// deserialized back with `serde_json::from_value` by revision steps.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Critique {
+    /// Weighted overall score between `0.0` and `1.0`.
+    pub score: f64,
+    /// Whether the overall score met the configured threshold.
+    pub passed: bool,
+    /// Per-criterion scores, in rubric order.
+    pub criteria: Vec<CriterionScore>,
+    /// Combined feedback for criteria that fell short.
+    pub feedback: String,
+}
+
+/// A node that scores an answer against a rubric using a [`ChatModel`].
+///
+/// The input is an object with an `answer` field and an optional `task`
+/// field describing what the answer should accomplish. The output echoes the
+/// input fields and adds a `critique` field containing a [`Critique`].
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::llm::{ChatModel, ChatRequest, ChatResponse, Message};
+/// use rustyflow::reflection::{Criterion, CritiqueNode};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// struct Grader;
+///
+/// #[async_trait]
+/// impl ChatModel for Grader {
+///     async fn chat(&self, _request: ChatRequest) -> Result<ChatResponse, FlowError> {
+///         let reply = r#"{"criteria": [{"name": "accuracy", "score": 9, "feedback": "Correct."}]}"#;
+///         Ok(ChatResponse { message: Message::assistant(reply), usage: None })
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let critic = CritiqueNode::new(Grader, vec![Criterion::new("accuracy", "Facts are correct")]);
+/// let result = critic.call(json!({"task": "2 + 2?", "answer": "4"})).await?;
+/// assert_eq!(result["critique"]["passed"], true);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CritiqueNode<M: ChatModel> {
+    model: M,
+    rubric: Vec<Criterion>,
+    threshold: f64,
+}
+
+impl<M: ChatModel> CritiqueNode<M> {
+    /// Create a new critique node.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The chat model used as the critic
+    /// * `rubric` - The criteria the answer is scored against
+    pub fn new(model: M, rubric: Vec<Criterion>) -> Self {
+        Self {
+            model,
+            rubric,
+            threshold: 0.7,
+        }
+    }
+
+    /// Set the minimum overall score (between `0.0` and `1.0`) for an
+    /// answer to pass. Defaults to `0.7`.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    fn build_prompt(&self, task: Option<&str>, answer: &str) -> String {
+        let mut prompt = String::from(
+            "Evaluate the answer below against each criterion. Score every criterion \
+             from 0 to 10 and give concrete, actionable feedback.\n\nCriteria:\n",
+        );
+        for criterion in &self.rubric {
+            prompt.push_str(&format!(
+                "- {}: {}\n",
+                criterion.name, criterion.description
+            ));
+        }
+        if let Some(task) = task {
+            prompt.push_str(&format!("\nTask:\n{task}\n"));
+        }
+        prompt.push_str(&format!("\nAnswer:\n{answer}\n"));
+        prompt.push_str(
+            "\nRespond with JSON only, in the form \
+             {\"criteria\": [{\"name\": \"...\", \"score\": 0-10, \"feedback\": \"...\"}]}",
+        );
+        prompt
+    }
+
+    fn build_critique(&self, reply: &str) -> Result<Critique, FlowError> {
+        let parsed = extract_json(reply).ok_or_else(|| {
+            FlowError::NodeFailed("Critique response did not contain JSON".to_string())
+        })?;
+        let raw: Vec<CriterionScore> = match parsed {
+            Value::Array(_) => serde_json::from_value(parsed)?,
+            other => serde_json::from_value(other["criteria"].clone())?,
+        };
+
+        let mut criteria = Vec::with_capacity(self.rubric.len());
+        let mut weighted = 0.0;
+        let mut total_weight = 0.0;
+        for criterion in &self.rubric {
+            let scored = raw
+                .iter()
+                .find(|s| s.name == criterion.name)
+                .ok_or_else(|| {
+                    FlowError::NodeFailed(format!(
+                        "Critique response is missing criterion '{}'",
+                        criterion.name
+                    ))
+                })?;
+            let score = (scored.score / 10.0).clamp(0.0, 1.0);
+            weighted += score * criterion.weight;
+            total_weight += criterion.weight;
+            criteria.push(CriterionScore {
+                name: criterion.name.clone(),
+                score,
+                feedback: scored.feedback.clone(),
+            });
+        }
+
+        let score = if total_weight > 0.0 {
+            weighted / total_weight
+        } else {
+            0.0
+        };
+        let feedback = criteria
+            .iter()
+            .filter(|c| c.score < self.threshold)
+            .map(|c| format!("{}: {}", c.name, c.feedback))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(Critique {
+            score,
+            passed: score >= self.threshold,
+            criteria,
+            feedback,
+        })
+    }
+}
+
+#[async_trait]
+impl<M: ChatModel> Node for CritiqueNode<M> {
+    /// Score the input answer against the rubric.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the input has no `answer` field or
+    /// the model's reply cannot be parsed into scores for every criterion.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let answer = match &input["answer"] {
+            Value::String(text) => text.clone(),
+            Value::Null => {
+                return Err(FlowError::NodeFailed("Expected 'answer' field".to_string()))
+            }
+            other => other.to_string(),
+        };
+        let task = input["task"].as_str();
+
+        let request = ChatRequest::new(vec![
+            Message::system("You are a strict, fair reviewer."),
+            Message::user(self.build_prompt(task, &answer)),
+        ]);
+        let reply = record_usage(self.model.chat(request).await?)
+            .message
+            .content;
+        let critique = self.build_critique(&reply)?;
+
+        let mut output = match input {
+            Value::Object(map) => map,
+            _ => serde_json::Map::new(),
+        };
+        output.insert("critique".to_string(), json!(critique));
+        Ok(Value::Object(output))
+    }
+}
@@ -0,0 +1,780 @@
+//! Construction of nodes from type names and JSON parameters.
+//!
+//! A [`NodeRegistry`] maps type names such as `"prompt_template"` to
+//! factories that build a node from a JSON params blob. It is what lets
+//! flows be described as data rather than Rust code: a [`NodeSpec`] names a
+//! type and carries its params, and the registry turns it into a
+//! `Box<dyn Node>`. Applications register their own node types next to the
+//! built-in ones.
+//!
+//! # Built-in types
+//!
+//! [`NodeRegistry::with_builtins`] registers:
+//!
+//! | Type | Params |
+//! |------|--------|
+//! | `prompt_template` | `template`, or `messages` as `[{role, content}]` |
+//! | `difficulty_estimator` | optional `long_prompt_tokens` |
+//! | `batch` | `node` (a spec), optional `max_concurrency`, `chunk_size`, `chunk_delay_ms`, `spawn` |
+//! | `map_reduce` | `mapper` and `reducer` (specs), optional `max_concurrency` |
+//! | `monte_carlo` | `node` (a spec), `samples`, optional `pointer` |
+//! | `weighted_router` | `routes` as `[{node, weight}]`, optional `seed` |
+//! | `fallback` | `nodes` (specs, primary first), optional `on` (error [kinds](crate::FlowError::kind)) |
+//! | `race` | `nodes` (specs), optional `stagger_ms` |
+//! | `hedged` | `node` (a spec), `delay_ms`, optional `max_requests` |
+//! | `deadline` | `node` (a spec), `hard_ms`, optional `soft_ms`, `fallback` (a spec) |
+//! | `branch` | `arms` as `[{node, pointer, equals}]` or `[{node, action}]`, optional `default` (a spec) |
+//! | `loop` | `node` (a spec), `while` as `{pointer, equals}` or `{action}`, `max_iterations` |
+//! | `validate` | `schema` (a JSON Schema) |
+//! | `http_request` | `url`, optional `method`, `headers`, `body` (a template) or `json_body` (a pointer), `timeout_ms`, `retries`, `error_status` (`reqwest` feature) |
+//! | `paginated_fetch` | `url`, `pagination`, optional `headers`, `items_pointer`, `max_pages`, `max_items` (`reqwest` feature) |
+//! | `graphql` | `endpoint`, `query`, optional `headers`, `variables`, `operation_name`, `retries` (`reqwest` feature) |
+//! | `grpc` | `descriptor_set`, `endpoint`, `method`, optional `metadata`, `timeout_ms` (`grpc` feature) |
+//! | `transform` | `jq` (a filter) or `jsonpath` (a query), optional `collect` (`transform` feature) |
+//!
+//! Nodes that need a language model or other Rust values, such as
+//! [`ChatNode`](crate::llm::ChatNode), are not built in; register a factory
+//! that captures the model instead.
+
+use crate::batch::Batch;
+use crate::branch::Branch;
+use crate::deadline::Deadline;
+use crate::difficulty::DifficultyEstimator;
+use crate::error::FlowError;
+use crate::fallback::Fallback;
+use crate::graph::Condition;
+use crate::llm::Role;
+use crate::looping::Loop;
+use crate::map_reduce::MapReduce;
+use crate::node::Node;
+use crate::prompt::PromptTemplate;
+use crate::race::{Hedged, Race};
+use crate::resources::Tagged;
+use crate::router::WeightedRouter;
+use crate::sampling::MonteCarlo;
+use crate::schema::ValidateNode;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A node described as data: a registered type name and its params.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct NodeSpec {
+    /// The registered type name.
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// The params passed to the type's factory.
+    #[serde(default)]
+    pub params: Value,
+    /// Units of shared resource pools the node holds while it runs, such as
+    /// `{"gpu": 1}` (see [`ResourcePools`](crate::resources::ResourcePools)).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub resources: BTreeMap<String, u32>,
+}
+
+type Factory = Arc<dyn Fn(&Value, &NodeRegistry) -> Result<Box<dyn Node>, FlowError> + Send + Sync>;
+
+/// A table of node factories keyed by type name.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::registry::NodeRegistry;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+/// use async_trait::async_trait;
+///
+/// struct Greet(String);
+///
+/// #[async_trait]
+/// impl Node for Greet {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(format!("{}, {}!", self.0, input.as_str().unwrap_or("world"))))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let mut registry = NodeRegistry::with_builtins();
+/// registry.register("greet", |params| {
+///     let greeting = params["greeting"].as_str().unwrap_or("Hello").to_string();
+///     Ok(Box::new(Greet(greeting)))
+/// });
+///
+/// let node = registry.build(&json!({
+///     "type": "batch",
+///     "params": {"node": {"type": "greet", "params": {"greeting": "Hi"}}}
+/// }))?;
+/// assert_eq!(node.call(json!(["Ada", "Linus"])).await?, json!(["Hi, Ada!", "Hi, Linus!"]));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct NodeRegistry {
+    factories: HashMap<String, Factory>,
+}
+
+impl NodeRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the crate's own node types registered.
+    ///
+    /// See the [module documentation](self) for the types and their params.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register_builtins();
+        registry
+    }
+
+    /// Register a factory under `type_name`, replacing any existing one.
+    ///
+    /// # Arguments
+    ///
+    /// * `type_name` - The name used in a spec's `type` field
+    /// * `factory` - Builds a node from the spec's params
+    pub fn register<F>(&mut self, type_name: impl Into<String>, factory: F)
+    where
+        F: Fn(&Value) -> Result<Box<dyn Node>, FlowError> + Send + Sync + 'static,
+    {
+        self.factories
+            .insert(type_name.into(), Arc::new(move |params, _| factory(params)));
+    }
+
+    /// Register a factory for a node that wraps other nodes.
+    ///
+    /// The factory also receives the registry, so it can build the nested
+    /// specs in its params with [`NodeRegistry::build`].
+    pub fn register_composite<F>(&mut self, type_name: impl Into<String>, factory: F)
+    where
+        F: Fn(&Value, &NodeRegistry) -> Result<Box<dyn Node>, FlowError> + Send + Sync + 'static,
+    {
+        self.factories.insert(type_name.into(), Arc::new(factory));
+    }
+
+    /// Whether a factory is registered under `type_name`.
+    pub fn contains(&self, type_name: &str) -> bool {
+        self.factories.contains_key(type_name)
+    }
+
+    /// The registered type names, sorted.
+    pub fn type_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Build a node of a registered type.
+    ///
+    /// # Arguments
+    ///
+    /// * `type_name` - The registered type name
+    /// * `params` - The params for the type's factory
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the type is not registered, or the
+    /// factory's error if the params are invalid.
+    pub fn create(&self, type_name: &str, params: &Value) -> Result<Box<dyn Node>, FlowError> {
+        let factory = self
+            .factories
+            .get(type_name)
+            .ok_or_else(|| FlowError::NodeFailed(self.unknown_type(type_name)))?;
+        factory(params, self)
+    }
+
+    /// Describe an unregistered type name, suggesting the registered name
+    /// it is most likely a typo of, or listing them all if none is close.
+    pub(crate) fn unknown_type(&self, type_name: &str) -> String {
+        let names = self.type_names();
+        match closest(type_name, names.iter().copied()) {
+            Some(name) => format!("Unknown node type '{type_name}' (did you mean '{name}'?)"),
+            None => format!(
+                "Unknown node type '{type_name}' (registered: {})",
+                names.join(", ")
+            ),
+        }
+    }
+
+    /// Build a node from a spec given as JSON, such as
+    /// `{"type": "prompt_template", "params": {"template": "..."}}`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if `spec` is not a valid spec, or any
+    /// error from [`NodeRegistry::create`].
+    pub fn build(&self, spec: &Value) -> Result<Box<dyn Node>, FlowError> {
+        let spec: NodeSpec = serde_json::from_value(spec.clone())?;
+        self.create_spec(&spec)
+    }
+
+    /// Build the node a spec describes, tagged with the spec's resources.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from [`NodeRegistry::create`].
+    pub fn create_spec(&self, spec: &NodeSpec) -> Result<Box<dyn Node>, FlowError> {
+        let node = self.create(&spec.type_name, &spec.params)?;
+        if spec.resources.is_empty() {
+            return Ok(node);
+        }
+        let tagged = spec
+            .resources
+            .iter()
+            .fold(Tagged::new(node), |tagged, (pool, &units)| {
+                tagged.with_resource(pool.clone(), units)
+            });
+        Ok(Box::new(tagged))
+    }
+
+    fn register_builtins(&mut self) {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct TemplateParams {
+            template: Option<String>,
+            #[serde(default)]
+            messages: Vec<TemplateMessage>,
+        }
+
+        #[derive(Deserialize)]
+        struct TemplateMessage {
+            role: Role,
+            content: String,
+        }
+
+        self.register("prompt_template", |params| {
+            let params: TemplateParams = parse(params)?;
+            let template = match params.template {
+                Some(template) => PromptTemplate::new(template)?,
+                None => PromptTemplate::from_messages(
+                    params
+                        .messages
+                        .into_iter()
+                        .map(|message| (message.role, message.content))
+                        .collect(),
+                )?,
+            };
+            Ok(Box::new(template))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct EstimatorParams {
+            long_prompt_tokens: Option<usize>,
+        }
+
+        self.register("difficulty_estimator", |params| {
+            let params: EstimatorParams = parse(params)?;
+            let mut estimator = DifficultyEstimator::new();
+            if let Some(tokens) = params.long_prompt_tokens {
+                estimator = estimator.with_long_prompt_tokens(tokens);
+            }
+            Ok(Box::new(estimator))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct BatchParams {
+            node: Value,
+            max_concurrency: Option<usize>,
+            chunk_size: Option<usize>,
+            chunk_delay_ms: Option<u64>,
+            #[serde(default)]
+            spawn: bool,
+        }
+
+        self.register_composite("batch", |params, registry| {
+            let params: BatchParams = parse(params)?;
+            let mut batch = Batch::new(registry.build(&params.node)?);
+            if let Some(max) = params.max_concurrency {
+                batch = batch.with_max_concurrency(max);
+            }
+            if let Some(size) = params.chunk_size {
+                batch = batch.with_chunk_size(size);
+            }
+            if let Some(delay) = params.chunk_delay_ms {
+                batch = batch.with_chunk_delay(Duration::from_millis(delay));
+            }
+            Ok(match params.spawn {
+                true => Box::new(batch.spawned()),
+                false => Box::new(batch),
+            })
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct MapReduceParams {
+            mapper: Value,
+            reducer: Value,
+            max_concurrency: Option<usize>,
+        }
+
+        self.register_composite("map_reduce", |params, registry| {
+            let params: MapReduceParams = parse(params)?;
+            let mut map_reduce = MapReduce::new(
+                registry.build(&params.mapper)?,
+                registry.build(&params.reducer)?,
+            );
+            if let Some(max) = params.max_concurrency {
+                map_reduce = map_reduce.with_max_concurrency(max);
+            }
+            Ok(Box::new(map_reduce))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct MonteCarloParams {
+            node: Value,
+            samples: usize,
+            pointer: Option<String>,
+        }
+
+        self.register_composite("monte_carlo", |params, registry| {
+            let params: MonteCarloParams = parse(params)?;
+            let mut sampler = MonteCarlo::new(registry.build(&params.node)?, params.samples);
+            if let Some(pointer) = params.pointer {
+                sampler = sampler.with_pointer(pointer);
+            }
+            Ok(Box::new(sampler))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RouterParams {
+            routes: Vec<Route>,
+            seed: Option<u64>,
+        }
+
+        #[derive(Deserialize)]
+        struct Route {
+            node: Value,
+            weight: f64,
+        }
+
+        self.register_composite("weighted_router", |params, registry| {
+            let params: RouterParams = parse(params)?;
+            let routes = params
+                .routes
+                .into_iter()
+                .map(|route| Ok((registry.build(&route.node)?, route.weight)))
+                .collect::<Result<Vec<_>, FlowError>>()?;
+            let mut router = WeightedRouter::new(routes)?;
+            if let Some(seed) = params.seed {
+                router = router.with_seed(seed);
+            }
+            Ok(Box::new(router))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct FallbackParams {
+            nodes: Vec<Value>,
+            on: Option<Vec<String>>,
+        }
+
+        self.register_composite("fallback", |params, registry| {
+            let params: FallbackParams = parse(params)?;
+            let mut nodes = params.nodes.iter().map(|node| registry.build(node));
+            let primary = nodes.next().ok_or_else(|| {
+                FlowError::NodeFailed("Fallback needs at least one node".to_string())
+            })??;
+            let mut fallback = Fallback::new(primary);
+            for node in nodes {
+                fallback = fallback.with_fallback(node?);
+            }
+            if let Some(kinds) = params.on {
+                fallback = fallback
+                    .with_error_filter(move |error| kinds.iter().any(|kind| kind == error.kind()));
+            }
+            Ok(Box::new(fallback))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RaceParams {
+            nodes: Vec<Value>,
+            stagger_ms: Option<u64>,
+        }
+
+        self.register_composite("race", |params, registry| {
+            let params: RaceParams = parse(params)?;
+            let nodes = params
+                .nodes
+                .iter()
+                .map(|node| registry.build(node))
+                .collect::<Result<Vec<_>, FlowError>>()?;
+            let mut race = Race::new(nodes);
+            if let Some(stagger) = params.stagger_ms {
+                race = race.with_stagger(Duration::from_millis(stagger));
+            }
+            Ok(Box::new(race))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct HedgedParams {
+            node: Value,
+            delay_ms: u64,
+            max_requests: Option<usize>,
+        }
+
+        self.register_composite("hedged", |params, registry| {
+            let params: HedgedParams = parse(params)?;
+            let mut hedged = Hedged::new(
+                registry.build(&params.node)?,
+                Duration::from_millis(params.delay_ms),
+            );
+            if let Some(max) = params.max_requests {
+                hedged = hedged.with_max_requests(max);
+            }
+            Ok(Box::new(hedged))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct DeadlineParams {
+            node: Value,
+            hard_ms: u64,
+            soft_ms: Option<u64>,
+            fallback: Option<Value>,
+        }
+
+        self.register_composite("deadline", |params, registry| {
+            let params: DeadlineParams = parse(params)?;
+            let mut deadline = Deadline::new(
+                registry.build(&params.node)?,
+                Duration::from_millis(params.hard_ms),
+            );
+            if let Some(soft) = params.soft_ms {
+                deadline = deadline.with_soft_timeout(Duration::from_millis(soft));
+            }
+            if let Some(fallback) = &params.fallback {
+                deadline = deadline.with_fallback(registry.build(fallback)?);
+            }
+            Ok(Box::new(deadline))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct BranchParams {
+            arms: Vec<Arm>,
+            default: Option<Value>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Arm {
+            node: Value,
+            pointer: Option<String>,
+            #[serde(default, deserialize_with = "present")]
+            equals: Option<Value>,
+            action: Option<String>,
+        }
+
+        self.register_composite("branch", |params, registry| {
+            let params: BranchParams = parse(params)?;
+            let mut branch = Branch::new();
+            for arm in params.arms {
+                let condition = condition(arm.pointer, arm.equals, arm.action)?;
+                branch = branch.with_arm(condition, registry.build(&arm.node)?);
+            }
+            if let Some(default) = params.default {
+                branch = branch.with_default(registry.build(&default)?);
+            }
+            Ok(Box::new(branch))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct LoopParams {
+            node: Value,
+            #[serde(rename = "while")]
+            condition: ConditionParams,
+            max_iterations: usize,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct ConditionParams {
+            pointer: Option<String>,
+            #[serde(default, deserialize_with = "present")]
+            equals: Option<Value>,
+            action: Option<String>,
+        }
+
+        self.register_composite("loop", |params, registry| {
+            let params: LoopParams = parse(params)?;
+            let ConditionParams {
+                pointer,
+                equals,
+                action,
+            } = params.condition;
+            Ok(Box::new(Loop::new(
+                registry.build(&params.node)?,
+                condition(pointer, equals, action)?,
+                params.max_iterations,
+            )))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct ValidateParams {
+            schema: Value,
+        }
+
+        self.register("validate", |params| {
+            let params: ValidateParams = parse(params)?;
+            Ok(Box::new(ValidateNode::new(params.schema)))
+        });
+
+        #[cfg(feature = "reqwest")]
+        self.register_http_builtins();
+        #[cfg(feature = "grpc")]
+        self.register_grpc_builtins();
+        #[cfg(feature = "transform")]
+        self.register_transform_builtins();
+    }
+
+    #[cfg(feature = "reqwest")]
+    fn register_http_builtins(&mut self) {
+        use crate::graphql::GraphQlNode;
+        use crate::http::{HttpRequestNode, Method, PaginatedFetch, Pagination};
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RequestParams {
+            url: String,
+            method: Option<String>,
+            #[serde(default)]
+            headers: HashMap<String, String>,
+            body: Option<String>,
+            json_body: Option<String>,
+            timeout_ms: Option<u64>,
+            retries: Option<usize>,
+            #[serde(default)]
+            error_status: bool,
+        }
+
+        self.register("http_request", |params| {
+            let params: RequestParams = parse(params)?;
+            let mut node = HttpRequestNode::new(params.url)?;
+            if let Some(method) = params.method {
+                let method = Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|e| {
+                    FlowError::NodeFailed(format!("Invalid HTTP method '{method}': {e}"))
+                })?;
+                node = node.with_method(method);
+            }
+            for (name, value) in &params.headers {
+                node = node.with_header(name, value)?;
+            }
+            node = match (params.body, params.json_body) {
+                (Some(_), Some(_)) => {
+                    return Err(FlowError::NodeFailed(
+                        "Give either 'body' or 'json_body', not both".to_string(),
+                    ))
+                }
+                (Some(template), None) => node.with_body_template(template)?,
+                (None, Some(pointer)) => node.with_json_body(pointer),
+                (None, None) => node,
+            };
+            if let Some(timeout_ms) = params.timeout_ms {
+                node = node.with_timeout(Duration::from_millis(timeout_ms));
+            }
+            if let Some(retries) = params.retries {
+                node = node.with_retries(retries);
+            }
+            if params.error_status {
+                node = node.with_error_status();
+            }
+            Ok(Box::new(node))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct FetchParams {
+            url: String,
+            pagination: Pagination,
+            #[serde(default)]
+            headers: HashMap<String, String>,
+            items_pointer: Option<String>,
+            max_pages: Option<usize>,
+            max_items: Option<usize>,
+        }
+
+        self.register("paginated_fetch", |params| {
+            let params: FetchParams = parse(params)?;
+            let mut fetch = PaginatedFetch::new(params.url, params.pagination);
+            for (name, value) in &params.headers {
+                fetch = fetch.with_header(name, value)?;
+            }
+            if let Some(pointer) = params.items_pointer {
+                fetch = fetch.with_items_pointer(pointer);
+            }
+            if let Some(max_pages) = params.max_pages {
+                fetch = fetch.with_max_pages(max_pages);
+            }
+            if let Some(max_items) = params.max_items {
+                fetch = fetch.with_max_items(max_items);
+            }
+            Ok(Box::new(fetch))
+        });
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct GraphQlParams {
+            endpoint: String,
+            query: String,
+            #[serde(default)]
+            headers: HashMap<String, String>,
+            #[serde(default)]
+            variables: HashMap<String, String>,
+            operation_name: Option<String>,
+            retries: Option<usize>,
+        }
+
+        self.register("graphql", |params| {
+            let params: GraphQlParams = parse(params)?;
+            let mut node = GraphQlNode::new(params.endpoint, params.query);
+            for (name, value) in &params.headers {
+                node = node.with_header(name, value)?;
+            }
+            for (name, pointer) in params.variables {
+                node = node.with_variable(name, pointer);
+            }
+            if let Some(name) = params.operation_name {
+                node = node.with_operation_name(name);
+            }
+            if let Some(retries) = params.retries {
+                node = node.with_retries(retries);
+            }
+            Ok(Box::new(node))
+        });
+    }
+
+    #[cfg(feature = "grpc")]
+    fn register_grpc_builtins(&mut self) {
+        use crate::grpc::GrpcNode;
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct GrpcParams {
+            descriptor_set: String,
+            endpoint: String,
+            method: String,
+            #[serde(default)]
+            metadata: HashMap<String, String>,
+            timeout_ms: Option<u64>,
+        }
+
+        self.register("grpc", |params| {
+            let params: GrpcParams = parse(params)?;
+            let mut node =
+                GrpcNode::from_file(&params.descriptor_set, &params.endpoint, &params.method)?;
+            for (key, value) in &params.metadata {
+                node = node.with_metadata(key, value)?;
+            }
+            if let Some(timeout) = params.timeout_ms {
+                node = node.with_timeout(Duration::from_millis(timeout));
+            }
+            Ok(Box::new(node))
+        });
+    }
+
+    #[cfg(feature = "transform")]
+    fn register_transform_builtins(&mut self) {
+        use crate::transform::TransformNode;
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct TransformParams {
+            jq: Option<String>,
+            jsonpath: Option<String>,
+            #[serde(default)]
+            collect: bool,
+        }
+
+        self.register("transform", |params| {
+            let params: TransformParams = parse(params)?;
+            let node = match (params.jq, params.jsonpath) {
+                (Some(filter), None) => TransformNode::jq(&filter)?,
+                (None, Some(query)) => TransformNode::jsonpath(&query)?,
+                _ => {
+                    return Err(FlowError::NodeFailed(
+                        "A transform needs either 'jq' or 'jsonpath'".to_string(),
+                    ))
+                }
+            };
+            Ok(Box::new(if params.collect {
+                node.with_collect()
+            } else {
+                node
+            }))
+        });
+    }
+}
+
+/// Deserialize factory params, treating `null` as an empty object.
+fn parse<T: DeserializeOwned>(params: &Value) -> Result<T, FlowError> {
+    let params = match params {
+        Value::Null => Value::Object(Default::default()),
+        other => other.clone(),
+    };
+    Ok(serde_json::from_value(params)?)
+}
+
+/// Deserialize an optional field that is `Some` even when it is `null`.
+fn present<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(d).map(Some)
+}
+
+/// Build a [`Condition`] from `pointer` with an optional `equals`, or from
+/// `action`.
+fn condition(
+    pointer: Option<String>,
+    equals: Option<Value>,
+    action: Option<String>,
+) -> Result<Condition, FlowError> {
+    match (pointer, equals, action) {
+        (Some(pointer), Some(value), None) => Ok(Condition::Equals(pointer, value)),
+        (Some(pointer), None, None) => Ok(Condition::Exists(pointer)),
+        (None, None, Some(action)) => Ok(Condition::Action(action)),
+        _ => Err(FlowError::NodeFailed(
+            "A condition needs either 'pointer' (with optional 'equals') or 'action'".to_string(),
+        )),
+    }
+}
+
+/// The candidate `name` is most likely a typo of, if any is close enough.
+pub(crate) fn closest<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .min()
+        .filter(|(distance, candidate)| *distance <= (candidate.chars().count() / 3).max(2))
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between two strings, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
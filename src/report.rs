@@ -0,0 +1,282 @@
+//! Structured execution reports for debugging and billing.
+//!
+//! [`Flow::execute_traced`](crate::flow::Flow::execute_traced) measures every
+//! node it runs and returns an [`ExecutionReport`]. Nodes can add to the
+//! measurements of their own step with [`record_retry`], [`record_usage`],
+//! [`record_score`] and [`record_metadata`]; the built-in model, network and
+//! quality gate nodes already do. Outside a traced
+//! execution these functions do nothing, and work spawned onto other tasks is
+//! not attributed to the step.
+//!
+//! Nodes that run subprocesses or other runtimes keep their output with
+//! [`record_log`] or [`record_output`], as
+//! [`CommandNode`](crate::command::CommandNode) does with its stderr, so it
+//! is not lost: each line is logged inside the node's span, where a tracing
+//! subscriber or the `otel` layer correlates it with the node and its
+//! trace, and is kept in the step's
+//! [`NodeReport::logs`], from which
+//! [`RunRecord::with_logs`](crate::history::RunRecord::with_logs) copies it
+//! into run history.
+
+use crate::llm::Usage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+
+/// The most log lines kept per step; later lines are still logged.
+const MAX_LOG_LINES: usize = 1000;
+
+tokio::task_local! {
+    static CURRENT: RefCell<StepMetrics>;
+}
+
+/// Measurements recorded by a node while it runs.
+#[derive(Debug, Default)]
+pub(crate) struct StepMetrics {
+    pub(crate) retries: u32,
+    pub(crate) usage: Option<Usage>,
+    pub(crate) scores: BTreeMap<String, f64>,
+    pub(crate) logs: Vec<LogLine>,
+    pub(crate) metadata: BTreeMap<String, Value>,
+}
+
+/// The stream a node's output line was written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    /// Standard output.
+    Stdout,
+    /// Standard error.
+    Stderr,
+}
+
+/// A line of output recorded by a node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogLine {
+    /// The stream the line was written to.
+    pub stream: LogStream,
+    /// The line, without its line ending.
+    pub line: String,
+}
+
+/// Record that the current node retried an operation.
+pub fn record_retry() {
+    let _ = CURRENT.try_with(|metrics| metrics.borrow_mut().retries += 1);
+}
+
+/// Record language model token usage against the current node.
+///
+/// Usage is summed when a node makes several model calls.
+pub fn record_usage(usage: Usage) {
+    let _ = CURRENT.try_with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        let total = metrics.usage.get_or_insert_with(Usage::default);
+        total.prompt_tokens += usage.prompt_tokens;
+        total.completion_tokens += usage.completion_tokens;
+    });
+}
+
+/// Record a quality score of the current node's output, such as one from a
+/// [`QualityGate`](crate::scorer::QualityGate).
+///
+/// A later score with the same name replaces an earlier one.
+pub fn record_score(name: &str, score: f64) {
+    let _ = CURRENT.try_with(|metrics| {
+        metrics.borrow_mut().scores.insert(name.to_string(), score);
+    });
+}
+
+/// Record a piece of metadata about the current node's call, such as the
+/// model version that answered or the cache entry that was hit.
+///
+/// A later value with the same key replaces an earlier one.
+/// [`V2Node`](crate::node::V2Node) records the metadata of every
+/// [`NodeOutput`](crate::node::NodeOutput) this way.
+pub fn record_metadata(key: &str, value: Value) {
+    let _ = CURRENT.try_with(|metrics| {
+        metrics.borrow_mut().metadata.insert(key.to_string(), value);
+    });
+}
+
+/// Record a line of output of the current node, such as one written by a
+/// subprocess it runs.
+///
+/// The line is logged as a `tracing` event with target
+/// `rustyflow::node_log` inside the node's span, at `INFO` for stdout and
+/// `WARN` for stderr, even outside a traced execution. In a traced
+/// execution it is also kept in the step's [`NodeReport::logs`], up to 1000
+/// lines per step.
+pub fn record_log(stream: LogStream, line: impl Into<String>) {
+    let line = line.into();
+    match stream {
+        LogStream::Stdout => {
+            tracing::info!(target: "rustyflow::node_log", stream = "stdout", "{line}")
+        }
+        LogStream::Stderr => {
+            tracing::warn!(target: "rustyflow::node_log", stream = "stderr", "{line}")
+        }
+    }
+    let _ = CURRENT.try_with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        if metrics.logs.len() < MAX_LOG_LINES {
+            metrics.logs.push(LogLine { stream, line });
+        }
+    });
+}
+
+/// Record every line read from `reader` with [`record_log`], until it ends.
+///
+/// Await it inside the node's call, for example joined with waiting for a
+/// child process, with the child's piped stdout or stderr as `reader`.
+///
+/// # Errors
+///
+/// Returns any error reading from `reader`.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::history::RunRecord;
+/// use rustyflow::report::{record_output, LogStream};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// /// Stands in for a node running a script whose output is piped.
+/// struct Script;
+///
+/// #[async_trait]
+/// impl Node for Script {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let stdout: &[u8] = b"loading model\nscored 3 rows\n";
+///         let stderr: &[u8] = b"warning: slow disk\n";
+///         let (out, err) = tokio::join!(
+///             record_output(LogStream::Stdout, stdout),
+///             record_output(LogStream::Stderr, stderr),
+///         );
+///         out.and(err).map_err(|e| FlowError::NodeFailed(e.to_string()))?;
+///         Ok(input)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let flow = Flow::new(vec![Box::new(Script)]);
+/// let (result, report) = flow.execute_traced(json!({})).await;
+/// result?;
+/// assert_eq!(report.nodes[0].logs.len(), 3);
+///
+/// let run = RunRecord::new("run-1", "scoring").with_logs(&report);
+/// assert_eq!(run.logs[2].line, "warning: slow disk");
+/// assert_eq!(run.logs[2].node, "Script");
+/// # Ok(())
+/// # }
+/// ```
+pub async fn record_output<R: AsyncRead + Unpin>(
+    stream: LogStream,
+    reader: R,
+) -> std::io::Result<()> {
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        record_log(stream, line);
+    }
+    Ok(())
+}
+
+/// Run `future` as one step, collecting what it records.
+pub(crate) async fn measure<F: Future>(future: F) -> (F::Output, StepMetrics) {
+    let (output, metrics) = CURRENT
+        .scope(RefCell::new(StepMetrics::default()), async {
+            let output = future.await;
+            let metrics = CURRENT.with(|metrics| metrics.take());
+            (output, metrics)
+        })
+        .await;
+    (output, metrics)
+}
+
+/// The serialized size of a JSON value in bytes.
+pub(crate) fn json_size(value: &Value) -> usize {
+    struct Counter(usize);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+/// The measurements for one node of a traced execution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeReport {
+    /// Position of the node in the flow.
+    pub index: usize,
+    /// The node's [`Node::name`](crate::node::Node::name).
+    pub name: String,
+    /// Wall-clock time spent in the node.
+    pub duration: Duration,
+    /// Serialized size of the node's input in bytes.
+    pub input_bytes: usize,
+    /// Serialized size of the node's output in bytes, if it succeeded.
+    pub output_bytes: Option<usize>,
+    /// Retries the node reported with [`record_retry`].
+    pub retries: u32,
+    /// Token usage the node reported with [`record_usage`].
+    pub usage: Option<Usage>,
+    /// Quality scores the node reported with [`record_score`], by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scores: BTreeMap<String, f64>,
+    /// Output lines the node recorded with [`record_log`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<LogLine>,
+    /// Metadata the node reported with [`record_metadata`], by key.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, Value>,
+    /// The error message, if the node failed.
+    pub error: Option<String>,
+}
+
+/// A per-node record of a traced flow execution.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    /// One entry per node that ran, in execution order.
+    pub nodes: Vec<NodeReport>,
+    /// Wall-clock time of the whole execution.
+    pub duration: Duration,
+}
+
+impl ExecutionReport {
+    /// Token usage summed over all nodes.
+    pub fn total_usage(&self) -> Usage {
+        self.nodes
+            .iter()
+            .filter_map(|node| node.usage)
+            .fold(Usage::default(), |total, usage| Usage {
+                prompt_tokens: total.prompt_tokens + usage.prompt_tokens,
+                completion_tokens: total.completion_tokens + usage.completion_tokens,
+            })
+    }
+
+    /// The node that took the longest, if any ran.
+    pub fn slowest(&self) -> Option<&NodeReport> {
+        self.nodes.iter().max_by_key(|node| node.duration)
+    }
+
+    /// The report of the node that failed, if any.
+    pub fn failed(&self) -> Option<&NodeReport> {
+        self.nodes.iter().find(|node| node.error.is_some())
+    }
+}
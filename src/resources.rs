@@ -0,0 +1,238 @@
+//! Resource pools shared by concurrent flow runs.
+//!
+//! Nodes declare the scarce resources they use with [`Node::resources`]:
+//! a GPU, a heavy-CPU slot, or a place in the pool of requests allowed to an
+//! external API. [`ResourcePools`] is a [`FlowScheduler`] that holds each
+//! node call back until every pool it names has enough free units, and
+//! releases them when the call returns. Give the same pools (an
+//! `Arc<ResourcePools>`) to every flow, and the capacities hold across all
+//! of their concurrent runs, so a burst of requests cannot oversubscribe a
+//! local model server.
+//!
+//! Existing nodes can be tagged without changing them by wrapping them in
+//! [`Tagged`].
+//!
+//! A node must not call, while it holds units of a pool, another node that
+//! needs the same pool, or it may wait for itself. For this reason a
+//! [`Batch`](crate::Batch) does not inherit the resources of the node it
+//! wraps: each element is admitted on its own.
+
+use crate::error::FlowError;
+use crate::executor::{Admission, FlowScheduler, NodeCall};
+use crate::node::{HealthCheck, Node, SchemaProvider};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// A number of units of a named resource pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    /// The pool's name, such as `gpu` or `openai`.
+    pub pool: String,
+    /// How many units one call holds.
+    pub units: u32,
+}
+
+impl Resource {
+    /// Request `units` of `pool`.
+    pub fn new(pool: impl Into<String>, units: u32) -> Self {
+        Self {
+            pool: pool.into(),
+            units,
+        }
+    }
+}
+
+/// A node tagged with the resources it uses.
+pub struct Tagged<N: Node> {
+    node: N,
+    resources: Vec<Resource>,
+}
+
+impl<N: Node> Tagged<N> {
+    /// Wrap `node`, which declares no resources yet.
+    pub fn new(node: N) -> Self {
+        Self {
+            node,
+            resources: Vec::new(),
+        }
+    }
+
+    /// Hold `units` of `pool` while the node runs.
+    pub fn with_resource(mut self, pool: impl Into<String>, units: u32) -> Self {
+        self.resources.push(Resource::new(pool, units));
+        self
+    }
+}
+
+#[async_trait]
+impl<N: Node> Node for Tagged<N> {
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        self.node.call(input).await
+    }
+
+    async fn call_shared(&self, input: Arc<Value>) -> Result<Value, FlowError> {
+        self.node.call_shared(input).await
+    }
+
+    fn name(&self) -> &str {
+        self.node.name()
+    }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.node.input_schema()
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        self.node.schema_provider()
+    }
+
+    fn health_checkable(&self) -> Option<&dyn HealthCheck> {
+        self.node.health_checkable()
+    }
+
+    fn resources(&self) -> Vec<Resource> {
+        self.resources.clone()
+    }
+
+    async fn snapshot(&self) -> Result<Option<Value>, FlowError> {
+        self.node.snapshot().await
+    }
+
+    async fn restore(&self, state: Value) -> Result<(), FlowError> {
+        self.node.restore(state).await
+    }
+}
+
+struct Pool {
+    capacity: u32,
+    semaphore: Arc<Semaphore>,
+}
+
+/// A [`FlowScheduler`] that enforces the capacities of named resource pools.
+///
+/// Calls of nodes that declare no resources are admitted at once.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::resources::{ResourcePools, Tagged};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// static RUNNING: AtomicUsize = AtomicUsize::new(0);
+/// static PEAK: AtomicUsize = AtomicUsize::new(0);
+///
+/// struct LocalModel;
+///
+/// #[async_trait]
+/// impl Node for LocalModel {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+///         PEAK.fetch_max(running, Ordering::SeqCst);
+///         tokio::time::sleep(Duration::from_millis(10)).await;
+///         RUNNING.fetch_sub(1, Ordering::SeqCst);
+///         Ok(input)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// // One model server that can serve two requests at a time
+/// let pools = Arc::new(ResourcePools::new().with_pool("gpu", 2));
+///
+/// let flow = || {
+///     let node = Tagged::new(LocalModel).with_resource("gpu", 1);
+///     Flow::new(vec![Box::new(node)]).with_scheduler(Arc::clone(&pools))
+/// };
+/// let flows: Vec<Flow> = (0..6).map(|_| flow()).collect();
+///
+/// // Six concurrent runs share the two GPU slots
+/// let runs = flows.iter().map(|flow| flow.execute(json!(1)));
+/// for result in futures::future::join_all(runs).await {
+///     result?;
+/// }
+/// assert_eq!(PEAK.load(Ordering::SeqCst), 2);
+/// assert_eq!(pools.available("gpu"), Some(2));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct ResourcePools {
+    pools: BTreeMap<String, Pool>,
+}
+
+impl ResourcePools {
+    /// Create a scheduler without pools.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pool of `capacity` units.
+    pub fn with_pool(mut self, name: impl Into<String>, capacity: u32) -> Self {
+        let pool = Pool {
+            capacity,
+            semaphore: Arc::new(Semaphore::new(capacity as usize)),
+        };
+        self.pools.insert(name.into(), pool);
+        self
+    }
+
+    /// The units of a pool that are free now, or `None` for an unknown pool.
+    pub fn available(&self, name: &str) -> Option<usize> {
+        self.pools
+            .get(name)
+            .map(|pool| pool.semaphore.available_permits())
+    }
+}
+
+#[async_trait]
+impl FlowScheduler for ResourcePools {
+    /// Wait for every resource the node declares.
+    ///
+    /// Pools are acquired in name order, so two calls needing the same
+    /// pools cannot each hold one and wait for the other.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the node names an unknown pool or
+    /// needs more units than the pool has.
+    async fn admit(&self, call: &NodeCall<'_>) -> Result<Admission, FlowError> {
+        let mut needed: BTreeMap<String, u32> = BTreeMap::new();
+        for resource in call.node.resources() {
+            *needed.entry(resource.pool).or_default() += resource.units;
+        }
+        if needed.is_empty() {
+            return Ok(Admission::granted());
+        }
+
+        let mut permits = Vec::with_capacity(needed.len());
+        for (name, units) in needed {
+            let pool = self.pools.get(&name).ok_or_else(|| {
+                FlowError::NodeFailed(format!(
+                    "Node '{}' needs unknown resource pool '{name}'",
+                    call.node.name()
+                ))
+            })?;
+            if units > pool.capacity {
+                return Err(FlowError::NodeFailed(format!(
+                    "Node '{}' needs {units} units of '{name}', which has {}",
+                    call.node.name(),
+                    pool.capacity
+                )));
+            }
+            let permit = Arc::clone(&pool.semaphore)
+                .acquire_many_owned(units)
+                .await
+                .map_err(|_| FlowError::NodeFailed(format!("Resource pool '{name}' is closed")))?;
+            permits.push(permit);
+        }
+        Ok(Admission::holding(permits))
+    }
+}
@@ -0,0 +1,264 @@
+//! Routing between alternative nodes.
+//!
+//! This module provides [`WeightedRouter`], which sends each call to one of
+//! several child nodes chosen at random by weight. It is intended for gradual
+//! traffic shifting between prompts or models inside a single flow.
+
+use crate::error::FlowError;
+use crate::node::Node;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Call statistics for one route of a [`WeightedRouter`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouteStats {
+    /// Number of calls routed to the node.
+    pub calls: u64,
+    /// Number of those calls that returned an error.
+    pub errors: u64,
+    /// Total time spent in the node.
+    pub total_latency: Duration,
+}
+
+impl RouteStats {
+    /// The fraction of calls that failed, or `0.0` before any call.
+    pub fn error_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.calls as f64
+        }
+    }
+
+    /// The mean time per call, if any call was made.
+    pub fn mean_latency(&self) -> Option<Duration> {
+        (self.calls > 0).then(|| self.total_latency.div_f64(self.calls as f64))
+    }
+}
+
+type WeightFn = dyn Fn(&[RouteStats]) -> Vec<f64> + Send + Sync;
+
+/// A node that forwards each call to one child node picked by weight.
+///
+/// Weights are relative: routes weighted `9.0` and `1.0` receive about 90%
+/// and 10% of the calls. Weights can be changed while the router is in use
+/// with [`WeightedRouter::set_weights`], or derived from each route's
+/// observed [`RouteStats`] with [`WeightedRouter::with_dynamic_weights`].
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::router::WeightedRouter;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// struct Prompt(&'static str);
+///
+/// #[async_trait]
+/// impl Node for Prompt {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(self.0))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// // Shift 10% of traffic to the new prompt
+/// let router = WeightedRouter::new(vec![
+///     (Box::new(Prompt("v1")) as Box<dyn Node>, 9.0),
+///     (Box::new(Prompt("v2")), 1.0),
+/// ])?
+/// .with_seed(7);
+///
+/// for _ in 0..100 {
+///     router.call(json!({})).await?;
+/// }
+/// let stats = router.stats();
+/// assert_eq!(stats[0].calls + stats[1].calls, 100);
+/// assert!(stats[0].calls > stats[1].calls);
+/// # Ok(())
+/// # }
+/// ```
+pub struct WeightedRouter {
+    routes: Vec<Box<dyn Node>>,
+    weights: RwLock<Vec<f64>>,
+    dynamic: Option<Box<WeightFn>>,
+    stats: Mutex<Vec<RouteStats>>,
+    rng: AtomicU64,
+}
+
+impl WeightedRouter {
+    /// Create a router over weighted child nodes.
+    ///
+    /// # Arguments
+    ///
+    /// * `routes` - Child nodes paired with their relative weights
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if there are no routes or the weights
+    /// are invalid.
+    pub fn new(routes: Vec<(Box<dyn Node>, f64)>) -> Result<Self, FlowError> {
+        if routes.is_empty() {
+            return Err(FlowError::NodeFailed(
+                "WeightedRouter needs at least one route".to_string(),
+            ));
+        }
+        let (routes, weights): (Vec<_>, Vec<_>) = routes.into_iter().unzip();
+        validate_weights(&weights, routes.len())?;
+
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Ok(Self {
+            stats: Mutex::new(vec![RouteStats::default(); routes.len()]),
+            routes,
+            weights: RwLock::new(weights),
+            dynamic: None,
+            rng: AtomicU64::new(seed),
+        })
+    }
+
+    /// Seed the random route selection, for reproducible routing.
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.rng.store(seed, Ordering::Relaxed);
+        self
+    }
+
+    /// Compute weights from route statistics before every call.
+    ///
+    /// The function receives one [`RouteStats`] per route and returns one
+    /// weight per route; it replaces the configured weights. Invalid results
+    /// fall back to the configured weights.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use rustyflow::router::WeightedRouter;
+    /// # fn example(router: WeightedRouter) -> WeightedRouter {
+    /// // Favor routes with fewer errors
+    /// router.with_dynamic_weights(|stats| {
+    ///     stats.iter().map(|s| 1.0 - s.error_rate() + 0.01).collect()
+    /// })
+    /// # }
+    /// ```
+    pub fn with_dynamic_weights<F>(mut self, weights: F) -> Self
+    where
+        F: Fn(&[RouteStats]) -> Vec<f64> + Send + Sync + 'static,
+    {
+        self.dynamic = Some(Box::new(weights));
+        self
+    }
+
+    /// Replace the configured weights.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the number of weights does not match
+    /// the number of routes, or the weights are invalid.
+    pub fn set_weights(&self, weights: Vec<f64>) -> Result<(), FlowError> {
+        validate_weights(&weights, self.routes.len())?;
+        *self.weights.write().unwrap() = weights;
+        Ok(())
+    }
+
+    /// The configured weights.
+    pub fn weights(&self) -> Vec<f64> {
+        self.weights.read().unwrap().clone()
+    }
+
+    /// A snapshot of each route's call statistics.
+    pub fn stats(&self) -> Vec<RouteStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// The next pseudo-random number in `[0, 1)` (SplitMix64).
+    fn next_unit(&self) -> f64 {
+        let mut z = self
+            .rng
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn pick(&self) -> usize {
+        let weights = match &self.dynamic {
+            Some(dynamic) => {
+                let computed = dynamic(&self.stats());
+                match validate_weights(&computed, self.routes.len()) {
+                    Ok(()) => computed,
+                    Err(e) => {
+                        tracing::warn!("Ignoring dynamic weights: {e}");
+                        self.weights()
+                    }
+                }
+            }
+            None => self.weights(),
+        };
+
+        let total: f64 = weights.iter().sum();
+        let mut target = self.next_unit() * total;
+        for (index, weight) in weights.iter().enumerate() {
+            if target < *weight {
+                return index;
+            }
+            target -= weight;
+        }
+        // Floating point rounding can leave a sliver past the last weight
+        weights.iter().rposition(|w| *w > 0.0).unwrap_or(0)
+    }
+}
+
+fn validate_weights(weights: &[f64], routes: usize) -> Result<(), FlowError> {
+    if weights.len() != routes {
+        return Err(FlowError::NodeFailed(format!(
+            "Expected {routes} weights, got {}",
+            weights.len()
+        )));
+    }
+    if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+        return Err(FlowError::NodeFailed(
+            "Weights must be finite and non-negative".to_string(),
+        ));
+    }
+    if weights.iter().sum::<f64>() <= 0.0 {
+        return Err(FlowError::NodeFailed(
+            "At least one weight must be positive".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl Node for WeightedRouter {
+    /// Call the selected child node with the input.
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from the selected node.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let index = self.pick();
+        tracing::debug!("Routing to {} (route {index})", self.routes[index].name());
+
+        let started = Instant::now();
+        let result = self.routes[index].call(input).await;
+
+        let mut stats = self.stats.lock().unwrap();
+        let route = &mut stats[index];
+        route.calls += 1;
+        route.total_latency += started.elapsed();
+        if result.is_err() {
+            route.errors += 1;
+        }
+        result
+    }
+}
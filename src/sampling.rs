@@ -0,0 +1,272 @@
+//! Monte Carlo sampling over stochastic nodes.
+//!
+//! This module provides [`MonteCarlo`], a wrapper that runs a node several
+//! times concurrently with varied sampling parameters and aggregates the
+//! results into a single answer.
+
+use crate::budget::Budget;
+use crate::error::FlowError;
+use crate::node::Node;
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde_json::{json, Value};
+
+/// How sampled outputs are combined into a single value.
+pub enum Aggregation {
+    /// Average numeric outputs; fails if any sample is not a number.
+    Mean,
+    /// Pick the most frequent output, breaking ties by first occurrence.
+    Majority,
+    /// Let a judge node pick the answer; it receives the JSON array of samples.
+    Judge(Box<dyn Node>),
+    /// [`Aggregation::Mean`] if every sample is numeric, otherwise
+    /// [`Aggregation::Majority`].
+    Auto,
+}
+
+/// A node that samples a stochastic node multiple times and aggregates.
+///
+/// Each sample runs concurrently. When the input is a JSON object, every
+/// sample receives a copy with a distinct `seed` field and, if configured, a
+/// `temperature` field cycled from [`MonteCarlo::with_temperatures`]. A JSON
+/// array input is taken as chat messages and wrapped as
+/// `{"messages": [...]}` first, so that a
+/// [`ChatNode`](crate::llm::ChatNode) passes both fields on to its model as
+/// [`ChatRequest::seed`](crate::llm::ChatRequest::seed) and
+/// [`ChatRequest::temperature`](crate::llm::ChatRequest::temperature).
+///
+/// With a [`Budget`], each sample reserves `cost_per_sample` before it runs;
+/// sampling stops early once the budget is exhausted.
+///
+/// The output is `{"value": ..., "samples": [...], "agreement": n}` where
+/// `agreement` is the fraction of samples equal to the chosen value.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::sampling::{Aggregation, MonteCarlo};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// struct NoisyEstimate;
+///
+/// #[async_trait]
+/// impl Node for NoisyEstimate {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let seed = input["seed"].as_u64().unwrap_or(0) as f64;
+///         Ok(json!(10.0 + (seed - 1.5)))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let sampler = MonteCarlo::new(Box::new(NoisyEstimate), 4).with_aggregation(Aggregation::Mean);
+/// let result = sampler.call(json!({})).await?;
+/// assert_eq!(result["value"], 10.0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct MonteCarlo {
+    inner: Box<dyn Node>,
+    samples: usize,
+    temperatures: Vec<f32>,
+    aggregation: Aggregation,
+    pointer: Option<String>,
+    budget: Option<(Budget, f64)>,
+}
+
+impl MonteCarlo {
+    /// Create a sampler that runs `inner` `samples` times.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The stochastic node to sample
+    /// * `samples` - How many times to run it
+    pub fn new(inner: Box<dyn Node>, samples: usize) -> Self {
+        Self {
+            inner,
+            samples,
+            temperatures: Vec::new(),
+            aggregation: Aggregation::Auto,
+            pointer: None,
+            budget: None,
+        }
+    }
+
+    /// Cycle through the given temperatures across samples.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_trait::async_trait;
+    /// use rustyflow::llm::{ChatModel, ChatNode, ChatRequest, ChatResponse, Message};
+    /// use rustyflow::sampling::MonteCarlo;
+    /// use rustyflow::{FlowError, Node};
+    /// use serde_json::json;
+    ///
+    /// // Replies with the sampling parameters it was called with
+    /// struct Settings;
+    ///
+    /// #[async_trait]
+    /// impl ChatModel for Settings {
+    ///     async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, FlowError> {
+    ///         let reply = format!("{:?}/{:?}", request.seed, request.temperature);
+    ///         Ok(ChatResponse { message: Message::assistant(reply), usage: None })
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// let sampler = MonteCarlo::new(Box::new(ChatNode::new(Settings)), 3)
+    ///     .with_temperatures(vec![0.0, 1.0])
+    ///     .with_pointer("/message/content");
+    /// let result = sampler.call(json!([{"role": "user", "content": "Guess"}])).await?;
+    /// let samples: Vec<_> = result["samples"].as_array().unwrap().iter()
+    ///     .map(|sample| sample["message"]["content"].as_str().unwrap())
+    ///     .collect();
+    /// assert_eq!(samples, ["Some(0)/Some(0.0)", "Some(1)/Some(1.0)", "Some(2)/Some(0.0)"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_temperatures(mut self, temperatures: Vec<f32>) -> Self {
+        self.temperatures = temperatures;
+        self
+    }
+
+    /// Set how samples are combined. Defaults to [`Aggregation::Auto`].
+    pub fn with_aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Aggregate the value at a JSON pointer (e.g. `/answer`) instead of the
+    /// whole output.
+    pub fn with_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.pointer = Some(pointer.into());
+        self
+    }
+
+    /// Charge `cost_per_sample` to `budget` for every sample.
+    pub fn with_budget(mut self, budget: Budget, cost_per_sample: f64) -> Self {
+        self.budget = Some((budget, cost_per_sample));
+        self
+    }
+
+    fn sample_input(&self, input: &Value, index: usize) -> Value {
+        let mut sample = match input {
+            Value::Array(_) => json!({ "messages": input }),
+            other => other.clone(),
+        };
+        if let Value::Object(map) = &mut sample {
+            map.insert("seed".to_string(), json!(index));
+            if !self.temperatures.is_empty() {
+                let temperature = self.temperatures[index % self.temperatures.len()];
+                map.insert("temperature".to_string(), json!(temperature));
+            }
+        }
+        sample
+    }
+
+    /// Reserve budget for as many samples as allowed.
+    fn affordable_samples(&self) -> Result<usize, FlowError> {
+        let Some((budget, cost)) = &self.budget else {
+            return Ok(self.samples);
+        };
+        let mut granted = 0;
+        while granted < self.samples && budget.try_spend(*cost).is_ok() {
+            granted += 1;
+        }
+        if granted == 0 && self.samples > 0 {
+            return Err(FlowError::BudgetExceeded(format!(
+                "no budget left for a single sample ({} remaining)",
+                budget.remaining()
+            )));
+        }
+        Ok(granted)
+    }
+
+    async fn aggregate(&self, values: &[Value]) -> Result<Value, FlowError> {
+        match &self.aggregation {
+            Aggregation::Mean => mean(values),
+            Aggregation::Majority => Ok(majority(values)),
+            Aggregation::Judge(judge) => judge.call(Value::Array(values.to_vec())).await,
+            Aggregation::Auto if values.iter().all(Value::is_number) => mean(values),
+            Aggregation::Auto => Ok(majority(values)),
+        }
+    }
+}
+
+fn mean(values: &[Value]) -> Result<Value, FlowError> {
+    let numbers = values
+        .iter()
+        .map(|v| {
+            v.as_f64().ok_or_else(|| {
+                FlowError::NodeFailed(format!("Cannot average non-numeric sample {v}"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if numbers.is_empty() {
+        return Ok(Value::Null);
+    }
+    Ok(json!(numbers.iter().sum::<f64>() / numbers.len() as f64))
+}
+
+fn majority(values: &[Value]) -> Value {
+    let mut best: Option<(&Value, usize)> = None;
+    for candidate in values {
+        let count = values.iter().filter(|v| *v == candidate).count();
+        if best.map_or(true, |(_, best_count)| count > best_count) {
+            best = Some((candidate, count));
+        }
+    }
+    best.map(|(value, _)| value.clone()).unwrap_or(Value::Null)
+}
+
+#[async_trait]
+impl Node for MonteCarlo {
+    /// Sample the wrapped node and aggregate the outputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::BudgetExceeded` if the budget cannot cover a single
+    /// sample, `FlowError::NodeFailed` if a pointer is missing or values cannot
+    /// be averaged, or propagates the first sample error.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let count = self.affordable_samples()?;
+        let futures: Vec<_> = (0..count)
+            .map(|index| self.inner.call(self.sample_input(&input, index)))
+            .collect();
+        let results = join_all(futures).await;
+
+        let mut samples = Vec::with_capacity(count);
+        for result in results {
+            samples.push(result?);
+        }
+
+        let values = match &self.pointer {
+            Some(pointer) => samples
+                .iter()
+                .map(|sample| {
+                    sample.pointer(pointer).cloned().ok_or_else(|| {
+                        FlowError::NodeFailed(format!("Sample is missing field at {pointer}"))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => samples.clone(),
+        };
+
+        let value = self.aggregate(&values).await?;
+        let agreement = if values.is_empty() {
+            0.0
+        } else {
+            values.iter().filter(|v| **v == value).count() as f64 / values.len() as f64
+        };
+
+        Ok(json!({
+            "value": value,
+            "samples": samples,
+            "agreement": agreement,
+        }))
+    }
+}
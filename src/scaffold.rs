@@ -0,0 +1,236 @@
+//! Project templates for new RustyFlow applications.
+//!
+//! [`Scaffold`] generates a ready-to-build Cargo project for one of the
+//! common flow [`Pattern`]s, with typed [`Tool`](crate::Tool)s, stand-in
+//! models to swap for real providers, and integration tests that run the
+//! flow end to end. The `rustyflow new` command is a thin wrapper around it.
+
+use crate::error::FlowError;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The kind of application to generate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    /// Retrieval-augmented generation over an in-memory vector store.
+    Rag,
+    /// A ReAct agent with a typed calculator tool.
+    React,
+    /// A batch extract-transform-load pipeline.
+    BatchEtl,
+}
+
+impl Pattern {
+    /// Every pattern, in the order they are listed to users.
+    pub const ALL: [Pattern; 3] = [Pattern::Rag, Pattern::React, Pattern::BatchEtl];
+
+    /// The name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Pattern::Rag => "rag",
+            Pattern::React => "react",
+            Pattern::BatchEtl => "batch-etl",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Pattern::Rag => "retrieval-augmented generation flow",
+            Pattern::React => "ReAct agent",
+            Pattern::BatchEtl => "batch ETL pipeline",
+        }
+    }
+
+    /// The pattern's own source files, as `(path, template)` pairs.
+    fn templates(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Pattern::Rag => &[
+                ("src/lib.rs", include_str!("scaffold/rag/lib.rs.tmpl")),
+                ("src/main.rs", include_str!("scaffold/rag/main.rs.tmpl")),
+                ("src/model.rs", include_str!("scaffold/rag/model.rs.tmpl")),
+                ("src/tools.rs", include_str!("scaffold/rag/tools.rs.tmpl")),
+                (
+                    "tests/flow.rs",
+                    include_str!("scaffold/rag/flow_test.rs.tmpl"),
+                ),
+            ],
+            Pattern::React => &[
+                ("src/lib.rs", include_str!("scaffold/react/lib.rs.tmpl")),
+                ("src/main.rs", include_str!("scaffold/react/main.rs.tmpl")),
+                ("src/model.rs", include_str!("scaffold/react/model.rs.tmpl")),
+                ("src/tools.rs", include_str!("scaffold/react/tools.rs.tmpl")),
+                (
+                    "tests/flow.rs",
+                    include_str!("scaffold/react/flow_test.rs.tmpl"),
+                ),
+            ],
+            Pattern::BatchEtl => &[
+                ("src/lib.rs", include_str!("scaffold/batch_etl/lib.rs.tmpl")),
+                (
+                    "src/main.rs",
+                    include_str!("scaffold/batch_etl/main.rs.tmpl"),
+                ),
+                (
+                    "src/tools.rs",
+                    include_str!("scaffold/batch_etl/tools.rs.tmpl"),
+                ),
+                (
+                    "tests/flow.rs",
+                    include_str!("scaffold/batch_etl/flow_test.rs.tmpl"),
+                ),
+            ],
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = FlowError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "rag" => Ok(Pattern::Rag),
+            "react" | "agent" => Ok(Pattern::React),
+            "batch-etl" | "batch" | "etl" => Ok(Pattern::BatchEtl),
+            other => {
+                let names: Vec<&str> = Pattern::ALL.iter().map(|p| p.name()).collect();
+                Err(FlowError::NodeFailed(format!(
+                    "Unknown pattern '{other}', expected one of {}",
+                    names.join(", ")
+                )))
+            }
+        }
+    }
+}
+
+/// Files shared by every pattern.
+const COMMON: &[(&str, &str)] = &[
+    ("Cargo.toml", include_str!("scaffold/Cargo.toml.tmpl")),
+    ("README.md", include_str!("scaffold/README.md.tmpl")),
+    (".gitignore", include_str!("scaffold/gitignore.tmpl")),
+];
+
+/// A project generator.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::scaffold::{Pattern, Scaffold};
+/// use rustyflow::FlowError;
+///
+/// # fn main() -> Result<(), FlowError> {
+/// let scaffold = Scaffold::new("support-bot", "rag".parse()?)?;
+/// let files = scaffold.files();
+///
+/// let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+/// assert!(paths.contains(&"src/tools.rs"));
+/// assert!(paths.contains(&"tests/flow.rs"));
+///
+/// let (_, main) = files.iter().find(|(path, _)| path == "src/main.rs").unwrap();
+/// assert!(main.contains("use support_bot::"));
+///
+/// assert!(Scaffold::new("not a crate", Pattern::React).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scaffold {
+    name: String,
+    pattern: Pattern,
+    rustyflow_path: Option<PathBuf>,
+}
+
+impl Scaffold {
+    /// Describe a project.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The package name, which is also the directory name
+    /// * `pattern` - The kind of application to generate
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if `name` is not a valid package name.
+    pub fn new(name: impl Into<String>, pattern: Pattern) -> Result<Self, FlowError> {
+        let name = name.into();
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(FlowError::NodeFailed(format!(
+                "'{name}' is not a valid package name: use letters, digits, '-' and '_', \
+                 starting with a letter"
+            )));
+        }
+        Ok(Self {
+            name,
+            pattern,
+            rustyflow_path: None,
+        })
+    }
+
+    /// Depend on a local checkout of RustyFlow instead of the published
+    /// crate.
+    pub fn with_rustyflow_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.rustyflow_path = Some(path.into());
+        self
+    }
+
+    /// The generated files, as paths relative to the project directory and
+    /// their contents.
+    pub fn files(&self) -> Vec<(String, String)> {
+        let dependency = match &self.rustyflow_path {
+            Some(path) => format!("{{ path = {:?} }}", path.display().to_string()),
+            None => format!("\"{}\"", env!("CARGO_PKG_VERSION")),
+        };
+        let crate_name = self.name.replace('-', "_");
+        COMMON
+            .iter()
+            .chain(self.pattern.templates())
+            .map(|(path, template)| {
+                let contents = template
+                    .replace("__NAME__", &self.name)
+                    .replace("__CRATE__", &crate_name)
+                    .replace("__PATTERN__", self.pattern.description())
+                    .replace("__RUSTYFLOW__", &dependency);
+                (path.to_string(), contents)
+            })
+            .collect()
+    }
+
+    /// Write the project into `dir`, creating it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if `dir` exists and is not empty, or
+    /// a file cannot be written.
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<(), FlowError> {
+        let dir = dir.as_ref();
+        let io_error = |e: std::io::Error| {
+            FlowError::NodeFailed(format!("Cannot write {}: {e}", dir.display()))
+        };
+        if let Ok(mut entries) = std::fs::read_dir(dir) {
+            if entries.next().is_some() {
+                return Err(FlowError::NodeFailed(format!(
+                    "{} already exists and is not empty",
+                    dir.display()
+                )));
+            }
+        }
+        for (path, contents) in self.files() {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(io_error)?;
+            }
+            std::fs::write(&path, contents).map_err(io_error)?;
+        }
+        Ok(())
+    }
+}
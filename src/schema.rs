@@ -0,0 +1,562 @@
+//! Validating JSON values against JSON Schemas.
+//!
+//! [`validate`] checks a value against the subset of JSON Schema that
+//! `schemars` generates for Rust types, and reports every violation as a
+//! [`FieldError`] with the JSON pointer of the offending field. The
+//! supported keywords are `type`, `properties`, `required`,
+//! `additionalProperties`, `items`, `enum`, `const`, `minimum`, `maximum`,
+//! `minLength`, `maxLength`, `minItems`, `maxItems`, `allOf`, `anyOf`,
+//! `oneOf`, and local `$ref`s into `$defs` or `definitions`. Other keywords
+//! are ignored.
+//!
+//! [`ValidateNode`] runs the same check inside a flow, and [`compatible`]
+//! compares two schemas, so that
+//! [`Flow::with_contracts`](crate::flow::Flow::with_contracts) can find
+//! nodes whose output does not fit the next node's input before any run.
+
+use crate::error::FlowError;
+use crate::node::{Node, SchemaProvider};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// One violation of a schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// The JSON pointer of the offending value, `""` for the root.
+    pub path: String,
+    /// What is wrong with it.
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Check `value` against `schema`.
+///
+/// # Returns
+///
+/// Every violation found, or an empty list if the value is valid.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::schema::validate;
+/// use serde_json::json;
+///
+/// let schema = json!({
+///     "type": "object",
+///     "properties": {"a": {"type": "integer"}, "b": {"type": "integer"}},
+///     "required": ["a", "b"]
+/// });
+///
+/// assert!(validate(&schema, &json!({"a": 1, "b": 2})).is_empty());
+///
+/// let errors = validate(&schema, &json!({"a": "one"}));
+/// assert_eq!(errors[0].path, "/a");
+/// assert_eq!(errors[0].message, "expected integer, got string");
+/// assert_eq!(errors[1].message, "missing required field 'b'");
+/// ```
+pub fn validate(schema: &Value, value: &Value) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    check(schema, schema, value, "", &mut errors);
+    errors
+}
+
+fn check(root: &Value, schema: &Value, value: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    let Some(schema) = schema.as_object() else {
+        // `true` accepts anything, `false` nothing
+        if schema == &Value::Bool(false) {
+            push(errors, path, "no value is allowed here".to_string());
+        }
+        return;
+    };
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match resolve(root, reference) {
+            Some(target) => check(root, target, value, path, errors),
+            None => push(
+                errors,
+                path,
+                format!("unresolvable schema reference '{reference}'"),
+            ),
+        }
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+            let message = format!("expected {}, got {}", types.join(" or "), type_name(value));
+            push(errors, path, message);
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            push(
+                errors,
+                path,
+                format!("expected one of {}", allowed.join(", ")),
+            );
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            push(errors, path, format!("expected {constant}"));
+        }
+    }
+
+    check_bounds(schema, value, path, errors);
+    check_combinators(root, schema, value, path, errors);
+
+    match value {
+        Value::Object(object) => check_object(root, schema, object, path, errors),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(root, item_schema, item, &format!("{path}/{index}"), errors);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_object(
+    root: &Value,
+    schema: &Map<String, Value>,
+    object: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<FieldError>,
+) {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (key, field) in object {
+        let field_path = format!("{path}/{}", escape(key));
+        match properties.and_then(|properties| properties.get(key)) {
+            Some(field_schema) => check(root, field_schema, field, &field_path, errors),
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    push(errors, &field_path, format!("unknown field '{key}'"))
+                }
+                Some(extra) if extra.is_object() => check(root, extra, field, &field_path, errors),
+                _ => {}
+            },
+        }
+    }
+    if let Some(required) = schema.get("required").and_then(Value::as_array) {
+        for key in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(key) {
+                push(errors, path, format!("missing required field '{key}'"));
+            }
+        }
+    }
+}
+
+fn check_bounds(
+    schema: &Map<String, Value>,
+    value: &Value,
+    path: &str,
+    errors: &mut Vec<FieldError>,
+) {
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = bound("minimum").filter(|&minimum| number < minimum) {
+            push(errors, path, format!("must be at least {minimum}"));
+        }
+        if let Some(maximum) = bound("maximum").filter(|&maximum| number > maximum) {
+            push(errors, path, format!("must be at most {maximum}"));
+        }
+    }
+    let length = match value {
+        Value::String(text) => Some((text.chars().count(), "minLength", "maxLength", "characters")),
+        Value::Array(items) => Some((items.len(), "minItems", "maxItems", "items")),
+        _ => None,
+    };
+    if let Some((length, min_keyword, max_keyword, unit)) = length {
+        let length = length as f64;
+        if let Some(min) = bound(min_keyword).filter(|&min| length < min) {
+            push(errors, path, format!("must have at least {min} {unit}"));
+        }
+        if let Some(max) = bound(max_keyword).filter(|&max| length > max) {
+            push(errors, path, format!("must have at most {max} {unit}"));
+        }
+    }
+}
+
+fn check_combinators(
+    root: &Value,
+    schema: &Map<String, Value>,
+    value: &Value,
+    path: &str,
+    errors: &mut Vec<FieldError>,
+) {
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        for sub in all {
+            check(root, sub, value, path, errors);
+        }
+    }
+    let matching = |subs: &Vec<Value>| {
+        subs.iter()
+            .filter(|sub| {
+                let mut sub_errors = Vec::new();
+                check(root, sub, value, path, &mut sub_errors);
+                sub_errors.is_empty()
+            })
+            .count()
+    };
+    if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+        if matching(any) == 0 {
+            push(
+                errors,
+                path,
+                "does not match any allowed schema".to_string(),
+            );
+        }
+    }
+    if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+        if matching(one) != 1 {
+            push(
+                errors,
+                path,
+                "must match exactly one allowed schema".to_string(),
+            );
+        }
+    }
+}
+
+/// Check that every value matching `output` also matches `input`, so that
+/// a node producing `output` can feed a node accepting `input`.
+///
+/// The comparison is structural and conservative: it reports types that
+/// `input` does not accept, fields `input` requires that `output` does not
+/// guarantee, fields `input` rejects, and `enum` or `const` values outside
+/// `input`'s, recursing into the properties and items both schemas
+/// describe. What `output` leaves unspecified is assumed to fit. Array
+/// items are reported under a `*` path segment.
+///
+/// # Returns
+///
+/// Every mismatch found, or an empty list if the schemas fit.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::schema::compatible;
+/// use serde_json::json;
+///
+/// let output = json!({
+///     "type": "object",
+///     "properties": {"id": {"type": "integer"}, "tags": {"type": "array", "items": {"type": "string"}}},
+///     "required": ["id"]
+/// });
+/// let input = json!({
+///     "type": "object",
+///     "properties": {"id": {"type": "number"}, "tags": {"type": "array", "items": {"type": "integer"}}},
+///     "required": ["id", "name"]
+/// });
+///
+/// let errors = compatible(&output, &input);
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors[0].message, "required field 'name' may be missing");
+/// assert_eq!(errors[1].path, "/tags/*");
+/// assert_eq!(errors[1].message, "may be string, but integer is expected");
+/// ```
+pub fn compatible(output: &Value, input: &Value) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let roots = Roots { output, input };
+    compare(&roots, output, input, "", &mut errors);
+    errors
+}
+
+/// The whole schemas being compared, for resolving references.
+struct Roots<'a> {
+    output: &'a Value,
+    input: &'a Value,
+}
+
+fn compare(roots: &Roots, output: &Value, input: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    let output = dereference(roots.output, output);
+    let input = dereference(roots.input, input);
+    let Some(input) = input.as_object() else {
+        if input == &Value::Bool(false) {
+            push(errors, path, "no value is accepted here".to_string());
+        }
+        return;
+    };
+    // Anything may come from an output schema of `true`; assume it fits
+    let Some(output) = output.as_object() else {
+        return;
+    };
+
+    if let Some(all) = input.get("allOf").and_then(Value::as_array) {
+        for sub in all {
+            compare(roots, &Value::Object(output.clone()), sub, path, errors);
+        }
+    }
+
+    let produced = types(output);
+    let accepted = types(input);
+    if !produced.is_empty() && !accepted.is_empty() {
+        let unaccepted: Vec<&str> = produced
+            .iter()
+            .copied()
+            .filter(|produced| !accepted.iter().any(|accepted| covers(accepted, produced)))
+            .collect();
+        if !unaccepted.is_empty() {
+            let message = format!(
+                "may be {}, but {} is expected",
+                unaccepted.join(" or "),
+                accepted.join(" or ")
+            );
+            push(errors, path, message);
+            return;
+        }
+    }
+
+    let values = output
+        .get("enum")
+        .and_then(Value::as_array)
+        .cloned()
+        .or_else(|| output.get("const").map(|constant| vec![constant.clone()]));
+    let allowed = input
+        .get("enum")
+        .and_then(Value::as_array)
+        .cloned()
+        .or_else(|| input.get("const").map(|constant| vec![constant.clone()]));
+    if let (Some(values), Some(allowed)) = (values, allowed) {
+        for value in values.iter().filter(|value| !allowed.contains(value)) {
+            push(
+                errors,
+                path,
+                format!("may be {value}, which is not accepted"),
+            );
+        }
+    }
+
+    compare_objects(roots, output, input, path, errors);
+    if let (Some(output_items), Some(input_items)) = (output.get("items"), input.get("items")) {
+        compare(
+            roots,
+            output_items,
+            input_items,
+            &format!("{path}/*"),
+            errors,
+        );
+    }
+}
+
+fn compare_objects(
+    roots: &Roots,
+    output: &Map<String, Value>,
+    input: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<FieldError>,
+) {
+    let guaranteed = |key: &str| {
+        output
+            .get("required")
+            .and_then(Value::as_array)
+            .is_some_and(|required| required.iter().any(|field| field == key))
+    };
+    if let Some(required) = input.get("required").and_then(Value::as_array) {
+        for key in required.iter().filter_map(Value::as_str) {
+            if !guaranteed(key) {
+                push(
+                    errors,
+                    path,
+                    format!("required field '{key}' may be missing"),
+                );
+            }
+        }
+    }
+
+    let produced = output.get("properties").and_then(Value::as_object);
+    let accepted = input.get("properties").and_then(Value::as_object);
+    for (key, field) in produced.into_iter().flatten() {
+        let field_path = format!("{path}/{}", escape(key));
+        match accepted.and_then(|accepted| accepted.get(key)) {
+            Some(accepted) => compare(roots, field, accepted, &field_path, errors),
+            None => match input.get("additionalProperties") {
+                Some(Value::Bool(false)) => push(
+                    errors,
+                    &field_path,
+                    format!("field '{key}' is not accepted"),
+                ),
+                Some(extra) if extra.is_object() => {
+                    compare(roots, field, extra, &field_path, errors)
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+/// Follow the local references of `schema` within `root`, a few levels deep.
+fn dereference<'a>(root: &'a Value, mut schema: &'a Value) -> &'a Value {
+    for _ in 0..8 {
+        match schema
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| resolve(root, reference))
+        {
+            Some(target) => schema = target,
+            None => break,
+        }
+    }
+    schema
+}
+
+/// The type names a schema allows, empty if it does not say.
+fn types(schema: &Map<String, Value>) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns `true` if every value of type `produced` has type `accepted`.
+fn covers(accepted: &str, produced: &str) -> bool {
+    accepted == produced || (accepted == "number" && produced == "integer")
+}
+
+/// A node that passes its input on unchanged if it matches a JSON Schema,
+/// and fails otherwise.
+///
+/// Put one between nodes whose data shape matters, such as before a node
+/// that writes to a database, so that a mismatch fails the flow with every
+/// violation and its path instead of surfacing later. The schema is also
+/// the node's input and output [`SchemaProvider`] schema, so
+/// [`Flow::with_contracts`](crate::flow::Flow::with_contracts) checks it
+/// against its neighbours.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::schema::ValidateNode;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let node = ValidateNode::new(json!({
+///     "type": "object",
+///     "properties": {"email": {"type": "string"}, "age": {"type": "integer", "minimum": 0}},
+///     "required": ["email"]
+/// }));
+///
+/// let valid = json!({"email": "ada@example.com", "age": 36});
+/// assert_eq!(node.call(valid.clone()).await?, valid);
+///
+/// let Err(FlowError::Validation(errors)) = node.call(json!({"age": -1})).await else {
+///     panic!("expected a validation error");
+/// };
+/// assert_eq!(errors[0].to_string(), "/age: must be at least 0");
+/// assert_eq!(errors[1].to_string(), "missing required field 'email'");
+/// # Ok(())
+/// # }
+/// ```
+pub struct ValidateNode {
+    schema: Value,
+}
+
+impl ValidateNode {
+    /// Create a node that checks values against `schema`.
+    pub fn new(schema: Value) -> Self {
+        Self { schema }
+    }
+
+    /// Create a node that checks values against the JSON Schema of `T`.
+    pub fn of<T: JsonSchema>() -> Self {
+        Self::new(schemars::schema_for!(T).to_value())
+    }
+}
+
+#[async_trait]
+impl Node for ValidateNode {
+    /// Pass the input on if it matches the schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::Validation` with every violation if it does not.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let errors = validate(&self.schema, &input);
+        if errors.is_empty() {
+            Ok(input)
+        } else {
+            Err(FlowError::Validation(errors))
+        }
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        Some(self)
+    }
+}
+
+impl SchemaProvider for ValidateNode {
+    fn input_schema(&self) -> Option<Value> {
+        Some(self.schema.clone())
+    }
+
+    fn output_schema(&self) -> Option<Value> {
+        Some(self.schema.clone())
+    }
+}
+
+/// Follow a local reference such as `#/$defs/Point`.
+fn resolve<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    root.pointer(reference.strip_prefix('#')?)
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Escape a key for use in a JSON pointer.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn push(errors: &mut Vec<FieldError>, path: &str, message: String) {
+    errors.push(FieldError {
+        path: path.to_string(),
+        message,
+    });
+}
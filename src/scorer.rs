@@ -0,0 +1,645 @@
+//! Heuristic scorers for evaluating outputs without a language model.
+//!
+//! A [`Scorer`] compares an output with an expected value and returns a
+//! score between `0.0` and `1.0`. The scorers here are cheap and
+//! deterministic, so evals can run them on every example and flows can gate
+//! on them at run time with [`QualityGate`], which retries or escalates
+//! outputs that fall short, leaving
+//! [`Judge`](crate::judge::Judge) for what they cannot measure.
+//!
+//! | Scorer | Measures |
+//! |--------|----------|
+//! | [`ExactMatch`] | Equality, optionally ignoring case and whitespace |
+//! | [`RegexMatch`] | Whether the output matches a pattern |
+//! | [`Rouge`] | N-gram or longest-common-subsequence recall and precision (F1) |
+//! | [`Bleu`] | N-gram precision with a brevity penalty |
+//! | [`EmbeddingSimilarity`] | Cosine similarity of embeddings |
+//! | [`JsonFieldAccuracy`] | The share of expected JSON fields the output gets right |
+//!
+//! Non-string values are compared as their JSON text, except by
+//! [`ExactMatch`] and [`JsonFieldAccuracy`], which compare JSON values.
+
+use crate::embeddings::{cosine_similarity, Embedder};
+use crate::error::FlowError;
+use crate::node::Node;
+use crate::report;
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Scores an output against an expected value.
+#[async_trait]
+pub trait Scorer: Send + Sync {
+    /// Score `output` against `expected`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - A score between `0.0` (no match) and `1.0` (a perfect
+    ///   match)
+    /// * `Err(FlowError)` - An error if the score could not be computed,
+    ///   such as a failed embedding call
+    async fn score(&self, output: &Value, expected: &Value) -> Result<f64, FlowError>;
+
+    /// A short name for reports, such as `rouge_l`.
+    fn name(&self) -> &str;
+}
+
+/// The text of a value: strings as is, anything else as JSON.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Lowercase words, split on anything that is not a letter or digit.
+fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn ngrams(tokens: &[String], n: usize) -> HashMap<&[String], usize> {
+    let mut counts = HashMap::new();
+    if n > 0 {
+        for gram in tokens.windows(n) {
+            *counts.entry(gram).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// N-grams of `candidate` also in `reference`, counting each reference
+/// n-gram at most as often as it occurs there.
+fn clipped_overlap(
+    candidate: &HashMap<&[String], usize>,
+    reference: &HashMap<&[String], usize>,
+) -> usize {
+    candidate
+        .iter()
+        .map(|(gram, count)| (*count).min(reference.get(gram).copied().unwrap_or(0)))
+        .sum()
+}
+
+fn f1(overlap: usize, candidate: usize, reference: usize) -> f64 {
+    if overlap == 0 {
+        return 0.0;
+    }
+    let precision = overlap as f64 / candidate as f64;
+    let recall = overlap as f64 / reference as f64;
+    2.0 * precision * recall / (precision + recall)
+}
+
+/// Scores `1.0` if the output equals the expected value and `0.0`
+/// otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct ExactMatch {
+    normalize: bool,
+}
+
+impl ExactMatch {
+    /// Compare values exactly.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignore case, surrounding whitespace, and runs of whitespace when
+    /// comparing strings.
+    pub fn with_normalization(mut self) -> Self {
+        self.normalize = true;
+        self
+    }
+}
+
+#[async_trait]
+impl Scorer for ExactMatch {
+    async fn score(&self, output: &Value, expected: &Value) -> Result<f64, FlowError> {
+        let matched = match (output, expected) {
+            (Value::String(a), Value::String(b)) if self.normalize => {
+                let normalize = |s: &str| {
+                    s.split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .to_lowercase()
+                };
+                normalize(a) == normalize(b)
+            }
+            _ => output == expected,
+        };
+        Ok(if matched { 1.0 } else { 0.0 })
+    }
+
+    fn name(&self) -> &str {
+        "exact_match"
+    }
+}
+
+/// Scores `1.0` if the output contains a match of a regular expression and
+/// `0.0` otherwise. The expected value is ignored.
+#[derive(Debug, Clone)]
+pub struct RegexMatch {
+    regex: Regex,
+}
+
+impl RegexMatch {
+    /// Create a scorer for `pattern`, in Rust `regex` syntax. Anchor it with
+    /// `^...$` to require a full match.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the pattern is invalid.
+    pub fn new(pattern: &str) -> Result<Self, FlowError> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid pattern '{pattern}': {e}")))?;
+        Ok(Self { regex })
+    }
+}
+
+#[async_trait]
+impl Scorer for RegexMatch {
+    async fn score(&self, output: &Value, _expected: &Value) -> Result<f64, FlowError> {
+        Ok(if self.regex.is_match(&text(output)) {
+            1.0
+        } else {
+            0.0
+        })
+    }
+
+    fn name(&self) -> &str {
+        "regex"
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RougeKind {
+    N(usize),
+    L,
+}
+
+/// ROUGE F1 between the output and the expected text, on lowercase words.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::scorer::{Rouge, Scorer};
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), rustyflow::FlowError> {
+/// let score = Rouge::l()
+///     .score(&json!("the cat sat on the mat"), &json!("a cat sat on a mat"))
+///     .await?;
+/// assert!((score - 4.0 / 6.0).abs() < 1e-9);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rouge {
+    kind: RougeKind,
+    name: String,
+}
+
+impl Rouge {
+    /// ROUGE-N: overlap of word n-grams, such as ROUGE-1 for single words.
+    pub fn n(n: usize) -> Self {
+        let n = n.max(1);
+        Self {
+            kind: RougeKind::N(n),
+            name: format!("rouge_{n}"),
+        }
+    }
+
+    /// ROUGE-L: the longest common subsequence of words, which rewards
+    /// words in the same order without requiring them to be adjacent.
+    pub fn l() -> Self {
+        Self {
+            kind: RougeKind::L,
+            name: "rouge_l".to_string(),
+        }
+    }
+}
+
+fn longest_common_subsequence(a: &[String], b: &[String]) -> usize {
+    let mut previous = vec![0; b.len() + 1];
+    for x in a {
+        let mut current = vec![0; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            current[j + 1] = if x == y {
+                previous[j] + 1
+            } else {
+                current[j].max(previous[j + 1])
+            };
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[async_trait]
+impl Scorer for Rouge {
+    async fn score(&self, output: &Value, expected: &Value) -> Result<f64, FlowError> {
+        let candidate = tokens(&text(output));
+        let reference = tokens(&text(expected));
+        Ok(match self.kind {
+            RougeKind::N(n) => {
+                let candidate = ngrams(&candidate, n);
+                let reference = ngrams(&reference, n);
+                f1(
+                    clipped_overlap(&candidate, &reference),
+                    candidate.values().sum(),
+                    reference.values().sum(),
+                )
+            }
+            RougeKind::L => f1(
+                longest_common_subsequence(&candidate, &reference),
+                candidate.len(),
+                reference.len(),
+            ),
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Sentence-level BLEU of the output against the expected text, on
+/// lowercase words.
+///
+/// Precisions of 2-grams and longer are smoothed by adding one to their
+/// counts, so short outputs do not score zero for lacking a 4-gram match.
+#[derive(Debug, Clone)]
+pub struct Bleu {
+    max_n: usize,
+}
+
+impl Default for Bleu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bleu {
+    /// BLEU up to 4-grams.
+    pub fn new() -> Self {
+        Self { max_n: 4 }
+    }
+
+    /// Use n-grams up to `max_n` words long.
+    pub fn with_max_n(mut self, max_n: usize) -> Self {
+        self.max_n = max_n.max(1);
+        self
+    }
+}
+
+#[async_trait]
+impl Scorer for Bleu {
+    async fn score(&self, output: &Value, expected: &Value) -> Result<f64, FlowError> {
+        let candidate = tokens(&text(output));
+        let reference = tokens(&text(expected));
+        if candidate.is_empty() || reference.is_empty() {
+            return Ok(0.0);
+        }
+
+        let mut log_precision = 0.0;
+        for n in 1..=self.max_n {
+            let candidate_grams = ngrams(&candidate, n);
+            let overlap = clipped_overlap(&candidate_grams, &ngrams(&reference, n));
+            let total: usize = candidate_grams.values().sum();
+            let precision = if n == 1 {
+                overlap as f64 / total as f64
+            } else {
+                (overlap as f64 + 1.0) / (total as f64 + 1.0)
+            };
+            if precision == 0.0 {
+                return Ok(0.0);
+            }
+            log_precision += precision.ln() / self.max_n as f64;
+        }
+
+        let brevity = if candidate.len() < reference.len() {
+            (1.0 - reference.len() as f64 / candidate.len() as f64).exp()
+        } else {
+            1.0
+        };
+        Ok(brevity * log_precision.exp())
+    }
+
+    fn name(&self) -> &str {
+        "bleu"
+    }
+}
+
+/// Cosine similarity of the embeddings of the output and the expected text,
+/// with negative similarities scored `0.0`.
+pub struct EmbeddingSimilarity<E: Embedder> {
+    embedder: E,
+}
+
+impl<E: Embedder> EmbeddingSimilarity<E> {
+    /// Compare texts with vectors from `embedder`.
+    pub fn new(embedder: E) -> Self {
+        Self { embedder }
+    }
+}
+
+#[async_trait]
+impl<E: Embedder> Scorer for EmbeddingSimilarity<E> {
+    async fn score(&self, output: &Value, expected: &Value) -> Result<f64, FlowError> {
+        let vectors = self.embedder.embed(&[text(output), text(expected)]).await?;
+        match vectors.as_slice() {
+            [a, b] => Ok(f64::from(cosine_similarity(a, b)).max(0.0)),
+            _ => Err(FlowError::NodeFailed(format!(
+                "Embedder returned {} vectors for 2 texts",
+                vectors.len()
+            ))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "embedding_similarity"
+    }
+}
+
+/// The share of the expected value's fields that the output has with the
+/// same value.
+///
+/// Fields are the leaves of the expected JSON, addressed by JSON pointer,
+/// such as `/customer/name` or `/items/0/sku`, unless
+/// [`with_fields`](JsonFieldAccuracy::with_fields) lists the ones to check.
+/// An expected value without fields scores as an exact match.
+#[derive(Debug, Clone, Default)]
+pub struct JsonFieldAccuracy {
+    fields: Option<Vec<String>>,
+}
+
+impl JsonFieldAccuracy {
+    /// Check every leaf of the expected value.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check only the fields at these JSON pointers.
+    pub fn with_fields(mut self, pointers: Vec<String>) -> Self {
+        self.fields = Some(pointers);
+        self
+    }
+}
+
+fn leaves(value: &Value, path: &mut String, out: &mut Vec<String>) {
+    let children: Vec<(String, &Value)> = match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, child)| (key.clone(), child))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, child)| (i.to_string(), child))
+            .collect(),
+        _ => {
+            out.push(path.clone());
+            return;
+        }
+    };
+    for (key, child) in children {
+        let len = path.len();
+        path.push('/');
+        path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+        leaves(child, path, out);
+        path.truncate(len);
+    }
+}
+
+#[async_trait]
+impl Scorer for JsonFieldAccuracy {
+    async fn score(&self, output: &Value, expected: &Value) -> Result<f64, FlowError> {
+        let fields = match &self.fields {
+            Some(fields) => fields.clone(),
+            None => {
+                let mut fields = Vec::new();
+                leaves(expected, &mut String::new(), &mut fields);
+                fields
+            }
+        };
+        // Only the root: an empty object or array, or a scalar
+        if fields.is_empty() || fields == [""] {
+            return Ok(if output == expected { 1.0 } else { 0.0 });
+        }
+        let correct = fields
+            .iter()
+            .filter(|pointer| {
+                let expected = expected.pointer(pointer);
+                expected.is_some() && output.pointer(pointer) == expected
+            })
+            .count();
+        Ok(correct as f64 / fields.len() as f64)
+    }
+
+    fn name(&self) -> &str {
+        "json_field_accuracy"
+    }
+}
+
+/// A node that checks its input with scorers and routes what falls short.
+///
+/// Place it after the node whose output should be checked. The input is
+/// scored, or the value at [`with_output_pointer`](QualityGate::with_output_pointer)
+/// in it, against the [expected value](QualityGate::with_expected) or the
+/// value at [`with_expected_pointer`](QualityGate::with_expected_pointer).
+/// It passes if every scorer meets its threshold, and is then returned
+/// unchanged.
+///
+/// A failing value goes to the [retry](QualityGate::with_retry) node, whose
+/// output is scored again, up to its number of attempts; then to the
+/// [escalation](QualityGate::with_escalation) node, whose output is returned
+/// without scoring, such as a stronger model or a human review queue.
+/// Without either, the gate fails the flow.
+///
+/// Each score is recorded with [`record_score`](crate::report::record_score)
+/// under the scorer's name, and each retry with
+/// [`record_retry`](crate::report::record_retry), so traced executions show
+/// them in the gate's [`NodeReport`](crate::report::NodeReport).
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::scorer::{QualityGate, RegexMatch, Rouge};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// /// Rewrites the summary to stick to the reference.
+/// struct Revise;
+///
+/// #[async_trait]
+/// impl Node for Revise {
+///     async fn call(&self, mut input: Value) -> Result<Value, FlowError> {
+///         input["summary"] = input["reference"].clone();
+///         Ok(input)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let gate = QualityGate::new(Box::new(Rouge::n(1)), 0.5)
+///     .with_output_pointer("/summary")
+///     .with_expected_pointer("/reference")
+///     .with_retry(Box::new(Revise), 2);
+///
+/// let flow = Flow::new(vec![Box::new(gate)]);
+/// let draft = json!({"summary": "Costs fell", "reference": "Revenue grew by 10%"});
+/// let (result, report) = flow.execute_traced(draft).await;
+/// assert_eq!(result?["summary"], "Revenue grew by 10%");
+/// assert_eq!(report.nodes[0].scores["rouge_1"], 1.0);
+/// assert_eq!(report.nodes[0].retries, 1);
+///
+/// // Without a retry or escalation node, a failing value stops the flow
+/// let gate = QualityGate::new(Box::new(RegexMatch::new(r"#\d{6}")?), 1.0);
+/// assert!(gate.call(json!("Your order #123456 has shipped")).await.is_ok());
+/// assert!(gate.call(json!("Your order has shipped")).await.is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub struct QualityGate {
+    scorers: Vec<(Box<dyn Scorer>, f64)>,
+    expected: Value,
+    output_pointer: Option<String>,
+    expected_pointer: Option<String>,
+    retry: Option<(Box<dyn Node>, usize)>,
+    escalation: Option<Box<dyn Node>>,
+}
+
+impl QualityGate {
+    /// Create a gate that passes inputs scoring at least `threshold`.
+    ///
+    /// # Arguments
+    ///
+    /// * `scorer` - Scores the input
+    /// * `threshold` - The minimum passing score, between `0.0` and `1.0`
+    pub fn new(scorer: Box<dyn Scorer>, threshold: f64) -> Self {
+        Self {
+            scorers: vec![(scorer, threshold)],
+            expected: Value::Null,
+            output_pointer: None,
+            expected_pointer: None,
+            retry: None,
+            escalation: None,
+        }
+    }
+
+    /// Also require `scorer` to score at least `threshold`.
+    pub fn with_scorer(mut self, scorer: Box<dyn Scorer>, threshold: f64) -> Self {
+        self.scorers.push((scorer, threshold));
+        self
+    }
+
+    /// Score against this fixed value.
+    pub fn with_expected(mut self, expected: Value) -> Self {
+        self.expected = expected;
+        self
+    }
+
+    /// Score the value at this JSON pointer in the input rather than the
+    /// whole input.
+    pub fn with_output_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.output_pointer = Some(pointer.into());
+        self
+    }
+
+    /// Score against the value at this JSON pointer in the input, such as a
+    /// reference answer carried along by the flow.
+    pub fn with_expected_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.expected_pointer = Some(pointer.into());
+        self
+    }
+
+    /// Pass failing values to `node`, such as a revision step, and score
+    /// its output again, up to `max_attempts` times.
+    pub fn with_retry(mut self, node: Box<dyn Node>, max_attempts: usize) -> Self {
+        self.retry = Some((node, max_attempts));
+        self
+    }
+
+    /// Pass values that still fail to `node` and return its output.
+    pub fn with_escalation(mut self, node: Box<dyn Node>) -> Self {
+        self.escalation = Some(node);
+        self
+    }
+
+    /// Score `input`, returning the failures as `name scored x, below y`.
+    async fn check(&self, input: &Value) -> Result<Vec<String>, FlowError> {
+        let output = at(input, self.output_pointer.as_deref())?;
+        let expected = match &self.expected_pointer {
+            Some(pointer) => at(input, Some(pointer))?,
+            None => &self.expected,
+        };
+        let mut failures = Vec::new();
+        for (scorer, threshold) in &self.scorers {
+            let score = scorer.score(output, expected).await?;
+            report::record_score(scorer.name(), score);
+            if score < *threshold {
+                failures.push(format!(
+                    "{} scored {score:.3}, below {threshold}",
+                    scorer.name()
+                ));
+            }
+        }
+        Ok(failures)
+    }
+}
+
+fn at<'a>(input: &'a Value, pointer: Option<&str>) -> Result<&'a Value, FlowError> {
+    match pointer {
+        None => Ok(input),
+        Some(pointer) => input.pointer(pointer).ok_or_else(|| {
+            FlowError::NodeFailed(format!("Quality gate input has no value at '{pointer}'"))
+        }),
+    }
+}
+
+#[async_trait]
+impl Node for QualityGate {
+    /// Score the input and pass it on, retry it, or escalate it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the value still fails after all
+    /// retries and there is no escalation node, or a pointer has no value.
+    /// Errors of scorers and of the retry and escalation nodes are returned
+    /// as is.
+    async fn call(&self, mut input: Value) -> Result<Value, FlowError> {
+        let mut failures = self.check(&input).await?;
+        if failures.is_empty() {
+            return Ok(input);
+        }
+
+        if let Some((node, max_attempts)) = &self.retry {
+            for attempt in 1..=*max_attempts {
+                tracing::debug!(
+                    "Quality gate retry {attempt}/{max_attempts}: {}",
+                    failures.join("; ")
+                );
+                report::record_retry();
+                input = node.call(input).await?;
+                failures = self.check(&input).await?;
+                if failures.is_empty() {
+                    return Ok(input);
+                }
+            }
+        }
+
+        match &self.escalation {
+            Some(node) => {
+                tracing::warn!("Quality gate escalating: {}", failures.join("; "));
+                node.call(input).await
+            }
+            None => Err(FlowError::NodeFailed(format!(
+                "Quality gate failed: {}",
+                failures.join("; ")
+            ))),
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! Browser access and response hardening for the HTTP server.
+//!
+//! Three middleware layers make the [server](crate::server) routes safe to
+//! call from a browser frontend, each applied to a [`Router`] like
+//! [`RequireAuth`](crate::auth::RequireAuth):
+//!
+//! - [`Cors`] answers preflight requests and lets the listed origins read
+//!   responses, including the `ETag`, `Location` and `X-Session-Id` headers
+//!   the routes send.
+//! - [`SecurityHeaders`] adds headers such as `X-Content-Type-Options` and
+//!   `Content-Security-Policy` to every response that does not set them.
+//! - [`ContentTypes`] rejects request bodies of other media types with
+//!   `415 Unsupported Media Type`.
+//!
+//! Apply [`Cors`] last, so that it wraps the other layers and preflight
+//! requests are answered before authentication asks for a key.
+
+use crate::error::FlowError;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Json, Response};
+use axum::Router;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// Request headers the server routes read.
+const REQUEST_HEADERS: [&str; 6] = [
+    "content-type",
+    "authorization",
+    "x-api-key",
+    "x-session-id",
+    "if-none-match",
+    "prefer",
+];
+
+/// Response headers the server routes send that scripts may need.
+const EXPOSED_HEADERS: [&str; 5] = [
+    "etag",
+    "location",
+    "retry-after",
+    "x-session-id",
+    "preference-applied",
+];
+
+enum Origins {
+    Any,
+    List(Vec<HeaderValue>),
+}
+
+/// A CORS policy for the served routes.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::body::Body;
+/// use axum::http::{Request, StatusCode};
+/// use rustyflow::security::{ContentTypes, Cors, SecurityHeaders};
+/// use rustyflow::server::{router, FlowRegistry};
+/// use rustyflow::FlowError;
+/// use std::sync::Arc;
+/// use tower::ServiceExt;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let app = router(Arc::new(FlowRegistry::new()));
+/// let app = ContentTypes::json().apply(app);
+/// let app = SecurityHeaders::new().apply(app);
+/// let app = Cors::new().with_origin("https://app.example.com")?.apply(app)?;
+///
+/// // Preflight requests from the frontend are allowed
+/// let preflight = Request::options("/flows/chat/execute")
+///     .header("origin", "https://app.example.com")
+///     .header("access-control-request-method", "POST")
+///     .header("access-control-request-headers", "content-type")
+///     .body(Body::empty())
+///     .unwrap();
+/// let response = app.clone().oneshot(preflight).await.unwrap();
+/// assert_eq!(
+///     response.headers()["access-control-allow-origin"],
+///     "https://app.example.com"
+/// );
+///
+/// // Responses are hardened
+/// let response = app
+///     .clone()
+///     .oneshot(Request::get("/flows").body(Body::empty()).unwrap())
+///     .await
+///     .unwrap();
+/// assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+///
+/// // Bodies that are not JSON are turned away before reaching a flow
+/// let form = Request::post("/flows/chat/execute")
+///     .header("content-type", "application/x-www-form-urlencoded")
+///     .body(Body::from("message=hi"))
+///     .unwrap();
+/// let response = app.oneshot(form).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+///
+/// // Cookies are only ever sent to the origins named
+/// let any = Cors::new().with_any_origin().with_credentials();
+/// assert!(any.apply(router(Arc::new(FlowRegistry::new()))).is_err());
+/// assert!(Cors::new().with_any_origin().with_origin("https://app.example.com").is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub struct Cors {
+    origins: Origins,
+    credentials: bool,
+    max_age: Option<Duration>,
+    headers: Vec<HeaderName>,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cors {
+    /// Create a policy that allows no origins yet.
+    ///
+    /// Allowed origins may use `GET`, `POST` and `OPTIONS` with the headers
+    /// the server routes read, and read the headers they send.
+    pub fn new() -> Self {
+        Self {
+            origins: Origins::List(Vec::new()),
+            credentials: false,
+            max_age: None,
+            headers: REQUEST_HEADERS
+                .iter()
+                .map(|name| HeaderName::from_static(name))
+                .collect(),
+        }
+    }
+
+    /// Allow requests from `origin`, such as `https://app.example.com`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if `origin` is not a valid header
+    /// value, or if the policy already allows any origin.
+    pub fn with_origin(mut self, origin: &str) -> Result<Self, FlowError> {
+        let value = HeaderValue::from_str(origin.trim_end_matches('/'))
+            .map_err(|_| FlowError::NodeFailed(format!("Invalid CORS origin '{origin}'")))?;
+        match &mut self.origins {
+            Origins::List(origins) => origins.push(value),
+            Origins::Any => {
+                return Err(FlowError::NodeFailed(format!(
+                    "Cannot allow CORS origin '{origin}' in a policy that allows any origin"
+                )))
+            }
+        }
+        Ok(self)
+    }
+
+    /// Allow requests from any origin, replacing any origins allowed
+    /// before. Cannot be combined with
+    /// [`with_credentials`](Cors::with_credentials).
+    pub fn with_any_origin(mut self) -> Self {
+        self.origins = Origins::Any;
+        self
+    }
+
+    /// Let browsers send cookies, such as the session cookie, with
+    /// cross-origin requests. Only the origins allowed with
+    /// [`with_origin`](Cors::with_origin) may do so: allowing credentials
+    /// from any origin would let every website act as the user.
+    pub fn with_credentials(mut self) -> Self {
+        self.credentials = true;
+        self
+    }
+
+    /// Let browsers cache preflight answers for `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Also allow a request header of the application's own.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if `name` is not a valid header name.
+    pub fn with_header(mut self, name: &str) -> Result<Self, FlowError> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| FlowError::NodeFailed(format!("Invalid header name '{name}'")))?;
+        self.headers.push(name);
+        Ok(self)
+    }
+
+    /// Wrap every route of `router` in this policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the policy allows credentials
+    /// from any origin.
+    pub fn apply(self, router: Router) -> Result<Router, FlowError> {
+        let origins = match self.origins {
+            Origins::Any if self.credentials => {
+                return Err(FlowError::NodeFailed(
+                    "CORS credentials need an explicit list of origins, not any origin".to_string(),
+                ))
+            }
+            Origins::Any => AllowOrigin::any(),
+            Origins::List(origins) => AllowOrigin::list(origins),
+        };
+        let mut layer = CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+            .allow_headers(self.headers)
+            .expose_headers(EXPOSED_HEADERS.map(HeaderName::from_static))
+            .allow_credentials(self.credentials);
+        if let Some(max_age) = self.max_age {
+            layer = layer.max_age(max_age);
+        }
+        Ok(router.layer(layer))
+    }
+}
+
+/// Security headers added to every response that does not set them.
+///
+/// By default these are:
+///
+/// | Header | Value |
+/// |--------|-------|
+/// | `X-Content-Type-Options` | `nosniff` |
+/// | `X-Frame-Options` | `DENY` |
+/// | `Referrer-Policy` | `no-referrer` |
+/// | `Content-Security-Policy` | `default-src 'none'; frame-ancestors 'none'` |
+///
+/// `Strict-Transport-Security` is added with
+/// [`with_hsts`](SecurityHeaders::with_hsts), for servers only reached over
+/// HTTPS.
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecurityHeaders {
+    /// Create the default set of headers.
+    pub fn new() -> Self {
+        Self {
+            headers: vec![
+                (
+                    header::X_CONTENT_TYPE_OPTIONS,
+                    HeaderValue::from_static("nosniff"),
+                ),
+                (header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY")),
+                (
+                    header::REFERRER_POLICY,
+                    HeaderValue::from_static("no-referrer"),
+                ),
+                (
+                    header::CONTENT_SECURITY_POLICY,
+                    HeaderValue::from_static("default-src 'none'; frame-ancestors 'none'"),
+                ),
+            ],
+        }
+    }
+
+    /// Tell browsers to use HTTPS only for `max_age`, for this host and its
+    /// subdomains.
+    pub fn with_hsts(self, max_age: Duration) -> Self {
+        let value = format!("max-age={}; includeSubDomains", max_age.as_secs());
+        let value = HeaderValue::from_str(&value).expect("a number is a valid header value");
+        self.with(header::STRICT_TRANSPORT_SECURITY, value)
+    }
+
+    /// Add a header, or replace the value of one already in the set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if `name` or `value` is not valid in
+    /// a header.
+    pub fn with_header(self, name: &str, value: &str) -> Result<Self, FlowError> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| FlowError::NodeFailed(format!("Invalid header name '{name}'")))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| FlowError::NodeFailed(format!("Invalid value for header '{name}'")))?;
+        Ok(self.with(name, value))
+    }
+
+    fn with(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.retain(|(existing, _)| *existing != name);
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Add the headers to the responses of every route of `router`.
+    pub fn apply(self, router: Router) -> Router {
+        router.layer(middleware::from_fn_with_state(Arc::new(self), add_headers))
+    }
+}
+
+async fn add_headers(
+    State(security): State<Arc<SecurityHeaders>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    for (name, value) in &security.headers {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+    response
+}
+
+/// The media types accepted in request bodies.
+///
+/// `POST`, `PUT` and `PATCH` requests of any other type, or with no
+/// `Content-Type`, are answered `415 Unsupported Media Type` with
+/// `{"error": ..., "allowed": [...]}`. Parameters such as `charset` are
+/// ignored, and types compare case-insensitively.
+#[derive(Debug, Clone)]
+pub struct ContentTypes {
+    allowed: Vec<String>,
+}
+
+impl ContentTypes {
+    /// Accept only the media types in `allowed`, such as
+    /// `application/json`.
+    pub fn new<I, S>(allowed: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            allowed: allowed
+                .into_iter()
+                .map(|media_type| media_type.into().to_ascii_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Accept only JSON, the type every server route reads.
+    pub fn json() -> Self {
+        Self::new(["application/json"])
+    }
+
+    /// Check the bodies of requests to every route of `router`.
+    pub fn apply(self, router: Router) -> Router {
+        router.layer(middleware::from_fn_with_state(Arc::new(self), check_type))
+    }
+}
+
+async fn check_type(
+    State(types): State<Arc<ContentTypes>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if ![Method::POST, Method::PUT, Method::PATCH].contains(request.method()) {
+        return next.run(request).await;
+    }
+    let media_type = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or(value)
+                .trim()
+                .to_ascii_lowercase()
+        });
+    match media_type {
+        Some(media_type) if types.allowed.contains(&media_type) => next.run(request).await,
+        media_type => {
+            let error = match media_type {
+                Some(media_type) => format!("Unsupported content type '{media_type}'"),
+                None => "Missing content type".to_string(),
+            };
+            let body = json!({ "error": error, "allowed": types.allowed });
+            (StatusCode::UNSUPPORTED_MEDIA_TYPE, Json(body)).into_response()
+        }
+    }
+}
@@ -0,0 +1,438 @@
+//! Per-request model selection by cost, latency, capability and difficulty.
+//!
+//! This module provides [`ModelSelector`], which routes each chat request to
+//! one of several [`ChatModel`]s described by a [`ModelProfile`]: a cheap
+//! model for short, easy inputs and a stronger one for hard inputs, subject
+//! to the required capabilities, a latency target, and a remaining
+//! [`Budget`].
+
+use crate::budget::Budget;
+use crate::constraint::Constraint;
+use crate::error::FlowError;
+use crate::llm::{record_usage, ChatModel, ChatRequest, ChatResponse};
+use crate::node::Node;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+/// A feature a request may require from a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Native tool calling.
+    Tools,
+    /// Image inputs.
+    Vision,
+    /// Guaranteed JSON output.
+    JsonMode,
+}
+
+/// What a [`ModelSelector`] knows about one model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelProfile {
+    /// The name reported in the selector's output.
+    pub name: String,
+    /// Relative answer quality from `0.0` (weakest) to `1.0` (strongest).
+    pub quality: f64,
+    /// Cost per 1,000 prompt tokens, in the budget's units.
+    pub input_cost_per_1k: f64,
+    /// Cost per 1,000 completion tokens, in the budget's units.
+    pub output_cost_per_1k: f64,
+    /// Typical time to a complete response.
+    pub typical_latency: Duration,
+    /// The largest prompt plus completion the model accepts, in tokens.
+    pub context_window: u32,
+    /// Features the model supports.
+    pub capabilities: Vec<Capability>,
+}
+
+impl ModelProfile {
+    /// Describe a model with the given name and quality.
+    ///
+    /// The profile starts free, with a one second typical latency, a 128k
+    /// token context window, and no capabilities.
+    pub fn new(name: impl Into<String>, quality: f64) -> Self {
+        Self {
+            name: name.into(),
+            quality: quality.clamp(0.0, 1.0),
+            input_cost_per_1k: 0.0,
+            output_cost_per_1k: 0.0,
+            typical_latency: Duration::from_secs(1),
+            context_window: 128_000,
+            capabilities: Vec::new(),
+        }
+    }
+
+    /// Set the price per 1,000 prompt and completion tokens.
+    pub fn with_cost(mut self, input_per_1k: f64, output_per_1k: f64) -> Self {
+        self.input_cost_per_1k = input_per_1k;
+        self.output_cost_per_1k = output_per_1k;
+        self
+    }
+
+    /// Set the typical response latency.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.typical_latency = latency;
+        self
+    }
+
+    /// Set the context window in tokens.
+    pub fn with_context_window(mut self, tokens: u32) -> Self {
+        self.context_window = tokens;
+        self
+    }
+
+    /// Declare a supported capability.
+    pub fn with_capability(mut self, capability: Capability) -> Self {
+        self.capabilities.push(capability);
+        self
+    }
+
+    fn cost(&self, prompt_tokens: u32, completion_tokens: u32) -> f64 {
+        (prompt_tokens as f64 * self.input_cost_per_1k
+            + completion_tokens as f64 * self.output_cost_per_1k)
+            / 1000.0
+    }
+}
+
+/// A coarse difficulty bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    /// Difficulty below `1/3`.
+    Easy,
+    /// Difficulty from `1/3` up to `2/3`.
+    Medium,
+    /// Difficulty of `2/3` or more.
+    Hard,
+}
+
+impl Tier {
+    /// The bucket containing a difficulty between `0.0` and `1.0`.
+    pub fn from_difficulty(difficulty: f64) -> Self {
+        if difficulty < 1.0 / 3.0 {
+            Tier::Easy
+        } else if difficulty < 2.0 / 3.0 {
+            Tier::Medium
+        } else {
+            Tier::Hard
+        }
+    }
+}
+
+/// Extra routing information supplied with a request.
+///
+/// As node input, it is read from the optional `hint` field next to the
+/// request's `messages`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoutingHint {
+    /// Estimated difficulty from `0.0` (trivial) to `1.0` (hardest).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<f64>,
+    /// The difficulty bucket, convenient for branching on in a flow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<Tier>,
+    /// Capabilities the request needs beyond those implied by the request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capabilities: Vec<Capability>,
+}
+
+/// Chooses a model per request from a set of profiled [`ChatModel`]s.
+///
+/// For each request, the selector discards models that lack a required
+/// capability (tool calling is required when the request has tools), whose
+/// context window is too small, or whose estimated cost exceeds the remaining
+/// budget, and prefers models within the latency target. Among these it picks
+/// the cheapest model whose quality meets the request's difficulty, or the
+/// strongest one if none does.
+///
+/// Difficulty comes from the [`RoutingHint`] if present, and otherwise grows
+/// with prompt length up to [`ModelSelector::with_hard_prompt_tokens`]. Token
+/// counts are estimated at four characters per token.
+///
+/// `ModelSelector` is itself a [`ChatModel`], so it can stand in for a single
+/// model anywhere. As a [`Node`], it accepts the same input as
+/// [`ChatNode`](crate::llm::ChatNode) plus an optional `hint`, and adds the
+/// chosen model's name to the response as `model`.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::llm::{ChatModel, ChatRequest, ChatResponse, Message};
+/// use rustyflow::selector::{ModelProfile, ModelSelector};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// struct Echo(&'static str);
+///
+/// #[async_trait]
+/// impl ChatModel for Echo {
+///     async fn chat(&self, _request: ChatRequest) -> Result<ChatResponse, FlowError> {
+///         Ok(ChatResponse { message: Message::assistant(self.0), usage: None })
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let selector = ModelSelector::new()
+///     .with_model(ModelProfile::new("small", 0.3).with_cost(0.1, 0.2), Echo("small"))
+///     .with_model(ModelProfile::new("large", 0.9).with_cost(3.0, 6.0), Echo("large"));
+///
+/// let easy = selector.call(json!([{"role": "user", "content": "Hi!"}])).await?;
+/// assert_eq!(easy["model"], "small");
+///
+/// let hard = selector
+///     .call(json!({
+///         "messages": [{"role": "user", "content": "Prove the theorem."}],
+///         "hint": {"difficulty": 0.8}
+///     }))
+///     .await?;
+/// assert_eq!(hard["model"], "large");
+/// # Ok(())
+/// # }
+/// ```
+pub struct ModelSelector {
+    models: Vec<(ModelProfile, Box<dyn ChatModel>)>,
+    budget: Option<Budget>,
+    latency_target: Option<Duration>,
+    hard_prompt_tokens: u32,
+    default_completion_tokens: u32,
+}
+
+impl Default for ModelSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModelSelector {
+    /// Create a selector with no models.
+    pub fn new() -> Self {
+        Self {
+            models: Vec::new(),
+            budget: None,
+            latency_target: None,
+            hard_prompt_tokens: 4000,
+            default_completion_tokens: 512,
+        }
+    }
+
+    /// Add a candidate model.
+    pub fn with_model(mut self, profile: ModelProfile, model: impl ChatModel + 'static) -> Self {
+        self.models.push((profile, Box::new(model)));
+        self
+    }
+
+    /// Only choose models whose estimated cost fits the budget, and charge
+    /// each call's actual cost to it.
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Prefer models whose typical latency is within `target`.
+    ///
+    /// If no capable model meets the target, the fastest one is used.
+    pub fn with_latency_target(mut self, target: Duration) -> Self {
+        self.latency_target = Some(target);
+        self
+    }
+
+    /// Set the prompt length, in tokens, treated as maximally difficult.
+    pub fn with_hard_prompt_tokens(mut self, tokens: u32) -> Self {
+        self.hard_prompt_tokens = tokens.max(1);
+        self
+    }
+
+    /// Choose the model for a request.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&ModelProfile)` - The chosen model's profile
+    /// * `Err(FlowError)` - An error if no model is capable or affordable
+    pub fn select(
+        &self,
+        request: &ChatRequest,
+        hint: &RoutingHint,
+    ) -> Result<&ModelProfile, FlowError> {
+        self.choose(request, hint)
+            .map(|index| &self.models[index].0)
+    }
+
+    fn choose(&self, request: &ChatRequest, hint: &RoutingHint) -> Result<usize, FlowError> {
+        let prompt_tokens = estimate_tokens(request);
+        let completion_tokens = request.max_tokens.unwrap_or(self.default_completion_tokens);
+        let mut required = hint.capabilities.clone();
+        if !request.tools.is_empty() {
+            required.push(Capability::Tools);
+        }
+
+        let capable: Vec<usize> = (0..self.models.len())
+            .filter(|&i| {
+                let profile = &self.models[i].0;
+                required.iter().all(|c| profile.capabilities.contains(c))
+                    && profile.context_window >= prompt_tokens + completion_tokens
+            })
+            .collect();
+        if capable.is_empty() {
+            return Err(FlowError::NodeFailed(format!(
+                "No model supports {required:?} with a {} token context",
+                prompt_tokens + completion_tokens
+            )));
+        }
+
+        let affordable: Vec<usize> = capable
+            .into_iter()
+            .filter(|&i| {
+                let cost = self.models[i].0.cost(prompt_tokens, completion_tokens);
+                self.budget
+                    .as_ref()
+                    .map_or(true, |budget| cost <= budget.remaining())
+            })
+            .collect();
+        if affordable.is_empty() {
+            return Err(FlowError::BudgetExceeded(
+                "No capable model fits the remaining budget".to_string(),
+            ));
+        }
+
+        let candidates = match self.latency_target {
+            Some(target) => {
+                let fast: Vec<usize> = affordable
+                    .iter()
+                    .copied()
+                    .filter(|&i| self.models[i].0.typical_latency <= target)
+                    .collect();
+                if fast.is_empty() {
+                    let fastest = affordable
+                        .iter()
+                        .copied()
+                        .min_by_key(|&i| self.models[i].0.typical_latency);
+                    fastest.into_iter().collect()
+                } else {
+                    fast
+                }
+            }
+            None => affordable,
+        };
+
+        let difficulty = hint
+            .difficulty
+            .unwrap_or(prompt_tokens as f64 / self.hard_prompt_tokens as f64)
+            .clamp(0.0, 1.0);
+        let cost = |i: &usize| self.models[*i].0.cost(prompt_tokens, completion_tokens);
+        let good_enough = candidates
+            .iter()
+            .filter(|&&i| self.models[i].0.quality >= difficulty)
+            .min_by(|a, b| cost(a).total_cmp(&cost(b)));
+        let strongest = candidates.iter().max_by(|&&a, &&b| {
+            self.models[a]
+                .0
+                .quality
+                .total_cmp(&self.models[b].0.quality)
+        });
+
+        Ok(*good_enough.or(strongest).expect("candidates is not empty"))
+    }
+
+    async fn chat_with_hint(
+        &self,
+        request: ChatRequest,
+        hint: &RoutingHint,
+    ) -> Result<(ChatResponse, &str), FlowError> {
+        let index = self.choose(&request, hint)?;
+        let (profile, model) = &self.models[index];
+        tracing::debug!("Selected model {}", profile.name);
+
+        let estimate = profile.cost(
+            estimate_tokens(&request),
+            request.max_tokens.unwrap_or(self.default_completion_tokens),
+        );
+        if let Some(budget) = &self.budget {
+            budget.try_spend(estimate)?;
+        }
+        let response = match model.chat(request).await {
+            Ok(response) => response,
+            Err(e) => {
+                if let Some(budget) = &self.budget {
+                    budget.refund(estimate);
+                }
+                return Err(e);
+            }
+        };
+
+        // Replace the estimate with the reported cost
+        if let (Some(budget), Some(usage)) = (&self.budget, response.usage) {
+            let actual = profile.cost(usage.prompt_tokens, usage.completion_tokens);
+            budget.refund(estimate);
+            if let Err(e) = budget.try_spend(actual) {
+                tracing::warn!("Model {} overspent its estimate: {e}", profile.name);
+            }
+        }
+        Ok((response, &profile.name))
+    }
+}
+
+/// Estimate prompt tokens at four characters per token.
+fn estimate_tokens(request: &ChatRequest) -> u32 {
+    let chars: usize = request
+        .messages
+        .iter()
+        .map(|message| message.content.chars().count())
+        .sum();
+    (chars / 4) as u32 + 1
+}
+
+#[async_trait]
+impl ChatModel for ModelSelector {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, FlowError> {
+        Ok(self
+            .chat_with_hint(request, &RoutingHint::default())
+            .await?
+            .0)
+    }
+
+    /// Only if every model the selector may pick enforces `constraint`.
+    fn supports_constraint(&self, constraint: &Constraint) -> bool {
+        !self.models.is_empty()
+            && self
+                .models
+                .iter()
+                .all(|(_, model)| model.supports_constraint(constraint))
+    }
+}
+
+#[async_trait]
+impl Node for ModelSelector {
+    /// Select a model and send it the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::SerdeError` if the input is not a request,
+    /// `FlowError::NodeFailed` if no model is capable,
+    /// `FlowError::BudgetExceeded` if none is affordable, or propagates the
+    /// model's error.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let (request, hint) = match input {
+            Value::Array(_) => (
+                ChatRequest::new(serde_json::from_value(input)?),
+                RoutingHint::default(),
+            ),
+            mut other => {
+                let hint = match other.as_object_mut().and_then(|o| o.remove("hint")) {
+                    Some(hint) => serde_json::from_value(hint)?,
+                    None => RoutingHint::default(),
+                };
+                (serde_json::from_value(other)?, hint)
+            }
+        };
+
+        let (response, name) = self.chat_with_hint(request, &hint).await?;
+        let name = name.to_string();
+        let mut output = serde_json::to_value(record_usage(response))?;
+        output["model"] = Value::String(name);
+        Ok(output)
+    }
+}
@@ -0,0 +1,895 @@
+//! Hosting flows over HTTP.
+//!
+//! A [`FlowRegistry`] holds named flows, and [`router`] exposes them as an
+//! axum [`Router`] with these routes:
+//!
+//! | Route | Description |
+//! |-------|-------------|
+//! | `GET /flows` | List the registered flows |
+//! | `GET /flows/:name` | Describe one flow |
+//! | `POST /flows/:name/execute` | Execute a flow with the JSON request body as input |
+//! | `POST /flows/:name/stream` | Execute a flow, streaming [`ExecutionEvent`]s as Server-Sent Events |
+//! | `GET /flows/:name/health` | Run the [health checks](crate::node::HealthCheck) of a flow's nodes |
+//! | `POST /jobs` | Start a background [`Job`] from `{"flow": ..., "input": ...}` and return its id |
+//! | `GET /jobs/:id` | Poll a job's status, progress and result |
+//!
+//! Request bodies are validated against the flow's input schema: the one
+//! registered with [`FlowRegistry::with_input_schema`], or else the
+//! [`Node::input_schema`](crate::node::Node::input_schema) of the flow's
+//! first node, such as a [`ToolNode`](crate::tool::ToolNode) whose tool
+//! declares one. Invalid bodies are answered with `400 Bad Request` and a
+//! list of [`FieldError`]s:
+//!
+//! ```json
+//! {"error": "Invalid input", "fields": [{"path": "/a", "message": "expected integer, got string"}]}
+//! ```
+//!
+//! Failed executions are answered with the error's
+//! [`FlowError::status_code`], such as `504 Gateway Timeout` for a
+//! [`FlowError::Timeout`], and an `{"error": message}` body. Unknown flows
+//! and jobs are `404 Not Found`.
+//!
+//! The health route answers `200 OK`, or `503 Service Unavailable` if any
+//! check failed, with `{"healthy": bool, "nodes": [...]}` listing a
+//! [`NodeHealth`] for each node that has a check.
+//!
+//! The bundled `server` binary serves this router next to its `/metrics`
+//! endpoint; applications can merge it into their own router instead.
+//!
+//! Behind the [`Sessions`](crate::session::Sessions) middleware, the
+//! execute, stream and job routes add the request's session
+//! [key](crate::session::ActiveSession::key) to object inputs as `session`,
+//! replacing any the body sets, and its stored context as `context`,
+//! unless the body sets one. The execute route saves the `context` object
+//! a flow returns. Chat flows thereby continue a conversation across
+//! requests.
+//!
+//! Each Server-Sent Event of the stream endpoint is named after the event's
+//! `type` (`node_start`, `token_delta`, `warning`, `progress`, `node_end`,
+//! `final_result` or `error`) and carries the event as JSON data. The run is
+//! cancelled if the client disconnects. While no event is due, a
+//! `: keep-alive` comment is sent every 15 seconds, or as set with
+//! [`ServerOptions::with_keep_alive`], so that proxies and load balancers
+//! don't close an idle stream.
+//!
+//! Long runs of the execute route can become background [`Job`]s instead
+//! of holding the connection open. A request with `Prefer: respond-async`
+//! or `Expect: 202-accepted` is answered at once, and with
+//! [`ServerOptions::with_async_after`] any run still going after a time
+//! limit is too. Either way the answer is `202 Accepted` with
+//! `{"id": ..., "status": ...}` and a `Location` header of the job to poll;
+//! runs that finish in time are answered as usual.
+//!
+//! Results of the execute route carry an `ETag` that is a hash of the
+//! output. A client polling with the same input sends it back as
+//! `If-None-Match`, and is answered `304 Not Modified` with no body when
+//! the output is unchanged. If the flow caches its output with
+//! [`Flow::with_cache`] and holds a fresh one for the input, that answer
+//! comes without running any node.
+
+use crate::cache;
+use crate::error::FlowError;
+use crate::events::ExecutionEvent;
+use crate::flow::Flow;
+use crate::jobs::{Job, JobQueue};
+use crate::node::{NodeHealth, NodeInfo};
+use crate::pack::FlowPack;
+use crate::registry::NodeRegistry;
+use crate::schema::{self, FieldError};
+use crate::session::ActiveSession;
+use crate::telemetry::AbortOnDrop;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A description of a registered flow, as returned by `GET /flows`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowInfo {
+    /// The name the flow is registered under.
+    pub name: String,
+    /// What the flow does.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// A JSON Schema for the request body, registered or taken from the
+    /// first node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_schema: Option<Value>,
+    /// A JSON Schema for the flow's output, from the last node's
+    /// [`SchemaProvider`](crate::node::SchemaProvider).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
+    /// The flow's nodes, in order.
+    pub nodes: Vec<NodeInfo>,
+}
+
+struct Entry {
+    flow: Arc<Flow>,
+    description: Option<String>,
+    input_schema: Option<Value>,
+}
+
+/// A set of flows hosted under unique names.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::body::Body;
+/// use axum::http::{Request, StatusCode};
+/// use http_body_util::BodyExt;
+/// use rustyflow::server::{router, FlowRegistry};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::Arc;
+/// use async_trait::async_trait;
+/// use tower::ServiceExt;
+///
+/// struct Echo;
+///
+/// #[async_trait]
+/// impl Node for Echo {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(input)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let registry = FlowRegistry::new()
+///     .with_flow("echo", Flow::new(vec![Box::new(Echo)]))
+///     .with_description("echo", "Returns its input");
+/// let app = router(Arc::new(registry));
+///
+/// let request = Request::post("/flows/echo/execute")
+///     .header("content-type", "application/json")
+///     .body(Body::from(r#"{"hello": "world"}"#))
+///     .unwrap();
+/// let response = app.clone().oneshot(request).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::OK);
+/// let body = response.into_body().collect().await.unwrap().to_bytes();
+/// assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), json!({"hello": "world"}));
+///
+/// let request = Request::post("/flows/echo/stream")
+///     .header("content-type", "application/json")
+///     .body(Body::from("42"))
+///     .unwrap();
+/// let response = app.clone().oneshot(request).await.unwrap();
+/// let body = response.into_body().collect().await.unwrap().to_bytes();
+/// let events = String::from_utf8(body.to_vec()).unwrap();
+/// assert!(events.starts_with("event: node_start\n"));
+/// assert!(events.contains("event: final_result\n"));
+/// assert!(events.contains(r#""output":42"#));
+///
+/// let response = app.oneshot(Request::get("/flows").body(Body::empty()).unwrap()).await.unwrap();
+/// let body = response.into_body().collect().await.unwrap().to_bytes();
+/// let flows: Value = serde_json::from_slice(&body).unwrap();
+/// assert_eq!(flows[0]["name"], "echo");
+/// assert_eq!(flows[0]["nodes"], json!([{"index": 0, "name": "Echo", "type": "Echo"}]));
+/// # }
+/// ```
+#[derive(Default)]
+pub struct FlowRegistry {
+    flows: BTreeMap<String, Entry>,
+}
+
+impl FlowRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `flow` under `name`, replacing any flow already there.
+    pub fn with_flow(mut self, name: impl Into<String>, flow: Flow) -> Self {
+        self.insert(name.into(), Arc::new(flow));
+        self
+    }
+
+    /// Register a flow that is shared with other owners.
+    pub fn with_shared_flow(mut self, name: impl Into<String>, flow: Arc<Flow>) -> Self {
+        self.insert(name.into(), flow);
+        self
+    }
+
+    /// Describe the flow registered under `name`. Does nothing if there is
+    /// none.
+    pub fn with_description(mut self, name: &str, description: impl Into<String>) -> Self {
+        if let Some(entry) = self.flows.get_mut(name) {
+            entry.description = Some(description.into());
+        }
+        self
+    }
+
+    /// Attach a JSON Schema for the input of the flow registered under
+    /// `name`. Does nothing if there is none.
+    pub fn with_input_schema(mut self, name: &str, schema: Value) -> Self {
+        if let Some(entry) = self.flows.get_mut(name) {
+            entry.input_schema = Some(schema);
+        }
+        self
+    }
+
+    /// Build a [`FlowPack`] and register it under its manifest name, with
+    /// the manifest description and the pack's input schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from [`FlowPack::build`] or
+    /// [`FlowPack::input_schema`].
+    pub fn with_pack(self, pack: &FlowPack, nodes: &NodeRegistry) -> Result<Self, FlowError> {
+        let name = pack.manifest.name.clone();
+        let mut registry = self.with_flow(name.clone(), pack.build(nodes)?);
+        if let Some(description) = &pack.manifest.description {
+            registry = registry.with_description(&name, description.clone());
+        }
+        if let Some(schema) = pack.input_schema()? {
+            registry = registry.with_input_schema(&name, schema);
+        }
+        Ok(registry)
+    }
+
+    fn insert(&mut self, name: String, flow: Arc<Flow>) {
+        self.flows.insert(
+            name,
+            Entry {
+                flow,
+                description: None,
+                input_schema: None,
+            },
+        );
+    }
+
+    /// The flow registered under `name`.
+    pub fn get(&self, name: &str) -> Option<Arc<Flow>> {
+        self.flows.get(name).map(|entry| Arc::clone(&entry.flow))
+    }
+
+    /// The registered names, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        self.flows.keys().map(String::as_str).collect()
+    }
+
+    /// Describe the flow registered under `name`.
+    pub fn info(&self, name: &str) -> Option<FlowInfo> {
+        self.flows.get(name).map(|entry| FlowInfo {
+            name: name.to_string(),
+            description: entry.description.clone(),
+            input_schema: self.input_schema(name),
+            output_schema: entry
+                .flow
+                .len()
+                .checked_sub(1)
+                .and_then(|last| entry.flow.node(last))
+                .and_then(|node| node.schema_provider())
+                .and_then(|schemas| schemas.output_schema()),
+            nodes: entry.flow.nodes(),
+        })
+    }
+
+    /// The JSON Schema that request bodies for the flow registered under
+    /// `name` are validated against: the registered one, or else the input
+    /// schema of the flow's first node.
+    pub fn input_schema(&self, name: &str) -> Option<Value> {
+        let entry = self.flows.get(name)?;
+        entry
+            .input_schema
+            .clone()
+            .or_else(|| entry.flow.node(0)?.input_schema())
+    }
+
+    /// Check `input` against the input schema of the flow registered under
+    /// `name`.
+    ///
+    /// # Returns
+    ///
+    /// Every violation found, or an empty list if the input is valid or the
+    /// flow has no schema.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rustyflow::server::FlowRegistry;
+    /// use rustyflow::{Flow, FlowError, Tool, ToolNode};
+    /// use schemars::JsonSchema;
+    /// use serde::Deserialize;
+    /// use serde_json::{json, Value};
+    /// use async_trait::async_trait;
+    ///
+    /// #[derive(Deserialize, JsonSchema)]
+    /// struct AddRequest {
+    ///     a: i32,
+    ///     b: i32,
+    /// }
+    ///
+    /// struct Add;
+    ///
+    /// #[async_trait]
+    /// impl Tool for Add {
+    ///     type Input = AddRequest;
+    ///     type Output = i32;
+    ///
+    ///     async fn run(&self, input: AddRequest) -> Result<i32, FlowError> {
+    ///         Ok(input.a + input.b)
+    ///     }
+    ///
+    ///     fn input_schema(&self) -> Option<Value> {
+    ///         Some(schemars::schema_for!(AddRequest).to_value())
+    ///     }
+    /// }
+    ///
+    /// let registry =
+    ///     FlowRegistry::new().with_flow("add", Flow::new(vec![Box::new(ToolNode::new(Add))]));
+    ///
+    /// assert!(registry.validate("add", &json!({"a": 1, "b": 2})).is_empty());
+    /// let errors = registry.validate("add", &json!({"a": "one"}));
+    /// assert_eq!(errors[0].path, "/a");
+    /// assert_eq!(errors[1].message, "missing required field 'b'");
+    /// ```
+    pub fn validate(&self, name: &str, input: &Value) -> Vec<FieldError> {
+        match self.input_schema(name) {
+            Some(schema) => schema::validate(&schema, input),
+            None => Vec::new(),
+        }
+    }
+
+    /// Describe every registered flow, sorted by name.
+    pub fn list(&self) -> Vec<FlowInfo> {
+        self.flows
+            .keys()
+            .filter_map(|name| self.info(name))
+            .collect()
+    }
+}
+
+/// Build the routes serving the flows of `registry`.
+///
+/// Background jobs run on a fresh [`JobQueue`]; use [`router_with_jobs`] to
+/// share or configure it.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::body::Body;
+/// use axum::http::{Request, StatusCode};
+/// use rustyflow::cache::InMemoryCache;
+/// use rustyflow::server::{router, FlowRegistry};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use async_trait::async_trait;
+/// use tower::ServiceExt;
+///
+/// static CALLS: AtomicUsize = AtomicUsize::new(0);
+///
+/// /// Stands in for an expensive report.
+/// struct Report;
+///
+/// #[async_trait]
+/// impl Node for Report {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         CALLS.fetch_add(1, Ordering::SeqCst);
+///         Ok(json!({"account": input["account"], "balance": 100}))
+///     }
+/// }
+///
+/// /// Another flow's node, over the same cache.
+/// struct Audit;
+///
+/// #[async_trait]
+/// impl Node for Audit {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!({"account": input["account"], "flagged": false}))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let cache = Arc::new(InMemoryCache::new(1_000));
+/// let registry = FlowRegistry::new()
+///     .with_flow("report", Flow::new(vec![Box::new(Report)]).with_cache(cache.clone(), None))
+///     .with_flow("audit", Flow::new(vec![Box::new(Audit)]).with_cache(cache, None));
+/// let app = router(Arc::new(registry));
+///
+/// let execute = |flow: &str, etag: Option<&str>| {
+///     let mut request = Request::post(format!("/flows/{flow}/execute"))
+///         .header("content-type", "application/json");
+///     if let Some(etag) = etag {
+///         request = request.header("if-none-match", etag);
+///     }
+///     request.body(Body::from(r#"{"account": 7}"#)).unwrap()
+/// };
+///
+/// let response = app.clone().oneshot(execute("report", None)).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::OK);
+/// let etag = response.headers()["etag"].to_str().unwrap().to_string();
+///
+/// // Polling with the tag is answered from the cache, with no body
+/// let response = app.clone().oneshot(execute("report", Some(&etag))).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+/// assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+///
+/// // An outdated tag gets the current result
+/// let response = app.clone().oneshot(execute("report", Some("\"outdated\""))).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::OK);
+/// assert_eq!(response.headers()["etag"], etag.as_str());
+///
+/// // The other flow's output, cached in the same backend, has a tag of its own
+/// let response = app.clone().oneshot(execute("audit", None)).await.unwrap();
+/// let audit_etag = response.headers()["etag"].to_str().unwrap().to_string();
+/// assert_ne!(audit_etag, etag);
+/// let response = app.oneshot(execute("audit", Some(&etag))).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::OK);
+/// assert_eq!(response.headers()["etag"], audit_etag.as_str());
+/// # }
+/// ```
+pub fn router(registry: Arc<FlowRegistry>) -> Router {
+    router_with_jobs(registry, JobQueue::new())
+}
+
+/// Build the routes serving the flows of `registry`, running background
+/// jobs on `jobs`.
+pub fn router_with_jobs(registry: Arc<FlowRegistry>, jobs: JobQueue) -> Router {
+    router_with_options(registry, ServerOptions::new().with_jobs(jobs))
+}
+
+/// Build the routes serving the flows of `registry`, configured by
+/// `options`.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::body::Body;
+/// use axum::http::{Request, StatusCode};
+/// use http_body_util::BodyExt;
+/// use rustyflow::jobs::JobQueue;
+/// use rustyflow::server::{router_with_options, FlowRegistry, ServerOptions};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use async_trait::async_trait;
+/// use tower::ServiceExt;
+///
+/// static RESTED: AtomicUsize = AtomicUsize::new(0);
+///
+/// /// Sleeps for `ms` milliseconds.
+/// struct Nap;
+///
+/// #[async_trait]
+/// impl Node for Nap {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         tokio::time::sleep(Duration::from_millis(input["ms"].as_u64().unwrap_or(0))).await;
+///         RESTED.fetch_add(1, Ordering::SeqCst);
+///         Ok(json!("rested"))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let registry = FlowRegistry::new().with_flow("nap", Flow::new(vec![Box::new(Nap)]));
+/// let jobs = JobQueue::new();
+/// let options = ServerOptions::new()
+///     .with_jobs(jobs.clone())
+///     .with_keep_alive(Duration::from_secs(5))
+///     .with_async_after(Duration::from_millis(200));
+/// let app = router_with_options(Arc::new(registry), options);
+///
+/// let request = |route: &str, ms: u64| {
+///     Request::post(format!("/flows/nap/{route}"))
+///         .header("content-type", "application/json")
+///         .body(Body::from(json!({"ms": ms}).to_string()))
+///         .unwrap()
+/// };
+/// let execute = |ms| request("execute", ms);
+///
+/// // Short runs are answered with their result, and leave no job behind
+/// let response = app.clone().oneshot(execute(1)).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::OK);
+/// assert!(jobs.list().is_empty());
+///
+/// // A streamed run stops when its client disconnects
+/// let response = app.clone().oneshot(request("stream", 100)).await.unwrap();
+/// let mut events = response.into_body();
+/// events.frame().await.unwrap().unwrap();
+/// drop(events);
+/// tokio::time::sleep(Duration::from_millis(200)).await;
+/// assert_eq!(RESTED.load(Ordering::SeqCst), 1);
+///
+/// // Long ones turn into a job to poll
+/// let response = app.clone().oneshot(execute(400)).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::ACCEPTED);
+/// let location = response.headers()["location"].to_str().unwrap().to_string();
+/// let body = response.into_body().collect().await.unwrap().to_bytes();
+/// let job: Value = serde_json::from_slice(&body).unwrap();
+/// assert_eq!(location, format!("/jobs/{}", job["id"].as_str().unwrap()));
+///
+/// tokio::time::sleep(Duration::from_millis(400)).await;
+/// let response = app.oneshot(Request::get(location).body(Body::empty()).unwrap()).await.unwrap();
+/// let body = response.into_body().collect().await.unwrap().to_bytes();
+/// let job: Value = serde_json::from_slice(&body).unwrap();
+/// assert_eq!(job["status"], "succeeded");
+/// assert_eq!(job["result"], "rested");
+/// # }
+/// ```
+pub fn router_with_options(registry: Arc<FlowRegistry>, options: ServerOptions) -> Router {
+    Router::new()
+        .route("/flows", get(list_flows))
+        .route("/flows/:name", get(describe_flow))
+        .route("/flows/:name/execute", post(execute_flow))
+        .route("/flows/:name/stream", post(stream_flow))
+        .route("/flows/:name/health", get(flow_health))
+        .route("/jobs", post(submit_job))
+        .route("/jobs/:id", get(get_job))
+        .with_state(Arc::new(AppState { registry, options }))
+}
+
+/// How the routes of [`router_with_options`] run flows and hold
+/// connections.
+#[derive(Clone)]
+pub struct ServerOptions {
+    jobs: JobQueue,
+    keep_alive: Duration,
+    async_after: Option<Duration>,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServerOptions {
+    /// Create options with a fresh [`JobQueue`], a keep-alive comment
+    /// every 15 seconds, and no time limit on execute requests.
+    pub fn new() -> Self {
+        Self {
+            jobs: JobQueue::new(),
+            keep_alive: Duration::from_secs(15),
+            async_after: None,
+        }
+    }
+
+    /// Run background jobs on `jobs`.
+    pub fn with_jobs(mut self, jobs: JobQueue) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Send a keep-alive comment on event streams after `interval` without
+    /// events. Keep it below the idle timeout of proxies in front of the
+    /// server.
+    pub fn with_keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = interval;
+        self
+    }
+
+    /// Answer execute requests whose run takes longer than `limit` with
+    /// `202 Accepted` and the id of a job that finishes the run.
+    ///
+    /// Runs of execute requests then go through the job queue, and wait
+    /// for a worker if its concurrency is limited. Runs answered within
+    /// `limit` leave no job behind; the jobs of the others are kept for the
+    /// queue's [retention](JobQueue::with_retention).
+    pub fn with_async_after(mut self, limit: Duration) -> Self {
+        self.async_after = Some(limit);
+        self
+    }
+}
+
+struct AppState {
+    registry: Arc<FlowRegistry>,
+    options: ServerOptions,
+}
+
+/// The body of `POST /jobs`.
+#[derive(Debug, Clone, Deserialize)]
+struct JobRequest {
+    flow: String,
+    #[serde(default)]
+    input: Value,
+}
+
+/// A `400 Bad Request` answer if `input` does not match the flow's schema.
+fn reject_invalid(registry: &FlowRegistry, name: &str, input: &Value) -> Option<Response> {
+    let fields = registry.validate(name, input);
+    if fields.is_empty() {
+        return None;
+    }
+    let body = json!({ "error": "Invalid input", "fields": fields });
+    Some((StatusCode::BAD_REQUEST, Json(body)).into_response())
+}
+
+/// Open the session and add its key and stored context to an object
+/// input. The key always replaces a `session` of the body, so that no caller
+/// can name another's session.
+async fn with_session(
+    session: Option<&ActiveSession>,
+    mut input: Value,
+) -> Result<Value, FlowError> {
+    let Some(session) = session else {
+        return Ok(input);
+    };
+    let stored = session.open().await?;
+    let Some(fields) = input.as_object_mut() else {
+        return Ok(input);
+    };
+    if !fields.contains_key("context") {
+        fields.insert("context".to_string(), Value::Object(stored.context));
+    }
+    fields.insert(
+        "session".to_string(),
+        Value::String(session.key().to_string()),
+    );
+    Ok(input)
+}
+
+/// Save the `context` object of a flow's output to the session.
+async fn keep_context(session: Option<&ActiveSession>, output: &Value) {
+    let (Some(session), Some(Value::Object(context))) = (session, output.get("context")) else {
+        return;
+    };
+    if let Err(e) = session.save_context(context.clone()).await {
+        tracing::error!("Cannot save the context of session '{}': {e}", session.id);
+    }
+}
+
+fn not_found(name: &str) -> Response {
+    FlowError::NotFound(format!("Flow '{name}'")).into_response()
+}
+
+async fn list_flows(State(state): State<Arc<AppState>>) -> Json<Vec<FlowInfo>> {
+    Json(state.registry.list())
+}
+
+async fn describe_flow(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> Response {
+    match state.registry.info(&name) {
+        Some(info) => Json(info).into_response(),
+        None => not_found(&name),
+    }
+}
+
+async fn flow_health(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> Response {
+    let Some(flow) = state.registry.get(&name) else {
+        return not_found(&name);
+    };
+    let nodes: Vec<NodeHealth> = flow.check_health().await;
+    let healthy = nodes.iter().all(|node| node.error.is_none());
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(json!({ "healthy": healthy, "nodes": nodes }))).into_response()
+}
+
+async fn execute_flow(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    session: Option<Extension<ActiveSession>>,
+    headers: HeaderMap,
+    Json(input): Json<Value>,
+) -> Response {
+    let Some(flow) = state.registry.get(&name) else {
+        return not_found(&name);
+    };
+    if let Some(response) = reject_invalid(&state.registry, &name, &input) {
+        return response;
+    }
+    let session = session.map(|Extension(session)| session);
+    let input = match with_session(session.as_ref(), input).await {
+        Ok(input) => input,
+        Err(e) => return e.into_response(),
+    };
+
+    let tags = if_none_match(&headers);
+    if let Some(tags) = &tags {
+        if let Some(output) = flow.cached_output(&input).await {
+            let etag = etag(&output);
+            if etag_matches(tags, &etag) {
+                return not_modified(&etag);
+            }
+        }
+    }
+
+    let respond_async = wants_async(&headers);
+    let limit = state.options.async_after;
+    if !respond_async && limit.is_none() {
+        let result = flow.execute(input).await;
+        return finish(&name, session.as_ref(), tags.as_deref(), result).await;
+    }
+
+    let (id, mut result) = state.options.jobs.submit_watched(name.clone(), flow, input);
+    if let (false, Some(limit)) = (respond_async, limit) {
+        if let Ok(result) = tokio::time::timeout(limit, &mut result).await {
+            // Answered in full, so the job need not be kept for polling
+            state.options.jobs.forget(&id);
+            let result = result.unwrap_or_else(|_| Err(job_lost(&id)));
+            return finish(&name, session.as_ref(), tags.as_deref(), result).await;
+        }
+    }
+    if let Some(session) = session {
+        tokio::spawn(async move {
+            if let Ok(Ok(output)) = result.await {
+                keep_context(Some(&session), &output).await;
+            }
+        });
+    }
+    let mut response = accepted(&state.options.jobs, &id);
+    if respond_async {
+        response.headers_mut().insert(
+            "preference-applied",
+            HeaderValue::from_static("respond-async"),
+        );
+    }
+    response
+}
+
+/// Whether a request asks to be answered before its run finishes, with
+/// `Prefer: respond-async` or `Expect: 202-accepted`.
+fn wants_async(headers: &HeaderMap) -> bool {
+    let asks = |name: &str, token: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|value| value.trim().eq_ignore_ascii_case(token))
+    };
+    asks("prefer", "respond-async") || asks(header::EXPECT.as_str(), "202-accepted")
+}
+
+/// Answer with a flow's result and its `ETag`, or `304 Not Modified` if
+/// the client holds it already, keeping the context it returns.
+async fn finish(
+    name: &str,
+    session: Option<&ActiveSession>,
+    tags: Option<&str>,
+    result: Result<Value, FlowError>,
+) -> Response {
+    match result {
+        Ok(result) => {
+            keep_context(session, &result).await;
+            let etag = etag(&result);
+            if tags.is_some_and(|tags| etag_matches(tags, &etag)) {
+                return not_modified(&etag);
+            }
+            let mut response = (StatusCode::OK, Json(result)).into_response();
+            if let Ok(value) = HeaderValue::from_str(&etag) {
+                response.headers_mut().insert(header::ETAG, value);
+            }
+            response
+        }
+        Err(e) => {
+            tracing::error!("Flow '{name}' failed: {e}");
+            e.into_response()
+        }
+    }
+}
+
+/// The strong entity tag of a flow's output.
+fn etag(output: &Value) -> String {
+    format!("\"{}\"", cache::digest(output))
+}
+
+/// The entity tags of a request's `If-None-Match` headers, if it has any.
+fn if_none_match(headers: &HeaderMap) -> Option<String> {
+    let tags: Vec<&str> = headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    (!tags.is_empty()).then(|| tags.join(","))
+}
+
+/// Whether `etag` is among `tags`, compared weakly as `If-None-Match`
+/// requires.
+fn etag_matches(tags: &str, etag: &str) -> bool {
+    tags.split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+fn not_modified(etag: &str) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+fn job_lost(id: &str) -> FlowError {
+    FlowError::NodeFailed(format!("Job {id} ended without a result"))
+}
+
+/// A `202 Accepted` answer pointing at a job.
+fn accepted(jobs: &JobQueue, id: &str) -> Response {
+    let status = jobs.get(id).map(|job| job.status);
+    let body = json!({ "id": id, "status": status });
+    let mut response = (StatusCode::ACCEPTED, Json(body)).into_response();
+    if let Ok(location) = HeaderValue::from_str(&format!("/jobs/{id}")) {
+        response.headers_mut().insert(header::LOCATION, location);
+    }
+    response
+}
+
+async fn stream_flow(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    session: Option<Extension<ActiveSession>>,
+    Json(input): Json<Value>,
+) -> Response {
+    let Some(flow) = state.registry.get(&name) else {
+        return not_found(&name);
+    };
+    if let Some(response) = reject_invalid(&state.registry, &name, &input) {
+        return response;
+    }
+    let session = session.map(|Extension(session)| session);
+    let input = match with_session(session.as_ref(), input).await {
+        Ok(input) => input,
+        Err(e) => return e.into_response(),
+    };
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let run = tokio::spawn(async move {
+        if let Err(e) = flow.execute_with_events(input, &sender).await {
+            tracing::error!("Flow '{name}' failed: {e}");
+        }
+    });
+
+    // The stream owns the run, so a client that disconnects cancels it, as
+    // with the execute route
+    let run = AbortOnDrop(run.abort_handle());
+    let events = futures::stream::unfold((receiver, run), |(mut receiver, run)| async move {
+        let event = receiver.recv().await?;
+        Some((Ok::<_, Infallible>(sse_event(&event)), (receiver, run)))
+    });
+    Sse::new(events)
+        .keep_alive(
+            KeepAlive::new()
+                .interval(state.options.keep_alive)
+                .text("keep-alive"),
+        )
+        .into_response()
+}
+
+fn sse_event(event: &ExecutionEvent) -> Event {
+    let data = serde_json::to_value(event).unwrap_or(Value::Null);
+    let kind = data["type"].as_str().unwrap_or("event").to_string();
+    Event::default().event(kind).data(data.to_string())
+}
+
+async fn submit_job(
+    State(state): State<Arc<AppState>>,
+    session: Option<Extension<ActiveSession>>,
+    Json(request): Json<JobRequest>,
+) -> Response {
+    let Some(flow) = state.registry.get(&request.flow) else {
+        return not_found(&request.flow);
+    };
+    if let Some(response) = reject_invalid(&state.registry, &request.flow, &request.input) {
+        return response;
+    }
+    let session = session.map(|Extension(session)| session);
+    let input = match with_session(session.as_ref(), request.input).await {
+        Ok(input) => input,
+        Err(e) => return e.into_response(),
+    };
+    let id = state.options.jobs.submit(request.flow, flow, input);
+    accepted(&state.options.jobs, &id)
+}
+
+async fn get_job(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    match state.options.jobs.get(&id) {
+        Some(job) => Json::<Job>(job).into_response(),
+        None => FlowError::NotFound(format!("Job '{id}'")).into_response(),
+    }
+}
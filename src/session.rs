@@ -0,0 +1,482 @@
+//! Sessions that carry conversations across HTTP requests.
+//!
+//! A [`Session`] holds a conversation's messages and a free-form context
+//! object, kept in a [`SessionStore`] under a session id:
+//! [`InMemorySessionStore`] for a single process, or, with the `redis`
+//! feature, [`RedisStore`](crate::checkpoint::RedisStore) for replicas that
+//! share sessions.
+//!
+//! [`Sessions`] is a middleware layer that reads the id from the
+//! `X-Session-Id` header or the `rustyflow_session` cookie, starts a new
+//! session when there is none, and sends the id back in both. A new session
+//! is only stored once a request runs a flow in it, so requests such as
+//! listing flows or health checks leave nothing behind, and only ids the
+//! server stored are continued: an id with nothing stored under it, such as
+//! one a client made up, is replaced by a fresh random one. Behind
+//! [`RequireAuth`](crate::auth::RequireAuth), sessions are stored under the
+//! caller's [`Principal`] id as well, so no caller can reach another's
+//! sessions, even knowing their ids. The
+//! [server](crate::server) routes then run flows with the session's
+//! [key](ActiveSession::key) and stored context added to the input as
+//! `session` and `context`, and save the `context` object a flow returns. A
+//! [`MemoryNode`](crate::memory::MemoryNode) backed by [`SessionMemory`]
+//! over the same store picks the conversation up from the `session` field,
+//! so multi-turn chat flows need no state of their own.
+
+use crate::auth::Principal;
+use crate::error::FlowError;
+use crate::llm::Message;
+use crate::memory::Memory;
+use async_trait::async_trait;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The header a session id is read from and sent back in.
+pub const SESSION_HEADER: &str = "x-session-id";
+
+/// The cookie a session id is read from and set in.
+pub const SESSION_COOKIE: &str = "rustyflow_session";
+
+const MAX_ID_LEN: usize = 128;
+
+/// The state kept for one session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// The conversation so far.
+    #[serde(default)]
+    pub messages: Vec<Message>,
+    /// Application data carried from one request to the next.
+    #[serde(default)]
+    pub context: Map<String, Value>,
+}
+
+/// Storage for sessions, keyed by session id.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Load a session.
+    ///
+    /// # Returns
+    ///
+    /// The session, or `None` if no session is stored under `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read.
+    async fn load(&self, id: &str) -> Result<Option<Session>, FlowError>;
+
+    /// Store a session, replacing any stored under `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    async fn save(&self, id: &str, session: &Session) -> Result<(), FlowError>;
+
+    /// Remove a session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    async fn delete(&self, id: &str) -> Result<(), FlowError>;
+}
+
+/// Sessions idle this long are dropped from an [`InMemorySessionStore`],
+/// unless another TTL is set.
+const DEFAULT_IDLE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The most sessions an [`InMemorySessionStore`] holds, unless another cap
+/// is set.
+const DEFAULT_MAX_SESSIONS: usize = 10_000;
+
+/// A [`SessionStore`] held in process memory.
+///
+/// Sessions not loaded or saved for the idle TTL, a day by default, are
+/// dropped. Once the store holds its maximum number of sessions, 10,000 by
+/// default, saving a new one first drops the expired ones and then, if it is
+/// still full, the one idle the longest.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::session::{InMemorySessionStore, Session, SessionStore};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), rustyflow::FlowError> {
+/// let store = InMemorySessionStore::new()
+///     .with_idle_ttl(Duration::from_millis(20))
+///     .with_max_sessions(2);
+/// for id in ["a", "b", "c"] {
+///     store.save(id, &Session::default()).await?;
+/// }
+/// // The session idle the longest made room for the third
+/// assert!(store.load("a").await?.is_none());
+/// assert_eq!(store.len(), 2);
+///
+/// tokio::time::sleep(Duration::from_millis(30)).await;
+/// assert!(store.load("c").await?.is_none());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InMemorySessionStore {
+    sessions: Mutex<HashMap<String, (Session, Instant)>>,
+    idle_ttl: Duration,
+    max_sessions: usize,
+}
+
+impl Default for InMemorySessionStore {
+    fn default() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            idle_ttl: DEFAULT_IDLE_TTL,
+            max_sessions: DEFAULT_MAX_SESSIONS,
+        }
+    }
+}
+
+impl InMemorySessionStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop sessions not loaded or saved for `ttl`.
+    pub fn with_idle_ttl(mut self, ttl: Duration) -> Self {
+        self.idle_ttl = ttl;
+        self
+    }
+
+    /// Hold at most `max` sessions, at least 1.
+    pub fn with_max_sessions(mut self, max: usize) -> Self {
+        self.max_sessions = max.max(1);
+        self
+    }
+
+    /// The number of sessions held, including expired ones not yet dropped.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    /// Whether the store holds no sessions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn load(&self, id: &str) -> Result<Option<Session>, FlowError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some((session, used)) = sessions.get_mut(id) else {
+            return Ok(None);
+        };
+        if used.elapsed() >= self.idle_ttl {
+            sessions.remove(id);
+            return Ok(None);
+        }
+        *used = Instant::now();
+        Ok(Some(session.clone()))
+    }
+
+    async fn save(&self, id: &str, session: &Session) -> Result<(), FlowError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if !sessions.contains_key(id) && sessions.len() >= self.max_sessions {
+            sessions.retain(|_, (_, used)| used.elapsed() < self.idle_ttl);
+            if sessions.len() >= self.max_sessions {
+                let oldest = sessions
+                    .iter()
+                    .min_by_key(|(_, (_, used))| *used)
+                    .map(|(id, _)| id.clone());
+                if let Some(oldest) = oldest {
+                    sessions.remove(&oldest);
+                }
+            }
+        }
+        sessions.insert(id.to_string(), (session.clone(), Instant::now()));
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), FlowError> {
+        self.sessions.lock().unwrap().remove(id);
+        Ok(())
+    }
+}
+
+/// A [`Memory`] that keeps conversations in the sessions of a
+/// [`SessionStore`], with the session id as the memory's session.
+///
+/// Appending loads and saves the whole session, so two requests of one
+/// session that finish at the same time may lose one of their turns.
+pub struct SessionMemory {
+    store: Arc<dyn SessionStore>,
+}
+
+impl SessionMemory {
+    /// Create a memory over the sessions of `store`.
+    pub fn new(store: Arc<dyn SessionStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Memory for SessionMemory {
+    async fn messages(&self, session: &str) -> Result<Vec<Message>, FlowError> {
+        let stored = self.store.load(session).await?;
+        Ok(stored.map(|stored| stored.messages).unwrap_or_default())
+    }
+
+    async fn append(&self, session: &str, messages: Vec<Message>) -> Result<(), FlowError> {
+        let mut stored = self.store.load(session).await?.unwrap_or_default();
+        stored.messages.extend(messages);
+        self.store.save(session, &stored).await
+    }
+}
+
+/// The session of the current request, added as a request extension by
+/// [`Sessions`].
+#[derive(Clone)]
+pub struct ActiveSession {
+    /// The session id, as sent to the client.
+    pub id: String,
+    key: String,
+    store: Arc<dyn SessionStore>,
+}
+
+impl ActiveSession {
+    /// The key the session is stored under: its id, prefixed with the
+    /// authenticated caller's id, if any, as `caller/id`.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Load the session, storing an empty one first if nothing is stored
+    /// yet, so that its id is continued by later requests.
+    ///
+    /// The [server](crate::server) routes open the session of every request
+    /// that runs a flow; other requests leave nothing in the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read or written.
+    pub async fn open(&self) -> Result<Session, FlowError> {
+        match self.store.load(&self.key).await? {
+            Some(session) => Ok(session),
+            None => {
+                let session = Session::default();
+                self.store.save(&self.key, &session).await?;
+                Ok(session)
+            }
+        }
+    }
+
+    /// Load the session, or an empty one if nothing is stored yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read.
+    pub async fn load(&self) -> Result<Session, FlowError> {
+        Ok(self.store.load(&self.key).await?.unwrap_or_default())
+    }
+
+    /// Replace the session's context, keeping its messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read or written.
+    pub async fn save_context(&self, context: Map<String, Value>) -> Result<(), FlowError> {
+        let mut session = self.load().await?;
+        session.context = context;
+        self.store.save(&self.key, &session).await
+    }
+}
+
+/// Middleware that gives every request of a router a session.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use axum::body::Body;
+/// use axum::http::Request;
+/// use rustyflow::memory::MemoryNode;
+/// use rustyflow::server::{self, FlowRegistry};
+/// use rustyflow::session::{InMemorySessionStore, SessionMemory, SessionStore, Sessions};
+/// use rustyflow::{Flow, FlowError, Node};
+/// use serde_json::{json, Value};
+/// use std::sync::Arc;
+/// use tower::ServiceExt;
+///
+/// /// Replies with how many messages the conversation has.
+/// struct Chat;
+///
+/// #[async_trait]
+/// impl Node for Chat {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(format!("message {}", input["messages"].as_array().unwrap().len())))
+///     }
+/// }
+///
+/// /// Keeps no memory and returns no context.
+/// struct Ping;
+///
+/// #[async_trait]
+/// impl Node for Ping {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         Ok(json!("pong"))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let store: Arc<dyn SessionStore> = Arc::new(InMemorySessionStore::new());
+/// let memory = Arc::new(SessionMemory::new(store.clone()));
+/// let chat = Flow::new(vec![Box::new(MemoryNode::new(memory, Box::new(Chat)))]);
+/// let ping = Flow::new(vec![Box::new(Ping)]);
+/// let flows = FlowRegistry::new().with_flow("chat", chat).with_flow("ping", ping);
+/// let app = server::router(Arc::new(flows));
+/// let app = Sessions::new(store.clone()).apply(app);
+///
+/// let request_to = |flow: &str, session: Option<&str>| {
+///     let mut request = Request::post(format!("/flows/{flow}/execute"))
+///         .header("content-type", "application/json");
+///     if let Some(session) = session {
+///         request = request.header("x-session-id", session);
+///     }
+///     request.body(Body::from(r#"{"message": "hi"}"#)).unwrap()
+/// };
+/// let request = |session| request_to("chat", session);
+///
+/// // The first request starts a session; sending its id continues it
+/// let response = app.clone().oneshot(request(None)).await.unwrap();
+/// let session = response.headers()["x-session-id"].to_str().unwrap().to_string();
+/// assert!(response.headers()["set-cookie"].to_str().unwrap().contains(&session));
+///
+/// let response = app.clone().oneshot(request(Some(&session))).await.unwrap();
+/// let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+/// assert_eq!(body, r#""message 3""#);
+/// assert_eq!(store.load(&session).await.unwrap().unwrap().messages.len(), 4);
+///
+/// // Ids the server never issued start a session of their own
+/// let response = app.clone().oneshot(request(Some("1"))).await.unwrap();
+/// assert_ne!(response.headers()["x-session-id"], "1");
+///
+/// // A session is kept even when its flow stores nothing in it
+/// let response = app.clone().oneshot(request_to("ping", None)).await.unwrap();
+/// let session = response.headers()["x-session-id"].to_str().unwrap().to_string();
+/// let response = app.clone().oneshot(request_to("ping", Some(&session))).await.unwrap();
+/// assert_eq!(response.headers()["x-session-id"], session.as_str());
+///
+/// // Requests that run no flow store no session
+/// let response = app.oneshot(Request::get("/flows").body(Body::empty()).unwrap()).await.unwrap();
+/// let session = response.headers()["x-session-id"].to_str().unwrap();
+/// assert!(store.load(session).await.unwrap().is_none());
+/// # }
+/// ```
+pub struct Sessions {
+    store: Arc<dyn SessionStore>,
+}
+
+impl Sessions {
+    /// Keep the sessions of requests in `store`.
+    pub fn new(store: Arc<dyn SessionStore>) -> Self {
+        Self { store }
+    }
+
+    /// Wrap every route of `router` in this middleware.
+    pub fn apply(self, router: Router) -> Router {
+        router.layer(middleware::from_fn_with_state(Arc::new(self), attach))
+    }
+}
+
+async fn attach(
+    State(sessions): State<Arc<Sessions>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let from_cookie = cookie_id(request.headers());
+    let caller = request
+        .extensions()
+        .get::<Principal>()
+        .map(|principal| principal.id.clone());
+    let presented = header_id(request.headers()).or_else(|| from_cookie.clone());
+    let issued = match presented {
+        Some(id) => {
+            let key = store_key(caller.as_deref(), &id);
+            match sessions.store.load(&key).await {
+                Ok(stored) => stored.map(|_| (id, key)),
+                Err(e) => return e.into_response(),
+            }
+        }
+        None => None,
+    };
+    let (id, key) = match issued {
+        Some(issued) => issued,
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            let key = store_key(caller.as_deref(), &id);
+            (id, key)
+        }
+    };
+    request.extensions_mut().insert(ActiveSession {
+        id: id.clone(),
+        key,
+        store: Arc::clone(&sessions.store),
+    });
+
+    let mut response = next.run(request).await;
+    // Ids are checked to be header-safe before they are used
+    let value = HeaderValue::from_str(&id).expect("session ids are valid header values");
+    let headers = response.headers_mut();
+    headers.insert(SESSION_HEADER, value);
+    if from_cookie.as_deref() != Some(id.as_str()) {
+        let cookie = format!("{SESSION_COOKIE}={id}; Path=/; HttpOnly; SameSite=Lax");
+        if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+            headers.append(header::SET_COOKIE, cookie);
+        }
+    }
+    response
+}
+
+/// Scope `id` to `caller`. Ids hold no `/`, so keys of different callers
+/// never meet.
+fn store_key(caller: Option<&str>, id: &str) -> String {
+    match caller {
+        Some(caller) => format!("{caller}/{id}"),
+        None => id.to_string(),
+    }
+}
+
+fn header_id(headers: &HeaderMap) -> Option<String> {
+    let id = headers.get(SESSION_HEADER)?.to_str().ok()?;
+    valid_id(id.trim())
+}
+
+fn cookie_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .and_then(|(_, id)| valid_id(id))
+}
+
+/// Accept ids of letters, digits, `-` and `_`, so that no caller-chosen id
+/// can reach a header or a store key unescaped.
+fn valid_id(id: &str) -> Option<String> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_');
+    valid.then(|| id.to_string())
+}
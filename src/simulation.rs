@@ -0,0 +1,383 @@
+//! Multi-turn simulations of users talking to a conversational flow.
+//!
+//! A [`Simulation`] plays a [`SimulatedUser`] against a flow for a number of
+//! turns and evaluates the resulting transcript, so chat agents can be tested
+//! end to end without a person at the keyboard. [`ScriptedUser`] sends a
+//! fixed list of messages; [`LlmUser`] has a language model act out a
+//! persona with a goal, and end the conversation once the goal is met.
+//!
+//! On each turn the flow is called with the conversation so far as
+//! `{"messages": [...]}`, the input [`ChatNode`](crate::llm::ChatNode)
+//! accepts, and its reply is read from the output: a string, the `content`
+//! of a [`ChatResponse`](crate::llm::ChatResponse), an `answer` field, or
+//! the field [`Simulation::with_reply_pointer`] points at.
+//!
+//! The finished transcript is checked with Rust closures and evaluated by
+//! nodes such as a [`Judge`](crate::judge::Judge), which receive
+//! `{"answer": <transcript as text>, "task": <task>, "transcript": [...]}`.
+
+use crate::error::FlowError;
+use crate::llm::{record_usage, ChatModel, ChatRequest, Message, Role};
+use crate::node::Node;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+const DEFAULT_MAX_TURNS: usize = 10;
+
+/// What an [`LlmUser`] replies with to end the conversation.
+const END_MARKER: &str = "[END]";
+
+/// The user side of a simulated conversation.
+#[async_trait]
+pub trait SimulatedUser: Send + Sync {
+    /// Write the user's next message.
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript` - The conversation so far, empty before the first turn
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(message))` - The next user message
+    /// * `Ok(None)` - The user ends the conversation
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message cannot be produced.
+    async fn next_message(&self, transcript: &[Message]) -> Result<Option<String>, FlowError>;
+}
+
+/// A user that sends a fixed list of messages, one per turn, and ends the
+/// conversation once they run out.
+pub struct ScriptedUser {
+    messages: Vec<String>,
+}
+
+impl ScriptedUser {
+    /// Create a user that sends `messages` in order.
+    pub fn new<S: Into<String>>(messages: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            messages: messages.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl SimulatedUser for ScriptedUser {
+    async fn next_message(&self, transcript: &[Message]) -> Result<Option<String>, FlowError> {
+        let sent = transcript
+            .iter()
+            .filter(|message| message.role == Role::User)
+            .count();
+        Ok(self.messages.get(sent).cloned())
+    }
+}
+
+/// A user played by a language model, following a persona and a goal.
+///
+/// The model sees the conversation with the roles swapped, so the flow's
+/// replies arrive as user messages, and ends the conversation by replying
+/// `[END]`.
+pub struct LlmUser<M: ChatModel> {
+    model: M,
+    persona: String,
+    goal: Option<String>,
+}
+
+impl<M: ChatModel> LlmUser<M> {
+    /// Create a user played by `model`.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The model that writes the user's messages
+    /// * `persona` - Who the user is, e.g. "An impatient customer whose
+    ///   order arrived broken"
+    pub fn new(model: M, persona: impl Into<String>) -> Self {
+        Self {
+            model,
+            persona: persona.into(),
+            goal: None,
+        }
+    }
+
+    /// Set what the user wants out of the conversation; once it is met the
+    /// user ends the conversation.
+    pub fn with_goal(mut self, goal: impl Into<String>) -> Self {
+        self.goal = Some(goal.into());
+        self
+    }
+
+    fn system_prompt(&self) -> String {
+        let mut prompt = format!(
+            "You are role-playing a user talking to an assistant. Stay in character.\n\n\
+             Persona: {}\n",
+            self.persona
+        );
+        if let Some(goal) = &self.goal {
+            prompt.push_str(&format!("Goal: {goal}\n"));
+        }
+        prompt.push_str(&format!(
+            "\nWrite only the user's next message. Once the goal is met, or the conversation \
+             cannot go anywhere, reply with {END_MARKER} alone."
+        ));
+        prompt
+    }
+}
+
+#[async_trait]
+impl<M: ChatModel> SimulatedUser for LlmUser<M> {
+    async fn next_message(&self, transcript: &[Message]) -> Result<Option<String>, FlowError> {
+        let mut messages = vec![Message::system(self.system_prompt())];
+        messages.push(Message::user("Start the conversation."));
+        for message in transcript {
+            match message.role {
+                Role::User => messages.push(Message::assistant(message.content.clone())),
+                Role::Assistant if !message.content.is_empty() => {
+                    messages.push(Message::user(message.content.clone()))
+                }
+                _ => {}
+            }
+        }
+        let reply = record_usage(self.model.chat(ChatRequest::new(messages)).await?)
+            .message
+            .content;
+        let reply = reply.trim();
+        if reply.is_empty() || reply.contains(END_MARKER) {
+            return Ok(None);
+        }
+        Ok(Some(reply.to_string()))
+    }
+}
+
+type Check = Box<dyn Fn(&[Message]) -> bool + Send + Sync>;
+
+/// The outcome of a [`Simulation`].
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationReport {
+    /// The conversation, user and assistant messages alternating.
+    pub transcript: Vec<Message>,
+    /// The number of turns played.
+    pub turns: usize,
+    /// Whether the user ended the conversation before the turn limit.
+    pub completed: bool,
+    /// The result of each check, by name.
+    pub checks: BTreeMap<String, bool>,
+    /// The output of each evaluator, by name.
+    pub evaluations: BTreeMap<String, Value>,
+}
+
+impl SimulationReport {
+    /// Whether every check passed.
+    pub fn passed(&self) -> bool {
+        self.checks.values().all(|&passed| passed)
+    }
+}
+
+/// A simulated conversation between a [`SimulatedUser`] and a flow.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::simulation::{ScriptedUser, Simulation};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// /// A support bot that answers the latest message.
+/// struct SupportBot;
+///
+/// #[async_trait]
+/// impl Node for SupportBot {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let messages = input["messages"].as_array().unwrap();
+///         let last = messages.last().unwrap()["content"].as_str().unwrap();
+///         let reply = if last.contains("refund") {
+///             "I have issued a refund for order 1042."
+///         } else {
+///             "Hello! How can I help?"
+///         };
+///         Ok(json!(reply))
+///     }
+/// }
+///
+/// /// Counts the assistant's turns, standing in for a judge.
+/// struct Turns;
+///
+/// #[async_trait]
+/// impl Node for Turns {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let transcript = input["transcript"].as_array().unwrap();
+///         let replies = transcript.iter().filter(|m| m["role"] == "assistant").count();
+///         Ok(json!({"replies": replies}))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let user = ScriptedUser::new(["Hi there", "My order 1042 is broken, I want a refund"]);
+/// let report = Simulation::new(Box::new(SupportBot), Box::new(user))
+///     .with_max_turns(5)
+///     .with_task("Get a refund for a broken order")
+///     .with_check("refunded", |transcript| {
+///         transcript.iter().any(|m| m.content.contains("issued a refund"))
+///     })
+///     .with_evaluator("turns", Box::new(Turns))
+///     .run()
+///     .await?;
+///
+/// assert_eq!(report.turns, 2);
+/// assert!(report.completed);
+/// assert!(report.passed());
+/// assert_eq!(report.evaluations["turns"], json!({"replies": 2}));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Simulation {
+    flow: Box<dyn Node>,
+    user: Box<dyn SimulatedUser>,
+    max_turns: usize,
+    reply_pointer: Option<String>,
+    task: Option<String>,
+    checks: Vec<(String, Check)>,
+    evaluators: Vec<(String, Box<dyn Node>)>,
+}
+
+impl Simulation {
+    /// Create a simulation of `user` talking to `flow` for up to 10 turns.
+    ///
+    /// # Arguments
+    ///
+    /// * `flow` - The conversational node or flow under test
+    /// * `user` - The simulated user
+    pub fn new(flow: Box<dyn Node>, user: Box<dyn SimulatedUser>) -> Self {
+        Self {
+            flow,
+            user,
+            max_turns: DEFAULT_MAX_TURNS,
+            reply_pointer: None,
+            task: None,
+            checks: Vec::new(),
+            evaluators: Vec::new(),
+        }
+    }
+
+    /// Stop after `max_turns` user messages, at least 1.
+    pub fn with_max_turns(mut self, max_turns: usize) -> Self {
+        self.max_turns = max_turns.max(1);
+        self
+    }
+
+    /// Read the flow's reply from the string at this JSON pointer.
+    pub fn with_reply_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.reply_pointer = Some(pointer.into());
+        self
+    }
+
+    /// Describe what the conversation should achieve, for evaluators.
+    pub fn with_task(mut self, task: impl Into<String>) -> Self {
+        self.task = Some(task.into());
+        self
+    }
+
+    /// Check the finished transcript with a closure.
+    pub fn with_check<F>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(&[Message]) -> bool + Send + Sync + 'static,
+    {
+        self.checks.push((name.into(), Box::new(check)));
+        self
+    }
+
+    /// Evaluate the finished transcript with a node, such as a
+    /// [`Judge`](crate::judge::Judge).
+    pub fn with_evaluator(mut self, name: impl Into<String>, node: Box<dyn Node>) -> Self {
+        self.evaluators.push((name.into(), node));
+        self
+    }
+
+    /// Play the conversation and evaluate it.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error of the user, the flow or an evaluator, or
+    /// `FlowError::NodeFailed` if a reply cannot be found in the flow's
+    /// output.
+    pub async fn run(&self) -> Result<SimulationReport, FlowError> {
+        let mut transcript = Vec::new();
+        let mut turns = 0;
+        let mut completed = false;
+
+        while turns < self.max_turns {
+            let Some(message) = self.user.next_message(&transcript).await? else {
+                completed = true;
+                break;
+            };
+            transcript.push(Message::user(message));
+            turns += 1;
+
+            let output = self.flow.call(json!({ "messages": transcript })).await?;
+            transcript.push(Message::assistant(self.reply(&output)?));
+            tracing::debug!("Simulation finished turn {turns}");
+        }
+
+        let checks = self
+            .checks
+            .iter()
+            .map(|(name, check)| (name.clone(), check(&transcript)))
+            .collect();
+        let input = json!({
+            "answer": render(&transcript),
+            "task": self.task,
+            "transcript": transcript,
+        });
+        let mut evaluations = BTreeMap::new();
+        for (name, node) in &self.evaluators {
+            evaluations.insert(name.clone(), node.call(input.clone()).await?);
+        }
+
+        Ok(SimulationReport {
+            transcript,
+            turns,
+            completed,
+            checks,
+            evaluations,
+        })
+    }
+
+    fn reply(&self, output: &Value) -> Result<String, FlowError> {
+        let reply = match &self.reply_pointer {
+            Some(pointer) => output.pointer(pointer),
+            None if output.is_string() => Some(output),
+            None => output
+                .pointer("/message/content")
+                .or_else(|| output.get("answer")),
+        };
+        reply
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| {
+                FlowError::NodeFailed(format!(
+                    "No reply found in the flow's output: {}",
+                    crate::error::input_snippet(output)
+                ))
+            })
+    }
+}
+
+/// The transcript as plain text, one `User:` or `Assistant:` paragraph per
+/// message.
+fn render(transcript: &[Message]) -> String {
+    transcript
+        .iter()
+        .map(|message| {
+            let speaker = match message.role {
+                Role::User => "User",
+                _ => "Assistant",
+            };
+            format!("{speaker}: {}", message.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
@@ -0,0 +1,168 @@
+//! Nodes with managed, checkpointable state.
+//!
+//! Nodes are shared between concurrent runs, so any state they keep, such as
+//! counters, running totals or caches, needs interior mutability. Rather
+//! than wrapping fields in their own locks, implement [`StatefulNode`] and
+//! wrap the node in [`Stateful`]. The framework then:
+//!
+//! - keeps the state behind an `Arc<RwLock<_>>` and hands the node a
+//!   `&mut` reference for each call, so calls on one node run one at a time;
+//! - exposes it to other tasks through a cloneable [`StateHandle`];
+//! - snapshots it with every checkpoint of
+//!   [`Flow::execute_resumable`](crate::flow::Flow::execute_resumable) and
+//!   restores it when a run resumes.
+//!
+//! Changes a call makes before returning an error are kept. Because the lock
+//! is held for the whole call, avoid slow awaits inside stateful calls, or
+//! move the slow work to a stateless node earlier in the flow.
+
+use crate::error::FlowError;
+use crate::node::{short_type_name, Node};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+/// A node whose state is managed by the framework.
+///
+/// Wrap it in [`Stateful`] to use it as a [`Node`].
+#[async_trait]
+pub trait StatefulNode: Send + Sync {
+    /// The node's state. It must round-trip through JSON to be checkpointed.
+    type State: Serialize + DeserializeOwned + Default + Send + Sync;
+
+    /// Process `input` with exclusive access to the state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The node's state, to read and update
+    /// * `input` - The JSON input value to process
+    async fn call(&self, state: &mut Self::State, input: Value) -> Result<Value, FlowError>;
+
+    /// A human-readable name for the node, used as its [`Node::name`].
+    fn name(&self) -> &str {
+        short_type_name::<Self>()
+    }
+}
+
+/// A shared reference to the state of a [`Stateful`] node.
+pub struct StateHandle<S> {
+    state: Arc<RwLock<S>>,
+}
+
+impl<S> Clone for StateHandle<S> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<S> StateHandle<S> {
+    /// Wait for any running call to finish and borrow the state.
+    pub async fn read(&self) -> RwLockReadGuard<'_, S> {
+        self.state.read().await
+    }
+
+    /// Wait for any running call to finish and update the state.
+    pub async fn update<R>(&self, f: impl FnOnce(&mut S) -> R) -> R {
+        f(&mut *self.state.write().await)
+    }
+}
+
+/// Adapts a [`StatefulNode`] into a [`Node`] that owns its state.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::checkpoint::InMemoryCheckpointStore;
+/// use rustyflow::state::{Stateful, StatefulNode};
+/// use rustyflow::{Flow, FlowError};
+/// use serde_json::{json, Value};
+/// use async_trait::async_trait;
+///
+/// /// Counts the words it has seen across calls.
+/// struct WordCounter;
+///
+/// #[async_trait]
+/// impl StatefulNode for WordCounter {
+///     type State = u64;
+///
+///     async fn call(&self, seen: &mut u64, input: Value) -> Result<Value, FlowError> {
+///         *seen += input.as_str().unwrap_or("").split_whitespace().count() as u64;
+///         Ok(json!({"text": input, "words_so_far": *seen}))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let counter = Stateful::new(WordCounter);
+/// let seen = counter.handle();
+/// let flow = Flow::new(vec![Box::new(counter)]);
+///
+/// flow.execute(json!("one two")).await?;
+/// let result = flow.execute(json!("three")).await?;
+/// assert_eq!(result["words_so_far"], 3);
+/// assert_eq!(*seen.read().await, 3);
+///
+/// // Checkpoints carry the state, and resuming a run restores it
+/// let store = InMemoryCheckpointStore::new();
+/// flow.execute_resumable("run-1", json!("four five"), &store).await?;
+/// seen.update(|count| *count = 0).await;
+/// flow.execute_resumable("run-1", json!("four five"), &store).await?;
+/// assert_eq!(*seen.read().await, 5);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Stateful<N: StatefulNode> {
+    node: N,
+    state: Arc<RwLock<N::State>>,
+}
+
+impl<N: StatefulNode> Stateful<N> {
+    /// Wrap `node`, starting from the default state.
+    pub fn new(node: N) -> Self {
+        Self {
+            node,
+            state: Arc::new(RwLock::new(N::State::default())),
+        }
+    }
+
+    /// Start from `state` instead of the default.
+    pub fn with_state(self, state: N::State) -> Self {
+        Self {
+            node: self.node,
+            state: Arc::new(RwLock::new(state)),
+        }
+    }
+
+    /// A handle for reading or updating the state from other tasks.
+    pub fn handle(&self) -> StateHandle<N::State> {
+        StateHandle {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+#[async_trait]
+impl<N: StatefulNode> Node for Stateful<N> {
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let mut state = self.state.write().await;
+        self.node.call(&mut state, input).await
+    }
+
+    fn name(&self) -> &str {
+        self.node.name()
+    }
+
+    async fn snapshot(&self) -> Result<Option<Value>, FlowError> {
+        Ok(Some(serde_json::to_value(&*self.state.read().await)?))
+    }
+
+    async fn restore(&self, state: Value) -> Result<(), FlowError> {
+        *self.state.write().await = serde_json::from_value(state)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+//! Generator nodes and streaming execution.
+//!
+//! This module provides the [`Source`] trait for nodes that emit multiple
+//! outputs over time, [`StreamFlow`] for piping those outputs through
+//! downstream nodes, and [`Collect`] for using a source inside a regular flow.
+
+use crate::error::FlowError;
+use crate::node::Node;
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use serde_json::Value;
+
+/// A boxed stream of node outputs.
+pub type ValueStream<'a> = BoxStream<'a, Result<Value, FlowError>>;
+
+/// A node that produces a sequence of outputs instead of a single value.
+///
+/// Sources model producers such as pagination crawlers or chunked readers.
+/// Items are pulled lazily, so a source only does work as fast as the
+/// downstream stages consume it.
+///
+/// # Example
+///
+/// ```rust
+/// use futures::stream::{self, StreamExt};
+/// use rustyflow::stream::{Source, ValueStream};
+/// use serde_json::{json, Value};
+///
+/// /// Emits one page number at a time up to `input["pages"]`.
+/// struct Pages;
+///
+/// impl Source for Pages {
+///     fn stream(&self, input: Value) -> ValueStream<'_> {
+///         let pages = input["pages"].as_u64().unwrap_or(0);
+///         stream::iter(1..=pages).map(|page| Ok(json!({ "page": page }))).boxed()
+///     }
+/// }
+/// ```
+pub trait Source: Send + Sync {
+    /// Start producing items for the given input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The JSON input that parameterizes the source
+    ///
+    /// # Returns
+    ///
+    /// A stream of items; an `Err` item ends the stream for consumers.
+    fn stream(&self, input: Value) -> ValueStream<'_>;
+}
+
+/// A pipeline that runs every item from a [`Source`] through a chain of nodes.
+///
+/// Each item passes through the nodes sequentially, just like a
+/// [`Flow`](crate::flow::Flow), and results are yielded in source order as
+/// soon as they are ready.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use futures::stream::{self, StreamExt};
+/// use rustyflow::stream::{Source, StreamFlow, ValueStream};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// struct Numbers;
+///
+/// impl Source for Numbers {
+///     fn stream(&self, _input: Value) -> ValueStream<'_> {
+///         stream::iter(1..=3).map(|n| Ok(json!(n))).boxed()
+///     }
+/// }
+///
+/// struct Square;
+///
+/// #[async_trait]
+/// impl Node for Square {
+///     async fn call(&self, input: Value) -> Result<Value, FlowError> {
+///         let n = input.as_i64().unwrap_or(0);
+///         Ok(json!(n * n))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let flow = StreamFlow::new(Box::new(Numbers), vec![Box::new(Square)]);
+/// let result = flow.execute(json!(null)).await?;
+/// assert_eq!(result, json!([1, 4, 9]));
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamFlow {
+    source: Box<dyn Source>,
+    nodes: Vec<Box<dyn Node>>,
+}
+
+impl StreamFlow {
+    /// Create a new streaming flow.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source producing items
+    /// * `nodes` - Nodes applied in sequence to each item
+    pub fn new(source: Box<dyn Source>, nodes: Vec<Box<dyn Node>>) -> Self {
+        Self { source, nodes }
+    }
+
+    /// Create a streaming flow whose items come from a node that can stream
+    /// its output, as reported by [`Node::as_streaming`].
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The streaming node producing items
+    /// * `nodes` - Nodes applied in sequence to each item
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if `node` cannot stream its output.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use futures::stream::{self, StreamExt};
+    /// use rustyflow::stream::{Collect, Source, StreamFlow, ValueStream};
+    /// use rustyflow::{FlowError, Node};
+    /// use serde_json::{json, Value};
+    ///
+    /// struct Numbers;
+    ///
+    /// impl Source for Numbers {
+    ///     fn stream(&self, _input: Value) -> ValueStream<'_> {
+    ///         stream::iter(1..=3).map(|n| Ok(json!(n))).boxed()
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FlowError> {
+    /// // A node loaded as a plain `Box<dyn Node>` can still be streamed
+    /// let node: Box<dyn Node> = Box::new(Collect::new(Numbers));
+    /// let flow = StreamFlow::from_node(node, Vec::new())?;
+    /// assert_eq!(flow.execute(json!(null)).await?, json!([1, 2, 3]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_node(node: Box<dyn Node>, nodes: Vec<Box<dyn Node>>) -> Result<Self, FlowError> {
+        if node.as_streaming().is_none() {
+            return Err(FlowError::NodeFailed(format!(
+                "Node '{}' cannot stream its output",
+                node.name()
+            )));
+        }
+        Ok(Self::new(Box::new(StreamingNode(node)), nodes))
+    }
+
+    async fn process(&self, mut item: Value) -> Result<Value, FlowError> {
+        for node in &self.nodes {
+            item = node.call(item).await?;
+        }
+        Ok(item)
+    }
+
+    /// Stream processed items as they become available.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input passed to the source
+    pub fn stream(&self, input: Value) -> ValueStream<'_> {
+        self.source
+            .stream(input)
+            .and_then(move |item| self.process(item))
+            .boxed()
+    }
+
+    /// Run the flow to completion and collect all items into a JSON array.
+    ///
+    /// # Returns
+    ///
+    /// A JSON array of processed items, or the first error encountered.
+    pub async fn execute(&self, input: Value) -> Result<Value, FlowError> {
+        let items: Vec<Value> = self.stream(input).try_collect().await?;
+        Ok(Value::Array(items))
+    }
+}
+
+impl Source for StreamFlow {
+    fn stream(&self, input: Value) -> ValueStream<'_> {
+        StreamFlow::stream(self, input)
+    }
+}
+
+/// A node known to stream, used as the source of a [`StreamFlow`].
+struct StreamingNode(Box<dyn Node>);
+
+impl Source for StreamingNode {
+    fn stream(&self, input: Value) -> ValueStream<'_> {
+        match self.0.as_streaming() {
+            Some(source) => source.stream(input),
+            None => futures::stream::once(async {
+                Err(FlowError::NodeFailed(format!(
+                    "Node '{}' cannot stream its output",
+                    self.0.name()
+                )))
+            })
+            .boxed(),
+        }
+    }
+}
+
+/// A node that drains a [`Source`] into a JSON array.
+///
+/// `Collect` lets a generator be used anywhere a regular [`Node`] is
+/// expected, such as inside a [`Flow`](crate::flow::Flow).
+pub struct Collect<S: Source> {
+    source: S,
+}
+
+impl<S: Source> Collect<S> {
+    /// Create a node that collects every item produced by `source`.
+    pub fn new(source: S) -> Self {
+        Self { source }
+    }
+}
+
+#[async_trait]
+impl<S: Source> Node for Collect<S> {
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let items: Vec<Value> = self.source.stream(input).try_collect().await?;
+        Ok(Value::Array(items))
+    }
+
+    fn as_streaming(&self) -> Option<&dyn Source> {
+        Some(&self.source)
+    }
+}
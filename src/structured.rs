@@ -0,0 +1,250 @@
+//! Reliable structured output from language models.
+//!
+//! This module provides [`StructuredOutput`], a node that turns raw model text
+//! into JSON matching a Rust type, repairing common formatting mistakes and
+//! optionally asking the model to correct itself.
+
+use crate::constraint::Constraint;
+use crate::error::FlowError;
+use crate::llm::{extract_json, record_usage, ChatModel, ChatRequest, Message};
+use crate::node::{Node, SchemaProvider};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+
+/// A node that parses model output into a typed structure.
+///
+/// The input is either a string of raw model text or a serialized
+/// [`ChatResponse`](crate::llm::ChatResponse) from a
+/// [`ChatNode`](crate::llm::ChatNode). The node extracts JSON from prose or
+/// code fences, repairs trailing commas and truncated brackets, and checks
+/// that the result deserializes into `T`.
+///
+/// With [`StructuredOutput::with_retries`], a failed parse is sent back to the
+/// model together with the error and `T`'s JSON Schema, up to the configured
+/// number of times. If the model
+/// [supports](crate::llm::ChatModel::supports_constraint) JSON Schema
+/// constraints, the schema is also enforced on its correction.
+///
+/// The output is the validated JSON value, described by `T`'s JSON Schema
+/// as the node's [output schema](SchemaProvider::output_schema).
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::structured::StructuredOutput;
+/// use rustyflow::{FlowError, Node};
+/// use schemars::JsonSchema;
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize, JsonSchema)]
+/// struct Sentiment {
+///     label: String,
+///     confidence: f64,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let parser = StructuredOutput::<Sentiment>::new();
+/// let raw = "Sure! ```json\n{\"label\": \"positive\", \"confidence\": 0.9,}\n```";
+/// let result = parser.call(json!(raw)).await?;
+/// assert_eq!(result["label"], "positive");
+/// # Ok(())
+/// # }
+/// ```
+pub struct StructuredOutput<T> {
+    model: Option<Box<dyn ChatModel>>,
+    max_retries: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for StructuredOutput<T>
+where
+    T: DeserializeOwned + JsonSchema,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> StructuredOutput<T>
+where
+    T: DeserializeOwned + JsonSchema,
+{
+    /// Create a parser that only repairs and validates, without re-prompting.
+    pub fn new() -> Self {
+        Self {
+            model: None,
+            max_retries: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Ask `model` to fix invalid output up to `max_retries` times.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The chat model used to correct invalid output
+    /// * `max_retries` - The maximum number of correction attempts
+    pub fn with_retries(mut self, model: impl ChatModel + 'static, max_retries: usize) -> Self {
+        self.model = Some(Box::new(model));
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// The JSON Schema of `T`, as shown to the model when re-prompting.
+    pub fn schema() -> Value {
+        schemars::schema_for!(T).to_value()
+    }
+
+    /// Parse and validate raw text, returning the JSON value on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if no JSON can be recovered, or
+    /// `FlowError::SerdeError` if the JSON does not match `T`.
+    pub fn parse(text: &str) -> Result<Value, FlowError> {
+        let value = repair_json(text).ok_or_else(|| {
+            FlowError::NodeFailed("Model output did not contain valid JSON".to_string())
+        })?;
+        serde_json::from_value::<T>(value.clone())?;
+        Ok(value)
+    }
+
+    async fn correct(
+        &self,
+        model: &dyn ChatModel,
+        text: &str,
+        error: &FlowError,
+    ) -> Result<String, FlowError> {
+        let mut request = ChatRequest::new(vec![
+            Message::system(format!(
+                "You produce JSON that matches this JSON Schema exactly:\n{}\nRespond with JSON only.",
+                Self::schema()
+            )),
+            Message::user(format!(
+                "This output is invalid:\n{text}\n\nError: {error}\n\nReturn the corrected JSON."
+            )),
+        ]);
+        // Models that can enforce the schema cannot get it wrong again
+        let constraint = Constraint::JsonSchema(Self::schema());
+        if model.supports_constraint(&constraint) {
+            request = request.with_constraint(constraint);
+        }
+        Ok(record_usage(model.chat(request).await?).message.content)
+    }
+}
+
+/// Recover a JSON value from model text, fixing common mistakes.
+fn repair_json(text: &str) -> Option<Value> {
+    if let Some(value) = extract_json(text) {
+        return Some(value);
+    }
+
+    let start = text.find(['{', '['])?;
+    let mut candidate = String::with_capacity(text.len() - start);
+    let mut closers = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text[start..].chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            candidate.push(c);
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => closers.push('}'),
+            '[' => closers.push(']'),
+            '}' | ']' => {
+                // Drop a trailing comma before a closing bracket
+                let trimmed = candidate.trim_end().len();
+                if candidate[..trimmed].ends_with(',') {
+                    candidate.truncate(trimmed - 1);
+                }
+                closers.pop();
+            }
+            _ => {}
+        }
+        candidate.push(c);
+        if closers.is_empty() {
+            break;
+        }
+    }
+
+    // Close anything left open by truncated output
+    if in_string {
+        candidate.push('"');
+    }
+    let trimmed = candidate.trim_end().len();
+    if candidate[..trimmed].ends_with(',') {
+        candidate.truncate(trimmed - 1);
+    }
+    while let Some(closer) = closers.pop() {
+        candidate.push(closer);
+    }
+    serde_json::from_str(&candidate).ok()
+}
+
+#[async_trait]
+impl<T> Node for StructuredOutput<T>
+where
+    T: DeserializeOwned + JsonSchema,
+{
+    /// Parse the input text into `T`, re-prompting on failure if configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns the last parse or validation error once all retries are used,
+    /// or `FlowError::NodeFailed` if the input holds no text.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let mut text = match &input {
+            Value::String(text) => text.clone(),
+            other => other
+                .pointer("/message/content")
+                .or_else(|| other.get("content"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    FlowError::NodeFailed("Input must be model text or a chat response".to_string())
+                })?,
+        };
+
+        let mut attempt = 0;
+        loop {
+            let error = match Self::parse(&text) {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            match &self.model {
+                Some(model) if attempt < self.max_retries => {
+                    attempt += 1;
+                    crate::report::record_retry();
+                    text = self.correct(model.as_ref(), &text, &error).await?;
+                }
+                _ => return Err(error),
+            }
+        }
+    }
+
+    fn schema_provider(&self) -> Option<&dyn SchemaProvider> {
+        Some(self)
+    }
+}
+
+impl<T> SchemaProvider for StructuredOutput<T>
+where
+    T: DeserializeOwned + JsonSchema,
+{
+    fn output_schema(&self) -> Option<Value> {
+        Some(Self::schema())
+    }
+}
@@ -0,0 +1,186 @@
+//! Tracing instrumentation shared by the flow types.
+//!
+//! Every flow execution runs inside a `flow.execute` span carrying a generated
+//! `run_id`, and every node call inside a `node.call` span carrying the node's
+//! index and name. When the call finishes, its `duration_ms` is recorded, and
+//! `error` is recorded if it failed. Because node spans are children of the
+//! flow span, any subscriber that prints span context can correlate all log
+//! lines of one run.
+//!
+//! Failed spans also get the `otel.status_code` and `otel.status_message`
+//! fields, which `tracing-opentelemetry` maps to the OpenTelemetry span
+//! status (see [`otel`](crate::otel) with the `otel` feature). The same
+//! points feed the counters and histograms in [`metrics`](crate::metrics).
+//!
+//! Node calls go through the [`executor`](crate::executor) hooks inside
+//! their span; time spent waiting for admission is not part of
+//! `duration_ms`. Calls spawned onto their own task keep the span and the
+//! hooks of the task that spawned them.
+
+use crate::error::{self, FlowError};
+use crate::executor::{self, Hooks, NodeCall, NodeInput};
+use crate::metrics;
+use crate::node::Node;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::field::{display, Empty};
+use tracing::{Instrument, Span};
+
+/// The span and metric labels of one flow execution.
+pub(crate) struct FlowSpan {
+    span: Span,
+    flow: String,
+}
+
+impl FlowSpan {
+    /// The underlying tracing span.
+    pub(crate) fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Record that the execution started.
+    pub(crate) fn start(&self) {
+        metrics::execution_started(&self.flow);
+    }
+
+    /// Record the outcome, marking the span as failed on error.
+    pub(crate) fn finish<T>(&self, result: &Result<T, FlowError>) {
+        metrics::execution_finished(&self.flow, result.is_ok());
+        if let Err(e) = result {
+            record_error(&self.span, e);
+        }
+    }
+}
+
+/// Create the span for one execution of a flow.
+///
+/// `kind` names the flow type, such as `"Flow"` or `"Batch"`, and is also
+/// used as the flow name unless one is configured. A run id is generated
+/// unless the caller already has one.
+pub(crate) fn flow_span(
+    kind: &'static str,
+    name: Option<&str>,
+    nodes: usize,
+    run_id: Option<&str>,
+) -> FlowSpan {
+    let run_id = match run_id {
+        Some(run_id) => run_id.to_string(),
+        None => uuid::Uuid::new_v4().to_string(),
+    };
+    let flow = name.unwrap_or(kind);
+    let span = tracing::info_span!(
+        "flow.execute",
+        run_id,
+        flow.kind = kind,
+        flow.name = flow,
+        nodes,
+        error = Empty,
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+    );
+    FlowSpan {
+        span,
+        flow: flow.to_string(),
+    }
+}
+
+/// Run a flow body inside its span, recording its outcome.
+pub(crate) async fn in_flow_span<T, F>(flow: FlowSpan, body: F) -> Result<T, FlowError>
+where
+    F: Future<Output = Result<T, FlowError>>,
+{
+    flow.start();
+    let result = body.instrument(flow.span.clone()).await;
+    flow.finish(&result);
+    result
+}
+
+/// Mark a span as failed with the given error.
+fn record_error(span: &Span, error: &FlowError) {
+    span.record("error", display(error));
+    span.record("otel.status_code", "ERROR");
+    span.record("otel.status_message", display(error));
+}
+
+/// Call `node` inside a `node.call` span, recording its duration and error.
+///
+/// The input is an owned [`Value`], or an `Arc<Value>` shared with other
+/// calls, which reaches the node through [`Node::call_shared`].
+///
+/// Errors are returned wrapped in a [`FlowError::NodeError`] naming the node,
+/// its index and the start of its input.
+pub(crate) async fn call_node(
+    node: &dyn Node,
+    index: usize,
+    input: impl Into<NodeInput>,
+) -> Result<Value, FlowError> {
+    let input = input.into();
+    let span = tracing::info_span!(
+        "node.call",
+        node.index = index,
+        node.name = node.name(),
+        duration_ms = Empty,
+        error = Empty,
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+    );
+    let call = NodeCall { node, index };
+    let snippet = error::input_snippet(input.value());
+    let (result, elapsed) = async {
+        let _admission = match executor::admit(&call).await {
+            Ok(admission) => admission,
+            Err(e) => return (Err(e), Duration::ZERO),
+        };
+        let started = Instant::now();
+        let result = executor::execute(&call, input).await;
+        (result, started.elapsed())
+    }
+    .instrument(span.clone())
+    .await;
+
+    span.record("duration_ms", elapsed.as_secs_f64() * 1000.0);
+    metrics::node_called(node.name(), result.is_ok(), elapsed);
+    if let Err(e) = &result {
+        record_error(&span, e);
+    }
+    result.map_err(|e| e.in_node(node.name(), index, snippet))
+}
+
+/// Aborts a spawned task when dropped, so abandoning a call stops it.
+pub(crate) struct AbortOnDrop(pub(crate) tokio::task::AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Call `node` like [`call_node`], but on a task of its own, so calls made
+/// concurrently can run on different worker threads of the runtime.
+///
+/// The task is aborted if the returned future is dropped. A panic in the
+/// node is returned as a `FlowError::NodeFailed` for the node.
+pub(crate) async fn spawn_node(
+    node: Arc<dyn Node>,
+    index: usize,
+    input: impl Into<NodeInput>,
+) -> Result<Value, FlowError> {
+    let input = input.into();
+    let snippet = error::input_snippet(input.value());
+    let name = node.name().to_string();
+    let hooks = Hooks::current();
+    let task = tokio::spawn(
+        async move { hooks.scope(call_node(&*node, index, input)).await }
+            .instrument(Span::current()),
+    );
+    let _abort = AbortOnDrop(task.abort_handle());
+    match task.await {
+        Ok(result) => result,
+        Err(e) => {
+            Err(FlowError::NodeFailed(format!("Node task failed: {e}"))
+                .in_node(&name, index, snippet))
+        }
+    }
+}
@@ -1,13 +1,16 @@
 //! Type-safe tools with structured input and output.
 //!
-//! This module provides the [`Tool`] trait for type-safe operations and
-//! [`ToolNode`] for integrating tools into flows.
+//! This module provides the [`Tool`] trait for type-safe operations,
+//! [`ToolNode`] for integrating tools into flows, and [`ToolRegistry`] for
+//! exposing a named set of tools to agents.
 
 use crate::error::FlowError;
+use crate::llm::ToolSpec;
 use crate::node::Node;
 use async_trait::async_trait;
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
 
 /// A trait for type-safe tools that work with structured inputs and outputs.
 ///
@@ -73,6 +76,15 @@ pub trait Tool: Send + Sync {
     /// * `Ok(Self::Output)` - The successful result of the tool execution
     /// * `Err(FlowError)` - An error if the tool execution fails
     async fn run(&self, input: Self::Input) -> Result<Self::Output, FlowError>;
+
+    /// A JSON Schema for [`Tool::Input`], if the tool declares one.
+    ///
+    /// When `Input` derives `schemars::JsonSchema`, return
+    /// `Some(schemars::schema_for!(Self::Input).to_value())`. The schema is
+    /// exposed as the [`ToolNode`]'s [`Node::input_schema`].
+    fn input_schema(&self) -> Option<Value> {
+        None
+    }
 }
 
 /// A wrapper that allows type-safe Tools to be used as Nodes in the Flow system.
@@ -150,4 +162,146 @@ impl<T: Tool> Node for ToolNode<T> {
 
         Ok(output_value)
     }
+
+    fn input_schema(&self) -> Option<Value> {
+        self.tool.input_schema()
+    }
+}
+
+struct RegisteredTool {
+    description: String,
+    parameters: Value,
+    node: Box<dyn Node>,
+}
+
+/// A named collection of tools that can be offered to a language model.
+///
+/// Agents such as [`ReActAgent`](crate::agent::ReActAgent) look up tools by
+/// name in the registry, and use [`ToolRegistry::specs`] to describe the
+/// available tools to the model.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::tool::ToolRegistry;
+/// # use rustyflow::{Tool, FlowError};
+/// # use async_trait::async_trait;
+/// # use serde::{Deserialize, Serialize};
+/// # #[derive(Deserialize)] struct Input { value: i32 }
+/// # #[derive(Serialize)] struct Output { doubled: i32 }
+/// # struct Doubler;
+/// # #[async_trait]
+/// # impl Tool for Doubler {
+/// #     type Input = Input;
+/// #     type Output = Output;
+/// #     async fn run(&self, input: Self::Input) -> Result<Self::Output, FlowError> {
+/// #         Ok(Output { doubled: input.value * 2 })
+/// #     }
+/// # }
+///
+/// let mut registry = ToolRegistry::new();
+/// registry.register("double", "Doubles the `value` field", Doubler);
+/// assert!(registry.contains("double"));
+/// ```
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: BTreeMap<String, RegisteredTool>,
+}
+
+impl ToolRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a typed tool under the given name.
+    ///
+    /// Registering a tool with an existing name replaces the previous tool.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the model uses to call the tool
+    /// * `description` - What the tool does, shown to the model
+    /// * `tool` - The tool implementation
+    pub fn register<T>(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        tool: T,
+    ) -> &mut Self
+    where
+        T: Tool + 'static,
+    {
+        let parameters = tool
+            .input_schema()
+            .unwrap_or_else(|| json!({ "type": "object" }));
+        self.register_node(name, description, parameters, Box::new(ToolNode::new(tool)))
+    }
+
+    /// Register an arbitrary node as a tool.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the model uses to call the tool
+    /// * `description` - What the tool does, shown to the model
+    /// * `parameters` - JSON Schema describing the tool's arguments
+    /// * `node` - The node executed when the tool is called
+    pub fn register_node(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: Value,
+        node: Box<dyn Node>,
+    ) -> &mut Self {
+        self.tools.insert(
+            name.into(),
+            RegisteredTool {
+                description: description.into(),
+                parameters,
+                node,
+            },
+        );
+        self
+    }
+
+    /// Returns `true` if a tool with the given name is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+
+    /// Returns the number of registered tools.
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    /// Returns `true` if no tools are registered.
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Describe all registered tools for a language model.
+    pub fn specs(&self) -> Vec<ToolSpec> {
+        self.tools
+            .iter()
+            .map(|(name, tool)| ToolSpec {
+                name: name.clone(),
+                description: tool.description.clone(),
+                parameters: tool.parameters.clone(),
+            })
+            .collect()
+    }
+
+    /// Invoke the named tool with JSON arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if no tool with the given name is
+    /// registered, or propagates any error from the tool itself.
+    pub async fn invoke(&self, name: &str, arguments: Value) -> Result<Value, FlowError> {
+        let tool = self
+            .tools
+            .get(name)
+            .ok_or_else(|| FlowError::NodeFailed(format!("Unknown tool: {name}")))?;
+        tool.node.call(arguments).await
+    }
 }
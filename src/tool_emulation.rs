@@ -0,0 +1,281 @@
+//! Tool calling for models without native support.
+//!
+//! Many local and older models cannot return structured tool calls. This
+//! module provides [`EmulatedTools`], a [`ChatModel`] wrapper that describes
+//! the request's tools in the system prompt, asks the model to reply with a
+//! JSON tool call, and parses that reply back into [`ToolCall`]s. To the
+//! caller the wrapped model looks like one with native tool calling, so
+//! agents and flows written against [`ChatRequest::tools`] and
+//! [`Message::tool_calls`] work unchanged with it.
+//!
+//! The model is asked to call tools with one JSON object:
+//!
+//! ```text
+//! {"tool_calls": [{"name": "<tool name>", "arguments": {...}}]}
+//! ```
+//!
+//! A bare `{"name": ..., "arguments": ...}` object is accepted as well.
+//! Replies without a call to a known tool are returned as plain text. For
+//! decoders that constrain output with a JSON Schema or grammar,
+//! [`tool_call_schema`] describes the valid calls.
+
+use crate::constraint::Constraint;
+use crate::error::FlowError;
+use crate::llm::{
+    extract_json, ChatModel, ChatRequest, ChatResponse, Message, Role, ToolCall, ToolSpec,
+};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const DEFAULT_INSTRUCTIONS: &str = "You can call the tools listed below. To call tools, \
+reply with only a JSON object in this format:\n\
+{\"tool_calls\": [{\"name\": \"<tool name>\", \"arguments\": {<arguments>}}]}\n\
+Tool results are sent back to you in the next message. \
+When you do not need a tool, reply normally without JSON.";
+
+/// A [`ChatModel`] that emulates tool calling through prompting and parsing.
+///
+/// Requests without tools, and conversations without tool messages, are
+/// passed through unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::agent::ReActAgent;
+/// use rustyflow::llm::{ChatModel, ChatRequest, ChatResponse, Message};
+/// use rustyflow::tool::ToolRegistry;
+/// use rustyflow::tool_emulation::EmulatedTools;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::{json, Value};
+///
+/// /// A model that only ever sees and writes text.
+/// struct TextOnlyModel;
+///
+/// #[async_trait]
+/// impl ChatModel for TextOnlyModel {
+///     async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, FlowError> {
+///         assert!(request.tools.is_empty());
+///         let last = request.messages.last().unwrap();
+///         let reply = if last.content.starts_with("Tool result") {
+///             "Final Answer: 42".to_string()
+///         } else {
+///             r#"{"tool_calls": [{"name": "answer", "arguments": {}}]}"#.to_string()
+///         };
+///         Ok(ChatResponse { message: Message::assistant(reply), usage: None })
+///     }
+/// }
+///
+/// struct Answer;
+///
+/// #[async_trait]
+/// impl Node for Answer {
+///     async fn call(&self, _input: Value) -> Result<Value, FlowError> {
+///         Ok(json!(42))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let mut tools = ToolRegistry::new();
+/// tools.register_node("answer", "Looks up the answer", json!({"type": "object"}), Box::new(Answer));
+///
+/// let agent = ReActAgent::new(EmulatedTools::new(TextOnlyModel), tools);
+/// let result = agent.call(json!("What is the answer?")).await?;
+/// assert_eq!(result["steps"][0]["action"], "answer");
+/// assert_eq!(result["answer"], "42");
+/// # Ok(())
+/// # }
+/// ```
+pub struct EmulatedTools<M: ChatModel> {
+    model: M,
+    instructions: String,
+}
+
+impl<M: ChatModel> EmulatedTools<M> {
+    /// Wrap `model`, which does not support tool calling.
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            instructions: DEFAULT_INSTRUCTIONS.to_string(),
+        }
+    }
+
+    /// Replace the instructions placed before the tool list in the system
+    /// prompt. They should ask for the JSON format described in the
+    /// [module documentation](self).
+    pub fn with_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = instructions.into();
+        self
+    }
+
+    fn tool_prompt(&self, tools: &[ToolSpec]) -> String {
+        let mut prompt = self.instructions.clone();
+        prompt.push_str("\n\nTools:\n");
+        for spec in tools {
+            prompt.push_str(&format!(
+                "- {}: {} (arguments schema: {})\n",
+                spec.name, spec.description, spec.parameters
+            ));
+        }
+        prompt
+    }
+}
+
+/// Rewrite a conversation with tool calls and results as plain text.
+fn flatten(messages: Vec<Message>) -> Vec<Message> {
+    let mut tool_names: HashMap<String, String> = HashMap::new();
+    messages
+        .into_iter()
+        .map(|message| match message.role {
+            Role::Assistant if !message.tool_calls.is_empty() => {
+                let calls: Vec<Value> = message
+                    .tool_calls
+                    .iter()
+                    .map(|call| {
+                        tool_names.insert(call.id.clone(), call.name.clone());
+                        json!({ "name": call.name, "arguments": call.arguments })
+                    })
+                    .collect();
+                let call = json!({ "tool_calls": calls }).to_string();
+                let content = match message.content.trim() {
+                    "" => call,
+                    thought => format!("{thought}\n{call}"),
+                };
+                Message::assistant(content)
+            }
+            Role::Tool => {
+                let id = message.tool_call_id.unwrap_or_default();
+                let name = tool_names.get(&id).map_or("tool", String::as_str);
+                Message::user(format!("Tool result from {name}: {}", message.content))
+            }
+            _ => message,
+        })
+        .collect()
+}
+
+/// Parse the tool calls in a text reply, if it calls any known tool.
+fn parse_calls(text: &str, tools: &[ToolSpec]) -> Option<Vec<ToolCall>> {
+    let value = extract_json(text)?;
+    let calls = match value.get("tool_calls") {
+        Some(Value::Array(calls)) => calls.clone(),
+        _ => vec![value],
+    };
+    let calls: Vec<ToolCall> = calls
+        .into_iter()
+        .filter_map(|call| {
+            let name = call.get("name")?.as_str()?;
+            tools
+                .iter()
+                .any(|spec| spec.name == name)
+                .then(|| ToolCall {
+                    id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+                    name: name.to_string(),
+                    arguments: call.get("arguments").cloned().unwrap_or_else(|| json!({})),
+                })
+        })
+        .collect();
+    (!calls.is_empty()).then_some(calls)
+}
+
+/// The text before the JSON tool call, which models use for reasoning.
+fn thought(text: &str) -> String {
+    let end = text.find('{').unwrap_or(text.len());
+    text[..end]
+        .trim()
+        .trim_end_matches("```json")
+        .trim()
+        .to_string()
+}
+
+#[async_trait]
+impl<M: ChatModel> ChatModel for EmulatedTools<M> {
+    /// Send the request with its tools described in the prompt, and parse
+    /// tool calls out of the reply.
+    ///
+    /// # Errors
+    ///
+    /// Propagates any error from the wrapped model.
+    async fn chat(&self, mut request: ChatRequest) -> Result<ChatResponse, FlowError> {
+        let uses_tools = !request.tools.is_empty()
+            || request
+                .messages
+                .iter()
+                .any(|message| message.role == Role::Tool);
+        if !uses_tools {
+            return self.model.chat(request).await;
+        }
+
+        let tools = std::mem::take(&mut request.tools);
+        let mut messages = flatten(std::mem::take(&mut request.messages));
+        if !tools.is_empty() {
+            let prompt = self.tool_prompt(&tools);
+            match messages.first_mut() {
+                Some(first) if first.role == Role::System => {
+                    first.content = format!("{}\n\n{prompt}", first.content);
+                }
+                _ => messages.insert(0, Message::system(prompt)),
+            }
+        }
+        request.messages = messages;
+
+        let mut response = self.model.chat(request).await?;
+        if let Some(calls) = parse_calls(&response.message.content, &tools) {
+            response.message.content = thought(&response.message.content);
+            response.message.tool_calls = calls;
+        }
+        Ok(response)
+    }
+
+    fn supports_constraint(&self, constraint: &Constraint) -> bool {
+        self.model.supports_constraint(constraint)
+    }
+}
+
+/// A JSON Schema matching every valid emulated tool call for `tools`.
+///
+/// Pass it to a decoder that supports schema- or grammar-constrained
+/// generation so the model cannot produce malformed calls.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::llm::ToolSpec;
+/// use rustyflow::tool_emulation::tool_call_schema;
+/// use serde_json::json;
+///
+/// let tools = vec![ToolSpec {
+///     name: "search".to_string(),
+///     description: "Searches the web".to_string(),
+///     parameters: json!({"type": "object", "properties": {"query": {"type": "string"}}}),
+/// }];
+/// let schema = tool_call_schema(&tools);
+/// assert_eq!(schema["properties"]["tool_calls"]["items"]["anyOf"][0]["properties"]["name"]["const"], "search");
+/// ```
+pub fn tool_call_schema(tools: &[ToolSpec]) -> Value {
+    let calls: Vec<Value> = tools
+        .iter()
+        .map(|spec| {
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": { "const": spec.name },
+                    "arguments": spec.parameters,
+                },
+                "required": ["name", "arguments"],
+            })
+        })
+        .collect();
+    json!({
+        "type": "object",
+        "properties": {
+            "tool_calls": {
+                "type": "array",
+                "minItems": 1,
+                "items": { "anyOf": calls },
+            },
+        },
+        "required": ["tool_calls"],
+    })
+}
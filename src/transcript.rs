@@ -0,0 +1,264 @@
+//! Export of agent transcripts kept in run history.
+//!
+//! Runs recorded in a [`HistoryStore`] with a
+//! [`transcript`](RunRecord::transcript) can be rendered for people, as
+//! Markdown or a standalone HTML page, or as JSON Lines for fine-tuning, in
+//! the ShareGPT or OpenAI chat format. Runs without a transcript are
+//! skipped.
+//!
+//! | Format | Output |
+//! |--------|--------|
+//! | `markdown` | One section per run, one heading per message |
+//! | `html` | A standalone page with one `<article>` per run |
+//! | `sharegpt` | One `{"id", "conversations": [{"from", "value"}]}` line per run |
+//! | `openai` | One `{"messages": [...]}` line per run, with OpenAI-style tool calls |
+
+use crate::error::FlowError;
+use crate::history::{HistoryStore, RunRecord};
+use crate::llm::{Message, Role};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// A format transcripts can be exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    /// Markdown for reading and sharing.
+    Markdown,
+    /// A standalone HTML page.
+    Html,
+    /// ShareGPT JSON Lines, with `function_call` and `observation` turns for
+    /// tool use.
+    ShareGpt,
+    /// OpenAI chat fine-tuning JSON Lines.
+    OpenAi,
+}
+
+impl fmt::Display for TranscriptFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TranscriptFormat::Markdown => "markdown",
+            TranscriptFormat::Html => "html",
+            TranscriptFormat::ShareGpt => "sharegpt",
+            TranscriptFormat::OpenAi => "openai",
+        })
+    }
+}
+
+impl FromStr for TranscriptFormat {
+    type Err = FlowError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "markdown" | "md" => Ok(TranscriptFormat::Markdown),
+            "html" => Ok(TranscriptFormat::Html),
+            "sharegpt" => Ok(TranscriptFormat::ShareGpt),
+            "openai" => Ok(TranscriptFormat::OpenAi),
+            other => Err(FlowError::NodeFailed(format!(
+                "Unknown transcript format '{other}'; expected markdown, html, sharegpt or openai"
+            ))),
+        }
+    }
+}
+
+/// Render the transcripts of `runs` in `format`.
+///
+/// # Errors
+///
+/// Returns `FlowError::SerdeError` if a message cannot be serialized.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::history::RunRecord;
+/// use rustyflow::llm::Message;
+/// use rustyflow::transcript::{render, TranscriptFormat};
+/// use serde_json::Value;
+///
+/// # fn main() -> Result<(), rustyflow::FlowError> {
+/// let run = RunRecord::new("run-1", "support").with_transcript(vec![
+///     Message::user("Where is my order?"),
+///     Message::assistant("It ships tomorrow."),
+/// ]);
+///
+/// let markdown = render(&[run.clone()], TranscriptFormat::Markdown)?;
+/// assert!(markdown.contains("### User\n\nWhere is my order?"));
+///
+/// let jsonl = render(&[run], TranscriptFormat::ShareGpt)?;
+/// let line: Value = serde_json::from_str(jsonl.lines().next().unwrap())?;
+/// assert_eq!(line["conversations"][1]["from"], "gpt");
+/// # Ok(())
+/// # }
+/// ```
+pub fn render(runs: &[RunRecord], format: TranscriptFormat) -> Result<String, FlowError> {
+    let runs = runs.iter().filter(|run| !run.transcript.is_empty());
+    match format {
+        TranscriptFormat::Markdown => Ok(runs.map(markdown).collect::<Vec<_>>().join("\n")),
+        TranscriptFormat::Html => Ok(html(runs)),
+        TranscriptFormat::ShareGpt => json_lines(runs, sharegpt),
+        TranscriptFormat::OpenAi => json_lines(runs, openai),
+    }
+}
+
+/// Render the transcripts of the runs that started in `[from, to)`, in
+/// milliseconds since the UNIX epoch.
+///
+/// # Errors
+///
+/// Returns `FlowError::Checkpoint` if the store cannot be read, or the same
+/// errors as [`render`].
+pub async fn export(
+    store: &dyn HistoryStore,
+    from: u64,
+    to: u64,
+    format: TranscriptFormat,
+) -> Result<String, FlowError> {
+    render(&store.runs_between(from, to).await?, format)
+}
+
+fn role_title(role: Role) -> &'static str {
+    match role {
+        Role::System => "System",
+        Role::User => "User",
+        Role::Assistant => "Assistant",
+        Role::Tool => "Tool",
+    }
+}
+
+fn markdown(run: &RunRecord) -> String {
+    let mut out = format!("## Run {} ({})\n", run.run_id, run.flow);
+    for message in &run.transcript {
+        out.push_str(&format!("\n### {}\n\n", role_title(message.role)));
+        if !message.content.is_empty() {
+            out.push_str(&message.content);
+            out.push('\n');
+        }
+        for call in &message.tool_calls {
+            out.push_str(&format!(
+                "\nCalls `{}`:\n\n```json\n{}\n```\n",
+                call.name, call.arguments
+            ));
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn html<'a>(runs: impl Iterator<Item = &'a RunRecord>) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Transcripts</title>\n\
+         <style>\nbody { font-family: sans-serif; max-width: 48rem; margin: auto; }\n\
+         section { border-left: 3px solid #ccc; margin: 1rem 0; padding-left: 1rem; }\n\
+         .user { border-color: #36c; }\n.assistant { border-color: #3a3; }\n\
+         .tool { border-color: #c93; }\npre { white-space: pre-wrap; }\n</style>\n\
+         </head>\n<body>\n",
+    );
+    for run in runs {
+        out.push_str(&format!(
+            "<article>\n<h2>Run {} ({})</h2>\n",
+            escape_html(&run.run_id),
+            escape_html(&run.flow)
+        ));
+        for message in &run.transcript {
+            let title = role_title(message.role);
+            out.push_str(&format!(
+                "<section class=\"{}\">\n<h3>{title}</h3>\n<pre>{}</pre>\n",
+                title.to_lowercase(),
+                escape_html(&message.content)
+            ));
+            for call in &message.tool_calls {
+                out.push_str(&format!(
+                    "<p>Calls <code>{}</code></p>\n<pre>{}</pre>\n",
+                    escape_html(&call.name),
+                    escape_html(&call.arguments.to_string())
+                ));
+            }
+            out.push_str("</section>\n");
+        }
+        out.push_str("</article>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn json_lines<'a>(
+    runs: impl Iterator<Item = &'a RunRecord>,
+    line: fn(&RunRecord) -> Value,
+) -> Result<String, FlowError> {
+    let mut out = String::new();
+    for run in runs {
+        out.push_str(&serde_json::to_string(&line(run))?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn sharegpt(run: &RunRecord) -> Value {
+    let mut turns = Vec::new();
+    for message in &run.transcript {
+        let (from, value) = match message.role {
+            Role::System => ("system", message.content.clone()),
+            Role::User => ("human", message.content.clone()),
+            Role::Tool => ("observation", message.content.clone()),
+            Role::Assistant if message.tool_calls.is_empty() => ("gpt", message.content.clone()),
+            Role::Assistant => {
+                let calls: Vec<Value> = message
+                    .tool_calls
+                    .iter()
+                    .map(|call| json!({ "name": call.name, "arguments": call.arguments }))
+                    .collect();
+                let calls = match <[Value; 1]>::try_from(calls) {
+                    Ok([call]) => call,
+                    Err(calls) => Value::Array(calls),
+                };
+                ("function_call", calls.to_string())
+            }
+        };
+        turns.push(json!({ "from": from, "value": value }));
+    }
+    json!({ "id": run.run_id, "conversations": turns })
+}
+
+fn openai_message(message: &Message) -> Value {
+    let role = role_title(message.role).to_lowercase();
+    let mut value = json!({ "role": role, "content": message.content });
+    if !message.tool_calls.is_empty() {
+        let calls: Vec<Value> = message
+            .tool_calls
+            .iter()
+            .map(|call| {
+                json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": { "name": call.name, "arguments": call.arguments.to_string() },
+                })
+            })
+            .collect();
+        value["tool_calls"] = Value::Array(calls);
+    }
+    if let Some(id) = &message.tool_call_id {
+        value["tool_call_id"] = json!(id);
+    }
+    value
+}
+
+fn openai(run: &RunRecord) -> Value {
+    let messages: Vec<Value> = run.transcript.iter().map(openai_message).collect();
+    json!({ "messages": messages })
+}
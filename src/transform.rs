@@ -0,0 +1,223 @@
+//! Reshaping JSON between nodes with jq and JSONPath expressions.
+//!
+//! [`TransformNode`] covers the glue steps of a flow, such as picking
+//! fields, renaming keys or flattening arrays, with an expression instead
+//! of a bespoke node: a [jq](https://jqlang.github.io/jq/manual/) filter,
+//! run by the [jaq](https://github.com/01mf02/jaq) interpreter, or an
+//! [RFC 9535](https://www.rfc-editor.org/rfc/rfc9535) JSONPath query.
+//!
+//! This module is available with the `transform` feature.
+
+use crate::error::FlowError;
+use crate::node::Node;
+use async_trait::async_trait;
+use jaq_core::load::{lex, parse, Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, Filter, Native, RcIter};
+use jaq_json::Val;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+enum Expression {
+    Jq(Filter<Native<Val>>),
+    JsonPath(JsonPath),
+}
+
+/// A node that reshapes its input with a jq filter or a JSONPath query.
+///
+/// A jq filter and a JSONPath query can both produce several values. The
+/// node outputs a single value as is, `null` for none, and an array of
+/// them for several; with [`with_collect`](TransformNode::with_collect) it
+/// always outputs an array.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::transform::TransformNode;
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let input = json!({
+///     "user": {"first_name": "Ada", "last_name": "Lovelace"},
+///     "orders": [{"items": ["pen", "ink"]}, {"items": ["paper"]}]
+/// });
+///
+/// // Rename keys and flatten nested arrays
+/// let reshape = TransformNode::jq(
+///     "{name: .user.first_name, items: [.orders[].items[]]}",
+/// )?;
+/// assert_eq!(
+///     reshape.call(input.clone()).await?,
+///     json!({"name": "Ada", "items": ["pen", "ink", "paper"]})
+/// );
+///
+/// // Pick values wherever they are
+/// let items = TransformNode::jsonpath("$.orders[*].items[0]")?;
+/// assert_eq!(items.call(input.clone()).await?, json!(["pen", "paper"]));
+///
+/// let last = TransformNode::jsonpath("$.user.last_name")?.with_collect();
+/// assert_eq!(last.call(input).await?, json!(["Lovelace"]));
+/// # Ok(())
+/// # }
+/// ```
+pub struct TransformNode {
+    expression: Expression,
+    source: String,
+    collect: bool,
+}
+
+impl TransformNode {
+    /// Create a node that runs a jq filter, with jq's standard library.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The jq filter, such as `.items | map(.id)`
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the filter does not parse or uses
+    /// an undefined function or variable.
+    pub fn jq(filter: &str) -> Result<Self, FlowError> {
+        let invalid = |problems: Vec<String>| {
+            FlowError::NodeFailed(format!(
+                "Invalid jq filter '{filter}': {}",
+                problems.join("; ")
+            ))
+        };
+
+        let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+        let arena = Arena::default();
+        let modules = loader
+            .load(
+                &arena,
+                File {
+                    code: filter,
+                    path: (),
+                },
+            )
+            .map_err(|errors| {
+                invalid(
+                    errors
+                        .iter()
+                        .flat_map(|(_, error)| load_problems(error))
+                        .collect(),
+                )
+            })?;
+        let compiled = Compiler::default()
+            .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+            .compile(modules)
+            .map_err(|errors| {
+                invalid(
+                    errors
+                        .iter()
+                        .flat_map(|(_, undefined)| undefined)
+                        .map(|(name, kind)| format!("undefined {} '{name}'", kind.as_str()))
+                        .collect(),
+                )
+            })?;
+
+        Ok(Self {
+            expression: Expression::Jq(compiled),
+            source: filter.to_string(),
+            collect: false,
+        })
+    }
+
+    /// Create a node that runs a JSONPath query.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query, such as `$.items[*].id`
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the query does not parse.
+    pub fn jsonpath(query: &str) -> Result<Self, FlowError> {
+        let path = JsonPath::parse(query)
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid JSONPath query '{query}': {e}")))?;
+        Ok(Self {
+            expression: Expression::JsonPath(path),
+            source: query.to_string(),
+            collect: false,
+        })
+    }
+
+    /// Always output an array of the produced values, even for one or none.
+    pub fn with_collect(mut self) -> Self {
+        self.collect = true;
+        self
+    }
+
+    /// Every value the expression produces for `input`, in order.
+    fn evaluate(&self, input: Value) -> Result<Vec<Value>, FlowError> {
+        match &self.expression {
+            Expression::Jq(filter) => {
+                let inputs = RcIter::new(core::iter::empty());
+                filter
+                    .run((Ctx::new([], &inputs), Val::from(input)))
+                    .map(|result| {
+                        result.map(Value::from).map_err(|e| {
+                            FlowError::NodeFailed(format!(
+                                "jq filter '{}' failed: {e}",
+                                self.source
+                            ))
+                        })
+                    })
+                    .collect()
+            }
+            Expression::JsonPath(path) => {
+                Ok(path.query(&input).all().into_iter().cloned().collect())
+            }
+        }
+    }
+}
+
+/// Describe the problems of a jq module that failed to load.
+fn load_problems(error: &jaq_core::load::Error<&str>) -> Vec<String> {
+    match error {
+        jaq_core::load::Error::Io(errors) => errors
+            .iter()
+            .map(|(path, error)| format!("cannot load module '{path}': {error}"))
+            .collect(),
+        jaq_core::load::Error::Lex(errors) => errors
+            .iter()
+            .map(|(expected, found): &lex::Error<&str>| {
+                format!("expected {} {}", expected.as_str(), near(found))
+            })
+            .collect(),
+        jaq_core::load::Error::Parse(errors) => errors
+            .iter()
+            .map(|(expected, found): &parse::Error<&str>| {
+                format!("expected {} {}", expected.as_str(), near(found))
+            })
+            .collect(),
+    }
+}
+
+/// Where in a filter a problem was found, from the rest of the filter.
+fn near(rest: &str) -> String {
+    match rest.chars().take(10).collect::<String>() {
+        snippet if snippet.is_empty() => "at the end".to_string(),
+        snippet => format!("at '{snippet}'"),
+    }
+}
+
+#[async_trait]
+impl Node for TransformNode {
+    /// Run the expression on the input.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if a jq filter fails on the input,
+    /// for example by indexing a string.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let mut values = self.evaluate(input)?;
+        Ok(match values.len() {
+            _ if self.collect => Value::Array(values),
+            0 => Value::Null,
+            1 => values.remove(0),
+            _ => Value::Array(values),
+        })
+    }
+}
@@ -0,0 +1,419 @@
+//! Vector storage and similarity search for retrieval-augmented flows.
+//!
+//! This module defines the [`VectorStore`] trait, an [`InMemoryVectorStore`]
+//! implementation, and the [`UpsertNode`] and [`RetrieveNode`] nodes that
+//! together with an [`Embedder`] make up a complete ingest + retrieve flow.
+//! With the `qdrant` feature, `QdrantStore` provides a production backend.
+
+use crate::embeddings::{cosine_similarity, Embedder};
+use crate::error::FlowError;
+use crate::node::Node;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "qdrant")]
+mod qdrant;
+#[cfg(feature = "qdrant")]
+pub use qdrant::QdrantStore;
+
+/// A document stored alongside its embedding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    /// Unique identifier; upserting an existing id replaces the document.
+    pub id: String,
+    /// The original text.
+    #[serde(default)]
+    pub text: String,
+    /// The embedding vector.
+    pub vector: Vec<f32>,
+    /// Arbitrary metadata used for filtering and returned with results.
+    #[serde(default)]
+    pub metadata: Value,
+}
+
+/// A document returned from a similarity query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoredDocument {
+    /// The document identifier.
+    pub id: String,
+    /// The original text.
+    pub text: String,
+    /// The document metadata.
+    pub metadata: Value,
+    /// Similarity to the query vector (cosine similarity, higher is closer).
+    pub score: f32,
+}
+
+/// Restricts a query to documents whose metadata fields equal given values.
+///
+/// # Example
+///
+/// ```rust
+/// use rustyflow::vector_store::Filter;
+///
+/// let filter = Filter::new().eq("lang", "en").eq("year", 2024);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Filter {
+    /// Metadata field name to required value.
+    #[serde(default)]
+    pub equals: BTreeMap<String, Value>,
+}
+
+impl Filter {
+    /// Create a filter that matches every document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require the metadata field `field` to equal `value`.
+    pub fn eq(mut self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.equals.insert(field.into(), value.into());
+        self
+    }
+
+    /// Returns `true` if the metadata satisfies every condition.
+    pub fn matches(&self, metadata: &Value) -> bool {
+        self.equals
+            .iter()
+            .all(|(field, expected)| metadata.get(field) == Some(expected))
+    }
+}
+
+/// Storage for embedded documents with similarity search.
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// Insert or replace documents by id.
+    async fn upsert(&self, documents: Vec<Document>) -> Result<(), FlowError>;
+
+    /// Return the `top_k` documents most similar to `vector`.
+    ///
+    /// # Arguments
+    ///
+    /// * `vector` - The query embedding
+    /// * `top_k` - The maximum number of results
+    /// * `filter` - Optional metadata conditions results must satisfy
+    async fn query(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredDocument>, FlowError>;
+
+    /// Delete documents by id; unknown ids are ignored.
+    async fn delete(&self, ids: &[String]) -> Result<(), FlowError>;
+}
+
+#[async_trait]
+impl<S: VectorStore + ?Sized> VectorStore for Arc<S> {
+    async fn upsert(&self, documents: Vec<Document>) -> Result<(), FlowError> {
+        (**self).upsert(documents).await
+    }
+
+    async fn query(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredDocument>, FlowError> {
+        (**self).query(vector, top_k, filter).await
+    }
+
+    async fn delete(&self, ids: &[String]) -> Result<(), FlowError> {
+        (**self).delete(ids).await
+    }
+}
+
+/// A [`VectorStore`] that keeps documents in memory and searches exhaustively.
+///
+/// Suitable for tests, prototypes, and corpora of up to tens of thousands of
+/// documents.
+#[derive(Default)]
+pub struct InMemoryVectorStore {
+    documents: RwLock<HashMap<String, Document>>,
+}
+
+impl InMemoryVectorStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of stored documents.
+    pub fn len(&self) -> usize {
+        self.documents
+            .read()
+            .expect("vector store lock poisoned")
+            .len()
+    }
+
+    /// Returns `true` if the store holds no documents.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl VectorStore for InMemoryVectorStore {
+    async fn upsert(&self, documents: Vec<Document>) -> Result<(), FlowError> {
+        let mut stored = self.documents.write().expect("vector store lock poisoned");
+        for document in documents {
+            stored.insert(document.id.clone(), document);
+        }
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredDocument>, FlowError> {
+        let stored = self.documents.read().expect("vector store lock poisoned");
+        let mut scored: Vec<ScoredDocument> = stored
+            .values()
+            .filter(|doc| filter.map_or(true, |f| f.matches(&doc.metadata)))
+            .map(|doc| ScoredDocument {
+                id: doc.id.clone(),
+                text: doc.text.clone(),
+                metadata: doc.metadata.clone(),
+                score: cosine_similarity(vector, &doc.vector),
+            })
+            .collect();
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(top_k);
+        Ok(scored)
+    }
+
+    async fn delete(&self, ids: &[String]) -> Result<(), FlowError> {
+        let mut stored = self.documents.write().expect("vector store lock poisoned");
+        for id in ids {
+            stored.remove(id);
+        }
+        Ok(())
+    }
+}
+
+/// Derive a stable id from document text (64-bit FNV-1a).
+fn content_id(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// A node that embeds documents and writes them to a [`VectorStore`].
+///
+/// The input is a JSON array whose elements are either strings or objects
+/// with a `text` field and optional `id` and `metadata` fields. Documents
+/// without an id get one derived from their text. The output is
+/// `{"upserted": n, "ids": [...]}`.
+pub struct UpsertNode<E: Embedder, S: VectorStore> {
+    embedder: E,
+    store: S,
+}
+
+impl<E: Embedder, S: VectorStore> UpsertNode<E, S> {
+    /// Create a new ingest node.
+    ///
+    /// # Arguments
+    ///
+    /// * `embedder` - Embeds document text
+    /// * `store` - Receives the embedded documents
+    pub fn new(embedder: E, store: S) -> Self {
+        Self { embedder, store }
+    }
+}
+
+#[async_trait]
+impl<E: Embedder, S: VectorStore> Node for UpsertNode<E, S> {
+    /// Embed and store the input documents.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if the input is not an array of
+    /// documents, or propagates embedder and store errors.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let items = match input {
+            Value::Array(items) => items,
+            _ => {
+                return Err(FlowError::NodeFailed(
+                    "Input must be a JSON array of documents".to_string(),
+                ))
+            }
+        };
+
+        let mut pending = Vec::with_capacity(items.len());
+        for item in items {
+            let (id, text, metadata) = match item {
+                Value::String(text) => (None, text, Value::Null),
+                Value::Object(mut map) => {
+                    let text = match map.remove("text") {
+                        Some(Value::String(text)) => text,
+                        _ => {
+                            return Err(FlowError::NodeFailed(
+                                "Each document must have a string 'text' field".to_string(),
+                            ))
+                        }
+                    };
+                    let id = map.remove("id").map(|id| match id {
+                        Value::String(id) => id,
+                        other => other.to_string(),
+                    });
+                    (id, text, map.remove("metadata").unwrap_or(Value::Null))
+                }
+                _ => {
+                    return Err(FlowError::NodeFailed(
+                        "Each document must be a string or an object".to_string(),
+                    ))
+                }
+            };
+            let id = id.unwrap_or_else(|| content_id(&text));
+            pending.push((id, text, metadata));
+        }
+
+        let texts: Vec<String> = pending.iter().map(|(_, text, _)| text.clone()).collect();
+        let vectors = self.embedder.embed(&texts).await?;
+        if vectors.len() != pending.len() {
+            return Err(FlowError::NodeFailed(format!(
+                "Embedder returned {} vectors for {} documents",
+                vectors.len(),
+                pending.len()
+            )));
+        }
+
+        let documents: Vec<Document> = pending
+            .into_iter()
+            .zip(vectors)
+            .map(|((id, text, metadata), vector)| Document {
+                id,
+                text,
+                vector,
+                metadata,
+            })
+            .collect();
+        let ids: Vec<String> = documents.iter().map(|d| d.id.clone()).collect();
+        self.store.upsert(documents).await?;
+
+        Ok(json!({ "upserted": ids.len(), "ids": ids }))
+    }
+}
+
+/// A node that retrieves the documents most similar to a query.
+///
+/// The input is either a query string or an object with a `query` field and
+/// optional `top_k` and `filter` (see [`Filter`]) fields. The output is the
+/// input object with a `documents` field holding the [`ScoredDocument`]s,
+/// ready to be rendered by a [`PromptTemplate`](crate::prompt::PromptTemplate).
+///
+/// # Example
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use rustyflow::embeddings::Embedder;
+/// use rustyflow::vector_store::{InMemoryVectorStore, RetrieveNode, UpsertNode};
+/// use rustyflow::{FlowError, Node};
+/// use serde_json::json;
+/// use std::sync::Arc;
+///
+/// struct ToyEmbedder;
+///
+/// #[async_trait]
+/// impl Embedder for ToyEmbedder {
+///     async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, FlowError> {
+///         Ok(texts.iter().map(|t| vec![t.contains("rust") as u8 as f32, t.contains("python") as u8 as f32]).collect())
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), FlowError> {
+/// let store = Arc::new(InMemoryVectorStore::new());
+/// let ingest = UpsertNode::new(ToyEmbedder, store.clone());
+/// ingest.call(json!(["rust is fast", "python is popular"])).await?;
+///
+/// let retrieve = RetrieveNode::new(ToyEmbedder, store).with_top_k(1);
+/// let result = retrieve.call(json!({"query": "tell me about rust"})).await?;
+/// assert_eq!(result["documents"][0]["text"], "rust is fast");
+/// # Ok(())
+/// # }
+/// ```
+pub struct RetrieveNode<E: Embedder, S: VectorStore> {
+    embedder: E,
+    store: S,
+    top_k: usize,
+}
+
+impl<E: Embedder, S: VectorStore> RetrieveNode<E, S> {
+    /// Create a new retrieval node returning up to 4 documents.
+    ///
+    /// # Arguments
+    ///
+    /// * `embedder` - Embeds the query text
+    /// * `store` - The store to search
+    pub fn new(embedder: E, store: S) -> Self {
+        Self {
+            embedder,
+            store,
+            top_k: 4,
+        }
+    }
+
+    /// Set the default number of documents to return.
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+}
+
+#[async_trait]
+impl<E: Embedder, S: VectorStore> Node for RetrieveNode<E, S> {
+    /// Retrieve documents similar to the input query.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FlowError::NodeFailed` if no query text is present, or
+    /// propagates embedder and store errors.
+    async fn call(&self, input: Value) -> Result<Value, FlowError> {
+        let mut output = match input {
+            Value::String(query) => {
+                let mut map = Map::new();
+                map.insert("query".to_string(), Value::String(query));
+                map
+            }
+            Value::Object(map) => map,
+            _ => {
+                return Err(FlowError::NodeFailed(
+                    "Input must be a query string or an object with a 'query' field".to_string(),
+                ))
+            }
+        };
+
+        let query = output
+            .get("query")
+            .and_then(Value::as_str)
+            .ok_or_else(|| FlowError::NodeFailed("Expected 'query' field".to_string()))?
+            .to_string();
+        let top_k = output
+            .get("top_k")
+            .and_then(Value::as_u64)
+            .map_or(self.top_k, |k| k as usize);
+        let filter: Option<Filter> = output
+            .get("filter")
+            .map(|f| serde_json::from_value(f.clone()))
+            .transpose()?;
+
+        let vector = self
+            .embedder
+            .embed(&[query])
+            .await?
+            .pop()
+            .ok_or_else(|| FlowError::NodeFailed("Embedder returned no vector".to_string()))?;
+        let documents = self.store.query(&vector, top_k, filter.as_ref()).await?;
+
+        output.insert("documents".to_string(), serde_json::to_value(documents)?);
+        Ok(Value::Object(output))
+    }
+}
@@ -0,0 +1,217 @@
+//! [Qdrant](https://qdrant.tech) backend for [`VectorStore`].
+
+use super::{Document, Filter, ScoredDocument, VectorStore};
+use crate::error::FlowError;
+use async_trait::async_trait;
+use reqwest::{Client, Method, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// A [`VectorStore`] backed by a Qdrant collection over its HTTP API.
+///
+/// Document ids are mapped to deterministic UUIDs (Qdrant only accepts
+/// integers and UUIDs as point ids); the original id, text and metadata are
+/// stored in the point payload. [`Filter`] conditions are translated into
+/// Qdrant `must` match conditions on the `metadata` payload field.
+///
+/// Available with the `qdrant` feature.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use rustyflow::vector_store::QdrantStore;
+/// use rustyflow::FlowError;
+///
+/// # async fn example() -> Result<(), FlowError> {
+/// let store = QdrantStore::new("http://localhost:6333", "docs").with_api_key("secret");
+/// store.ensure_collection(1536).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct QdrantStore {
+    client: Client,
+    base_url: String,
+    collection: String,
+    api_key: Option<String>,
+}
+
+impl QdrantStore {
+    /// Create a store for a collection on the given Qdrant server.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The Qdrant HTTP URL, e.g. `http://localhost:6333`
+    /// * `collection` - The collection name
+    pub fn new(base_url: impl Into<String>, collection: impl Into<String>) -> Self {
+        Self {
+            client: crate::http::shared_client(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            collection: collection.into(),
+            api_key: None,
+        }
+    }
+
+    /// Authenticate with an API key.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Use a preconfigured HTTP client.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Create the collection with cosine distance if it does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `dimensions` - The embedding vector size
+    pub async fn ensure_collection(&self, dimensions: usize) -> Result<(), FlowError> {
+        let path = format!("/collections/{}", self.collection);
+        if self.request(Method::GET, &path, None).await?.is_some() {
+            return Ok(());
+        }
+        self.request(
+            Method::PUT,
+            &path,
+            Some(json!({ "vectors": { "size": dimensions, "distance": "Cosine" } })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Delete the collection and all its points.
+    pub async fn delete_collection(&self) -> Result<(), FlowError> {
+        let path = format!("/collections/{}", self.collection);
+        self.request(Method::DELETE, &path, None).await?;
+        Ok(())
+    }
+
+    /// Send a request, returning `None` for `404 Not Found`.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Option<Value>, FlowError> {
+        let mut request = self
+            .client
+            .request(method, format!("{}{path}", self.base_url));
+        if let Some(key) = &self.api_key {
+            request = request.header("api-key", key);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Qdrant request failed: {e}")))?;
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(FlowError::NodeFailed(format!(
+                "Qdrant request to {path} failed with status {status}: {text}"
+            )));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| FlowError::NodeFailed(format!("Invalid Qdrant response: {e}")))?;
+        Ok(Some(body))
+    }
+
+    fn collection_not_found(&self) -> FlowError {
+        FlowError::NodeFailed(format!(
+            "Qdrant collection '{}' does not exist",
+            self.collection
+        ))
+    }
+}
+
+/// Map an arbitrary document id to a stable Qdrant point id.
+fn point_id(id: &str) -> String {
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, id.as_bytes()).to_string()
+}
+
+fn qdrant_filter(filter: &Filter) -> Value {
+    let must: Vec<Value> = filter
+        .equals
+        .iter()
+        .map(|(field, value)| json!({ "key": format!("metadata.{field}"), "match": { "value": value } }))
+        .collect();
+    json!({ "must": must })
+}
+
+#[async_trait]
+impl VectorStore for QdrantStore {
+    async fn upsert(&self, documents: Vec<Document>) -> Result<(), FlowError> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let points: Vec<Value> = documents
+            .into_iter()
+            .map(|doc| {
+                json!({
+                    "id": point_id(&doc.id),
+                    "vector": doc.vector,
+                    "payload": { "id": doc.id, "text": doc.text, "metadata": doc.metadata },
+                })
+            })
+            .collect();
+        let path = format!("/collections/{}/points?wait=true", self.collection);
+        self.request(Method::PUT, &path, Some(json!({ "points": points })))
+            .await?
+            .ok_or_else(|| self.collection_not_found())?;
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<ScoredDocument>, FlowError> {
+        let mut body = json!({ "vector": vector, "limit": top_k, "with_payload": true });
+        if let Some(filter) = filter.filter(|f| !f.equals.is_empty()) {
+            body["filter"] = qdrant_filter(filter);
+        }
+        let path = format!("/collections/{}/points/search", self.collection);
+        let response = self
+            .request(Method::POST, &path, Some(body))
+            .await?
+            .ok_or_else(|| self.collection_not_found())?;
+
+        let hits = response["result"].as_array().cloned().unwrap_or_default();
+        Ok(hits
+            .into_iter()
+            .map(|hit| {
+                let payload = &hit["payload"];
+                ScoredDocument {
+                    id: payload["id"].as_str().unwrap_or_default().to_string(),
+                    text: payload["text"].as_str().unwrap_or_default().to_string(),
+                    metadata: payload["metadata"].clone(),
+                    score: hit["score"].as_f64().unwrap_or_default() as f32,
+                }
+            })
+            .collect())
+    }
+
+    async fn delete(&self, ids: &[String]) -> Result<(), FlowError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let points: Vec<String> = ids.iter().map(|id| point_id(id)).collect();
+        let path = format!("/collections/{}/points/delete?wait=true", self.collection);
+        self.request(Method::POST, &path, Some(json!({ "points": points })))
+            .await?
+            .ok_or_else(|| self.collection_not_found())?;
+        Ok(())
+    }
+}